@@ -6,11 +6,10 @@
 
 extern crate alloc;
 
-use sel4_externally_shared::{
-    access::{ReadOnly, ReadWrite},
-    ExternallySharedRef,
+use sel4_externally_shared::access::ReadOnly;
+use sel4_microkit::{
+    memory_region, protection_domain, Channel, Handler, MemoryRegion, MessageInfo,
 };
-use sel4_microkit::{memory_region_symbol, protection_domain, Channel, Handler, MessageInfo};
 use sel4_microkit_message::MessageInfoExt as _;
 
 use banscii_artist_interface_types::*;
@@ -26,17 +25,9 @@ const REGION_SIZE: usize = 0x4_000;
 
 #[protection_domain(heap_size = 0x10000)]
 fn init() -> HandlerImpl {
-    let region_in = unsafe {
-        ExternallySharedRef::<'static, [u8]>::new_read_only(
-            memory_region_symbol!(region_in_start: *mut [u8], n = REGION_SIZE),
-        )
-    };
-
-    let region_out = unsafe {
-        ExternallySharedRef::<'static, [u8]>::new(
-            memory_region_symbol!(region_out_start: *mut [u8], n = REGION_SIZE),
-        )
-    };
+    let region_in = memory_region!(region_in_start: *const [u8], size = REGION_SIZE);
+
+    let region_out = memory_region!(region_out_start: *mut [u8], size = REGION_SIZE);
 
     HandlerImpl {
         region_in,
@@ -45,8 +36,8 @@ fn init() -> HandlerImpl {
 }
 
 struct HandlerImpl {
-    region_in: ExternallySharedRef<'static, [u8], ReadOnly>,
-    region_out: ExternallySharedRef<'static, [u8], ReadWrite>,
+    region_in: MemoryRegion<[u8], ReadOnly>,
+    region_out: MemoryRegion<[u8]>,
 }
 
 impl Handler for HandlerImpl {
@@ -75,7 +66,7 @@ impl Handler for HandlerImpl {
                     let masterpiece_end = masterpiece_start + masterpiece_size;
 
                     self.region_out
-                        .as_mut_ptr()
+                        .as_ptr()
                         .index(masterpiece_start..masterpiece_end)
                         .copy_from_slice(&masterpiece.pixel_data);
 
@@ -87,7 +78,7 @@ impl Handler for HandlerImpl {
                     let signature_end = signature_start + signature_size;
 
                     self.region_out
-                        .as_mut_ptr()
+                        .as_ptr()
                         .index(signature_start..signature_end)
                         .copy_from_slice(signature);
 
@@ -11,11 +11,10 @@ use core::fmt::Write;
 use core::mem;
 use core::str;
 
-use sel4_externally_shared::{
-    access::{ReadOnly, ReadWrite},
-    ExternallySharedRef,
+use sel4_externally_shared::access::ReadOnly;
+use sel4_microkit::{
+    memory_region, protection_domain, Channel, Handler, MemoryRegion, MessageInfo,
 };
-use sel4_microkit::{memory_region_symbol, protection_domain, Channel, Handler, MessageInfo};
 use sel4_microkit_message::MessageInfoExt as _;
 
 use banscii_artist_interface_types as artist;
@@ -31,17 +30,9 @@ const MAX_SUBJECT_LEN: usize = 16;
 
 #[protection_domain(heap_size = 0x10000)]
 fn init() -> impl Handler {
-    let region_in = unsafe {
-        ExternallySharedRef::<'static, [u8]>::new_read_only(
-            memory_region_symbol!(region_in_start: *mut [u8], n = REGION_SIZE),
-        )
-    };
-
-    let region_out = unsafe {
-        ExternallySharedRef::<'static, [u8]>::new(
-            memory_region_symbol!(region_out_start: *mut [u8], n = REGION_SIZE),
-        )
-    };
+    let region_in = memory_region!(region_in_start: *const [u8], size = REGION_SIZE);
+
+    let region_out = memory_region!(region_out_start: *mut [u8], size = REGION_SIZE);
 
     prompt();
 
@@ -53,8 +44,8 @@ fn init() -> impl Handler {
 }
 
 struct HandlerImpl {
-    region_in: ExternallySharedRef<'static, [u8], ReadOnly>,
-    region_out: ExternallySharedRef<'static, [u8], ReadWrite>,
+    region_in: MemoryRegion<[u8], ReadOnly>,
+    region_out: MemoryRegion<[u8]>,
     buffer: Vec<u8>,
 }
 
@@ -116,7 +107,7 @@ impl HandlerImpl {
         let draft_end = draft_start + draft_size;
 
         self.region_out
-            .as_mut_ptr()
+            .as_ptr()
             .index(draft_start..draft_end)
             .copy_from_slice(&draft.pixel_data);
 
@@ -0,0 +1,158 @@
+//! A NAPI-style policy for switching a driver between interrupt-driven operation and bounded
+//! polling.
+//!
+//! On each interrupt, a driver services its device in rounds, reporting the number of requests
+//! handled in each round to [`AdaptivePolling::poll_again`]. In interrupt mode, a single round is
+//! performed per interrupt. When the average number of requests handled per interrupt is high,
+//! which indicates a high request rate, the policy switches to polling mode, in which rounds are
+//! repeated until one finds no work or the budget is exhausted, amortizing the cost of an
+//! interrupt over more requests. When the rate drops, it switches back, so that no CPU time is
+//! spent polling an idle device.
+//!
+//! In polling mode, drivers also ask the device not to raise interrupts while they poll, and allow
+//! them again before waiting for the next notification.
+
+#![no_std]
+
+use serde::{Deserialize, Serialize};
+
+// Fixed-point scale for the average batch size.
+const AVERAGE_SHIFT: u32 = 4;
+
+// The weight of the latest batch in the average is `1 / 2^AVERAGE_WEIGHT_SHIFT`.
+const AVERAGE_WEIGHT_SHIFT: u32 = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PollingConfig {
+    /// Switch to polling mode when the average number of requests handled per interrupt reaches
+    /// this value.
+    pub enter_threshold: usize,
+    /// Switch back to interrupt mode when the average drops below this value.
+    pub exit_threshold: usize,
+    /// Maximum number of rounds per interrupt in polling mode.
+    pub budget: usize,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            enter_threshold: 4,
+            exit_threshold: 2,
+            budget: 16,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PollingMode {
+    Interrupt,
+    Polling,
+}
+
+/// Counters describing the behavior of an [`AdaptivePolling`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollingMetrics {
+    /// Number of notifications, from either the device or a client, serviced.
+    pub interrupts: u64,
+    /// Number of rounds performed.
+    pub rounds: u64,
+    /// Number of requests handled.
+    pub requests: u64,
+    /// Number of interrupts serviced in polling mode which ended because the budget was exhausted.
+    pub budget_exhausted: u64,
+    /// Number of switches into polling mode.
+    pub polling_entered: u64,
+    /// Number of switches back into interrupt mode.
+    pub polling_exited: u64,
+}
+
+pub struct AdaptivePolling {
+    config: PollingConfig,
+    mode: PollingMode,
+    average_batch: usize,
+    batch: usize,
+    rounds: usize,
+    metrics: PollingMetrics,
+}
+
+impl AdaptivePolling {
+    pub fn new(config: PollingConfig) -> Self {
+        assert!(config.exit_threshold <= config.enter_threshold);
+        assert_ne!(config.budget, 0);
+        Self {
+            config,
+            mode: PollingMode::Interrupt,
+            average_batch: 0,
+            batch: 0,
+            rounds: 0,
+            metrics: PollingMetrics::default(),
+        }
+    }
+
+    pub fn mode(&self) -> PollingMode {
+        self.mode
+    }
+
+    pub fn metrics(&self) -> &PollingMetrics {
+        &self.metrics
+    }
+
+    pub fn reset_metrics(&mut self) {
+        self.metrics = PollingMetrics::default();
+    }
+
+    /// Records a round which handled `requests` requests, and returns whether another round should
+    /// be performed before acknowledging the interrupt.
+    pub fn poll_again(&mut self, requests: usize) -> bool {
+        self.rounds += 1;
+        self.batch += requests;
+        self.metrics.rounds += 1;
+        self.metrics.requests += u64::try_from(requests).unwrap();
+        let again = match self.mode {
+            PollingMode::Interrupt => false,
+            PollingMode::Polling => {
+                if requests == 0 {
+                    false
+                } else if self.rounds >= self.config.budget {
+                    self.metrics.budget_exhausted += 1;
+                    false
+                } else {
+                    true
+                }
+            }
+        };
+        if !again {
+            self.finish_interrupt();
+        }
+        again
+    }
+
+    fn finish_interrupt(&mut self) {
+        self.metrics.interrupts += 1;
+        self.average_batch = self.average_batch - (self.average_batch >> AVERAGE_WEIGHT_SHIFT)
+            + ((self.batch << AVERAGE_SHIFT) >> AVERAGE_WEIGHT_SHIFT);
+        self.batch = 0;
+        self.rounds = 0;
+        match self.mode {
+            PollingMode::Interrupt
+                if self.average_batch >= self.config.enter_threshold << AVERAGE_SHIFT =>
+            {
+                self.mode = PollingMode::Polling;
+                self.metrics.polling_entered += 1;
+            }
+            PollingMode::Polling
+                if self.average_batch < self.config.exit_threshold << AVERAGE_SHIFT =>
+            {
+                self.mode = PollingMode::Interrupt;
+                self.metrics.polling_exited += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for AdaptivePolling {
+    fn default() -> Self {
+        Self::new(PollingConfig::default())
+    }
+}
@@ -18,24 +18,24 @@ static GLOBAL_STATE: ImmediateSyncOnceCell<GenericMutex<PanickingMutexSyncOps, S
 
 struct State {
     dma_region: ExternallySharedRef<'static, [u8]>,
-    dma_region_paddr: usize,
+    dma_region_paddr: sel4::PAddr,
     bounce_buffer_allocator: BounceBufferAllocator<Basic>,
 }
 
 impl State {
     fn offset_to_paddr(&self, offset: usize) -> PhysAddr {
-        self.dma_region_paddr.checked_add(offset).unwrap()
+        (self.dma_region_paddr + offset).addr()
     }
 
     fn paddr_to_offset(&self, paddr: PhysAddr) -> usize {
-        paddr.checked_sub(self.dma_region_paddr).unwrap()
+        sel4::PAddr::new(paddr) - self.dma_region_paddr
     }
 }
 
 pub struct HalImpl;
 
 impl HalImpl {
-    pub fn init(dma_region_size: usize, dma_region_vaddr: usize, dma_region_paddr: usize) {
+    pub fn init(dma_region_size: usize, dma_region_vaddr: usize, dma_region_paddr: sel4::PAddr) {
         let dma_region_ptr = NonNull::new(ptr::from_raw_parts_mut(
             ptr::from_exposed_addr_mut(dma_region_vaddr),
             dma_region_size,
@@ -47,7 +47,7 @@ impl HalImpl {
         let max_alignment = 1
             << dma_region_vaddr
                 .trailing_zeros()
-                .min(dma_region_paddr.trailing_zeros());
+                .min(dma_region_paddr.addr().trailing_zeros());
 
         let bounce_buffer_allocator =
             BounceBufferAllocator::new(Basic::new(dma_region_size), max_alignment);
@@ -64,6 +64,19 @@ impl HalImpl {
             .ok()
             .unwrap();
     }
+
+    /// Translates the physical address of memory allocated with [`Hal::dma_alloc`] to a pointer
+    /// through which it can be accessed.
+    pub fn dma_phys_to_virt(paddr: PhysAddr) -> NonNull<u8> {
+        let mut state = GLOBAL_STATE.get().unwrap().lock();
+        let offset = state.paddr_to_offset(paddr);
+        state
+            .dma_region
+            .as_mut_ptr()
+            .index(offset..offset + 1)
+            .as_raw_ptr()
+            .as_non_null_ptr()
+    }
 }
 
 unsafe impl Hal for HalImpl {
@@ -0,0 +1,19 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Path to the trace to replay, which is embedded in the program image.
+const TRACE_ENV: &str = "LOAD_GENERATOR_TRACE";
+
+fn main() {
+    let trace_path = match env::var(TRACE_ENV) {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => {
+            PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("traces/default.trace")
+        }
+    };
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::copy(&trace_path, PathBuf::from(&out_dir).join("trace.txt")).unwrap();
+    println!("cargo:rerun-if-env-changed={TRACE_ENV}");
+    println!("cargo:rerun-if-changed={}", trace_path.display());
+}
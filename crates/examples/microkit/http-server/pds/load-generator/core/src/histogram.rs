@@ -0,0 +1,126 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Each power of two is divided into `2^SUB_BUCKET_BITS` buckets, so that recorded values are
+// accurate to within `1 / 2^SUB_BUCKET_BITS` of their magnitude.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const NUM_BUCKETS: usize = SUB_BUCKETS + (u64::BITS - SUB_BUCKET_BITS) as usize * SUB_BUCKETS;
+
+/// A histogram of latencies with logarithmically sized buckets, in the style of HdrHistogram.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: vec![0; NUM_BUCKETS],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.buckets[bucket_index(value)] += 1;
+        self.count += 1;
+        self.sum += u128::from(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<u64> {
+        (self.count > 0).then(|| (self.sum / u128::from(self.count)).try_into().unwrap())
+    }
+
+    /// The smallest value which at least `permille / 1000` of the recorded values do not exceed,
+    /// to within the resolution of the histogram.
+    pub fn value_at_permille(&self, permille: u32) -> Option<u64> {
+        assert!(permille <= 1000);
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (self.count * u64::from(permille) + 999) / 1000;
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank.max(1) {
+                return Some(bucket_upper_bound(i).clamp(self.min, self.max));
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let exponent = u64::BITS - 1 - value.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub_bucket = (value >> shift) as usize & (SUB_BUCKETS - 1);
+    SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub_bucket
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = ((index - SUB_BUCKETS) / SUB_BUCKETS) as u32;
+    let sub_bucket = ((index - SUB_BUCKETS) % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + sub_bucket) << shift;
+    lower + ((1 << shift) - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets_cover_values() {
+        for value in (0..100_000).chain([u64::MAX - 1, u64::MAX]) {
+            let i = bucket_index(value);
+            assert!(value <= bucket_upper_bound(i));
+            assert!(i == 0 || value > bucket_upper_bound(i - 1));
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = LatencyHistogram::new();
+        for value in 1..=1000 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.value_at_permille(0), Some(1));
+        assert_eq!(histogram.value_at_permille(1000), Some(1000));
+        let p50 = histogram.value_at_permille(500).unwrap();
+        assert!((500..500 + 500 / 16).contains(&p50));
+        let p990 = histogram.value_at_permille(990).unwrap();
+        assert!((990..990 + 990 / 16).contains(&p990));
+    }
+}
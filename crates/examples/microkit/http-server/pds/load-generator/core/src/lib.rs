@@ -0,0 +1,16 @@
+//! Replaying recorded request traces and summarizing their latencies, independently of how
+//! requests are sent and time is measured.
+
+#![no_std]
+
+extern crate alloc;
+
+mod histogram;
+mod replay;
+mod trace;
+
+pub use histogram::LatencyHistogram;
+pub use replay::{Action, Replayer, Report};
+pub use trace::{parse_trace, ParseError, ParseErrorKind, TraceEntry};
+
+pub type Microseconds = u64;
@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{LatencyHistogram, Microseconds, TraceEntry};
+
+const REPORTED_PERMILLES: &[u32] = &[500, 900, 990, 999];
+
+/// Replays a trace open-loop: each request is due at a fixed offset from the start of the replay,
+/// regardless of how long earlier requests took.
+///
+/// Two latencies are recorded for each request. The service time runs from when the request was
+/// actually sent. The response time runs from when it was due, and so includes any time spent
+/// waiting for earlier requests, which the service time alone would hide.
+pub struct Replayer {
+    entries: Vec<TraceEntry>,
+    next: usize,
+    start: Option<Microseconds>,
+    service_times: LatencyHistogram,
+    response_times: LatencyHistogram,
+}
+
+pub enum Action<'a> {
+    /// Send this request, then call [`Replayer::record_response`].
+    Send(&'a TraceEntry),
+    /// Nothing is due until this time.
+    WaitUntil(Microseconds),
+    Done,
+}
+
+impl Replayer {
+    pub fn new(entries: Vec<TraceEntry>) -> Self {
+        Self {
+            entries,
+            next: 0,
+            start: None,
+            service_times: LatencyHistogram::new(),
+            response_times: LatencyHistogram::new(),
+        }
+    }
+
+    /// The replay starts at the `now` of the first call.
+    pub fn next_action(&mut self, now: Microseconds) -> Action<'_> {
+        let start = *self.start.get_or_insert(now);
+        match self.entries.get(self.next) {
+            None => Action::Done,
+            Some(entry) if now < start + entry.at => Action::WaitUntil(start + entry.at),
+            Some(entry) => Action::Send(entry),
+        }
+    }
+
+    /// Records the response to the request most recently returned by [`Replayer::next_action`].
+    pub fn record_response(&mut self, sent: Microseconds, received: Microseconds) {
+        let due = self.start.unwrap() + self.entries[self.next].at;
+        self.service_times.record(received.saturating_sub(sent));
+        self.response_times.record(received.saturating_sub(due));
+        self.next += 1;
+    }
+
+    pub fn service_times(&self) -> &LatencyHistogram {
+        &self.service_times
+    }
+
+    pub fn response_times(&self) -> &LatencyHistogram {
+        &self.response_times
+    }
+
+    pub fn report(&self) -> Report<'_> {
+        Report { replayer: self }
+    }
+}
+
+/// A summary of a replay's latencies, for display.
+pub struct Report<'a> {
+    replayer: &'a Replayer,
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} requests completed",
+            self.replayer.next,
+            self.replayer.entries.len()
+        )?;
+        for (name, histogram) in [
+            ("service time", &self.replayer.service_times),
+            ("response time", &self.replayer.response_times),
+        ] {
+            write!(f, "{name} (us):")?;
+            match (histogram.min(), histogram.mean(), histogram.max()) {
+                (Some(min), Some(mean), Some(max)) => {
+                    write!(f, " min={min} mean={mean}")?;
+                    for permille in REPORTED_PERMILLES {
+                        let value = histogram.value_at_permille(*permille).unwrap();
+                        write!(f, " p{}={value}", Permille(*permille))?;
+                    }
+                    writeln!(f, " max={max}")?;
+                }
+                _ => writeln!(f, " none")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Permille(u32);
+
+impl fmt::Display for Permille {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 % 10 {
+            0 => write!(f, "{}", self.0 / 10),
+            tenths => write!(f, "{}.{}", self.0 / 10, tenths),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::num::ParseIntError;
+
+use crate::Microseconds;
+
+/// A request to be sent as a protected procedure call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// When the request should be sent, relative to the start of the replay.
+    pub at: Microseconds,
+    pub label: u64,
+    pub msg_regs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based.
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingField,
+    InvalidNumber(ParseIntError),
+    OutOfOrder,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::MissingField => write!(f, "expected a time and a label"),
+            ParseErrorKind::InvalidNumber(err) => write!(f, "invalid number: {err}"),
+            ParseErrorKind::OutOfOrder => write!(f, "entry is earlier than the one before it"),
+        }
+    }
+}
+
+/// Parses a trace with one request per line, of the form `<at> <label> [<msg_reg>...]`.
+///
+/// `<at>` is in microseconds, and must not decrease from one line to the next. Numbers are
+/// decimal, or hexadecimal with a `0x` prefix. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn parse_trace(text: &str) -> Result<Vec<TraceEntry>, ParseError> {
+    let mut entries: Vec<TraceEntry> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |kind| ParseError { line: i + 1, kind };
+        let mut fields = line.split_whitespace().map(parse_number);
+        let mut next_field = || {
+            fields
+                .next()
+                .ok_or(ParseErrorKind::MissingField)?
+                .map_err(ParseErrorKind::InvalidNumber)
+        };
+        let at = next_field().map_err(err)?;
+        let label = next_field().map_err(err)?;
+        let msg_regs = fields
+            .collect::<Result<_, _>>()
+            .map_err(|e| err(ParseErrorKind::InvalidNumber(e)))?;
+        if entries.last().is_some_and(|prev| prev.at > at) {
+            return Err(err(ParseErrorKind::OutOfOrder));
+        }
+        entries.push(TraceEntry {
+            at,
+            label,
+            msg_regs,
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_number(s: &str) -> Result<u64, ParseIntError> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+}
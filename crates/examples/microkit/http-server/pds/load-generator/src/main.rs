@@ -0,0 +1,99 @@
+//! Replays a trace of protected procedure calls against the service on the `TARGET` channel, and
+//! prints a summary of their latencies once the trace has been exhausted.
+//!
+//! Time is measured using the timer driver on the `TIMER` channel, so measured latencies include
+//! the cost of a call to the timer driver. The SP804 driver serves a single client, so this
+//! protection domain needs its own instance of the driver, or must take the place of the HTTP
+//! server in the system description.
+//!
+//! The trace is embedded at build time from the path in `LOAD_GENERATOR_TRACE`, or from
+//! `traces/default.trace` if it is unset. See `parse_trace` for the format.
+
+#![no_std]
+#![no_main]
+#![feature(never_type)]
+
+extern crate alloc;
+
+use sel4_microkit::{
+    debug_println, protection_domain, with_msg_regs_mut, Channel, Handler, MessageInfo,
+};
+
+use microkit_http_server_example_load_generator_core::{parse_trace, Action, Replayer};
+
+mod timer_client;
+
+use timer_client::TimerClient;
+
+const TIMER: Channel = Channel::new(0);
+const TARGET: Channel = Channel::new(1);
+
+const TRACE: &str = include_str!(concat!(env!("OUT_DIR"), "/trace.txt"));
+
+#[protection_domain(
+    heap_size = 1024 * 1024,
+)]
+fn init() -> HandlerImpl {
+    let entries = parse_trace(TRACE).unwrap_or_else(|err| panic!("invalid trace: {err}"));
+    let mut handler = HandlerImpl {
+        timer: TimerClient::new(TIMER),
+        replayer: Replayer::new(entries),
+        reported: false,
+    };
+    handler.step();
+    handler
+}
+
+struct HandlerImpl {
+    timer: TimerClient,
+    replayer: Replayer,
+    reported: bool,
+}
+
+impl HandlerImpl {
+    // Sends each request which is due, and then either sets a timeout for the next one or reports
+    // the results.
+    fn step(&mut self) {
+        while !self.reported {
+            let now = self.timer.now();
+            match self.replayer.next_action(now) {
+                Action::Send(entry) => {
+                    let msg_info =
+                        MessageInfo::new(entry.label.try_into().unwrap(), entry.msg_regs.len());
+                    with_msg_regs_mut(|msg_regs| {
+                        for (msg_reg, value) in msg_regs.iter_mut().zip(&entry.msg_regs) {
+                            *msg_reg = (*value).try_into().unwrap();
+                        }
+                    });
+                    TARGET.pp_call(msg_info);
+                    let received = self.timer.now();
+                    self.replayer.record_response(now, received);
+                }
+                Action::WaitUntil(due) => {
+                    self.timer.set_timeout(due - now);
+                    return;
+                }
+                Action::Done => {
+                    debug_println!("{}", self.replayer.report());
+                    self.reported = true;
+                }
+            }
+        }
+    }
+}
+
+impl Handler for HandlerImpl {
+    type Error = !;
+
+    fn notified(&mut self, channel: Channel) -> Result<(), Self::Error> {
+        match channel {
+            TIMER => {
+                self.step();
+            }
+            _ => {
+                unreachable!()
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! JSON request and response bodies, via `serde-json-core`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+const INITIAL_ENCODE_BUFFER_SIZE: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DecodeError {
+    pub(crate) err: serde_json_core::de::Error,
+    /// 1-based line and column of the byte at which decoding failed.
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.err
+        )
+    }
+}
+
+pub(crate) fn decode<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, DecodeError> {
+    serde_json_core::from_slice(body)
+        .map(|(value, _)| value)
+        .map_err(|err| {
+            let pos = error_offset::<T>(body, &err).saturating_sub(1);
+            let preceding = &body[..pos];
+            let line_start = preceding
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(0, |i| i + 1);
+            DecodeError {
+                err,
+                line: preceding.iter().filter(|b| **b == b'\n').count() + 1,
+                column: pos - line_start + 1,
+            }
+        })
+}
+
+// `serde-json-core` does not report where an error occurred. Decoding a prefix which ends before
+// the offending byte fails by running out of input instead, so the error is located by searching
+// for the shortest prefix on which decoding fails in the same way as on the whole input. The
+// offending byte is the last byte of that prefix.
+fn error_offset<'a, T: Deserialize<'a>>(body: &'a [u8], err: &serde_json_core::de::Error) -> usize {
+    let fails_in_same_way = |len: usize| matches!(serde_json_core::from_slice::<T>(&body[..len]), Err(prefix_err) if &prefix_err == err);
+    let mut lo = 0;
+    let mut hi = body.len();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if fails_in_same_way(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    lo
+}
+
+pub(crate) fn encode<T: Serialize + ?Sized>(value: &T) -> Vec<u8> {
+    let mut buf = vec![0; INITIAL_ENCODE_BUFFER_SIZE];
+    loop {
+        match serde_json_core::to_slice(value, &mut buf) {
+            Ok(n) => {
+                buf.truncate(n);
+                return buf;
+            }
+            Err(serde_json_core::ser::Error::BufferFull) => {
+                buf.resize(buf.len() * 2, 0);
+            }
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 #![no_std]
+#![feature(async_fn_in_trait)]
 #![feature(pattern)]
 
 extern crate alloc;
@@ -12,16 +13,21 @@ use futures::task::LocalSpawnExt;
 
 use mbedtls::ssl::async_io::ClosedError;
 
-use sel4_async_block_io::BytesIO;
+use sel4_async_block_io::LendingBytesIO;
 use sel4_async_block_io_cpiofs as cpiofs;
-use sel4_async_network::{SharedNetwork, TcpSocketError};
+use sel4_async_network::{
+    PooledTcpSocket, SharedNetwork, TcpSocketError, TcpSocketPool, TcpSocketPoolConfig,
+};
 use sel4_async_network_mbedtls::{
     insecure_dummy_rng, mbedtls, seed_insecure_dummy_rng, DbgCallbackBuilder, TcpSocketWrapper,
 };
 use sel4_async_single_threaded_executor::LocalSpawner;
 use sel4_async_timers::SharedTimers;
 
+mod json;
 mod mime;
+mod multipart;
+mod send_file;
 mod server;
 
 use server::Server;
@@ -31,10 +37,12 @@ const HTTPS_PORT: u16 = 443;
 
 const NUM_SIMULTANEOUS_CONNECTIONS: usize = 32;
 
-type SocketUser = Box<dyn Fn(TcpSocketWrapper) -> LocalBoxFuture<'static, ()>>;
+type SocketWrapper = TcpSocketWrapper<PooledTcpSocket>;
+
+type SocketUser = Box<dyn Fn(SocketWrapper) -> LocalBoxFuture<'static, ()>>;
 
-pub async fn run_server<T: BytesIO + 'static>(
-    _timers_ctx: SharedTimers,
+pub async fn run_server<T: LendingBytesIO + 'static>(
+    timers_ctx: SharedTimers,
     network_ctx: SharedNetwork,
     fs_io: T,
     spawner: LocalSpawner,
@@ -50,11 +58,14 @@ pub async fn run_server<T: BytesIO + 'static>(
 
     let index = cpiofs::Index::create(fs_io).await;
 
-    let server = Rc::new(Server::new(index));
+    let network_config = network_ctx.wait_for_config().await;
+    log::info!("Serving on {}", network_config.address.address());
+
+    let server = Rc::new(Server::new(index, timers_ctx));
 
     let use_socket_for_http_closure: SocketUser = Box::new({
         let server = server.clone();
-        move |socket: TcpSocketWrapper| {
+        move |socket: SocketWrapper| {
             let server = server.clone();
             Box::pin(async move {
                 use_socket_for_http(&server, socket)
@@ -69,7 +80,7 @@ pub async fn run_server<T: BytesIO + 'static>(
     let use_socket_for_https_closure: SocketUser = Box::new({
         let server = server.clone();
         let config = Arc::new(mk_config(cert_pem, priv_pem).unwrap());
-        move |socket: TcpSocketWrapper| {
+        move |socket: SocketWrapper| {
             let server = server.clone();
             let config = config.clone();
             Box::pin(async move {
@@ -83,14 +94,22 @@ pub async fn run_server<T: BytesIO + 'static>(
     });
 
     for f in [use_socket_for_http_closure, use_socket_for_https_closure].map(Rc::new) {
+        let pool = TcpSocketPool::new(
+            &network_ctx,
+            TcpSocketPoolConfig {
+                num_sockets: NUM_SIMULTANEOUS_CONNECTIONS,
+                rx_buffer_size: 8192,
+                tx_buffer_size: 65535,
+            },
+        );
         for _ in 0..NUM_SIMULTANEOUS_CONNECTIONS {
             spawner
                 .spawn_local({
-                    let network_ctx = network_ctx.clone();
+                    let pool = pool.clone();
                     let f = f.clone();
                     async move {
                         loop {
-                            let socket = network_ctx.new_tcp_socket_with_buffer_sizes(8192, 65535);
+                            let socket = pool.acquire().await;
                             f(TcpSocketWrapper::new(socket)).await;
                         }
                     }
@@ -102,9 +121,9 @@ pub async fn run_server<T: BytesIO + 'static>(
     future::pending().await
 }
 
-async fn use_socket_for_http<T: BytesIO>(
+async fn use_socket_for_http<T: LendingBytesIO>(
     server: &Server<T>,
-    mut socket: TcpSocketWrapper,
+    mut socket: SocketWrapper,
 ) -> Result<(), ClosedError<TcpSocketError>> {
     socket.inner_mut().accept(HTTP_PORT).await?;
     server.handle_connection(&mut socket).await?;
@@ -112,10 +131,10 @@ async fn use_socket_for_http<T: BytesIO>(
     Ok(())
 }
 
-async fn use_socket_for_https<T: BytesIO>(
+async fn use_socket_for_https<T: LendingBytesIO>(
     server: &Server<T>,
     config: Arc<mbedtls::ssl::Config>,
-    mut socket: TcpSocketWrapper,
+    mut socket: SocketWrapper,
 ) -> Result<(), ClosedError<mbedtls::Error>> {
     socket.inner_mut().accept(HTTPS_PORT).await.unwrap(); // TODO
     let mut ctx = mbedtls::ssl::Context::new(config);
@@ -0,0 +1,233 @@
+//! Streaming `multipart/form-data` parsing (RFC 7578) with bounded memory.
+//!
+//! The parser owns a fixed-size buffer. Callers alternate between filling
+//! [`Parser::spare`] with more of the request body and draining [`Parser::next_event`] until it
+//! returns `None`. Part bodies are yielded in pieces as they arrive, so parts of any size can be
+//! handled.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str;
+
+const BUFFER_SIZE: usize = 4096;
+
+// RFC 2046, Section 5.1.1
+const MAX_BOUNDARY_LEN: usize = 70;
+
+const MAX_PART_HEADERS: usize = 8;
+
+pub(crate) struct Parser {
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    state: State,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    Preamble,
+    Delimiter,
+    Headers,
+    Body,
+    Epilogue,
+}
+
+#[derive(Debug)]
+pub(crate) enum Event<'a> {
+    PartStart(PartHeaders),
+    Data(&'a [u8]),
+    PartEnd,
+    End,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PartHeaders {
+    pub(crate) name: Option<String>,
+    pub(crate) filename: Option<String>,
+    pub(crate) content_type: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Error {
+    InvalidBoundary,
+    InvalidDelimiter,
+    InvalidHeaders,
+    HeadersTooLarge,
+}
+
+impl Parser {
+    pub(crate) fn new(boundary: &str) -> Result<Self, Error> {
+        if boundary.is_empty() || boundary.len() > MAX_BOUNDARY_LEN {
+            return Err(Error::InvalidBoundary);
+        }
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        let mut buf = vec![0; BUFFER_SIZE];
+        // The first delimiter may appear at the very start of the body, without a preceding CRLF.
+        buf[..2].copy_from_slice(b"\r\n");
+        Ok(Self {
+            delimiter,
+            buf,
+            start: 0,
+            end: 2,
+            state: State::Preamble,
+        })
+    }
+
+    /// Space into which more of the body can be read, followed by a call to [`Parser::fill`].
+    /// Non-empty once [`Parser::next_event`] has returned `None`.
+    pub(crate) fn spare(&mut self) -> &mut [u8] {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        &mut self.buf[self.end..]
+    }
+
+    pub(crate) fn fill(&mut self, n: usize) {
+        assert!(self.end + n <= self.buf.len());
+        self.end += n;
+    }
+
+    /// Whether the closing delimiter has been reached.
+    pub(crate) fn is_done(&self) -> bool {
+        self.state == State::Epilogue
+    }
+
+    /// Returns the next event, or `None` if more input is required.
+    pub(crate) fn next_event(&mut self) -> Result<Option<Event<'_>>, Error> {
+        let delimiter_len = self.delimiter.len();
+        loop {
+            let pending = &self.buf[self.start..self.end];
+            match self.state {
+                State::Preamble => match find(pending, &self.delimiter) {
+                    Some(i) => {
+                        self.start += i + delimiter_len;
+                        self.state = State::Delimiter;
+                    }
+                    None => {
+                        self.start = self.end - pending.len().min(delimiter_len - 1);
+                        return Ok(None);
+                    }
+                },
+                State::Delimiter => {
+                    if pending.len() < 2 {
+                        return Ok(None);
+                    }
+                    let suffix = &pending[..2];
+                    self.start += 2;
+                    match suffix {
+                        b"--" => {
+                            self.state = State::Epilogue;
+                            return Ok(Some(Event::End));
+                        }
+                        b"\r\n" => {
+                            self.state = State::Headers;
+                        }
+                        _ => return Err(Error::InvalidDelimiter),
+                    }
+                }
+                State::Headers => {
+                    let mut headers = [httparse::EMPTY_HEADER; MAX_PART_HEADERS];
+                    match httparse::parse_headers(pending, &mut headers) {
+                        Ok(httparse::Status::Complete((n, headers))) => {
+                            let part_headers = PartHeaders::from_headers(headers);
+                            self.start += n;
+                            self.state = State::Body;
+                            return Ok(Some(Event::PartStart(part_headers)));
+                        }
+                        Ok(httparse::Status::Partial) => {
+                            if pending.len() == self.buf.len() {
+                                return Err(Error::HeadersTooLarge);
+                            }
+                            return Ok(None);
+                        }
+                        Err(_) => return Err(Error::InvalidHeaders),
+                    }
+                }
+                State::Body => {
+                    let n = match find(pending, &self.delimiter) {
+                        Some(0) => {
+                            self.start += delimiter_len;
+                            self.state = State::Delimiter;
+                            return Ok(Some(Event::PartEnd));
+                        }
+                        Some(i) => i,
+                        // The tail of the buffer may hold the beginning of a delimiter.
+                        None => pending.len().saturating_sub(delimiter_len - 1),
+                    };
+                    if n == 0 {
+                        return Ok(None);
+                    }
+                    let data = self.start..self.start + n;
+                    self.start += n;
+                    return Ok(Some(Event::Data(&self.buf[data])));
+                }
+                State::Epilogue => {
+                    self.start = self.end;
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+impl PartHeaders {
+    fn from_headers(headers: &[httparse::Header]) -> Self {
+        let mut this = Self::default();
+        for header in headers {
+            let Ok(value) = str::from_utf8(header.value) else {
+                continue;
+            };
+            if header.name.eq_ignore_ascii_case("Content-Disposition") {
+                for (key, value) in params(value) {
+                    if key.eq_ignore_ascii_case("name") {
+                        this.name = Some(value.to_string());
+                    } else if key.eq_ignore_ascii_case("filename") {
+                        this.filename = Some(value.to_string());
+                    }
+                }
+            } else if header.name.eq_ignore_ascii_case("Content-Type") {
+                this.content_type = Some(value.trim().to_string());
+            }
+        }
+        this
+    }
+}
+
+/// Extracts the boundary from the value of a request's `Content-Type` header, if it is
+/// `multipart/form-data`.
+pub(crate) fn boundary_from_content_type(content_type: &str) -> Option<&str> {
+    let (media_type, _) = content_type.split_once(';')?;
+    if !media_type
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    params(content_type)
+        .find(|(key, _)| key.eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value)
+}
+
+// Parameters following the first `;` of a header value, with quotes removed.
+fn params(value: &str) -> impl Iterator<Item = (&str, &str)> {
+    value.split(';').skip(1).filter_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        Some((key.trim(), value))
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,58 @@
+//! Sending file contents over a connection.
+//!
+//! By default, file contents are read into an intermediate buffer, which is then passed to
+//! [`AsyncIo::send`]. Over plain TCP, this can be avoided: slices of cached blocks are handed
+//! directly to [`TcpSocket::try_send`], which copies them straight into smoltcp's transmit buffer.
+//! This is the only copy of file contents between the block cache and the network device.
+
+use alloc::vec;
+
+use mbedtls::ssl::async_io::{AsyncIo, AsyncIoExt, ClosedError};
+
+use sel4_async_block_io::LendingBytesIO;
+use sel4_async_block_io_cpiofs as cpiofs;
+use sel4_async_network::{PooledTcpSocket, TcpSocketError};
+use sel4_async_network_mbedtls::{mbedtls, TcpSocketWrapper};
+
+const BUFFER_SIZE: usize = 2048;
+
+pub(crate) trait SendFile: AsyncIo {
+    async fn send_file<T: LendingBytesIO>(
+        &mut self,
+        index: &cpiofs::Index<T>,
+        entry: &cpiofs::Entry,
+    ) -> Result<(), ClosedError<Self::Error>> {
+        let mut buf = vec![0; BUFFER_SIZE];
+        let mut pos = 0;
+        while pos < entry.data_size() {
+            let n = buf.len().min(entry.data_size() - pos);
+            index.read_data(entry, pos, &mut buf[..n]).await;
+            self.send_all(&buf[..n]).await?;
+            pos += n;
+        }
+        Ok(())
+    }
+}
+
+impl SendFile for TcpSocketWrapper<PooledTcpSocket> {
+    async fn send_file<T: LendingBytesIO>(
+        &mut self,
+        index: &cpiofs::Index<T>,
+        entry: &cpiofs::Entry,
+    ) -> Result<(), ClosedError<TcpSocketError>> {
+        let socket = self.inner_mut();
+        let mut pos = 0;
+        while pos < entry.data_size() {
+            socket.send_ready().await?;
+            pos += index
+                .read_data_with(entry, pos, entry.data_size() - pos, |data| {
+                    socket.try_send(data)
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// Contents must be encrypted, so the default implementation is used.
+impl<T: AsyncIo> SendFile for mbedtls::ssl::Context<T> {}
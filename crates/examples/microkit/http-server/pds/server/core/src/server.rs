@@ -2,26 +2,57 @@ use alloc::borrow::ToOwned;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::str;
 use core::str::pattern::Pattern;
 
+use futures::future::FutureExt;
+use serde::{Deserialize, Serialize};
+
 use mbedtls::ssl::async_io::{AsyncIo, AsyncIoExt, ClosedError};
 
-use sel4_async_block_io::BytesIO;
+use sel4_async_block_io::LendingBytesIO;
 use sel4_async_block_io_cpiofs as cpiofs;
 use sel4_async_network_mbedtls::mbedtls;
+use sel4_async_timers::{SharedTimers, SystemTime};
+use sel4_async_tmpfs::TmpFs;
 
+use crate::json;
 use crate::mime::content_type_from_name;
+use crate::multipart;
+use crate::send_file::SendFile;
+
+const UPLOAD_PATH: &str = "/upload";
+const UPLOAD_DIR: &str = "uploads";
+const MAX_UPLOAD_SIZE: usize = 16 * 1024 * 1024;
+const API_PATH_PREFIX: &str = "/api/";
+const MAX_API_REQUEST_BODY_SIZE: usize = 4096;
 
 pub(crate) struct Server<T> {
     index: cpiofs::Index<T>,
+    timers: SharedTimers,
+    // `TmpFs` operations complete immediately, so they are driven to completion in place with
+    // `now_or_never` rather than holding a borrow of this across an await point.
+    uploads: RefCell<TmpFs>,
 }
 
-impl<T: BytesIO> Server<T> {
-    pub(crate) fn new(index: cpiofs::Index<T>) -> Self {
-        Self { index }
+impl<T: LendingBytesIO> Server<T> {
+    pub(crate) fn new(index: cpiofs::Index<T>, timers: SharedTimers) -> Self {
+        let mut uploads = TmpFs::new();
+        uploads
+            .create_dir(UPLOAD_DIR)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        Self {
+            index,
+            timers,
+            uploads: RefCell::new(uploads),
+        }
     }
 
-    pub(crate) async fn handle_connection<U: AsyncIo>(
+    pub(crate) async fn handle_connection<U: SendFile>(
         &self,
         conn: &mut U,
     ) -> Result<(), ClosedError<U::Error>> {
@@ -41,10 +72,25 @@ impl<T: BytesIO> Server<T> {
             let mut keep_alive = false;
             match req.parse(&buf) {
                 Ok(status) => {
-                    assert!(status.is_complete());
-                    self.handle_request(conn, req.path.unwrap()).await?;
-                    if should_keep_alive(&req) {
-                        keep_alive = true;
+                    let header_len = match status {
+                        httparse::Status::Complete(n) => n,
+                        httparse::Status::Partial => panic!(),
+                    };
+                    // For requests with bodies, the body may not have been consumed in full, so the
+                    // connection cannot be reused.
+                    if req.method == Some("POST") && req.path == Some(UPLOAD_PATH) {
+                        self.handle_upload(conn, &req, &buf[header_len..i]).await?;
+                    } else if req
+                        .path
+                        .is_some_and(|path| API_PATH_PREFIX.is_prefix_of(path))
+                    {
+                        self.handle_api_request(conn, &req, &buf[header_len..i])
+                            .await?;
+                    } else {
+                        self.handle_request(conn, req.path.unwrap()).await?;
+                        if should_keep_alive(&req) {
+                            keep_alive = true;
+                        }
                     }
                 }
                 Err(err) => {
@@ -58,7 +104,7 @@ impl<T: BytesIO> Server<T> {
         Ok(())
     }
 
-    async fn handle_request<U: AsyncIo>(
+    async fn handle_request<U: SendFile>(
         &self,
         conn: &mut U,
         request_path: &str,
@@ -78,7 +124,232 @@ impl<T: BytesIO> Server<T> {
         Ok(())
     }
 
-    async fn serve_file<U: AsyncIo>(
+    /// Receives a `multipart/form-data` body, storing each file it contains under [`UPLOAD_DIR`].
+    /// Other form fields are ignored.
+    async fn handle_upload<U: AsyncIo>(
+        &self,
+        conn: &mut U,
+        req: &httparse::Request<'_, '_>,
+        body_prefix: &[u8],
+    ) -> Result<(), ClosedError<U::Error>> {
+        let content_length = find_header(req, "Content-Length")
+            .and_then(|value| str::from_utf8(value).ok())
+            .and_then(|value| value.trim().parse::<usize>().ok());
+        let boundary = find_header(req, "Content-Type")
+            .and_then(|value| str::from_utf8(value).ok())
+            .and_then(multipart::boundary_from_content_type);
+        let (Some(content_length), Some(boundary)) = (content_length, boundary) else {
+            return self
+                .serve_text(conn, 400, "Bad Request", "Bad Request")
+                .await;
+        };
+        if content_length > MAX_UPLOAD_SIZE {
+            return self
+                .serve_text(conn, 413, "Payload Too Large", "Payload Too Large")
+                .await;
+        }
+        let Ok(mut parser) = multipart::Parser::new(boundary) else {
+            return self
+                .serve_text(conn, 400, "Bad Request", "Bad Request")
+                .await;
+        };
+
+        let mut prefix = &body_prefix[..body_prefix.len().min(content_length)];
+        let mut remaining = content_length - prefix.len();
+        let mut current_file = None;
+        let mut offset = 0;
+        let mut stored = vec![];
+        loop {
+            loop {
+                let event = match parser.next_event() {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(err) => {
+                        log::warn!("error parsing multipart body: {err:?}");
+                        return self
+                            .serve_text(conn, 400, "Bad Request", "Bad Request")
+                            .await;
+                    }
+                };
+                let result = match event {
+                    multipart::Event::PartStart(headers) => {
+                        log::debug!(
+                            "receiving part {:?} ({:?})",
+                            headers.name,
+                            headers.content_type
+                        );
+                        offset = 0;
+                        current_file = headers
+                            .filename
+                            .as_deref()
+                            .and_then(sanitize_filename)
+                            .map(|name| format!("{UPLOAD_DIR}/{name}"));
+                        match &current_file {
+                            Some(path) => self.create_upload(path),
+                            None => Ok(()),
+                        }
+                    }
+                    multipart::Event::Data(data) => match &current_file {
+                        Some(path) => {
+                            let result = self
+                                .uploads
+                                .borrow_mut()
+                                .write(path, offset, data)
+                                .now_or_never()
+                                .unwrap();
+                            offset += data.len();
+                            result
+                        }
+                        None => Ok(()),
+                    },
+                    multipart::Event::PartEnd => {
+                        stored.extend(current_file.take());
+                        Ok(())
+                    }
+                    multipart::Event::End => Ok(()),
+                };
+                if let Err(err) = result {
+                    log::warn!("error storing upload: {err}");
+                    return self
+                        .serve_text(conn, 500, "Internal Server Error", "Internal Server Error")
+                        .await;
+                }
+            }
+            if parser.is_done() {
+                break;
+            }
+            let spare = parser.spare();
+            let n = if !prefix.is_empty() {
+                let n = spare.len().min(prefix.len());
+                spare[..n].copy_from_slice(&prefix[..n]);
+                prefix = &prefix[n..];
+                n
+            } else if remaining > 0 {
+                let n = spare.len().min(remaining);
+                let n = conn.recv(&mut spare[..n]).await?;
+                assert_ne!(n, 0);
+                remaining -= n;
+                n
+            } else {
+                log::warn!("multipart body ended before its closing delimiter");
+                return self
+                    .serve_text(conn, 400, "Bad Request", "Bad Request")
+                    .await;
+            };
+            parser.fill(n);
+        }
+
+        let mut body = format!("Stored {} file(s)\n", stored.len());
+        for path in &stored {
+            body.push_str(path);
+            body.push('\n');
+        }
+        self.serve_text(conn, 200, "OK", &body).await
+    }
+
+    async fn handle_api_request<U: AsyncIo>(
+        &self,
+        conn: &mut U,
+        req: &httparse::Request<'_, '_>,
+        body_prefix: &[u8],
+    ) -> Result<(), ClosedError<U::Error>> {
+        match (req.method.unwrap(), req.path.unwrap()) {
+            ("GET", "/api/uploads") => {
+                let uploads = self.list_uploads();
+                let uploads = uploads
+                    .iter()
+                    .map(|(name, size)| UploadInfo { name, size: *size })
+                    .collect::<Vec<_>>();
+                self.serve_json(conn, uploads.as_slice()).await
+            }
+            ("POST", "/api/uploads/remove") => {
+                let Some(body) = self.read_body(conn, req, body_prefix).await? else {
+                    return self
+                        .serve_text(conn, 400, "Bad Request", "Bad Request")
+                        .await;
+                };
+                let request = match json::decode::<RemoveUploadRequest>(&body) {
+                    Ok(request) => request,
+                    Err(err) => {
+                        return self
+                            .serve_text(conn, 400, "Bad Request", &format!("{err}\n"))
+                            .await;
+                    }
+                };
+                let removed = match sanitize_filename(request.name) {
+                    Some(name) => self
+                        .uploads
+                        .borrow_mut()
+                        .remove(&format!("{UPLOAD_DIR}/{name}"))
+                        .now_or_never()
+                        .unwrap()
+                        .is_ok(),
+                    None => false,
+                };
+                self.serve_json(conn, &RemoveUploadResponse { removed })
+                    .await
+            }
+            _ => self.serve_not_found(conn).await,
+        }
+    }
+
+    /// Reads a request body of at most [`MAX_API_REQUEST_BODY_SIZE`] bytes, or returns `None` if
+    /// the request has no `Content-Length` or exceeds that limit.
+    async fn read_body<U: AsyncIo>(
+        &self,
+        conn: &mut U,
+        req: &httparse::Request<'_, '_>,
+        body_prefix: &[u8],
+    ) -> Result<Option<Vec<u8>>, ClosedError<U::Error>> {
+        let content_length = find_header(req, "Content-Length")
+            .and_then(|value| str::from_utf8(value).ok())
+            .and_then(|value| value.trim().parse::<usize>().ok());
+        let Some(content_length) = content_length else {
+            return Ok(None);
+        };
+        if content_length > MAX_API_REQUEST_BODY_SIZE {
+            return Ok(None);
+        }
+        let mut body = vec![0; content_length];
+        let mut i = body_prefix.len().min(content_length);
+        body[..i].copy_from_slice(&body_prefix[..i]);
+        while i < content_length {
+            let n = conn.recv(&mut body[i..]).await?;
+            assert_ne!(n, 0);
+            i += n;
+        }
+        Ok(Some(body))
+    }
+
+    fn list_uploads(&self) -> Vec<(String, usize)> {
+        let uploads = self.uploads.borrow();
+        uploads
+            .read_dir(UPLOAD_DIR)
+            .now_or_never()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| {
+                let size = uploads
+                    .metadata(&format!("{UPLOAD_DIR}/{name}"))
+                    .now_or_never()
+                    .unwrap()
+                    .unwrap()
+                    .size;
+                (name, size)
+            })
+            .collect()
+    }
+
+    fn create_upload(&self, path: &str) -> Result<(), sel4_async_tmpfs::Error> {
+        let mut uploads = self.uploads.borrow_mut();
+        if uploads.metadata(path).now_or_never().unwrap().is_ok() {
+            uploads.remove(path).now_or_never().unwrap()?;
+        }
+        uploads.create_file(path).now_or_never().unwrap()
+    }
+
+    async fn serve_file<U: SendFile>(
         &self,
         conn: &mut U,
         content_type: &str,
@@ -93,17 +364,20 @@ impl<T: BytesIO> Server<T> {
             entry.data_size().to_string().as_bytes(),
         )
         .await?;
-        self.finish_response_headers(conn).await?;
-        {
-            let mut buf = vec![0; 2048];
-            let mut pos = 0;
-            while pos < entry.data_size() {
-                let n = buf.len().min(entry.data_size() - pos);
-                self.index.read_data(entry, pos, &mut buf[..n]).await;
-                conn.send_all(&buf[..n]).await?;
-                pos += n;
-            }
+        let mut last_modified = SystemTime::from_secs_since_unix_epoch(entry.mtime().into());
+        // RFC 9110, Section 8.8.2.1: a Last-Modified later than the Date must be replaced by the
+        // Date.
+        if let Some(now) = self.timers.system_time() {
+            last_modified = last_modified.min(now);
         }
+        self.send_response_header(
+            conn,
+            "Last-Modified",
+            last_modified.http_date().to_string().as_bytes(),
+        )
+        .await?;
+        self.finish_response_headers(conn).await?;
+        conn.send_file(&self.index, entry).await?;
         Ok(())
     }
 
@@ -137,6 +411,40 @@ impl<T: BytesIO> Server<T> {
         Ok(())
     }
 
+    async fn serve_text<U: AsyncIo>(
+        &self,
+        conn: &mut U,
+        status_code: usize,
+        reason_phrase: &str,
+        body: &str,
+    ) -> Result<(), ClosedError<U::Error>> {
+        self.start_response_headers(conn, status_code, reason_phrase)
+            .await?;
+        self.send_response_header(conn, "Content-Type", b"text/plain")
+            .await?;
+        self.send_response_header(conn, "Content-Length", body.len().to_string().as_bytes())
+            .await?;
+        self.finish_response_headers(conn).await?;
+        conn.send_all(body.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn serve_json<U: AsyncIo, V: Serialize + ?Sized>(
+        &self,
+        conn: &mut U,
+        value: &V,
+    ) -> Result<(), ClosedError<U::Error>> {
+        let body = json::encode(value);
+        self.start_response_headers(conn, 200, "OK").await?;
+        self.send_response_header(conn, "Content-Type", b"application/json")
+            .await?;
+        self.send_response_header(conn, "Content-Length", body.len().to_string().as_bytes())
+            .await?;
+        self.finish_response_headers(conn).await?;
+        conn.send_all(&body).await?;
+        Ok(())
+    }
+
     async fn start_response_headers<U: AsyncIo>(
         &self,
         conn: &mut U,
@@ -148,6 +456,11 @@ impl<T: BytesIO> Server<T> {
         conn.send_all(b" ").await?;
         conn.send_all(reason_phrase.as_bytes()).await?;
         conn.send_all(b"\r\n").await?;
+        // Omitted until the wall-clock time is known, as permitted by RFC 9110, Section 6.6.1.
+        if let Some(now) = self.timers.system_time() {
+            self.send_response_header(conn, "Date", now.http_date().to_string().as_bytes())
+                .await?;
+        }
         Ok(())
     }
 
@@ -180,8 +493,7 @@ impl<T: BytesIO> Server<T> {
         let normalized = request_path.trim_matches('/');
         if normalized.is_empty() {
             let file_path = "index.html";
-            if let Some(location) = self.index.lookup(file_path) {
-                let entry = self.index.read_entry(location).await;
+            if let Some(&entry) = self.index.lookup(file_path) {
                 if entry.ty() == cpiofs::EntryType::RegularFile {
                     return RequestPathStatus::Ok {
                         file_path: file_path.to_owned(),
@@ -189,8 +501,7 @@ impl<T: BytesIO> Server<T> {
                     };
                 }
             }
-        } else if let Some(location) = self.index.lookup(normalized) {
-            let entry = self.index.read_entry(location).await;
+        } else if let Some(&entry) = self.index.lookup(normalized) {
             match entry.ty() {
                 cpiofs::EntryType::RegularFile => {
                     return RequestPathStatus::Ok {
@@ -205,8 +516,7 @@ impl<T: BytesIO> Server<T> {
                         };
                     }
                     let normalized_with_index_html = format!("{}/index.html", normalized);
-                    if let Some(location) = self.index.lookup(&normalized_with_index_html) {
-                        let entry = self.index.read_entry(location).await;
+                    if let Some(&entry) = self.index.lookup(&normalized_with_index_html) {
                         return RequestPathStatus::Ok {
                             file_path: normalized_with_index_html,
                             entry,
@@ -221,6 +531,22 @@ impl<T: BytesIO> Server<T> {
     }
 }
 
+#[derive(Debug, Serialize)]
+struct UploadInfo<'a> {
+    name: &'a str,
+    size: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoveUploadRequest<'a> {
+    name: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct RemoveUploadResponse {
+    removed: bool,
+}
+
 #[derive(Debug)]
 enum RequestPathStatus {
     Ok {
@@ -239,6 +565,22 @@ fn is_request_complete(buf: &[u8]) -> Result<bool, httparse::Error> {
     req.parse(buf).map(|status| status.is_complete())
 }
 
+fn find_header<'a>(req: &httparse::Request<'_, 'a>, name: &str) -> Option<&'a [u8]> {
+    req.headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value)
+}
+
+// Keeps only the final path component of a client-supplied file name.
+fn sanitize_filename(filename: &str) -> Option<&str> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap();
+    match name {
+        "" | "." | ".." => None,
+        _ => Some(name),
+    }
+}
+
 fn should_keep_alive(req: &httparse::Request) -> bool {
     let version = req.version.unwrap();
     let default = match version {
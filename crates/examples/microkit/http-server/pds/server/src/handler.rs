@@ -5,15 +5,17 @@ use core::pin::Pin;
 use futures::future::LocalBoxFuture;
 
 use smoltcp::iface::Config;
-use smoltcp::time::{Duration, Instant};
+use smoltcp::time::Instant;
 
 use sel4_async_block_io::{BytesIOAdapter, CachedBlockIO};
 use sel4_async_network::{DhcpOverrides, SharedNetwork};
-use sel4_async_single_threaded_executor::{LocalPool, LocalSpawner};
+use sel4_async_single_threaded_executor::{
+    with_wake_source, Drive, EventSource, LocalPool, LocalSpawner, WakeSource,
+};
 use sel4_async_timers::SharedTimers;
 use sel4_shared_ring_buffer_block_io::BlockIO;
 
-use crate::{DeviceImpl, TimerClient, BLOCK_SIZE};
+use crate::{DeviceImpl, TimerClient, BLOCK_SIZE, LOGGER};
 
 type BytesIOImpl = BytesIOAdapter<CachedBlockIO<BlockIO, BLOCK_SIZE>, BLOCK_SIZE>;
 
@@ -23,13 +25,19 @@ pub(crate) struct HandlerImpl {
     timer_driver_channel: sel4_microkit::Channel,
     net_driver_channel: sel4_microkit::Channel,
     block_driver_channel: sel4_microkit::Channel,
+    event_sources: EventSources,
+    local_pool: LocalPool,
+    fut: LocalBoxFuture<'static, !>,
+}
+
+struct EventSources {
     timer: TimerClient,
     net_device: DeviceImpl,
     fs_block_io: BlockIO,
     shared_timers: SharedTimers,
     shared_network: SharedNetwork,
-    local_pool: LocalPool,
-    fut: LocalBoxFuture<'static, !>,
+    // As of the most recent call to `poll`.
+    now: Instant,
 }
 
 impl HandlerImpl {
@@ -44,7 +52,7 @@ impl HandlerImpl {
         fs_block_io: BlockIO,
         f: impl FnOnce(SharedTimers, SharedNetwork, BytesIOImpl, LocalSpawner) -> T,
     ) -> Self {
-        let now = Self::now_with_timer_client(&timer);
+        let now = now_with_timer_client(&timer);
 
         let shared_timers = SharedTimers::new(now);
 
@@ -70,11 +78,14 @@ impl HandlerImpl {
             timer_driver_channel,
             net_driver_channel,
             block_driver_channel,
-            timer,
-            net_device,
-            fs_block_io,
-            shared_timers,
-            shared_network,
+            event_sources: EventSources {
+                timer,
+                net_device,
+                fs_block_io,
+                shared_timers,
+                shared_network,
+                now,
+            },
             local_pool,
             fut,
         };
@@ -84,54 +95,60 @@ impl HandlerImpl {
         this
     }
 
-    fn now(&self) -> Instant {
-        Self::now_with_timer_client(&self.timer)
-    }
-
-    fn now_with_timer_client(timer: &TimerClient) -> Instant {
-        Instant::from_micros(i64::try_from(timer.now()).unwrap())
-    }
-
-    fn set_timeout(&self, d: Duration) {
-        self.timer.set_timeout(d.micros())
-    }
-
-    // TODO focused polling using these args doesn't play nicely with "repoll" mechanism below
+    // TODO focused polling using these args doesn't play nicely with "repoll" mechanism in
+    // `LocalPool::drive`
     fn react(
         &mut self,
         _timer_notification: bool,
         _net_notification: bool,
         _block_notification: bool,
     ) {
-        loop {
-            let _ = self.local_pool.run_until_stalled(Pin::new(&mut self.fut));
-            let now = self.now();
-            let mut activity = false;
-            activity |= self.shared_timers.poll(now);
-            activity |= self.net_device.poll();
-            activity |= self.shared_network.poll(now, &mut self.net_device);
-            activity |= self.fs_block_io.poll();
-            if !activity {
-                let delays = &[
-                    self.shared_timers.poll_delay(now),
-                    self.shared_network.poll_delay(now),
-                ];
-                let mut repoll = false;
-                if let Some(delay) = delays.iter().filter_map(Option::as_ref).min() {
-                    if delay == &Duration::ZERO {
-                        repoll = true;
-                    } else {
-                        self.set_timeout(*delay);
-                    }
-                }
-                if !repoll {
-                    break;
+        match self
+            .local_pool
+            .drive(Pin::new(&mut self.fut), &mut [&mut self.event_sources])
+        {
+            Drive::Complete(never) => never,
+            Drive::Idle { poll_delay } => {
+                LOGGER.drain();
+                if let Some(delay) = poll_delay {
+                    self.event_sources
+                        .timer
+                        .set_timeout(delay.as_micros().try_into().unwrap());
                 }
             }
         }
     }
 }
 
+impl EventSource for EventSources {
+    fn poll(&mut self) -> bool {
+        self.now = now_with_timer_client(&self.timer);
+        let mut activity = false;
+        activity |= with_wake_source(WakeSource::Timer, || self.shared_timers.poll(self.now));
+        with_wake_source(WakeSource::Network, || {
+            activity |= self.net_device.poll();
+            activity |= self.shared_network.poll(self.now, &mut self.net_device);
+        });
+        activity |= with_wake_source(WakeSource::BlockIO, || self.fs_block_io.poll());
+        activity
+    }
+
+    fn poll_delay(&mut self) -> Option<core::time::Duration> {
+        [
+            self.shared_timers.poll_delay(self.now),
+            self.shared_network.poll_delay(self.now),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .map(|delay| core::time::Duration::from_micros(delay.total_micros()))
+    }
+}
+
+fn now_with_timer_client(timer: &TimerClient) -> Instant {
+    Instant::from_micros(i64::try_from(timer.now()).unwrap())
+}
+
 impl sel4_microkit::Handler for HandlerImpl {
     type Error = !;
 
@@ -13,10 +13,10 @@ extern crate alloc;
 
 use smoltcp::iface::Config;
 use smoltcp::phy::{Device, Medium};
-use smoltcp::wire::{EthernetAddress, HardwareAddress};
+use smoltcp::wire::{EthernetAddress, HardwareAddress, ETHERNET_HEADER_LEN};
 
 use sel4_externally_shared::ExternallySharedRef;
-use sel4_logging::{LevelFilter, Logger, LoggerBuilder};
+use sel4_logging::{DeferredLogger, LevelFilter, LoggerBuilder};
 use sel4_microkit::{memory_region_symbol, protection_domain, var, Channel, Handler};
 use sel4_shared_ring_buffer::{RingBuffer, RingBuffers};
 use sel4_shared_ring_buffer_block_io::{BlockIO, BLOCK_SIZE};
@@ -42,11 +42,17 @@ const LOG_LEVEL: LevelFilter = {
     // LevelFilter::Warn
 };
 
-static LOGGER: Logger = LoggerBuilder::const_default()
-    .level_filter(LOG_LEVEL)
-    .filter(|meta| !meta.target().starts_with("sel4_sys"))
-    .write(|s| sel4::debug_print!("{}", s))
-    .build();
+const LOG_BUFFER_SIZE: usize = 64 * 1024;
+
+// Drained by `HandlerImpl` whenever it is idle, so that logging does not add latency to the
+// handling of events.
+static LOGGER: DeferredLogger<LOG_BUFFER_SIZE> = DeferredLogger::new(
+    LoggerBuilder::const_default()
+        .level_filter(LOG_LEVEL)
+        .filter(|meta| !meta.target().starts_with("sel4_sys"))
+        .write(|s| sel4::debug_print!("{}", s))
+        .build(),
+);
 
 const TIMER_DRIVER: Channel = Channel::new(0);
 const NET_DRIVER: Channel = Channel::new(1);
@@ -98,7 +104,8 @@ fn init() -> impl Handler {
         },
         16,
         2048,
-        1500,
+        // smoltcp includes the Ethernet header in the MTU.
+        net_client.get_mtu() + ETHERNET_HEADER_LEN,
     );
 
     let net_config = {
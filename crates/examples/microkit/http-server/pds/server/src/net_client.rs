@@ -21,4 +21,14 @@ impl NetClient {
             .unwrap();
         resp.mac_address
     }
+
+    pub fn get_mtu(&self) -> usize {
+        let req = Request::GetMtu;
+        let resp: GetMtuResponse = self
+            .channel
+            .pp_call(MessageInfo::send_using_postcard(req).unwrap())
+            .recv_using_postcard()
+            .unwrap();
+        resp.mtu
+    }
 }
@@ -0,0 +1,15 @@
+#![no_std]
+
+use serde::{Deserialize, Serialize};
+
+pub use microkit_http_server_example_adaptive_polling::PollingMetrics;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+    GetPollingMetrics,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPollingMetricsResponse {
+    pub metrics: PollingMetrics,
+}
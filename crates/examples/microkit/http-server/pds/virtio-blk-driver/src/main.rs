@@ -11,19 +11,21 @@ use core::ptr::NonNull;
 
 use virtio_drivers::{
     device::blk::*,
-    transport::{
-        mmio::{MmioTransport, VirtIOHeader},
-        DeviceType, Transport,
-    },
+    transport::{mmio::MmioTransport, DeviceType},
 };
 
 use sel4_externally_shared::ExternallySharedRef;
-use sel4_microkit::{memory_region_symbol, protection_domain, var, Channel, Handler};
+use sel4_microkit::{memory_region_symbol, protection_domain, var, Channel, Handler, MessageInfo};
+use sel4_microkit_message::MessageInfoExt as _;
 use sel4_shared_ring_buffer::{RingBuffer, RingBuffers};
 use sel4_shared_ring_buffer_block_io_types::{
     BlockIORequest, BlockIORequestStatus, BlockIORequestType,
 };
+use sel4_virtio_batching::{BatchingTransport, NotificationBatch};
+use sel4_virtio_mmio::{probe_region, MmioRegion, QEMU_VIRT_SLOT_SIZE};
 
+use microkit_http_server_example_adaptive_polling::{AdaptivePolling, PollingMode};
+use microkit_http_server_example_virtio_blk_driver_interface_types::*;
 use microkit_http_server_example_virtio_hal_impl::HalImpl;
 
 const DEVICE: Channel = Channel::new(0);
@@ -31,6 +33,9 @@ const CLIENT: Channel = Channel::new(1);
 
 // HACK hard-coded in virtio-drivers
 const QUEUE_SIZE: usize = 4;
+const QUEUE: u16 = 0;
+
+type Transport = BatchingTransport<MmioTransport>;
 
 #[protection_domain(
     heap_size = 64 * 1024,
@@ -39,18 +44,26 @@ fn init() -> HandlerImpl {
     HalImpl::init(
         *var!(virtio_blk_driver_dma_size: usize = 0),
         *var!(virtio_blk_driver_dma_vaddr: usize = 0),
-        *var!(virtio_blk_driver_dma_paddr: usize = 0),
+        sel4::PAddr::new(*var!(virtio_blk_driver_dma_paddr: usize = 0)),
     );
 
-    let mut dev = {
-        let header = NonNull::new(
-            (*var!(virtio_blk_mmio_vaddr: usize = 0) + *var!(virtio_blk_mmio_offset: usize = 0))
-                as *mut VirtIOHeader,
+    let (mut dev, notification_batch) = {
+        let region = MmioRegion {
+            vaddr: *var!(virtio_blk_mmio_vaddr: usize = 0),
+            paddr: *var!(virtio_blk_mmio_paddr: usize = 0),
+            size: *var!(virtio_blk_mmio_size: usize = 0),
+            slot_size: QEMU_VIRT_SLOT_SIZE,
+        };
+        let device = unsafe { probe_region(&region) }
+            .find(|device| device.device_type() == DeviceType::Block)
+            .unwrap();
+        let transport = unsafe { device.transport() }.unwrap();
+        // Requests are submitted in batches by HandlerImpl::service.
+        let (transport, notification_batch) = BatchingTransport::new(transport, &[QUEUE]);
+        (
+            VirtIOBlk::<HalImpl, Transport>::new(transport).unwrap(),
+            notification_batch,
         )
-        .unwrap();
-        let transport = unsafe { MmioTransport::new(header) }.unwrap();
-        assert_eq!(transport.device_type(), DeviceType::Block);
-        VirtIOBlk::<HalImpl, MmioTransport>::new(transport).unwrap()
     };
 
     let client_region = unsafe {
@@ -59,7 +72,8 @@ fn init() -> HandlerImpl {
         )
     };
 
-    let client_client_dma_region_paddr = *var!(virtio_blk_client_dma_paddr: usize = 0);
+    let client_client_dma_region_paddr =
+        sel4::PAddr::new(*var!(virtio_blk_client_dma_paddr: usize = 0));
 
     let ring_buffers = unsafe {
         RingBuffers::<'_, fn() -> Result<(), !>, BlockIORequest>::new(
@@ -75,10 +89,13 @@ fn init() -> HandlerImpl {
 
     HandlerImpl {
         dev,
+        notification_batch,
         client_region,
         client_client_dma_region_paddr,
         ring_buffers,
         pending: BTreeMap::new(),
+        pending_flush: None,
+        polling: AdaptivePolling::default(),
     }
 }
 
@@ -88,11 +105,146 @@ fn notify_client() -> Result<(), !> {
 }
 
 struct HandlerImpl {
-    dev: VirtIOBlk<HalImpl, MmioTransport>,
+    dev: VirtIOBlk<HalImpl, Transport>,
+    notification_batch: NotificationBatch<MmioTransport>,
     client_region: ExternallySharedRef<'static, [u8]>,
-    client_client_dma_region_paddr: usize,
+    client_client_dma_region_paddr: sel4::PAddr,
     ring_buffers: RingBuffers<'static, fn() -> Result<(), !>, BlockIORequest>,
     pending: BTreeMap<u16, Pin<Box<PendingEntry>>>,
+    pending_flush: Option<BlockIORequest>,
+    polling: AdaptivePolling,
+}
+
+impl HandlerImpl {
+    // Returns the number of requests completed.
+    fn service(&mut self) -> usize {
+        let mut num_completed = 0;
+        let mut notify = false;
+
+        while self.dev.peek_used().is_some() {
+            let token = self.dev.peek_used().unwrap();
+            let mut pending_entry = self.pending.remove(&token).unwrap();
+            let mut buf_ptr = self.client_buf(&pending_entry.client_req);
+            unsafe {
+                let pending_entry = &mut *pending_entry;
+                match pending_entry.client_req.ty().unwrap() {
+                    BlockIORequestType::Read => self.dev.complete_read_block(
+                        token,
+                        &pending_entry.virtio_req,
+                        buf_ptr.as_mut(),
+                        &mut pending_entry.virtio_resp,
+                    ),
+                    BlockIORequestType::Write => self.dev.complete_write_block(
+                        token,
+                        &pending_entry.virtio_req,
+                        buf_ptr.as_ref(),
+                        &mut pending_entry.virtio_resp,
+                    ),
+                    BlockIORequestType::Flush => unreachable!(),
+                }
+                .unwrap();
+            }
+            let status = match pending_entry.virtio_resp.status() {
+                RespStatus::OK => BlockIORequestStatus::Ok,
+                _ => BlockIORequestStatus::IOError,
+            };
+            self.complete(pending_entry.client_req, status);
+            num_completed += 1;
+            notify = true;
+        }
+
+        loop {
+            // A flush is a barrier: it is performed once all earlier requests have completed, and
+            // no later requests are submitted before it.
+            if let Some(client_req) = self.pending_flush {
+                if !self.pending.is_empty() {
+                    break;
+                }
+                self.pending_flush = None;
+                let dev = &mut self.dev;
+                let status = match self.notification_batch.unbatched(|| dev.flush()) {
+                    Ok(()) => BlockIORequestStatus::Ok,
+                    Err(_) => BlockIORequestStatus::IOError,
+                };
+                self.complete(client_req, status);
+                num_completed += 1;
+                notify = true;
+            }
+
+            if self.pending.len() >= QUEUE_SIZE || self.ring_buffers.free().is_empty() {
+                break;
+            }
+
+            let client_req = self.ring_buffers.free_mut().dequeue().unwrap();
+            let ty = client_req.ty().unwrap();
+            if ty == BlockIORequestType::Flush {
+                self.pending_flush = Some(client_req);
+                continue;
+            }
+            let mut pending_entry = Box::pin(PendingEntry {
+                client_req,
+                virtio_req: BlkReq::default(),
+                virtio_resp: BlkResp::default(),
+            });
+            let mut buf_ptr = self.client_buf(&client_req);
+            let token = unsafe {
+                let pending_entry = &mut *pending_entry;
+                match ty {
+                    BlockIORequestType::Read => self.dev.read_block_nb(
+                        client_req.block_id(),
+                        &mut pending_entry.virtio_req,
+                        buf_ptr.as_mut(),
+                        &mut pending_entry.virtio_resp,
+                    ),
+                    BlockIORequestType::Write => self.dev.write_block_nb(
+                        client_req.block_id(),
+                        &mut pending_entry.virtio_req,
+                        buf_ptr.as_ref(),
+                        &mut pending_entry.virtio_resp,
+                    ),
+                    BlockIORequestType::Flush => unreachable!(),
+                }
+                .unwrap()
+            };
+            assert!(self.pending.insert(token, pending_entry).is_none());
+            notify = true;
+        }
+
+        // Submit everything dequeued above with a single notification.
+        self.notification_batch.flush();
+
+        if notify {
+            self.ring_buffers.notify().unwrap();
+        }
+
+        num_completed
+    }
+
+    fn set_interrupts_suppressed(&self, suppressed: bool) {
+        self.notification_batch.set_interrupts_suppressed(
+            &[QUEUE],
+            suppressed,
+            HalImpl::dma_phys_to_virt,
+        )
+    }
+
+    fn client_buf(&mut self, client_req: &BlockIORequest) -> NonNull<[u8]> {
+        let buf_range = {
+            let start = sel4::PAddr::new(client_req.buf().encoded_addr())
+                - self.client_client_dma_region_paddr;
+            let len = usize::try_from(client_req.buf().len()).unwrap();
+            start..start + len
+        };
+        self.client_region
+            .as_mut_ptr()
+            .index(buf_range)
+            .as_raw_ptr()
+    }
+
+    fn complete(&mut self, mut client_req: BlockIORequest, status: BlockIORequestStatus) {
+        client_req.set_status(status);
+        self.ring_buffers.used_mut().enqueue(client_req).unwrap();
+    }
 }
 
 struct PendingEntry {
@@ -107,79 +259,23 @@ impl Handler for HandlerImpl {
     fn notified(&mut self, channel: Channel) -> Result<(), Self::Error> {
         match channel {
             DEVICE | CLIENT => {
-                let mut notify = false;
-
-                while self.dev.peek_used().is_some() {
-                    let token = self.dev.peek_used().unwrap();
-                    let mut pending_entry = self.pending.remove(&token).unwrap();
-                    let buf_range = {
-                        let start = pending_entry.client_req.buf().encoded_addr()
-                            - self.client_client_dma_region_paddr;
-                        let len = usize::try_from(pending_entry.client_req.buf().len()).unwrap();
-                        start..start + len
-                    };
-                    let mut buf_ptr = self
-                        .client_region
-                        .as_mut_ptr()
-                        .index(buf_range)
-                        .as_raw_ptr();
-                    unsafe {
-                        let pending_entry = &mut *pending_entry;
-                        self.dev
-                            .complete_read_block(
-                                token,
-                                &pending_entry.virtio_req,
-                                buf_ptr.as_mut(),
-                                &mut pending_entry.virtio_resp,
-                            )
-                            .unwrap();
-                    }
-                    let status = match pending_entry.virtio_resp.status() {
-                        RespStatus::OK => BlockIORequestStatus::Ok,
-                        _ => panic!(),
-                    };
-                    let mut completed_req = pending_entry.client_req;
-                    completed_req.set_status(status);
-                    self.ring_buffers.used_mut().enqueue(completed_req).unwrap();
-                    notify = true;
+                // See the virtio-net driver.
+                let polling = self.polling.mode() == PollingMode::Polling;
+                if polling {
+                    self.set_interrupts_suppressed(true);
                 }
 
-                while self.pending.len() < QUEUE_SIZE && !self.ring_buffers.free().is_empty() {
-                    let client_req = self.ring_buffers.free_mut().dequeue().unwrap();
-                    assert_eq!(client_req.ty().unwrap(), BlockIORequestType::Read);
-                    let mut pending_entry = Box::pin(PendingEntry {
-                        client_req,
-                        virtio_req: BlkReq::default(),
-                        virtio_resp: BlkResp::default(),
-                    });
-                    let buf_range = {
-                        let start =
-                            client_req.buf().encoded_addr() - self.client_client_dma_region_paddr;
-                        let len = usize::try_from(client_req.buf().len()).unwrap();
-                        start..start + len
-                    };
-                    let mut buf_ptr = self
-                        .client_region
-                        .as_mut_ptr()
-                        .index(buf_range)
-                        .as_raw_ptr();
-                    let token = unsafe {
-                        let pending_entry = &mut *pending_entry;
-                        self.dev
-                            .read_block_nb(
-                                pending_entry.client_req.block_id(),
-                                &mut pending_entry.virtio_req,
-                                buf_ptr.as_mut(),
-                                &mut pending_entry.virtio_resp,
-                            )
-                            .unwrap()
-                    };
-                    assert!(self.pending.insert(token, pending_entry).is_none());
-                    notify = true;
+                loop {
+                    let num_requests = self.service();
+                    if !self.polling.poll_again(num_requests) {
+                        break;
+                    }
                 }
 
-                if notify {
-                    self.ring_buffers.notify().unwrap();
+                if polling {
+                    self.set_interrupts_suppressed(false);
+                    // Pick up any requests which completed while interrupts were suppressed.
+                    self.service();
                 }
 
                 self.dev.ack_interrupt();
@@ -191,4 +287,27 @@ impl Handler for HandlerImpl {
         }
         Ok(())
     }
+
+    fn protected(
+        &mut self,
+        channel: Channel,
+        msg_info: MessageInfo,
+    ) -> Result<MessageInfo, Self::Error> {
+        Ok(match channel {
+            CLIENT => match msg_info.recv_using_postcard::<Request>() {
+                Ok(req) => match req {
+                    Request::GetPollingMetrics => {
+                        MessageInfo::send_using_postcard(GetPollingMetricsResponse {
+                            metrics: *self.polling.metrics(),
+                        })
+                        .unwrap()
+                    }
+                },
+                Err(_) => MessageInfo::send_unspecified_error(),
+            },
+            _ => {
+                unreachable!()
+            }
+        })
+    }
 }
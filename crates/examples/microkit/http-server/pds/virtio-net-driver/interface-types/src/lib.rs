@@ -2,15 +2,30 @@
 
 use serde::{Deserialize, Serialize};
 
+pub use microkit_http_server_example_adaptive_polling::PollingMetrics;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MacAddress(pub [u8; 6]);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
     GetMacAddress,
+    GetMtu,
+    GetPollingMetrics,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetMacAddressResponse {
     pub mac_address: MacAddress,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetMtuResponse {
+    /// The largest IP packet which can be sent or received, excluding the Ethernet header.
+    pub mtu: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetPollingMetricsResponse {
+    pub metrics: PollingMetrics,
+}
@@ -2,21 +2,16 @@
 #![no_main]
 #![feature(never_type)]
 
-use core::ptr::NonNull;
-
-use virtio_drivers::{
-    device::net::*,
-    transport::{
-        mmio::{MmioTransport, VirtIOHeader},
-        DeviceType, Transport,
-    },
-};
+use virtio_drivers::transport::{mmio::MmioTransport, DeviceType};
 
 use sel4_externally_shared::ExternallySharedRef;
 use sel4_microkit::{memory_region_symbol, protection_domain, var, Channel, Handler, MessageInfo};
 use sel4_microkit_message::MessageInfoExt as _;
 use sel4_shared_ring_buffer::{RingBuffer, RingBuffers};
+use sel4_virtio_mmio::{probe_region, MmioRegion, QEMU_VIRT_SLOT_SIZE};
+use sel4_virtio_net::{VirtioNetConfig, VirtioNetDriver};
 
+use microkit_http_server_example_adaptive_polling::{AdaptivePolling, PollingMode};
 use microkit_http_server_example_virtio_hal_impl::HalImpl;
 use microkit_http_server_example_virtio_net_driver_interface_types::*;
 
@@ -26,6 +21,9 @@ const CLIENT: Channel = Channel::new(1);
 const NET_QUEUE_SIZE: usize = 16;
 const NET_BUFFER_LEN: usize = 2048;
 
+type Driver =
+    VirtioNetDriver<'static, HalImpl, MmioTransport, fn() -> Result<(), !>, NET_QUEUE_SIZE>;
+
 #[protection_domain(
     heap_size = 512 * 1024,
 )]
@@ -33,18 +31,21 @@ fn init() -> HandlerImpl {
     HalImpl::init(
         *var!(virtio_net_driver_dma_size: usize = 0),
         *var!(virtio_net_driver_dma_vaddr: usize = 0),
-        *var!(virtio_net_driver_dma_paddr: usize = 0),
+        sel4::PAddr::new(*var!(virtio_net_driver_dma_paddr: usize = 0)),
     );
 
-    let mut dev = {
-        let header = NonNull::new(
-            (*var!(virtio_net_mmio_vaddr: usize = 0) + *var!(virtio_net_mmio_offset: usize = 0))
-                as *mut VirtIOHeader,
-        )
-        .unwrap();
-        let transport = unsafe { MmioTransport::new(header) }.unwrap();
-        assert_eq!(transport.device_type(), DeviceType::Network);
-        VirtIONet::<HalImpl, MmioTransport, NET_QUEUE_SIZE>::new(transport, NET_BUFFER_LEN).unwrap()
+    let transport = {
+        let region = MmioRegion {
+            vaddr: *var!(virtio_net_mmio_vaddr: usize = 0),
+            paddr: *var!(virtio_net_mmio_paddr: usize = 0),
+            size: *var!(virtio_net_mmio_size: usize = 0),
+            slot_size: QEMU_VIRT_SLOT_SIZE,
+        };
+        let device = unsafe { probe_region(&region) }
+            .find(|device| device.device_type() == DeviceType::Network)
+            .unwrap();
+        let transport = unsafe { device.transport() }.unwrap();
+        transport
     };
 
     let client_region = unsafe {
@@ -73,15 +74,24 @@ fn init() -> HandlerImpl {
         )
     };
 
-    dev.ack_interrupt();
-    DEVICE.irq_ack().unwrap();
-
-    HandlerImpl {
-        dev,
+    let mut driver = Driver::new(
+        transport,
+        &VirtioNetConfig {
+            buffer_len: NET_BUFFER_LEN,
+            ..Default::default()
+        },
         client_region,
         client_client_dma_region_paddr,
         rx_ring_buffers,
         tx_ring_buffers,
+    )
+    .unwrap();
+
+    driver.ack_interrupt(|| DEVICE.irq_ack().unwrap());
+
+    HandlerImpl {
+        driver,
+        polling: AdaptivePolling::default(),
     }
 }
 
@@ -91,11 +101,8 @@ fn notify_client() -> Result<(), !> {
 }
 
 struct HandlerImpl {
-    dev: VirtIONet<HalImpl, MmioTransport, NET_QUEUE_SIZE>,
-    client_region: ExternallySharedRef<'static, [u8]>,
-    client_client_dma_region_paddr: usize,
-    rx_ring_buffers: RingBuffers<'static, fn() -> Result<(), !>>,
-    tx_ring_buffers: RingBuffers<'static, fn() -> Result<(), !>>,
+    driver: Driver,
+    polling: AdaptivePolling,
 }
 
 impl Handler for HandlerImpl {
@@ -104,54 +111,30 @@ impl Handler for HandlerImpl {
     fn notified(&mut self, channel: Channel) -> Result<(), Self::Error> {
         match channel {
             DEVICE | CLIENT => {
-                let mut notify_rx = false;
-
-                while self.dev.can_recv() && !self.rx_ring_buffers.free().is_empty() {
-                    let rx_buf = self.dev.receive().unwrap();
-                    let desc = self.rx_ring_buffers.free_mut().dequeue().unwrap();
-                    let desc_len = usize::try_from(desc.len()).unwrap();
-                    assert!(desc_len >= rx_buf.packet_len());
-                    let buf_range = {
-                        let start = desc.encoded_addr() - self.client_client_dma_region_paddr;
-                        start..start + rx_buf.packet_len()
-                    };
-                    self.client_region
-                        .as_mut_ptr()
-                        .index(buf_range)
-                        .copy_from_slice(rx_buf.packet());
-                    self.dev.recycle_rx_buffer(rx_buf).unwrap();
-                    self.rx_ring_buffers.used_mut().enqueue(desc).unwrap();
-                    notify_rx = true;
-                }
-
-                if notify_rx {
-                    self.rx_ring_buffers.notify().unwrap();
+                // In polling mode, the device is asked not to interrupt while it is being polled.
+                // Interrupts are allowed again before waiting for the next notification, because
+                // nothing else would prompt the driver to poll.
+                let polling = self.polling.mode() == PollingMode::Polling;
+                if polling {
+                    self.driver
+                        .set_interrupts_suppressed(true, HalImpl::dma_phys_to_virt);
                 }
 
-                let mut notify_tx = false;
-
-                while !self.tx_ring_buffers.free().is_empty() && self.dev.can_send() {
-                    let desc = self.tx_ring_buffers.free_mut().dequeue().unwrap();
-                    let buf_range = {
-                        let start = desc.encoded_addr() - self.client_client_dma_region_paddr;
-                        start..start + usize::try_from(desc.len()).unwrap()
-                    };
-                    let mut tx_buf = self.dev.new_tx_buffer(buf_range.len());
-                    self.client_region
-                        .as_ptr()
-                        .index(buf_range)
-                        .copy_into_slice(tx_buf.packet_mut());
-                    self.dev.send(tx_buf).unwrap();
-                    self.tx_ring_buffers.used_mut().enqueue(desc).unwrap();
-                    notify_tx = true;
+                loop {
+                    let num_packets = self.driver.service().unwrap();
+                    if !self.polling.poll_again(num_packets) {
+                        break;
+                    }
                 }
 
-                if notify_tx {
-                    self.tx_ring_buffers.notify().unwrap();
+                if polling {
+                    self.driver
+                        .set_interrupts_suppressed(false, HalImpl::dma_phys_to_virt);
+                    // Pick up any packets which arrived while interrupts were suppressed.
+                    self.driver.service().unwrap();
                 }
 
-                self.dev.ack_interrupt();
-                DEVICE.irq_ack().unwrap();
+                self.driver.ack_interrupt(|| DEVICE.irq_ack().unwrap());
             }
             _ => {
                 unreachable!()
@@ -169,12 +152,22 @@ impl Handler for HandlerImpl {
             CLIENT => match msg_info.recv_using_postcard::<Request>() {
                 Ok(req) => match req {
                     Request::GetMacAddress => {
-                        let mac_address = self.dev.mac_address();
+                        let mac_address = self.driver.mac_address();
                         MessageInfo::send_using_postcard(GetMacAddressResponse {
                             mac_address: MacAddress(mac_address),
                         })
                         .unwrap()
                     }
+                    Request::GetMtu => MessageInfo::send_using_postcard(GetMtuResponse {
+                        mtu: self.driver.mtu(),
+                    })
+                    .unwrap(),
+                    Request::GetPollingMetrics => {
+                        MessageInfo::send_using_postcard(GetPollingMetricsResponse {
+                            metrics: *self.polling.metrics(),
+                        })
+                        .unwrap()
+                    }
                 },
                 Err(_) => MessageInfo::send_unspecified_error(),
             },
@@ -114,7 +114,7 @@ pub fn prepare_bytes_for_send(bytes: &[u8]) -> Result<MessageInfo, Error> {
         .build())
 }
 
-fn recv_data<T: for<'a> Deserialize<'a>>(info: &MessageInfo) -> Result<T, Error> {
+pub fn recv_data<T: for<'a> Deserialize<'a>>(info: &MessageInfo) -> Result<T, Error> {
     sel4::with_borrow_ipc_buffer(|ipc_buffer| recv_data_with_borrow_ipc_buffer(info, ipc_buffer))
 }
 
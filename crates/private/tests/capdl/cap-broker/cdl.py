@@ -0,0 +1,89 @@
+from capdl import ObjectType, Cap
+from capdl_simple_composition import BaseComposition, ElfComponent
+
+# Components' CSpaces are given a fixed size, whose upper half is left free for capabilities
+# created or received at runtime.
+CSPACE_SIZE_BITS = 12
+
+PRODUCER_BADGE = 1
+CONSUMER_BADGE = 2
+
+class CapHoldingComponent(ElfComponent):
+
+    def __init__(self, *args, **kwargs):
+        super().__init__(*args, **kwargs)
+
+        self.primary_thread.tcb['sc_slot'] = self.new_sched_context("primary")
+
+        self.cnode().size_bits = CSPACE_SIZE_BITS
+
+    def own_cnode(self):
+        guard_size = self.composition.kernel_config.word_size() - CSPACE_SIZE_BITS
+        return self.cspace().alloc(self.cnode(), guard_size=guard_size)
+
+    def free_slots(self):
+        return {
+            'start': 1 << (CSPACE_SIZE_BITS - 1),
+            'end': 1 << CSPACE_SIZE_BITS,
+        }
+
+class BrokerComponent(CapHoldingComponent):
+
+    def __init__(self, *args, **kwargs):
+        super().__init__(*args, **kwargs)
+
+        self.endpoint = self.alloc(ObjectType.seL4_EndpointObject, name='endpoint')
+
+        if self.composition.kernel_config.is_mcs():
+            reply_authority = self.cspace().alloc(
+                self.alloc(ObjectType.seL4_RTReplyObject, name='reply'),
+                )
+        else:
+            reply_authority = None
+
+        self._arg = {
+            'endpoint': self.cspace().alloc(self.endpoint, read=True, write=True, grant=True),
+            'reply_authority': reply_authority,
+            'cnode': self.own_cnode(),
+            'free_slots': self.free_slots(),
+            'policy': {
+                'doorbell': {
+                    'depositors': [PRODUCER_BADGE],
+                    'retrievers': [CONSUMER_BADGE],
+                },
+            },
+        }
+
+    def arg_json(self):
+        return self._arg
+
+class ClientComponent(CapHoldingComponent):
+
+    def __init__(self, *args, role, broker, badge, **kwargs):
+        super().__init__(*args, **kwargs)
+
+        if role == 'Producer':
+            untyped = self.alloc(ObjectType.seL4_UntypedObject, name='untyped', size_bits=12)
+            untyped = self.cspace().alloc(untyped)
+        else:
+            untyped = None
+
+        self._arg = {
+            'role': role,
+            'broker': self.cspace().alloc(broker.endpoint, read=True, write=True, grant=True, badge=badge),
+            'cnode': self.own_cnode(),
+            'free_slots': self.free_slots(),
+            'untyped': untyped,
+        }
+
+    def arg_json(self):
+        return self._arg
+
+class CapBrokerComposition(BaseComposition):
+
+    def compose(self):
+        broker = self.component(BrokerComponent, 'broker')
+        self.component(ClientComponent, 'producer', role='Producer', broker=broker, badge=PRODUCER_BADGE)
+        self.component(ClientComponent, 'consumer', role='Consumer', broker=broker, badge=CONSUMER_BADGE)
+
+CapBrokerComposition.from_env().run()
@@ -0,0 +1,188 @@
+//! A broker with which clients deposit capabilities to objects created at runtime under string
+//! keys, and from which other clients retrieve copies of them, subject to a static policy.
+//!
+//! Clients are identified by the badges of their endpoint capabilities. Stored capabilities are
+//! kept in a range of free slots in the broker's own CSpace.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use sel4::{Badge, CNode, CPtr, CPtrBits, MessageInfo, MessageInfoBuilder};
+use sel4_simple_task_config_types::*;
+use sel4_simple_task_rpc::easy::{prepare_data_for_send, recv_data};
+use sel4_simple_task_runtime::{debug_println, main_json};
+
+use tests_capdl_cap_broker_interface_types::{Error, Request, Response};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub endpoint: ConfigCPtr<Endpoint>,
+    pub reply_authority: Option<ConfigCPtr<Unspecified>>,
+    pub cnode: ConfigCPtr<CNode>,
+    pub free_slots: Range<CPtrBits>,
+    pub policy: BTreeMap<String, KeyPolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyPolicy {
+    pub depositors: Vec<ConfigBadge>,
+    pub retrievers: Vec<ConfigBadge>,
+}
+
+impl KeyPolicy {
+    fn permits(&self, request: &Request, badge: Badge) -> bool {
+        let clients = match request {
+            Request::Deposit { .. } | Request::Withdraw { .. } => &self.depositors,
+            Request::Retrieve { .. } => &self.retrievers,
+        };
+        clients.iter().any(|client| client.get() == badge)
+    }
+}
+
+#[main_json]
+fn main(config: Config) -> ! {
+    let endpoint = config.endpoint.get();
+    let mut broker = Broker::new(
+        config.cnode.get(),
+        config.free_slots.clone(),
+        config.policy.clone(),
+    );
+
+    sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| {
+        ipc_buffer.set_recv_slot(&broker.absolute(broker.landing));
+    });
+
+    debug_println!("cap broker ready");
+
+    let mut reply = None;
+    loop {
+        let (info, badge) = match reply.take() {
+            Some(info) => endpoint.reply_recv(info, reply_authority(&config)),
+            None => endpoint.recv(reply_authority(&config)),
+        };
+        reply = Some(broker.handle(&info, badge));
+    }
+}
+
+fn reply_authority(config: &Config) -> sel4::ReplyAuthority {
+    sel4::sel4_cfg_if! {
+        if #[cfg(KERNEL_MCS)] {
+            config.reply_authority.as_ref().unwrap().get().cast()
+        } else {
+            assert!(config.reply_authority.is_none());
+            sel4::ImplicitReplyAuthority
+        }
+    }
+}
+
+struct Broker {
+    cnode: CNode,
+    // Receives the capability accompanying each request. Empty between requests.
+    landing: CPtrBits,
+    free: Vec<CPtrBits>,
+    stored: BTreeMap<String, CPtrBits>,
+    policy: BTreeMap<String, KeyPolicy>,
+}
+
+impl Broker {
+    fn new(cnode: CNode, mut slots: Range<CPtrBits>, policy: BTreeMap<String, KeyPolicy>) -> Self {
+        let landing = slots.next().unwrap();
+        Self {
+            cnode,
+            landing,
+            free: slots.rev().collect(),
+            stored: BTreeMap::new(),
+            policy,
+        }
+    }
+
+    fn absolute(&self, slot: CPtrBits) -> sel4::AbsoluteCPtr {
+        self.cnode.relative(CPtr::from_bits(slot))
+    }
+
+    fn handle(&mut self, info: &MessageInfo, badge: Badge) -> MessageInfo {
+        let received_cap = info.extra_caps() == 1 && info.caps_unwrapped() == 0;
+        let (response, cap) = match recv_data::<Request>(info) {
+            Ok(request) => match self.handle_request(&request, badge, received_cap) {
+                Ok(cap) => (Ok(()), cap),
+                Err(err) => {
+                    debug_println!("denied {request:?} from client {badge}: {err}");
+                    (Err(err), None)
+                }
+            },
+            Err(_) => (Err(Error::InvalidRequest), None),
+        };
+
+        // A capability which was not stored is discarded, so that the next one can be received.
+        self.absolute(self.landing).delete().unwrap();
+
+        let send_info = prepare_data_for_send::<Response>(&response).unwrap();
+        match cap {
+            Some(slot) => {
+                sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| {
+                    ipc_buffer.caps_or_badges_mut()[0] = slot;
+                });
+                MessageInfoBuilder::default()
+                    .length(send_info.length())
+                    .extra_caps(1)
+                    .build()
+            }
+            None => send_info,
+        }
+    }
+
+    // On success, returns the slot of the capability with which to reply, if any.
+    fn handle_request(
+        &mut self,
+        request: &Request,
+        badge: Badge,
+        received_cap: bool,
+    ) -> Result<Option<CPtrBits>, Error> {
+        let key = match request {
+            Request::Deposit { key } | Request::Retrieve { key } | Request::Withdraw { key } => key,
+        };
+        if !self
+            .policy
+            .get(key)
+            .is_some_and(|policy| policy.permits(request, badge))
+        {
+            return Err(Error::NotPermitted);
+        }
+        match request {
+            Request::Deposit { .. } => {
+                if !received_cap {
+                    return Err(Error::MissingCap);
+                }
+                if self.stored.contains_key(key) {
+                    return Err(Error::KeyInUse);
+                }
+                let slot = self.free.pop().ok_or(Error::StorageFull)?;
+                self.absolute(slot)
+                    .r#move(&self.absolute(self.landing))
+                    .unwrap();
+                self.stored.insert(key.clone(), slot);
+                Ok(None)
+            }
+            Request::Retrieve { .. } => {
+                let slot = *self.stored.get(key).ok_or(Error::NoSuchKey)?;
+                Ok(Some(slot))
+            }
+            Request::Withdraw { .. } => {
+                let slot = self.stored.remove(key).ok_or(Error::NoSuchKey)?;
+                self.absolute(slot).revoke().unwrap();
+                self.absolute(slot).delete().unwrap();
+                self.free.push(slot);
+                Ok(None)
+            }
+        }
+    }
+}
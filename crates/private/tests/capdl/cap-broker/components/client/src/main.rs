@@ -0,0 +1,127 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::string::ToString;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use sel4::{CPtr, CPtrBits, MessageInfoBuilder};
+use sel4_simple_task_config_types::*;
+use sel4_simple_task_rpc::easy::{prepare_data_for_send, recv_data};
+use sel4_simple_task_runtime::{debug_println, main_json};
+
+use tests_capdl_cap_broker_interface_types::{Error, Request, Response};
+
+const KEY: &str = "doorbell";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub role: Role,
+    pub broker: ConfigCPtr<Endpoint>,
+    pub cnode: ConfigCPtr<CNode>,
+    pub free_slots: Range<CPtrBits>,
+    pub untyped: Option<ConfigCPtr<Untyped>>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum Role {
+    /// Creates a notification, deposits it with the broker, and waits for the consumer to signal
+    /// it.
+    Producer,
+    /// Retrieves the producer's notification from the broker and signals it.
+    Consumer,
+}
+
+#[main_json]
+fn main(config: Config) {
+    let broker = config.broker.get();
+    let cnode = config.cnode.get();
+    let slot = CPtr::from_bits(config.free_slots.start);
+
+    let call = |request: Request, cap: Option<CPtr>| -> Response {
+        let info = prepare_data_for_send(&request).unwrap();
+        let info = match cap {
+            Some(cap) => {
+                sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| {
+                    ipc_buffer.caps_or_badges_mut()[0] = cap.bits();
+                });
+                MessageInfoBuilder::default()
+                    .length(info.length())
+                    .extra_caps(1)
+                    .build()
+            }
+            None => info,
+        };
+        recv_data(&broker.call(info)).unwrap()
+    };
+
+    match config.role {
+        Role::Producer => {
+            let notification = slot.cast::<sel4::cap_type::Notification>();
+            config
+                .untyped
+                .unwrap()
+                .get()
+                .untyped_retype(
+                    &sel4::ObjectBlueprint::Notification,
+                    &cnode.relative_self(),
+                    slot.bits().try_into().unwrap(),
+                    1,
+                )
+                .unwrap();
+
+            call(
+                Request::Deposit {
+                    key: KEY.to_string(),
+                },
+                Some(slot),
+            )
+            .unwrap();
+
+            assert_eq!(
+                call(
+                    Request::Retrieve {
+                        key: KEY.to_string(),
+                    },
+                    None,
+                ),
+                Err(Error::NotPermitted),
+            );
+
+            notification.wait();
+
+            call(
+                Request::Withdraw {
+                    key: KEY.to_string(),
+                },
+                None,
+            )
+            .unwrap();
+
+            debug_println!("TEST_PASS");
+        }
+        Role::Consumer => {
+            sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| {
+                ipc_buffer.set_recv_slot(&cnode.relative(slot));
+            });
+
+            loop {
+                match call(
+                    Request::Retrieve {
+                        key: KEY.to_string(),
+                    },
+                    None,
+                ) {
+                    Ok(()) => break,
+                    Err(Error::NoSuchKey) => sel4::r#yield(),
+                    Err(err) => panic!("{err}"),
+                }
+            }
+
+            slot.cast::<sel4::cap_type::Notification>().signal();
+        }
+    }
+}
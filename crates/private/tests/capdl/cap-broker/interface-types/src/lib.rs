@@ -0,0 +1,52 @@
+//! The protocol spoken between the capability broker and its clients.
+//!
+//! Requests and responses are encoded with `postcard`. A capability which accompanies a message
+//! is passed as the message's single extra capability.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Request {
+    /// Stores the accompanying capability under `key`.
+    Deposit { key: String },
+    /// Returns a copy of the capability stored under `key`.
+    Retrieve { key: String },
+    /// Deletes the capability stored under `key`, revoking any copies handed out by
+    /// [`Request::Retrieve`].
+    Withdraw { key: String },
+}
+
+/// A successful response to [`Request::Retrieve`] is accompanied by a capability.
+pub type Response = Result<(), Error>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Error {
+    InvalidRequest,
+    /// The policy does not permit this client to perform this request on this key.
+    NotPermitted,
+    NoSuchKey,
+    KeyInUse,
+    /// A [`Request::Deposit`] was not accompanied by a capability.
+    MissingCap,
+    StorageFull,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidRequest => write!(f, "invalid request"),
+            Self::NotPermitted => write!(f, "not permitted"),
+            Self::NoSuchKey => write!(f, "no such key"),
+            Self::KeyInUse => write!(f, "key in use"),
+            Self::MissingCap => write!(f, "missing capability"),
+            Self::StorageFull => write!(f, "storage full"),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use sel4_device_tree::{Device, DeviceTree, Interrupt, Reg};
+use sel4_driver_registry::{Driver, DriverRegistry, Platform, Probe, ProbeError};
+use sel4_root_task::{debug_println, root_task};
+
+#[root_task(heap_size = 64 * 1024)]
+fn main(bootinfo: &sel4::BootInfo) -> ! {
+    let fdt = bootinfo
+        .extra()
+        .find(|extra| extra.id == sel4::BootInfoExtraId::Fdt)
+        .expect("no device tree passed by the loader");
+    let dt = DeviceTree::new(fdt.content()).unwrap();
+
+    let mut registry = DriverRegistry::new();
+    registry
+        .register(Driver {
+            name: "serial",
+            compatible: &["arm,pl011", "ns16550a"],
+            probe: probe_any,
+        })
+        .register(Driver {
+            name: "virtio-mmio",
+            compatible: &["virtio,mmio"],
+            probe: probe_any,
+        });
+
+    let mut platform = RootTaskPlatform {
+        bootinfo,
+        empty_slots: bootinfo.empty(),
+    };
+
+    let results = registry.probe_all(&dt, &mut platform);
+
+    debug_println!("Bound devices:");
+    for bound in &results.bound {
+        let device = dt.device(bound.device);
+        debug_println!(
+            "{} ({}): {} interrupts",
+            device.path(),
+            bound.driver,
+            bound.instance.irqs.len(),
+        );
+        for mmio in &bound.instance.mmio {
+            debug_println!(
+                "    untyped {:#x} + {:#x}",
+                mmio.untyped.bits(),
+                mmio.offset
+            );
+        }
+        assert_eq!(bound.instance.mmio.len(), device.reg().len());
+        assert_eq!(bound.instance.irqs.len(), device.interrupts().len());
+    }
+
+    debug_println!("Devices which could not be probed:");
+    for failed in &results.failed {
+        debug_println!(
+            "{} ({}): {:?}",
+            dt.device(failed.device).path(),
+            failed.driver,
+            failed.err,
+        );
+    }
+
+    // The QEMU virt machines have a bank of virtio-mmio slots.
+    assert!(results
+        .bound
+        .iter()
+        .any(|bound| bound.driver == "virtio-mmio"));
+
+    // The kernel keeps the memory of the device it uses for debug output, so only that device can
+    // fail to be probed.
+    assert!(results.failed.iter().all(|failed| {
+        failed.driver == "serial"
+            && matches!(
+                failed.err,
+                ProbeError::Probe(PlatformError::NoDeviceUntyped)
+            )
+    }));
+
+    debug_println!("TEST_PASS");
+
+    sel4::BootInfo::init_thread_tcb().tcb_suspend().unwrap();
+    unreachable!()
+}
+
+struct Resources {
+    mmio: Vec<DeviceMemory>,
+    irqs: Vec<sel4::IRQHandler>,
+}
+
+fn probe_any(probe: Probe<'_, '_, RootTaskPlatform<'_>>) -> Result<Resources, PlatformError> {
+    Ok(Resources {
+        mmio: probe.mmio,
+        irqs: probe.irqs,
+    })
+}
+
+// Finds device untypeds and obtains IRQ handlers directly from the initial CSpace.
+struct RootTaskPlatform<'a> {
+    bootinfo: &'a sel4::BootInfo,
+    empty_slots: Range<sel4::InitCSpaceSlot>,
+}
+
+struct DeviceMemory {
+    untyped: sel4::Untyped,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum PlatformError {
+    NoDeviceUntyped,
+    UnsupportedInterrupt,
+    OutOfSlots,
+    Kernel(sel4::Error),
+}
+
+impl Platform for RootTaskPlatform<'_> {
+    type Mmio = DeviceMemory;
+    type Irq = sel4::IRQHandler;
+    type Error = PlatformError;
+
+    fn map_mmio(&mut self, _device: &Device, reg: &Reg) -> Result<Self::Mmio, Self::Error> {
+        let start = sel4::PAddr::new(usize::try_from(reg.addr).unwrap());
+        let end = start + usize::try_from(reg.size).unwrap();
+        // Device untypeds come first in the untyped list.
+        let (i, ut) = self
+            .bootinfo
+            .device_untyped_list()
+            .iter()
+            .enumerate()
+            .find(|(_, ut)| ut.paddr() <= start && end <= ut.paddr() + (1 << ut.size_bits()))
+            .ok_or(PlatformError::NoDeviceUntyped)?;
+        Ok(DeviceMemory {
+            untyped: sel4::BootInfo::init_cspace_local_cptr(self.bootinfo.untyped().start + i),
+            offset: start - ut.paddr(),
+        })
+    }
+
+    fn irq(&mut self, _device: &Device, interrupt: &Interrupt) -> Result<Self::Irq, Self::Error> {
+        let irq = match interrupt.cells.as_slice() {
+            // As for the RISC-V PLIC.
+            [irq] => *irq,
+            _ => interrupt
+                .gic_irq()
+                .ok_or(PlatformError::UnsupportedInterrupt)?,
+        };
+        let slot = self.empty_slots.next().ok_or(PlatformError::OutOfSlots)?;
+        let handler = sel4::BootInfo::init_cspace_local_cptr::<sel4::cap_type::IRQHandler>(slot);
+        sel4::BootInfo::irq_control()
+            .irq_control_get(
+                irq.into(),
+                &sel4::BootInfo::init_thread_cnode().relative(handler),
+            )
+            .map_err(PlatformError::Kernel)?;
+        Ok(handler)
+    }
+}
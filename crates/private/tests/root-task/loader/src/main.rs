@@ -21,7 +21,7 @@ fn main(bootinfo: &sel4::BootInfo) -> ! {
     assert_eq!(X.0, 1337);
 
     debug_println!("Gaps in device untypeds:");
-    let mut last_end = 0;
+    let mut last_end = sel4::PAddr::new(0);
     for ut in bootinfo.device_untyped_list() {
         if ut.paddr() > last_end {
             debug_println!("{:x?}", last_end..ut.paddr());
@@ -30,7 +30,7 @@ fn main(bootinfo: &sel4::BootInfo) -> ! {
     }
 
     debug_println!("Gaps in kernel untypeds:");
-    let mut last_end = PLATFORM_INFO.memory[0].start.try_into().unwrap();
+    let mut last_end = sel4::PAddr::new(PLATFORM_INFO.memory[0].start.try_into().unwrap());
     for ut in bootinfo.kernel_untyped_list() {
         if ut.paddr() > last_end {
             debug_println!("{:x?}", last_end..ut.paddr());
@@ -0,0 +1,481 @@
+//! A read-only 9P2000.L client, for access to directories shared by the host, for example through
+//! virtio-9p (see `sel4-virtio-9p`).
+//!
+//! [`Client`] is generic over the [`Transport`] which carries its messages, and implements
+//! [`FileSystem`].
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+
+use sel4_async_fs::{self as fs, DirEntry, FileSystem, Metadata};
+
+mod wire;
+
+use wire::{Decoder, Encoder, Malformed, Qid};
+
+pub const VERSION: &str = "9P2000.L";
+
+/// Carries 9P messages to and from a server.
+pub trait Transport {
+    type Error: fmt::Debug;
+
+    /// Sends `request`, and receives the corresponding response into `response`, returning its
+    /// length.
+    ///
+    /// Implementations may have several transactions in flight at once.
+    async fn transact(&self, request: &[u8], response: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    /// The largest message size to propose to the server, which may choose a smaller one.
+    pub max_message_size: usize,
+    /// The user name with which to attach.
+    pub uname: String,
+    /// The name of the tree to attach to, which many servers ignore.
+    pub aname: String,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            max_message_size: 8192,
+            uname: String::new(),
+            aname: String::new(),
+        }
+    }
+}
+
+pub struct Client<T> {
+    transport: T,
+    msize: usize,
+    root: Fid,
+    next_fid: Cell<Fid>,
+    free_fids: RefCell<Vec<Fid>>,
+    next_tag: Cell<u16>,
+}
+
+type Fid = u32;
+
+/// An open regular file. See [`FileSystem::close`].
+#[derive(Debug)]
+pub struct File {
+    fid: Fid,
+    iounit: usize,
+}
+
+// The subset of an Rgetattr which is used.
+struct Attr {
+    qid: Qid,
+    mode: u32,
+    size: u64,
+    mtime: Option<u64>,
+}
+
+impl<T: Transport> Client<T> {
+    /// Negotiates the protocol version and message size with the server, and attaches to its
+    /// tree.
+    pub async fn new(transport: T, config: &ClientConfig) -> Result<Self, Error<T::Error>> {
+        let mut this = Self {
+            transport,
+            msize: config.max_message_size,
+            root: 0,
+            next_fid: Cell::new(1),
+            free_fids: RefCell::new(vec![]),
+            next_tag: Cell::new(0),
+        };
+        let (msize, version) = this
+            .transact(
+                Encoder::new(wire::TVERSION, wire::NOTAG)
+                    .u32(config.max_message_size.try_into().unwrap())
+                    .str(VERSION),
+                |resp| Ok((resp.u32()?, resp.str()?)),
+            )
+            .await?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion);
+        }
+        this.msize = usize::try_from(msize).unwrap().min(config.max_message_size);
+        // Leave room for at least one byte of data in each Rread and Rreaddir.
+        if this.msize <= wire::IO_HEADER_SIZE {
+            return Err(Error::Malformed);
+        }
+        log::debug!("negotiated msize {}", this.msize);
+        this.transact(
+            Encoder::new(wire::TATTACH, this.tag())
+                .u32(this.root)
+                .u32(wire::NOFID)
+                .str(&config.uname)
+                .str(&config.aname)
+                .u32(wire::NOFID),
+            |resp| resp.qid(),
+        )
+        .await?;
+        Ok(this)
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// The negotiated maximum message size.
+    pub fn msize(&self) -> usize {
+        self.msize
+    }
+
+    fn tag(&self) -> u16 {
+        let tag = self.next_tag.get();
+        self.next_tag.set(match tag.wrapping_add(1) {
+            wire::NOTAG => 0,
+            next => next,
+        });
+        tag
+    }
+
+    fn alloc_fid(&self) -> Fid {
+        self.free_fids.borrow_mut().pop().unwrap_or_else(|| {
+            let fid = self.next_fid.get();
+            self.next_fid.set(fid + 1);
+            fid
+        })
+    }
+
+    fn free_fid(&self, fid: Fid) {
+        self.free_fids.borrow_mut().push(fid)
+    }
+
+    async fn transact<R>(
+        &self,
+        req: &mut Encoder,
+        f: impl FnOnce(&mut Decoder) -> Result<R, Malformed>,
+    ) -> Result<R, Error<T::Error>> {
+        let tag = req.tag();
+        let ty = req.ty();
+        let req = req.finish();
+        let mut resp = vec![0; self.msize];
+        let n = self
+            .transport
+            .transact(&req, &mut resp)
+            .await
+            .map_err(Error::Transport)?;
+        let mut resp = Decoder::new(&resp[..n]);
+        let size = usize::try_from(resp.u32()?).unwrap();
+        if size != n {
+            return Err(Error::Malformed);
+        }
+        let resp_ty = resp.u8()?;
+        if resp.u16()? != tag {
+            return Err(Error::Malformed);
+        }
+        if resp_ty == wire::RLERROR {
+            return Err(Error::Remote(resp.u32()?));
+        }
+        if resp_ty != ty + 1 {
+            return Err(Error::Malformed);
+        }
+        let r = f(&mut resp)?;
+        if !resp.is_empty() {
+            return Err(Error::Malformed);
+        }
+        Ok(r)
+    }
+
+    // Walks from the root to `path`, returning a new fid for it, along with its qid.
+    async fn walk(&self, path: &str) -> Result<(Fid, Qid), Error<T::Error>> {
+        let names = fs::components(path).collect::<Vec<_>>();
+        let fid = self.alloc_fid();
+        let mut qid = None;
+        let mut from = self.root;
+        // A walk with no names clones the fid.
+        let chunks = if names.is_empty() {
+            vec![&[][..]]
+        } else {
+            names.chunks(wire::MAX_WALK_ELEMENTS).collect()
+        };
+        for chunk in chunks {
+            let mut req = Encoder::new(wire::TWALK, self.tag());
+            req.u32(from).u32(fid).u16(chunk.len().try_into().unwrap());
+            for name in chunk {
+                req.str(name);
+            }
+            let result = self
+                .transact(&mut req, |resp| {
+                    (0..resp.u16()?)
+                        .map(|_| resp.qid())
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .await;
+            let qids = match result {
+                Ok(qids) if qids.len() == chunk.len() => qids,
+                // A partial walk does not create the new fid.
+                Ok(_) if from == fid => {
+                    self.clunk(fid).await?;
+                    return Err(Error::NotFound);
+                }
+                Ok(_) => {
+                    self.free_fid(fid);
+                    return Err(Error::NotFound);
+                }
+                Err(err) => {
+                    if from == fid {
+                        self.clunk(fid).await?;
+                    } else {
+                        self.free_fid(fid);
+                    }
+                    return Err(match err {
+                        Error::Remote(wire::ENOENT) => Error::NotFound,
+                        err => err,
+                    });
+                }
+            };
+            qid = qids.last().copied().or(qid);
+            from = fid;
+        }
+        let qid = match qid {
+            Some(qid) => qid,
+            None => match self.getattr(fid, 0).await {
+                Ok(attr) => attr.qid,
+                Err(err) => {
+                    self.clunk(fid).await?;
+                    return Err(err);
+                }
+            },
+        };
+        Ok((fid, qid))
+    }
+
+    async fn clunk(&self, fid: Fid) -> Result<(), Error<T::Error>> {
+        let result = self
+            .transact(Encoder::new(wire::TCLUNK, self.tag()).u32(fid), |_| Ok(()))
+            .await;
+        // The fid is released even if the server reports an error.
+        self.free_fid(fid);
+        result
+    }
+
+    async fn lopen(&self, fid: Fid, flags: u32) -> Result<usize, Error<T::Error>> {
+        let (_qid, iounit) = self
+            .transact(
+                Encoder::new(wire::TLOPEN, self.tag()).u32(fid).u32(flags),
+                |resp| Ok((resp.qid()?, resp.u32()?)),
+            )
+            .await?;
+        Ok(iounit.try_into().unwrap())
+    }
+
+    async fn getattr(&self, fid: Fid, mask: u64) -> Result<Attr, Error<T::Error>> {
+        self.transact(
+            Encoder::new(wire::TGETATTR, self.tag()).u32(fid).u64(mask),
+            |resp| {
+                let valid = resp.u64()?;
+                let qid = resp.qid()?;
+                let mode = resp.u32()?;
+                let _uid = resp.u32()?;
+                let _gid = resp.u32()?;
+                let _nlink = resp.u64()?;
+                let _rdev = resp.u64()?;
+                let size = resp.u64()?;
+                // blksize, blocks, atime
+                resp.bytes(8 * 4)?;
+                let mtime_sec = resp.u64()?;
+                // mtime_nsec, ctime, btime, gen, data_version
+                resp.bytes(8 * 7)?;
+                Ok(Attr {
+                    qid,
+                    mode,
+                    size,
+                    mtime: (valid & wire::GETATTR_MTIME != 0).then_some(mtime_sec),
+                })
+            },
+        )
+        .await
+    }
+
+    // The largest count which fits in a single Rread or Rreaddir. `Client::new` ensures that this
+    // is nonzero.
+    fn max_io_count(&self, iounit: usize) -> usize {
+        let max = self.msize - wire::IO_HEADER_SIZE;
+        if iounit == 0 {
+            max
+        } else {
+            iounit.min(max)
+        }
+    }
+
+    async fn read_dir_fid(&self, fid: Fid) -> Result<Vec<DirEntry>, Error<T::Error>> {
+        let iounit = self
+            .lopen(fid, wire::DOTL_RDONLY | wire::DOTL_DIRECTORY)
+            .await?;
+        let count = self.max_io_count(iounit);
+        let mut entries = vec![];
+        let mut offset = 0;
+        loop {
+            let batch = self
+                .transact(
+                    Encoder::new(wire::TREADDIR, self.tag())
+                        .u32(fid)
+                        .u64(offset)
+                        .u32(count.try_into().unwrap()),
+                    |resp| {
+                        let n = resp.u32()?.try_into().unwrap();
+                        let mut data = Decoder::new(resp.bytes(n)?);
+                        let mut batch = vec![];
+                        while !data.is_empty() {
+                            let _qid = data.qid()?;
+                            let offset = data.u64()?;
+                            let ty = data.u8()?;
+                            let name = data.str()?;
+                            batch.push((offset, ty, name));
+                        }
+                        Ok(batch)
+                    },
+                )
+                .await?;
+            let Some((last_offset, _, _)) = batch.last() else {
+                break;
+            };
+            offset = *last_offset;
+            entries.extend(
+                batch
+                    .into_iter()
+                    .filter(|(_, _, name)| name != "." && name != "..")
+                    .map(|(_, ty, name)| DirEntry {
+                        name,
+                        ty: entry_type_from_dirent_type(ty),
+                    }),
+            );
+        }
+        Ok(entries)
+    }
+}
+
+impl<T: Transport> FileSystem for Client<T> {
+    type Error = Error<T::Error>;
+
+    type File = File;
+
+    async fn open(&self, path: &str) -> Result<File, Self::Error> {
+        let (fid, qid) = self.walk(path).await?;
+        let result = if qid.ty & wire::QID_TYPE_DIR != 0 {
+            Err(Error::IsADirectory)
+        } else {
+            self.lopen(fid, wire::DOTL_RDONLY).await
+        };
+        match result {
+            Ok(iounit) => Ok(File { fid, iounit }),
+            Err(err) => {
+                self.clunk(fid).await?;
+                Err(err)
+            }
+        }
+    }
+
+    async fn read(&self, file: &File, offset: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let count = buf.len().min(self.max_io_count(file.iounit));
+        self.transact(
+            Encoder::new(wire::TREAD, self.tag())
+                .u32(file.fid)
+                .u64(offset.try_into().unwrap())
+                .u32(count.try_into().unwrap()),
+            |resp| {
+                let n = usize::try_from(resp.u32()?).unwrap();
+                if n > count {
+                    return Err(Malformed);
+                }
+                buf[..n].copy_from_slice(resp.bytes(n)?);
+                Ok(n)
+            },
+        )
+        .await
+    }
+
+    async fn close(&self, file: File) -> Result<(), Self::Error> {
+        self.clunk(file.fid).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<Metadata, Self::Error> {
+        let (fid, _qid) = self.walk(path).await?;
+        let result = self
+            .getattr(
+                fid,
+                wire::GETATTR_MODE | wire::GETATTR_SIZE | wire::GETATTR_MTIME,
+            )
+            .await;
+        self.clunk(fid).await?;
+        let attr = result?;
+        Ok(Metadata {
+            ty: entry_type_from_mode(attr.mode),
+            size: attr.size.try_into().unwrap(),
+            modified: attr.mtime,
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, Self::Error> {
+        let (fid, qid) = self.walk(path).await?;
+        let result = if qid.ty & wire::QID_TYPE_DIR == 0 {
+            Err(Error::NotADirectory)
+        } else {
+            self.read_dir_fid(fid).await
+        };
+        self.clunk(fid).await?;
+        result
+    }
+}
+
+fn entry_type_from_mode(mode: u32) -> fs::EntryType {
+    match mode & wire::S_IFMT {
+        wire::S_IFREG => fs::EntryType::RegularFile,
+        wire::S_IFDIR => fs::EntryType::Directory,
+        wire::S_IFLNK => fs::EntryType::SymbolicLink,
+        _ => fs::EntryType::Other,
+    }
+}
+
+fn entry_type_from_dirent_type(ty: u8) -> fs::EntryType {
+    match ty {
+        wire::DT_REG => fs::EntryType::RegularFile,
+        wire::DT_DIR => fs::EntryType::Directory,
+        wire::DT_LNK => fs::EntryType::SymbolicLink,
+        _ => fs::EntryType::Other,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    Transport(E),
+    /// The server returned an `Rlerror` with this Linux errno.
+    Remote(u32),
+    Malformed,
+    UnsupportedVersion,
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+}
+
+impl<E> From<Malformed> for Error<E> {
+    fn from(_: Malformed) -> Self {
+        Self::Malformed
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Transport(err) => write!(f, "transport error: {err:?}"),
+            Self::Remote(errno) => write!(f, "server error: errno {errno}"),
+            Self::Malformed => write!(f, "malformed response"),
+            Self::UnsupportedVersion => write!(f, "server does not support {VERSION}"),
+            Self::NotFound => write!(f, "not found"),
+            Self::NotADirectory => write!(f, "not a directory"),
+            Self::IsADirectory => write!(f, "is a directory"),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Encoding and decoding of 9P2000.L messages.
+//!
+//! Each message is `size[4] type[1] tag[2]` followed by type-specific fields, all little-endian.
+//! Strings are `len[2]` followed by UTF-8 bytes.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// Header plus the fixed fields of Rread and Rreaddir.
+pub(crate) const IO_HEADER_SIZE: usize = 24;
+
+pub(crate) const NOTAG: u16 = !0;
+pub(crate) const NOFID: u32 = !0;
+
+pub(crate) const RLERROR: u8 = 7;
+pub(crate) const TLOPEN: u8 = 12;
+pub(crate) const TGETATTR: u8 = 24;
+pub(crate) const TREADDIR: u8 = 40;
+pub(crate) const TVERSION: u8 = 100;
+pub(crate) const TATTACH: u8 = 104;
+pub(crate) const TWALK: u8 = 110;
+pub(crate) const TREAD: u8 = 116;
+pub(crate) const TCLUNK: u8 = 120;
+
+pub(crate) const MAX_WALK_ELEMENTS: usize = 16;
+
+pub(crate) const GETATTR_MODE: u64 = 0x0000_0001;
+pub(crate) const GETATTR_MTIME: u64 = 0x0000_0040;
+pub(crate) const GETATTR_SIZE: u64 = 0x0000_0200;
+
+pub(crate) const DOTL_RDONLY: u32 = 0o0;
+pub(crate) const DOTL_DIRECTORY: u32 = 0o200000;
+
+pub(crate) const QID_TYPE_DIR: u8 = 0x80;
+
+pub(crate) const S_IFMT: u32 = 0o170000;
+pub(crate) const S_IFDIR: u32 = 0o040000;
+pub(crate) const S_IFREG: u32 = 0o100000;
+pub(crate) const S_IFLNK: u32 = 0o120000;
+
+pub(crate) const ENOENT: u32 = 2;
+
+pub(crate) const DT_DIR: u8 = 4;
+pub(crate) const DT_REG: u8 = 8;
+pub(crate) const DT_LNK: u8 = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Qid {
+    pub(crate) ty: u8,
+    pub(crate) version: u32,
+    pub(crate) path: u64,
+}
+
+pub(crate) struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn new(ty: u8, tag: u16) -> Self {
+        let mut this = Self { buf: Vec::new() };
+        this.u32(0).u8(ty).u16(tag);
+        this
+    }
+
+    pub(crate) fn ty(&self) -> u8 {
+        self.buf[4]
+    }
+
+    pub(crate) fn tag(&self) -> u16 {
+        u16::from_le_bytes(self.buf[5..7].try_into().unwrap())
+    }
+
+    pub(crate) fn u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub(crate) fn u16(&mut self, v: u16) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) -> &mut Self {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn str(&mut self, v: &str) -> &mut Self {
+        self.u16(v.len().try_into().unwrap());
+        self.buf.extend_from_slice(v.as_bytes());
+        self
+    }
+
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        let size = u32::try_from(self.buf.len()).unwrap();
+        self.buf[..4].copy_from_slice(&size.to_le_bytes());
+        core::mem::take(&mut self.buf)
+    }
+}
+
+/// Returned when a message is truncated or otherwise malformed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Malformed;
+
+pub(crate) struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub(crate) fn bytes(&mut self, n: usize) -> Result<&'a [u8], Malformed> {
+        if n > self.buf.len() {
+            return Err(Malformed);
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Malformed> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Malformed> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Malformed> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Malformed> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn str(&mut self) -> Result<String, Malformed> {
+        let len = self.u16()?.into();
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| Malformed)
+    }
+
+    pub(crate) fn qid(&mut self) -> Result<Qid, Malformed> {
+        Ok(Qid {
+            ty: self.u8()?,
+            version: self.u32()?,
+            path: self.u64()?,
+        })
+    }
+}
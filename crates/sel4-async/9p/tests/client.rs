@@ -0,0 +1,333 @@
+#![feature(async_fn_in_trait)]
+
+use core::cell::RefCell;
+use std::collections::BTreeMap;
+
+use sel4_async_9p::{Client, ClientConfig, Error, Transport};
+use sel4_async_fs::{DirEntry, EntryType, FileSystem};
+use sel4_async_test_utils::block_on;
+
+const SERVER_MSIZE: usize = 256;
+
+const ENOENT: u32 = 2;
+
+// A minimal in-memory 9P2000.L server.
+struct MemoryServer {
+    files: BTreeMap<String, Vec<u8>>,
+    dirs: Vec<String>,
+    fids: RefCell<BTreeMap<u32, String>>,
+}
+
+impl MemoryServer {
+    fn new(files: &[(&str, &[u8])]) -> Self {
+        let mut dirs = vec![String::new()];
+        for (path, _) in files {
+            let mut components = path.split('/').collect::<Vec<_>>();
+            components.pop();
+            for i in 1..=components.len() {
+                let dir = components[..i].join("/");
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        Self {
+            files: files
+                .iter()
+                .map(|(path, data)| (path.to_string(), data.to_vec()))
+                .collect(),
+            dirs,
+            fids: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.dirs.iter().any(|dir| dir == path)
+    }
+
+    fn qid(&self, path: &str) -> Vec<u8> {
+        let mut qid = vec![if self.is_dir(path) { 0x80 } else { 0 }];
+        qid.extend(0u32.to_le_bytes());
+        qid.extend((path.len() as u64).to_le_bytes());
+        qid
+    }
+
+    fn children(&self, dir: &str) -> Vec<(String, bool)> {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        let mut children = vec![(".".to_owned(), true), ("..".to_owned(), true)];
+        for (path, is_dir) in self
+            .files
+            .keys()
+            .map(|path| (path, false))
+            .chain(self.dirs.iter().map(|path| (path, true)))
+        {
+            if let Some(name) = path.strip_prefix(&prefix) {
+                if !name.is_empty() && !name.contains('/') {
+                    children.push((name.to_owned(), is_dir));
+                }
+            }
+        }
+        children
+    }
+
+    fn handle(&self, req: &[u8]) -> Vec<u8> {
+        let mut req = Reader(&req[4..]);
+        let ty = req.u8();
+        let tag = req.u16();
+        let mut resp = vec![];
+        let resp_ty = match self.handle_body(ty, &mut req, &mut resp) {
+            Ok(()) => ty + 1,
+            Err(errno) => {
+                resp = errno.to_le_bytes().to_vec();
+                7
+            }
+        };
+        let mut msg = ((4 + 1 + 2 + resp.len()) as u32).to_le_bytes().to_vec();
+        msg.push(resp_ty);
+        msg.extend(tag.to_le_bytes());
+        msg.extend(resp);
+        assert!(msg.len() <= SERVER_MSIZE);
+        msg
+    }
+
+    fn handle_body(&self, ty: u8, req: &mut Reader, resp: &mut Vec<u8>) -> Result<(), u32> {
+        let mut fids = self.fids.borrow_mut();
+        match ty {
+            // Tversion
+            100 => {
+                let msize = (req.u32() as usize).min(SERVER_MSIZE);
+                let version = req.str();
+                resp.extend((msize as u32).to_le_bytes());
+                put_str(resp, &version);
+            }
+            // Tattach
+            104 => {
+                let fid = req.u32();
+                fids.insert(fid, String::new());
+                resp.extend(self.qid(""));
+            }
+            // Twalk
+            110 => {
+                let path = fids[&req.u32()].clone();
+                let newfid = req.u32();
+                let names = (0..req.u16()).map(|_| req.str()).collect::<Vec<_>>();
+                assert!(names.len() <= 16);
+                let mut path = path;
+                let mut qids = vec![];
+                for name in &names {
+                    let candidate = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{path}/{name}")
+                    };
+                    if !self.is_dir(&candidate) && !self.files.contains_key(&candidate) {
+                        break;
+                    }
+                    qids.push(self.qid(&candidate));
+                    path = candidate;
+                }
+                if qids.is_empty() && !names.is_empty() {
+                    return Err(ENOENT);
+                }
+                if qids.len() == names.len() {
+                    fids.insert(newfid, path);
+                }
+                resp.extend((qids.len() as u16).to_le_bytes());
+                resp.extend(qids.concat());
+            }
+            // Tlopen
+            12 => {
+                let path = &fids[&req.u32()];
+                resp.extend(self.qid(path));
+                resp.extend(0u32.to_le_bytes());
+            }
+            // Tread
+            116 => {
+                let data = &self.files[&fids[&req.u32()]];
+                let offset = (req.u64() as usize).min(data.len());
+                let count = (req.u32() as usize).min(data.len() - offset);
+                resp.extend((count as u32).to_le_bytes());
+                resp.extend(&data[offset..][..count]);
+            }
+            // Treaddir
+            40 => {
+                let path = &fids[&req.u32()];
+                let offset = req.u64() as usize;
+                let count = req.u32() as usize;
+                let mut data = vec![];
+                for (i, (name, is_dir)) in self.children(path).iter().enumerate().skip(offset) {
+                    let mut entry = self.qid(name);
+                    entry.extend(((i + 1) as u64).to_le_bytes());
+                    entry.push(if *is_dir { 4 } else { 8 });
+                    put_str(&mut entry, name);
+                    if data.len() + entry.len() > count {
+                        break;
+                    }
+                    data.extend(entry);
+                }
+                resp.extend((data.len() as u32).to_le_bytes());
+                resp.extend(data);
+            }
+            // Tgetattr
+            24 => {
+                let path = &fids[&req.u32()];
+                let (mode, size) = match self.files.get(path) {
+                    Some(data) => (0o100644u32, data.len() as u64),
+                    None => (0o040755, 0),
+                };
+                resp.extend(0x7ffu64.to_le_bytes());
+                resp.extend(self.qid(path));
+                resp.extend(mode.to_le_bytes());
+                resp.extend([0; 8]);
+                resp.extend(1u64.to_le_bytes());
+                resp.extend(0u64.to_le_bytes());
+                resp.extend(size.to_le_bytes());
+                resp.extend([0; 8 * 12]);
+            }
+            // Tclunk
+            120 => {
+                fids.remove(&req.u32()).unwrap();
+            }
+            _ => panic!("unexpected message type {ty}"),
+        }
+        Ok(())
+    }
+}
+
+impl Transport for &MemoryServer {
+    type Error = ();
+
+    async fn transact(&self, request: &[u8], response: &mut [u8]) -> Result<usize, ()> {
+        let msg = self.handle(request);
+        response[..msg.len()].copy_from_slice(&msg);
+        Ok(msg.len())
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        bytes.try_into().unwrap()
+    }
+
+    fn u8(&mut self) -> u8 {
+        u8::from_le_bytes(self.take())
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+
+    fn str(&mut self) -> String {
+        let len = self.u16().into();
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend((s.len() as u16).to_le_bytes());
+    buf.extend(s.as_bytes());
+}
+
+#[test]
+fn read_files_and_directories() {
+    let big = (0..300).map(|i| i as u8).collect::<Vec<_>>();
+    let deep = (0..20).map(|_| "d").collect::<Vec<_>>().join("/") + "/deep.txt";
+    let server = MemoryServer::new(&[
+        ("index.html", b"<html></html>"),
+        ("static/big.bin", &big),
+        ("static/a.txt", b"a"),
+        ("static/b.txt", b"b"),
+        ("static/c.txt", b"c"),
+        (&deep, b"deep"),
+    ]);
+    let client = block_on(Client::new(
+        &server,
+        &ClientConfig {
+            max_message_size: 4096,
+            ..Default::default()
+        },
+    ))
+    .unwrap();
+    assert_eq!(client.msize(), SERVER_MSIZE);
+
+    let read_all = |path: &str| {
+        block_on(async {
+            let file = client.open(path).await?;
+            let mut data = vec![];
+            let mut buf = [0; 1000];
+            loop {
+                let n = client.read(&file, data.len(), &mut buf).await?;
+                if n == 0 {
+                    break;
+                }
+                data.extend_from_slice(&buf[..n]);
+            }
+            client.close(file).await?;
+            Ok::<_, Error<()>>(data)
+        })
+    };
+
+    assert_eq!(read_all("/index.html").unwrap(), b"<html></html>");
+    assert_eq!(read_all("static/big.bin").unwrap(), big);
+    assert_eq!(read_all(&deep).unwrap(), b"deep");
+    assert_eq!(read_all("missing").unwrap_err(), Error::NotFound);
+    assert_eq!(read_all("static/missing").unwrap_err(), Error::NotFound);
+    assert_eq!(read_all("static").unwrap_err(), Error::IsADirectory);
+
+    let stat = block_on(client.stat("static/big.bin")).unwrap();
+    assert_eq!(
+        (stat.ty, stat.size, stat.modified),
+        (EntryType::RegularFile, 300, Some(0))
+    );
+    assert_eq!(block_on(client.stat("/")).unwrap().ty, EntryType::Directory);
+
+    let mut entries = block_on(client.read_dir("static/")).unwrap();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        entries,
+        ["a.txt", "b.txt", "big.bin", "c.txt"]
+            .map(|name| DirEntry {
+                name: name.to_owned(),
+                ty: EntryType::RegularFile,
+            })
+            .to_vec()
+    );
+    assert_eq!(
+        block_on(client.read_dir("index.html")).unwrap_err(),
+        Error::NotADirectory
+    );
+
+    // Only the root fid remains.
+    assert_eq!(server.fids.borrow().len(), 1);
+}
+
+#[test]
+fn rejects_msize_without_room_for_data() {
+    let server = MemoryServer::new(&[]);
+    let r = block_on(Client::new(
+        &server,
+        &ClientConfig {
+            max_message_size: 24,
+            ..Default::default()
+        },
+    ));
+    assert_eq!(r.err(), Some(Error::Malformed));
+}
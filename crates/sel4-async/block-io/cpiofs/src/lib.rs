@@ -1,3 +1,8 @@
+//! A read-only filesystem over a `newc` (or `crc`) format CPIO archive.
+//!
+//! [`Index::create`] scans the archive once, keeping the header of each entry in memory, so that
+//! looking up paths, listing directories, and reading metadata do not touch the underlying storage.
+
 #![no_std]
 #![feature(async_fn_in_trait)]
 #![feature(int_roundings)]
@@ -5,15 +10,19 @@
 
 extern crate alloc;
 
-use alloc::collections::BTreeMap;
-use alloc::string::String;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 use core::mem;
 
+use hashbrown::HashMap;
 use hex::FromHex;
 use zerocopy::{AsBytes, FromBytes};
 
-use sel4_async_block_io::BytesIO;
+use sel4_async_block_io::{BytesIO, LendingBytesIO};
+use sel4_async_fs::{self as fs, DirEntry, FileSystem, Metadata};
 
 const CPIO_ALIGN: usize = 4;
 
@@ -110,6 +119,16 @@ impl Entry {
         }
     }
 
+    /// The file type and permission bits, as in `st_mode`.
+    pub fn mode(&self) -> u32 {
+        self.header().c_mode.get()
+    }
+
+    /// The time of last modification, in seconds since the Unix epoch.
+    pub fn mtime(&self) -> u32 {
+        self.header().c_mtime.get()
+    }
+
     pub fn location(&self) -> &EntryLocation {
         &self.location
     }
@@ -147,14 +166,27 @@ pub enum EntryType {
     SymbolicLink,
 }
 
+impl From<EntryType> for fs::EntryType {
+    fn from(ty: EntryType) -> Self {
+        match ty {
+            EntryType::RegularFile => Self::RegularFile,
+            EntryType::Directory => Self::Directory,
+            EntryType::SymbolicLink => Self::SymbolicLink,
+        }
+    }
+}
+
 pub struct Index<T> {
-    entries: BTreeMap<String, EntryLocation>,
+    entries: HashMap<String, Entry>,
+    // The names of the children of each directory, keyed by the path of the directory.
+    children: HashMap<String, Vec<String>>,
     io: T,
 }
 
 impl<T: BytesIO> Index<T> {
     pub async fn create(io: T) -> Self {
-        let mut entries = BTreeMap::new();
+        let mut entries = HashMap::new();
+        let mut children = HashMap::<_, Vec<_>>::new();
         let mut location = EntryLocation::first();
         loop {
             let entry = location.read_entry(&io).await;
@@ -163,19 +195,40 @@ impl<T: BytesIO> Index<T> {
                 break;
             }
             location = entry.next_entry_location();
-            entries.insert(path, entry.location);
+            let (parent, name) = path.rsplit_once('/').unwrap_or(("", &path));
+            if name != "." && name != ".." {
+                children
+                    .entry(parent.to_string())
+                    .or_default()
+                    .push(name.to_string());
+            }
+            entries.insert(path, entry);
+        }
+        Self {
+            entries,
+            children,
+            io,
         }
-        Self { entries, io }
     }
 
-    pub fn lookup(&self, path: &str) -> Option<&EntryLocation> {
+    pub fn lookup(&self, path: &str) -> Option<&Entry> {
         self.entries.get(path)
     }
 
-    pub fn entries(&self) -> &BTreeMap<String, EntryLocation> {
+    pub fn entries(&self) -> &HashMap<String, Entry> {
         &self.entries
     }
 
+    /// Returns the names of the entries of the directory at `path`, excluding `.` and `..`, if it
+    /// has any.
+    pub fn children(&self, path: &str) -> &[String] {
+        self.children
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Reads the entry at `location` from the underlying storage, rather than from the index.
     pub async fn read_entry(&self, location: &EntryLocation) -> Entry {
         location.read_entry(&self.io).await
     }
@@ -185,3 +238,101 @@ impl<T: BytesIO> Index<T> {
         self.io.read(offset, buf).await;
     }
 }
+
+impl<T: LendingBytesIO> Index<T> {
+    /// Like [`Index::read_data`], but calls `f` on a prefix of the `len` bytes of data at
+    /// `offset_into_data`, in place. See [`LendingBytesIO::read_with`].
+    pub async fn read_data_with<F: FnOnce(&[u8]) -> R, R>(
+        &self,
+        entry: &Entry,
+        offset_into_data: usize,
+        len: usize,
+        f: F,
+    ) -> R {
+        assert!(offset_into_data + len <= entry.data_size());
+        let offset = entry.data_offset() + offset_into_data;
+        self.io.read_with(offset, len, f).await
+    }
+}
+
+impl<T: BytesIO> FileSystem for Index<T> {
+    type Error = Error;
+
+    type File = Entry;
+
+    async fn open(&self, path: &str) -> Result<Entry, Error> {
+        let entry = *self.lookup(&normalize(path)).ok_or(Error::NotFound)?;
+        match entry.ty() {
+            EntryType::RegularFile => Ok(entry),
+            EntryType::Directory => Err(Error::IsADirectory),
+            EntryType::SymbolicLink => Err(Error::NotARegularFile),
+        }
+    }
+
+    async fn read(&self, file: &Entry, offset: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = buf.len().min(file.data_size().saturating_sub(offset));
+        self.read_data(file, offset, &mut buf[..n]).await;
+        Ok(n)
+    }
+
+    async fn stat(&self, path: &str) -> Result<Metadata, Error> {
+        let path = normalize(path);
+        if path.is_empty() {
+            return Ok(Metadata {
+                ty: fs::EntryType::Directory,
+                size: 0,
+                modified: None,
+            });
+        }
+        let entry = self.lookup(&path).ok_or(Error::NotFound)?;
+        Ok(Metadata {
+            ty: entry.ty().into(),
+            size: entry.data_size(),
+            modified: Some(entry.mtime().into()),
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, Error> {
+        let path = normalize(path);
+        if self.stat(&path).await?.ty != fs::EntryType::Directory {
+            return Err(Error::NotADirectory);
+        }
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{path}/")
+        };
+        let dir_entries = self
+            .children(&path)
+            .iter()
+            .map(|name| DirEntry {
+                name: name.clone(),
+                ty: self.entries[&(prefix.clone() + name)].ty().into(),
+            })
+            .collect();
+        Ok(dir_entries)
+    }
+}
+
+fn normalize(path: &str) -> String {
+    fs::components(path).collect::<Vec<_>>().join("/")
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+    NotARegularFile,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::NotADirectory => write!(f, "not a directory"),
+            Self::IsADirectory => write!(f, "is a directory"),
+            Self::NotARegularFile => write!(f, "not a regular file"),
+        }
+    }
+}
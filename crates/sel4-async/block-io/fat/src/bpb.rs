@@ -0,0 +1,183 @@
+use sel4_async_block_io::BytesIO;
+
+use crate::Error;
+
+const BOOT_SECTOR_SIZE: usize = 512;
+const BOOT_SIGNATURE: u16 = 0xaa55;
+
+const FS_INFO_LEAD_SIGNATURE: u32 = 0x4161_5252;
+const FS_INFO_STRUCT_SIGNATURE: u32 = 0x6141_7272;
+const FS_INFO_FREE_COUNT_OFFSET: usize = 488;
+const FS_INFO_NEXT_FREE_OFFSET: usize = 492;
+
+/// Free counts and hints of this value are unknown.
+pub(crate) const FS_INFO_UNKNOWN: u32 = 0xffff_ffff;
+
+// Set in the extended flags when only the active FAT is in use.
+const EXT_FLAGS_NO_MIRRORING: u16 = 1 << 7;
+
+/// The layout of a volume, as described by its BIOS parameter block.
+#[derive(Clone, Debug)]
+pub(crate) struct Geometry {
+    pub(crate) bytes_per_cluster: usize,
+    fat_offset: usize,
+    fat_size: usize,
+    // The FATs which are kept up to date, the first of which is read from.
+    fats: FatSelection,
+    data_offset: usize,
+    pub(crate) num_clusters: u32,
+    pub(crate) root_cluster: u32,
+    pub(crate) fs_info_offset: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum FatSelection {
+    All(usize),
+    Only(usize),
+}
+
+impl Geometry {
+    pub(crate) async fn read(io: &impl BytesIO) -> Result<Self, Error> {
+        let mut sector = [0; BOOT_SECTOR_SIZE];
+        io.read(0, &mut sector).await;
+
+        let u8_at = |offset: usize| sector[offset];
+        let u16_at = |offset: usize| u16::from_le_bytes(sector[offset..][..2].try_into().unwrap());
+        let u32_at = |offset: usize| u32::from_le_bytes(sector[offset..][..4].try_into().unwrap());
+
+        if u16_at(510) != BOOT_SIGNATURE {
+            return Err(Error::Malformed);
+        }
+
+        let bytes_per_sector = usize::from(u16_at(11));
+        let sectors_per_cluster = usize::from(u8_at(13));
+        let num_reserved_sectors = usize::from(u16_at(14));
+        let num_fats = usize::from(u8_at(16));
+        if ![512, 1024, 2048, 4096].contains(&bytes_per_sector)
+            || !sectors_per_cluster.is_power_of_two()
+            || num_reserved_sectors == 0
+            || num_fats == 0
+        {
+            return Err(Error::Malformed);
+        }
+
+        // As Linux does, identify FAT32 by the layout of the BPB rather than by the number of
+        // clusters, so that small volumes formatted with `mkfs.fat -F 32` are accepted.
+        let root_entry_count = u16_at(17);
+        let fat_size_16 = u16_at(22);
+        let fat_size_32 = u32_at(36);
+        if root_entry_count != 0 || fat_size_16 != 0 || fat_size_32 == 0 {
+            return Err(Error::Unsupported);
+        }
+        // Version 0.0
+        if u16_at(42) != 0 {
+            return Err(Error::Unsupported);
+        }
+
+        let num_sectors = match u16_at(19) {
+            0 => usize::try_from(u32_at(32)).unwrap(),
+            n => usize::from(n),
+        };
+        let fat_size_in_sectors = usize::try_from(fat_size_32).unwrap();
+        let num_metadata_sectors = num_reserved_sectors + num_fats * fat_size_in_sectors;
+        let num_data_sectors = num_sectors
+            .checked_sub(num_metadata_sectors)
+            .ok_or(Error::Malformed)?;
+
+        let fat_size = fat_size_in_sectors * bytes_per_sector;
+        // Clusters 0 and 1 have FAT entries, but no data.
+        let num_clusters = (num_data_sectors / sectors_per_cluster)
+            .min(fat_size / 4 - 2)
+            .min(usize::try_from(MAX_CLUSTER - 1).unwrap());
+
+        let ext_flags = u16_at(40);
+        let fats = if ext_flags & EXT_FLAGS_NO_MIRRORING != 0 {
+            let active = usize::from(ext_flags & 0xf);
+            if active >= num_fats {
+                return Err(Error::Malformed);
+            }
+            FatSelection::Only(active)
+        } else {
+            FatSelection::All(num_fats)
+        };
+
+        let geometry = Self {
+            bytes_per_cluster: sectors_per_cluster * bytes_per_sector,
+            fat_offset: num_reserved_sectors * bytes_per_sector,
+            fat_size,
+            fats,
+            data_offset: num_metadata_sectors * bytes_per_sector,
+            num_clusters: num_clusters.try_into().unwrap(),
+            root_cluster: u32_at(44),
+            fs_info_offset: None,
+        };
+
+        if !geometry.is_valid_cluster(geometry.root_cluster) {
+            return Err(Error::Malformed);
+        }
+
+        let fs_info_sector = usize::from(u16_at(48));
+        let fs_info_offset = if fs_info_sector == 0 || fs_info_sector >= num_reserved_sectors {
+            None
+        } else {
+            let offset = fs_info_sector * bytes_per_sector;
+            let mut fs_info = [0; BOOT_SECTOR_SIZE];
+            io.read(offset, &mut fs_info).await;
+            let u32_at =
+                |offset: usize| u32::from_le_bytes(fs_info[offset..][..4].try_into().unwrap());
+            (u32_at(0) == FS_INFO_LEAD_SIGNATURE && u32_at(484) == FS_INFO_STRUCT_SIGNATURE)
+                .then_some(offset)
+        };
+
+        Ok(Self {
+            fs_info_offset,
+            ..geometry
+        })
+    }
+
+    pub(crate) fn is_valid_cluster(&self, cluster: u32) -> bool {
+        (2..self.num_clusters + 2).contains(&cluster)
+    }
+
+    pub(crate) fn cluster_offset(&self, cluster: u32) -> usize {
+        assert!(self.is_valid_cluster(cluster));
+        self.data_offset + usize::try_from(cluster - 2).unwrap() * self.bytes_per_cluster
+    }
+
+    /// The byte offset of the entry for `cluster` in the FAT which is read from.
+    pub(crate) fn fat_entry_offset(&self, cluster: u32) -> usize {
+        self.fat_entry_offsets(cluster).next().unwrap()
+    }
+
+    /// The byte offsets of the entries for `cluster` in each FAT which is written to.
+    pub(crate) fn fat_entry_offsets(&self, cluster: u32) -> impl Iterator<Item = usize> + '_ {
+        let fats = match self.fats {
+            FatSelection::All(n) => 0..n,
+            FatSelection::Only(i) => i..i + 1,
+        };
+        let offset_into_fat = usize::try_from(cluster).unwrap() * 4;
+        fats.map(move |i| self.fat_offset + i * self.fat_size + offset_into_fat)
+    }
+
+    pub(crate) fn fs_info_free_count_offset(&self) -> Option<usize> {
+        self.fs_info_offset
+            .map(|offset| offset + FS_INFO_FREE_COUNT_OFFSET)
+    }
+
+    pub(crate) fn fs_info_next_free_offset(&self) -> Option<usize> {
+        self.fs_info_offset
+            .map(|offset| offset + FS_INFO_NEXT_FREE_OFFSET)
+    }
+}
+
+/// FAT entries are 28 bits wide. The upper 4 bits are reserved and must be preserved.
+pub(crate) const FAT_ENTRY_MASK: u32 = 0x0fff_ffff;
+
+pub(crate) const FREE: u32 = 0;
+
+const MAX_CLUSTER: u32 = 0x0fff_fff6;
+
+/// Values at least this large mark the end of a chain.
+pub(crate) const END_OF_CHAIN_MIN: u32 = 0x0fff_fff8;
+
+pub(crate) const END_OF_CHAIN: u32 = 0x0fff_ffff;
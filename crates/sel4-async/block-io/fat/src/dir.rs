@@ -0,0 +1,445 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+
+use crate::Error;
+
+pub(crate) const ENTRY_SIZE: usize = 32;
+
+/// Directories may have at most this many entries.
+pub(crate) const MAX_ENTRIES: usize = 1 << 16;
+
+pub(crate) const ATTR_READ_ONLY: u8 = 0x01;
+pub(crate) const ATTR_HIDDEN: u8 = 0x02;
+pub(crate) const ATTR_SYSTEM: u8 = 0x04;
+pub(crate) const ATTR_VOLUME_ID: u8 = 0x08;
+pub(crate) const ATTR_DIRECTORY: u8 = 0x10;
+pub(crate) const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LONG_NAME: u8 = ATTR_READ_ONLY | ATTR_HIDDEN | ATTR_SYSTEM | ATTR_VOLUME_ID;
+const ATTR_LONG_NAME_MASK: u8 = ATTR_LONG_NAME | ATTR_DIRECTORY | ATTR_ARCHIVE;
+
+// The first byte of the name of an entry.
+const END_OF_DIRECTORY: u8 = 0x00;
+pub(crate) const DELETED: u8 = 0xe5;
+// Stands in for a leading 0xe5, which would otherwise mark the entry as deleted.
+const ESCAPED_E5: u8 = 0x05;
+
+// Flags in the reserved byte, used by Windows NT and Linux to record that a name which is
+// otherwise a valid short name is in lower case.
+const NT_LOWERCASE_BASE: u8 = 0x08;
+const NT_LOWERCASE_EXT: u8 = 0x10;
+
+const LAST_LONG_ENTRY: u8 = 0x40;
+const LONG_ENTRY_ORDINAL_MASK: u8 = 0x1f;
+const CHARS_PER_LONG_ENTRY: usize = 13;
+const LONG_NAME_CHAR_OFFSETS: [usize; CHARS_PER_LONG_ENTRY] =
+    [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+const MAX_LONG_NAME_LEN: usize = 255;
+
+// 1980-01-01, the earliest date which can be represented, used in the absence of a clock.
+const EPOCH_DATE: u16 = (1 << 5) | 1;
+
+pub(crate) type ShortName = [u8; 11];
+
+pub(crate) const DOT: ShortName = *b".          ";
+pub(crate) const DOT_DOT: ShortName = *b"..         ";
+
+/// A short directory entry.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct RawEntry(pub(crate) [u8; ENTRY_SIZE]);
+
+impl RawEntry {
+    pub(crate) fn new(short_name: &ShortName, nt_flags: u8, attr: u8, first_cluster: u32) -> Self {
+        let mut entry = Self([0; ENTRY_SIZE]);
+        entry.0[..11].copy_from_slice(short_name);
+        entry.0[11] = attr;
+        entry.0[12] = nt_flags;
+        for offset in [16, 18, 24] {
+            entry.set_u16(offset, EPOCH_DATE);
+        }
+        entry.set_first_cluster(first_cluster);
+        entry
+    }
+
+    fn u16_at(&self, offset: usize) -> u16 {
+        u16::from_le_bytes(self.0[offset..][..2].try_into().unwrap())
+    }
+
+    fn set_u16(&mut self, offset: usize, value: u16) {
+        self.0[offset..][..2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn is_deleted(&self) -> bool {
+        self.0[0] == DELETED
+    }
+
+    pub(crate) fn short_name(&self) -> ShortName {
+        self.0[..11].try_into().unwrap()
+    }
+
+    pub(crate) fn attr(&self) -> u8 {
+        self.0[11]
+    }
+
+    pub(crate) fn set_attr(&mut self, attr: u8) {
+        self.0[11] = attr;
+    }
+
+    pub(crate) fn is_dir(&self) -> bool {
+        self.attr() & ATTR_DIRECTORY != 0
+    }
+
+    pub(crate) fn first_cluster(&self) -> u32 {
+        (u32::from(self.u16_at(20)) << 16) | u32::from(self.u16_at(26))
+    }
+
+    pub(crate) fn set_first_cluster(&mut self, cluster: u32) {
+        self.set_u16(20, (cluster >> 16) as u16);
+        self.set_u16(26, cluster as u16);
+    }
+
+    pub(crate) fn size(&self) -> u32 {
+        u32::from_le_bytes(self.0[28..].try_into().unwrap())
+    }
+
+    pub(crate) fn set_size(&mut self, size: u32) {
+        self.0[28..].copy_from_slice(&size.to_le_bytes());
+    }
+
+    /// The time of last modification, in seconds since the Unix epoch. FAT timestamps have no time
+    /// zone, and are taken to be in UTC.
+    pub(crate) fn modified(&self) -> Option<u64> {
+        let date = self.u16_at(24);
+        let time = self.u16_at(22);
+        let year = 1980 + u64::from(date >> 9);
+        let month = u64::from((date >> 5) & 0xf);
+        let day = u64::from(date & 0x1f);
+        if !(1..=12).contains(&month) || day == 0 {
+            return None;
+        }
+        let hours = u64::from(time >> 11);
+        let minutes = u64::from((time >> 5) & 0x3f);
+        let seconds = u64::from(time & 0x1f) * 2;
+        Some(
+            days_since_unix_epoch(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds,
+        )
+    }
+
+    fn display_name(&self) -> String {
+        let name = self.short_name();
+        let (base, ext) = name.split_at(8);
+        let push = |display: &mut String, part: &[u8], lowercase: bool| {
+            for (i, b) in part.iter().copied().enumerate() {
+                let b = if i == 0 && b == ESCAPED_E5 {
+                    DELETED
+                } else {
+                    b
+                };
+                // Short names are in an OEM code page, of which only ASCII is interpreted here.
+                let c = if b.is_ascii() {
+                    char::from(b)
+                } else {
+                    REPLACEMENT_CHARACTER
+                };
+                display.push(if lowercase { c.to_ascii_lowercase() } else { c });
+            }
+        };
+        let mut display = String::new();
+        push(
+            &mut display,
+            trim_padding(base),
+            self.0[12] & NT_LOWERCASE_BASE != 0,
+        );
+        let ext = trim_padding(ext);
+        if !ext.is_empty() {
+            display.push('.');
+            push(&mut display, ext, self.0[12] & NT_LOWERCASE_EXT != 0);
+        }
+        display
+    }
+}
+
+// From Howard Hinnant's `days_from_civil`, for years from 1 BCE.
+fn days_since_unix_epoch(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn trim_padding(part: &[u8]) -> &[u8] {
+    let len = part.iter().rposition(|b| *b != b' ').map_or(0, |i| i + 1);
+    &part[..len]
+}
+
+/// An entry of a directory which has been read into memory.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub(crate) name: String,
+    pub(crate) raw: RawEntry,
+    /// The index of the first slot of this entry, including any long name entries.
+    pub(crate) first_slot: usize,
+    /// The index of the slot of the short entry.
+    pub(crate) slot: usize,
+}
+
+impl Entry {
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        eq_ignore_case(&self.name, name) || eq_ignore_case(&self.raw.display_name(), name)
+    }
+}
+
+/// Names are compared case-insensitively, as on other implementations.
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_uppercase)
+        .eq(b.chars().flat_map(char::to_uppercase))
+}
+
+/// The contents of a directory, in terms of 32-byte slots.
+pub(crate) struct Slots<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Slots<'a> {
+    fn get(&self, i: usize) -> &'a [u8; ENTRY_SIZE] {
+        self.0[i * ENTRY_SIZE..][..ENTRY_SIZE].try_into().unwrap()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len() / ENTRY_SIZE
+    }
+
+    /// The index of the slot which marks the end of the directory, or the number of slots if there
+    /// is none.
+    pub(crate) fn end(&self) -> usize {
+        (0..self.len())
+            .find(|i| self.get(*i)[0] == END_OF_DIRECTORY)
+            .unwrap_or(self.len())
+    }
+
+    /// Returns the entries of the directory, excluding `.`, `..`, and the volume label.
+    pub(crate) fn entries(&self) -> Vec<Entry> {
+        let mut entries = vec![];
+        let mut long_name = LongNameAccumulator::default();
+        for i in 0..self.end() {
+            let slot = self.get(i);
+            if slot[0] == DELETED {
+                long_name = LongNameAccumulator::default();
+                continue;
+            }
+            if slot[11] & ATTR_LONG_NAME_MASK == ATTR_LONG_NAME {
+                long_name.push(i, slot);
+                continue;
+            }
+            let raw = RawEntry(*slot);
+            let long_name = core::mem::take(&mut long_name);
+            if raw.attr() & ATTR_VOLUME_ID != 0
+                || raw.short_name() == DOT
+                || raw.short_name() == DOT_DOT
+            {
+                continue;
+            }
+            let (name, first_slot) = match long_name.finish(&raw.short_name()) {
+                Some((name, first_slot)) => (name, first_slot),
+                None => (raw.display_name(), i),
+            };
+            entries.push(Entry {
+                name,
+                raw,
+                first_slot,
+                slot: i,
+            });
+        }
+        entries
+    }
+
+    /// Returns the index of the first of a run of `n` free slots, if there is one.
+    pub(crate) fn find_free(&self, n: usize) -> Option<usize> {
+        let end = self.end();
+        let mut run_start = 0;
+        for i in 0..end {
+            if self.get(i)[0] != DELETED {
+                run_start = i + 1;
+            } else if i + 1 - run_start == n {
+                return Some(run_start);
+            }
+        }
+        // Free slots before the end marker must adjoin it, as readers stop there.
+        (run_start + n <= self.len()).then_some(run_start)
+    }
+}
+
+#[derive(Default)]
+struct LongNameAccumulator {
+    first_slot: usize,
+    checksum: u8,
+    next_ordinal: u8,
+    chars: Vec<u16>,
+}
+
+impl LongNameAccumulator {
+    fn push(&mut self, i: usize, slot: &[u8; ENTRY_SIZE]) {
+        let ordinal = slot[0] & LONG_ENTRY_ORDINAL_MASK;
+        let checksum = slot[13];
+        if slot[0] & LAST_LONG_ENTRY != 0 {
+            *self = Self {
+                first_slot: i,
+                checksum,
+                next_ordinal: ordinal,
+                chars: vec![0; usize::from(ordinal) * CHARS_PER_LONG_ENTRY],
+            };
+        } else if ordinal == 0 || ordinal != self.next_ordinal || checksum != self.checksum {
+            *self = Self::default();
+            return;
+        }
+        if ordinal == 0 {
+            return;
+        }
+        let start = usize::from(ordinal - 1) * CHARS_PER_LONG_ENTRY;
+        for (j, offset) in LONG_NAME_CHAR_OFFSETS.iter().enumerate() {
+            self.chars[start + j] = u16::from_le_bytes([slot[*offset], slot[offset + 1]]);
+        }
+        self.next_ordinal = ordinal - 1;
+    }
+
+    fn finish(self, short_name: &ShortName) -> Option<(String, usize)> {
+        if self.chars.is_empty() || self.next_ordinal != 0 || self.checksum != checksum(short_name)
+        {
+            return None;
+        }
+        let len = self
+            .chars
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(self.chars.len());
+        let name = decode_utf16(self.chars[..len].iter().copied())
+            .map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+            .collect();
+        Some((name, self.first_slot))
+    }
+}
+
+fn checksum(short_name: &ShortName) -> u8 {
+    short_name
+        .iter()
+        .fold(0u8, |sum, b| sum.rotate_right(1).wrapping_add(*b))
+}
+
+pub(crate) fn validate_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.ends_with(['.', ' '])
+        && name.encode_utf16().count() <= MAX_LONG_NAME_LEN
+        && !name.chars().any(|c| c < ' ' || "\"*/:<>?\\|".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidName)
+    }
+}
+
+/// Returns the short name and flags of the reserved byte which represent `name` exactly, if any.
+pub(crate) fn exact_short_name(name: &str) -> Option<(ShortName, u8)> {
+    let (base, ext) = match name.split_once('.') {
+        Some((base, ext)) => (base, ext),
+        None => (name, ""),
+    };
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 {
+        return None;
+    }
+    let mut short_name = [b' '; 11];
+    let mut nt_flags = 0;
+    let (short_base, short_ext) = short_name.split_at_mut(8);
+    for (part, dst, flag) in [
+        (base, short_base, NT_LOWERCASE_BASE),
+        (ext, short_ext, NT_LOWERCASE_EXT),
+    ] {
+        if !part.bytes().all(is_short_name_byte) {
+            return None;
+        }
+        let has_upper = part.bytes().any(|b| b.is_ascii_uppercase());
+        let has_lower = part.bytes().any(|b| b.is_ascii_lowercase());
+        match (has_upper, has_lower) {
+            (true, true) => return None,
+            (false, true) => nt_flags |= flag,
+            _ => {}
+        }
+        dst[..part.len()].copy_from_slice(part.to_ascii_uppercase().as_bytes());
+    }
+    Some((short_name, nt_flags))
+}
+
+fn is_short_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"$%'-_@~`!(){}^#&".contains(&b)
+}
+
+/// Returns a short name for `name` of the form `BASE~N.EXT`, with the smallest `N` for which
+/// `taken` returns `false`.
+pub(crate) fn generate_short_name(
+    name: &str,
+    mut taken: impl FnMut(&ShortName) -> bool,
+) -> Result<ShortName, Error> {
+    let squash = |part: &str| {
+        part.chars()
+            .filter(|c| *c != ' ' && *c != '.')
+            .map(|c| {
+                let b = u8::try_from(c.to_ascii_uppercase()).unwrap_or(b'_');
+                if is_short_name_byte(b) {
+                    b
+                } else {
+                    b'_'
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let name = name.trim_start_matches('.');
+    let (base, ext) = match name.rsplit_once('.') {
+        Some((base, ext)) => (squash(base), squash(ext)),
+        None => (squash(name), vec![]),
+    };
+    let mut short_name = [b' '; 11];
+    let ext_len = ext.len().min(3);
+    short_name[8..][..ext_len].copy_from_slice(&ext[..ext_len]);
+    for n in 1..1_000_000 {
+        let tail = alloc::format!("~{n}");
+        let base_len = base.len().min(8 - tail.len());
+        short_name[..8].fill(b' ');
+        short_name[..base_len].copy_from_slice(&base[..base_len]);
+        short_name[base_len..][..tail.len()].copy_from_slice(tail.as_bytes());
+        if !taken(&short_name) {
+            return Ok(short_name);
+        }
+    }
+    Err(Error::NoSpace)
+}
+
+/// Returns the long name entries for `name`, in the order in which they appear on disk.
+pub(crate) fn long_name_entries(name: &str, short_name: &ShortName) -> Vec<[u8; ENTRY_SIZE]> {
+    let mut chars = name.encode_utf16().collect::<Vec<_>>();
+    let num_entries = chars.len().div_ceil(CHARS_PER_LONG_ENTRY);
+    if chars.len() % CHARS_PER_LONG_ENTRY != 0 {
+        chars.push(0);
+    }
+    chars.resize(num_entries * CHARS_PER_LONG_ENTRY, 0xffff);
+    let checksum = checksum(short_name);
+    (0..num_entries)
+        .rev()
+        .map(|i| {
+            let mut slot = [0; ENTRY_SIZE];
+            slot[0] = u8::try_from(i + 1).unwrap();
+            if i + 1 == num_entries {
+                slot[0] |= LAST_LONG_ENTRY;
+            }
+            slot[11] = ATTR_LONG_NAME;
+            slot[13] = checksum;
+            for (j, offset) in LONG_NAME_CHAR_OFFSETS.iter().enumerate() {
+                slot[*offset..][..2]
+                    .copy_from_slice(&chars[i * CHARS_PER_LONG_ENTRY + j].to_le_bytes());
+            }
+            slot
+        })
+        .collect()
+}
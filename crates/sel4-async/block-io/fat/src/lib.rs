@@ -0,0 +1,659 @@
+//! A FAT32 filesystem over [`BytesIO`], readable through [`FileSystem`] and, when the underlying
+//! storage is [`WritableBytesIO`], writable through [`WritableFileSystem`].
+//!
+//! Long file names are read and written. Names are matched case-insensitively, against either the
+//! long or the short name of an entry. In the absence of a clock, timestamps are set to the FAT
+//! epoch.
+//!
+//! Modifications are made in place, one field at a time, so a volume on which a modifying
+//! operation was interrupted may need to be repaired with `fsck.fat`. Filesystem metadata,
+//! particularly the FAT, is written to frequently, so the underlying storage is best wrapped in a
+//! [`WriteBackBlockIO`](sel4_async_block_io::WriteBackBlockIO), in which case
+//! [`flush`](WritableFileSystem::flush) must be called for modifications to persist.
+//!
+//! Modifying operations are serialized with respect to each other, but not with respect to reads.
+//! A read which runs concurrently with a modification of the same file or directory may observe it
+//! partially applied.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+#![feature(int_roundings)]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use sel4_async_block_io::{BytesIO, WritableBytesIO};
+use sel4_async_fs::{self as fs, DirEntry, FileSystem, Metadata, WritableFileSystem};
+use sel4_async_sync::Mutex;
+
+mod bpb;
+mod dir;
+
+use bpb::{Geometry, END_OF_CHAIN, END_OF_CHAIN_MIN, FAT_ENTRY_MASK, FREE, FS_INFO_UNKNOWN};
+use dir::{RawEntry, Slots, ATTR_ARCHIVE, ATTR_DIRECTORY, DOT, DOT_DOT, ENTRY_SIZE};
+
+pub struct Volume<T> {
+    io: T,
+    geometry: Geometry,
+    state: Mutex<State>,
+}
+
+struct State {
+    // Where to begin the search for a free cluster.
+    next_free: u32,
+    modified: bool,
+}
+
+/// A handle to an open regular file, which refers to the file's directory entry.
+///
+/// A handle must not be used after the file it refers to has been removed.
+#[derive(Debug)]
+pub struct File {
+    entry_offset: usize,
+}
+
+enum Node {
+    Root,
+    Entry {
+        entry: dir::Entry,
+        // The offsets of each of the entry's slots, the last of which is the short entry.
+        slot_offsets: Vec<usize>,
+    },
+}
+
+impl Node {
+    fn dir_cluster(&self, geometry: &Geometry) -> Result<u32, Error> {
+        match self {
+            Self::Root => Ok(geometry.root_cluster),
+            Self::Entry { entry, .. } if entry.raw.is_dir() => Ok(entry.raw.first_cluster()),
+            _ => Err(Error::NotADirectory),
+        }
+    }
+}
+
+// A directory which has been read into memory.
+struct LoadedDir {
+    clusters: Vec<u32>,
+    data: Vec<u8>,
+}
+
+impl LoadedDir {
+    fn slots(&self) -> Slots {
+        Slots(&self.data)
+    }
+
+    fn find(&self, name: &str) -> Option<dir::Entry> {
+        self.slots()
+            .entries()
+            .into_iter()
+            .find(|entry| entry.matches(name))
+    }
+
+    fn slot_offset(&self, geometry: &Geometry, i: usize) -> usize {
+        let offset = i * ENTRY_SIZE;
+        let cluster = self.clusters[offset / geometry.bytes_per_cluster];
+        geometry.cluster_offset(cluster) + offset % geometry.bytes_per_cluster
+    }
+}
+
+impl<T: BytesIO> Volume<T> {
+    pub async fn mount(io: T) -> Result<Self, Error> {
+        let geometry = Geometry::read(&io).await?;
+        let mut next_free = 2;
+        if let Some(offset) = geometry.fs_info_next_free_offset() {
+            let hint = read_u32(&io, offset).await;
+            if geometry.is_valid_cluster(hint) {
+                next_free = hint;
+            }
+        }
+        Ok(Self {
+            io,
+            geometry,
+            state: Mutex::new(State {
+                next_free,
+                modified: false,
+            }),
+        })
+    }
+
+    pub fn io(&self) -> &T {
+        &self.io
+    }
+
+    pub fn into_io(self) -> T {
+        self.io
+    }
+
+    async fn next_cluster(&self, cluster: u32) -> Result<Option<u32>, Error> {
+        let value =
+            read_u32(&self.io, self.geometry.fat_entry_offset(cluster)).await & FAT_ENTRY_MASK;
+        if value >= END_OF_CHAIN_MIN {
+            Ok(None)
+        } else if self.geometry.is_valid_cluster(value) {
+            Ok(Some(value))
+        } else {
+            Err(Error::Malformed)
+        }
+    }
+
+    async fn chain(&self, first_cluster: u32) -> Result<Vec<u32>, Error> {
+        if !self.geometry.is_valid_cluster(first_cluster) {
+            return Err(Error::Malformed);
+        }
+        let mut clusters = vec![first_cluster];
+        while let Some(cluster) = self.next_cluster(*clusters.last().unwrap()).await? {
+            if clusters.len() >= usize::try_from(self.geometry.num_clusters).unwrap() {
+                // The chain has a cycle.
+                return Err(Error::Malformed);
+            }
+            clusters.push(cluster);
+        }
+        Ok(clusters)
+    }
+
+    /// Returns the `(offset, len)` extents on disk of the `len` bytes at `offset` into the chain
+    /// starting at `first_cluster`.
+    async fn extents(
+        &self,
+        first_cluster: u32,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<(usize, usize)>, Error> {
+        let bytes_per_cluster = self.geometry.bytes_per_cluster;
+        let mut extents: Vec<(usize, usize)> = vec![];
+        if len == 0 {
+            return Ok(extents);
+        }
+        if !self.geometry.is_valid_cluster(first_cluster) {
+            return Err(Error::Malformed);
+        }
+        let mut cluster = first_cluster;
+        for _ in 0..offset / bytes_per_cluster {
+            cluster = self.next_cluster(cluster).await?.ok_or(Error::Malformed)?;
+        }
+        let mut offset_into_cluster = offset % bytes_per_cluster;
+        let mut remaining = len;
+        loop {
+            let n = remaining.min(bytes_per_cluster - offset_into_cluster);
+            let start = self.geometry.cluster_offset(cluster) + offset_into_cluster;
+            match extents.last_mut() {
+                Some((last_start, last_len)) if *last_start + *last_len == start => {
+                    *last_len += n;
+                }
+                _ => extents.push((start, n)),
+            }
+            remaining -= n;
+            if remaining == 0 {
+                return Ok(extents);
+            }
+            offset_into_cluster = 0;
+            cluster = self.next_cluster(cluster).await?.ok_or(Error::Malformed)?;
+        }
+    }
+
+    async fn load_dir(&self, first_cluster: u32) -> Result<LoadedDir, Error> {
+        let clusters = self.chain(first_cluster).await?;
+        let bytes_per_cluster = self.geometry.bytes_per_cluster;
+        let mut data = vec![0; clusters.len() * bytes_per_cluster];
+        for (cluster, buf) in clusters.iter().zip(data.chunks_mut(bytes_per_cluster)) {
+            self.io
+                .read(self.geometry.cluster_offset(*cluster), buf)
+                .await;
+        }
+        Ok(LoadedDir { clusters, data })
+    }
+
+    async fn lookup(&self, path: &str) -> Result<Node, Error> {
+        let mut node = Node::Root;
+        for name in fs::components(path) {
+            let dir = self.load_dir(node.dir_cluster(&self.geometry)?).await?;
+            let entry = dir.find(name).ok_or(Error::NotFound)?;
+            let slot_offsets = (entry.first_slot..=entry.slot)
+                .map(|i| dir.slot_offset(&self.geometry, i))
+                .collect();
+            node = Node::Entry {
+                entry,
+                slot_offsets,
+            };
+        }
+        Ok(node)
+    }
+
+    /// Returns the first cluster of the directory which would contain `path`, and the last
+    /// component of `path`.
+    async fn lookup_parent<'a>(&self, path: &'a str) -> Result<(u32, &'a str), Error> {
+        let mut components = fs::components(path).collect::<Vec<_>>();
+        let name = components.pop().ok_or(Error::InvalidName)?;
+        dir::validate_name(name)?;
+        let parent = self.lookup(&components.join("/")).await?;
+        Ok((parent.dir_cluster(&self.geometry)?, name))
+    }
+
+    async fn read_entry(&self, offset: usize) -> RawEntry {
+        let mut raw = RawEntry([0; ENTRY_SIZE]);
+        self.io.read(offset, &mut raw.0).await;
+        raw
+    }
+
+    async fn file_entry(&self, file: &File) -> Result<RawEntry, Error> {
+        let raw = self.read_entry(file.entry_offset).await;
+        if raw.is_deleted() {
+            return Err(Error::NotFound);
+        }
+        Ok(raw)
+    }
+}
+
+impl<T: WritableBytesIO> Volume<T> {
+    async fn modify(&self, state: &mut State) {
+        if !state.modified {
+            state.modified = true;
+            // The free count is not maintained.
+            if let Some(offset) = self.geometry.fs_info_free_count_offset() {
+                write_u32(&self.io, offset, FS_INFO_UNKNOWN).await;
+            }
+        }
+    }
+
+    async fn set_fat_entry(&self, state: &mut State, cluster: u32, value: u32) {
+        self.modify(state).await;
+        for offset in self.geometry.fat_entry_offsets(cluster) {
+            let reserved = read_u32(&self.io, offset).await & !FAT_ENTRY_MASK;
+            write_u32(&self.io, offset, reserved | value).await;
+        }
+    }
+
+    /// Allocates `n` clusters, each of which is marked as the end of a chain.
+    async fn allocate(&self, state: &mut State, n: usize) -> Result<Vec<u32>, Error> {
+        let mut clusters = vec![];
+        let mut candidate = state.next_free;
+        for _ in 0..self.geometry.num_clusters {
+            if clusters.len() == n {
+                break;
+            }
+            if !self.geometry.is_valid_cluster(candidate) {
+                candidate = 2;
+            }
+            let offset = self.geometry.fat_entry_offset(candidate);
+            if read_u32(&self.io, offset).await & FAT_ENTRY_MASK == FREE {
+                self.set_fat_entry(state, candidate, END_OF_CHAIN).await;
+                clusters.push(candidate);
+            }
+            candidate += 1;
+        }
+        state.next_free = candidate;
+        if clusters.len() < n {
+            for cluster in clusters {
+                self.set_fat_entry(state, cluster, FREE).await;
+            }
+            return Err(Error::NoSpace);
+        }
+        Ok(clusters)
+    }
+
+    async fn zero(&self, extents: &[(usize, usize)]) {
+        let zeros = vec![0; self.geometry.bytes_per_cluster];
+        for (start, len) in extents {
+            for chunk_start in (0..*len).step_by(zeros.len()) {
+                let n = zeros.len().min(len - chunk_start);
+                self.io.write(start + chunk_start, &zeros[..n]).await;
+            }
+        }
+    }
+
+    /// Resizes the file described by `raw` to `len` bytes, allocating or freeing clusters, and
+    /// zero-filling any part of the extension which lies before `zero_until`.
+    async fn resize(
+        &self,
+        state: &mut State,
+        raw: &mut RawEntry,
+        len: usize,
+        zero_until: usize,
+    ) -> Result<(), Error> {
+        let size = u32::try_from(len).map_err(|_| Error::FileTooLarge)?;
+        let old_len = usize::try_from(raw.size()).unwrap();
+        let clusters = match raw.first_cluster() {
+            0 => vec![],
+            first_cluster => self.chain(first_cluster).await?,
+        };
+        let num_clusters = len.div_ceil(self.geometry.bytes_per_cluster);
+        match num_clusters.cmp(&clusters.len()) {
+            Ordering::Greater => {
+                let new = self.allocate(state, num_clusters - clusters.len()).await?;
+                match clusters.last() {
+                    Some(last) => self.set_fat_entry(state, *last, new[0]).await,
+                    None => raw.set_first_cluster(new[0]),
+                }
+                for pair in new.windows(2) {
+                    self.set_fat_entry(state, pair[0], pair[1]).await;
+                }
+            }
+            Ordering::Less => {
+                match num_clusters.checked_sub(1) {
+                    Some(last) => {
+                        self.set_fat_entry(state, clusters[last], END_OF_CHAIN)
+                            .await
+                    }
+                    None => raw.set_first_cluster(0),
+                }
+                for cluster in &clusters[num_clusters..] {
+                    self.set_fat_entry(state, *cluster, FREE).await;
+                }
+            }
+            Ordering::Equal => {}
+        }
+        let zero_until = zero_until.min(len);
+        if zero_until > old_len {
+            let extents = self
+                .extents(raw.first_cluster(), old_len, zero_until - old_len)
+                .await?;
+            self.zero(&extents).await;
+        }
+        raw.set_size(size);
+        Ok(())
+    }
+
+    /// Adds an entry for `name`, which must not already exist, to `dir`, returning the offset of
+    /// its short entry.
+    async fn create_entry(
+        &self,
+        state: &mut State,
+        dir: &mut LoadedDir,
+        name: &str,
+        attr: u8,
+        first_cluster: u32,
+    ) -> Result<usize, Error> {
+        let existing = dir.slots().entries();
+        let taken = |short_name: &dir::ShortName| {
+            existing
+                .iter()
+                .any(|entry| &entry.raw.short_name() == short_name)
+        };
+        let (short_name, nt_flags, long_name_entries) = match dir::exact_short_name(name) {
+            Some((short_name, nt_flags)) if !taken(&short_name) => (short_name, nt_flags, vec![]),
+            _ => {
+                let short_name = dir::generate_short_name(name, taken)?;
+                let long_name_entries = dir::long_name_entries(name, &short_name);
+                (short_name, 0, long_name_entries)
+            }
+        };
+        let raw = RawEntry::new(&short_name, nt_flags, attr, first_cluster);
+
+        let num_slots = long_name_entries.len() + 1;
+        let start = loop {
+            if let Some(start) = dir.slots().find_free(num_slots) {
+                break start;
+            }
+            if dir.data.len() / ENTRY_SIZE >= dir::MAX_ENTRIES {
+                return Err(Error::NoSpace);
+            }
+            let cluster = self.allocate(state, 1).await?[0];
+            self.zero(&[(
+                self.geometry.cluster_offset(cluster),
+                self.geometry.bytes_per_cluster,
+            )])
+            .await;
+            self.set_fat_entry(state, *dir.clusters.last().unwrap(), cluster)
+                .await;
+            dir.clusters.push(cluster);
+            dir.data
+                .resize(dir.data.len() + self.geometry.bytes_per_cluster, 0);
+        };
+
+        // If the new entries extend past the end of the directory, make sure that a new end
+        // follows them.
+        let end = start + num_slots;
+        if end > dir.slots().end() && end * ENTRY_SIZE < dir.data.len() {
+            self.io
+                .write(dir.slot_offset(&self.geometry, end), &[0])
+                .await;
+        }
+        for (i, slot) in long_name_entries.iter().chain([&raw.0]).enumerate() {
+            self.io
+                .write(dir.slot_offset(&self.geometry, start + i), slot)
+                .await;
+        }
+        Ok(dir.slot_offset(&self.geometry, end - 1))
+    }
+}
+
+impl<T: BytesIO> FileSystem for Volume<T> {
+    type Error = Error;
+
+    type File = File;
+
+    async fn open(&self, path: &str) -> Result<File, Error> {
+        match self.lookup(path).await? {
+            Node::Entry {
+                entry,
+                slot_offsets,
+            } if !entry.raw.is_dir() => Ok(File {
+                entry_offset: *slot_offsets.last().unwrap(),
+            }),
+            _ => Err(Error::IsADirectory),
+        }
+    }
+
+    async fn read(&self, file: &File, offset: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let raw = self.file_entry(file).await?;
+        let size = usize::try_from(raw.size()).unwrap();
+        let n = buf.len().min(size.saturating_sub(offset));
+        let mut pos = 0;
+        for (start, len) in self.extents(raw.first_cluster(), offset, n).await? {
+            self.io.read(start, &mut buf[pos..][..len]).await;
+            pos += len;
+        }
+        Ok(n)
+    }
+
+    async fn stat(&self, path: &str) -> Result<Metadata, Error> {
+        Ok(match self.lookup(path).await? {
+            Node::Entry { entry, .. } if !entry.raw.is_dir() => Metadata {
+                ty: fs::EntryType::RegularFile,
+                size: usize::try_from(entry.raw.size()).unwrap(),
+                modified: entry.raw.modified(),
+            },
+            Node::Entry { entry, .. } => Metadata {
+                ty: fs::EntryType::Directory,
+                size: 0,
+                modified: entry.raw.modified(),
+            },
+            Node::Root => Metadata {
+                ty: fs::EntryType::Directory,
+                size: 0,
+                modified: None,
+            },
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, Error> {
+        let node = self.lookup(path).await?;
+        let dir = self.load_dir(node.dir_cluster(&self.geometry)?).await?;
+        Ok(dir
+            .slots()
+            .entries()
+            .into_iter()
+            .map(|entry| DirEntry {
+                ty: if entry.raw.is_dir() {
+                    fs::EntryType::Directory
+                } else {
+                    fs::EntryType::RegularFile
+                },
+                name: entry.name,
+            })
+            .collect())
+    }
+}
+
+impl<T: WritableBytesIO> WritableFileSystem for Volume<T> {
+    async fn create(&self, path: &str) -> Result<File, Error> {
+        let mut state = self.state.lock().await;
+        let (parent, name) = self.lookup_parent(path).await?;
+        let mut dir = self.load_dir(parent).await?;
+        let entry_offset = match dir.find(name) {
+            Some(entry) if entry.raw.is_dir() => return Err(Error::IsADirectory),
+            Some(entry) => {
+                let entry_offset = dir.slot_offset(&self.geometry, entry.slot);
+                let mut raw = entry.raw;
+                self.resize(&mut state, &mut raw, 0, 0).await?;
+                self.io.write(entry_offset, &raw.0).await;
+                entry_offset
+            }
+            None => {
+                self.create_entry(&mut state, &mut dir, name, ATTR_ARCHIVE, 0)
+                    .await?
+            }
+        };
+        Ok(File { entry_offset })
+    }
+
+    async fn write(&self, file: &File, offset: usize, buf: &[u8]) -> Result<(), Error> {
+        let mut state = self.state.lock().await;
+        let mut raw = self.file_entry(file).await?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let end = offset + buf.len();
+        if end > usize::try_from(raw.size()).unwrap() {
+            self.resize(&mut state, &mut raw, end, offset).await?;
+        }
+        let mut pos = 0;
+        for (start, len) in self.extents(raw.first_cluster(), offset, buf.len()).await? {
+            self.io.write(start, &buf[pos..][..len]).await;
+            pos += len;
+        }
+        raw.set_attr(raw.attr() | ATTR_ARCHIVE);
+        self.io.write(file.entry_offset, &raw.0).await;
+        Ok(())
+    }
+
+    async fn set_len(&self, file: &File, len: usize) -> Result<(), Error> {
+        let mut state = self.state.lock().await;
+        let mut raw = self.file_entry(file).await?;
+        self.resize(&mut state, &mut raw, len, len).await?;
+        raw.set_attr(raw.attr() | ATTR_ARCHIVE);
+        self.io.write(file.entry_offset, &raw.0).await;
+        Ok(())
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<(), Error> {
+        let mut state = self.state.lock().await;
+        let (parent, name) = self.lookup_parent(path).await?;
+        let mut dir = self.load_dir(parent).await?;
+        if dir.find(name).is_some() {
+            return Err(Error::AlreadyExists);
+        }
+        let cluster = self.allocate(&mut state, 1).await?[0];
+        let cluster_offset = self.geometry.cluster_offset(cluster);
+        self.zero(&[(cluster_offset, self.geometry.bytes_per_cluster)])
+            .await;
+        // The root directory is referred to as cluster 0.
+        let parent_ref = if parent == self.geometry.root_cluster {
+            0
+        } else {
+            parent
+        };
+        for (i, (short_name, first_cluster)) in
+            [(DOT, cluster), (DOT_DOT, parent_ref)].iter().enumerate()
+        {
+            let raw = RawEntry::new(short_name, 0, ATTR_DIRECTORY, *first_cluster);
+            self.io.write(cluster_offset + i * ENTRY_SIZE, &raw.0).await;
+        }
+        if let Err(err) = self
+            .create_entry(&mut state, &mut dir, name, ATTR_DIRECTORY, cluster)
+            .await
+        {
+            self.set_fat_entry(&mut state, cluster, FREE).await;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, path: &str) -> Result<(), Error> {
+        let mut state = self.state.lock().await;
+        let Node::Entry {
+            entry,
+            slot_offsets,
+        } = self.lookup(path).await?
+        else {
+            return Err(Error::InvalidName);
+        };
+        let first_cluster = entry.raw.first_cluster();
+        if entry.raw.is_dir()
+            && !self
+                .load_dir(first_cluster)
+                .await?
+                .slots()
+                .entries()
+                .is_empty()
+        {
+            return Err(Error::DirectoryNotEmpty);
+        }
+        self.modify(&mut state).await;
+        for offset in slot_offsets {
+            self.io.write(offset, &[dir::DELETED]).await;
+        }
+        if first_cluster != 0 {
+            for cluster in self.chain(first_cluster).await? {
+                self.set_fat_entry(&mut state, cluster, FREE).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        let state = self.state.lock().await;
+        if state.modified {
+            if let Some(offset) = self.geometry.fs_info_next_free_offset() {
+                write_u32(&self.io, offset, state.next_free).await;
+            }
+        }
+        self.io.flush().await;
+        Ok(())
+    }
+}
+
+async fn read_u32(io: &impl BytesIO, offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    io.read(offset, &mut buf).await;
+    u32::from_le_bytes(buf)
+}
+
+async fn write_u32(io: &impl WritableBytesIO, offset: usize, value: u32) {
+    io.write(offset, &value.to_le_bytes()).await;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+    AlreadyExists,
+    DirectoryNotEmpty,
+    InvalidName,
+    NoSpace,
+    FileTooLarge,
+    Unsupported,
+    Malformed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::NotADirectory => write!(f, "not a directory"),
+            Self::IsADirectory => write!(f, "is a directory"),
+            Self::AlreadyExists => write!(f, "already exists"),
+            Self::DirectoryNotEmpty => write!(f, "directory not empty"),
+            Self::InvalidName => write!(f, "invalid name"),
+            Self::NoSpace => write!(f, "no space left on volume"),
+            Self::FileTooLarge => write!(f, "file too large"),
+            Self::Unsupported => write!(f, "unsupported filesystem (only FAT32 is supported)"),
+            Self::Malformed => write!(f, "malformed filesystem"),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+#![feature(async_fn_in_trait)]
+
+use sel4_async_block_io::{BytesIOAdapter, WriteBackBlockIO};
+use sel4_async_block_io_fat::{Error, Volume};
+use sel4_async_fs::{DirEntry, EntryType, FileSystem, WritableFileSystem};
+use sel4_async_test_utils::{block_on, MemoryBlockIO};
+
+const SECTOR_SIZE: usize = 512;
+const NUM_RESERVED_SECTORS: usize = 32;
+const NUM_FATS: usize = 2;
+const FAT_SIZE_IN_SECTORS: usize = 8;
+const NUM_DATA_SECTORS: usize = 200;
+const NUM_SECTORS: usize = NUM_RESERVED_SECTORS + NUM_FATS * FAT_SIZE_IN_SECTORS + NUM_DATA_SECTORS;
+
+type Disk = MemoryBlockIO<SECTOR_SIZE>;
+
+// Lays out a volume as `mkfs.fat -F 32 -s 1` would, with the root directory in cluster 2.
+fn format() -> Disk {
+    let mut image = vec![0; NUM_SECTORS * SECTOR_SIZE];
+    let mut put =
+        |offset: usize, bytes: &[u8]| image[offset..][..bytes.len()].copy_from_slice(bytes);
+    put(0, &[0xeb, 0x58, 0x90]);
+    put(3, b"mkfs.fat");
+    put(11, &u16::try_from(SECTOR_SIZE).unwrap().to_le_bytes());
+    put(13, &[1]);
+    put(
+        14,
+        &u16::try_from(NUM_RESERVED_SECTORS).unwrap().to_le_bytes(),
+    );
+    put(16, &[u8::try_from(NUM_FATS).unwrap()]);
+    put(21, &[0xf8]);
+    put(32, &u32::try_from(NUM_SECTORS).unwrap().to_le_bytes());
+    put(
+        36,
+        &u32::try_from(FAT_SIZE_IN_SECTORS).unwrap().to_le_bytes(),
+    );
+    put(44, &2u32.to_le_bytes());
+    put(48, &1u16.to_le_bytes());
+    put(510, &[0x55, 0xaa]);
+    put(SECTOR_SIZE, &0x4161_5252u32.to_le_bytes());
+    put(SECTOR_SIZE + 484, &0x6141_7272u32.to_le_bytes());
+    put(SECTOR_SIZE + 488, &0xffff_ffffu32.to_le_bytes());
+    put(SECTOR_SIZE + 492, &3u32.to_le_bytes());
+    for i in 0..NUM_FATS {
+        let fat_offset = (NUM_RESERVED_SECTORS + i * FAT_SIZE_IN_SECTORS) * SECTOR_SIZE;
+        for (j, value) in [0x0fff_fff8u32, 0x0fff_ffff, 0x0fff_ffff]
+            .iter()
+            .enumerate()
+        {
+            put(fat_offset + j * 4, &value.to_le_bytes());
+        }
+    }
+    Disk::from_bytes(&image)
+}
+
+type Io = BytesIOAdapter<WriteBackBlockIO<Disk, SECTOR_SIZE>, SECTOR_SIZE>;
+
+fn mount(disk: &Disk) -> Volume<Io> {
+    block_on(Volume::mount(BytesIOAdapter::new(WriteBackBlockIO::new(
+        disk.clone(),
+        8,
+    ))))
+    .unwrap()
+}
+
+fn read_all(volume: &Volume<Io>, path: &str) -> Result<Vec<u8>, Error> {
+    block_on(async {
+        let file = volume.open(path).await?;
+        let size = volume.stat(path).await?.size;
+        let mut data = vec![0; size + 1];
+        let n = volume.read(&file, 0, &mut data).await?;
+        assert_eq!(n, size);
+        data.truncate(n);
+        Ok(data)
+    })
+}
+
+fn write_all(volume: &Volume<Io>, path: &str, data: &[u8]) -> Result<(), Error> {
+    block_on(async {
+        let file = volume.create(path).await?;
+        // In uneven pieces, to cross sector and cluster boundaries.
+        for (i, chunk) in data.chunks(300).enumerate() {
+            volume.write(&file, i * 300, chunk).await?;
+        }
+        volume.close(file).await
+    })
+}
+
+fn sorted_entries(volume: &Volume<Io>, path: &str) -> Vec<DirEntry> {
+    let mut entries = block_on(volume.read_dir(path)).unwrap();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+fn entry(name: &str, ty: EntryType) -> DirEntry {
+    DirEntry {
+        name: name.to_owned(),
+        ty,
+    }
+}
+
+#[test]
+fn create_write_read_and_remove() {
+    let disk = format();
+    let volume = mount(&disk);
+
+    let big = (0..5000).map(|i| i as u8).collect::<Vec<_>>();
+    block_on(volume.create_dir("logs")).unwrap();
+    block_on(volume.create_dir("logs/old")).unwrap();
+    write_all(&volume, "README", b"hello").unwrap();
+    write_all(&volume, "logs/A Long Name.txt", &big).unwrap();
+    write_all(&volume, "logs/data.bin", b"data").unwrap();
+    // Many entries, to extend the directory beyond one cluster
+    for i in 0..40 {
+        write_all(&volume, &format!("logs/old/entry number {i}"), b"x").unwrap();
+    }
+
+    assert_eq!(
+        block_on(volume.create_dir("LOGS")).unwrap_err(),
+        Error::AlreadyExists
+    );
+    assert_eq!(
+        write_all(&volume, "logs", b"").unwrap_err(),
+        Error::IsADirectory
+    );
+    assert_eq!(
+        write_all(&volume, "README/x", b"").unwrap_err(),
+        Error::NotADirectory
+    );
+    assert_eq!(
+        write_all(&volume, "logs/a?b", b"").unwrap_err(),
+        Error::InvalidName
+    );
+    assert_eq!(
+        block_on(volume.remove("logs")).unwrap_err(),
+        Error::DirectoryNotEmpty
+    );
+
+    block_on(volume.flush()).unwrap();
+    drop(volume);
+
+    let volume = mount(&disk);
+    assert_eq!(read_all(&volume, "readme").unwrap(), b"hello");
+    // 1980-01-01, in the absence of a clock
+    assert_eq!(
+        block_on(volume.stat("README")).unwrap().modified,
+        Some(315_532_800)
+    );
+    assert_eq!(read_all(&volume, "/logs/a long name.TXT").unwrap(), big);
+    assert_eq!(read_all(&volume, "logs/old/entry number 39").unwrap(), b"x");
+    assert_eq!(
+        read_all(&volume, "logs/missing").unwrap_err(),
+        Error::NotFound
+    );
+    assert_eq!(read_all(&volume, "logs").unwrap_err(), Error::IsADirectory);
+    assert_eq!(
+        sorted_entries(&volume, ""),
+        [
+            entry("README", EntryType::RegularFile),
+            entry("logs", EntryType::Directory),
+        ]
+    );
+    assert_eq!(
+        sorted_entries(&volume, "logs"),
+        [
+            entry("A Long Name.txt", EntryType::RegularFile),
+            entry("data.bin", EntryType::RegularFile),
+            entry("old", EntryType::Directory),
+        ]
+    );
+    assert_eq!(sorted_entries(&volume, "logs/old").len(), 40);
+
+    // Overwrite, extend past a gap, and truncate
+    block_on(async {
+        let file = volume.open("logs/data.bin").await?;
+        volume.write(&file, 2, b"TA").await?;
+        volume.write(&file, 1000, b"end").await?;
+        volume.close(file).await
+    })
+    .unwrap();
+    let mut expected = b"daTA".to_vec();
+    expected.resize(1000, 0);
+    expected.extend(b"end");
+    assert_eq!(read_all(&volume, "logs/data.bin").unwrap(), expected);
+    write_all(&volume, "logs/A Long Name.txt", b"short").unwrap();
+    assert_eq!(read_all(&volume, "logs/A Long Name.txt").unwrap(), b"short");
+
+    for i in 0..40 {
+        block_on(volume.remove(&format!("logs/old/entry number {i}"))).unwrap();
+    }
+    block_on(volume.remove("logs/old")).unwrap();
+    block_on(volume.remove("logs/data.bin")).unwrap();
+    assert_eq!(
+        sorted_entries(&volume, "logs"),
+        [entry("A Long Name.txt", EntryType::RegularFile)]
+    );
+    assert_eq!(
+        block_on(volume.stat("logs/old")).unwrap_err(),
+        Error::NotFound
+    );
+    block_on(volume.flush()).unwrap();
+
+    // Freed clusters are reused, so a file nearly the size of the volume fits.
+    let volume = mount(&disk);
+    let huge = vec![0xaa; (NUM_DATA_SECTORS - 8) * SECTOR_SIZE];
+    write_all(&volume, "huge", &huge).unwrap();
+    assert_eq!(read_all(&volume, "huge").unwrap(), huge);
+    assert_eq!(
+        write_all(&volume, "huge2", &huge).unwrap_err(),
+        Error::NoSpace
+    );
+}
@@ -0,0 +1,368 @@
+//! Packet capture to a bounded ring on a block device, which can be read back as a
+//! [pcap](https://www.tcpdump.org/manpages/pcap-savefile.5.html) file.
+//!
+//! Frames are captured synchronously, for example by a network device adapter, into a
+//! [`CaptureQueue`] in memory. A task running [`PcapRing::run`] moves them from the queue to the
+//! ring. The ring is a fixed number of fixed-size slots, each of which holds one pcap record
+//! truncated to the ring's snapshot length, so the oldest records are overwritten once the ring is
+//! full. A [`PcapSnapshot`] presents the records in the ring, oldest first, behind a pcap file
+//! header, as a [`BytesIO`] of known length, suitable for serving over HTTP.
+//!
+//! The ring's metadata, which records the number of records written, is at the start of the ring,
+//! so a ring can be reopened with [`PcapRing::open`], for example after a reboot.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+use sel4_async_block_io::{BytesIO, WritableBytesIO};
+
+const RING_MAGIC: [u8; 8] = *b"sel4pcap";
+
+const RING_HEADER_SIZE: usize = 24;
+
+const FILE_HEADER_SIZE: usize = 24;
+
+const RECORD_HEADER_SIZE: usize = 16;
+
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// A bounded queue of captured frames, which can be shared between the code which captures them
+/// and the task which writes them to a [`PcapRing`].
+///
+/// Frames which arrive while the queue is full are dropped and counted.
+#[derive(Clone)]
+pub struct CaptureQueue {
+    inner: Rc<RefCell<CaptureQueueInner>>,
+}
+
+struct CaptureQueueInner {
+    frames: VecDeque<CapturedFrame>,
+    capacity: usize,
+    snaplen: usize,
+    dropped: usize,
+    waker: Option<Waker>,
+}
+
+/// A frame in a [`CaptureQueue`], truncated to the queue's snapshot length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    pub timestamp_micros: u64,
+    pub orig_len: usize,
+    pub data: Vec<u8>,
+}
+
+impl CaptureQueue {
+    /// `capacity` is the maximum number of frames held, and `snaplen` the maximum number of bytes
+    /// held of each.
+    pub fn new(capacity: usize, snaplen: usize) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(CaptureQueueInner {
+                frames: VecDeque::with_capacity(capacity),
+                capacity,
+                snaplen,
+                dropped: 0,
+                waker: None,
+            })),
+        }
+    }
+
+    pub fn capture(&self, timestamp_micros: u64, frame: &[u8]) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.frames.len() == inner.capacity {
+            inner.dropped += 1;
+            return;
+        }
+        let data = frame[..frame.len().min(inner.snaplen)].to_vec();
+        inner.frames.push_back(CapturedFrame {
+            timestamp_micros,
+            orig_len: frame.len(),
+            data,
+        });
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// The number of frames dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.inner.borrow().dropped
+    }
+
+    pub fn pop(&self) -> Option<CapturedFrame> {
+        self.inner.borrow_mut().frames.pop_front()
+    }
+
+    /// Waits until the queue is non-empty.
+    pub async fn wait(&self) {
+        poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            if inner.frames.is_empty() {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await
+    }
+}
+
+/// A ring of pcap records in the region of `io` starting at a given offset.
+pub struct PcapRing<T> {
+    io: T,
+    offset: usize,
+    snaplen: usize,
+    num_slots: usize,
+    next_seq: Cell<u64>,
+}
+
+impl<T> PcapRing<T> {
+    pub fn io(&self) -> &T {
+        &self.io
+    }
+
+    pub fn snaplen(&self) -> usize {
+        self.snaplen
+    }
+
+    pub fn num_slots(&self) -> usize {
+        self.num_slots
+    }
+
+    /// The number of records currently in the ring.
+    pub fn num_records(&self) -> usize {
+        self.next_seq.get().min(self.num_slots as u64) as usize
+    }
+
+    /// The number of records ever written to the ring, including those since overwritten.
+    pub fn num_records_written(&self) -> u64 {
+        self.next_seq.get()
+    }
+
+    fn slot_size(&self) -> usize {
+        RECORD_HEADER_SIZE + self.snaplen
+    }
+
+    fn slot_offset(&self, seq: u64) -> usize {
+        let slot = (seq % self.num_slots as u64) as usize;
+        self.offset + RING_HEADER_SIZE + slot * self.slot_size()
+    }
+
+    fn first_seq(&self) -> u64 {
+        self.next_seq.get() - self.num_records() as u64
+    }
+
+    fn encode_ring_header(&self) -> [u8; RING_HEADER_SIZE] {
+        let mut buf = [0; RING_HEADER_SIZE];
+        buf[0..8].copy_from_slice(&RING_MAGIC);
+        buf[8..12].copy_from_slice(&u32::try_from(self.snaplen).unwrap().to_le_bytes());
+        buf[12..16].copy_from_slice(&u32::try_from(self.num_slots).unwrap().to_le_bytes());
+        buf[16..24].copy_from_slice(&self.next_seq.get().to_le_bytes());
+        buf
+    }
+}
+
+impl<T: BytesIO> PcapRing<T> {
+    /// Opens the ring at `offset` in `io`, or returns `None` if there is no ring there.
+    pub async fn open(io: T, offset: usize) -> Option<Self> {
+        let mut buf = [0; RING_HEADER_SIZE];
+        io.read(offset, &mut buf).await;
+        if buf[0..8] != RING_MAGIC {
+            return None;
+        }
+        let snaplen = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        let num_slots = u32::from_le_bytes(buf[12..16].try_into().unwrap());
+        let next_seq = u64::from_le_bytes(buf[16..24].try_into().unwrap());
+        if num_slots == 0 {
+            return None;
+        }
+        Some(Self {
+            io,
+            offset,
+            snaplen: snaplen.try_into().unwrap(),
+            num_slots: num_slots.try_into().unwrap(),
+            next_seq: Cell::new(next_seq),
+        })
+    }
+
+    /// Takes a snapshot of the records currently in the ring.
+    ///
+    /// Records which are overwritten while the snapshot is being read are read as they are at that
+    /// time, so capture should be paused while reading a snapshot of a full ring.
+    pub async fn snapshot(&self) -> PcapSnapshot<'_, T> {
+        let mut records = Vec::with_capacity(self.num_records());
+        let mut file_offset = FILE_HEADER_SIZE;
+        for seq in self.first_seq()..self.next_seq.get() {
+            let slot_offset = self.slot_offset(seq);
+            let mut header = [0; RECORD_HEADER_SIZE];
+            self.io.read(slot_offset, &mut header).await;
+            let incl_len = u32::from_le_bytes(header[8..12].try_into().unwrap());
+            let len = RECORD_HEADER_SIZE + usize::try_from(incl_len).unwrap().min(self.snaplen);
+            records.push(SnapshotRecord {
+                file_offset,
+                slot_offset,
+                len,
+            });
+            file_offset += len;
+        }
+        PcapSnapshot {
+            ring: self,
+            records,
+            size: file_offset,
+        }
+    }
+}
+
+impl<T: WritableBytesIO> PcapRing<T> {
+    /// Creates an empty ring of at most `size` bytes at `offset` in `io`, holding up to `snaplen`
+    /// bytes of each frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is too small for a single record.
+    pub async fn create(io: T, offset: usize, size: usize, snaplen: usize) -> Self {
+        let slot_size = RECORD_HEADER_SIZE + snaplen;
+        let num_slots = size.saturating_sub(RING_HEADER_SIZE) / slot_size;
+        assert!(num_slots > 0);
+        let this = Self {
+            io,
+            offset,
+            snaplen,
+            num_slots,
+            next_seq: Cell::new(0),
+        };
+        this.write_ring_header().await;
+        this.io.flush().await;
+        this
+    }
+
+    async fn write_ring_header(&self) {
+        self.io.write(self.offset, &self.encode_ring_header()).await;
+    }
+
+    async fn write_record(&self, frame: &CapturedFrame) {
+        let incl_len = frame.data.len().min(self.snaplen);
+        let mut buf = vec![0; RECORD_HEADER_SIZE + incl_len];
+        let ts_sec = u32::try_from(frame.timestamp_micros / 1_000_000).unwrap_or(u32::MAX);
+        let ts_usec = (frame.timestamp_micros % 1_000_000) as u32;
+        buf[0..4].copy_from_slice(&ts_sec.to_le_bytes());
+        buf[4..8].copy_from_slice(&ts_usec.to_le_bytes());
+        buf[8..12].copy_from_slice(&u32::try_from(incl_len).unwrap().to_le_bytes());
+        buf[12..16].copy_from_slice(&u32::try_from(frame.orig_len).unwrap().to_le_bytes());
+        buf[RECORD_HEADER_SIZE..].copy_from_slice(&frame.data[..incl_len]);
+        let seq = self.next_seq.get();
+        self.io.write(self.slot_offset(seq), &buf).await;
+        self.next_seq.set(seq + 1);
+    }
+
+    /// Appends `frame` to the ring, overwriting the oldest record if the ring is full.
+    pub async fn append(&self, frame: &CapturedFrame) {
+        self.write_record(frame).await;
+        self.write_ring_header().await;
+    }
+
+    /// Moves all frames in `queue` to the ring, and returns the number moved.
+    pub async fn drain(&self, queue: &CaptureQueue) -> usize {
+        let mut n = 0;
+        while let Some(frame) = queue.pop() {
+            self.write_record(&frame).await;
+            n += 1;
+        }
+        if n > 0 {
+            self.write_ring_header().await;
+            self.io.flush().await;
+        }
+        n
+    }
+
+    /// Moves frames from `queue` to the ring as they arrive.
+    pub async fn run(&self, queue: &CaptureQueue) -> ! {
+        loop {
+            queue.wait().await;
+            self.drain(queue).await;
+        }
+    }
+
+    /// Discards all records.
+    pub async fn clear(&self) {
+        self.next_seq.set(0);
+        self.write_ring_header().await;
+        self.io.flush().await;
+    }
+}
+
+/// The records in a [`PcapRing`] at a point in time, as a pcap file.
+pub struct PcapSnapshot<'a, T> {
+    ring: &'a PcapRing<T>,
+    records: Vec<SnapshotRecord>,
+    size: usize,
+}
+
+struct SnapshotRecord {
+    file_offset: usize,
+    slot_offset: usize,
+    len: usize,
+}
+
+impl<'a, T> PcapSnapshot<'a, T> {
+    /// The size of the pcap file, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn num_records(&self) -> usize {
+        self.records.len()
+    }
+
+    fn file_header(&self) -> [u8; FILE_HEADER_SIZE] {
+        let mut buf = [0; FILE_HEADER_SIZE];
+        buf[0..4].copy_from_slice(&PCAP_MAGIC.to_le_bytes());
+        buf[4..6].copy_from_slice(&2u16.to_le_bytes());
+        buf[6..8].copy_from_slice(&4u16.to_le_bytes());
+        // thiszone and sigfigs are zero
+        buf[16..20].copy_from_slice(&u32::try_from(self.ring.snaplen).unwrap().to_le_bytes());
+        buf[20..24].copy_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        buf
+    }
+}
+
+impl<'a, T: BytesIO> BytesIO for PcapSnapshot<'a, T> {
+    async fn read(&self, offset: usize, buf: &mut [u8]) {
+        assert!(offset + buf.len() <= self.size);
+        let mut pos = offset;
+        let mut buf = buf;
+        if pos < FILE_HEADER_SIZE && !buf.is_empty() {
+            let n = buf.len().min(FILE_HEADER_SIZE - pos);
+            buf[..n].copy_from_slice(&self.file_header()[pos..][..n]);
+            pos += n;
+            buf = &mut buf[n..];
+        }
+        let mut i = self
+            .records
+            .partition_point(|record| record.file_offset + record.len <= pos);
+        while !buf.is_empty() {
+            let record = &self.records[i];
+            let offset_into_record = pos - record.file_offset;
+            let n = buf.len().min(record.len - offset_into_record);
+            self.ring
+                .io
+                .read(record.slot_offset + offset_into_record, &mut buf[..n])
+                .await;
+            pos += n;
+            buf = &mut buf[n..];
+            i += 1;
+        }
+    }
+}
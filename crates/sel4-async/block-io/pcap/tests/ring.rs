@@ -0,0 +1,83 @@
+#![feature(async_fn_in_trait)]
+
+use sel4_async_block_io::BytesIO;
+use sel4_async_block_io_pcap::{CaptureQueue, PcapRing};
+use sel4_async_test_utils::{block_on, MemoryBytesIO};
+
+fn read_all<T: BytesIO>(ring: &PcapRing<T>) -> Vec<u8> {
+    block_on(async {
+        let snapshot = ring.snapshot().await;
+        let mut buf = vec![0; snapshot.size()];
+        // Read in uneven pieces to exercise reads which span records.
+        let mut pos = 0;
+        while pos < buf.len() {
+            let n = 7.min(buf.len() - pos);
+            snapshot.read(pos, &mut buf[pos..][..n]).await;
+            pos += n;
+        }
+        buf
+    })
+}
+
+fn parse_records(file: &[u8]) -> Vec<(u32, u32, Vec<u8>, u32)> {
+    assert_eq!(&file[0..4], &0xa1b2_c3d4u32.to_le_bytes());
+    let u32_at = |i: usize| u32::from_le_bytes(file[i..i + 4].try_into().unwrap());
+    let mut records = vec![];
+    let mut i = 24;
+    while i < file.len() {
+        let incl_len = u32_at(i + 8);
+        let data = file[i + 16..][..incl_len as usize].to_vec();
+        records.push((u32_at(i), u32_at(i + 4), data, u32_at(i + 12)));
+        i += 16 + incl_len as usize;
+    }
+    records
+}
+
+#[test]
+fn wraps_and_reopens() {
+    let io = MemoryBytesIO::new(4096);
+    let offset = 100;
+    let snaplen = 8;
+    let queue = CaptureQueue::new(16, 64);
+    let ring = block_on(PcapRing::create(
+        io.clone(),
+        offset,
+        24 + 3 * (16 + snaplen),
+        snaplen,
+    ));
+    assert_eq!(ring.num_slots(), 3);
+
+    for i in 0..5u8 {
+        let frame = vec![i; usize::from(i) * 4];
+        queue.capture(1_000_000 * u64::from(i) + 42, &frame);
+    }
+    assert_eq!(block_on(ring.drain(&queue)), 5);
+    assert_eq!(ring.num_records(), 3);
+
+    let expected = (2..5u8)
+        .map(|i| {
+            let len = u32::from(i) * 4;
+            (u32::from(i), 42, vec![i; len.min(8) as usize], len)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(parse_records(&read_all(&ring)), expected);
+
+    let reopened = block_on(PcapRing::open(io, offset)).unwrap();
+    assert_eq!(reopened.num_records_written(), 5);
+    assert_eq!(parse_records(&read_all(&reopened)), expected);
+
+    block_on(reopened.clear());
+    assert_eq!(read_all(&reopened).len(), 24);
+}
+
+#[test]
+fn queue_drops_when_full() {
+    let queue = CaptureQueue::new(2, 4);
+    for _ in 0..3 {
+        queue.capture(0, &[1, 2, 3, 4, 5]);
+    }
+    assert_eq!(queue.dropped(), 1);
+    let frame = queue.pop().unwrap();
+    assert_eq!(frame.data, [1, 2, 3, 4]);
+    assert_eq!(frame.orig_len, 5);
+}
@@ -10,7 +10,7 @@ extern crate alloc;
 mod when_alloc;
 
 #[cfg(feature = "alloc")]
-pub use when_alloc::{BytesIOAdapter, CachedBlockIO};
+pub use when_alloc::{BytesIOAdapter, CacheStats, CachedBlockIO, WriteBackBlockIO};
 
 // NOTE: type gymnastics due to current limitations of generic_const_exprs
 
@@ -18,8 +18,68 @@ pub type BlockId = usize;
 
 pub trait BlockIO<const BLOCK_SIZE: usize> {
     async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]);
+
+    /// Reads the consecutive blocks starting at `start_block_id` into `bufs`.
+    ///
+    /// The default implementation reads one block at a time. Implementations which can have
+    /// several requests in flight should override it.
+    async fn read_blocks(&self, start_block_id: usize, bufs: &mut [[u8; BLOCK_SIZE]]) {
+        for (i, buf) in bufs.iter_mut().enumerate() {
+            self.read_block(start_block_id + i, buf).await;
+        }
+    }
+}
+
+pub trait WritableBlockIO<const BLOCK_SIZE: usize>: BlockIO<BLOCK_SIZE> {
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]);
+
+    /// Writes `bufs` to the consecutive blocks starting at `start_block_id`.
+    ///
+    /// The default implementation writes one block at a time.
+    async fn write_blocks(&self, start_block_id: usize, bufs: &[[u8; BLOCK_SIZE]]) {
+        for (i, buf) in bufs.iter().enumerate() {
+            self.write_block(start_block_id + i, buf).await;
+        }
+    }
+
+    /// Waits until all completed writes have reached stable storage.
+    ///
+    /// The default implementation does nothing, which is appropriate for devices without a
+    /// volatile write cache.
+    async fn flush(&self) {}
+}
+
+/// A [`BlockIO`] which can lend out blocks in place, such as a cache.
+pub trait LendingBlockIO<const BLOCK_SIZE: usize>: BlockIO<BLOCK_SIZE> {
+    /// Calls `f` on the contents of a block, without first copying them into a buffer owned by the
+    /// caller.
+    async fn read_block_with<F: FnOnce(&[u8; BLOCK_SIZE]) -> R, R>(
+        &self,
+        block_id: usize,
+        f: F,
+    ) -> R;
 }
 
 pub trait BytesIO {
     async fn read(&self, offset: usize, buf: &mut [u8]);
 }
+
+/// A [`BytesIO`] which can lend out its contents in place, so that they can be copied straight to
+/// their destination, such as a socket's transmit buffer.
+pub trait LendingBytesIO: BytesIO {
+    /// Calls `f` on a prefix of the `len` bytes at `offset`, without first copying them into a
+    /// buffer owned by the caller.
+    ///
+    /// The prefix is non-empty unless `len` is zero, but may be shorter than `len`, for example
+    /// because it ends at a block boundary.
+    async fn read_with<F: FnOnce(&[u8]) -> R, R>(&self, offset: usize, len: usize, f: F) -> R;
+}
+
+pub trait WritableBytesIO: BytesIO {
+    async fn write(&self, offset: usize, buf: &[u8]);
+
+    /// Waits until all completed writes have reached stable storage.
+    ///
+    /// The default implementation does nothing.
+    async fn flush(&self) {}
+}
@@ -1,11 +1,18 @@
-use core::cell::RefCell;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
 use core::marker::PhantomData;
 use core::num::NonZeroUsize;
 
 use futures::future;
 use lru::LruCache;
 
-use crate::{BlockIO, BlockId, BytesIO};
+use sel4_async_copy_engine::{CopyEngine, SoftwareCopyEngine};
+
+use crate::{
+    BlockIO, BlockId, BytesIO, LendingBlockIO, LendingBytesIO, WritableBlockIO, WritableBytesIO,
+};
 
 #[derive(Clone, Debug)]
 pub struct BytesIOAdapter<T, const BLOCK_SIZE: usize> {
@@ -43,6 +50,16 @@ impl<const BLOCK_SIZE: usize, T: BlockIO<BLOCK_SIZE>> BytesIOAdapter<T, BLOCK_SI
     }
 }
 
+impl<const BLOCK_SIZE: usize, T: WritableBlockIO<BLOCK_SIZE>> BytesIOAdapter<T, BLOCK_SIZE> {
+    async fn write_partial_block(&self, block_id: usize, offset_into_block: usize, buf: &[u8]) {
+        assert!(offset_into_block + buf.len() <= BLOCK_SIZE);
+        let mut block_buf = [0; BLOCK_SIZE];
+        self.inner().read_block(block_id, &mut block_buf).await;
+        block_buf[offset_into_block..][..buf.len()].copy_from_slice(buf);
+        self.inner().write_block(block_id, &block_buf).await;
+    }
+}
+
 impl<const BLOCK_SIZE: usize, T: BlockIO<BLOCK_SIZE>> BytesIO for BytesIOAdapter<T, BLOCK_SIZE> {
     async fn read(&self, offset: usize, buf: &mut [u8]) {
         let offset_of_first_full_chunk = offset.next_multiple_of(BLOCK_SIZE);
@@ -83,22 +100,117 @@ impl<const BLOCK_SIZE: usize, T: BlockIO<BLOCK_SIZE>> BytesIO for BytesIOAdapter
     }
 }
 
+impl<const BLOCK_SIZE: usize, T: LendingBlockIO<BLOCK_SIZE>> LendingBytesIO
+    for BytesIOAdapter<T, BLOCK_SIZE>
+{
+    async fn read_with<F: FnOnce(&[u8]) -> R, R>(&self, offset: usize, len: usize, f: F) -> R {
+        if len == 0 {
+            return f(&[]);
+        }
+        let offset_into_block = offset % BLOCK_SIZE;
+        let n = len.min(BLOCK_SIZE - offset_into_block);
+        self.inner()
+            .read_block_with(offset / BLOCK_SIZE, |block| {
+                f(&block[offset_into_block..][..n])
+            })
+            .await
+    }
+}
+
+impl<const BLOCK_SIZE: usize, T: WritableBlockIO<BLOCK_SIZE>> WritableBytesIO
+    for BytesIOAdapter<T, BLOCK_SIZE>
+{
+    async fn write(&self, offset: usize, buf: &[u8]) {
+        if buf.is_empty() {
+            return;
+        }
+        let offset_into_first_block = offset % BLOCK_SIZE;
+        let left_partial_chunk_len = if offset_into_first_block == 0 {
+            0
+        } else {
+            (BLOCK_SIZE - offset_into_first_block).min(buf.len())
+        };
+        let (left_partial_chunk, rest) = buf.split_at(left_partial_chunk_len);
+        if !left_partial_chunk.is_empty() {
+            self.write_partial_block(
+                offset / BLOCK_SIZE,
+                offset_into_first_block,
+                left_partial_chunk,
+            )
+            .await;
+        }
+        let block_id_of_first_full_chunk = (offset + left_partial_chunk_len) / BLOCK_SIZE;
+        let (mid_chunks, right_partial_chunk) = rest.as_chunks::<BLOCK_SIZE>();
+        if !mid_chunks.is_empty() {
+            self.inner()
+                .write_blocks(block_id_of_first_full_chunk, mid_chunks)
+                .await;
+        }
+        if !right_partial_chunk.is_empty() {
+            let block_id = block_id_of_first_full_chunk + mid_chunks.len();
+            self.write_partial_block(block_id, 0, right_partial_chunk)
+                .await;
+        }
+    }
+
+    async fn flush(&self) {
+        self.inner().flush().await;
+    }
+}
+
 #[derive(Debug)]
-pub struct CachedBlockIO<T, const BLOCK_SIZE: usize> {
+pub struct CachedBlockIO<T, const BLOCK_SIZE: usize, C = SoftwareCopyEngine> {
     inner: T,
-    lru: RefCell<LruCache<BlockId, [u8; BLOCK_SIZE]>>,
+    lru: RefCell<LruCache<BlockId, Rc<[u8; BLOCK_SIZE]>>>,
+    copy_engine: C,
+    stats: Cell<CacheStats>,
+}
+
+/// Counts of block reads served by a [`CachedBlockIO`], as returned by
+/// [`CachedBlockIO::stats`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of reads served from the cache.
+    pub hits: u64,
+    /// Number of reads which went to the underlying device.
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// The fraction of reads served from the cache, or `None` if there have been none.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total != 0).then_some(self.hits as f64 / total as f64)
+    }
 }
 
 impl<T, const BLOCK_SIZE: usize> CachedBlockIO<T, BLOCK_SIZE> {
     pub fn new(inner: T, cache_size_in_blocks: usize) -> Self {
+        Self::with_copy_engine(inner, cache_size_in_blocks, SoftwareCopyEngine)
+    }
+}
+
+impl<T, const BLOCK_SIZE: usize, C> CachedBlockIO<T, BLOCK_SIZE, C> {
+    /// Like [`CachedBlockIO::new`], but copies blocks out of the cache using `copy_engine`.
+    pub fn with_copy_engine(inner: T, cache_size_in_blocks: usize, copy_engine: C) -> Self {
         Self {
             inner,
             lru: RefCell::new(LruCache::new(
                 NonZeroUsize::new(cache_size_in_blocks).unwrap(),
             )),
+            copy_engine,
+            stats: Cell::new(CacheStats::default()),
         }
     }
 
+    pub fn stats(&self) -> CacheStats {
+        self.stats.get()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.set(CacheStats::default())
+    }
+
     pub fn inner(&self) -> &T {
         &self.inner
     }
@@ -110,18 +222,237 @@ impl<T, const BLOCK_SIZE: usize> CachedBlockIO<T, BLOCK_SIZE> {
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    pub fn copy_engine(&self) -> &C {
+        &self.copy_engine
+    }
+}
+
+impl<T: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize, C> CachedBlockIO<T, BLOCK_SIZE, C> {
+    // Cached blocks are reference counted, so that they can be copied out without holding a
+    // core::cell::RefMut across an await, and without being freed if evicted in the meantime.
+    async fn get_block(&self, block_id: usize) -> Rc<[u8; BLOCK_SIZE]> {
+        let mut stats = self.stats.get();
+        if let Some(block) = self.lru.borrow_mut().get(&block_id) {
+            stats.hits += 1;
+            self.stats.set(stats);
+            return block.clone();
+        }
+        stats.misses += 1;
+        self.stats.set(stats);
+        let mut block = Rc::new([0; BLOCK_SIZE]);
+        self.inner()
+            .read_block(block_id, Rc::get_mut(&mut block).unwrap())
+            .await;
+        // A write which completed while this read was in flight has already cached a newer
+        // version of the block.
+        let mut lru = self.lru.borrow_mut();
+        match lru.get(&block_id) {
+            Some(newer) => newer.clone(),
+            None => {
+                let _ = lru.put(block_id, block.clone());
+                block
+            }
+        }
+    }
 }
 
-impl<T: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> BlockIO<BLOCK_SIZE>
-    for CachedBlockIO<T, BLOCK_SIZE>
+impl<T: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize, C: CopyEngine> BlockIO<BLOCK_SIZE>
+    for CachedBlockIO<T, BLOCK_SIZE, C>
 {
     async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
-        // NOTE: odd control flow to avoid holding core::cell::RefMut across await
-        if let Some(block) = self.lru.borrow_mut().get(&block_id) {
-            *buf = *block;
-            return;
+        let block = self.get_block(block_id).await;
+        self.copy_engine.copy(buf, &*block).await
+    }
+}
+
+impl<T: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize, C: CopyEngine> LendingBlockIO<BLOCK_SIZE>
+    for CachedBlockIO<T, BLOCK_SIZE, C>
+{
+    async fn read_block_with<F: FnOnce(&[u8; BLOCK_SIZE]) -> R, R>(
+        &self,
+        block_id: usize,
+        f: F,
+    ) -> R {
+        f(&*self.get_block(block_id).await)
+    }
+}
+
+/// Writes through to the underlying device.
+impl<T: WritableBlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize, C: CopyEngine>
+    WritableBlockIO<BLOCK_SIZE> for CachedBlockIO<T, BLOCK_SIZE, C>
+{
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        self.inner().write_block(block_id, buf).await;
+        let _ = self.lru.borrow_mut().put(block_id, Rc::new(*buf));
+    }
+
+    async fn flush(&self) {
+        self.inner().flush().await;
+    }
+}
+
+/// Holds written blocks in the cache until they are evicted or flushed, so that repeated writes to
+/// the same block, such as those made to filesystem metadata, reach the underlying device once.
+#[derive(Debug)]
+pub struct WriteBackBlockIO<T, const BLOCK_SIZE: usize> {
+    inner: T,
+    lru: RefCell<LruCache<BlockId, CacheEntry<BLOCK_SIZE>>>,
+    // Blocks which were dirty when evicted, and which are being written back. Reads of these must
+    // not go to the underlying device until the writes have completed.
+    writing_back: RefCell<BTreeMap<BlockId, Rc<[u8; BLOCK_SIZE]>>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry<const BLOCK_SIZE: usize> {
+    block: Rc<[u8; BLOCK_SIZE]>,
+    dirty: bool,
+}
+
+impl<T, const BLOCK_SIZE: usize> WriteBackBlockIO<T, BLOCK_SIZE> {
+    pub fn new(inner: T, cache_size_in_blocks: usize) -> Self {
+        Self {
+            inner,
+            lru: RefCell::new(LruCache::new(
+                NonZeroUsize::new(cache_size_in_blocks).unwrap(),
+            )),
+            writing_back: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Discards any writes which have not been flushed.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The number of cached blocks which have been written but not yet written back.
+    pub fn num_dirty(&self) -> usize {
+        self.lru
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.dirty)
+            .count()
+    }
+}
+
+impl<T: WritableBlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> WriteBackBlockIO<T, BLOCK_SIZE> {
+    async fn get_block(&self, block_id: usize) -> Rc<[u8; BLOCK_SIZE]> {
+        if let Some(entry) = self.lru.borrow_mut().get(&block_id) {
+            return entry.block.clone();
+        }
+        if let Some(block) = self.writing_back.borrow().get(&block_id) {
+            return block.clone();
+        }
+        let mut block = Rc::new([0; BLOCK_SIZE]);
+        self.inner()
+            .read_block(block_id, Rc::get_mut(&mut block).unwrap())
+            .await;
+        // A write which completed while this read was in flight has already cached a newer
+        // version of the block.
+        let newer = self
+            .lru
+            .borrow_mut()
+            .get(&block_id)
+            .map(|entry| entry.block.clone());
+        match newer {
+            Some(newer) => newer,
+            None => {
+                self.insert(
+                    block_id,
+                    CacheEntry {
+                        block: block.clone(),
+                        dirty: false,
+                    },
+                )
+                .await;
+                block
+            }
+        }
+    }
+
+    async fn insert(&self, block_id: usize, entry: CacheEntry<BLOCK_SIZE>) {
+        let evicted = self.lru.borrow_mut().push(block_id, entry);
+        if let Some((evicted_block_id, evicted)) = evicted {
+            if evicted_block_id != block_id && evicted.dirty {
+                self.write_back(evicted_block_id, evicted.block).await;
+            }
+        }
+    }
+
+    async fn write_back(&self, block_id: usize, block: Rc<[u8; BLOCK_SIZE]>) {
+        self.writing_back
+            .borrow_mut()
+            .insert(block_id, block.clone());
+        self.inner().write_block(block_id, &block).await;
+        let mut writing_back = self.writing_back.borrow_mut();
+        if writing_back
+            .get(&block_id)
+            .is_some_and(|in_flight| Rc::ptr_eq(in_flight, &block))
+        {
+            writing_back.remove(&block_id);
+        }
+    }
+}
+
+impl<T: WritableBlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> BlockIO<BLOCK_SIZE>
+    for WriteBackBlockIO<T, BLOCK_SIZE>
+{
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        *buf = *self.get_block(block_id).await;
+    }
+}
+
+impl<T: WritableBlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> LendingBlockIO<BLOCK_SIZE>
+    for WriteBackBlockIO<T, BLOCK_SIZE>
+{
+    async fn read_block_with<F: FnOnce(&[u8; BLOCK_SIZE]) -> R, R>(
+        &self,
+        block_id: usize,
+        f: F,
+    ) -> R {
+        f(&*self.get_block(block_id).await)
+    }
+}
+
+impl<T: WritableBlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> WritableBlockIO<BLOCK_SIZE>
+    for WriteBackBlockIO<T, BLOCK_SIZE>
+{
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        self.insert(
+            block_id,
+            CacheEntry {
+                block: Rc::new(*buf),
+                dirty: true,
+            },
+        )
+        .await;
+    }
+
+    /// Writes back all dirty blocks, in order of block ID, and then flushes the underlying
+    /// device.
+    async fn flush(&self) {
+        let mut dirty = self
+            .lru
+            .borrow_mut()
+            .iter_mut()
+            .filter(|(_, entry)| entry.dirty)
+            .map(|(block_id, entry)| {
+                entry.dirty = false;
+                (*block_id, entry.block.clone())
+            })
+            .collect::<Vec<_>>();
+        dirty.sort_unstable_by_key(|(block_id, _)| *block_id);
+        for (block_id, block) in dirty {
+            self.write_back(block_id, block).await;
         }
-        self.inner().read_block(block_id, buf).await;
-        let _ = self.lru.borrow_mut().put(block_id, *buf);
+        self.inner().flush().await;
     }
 }
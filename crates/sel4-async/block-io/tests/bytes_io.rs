@@ -0,0 +1,167 @@
+#![feature(async_fn_in_trait)]
+
+use core::cell::{Cell, RefCell};
+use core::pin::pin;
+use core::task::Poll;
+
+use sel4_async_block_io::{
+    BlockIO, BytesIO, BytesIOAdapter, CacheStats, CachedBlockIO, LendingBytesIO, WritableBlockIO,
+    WritableBytesIO, WriteBackBlockIO,
+};
+use sel4_async_copy_engine::{CopyEngine, OffloadLargeCopies, SoftwareCopyEngine};
+use sel4_async_single_threaded_executor::run_until_stalled;
+
+const BLOCK_SIZE: usize = 16;
+
+struct MemoryBlockIO {
+    blocks: RefCell<Vec<[u8; BLOCK_SIZE]>>,
+    reads: Cell<usize>,
+}
+
+impl MemoryBlockIO {
+    fn new(num_blocks: usize) -> Self {
+        Self {
+            blocks: RefCell::new(
+                (0..num_blocks)
+                    .map(|i| [u8::try_from(i).unwrap(); BLOCK_SIZE])
+                    .collect(),
+            ),
+            reads: Cell::new(0),
+        }
+    }
+}
+
+impl BlockIO<BLOCK_SIZE> for MemoryBlockIO {
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.reads.set(self.reads.get() + 1);
+        *buf = self.blocks.borrow()[block_id];
+    }
+}
+
+impl WritableBlockIO<BLOCK_SIZE> for MemoryBlockIO {
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        self.blocks.borrow_mut()[block_id] = *buf;
+    }
+}
+
+fn block_on<T>(future: impl core::future::Future<Output = T>) -> T {
+    match run_until_stalled(pin!(future)) {
+        Poll::Ready(v) => v,
+        Poll::Pending => panic!("stalled"),
+    }
+}
+
+fn expected(num_blocks: usize) -> Vec<u8> {
+    (0..num_blocks)
+        .flat_map(|i| [u8::try_from(i).unwrap(); BLOCK_SIZE])
+        .collect()
+}
+
+fn expected_blocks(num_blocks: usize) -> Vec<[u8; BLOCK_SIZE]> {
+    (0..num_blocks)
+        .map(|i| [u8::try_from(i).unwrap(); BLOCK_SIZE])
+        .collect()
+}
+
+#[test]
+fn write_across_block_boundaries() {
+    let io = BytesIOAdapter::<_, BLOCK_SIZE>::new(MemoryBlockIO::new(6));
+    let mut expected = expected(6);
+    for (offset, len) in [(3, 5), (10, 40), (32, 16), (47, 2), (0, 96)] {
+        let data = (0..len)
+            .map(|i| 0x80 | u8::try_from(i).unwrap())
+            .collect::<Vec<_>>();
+        block_on(io.write(offset, &data));
+        expected[offset..][..len].copy_from_slice(&data);
+        let mut actual = vec![0; expected.len()];
+        block_on(io.read(0, &mut actual));
+        assert_eq!(actual, expected, "offset={offset} len={len}");
+    }
+}
+
+#[test]
+fn cache_writes_through() {
+    let io = CachedBlockIO::new(MemoryBlockIO::new(4), 2);
+    let mut buf = [0; BLOCK_SIZE];
+    block_on(io.read_block(1, &mut buf));
+    block_on(io.write_block(1, &[0xff; BLOCK_SIZE]));
+    block_on(io.read_block(1, &mut buf));
+    assert_eq!(buf, [0xff; BLOCK_SIZE]);
+    assert_eq!(io.inner().blocks.borrow()[1], [0xff; BLOCK_SIZE]);
+    assert_eq!(io.inner().reads.get(), 1);
+    assert_eq!(io.stats(), CacheStats { hits: 1, misses: 1 });
+    assert_eq!(io.stats().hit_rate(), Some(0.5));
+}
+
+#[test]
+fn cache_writes_back() {
+    let io = BytesIOAdapter::<_, BLOCK_SIZE>::new(WriteBackBlockIO::new(MemoryBlockIO::new(4), 2));
+    block_on(io.write(4, &[0xff; 4]));
+    block_on(io.write(20, &[0xfe; 4]));
+    assert_eq!(io.inner().num_dirty(), 2);
+    assert_eq!(io.inner().inner().blocks.borrow()[..], expected_blocks(4));
+
+    // Evicting block 0 writes it back
+    block_on(io.write(36, &[0xfd; 4]));
+    assert_eq!(io.inner().inner().blocks.borrow()[0][4..8], [0xff; 4]);
+    assert_eq!(io.inner().inner().blocks.borrow()[1], [1; BLOCK_SIZE]);
+
+    block_on(io.flush());
+    assert_eq!(io.inner().num_dirty(), 0);
+    let mut actual = vec![0; 4 * BLOCK_SIZE];
+    block_on(io.read(0, &mut actual));
+    assert_eq!(io.inner().inner().blocks.borrow().concat(), actual,);
+    assert_eq!(actual[20..24], [0xfe; 4]);
+    assert_eq!(actual[36..40], [0xfd; 4]);
+}
+
+#[test]
+fn lend_cached_blocks() {
+    let io = BytesIOAdapter::<_, BLOCK_SIZE>::new(CachedBlockIO::new(MemoryBlockIO::new(4), 4));
+    let (offset, len) = (5, 50);
+    for _ in 0..2 {
+        let mut actual = vec![];
+        while actual.len() < len {
+            let pos = offset + actual.len();
+            block_on(io.read_with(pos, len - actual.len(), |data| {
+                // Each slice is lent from a single cached block
+                assert!(!data.is_empty());
+                assert!(pos % BLOCK_SIZE + data.len() <= BLOCK_SIZE);
+                actual.extend_from_slice(data);
+            }));
+        }
+        assert_eq!(actual, expected(4)[offset..][..len]);
+    }
+    assert_eq!(io.inner().inner().reads.get(), 4);
+}
+
+#[derive(Default)]
+struct CountingCopyEngine {
+    copies: Cell<usize>,
+}
+
+impl CopyEngine for CountingCopyEngine {
+    async unsafe fn copy_raw(&self, dst: *mut u8, src: *const u8, len: usize) {
+        self.copies.set(self.copies.get() + 1);
+        SoftwareCopyEngine.copy_raw(dst, src, len).await
+    }
+}
+
+#[test]
+fn offload_large_copies_out_of_cache() {
+    let io = CachedBlockIO::with_copy_engine(
+        MemoryBlockIO::new(4),
+        4,
+        OffloadLargeCopies::new(CountingCopyEngine::default(), BLOCK_SIZE),
+    );
+    let mut buf = [0; BLOCK_SIZE];
+    for block_id in [2, 3, 2] {
+        block_on(io.read_block(block_id, &mut buf));
+        assert_eq!(buf, [u8::try_from(block_id).unwrap(); BLOCK_SIZE]);
+    }
+    assert_eq!(io.copy_engine().inner().copies.get(), 3);
+
+    let mut small = [0; BLOCK_SIZE - 1];
+    block_on(io.copy_engine().copy(&mut small, &buf[1..]));
+    assert_eq!(io.copy_engine().inner().copies.get(), 3);
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+
+use clap::{App, Arg};
+
+use sel4_async_block_io_verity::build_hash_tree;
+
+// Writes the hash tree for an image, zero-padded to a whole number of blocks, and prints its root
+// hash in hexadecimal.
+fn main() -> Result<(), io::Error> {
+    let matches = App::new("")
+        .arg(
+            Arg::new("image")
+                .short('i')
+                .value_name("IMAGE")
+                .required(true),
+        )
+        .arg(
+            Arg::new("out_hash_tree")
+                .short('o')
+                .value_name("OUT_HASH_TREE")
+                .required(true),
+        )
+        .arg(
+            Arg::new("block_size")
+                .long("block-size")
+                .short('b')
+                .value_name("BLOCK_SIZE")
+                .value_parser(["512", "1024", "2048", "4096"])
+                .default_value("512"),
+        )
+        .get_matches();
+
+    let image_path = matches.get_one::<String>("image").unwrap().to_owned();
+    let out_hash_tree_path = matches
+        .get_one::<String>("out_hash_tree")
+        .unwrap()
+        .to_owned();
+    let block_size = matches
+        .get_one::<String>("block_size")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+
+    let image = fs::read(image_path)?;
+
+    let (hash_tree, root_hash) = match block_size {
+        512 => with_block_size::<512>(&image),
+        1024 => with_block_size::<1024>(&image),
+        2048 => with_block_size::<2048>(&image),
+        4096 => with_block_size::<4096>(&image),
+        _ => unreachable!(),
+    };
+
+    fs::write(out_hash_tree_path, hash_tree)?;
+    println!("{}", hex::encode(root_hash));
+    Ok(())
+}
+
+fn with_block_size<const BLOCK_SIZE: usize>(image: &[u8]) -> (Vec<u8>, [u8; 32]) {
+    let blocks = image
+        .chunks(BLOCK_SIZE)
+        .map(|chunk| {
+            let mut block = [0; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            block
+        })
+        .collect::<Vec<_>>();
+    let (hash_tree, root_hash) = build_hash_tree(&blocks);
+    (hash_tree.concat(), root_hash)
+}
@@ -0,0 +1,214 @@
+//! Integrity checking of read-only block devices against a Merkle tree, in the style of Linux's
+//! dm-verity.
+//!
+//! The hash tree is stored on a separate [`BlockIO`]. Each hash block holds up to
+//! [`HashTreeGeometry::FANOUT`] SHA-256 hashes, zero-padded. Level 0 holds the hashes of the data
+//! blocks, each subsequent level holds the hashes of the blocks of the level below it, and the top
+//! level consists of a single block, whose hash is the root hash. Levels are stored top level
+//! first.
+//!
+//! The root hash is the only trusted input, and must be provisioned through a trusted channel,
+//! such as the system's static configuration. [`build_hash_tree`] (with the `"alloc"` feature,
+//! which is enabled by default) and the accompanying CLI construct the tree for an image.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+#![feature(int_roundings)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt;
+
+use sha2::{Digest, Sha256};
+
+use sel4_async_block_io::BlockIO;
+
+pub const HASH_SIZE: usize = 32;
+
+pub type Hash = [u8; HASH_SIZE];
+
+pub fn hash_block<const BLOCK_SIZE: usize>(block: &[u8; BLOCK_SIZE]) -> Hash {
+    Sha256::digest(block).into()
+}
+
+/// The shape of the hash tree for a data device with `num_data_blocks` blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HashTreeGeometry<const BLOCK_SIZE: usize> {
+    num_data_blocks: usize,
+}
+
+impl<const BLOCK_SIZE: usize> HashTreeGeometry<BLOCK_SIZE> {
+    pub const FANOUT: usize = BLOCK_SIZE / HASH_SIZE;
+
+    pub fn new(num_data_blocks: usize) -> Self {
+        assert!(Self::FANOUT >= 2);
+        assert_ne!(num_data_blocks, 0);
+        Self { num_data_blocks }
+    }
+
+    pub fn num_data_blocks(&self) -> usize {
+        self.num_data_blocks
+    }
+
+    pub fn num_levels(&self) -> usize {
+        let mut level = 0;
+        while self.level_num_blocks(level) > 1 {
+            level += 1;
+        }
+        level + 1
+    }
+
+    /// Level 0 is the lowest level.
+    pub fn level_num_blocks(&self, level: usize) -> usize {
+        let mut n = self.num_data_blocks;
+        for _ in 0..=level {
+            n = n.div_ceil(Self::FANOUT);
+        }
+        n
+    }
+
+    /// The block ID of the first block of `level` within the hash tree.
+    pub fn level_start(&self, level: usize) -> usize {
+        (level + 1..self.num_levels())
+            .map(|above| self.level_num_blocks(above))
+            .sum()
+    }
+
+    pub fn num_hash_blocks(&self) -> usize {
+        self.level_start(0) + self.level_num_blocks(0)
+    }
+}
+
+/// A data block's contents do not match the hash tree, or the hash tree does not match the root
+/// hash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VerificationError {
+    pub block_id: usize,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "verification of block {} failed", self.block_id)
+    }
+}
+
+pub struct VerityBlockIO<T, U, const BLOCK_SIZE: usize> {
+    data: T,
+    hash_tree: U,
+    geometry: HashTreeGeometry<BLOCK_SIZE>,
+    root_hash: Hash,
+}
+
+impl<T, U, const BLOCK_SIZE: usize> VerityBlockIO<T, U, BLOCK_SIZE> {
+    pub fn new(data: T, hash_tree: U, num_data_blocks: usize, root_hash: Hash) -> Self {
+        Self {
+            data,
+            hash_tree,
+            geometry: HashTreeGeometry::new(num_data_blocks),
+            root_hash,
+        }
+    }
+
+    pub fn geometry(&self) -> &HashTreeGeometry<BLOCK_SIZE> {
+        &self.geometry
+    }
+
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    pub fn hash_tree(&self) -> &U {
+        &self.hash_tree
+    }
+}
+
+impl<T: BlockIO<BLOCK_SIZE>, U: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize>
+    VerityBlockIO<T, U, BLOCK_SIZE>
+{
+    /// Reads a data block, and checks it and the path from it to the root of the hash tree.
+    /// `buf` is zeroed on failure.
+    pub async fn try_read_block(
+        &self,
+        block_id: usize,
+        buf: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), VerificationError> {
+        assert!(block_id < self.geometry.num_data_blocks());
+        self.data.read_block(block_id, buf).await;
+        let r = self.verify(block_id, hash_block(buf)).await;
+        if r.is_err() {
+            buf.fill(0);
+        }
+        r
+    }
+
+    async fn verify(&self, block_id: usize, data_hash: Hash) -> Result<(), VerificationError> {
+        let err = VerificationError { block_id };
+        let fanout = HashTreeGeometry::<BLOCK_SIZE>::FANOUT;
+        let mut expected = data_hash;
+        let mut index = block_id;
+        let mut hash_block_buf = [0; BLOCK_SIZE];
+        for level in 0..self.geometry.num_levels() {
+            self.hash_tree
+                .read_block(
+                    self.geometry.level_start(level) + index / fanout,
+                    &mut hash_block_buf,
+                )
+                .await;
+            if hash_block_buf[(index % fanout) * HASH_SIZE..][..HASH_SIZE] != expected {
+                return Err(err);
+            }
+            expected = hash_block(&hash_block_buf);
+            index /= fanout;
+        }
+        if expected != self.root_hash {
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+/// Fails closed: panics if verification fails. Use [`VerityBlockIO::try_read_block`] to handle
+/// failures.
+impl<T: BlockIO<BLOCK_SIZE>, U: BlockIO<BLOCK_SIZE>, const BLOCK_SIZE: usize> BlockIO<BLOCK_SIZE>
+    for VerityBlockIO<T, U, BLOCK_SIZE>
+{
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        if let Err(err) = self.try_read_block(block_id, buf).await {
+            panic!("{err}");
+        }
+    }
+}
+
+/// Returns the hash tree, laid out as [`VerityBlockIO`] expects, and root hash for `data_blocks`.
+#[cfg(feature = "alloc")]
+pub fn build_hash_tree<const BLOCK_SIZE: usize>(
+    data_blocks: &[[u8; BLOCK_SIZE]],
+) -> (alloc::vec::Vec<[u8; BLOCK_SIZE]>, Hash) {
+    use alloc::vec::Vec;
+
+    let geometry = HashTreeGeometry::<BLOCK_SIZE>::new(data_blocks.len());
+    let fanout = HashTreeGeometry::<BLOCK_SIZE>::FANOUT;
+    let hash_level = |below: &[[u8; BLOCK_SIZE]]| {
+        below
+            .chunks(fanout)
+            .map(|children| {
+                let mut block = [0; BLOCK_SIZE];
+                for (i, child) in children.iter().enumerate() {
+                    block[i * HASH_SIZE..][..HASH_SIZE].copy_from_slice(&hash_block(child));
+                }
+                block
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut levels = Vec::<Vec<[u8; BLOCK_SIZE]>>::new();
+    for level in 0..geometry.num_levels() {
+        let blocks = hash_level(levels.last().map(Vec::as_slice).unwrap_or(data_blocks));
+        assert_eq!(blocks.len(), geometry.level_num_blocks(level));
+        levels.push(blocks);
+    }
+    let root_hash = hash_block(&levels.last().unwrap()[0]);
+    let tree = levels.into_iter().rev().flatten().collect::<Vec<_>>();
+    assert_eq!(tree.len(), geometry.num_hash_blocks());
+    (tree, root_hash)
+}
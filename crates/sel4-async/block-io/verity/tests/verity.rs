@@ -0,0 +1,39 @@
+#![feature(async_fn_in_trait)]
+
+use sel4_async_block_io_verity::{build_hash_tree, VerificationError, VerityBlockIO};
+use sel4_async_test_utils::{block_on, MemoryBlockIO};
+
+const BLOCK_SIZE: usize = 64;
+
+#[test]
+fn detects_tampering() {
+    // With a fanout of 2, 5 data blocks give a tree with 3 + 2 + 1 blocks.
+    let data = (0..5u8).map(|i| [i; BLOCK_SIZE]).collect::<Vec<_>>();
+    let (tree, root_hash) = build_hash_tree(&data);
+    assert_eq!(tree.len(), 6);
+
+    let data = MemoryBlockIO::from_blocks(data);
+    let tree = MemoryBlockIO::from_blocks(tree);
+    let io = VerityBlockIO::new(data.clone(), tree.clone(), 5, root_hash);
+
+    block_on(async {
+        let mut buf = [0; BLOCK_SIZE];
+        for block_id in 0..5 {
+            io.try_read_block(block_id, &mut buf).await.unwrap();
+            assert_eq!(buf, [u8::try_from(block_id).unwrap(); BLOCK_SIZE]);
+        }
+
+        data.blocks()[3][0] ^= 1;
+        assert_eq!(
+            io.try_read_block(3, &mut buf).await,
+            Err(VerificationError { block_id: 3 })
+        );
+        assert_eq!(buf, [0; BLOCK_SIZE]);
+        io.try_read_block(2, &mut buf).await.unwrap();
+        data.blocks()[3][0] ^= 1;
+
+        // Corrupt the top-level block, which every read depends on.
+        tree.blocks()[0][0] ^= 1;
+        assert!(io.try_read_block(0, &mut buf).await.is_err());
+    });
+}
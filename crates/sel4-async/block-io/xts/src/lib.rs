@@ -0,0 +1,131 @@
+//! Transparent encryption of block devices with XTS-AES ([IEEE
+//! 1619](https://standards.ieee.org/ieee/1619/4205/)).
+//!
+//! [`XtsBlockIO`] wraps a [`BlockIO`] and presents a view of its contents in which each block is
+//! decrypted on read and encrypted on write. Each block is a data unit whose sequence number is its
+//! block ID, so identical plaintext blocks at different positions produce different ciphertext.
+//!
+//! Note that XTS provides confidentiality but not integrity.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+use aes::cipher::consts::U16;
+use aes::cipher::{Block, BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+
+use sel4_async_block_io::{BlockIO, WritableBlockIO};
+
+pub use aes::{Aes128, Aes256};
+
+const CIPHER_BLOCK_SIZE: usize = 16;
+
+pub struct XtsBlockIO<T, C, const BLOCK_SIZE: usize> {
+    inner: T,
+    data_cipher: C,
+    tweak_cipher: C,
+}
+
+impl<T, C: KeyInit, const BLOCK_SIZE: usize> XtsBlockIO<T, C, BLOCK_SIZE> {
+    /// `key` is the concatenation of the data key and the tweak key, as in IEEE 1619. For example,
+    /// it is 64 bytes long for XTS-AES-256.
+    pub fn new(inner: T, key: &[u8]) -> Self {
+        let (data_key, tweak_key) = key.split_at(key.len() / 2);
+        Self::new_with_ciphers(
+            inner,
+            C::new_from_slice(data_key).unwrap(),
+            C::new_from_slice(tweak_key).unwrap(),
+        )
+    }
+}
+
+impl<T, C, const BLOCK_SIZE: usize> XtsBlockIO<T, C, BLOCK_SIZE> {
+    pub fn new_with_ciphers(inner: T, data_cipher: C, tweak_cipher: C) -> Self {
+        assert_eq!(BLOCK_SIZE % CIPHER_BLOCK_SIZE, 0);
+        Self {
+            inner,
+            data_cipher,
+            tweak_cipher,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, C, const BLOCK_SIZE: usize> XtsBlockIO<T, C, BLOCK_SIZE>
+where
+    C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>,
+{
+    pub fn encrypt_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.apply(block_id, buf, |block| self.data_cipher.encrypt_block(block))
+    }
+
+    pub fn decrypt_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.apply(block_id, buf, |block| self.data_cipher.decrypt_block(block))
+    }
+
+    fn apply(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE], f: impl Fn(&mut Block<C>)) {
+        let mut tweak = {
+            let mut block = Block::<C>::from(u128::try_from(block_id).unwrap().to_le_bytes());
+            self.tweak_cipher.encrypt_block(&mut block);
+            u128::from_le_bytes(block.into())
+        };
+        for chunk in buf.chunks_exact_mut(CIPHER_BLOCK_SIZE) {
+            let chunk: &mut [u8; CIPHER_BLOCK_SIZE] = chunk.try_into().unwrap();
+            let mut block = Block::<C>::from((u128::from_le_bytes(*chunk) ^ tweak).to_le_bytes());
+            f(&mut block);
+            *chunk = (u128::from_le_bytes(block.into()) ^ tweak).to_le_bytes();
+            tweak = mul_alpha(tweak);
+        }
+    }
+}
+
+// Multiplication by the primitive element of GF(2^128), with the little-endian representation
+// used by XTS.
+fn mul_alpha(x: u128) -> u128 {
+    let carry = x >> 127;
+    (x << 1) ^ (carry * 0x87)
+}
+
+impl<T, C, const BLOCK_SIZE: usize> BlockIO<BLOCK_SIZE> for XtsBlockIO<T, C, BLOCK_SIZE>
+where
+    T: BlockIO<BLOCK_SIZE>,
+    C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>,
+{
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.inner.read_block(block_id, buf).await;
+        self.decrypt_block(block_id, buf);
+    }
+
+    async fn read_blocks(&self, start_block_id: usize, bufs: &mut [[u8; BLOCK_SIZE]]) {
+        self.inner.read_blocks(start_block_id, bufs).await;
+        for (i, buf) in bufs.iter_mut().enumerate() {
+            self.decrypt_block(start_block_id + i, buf);
+        }
+    }
+}
+
+impl<T, C, const BLOCK_SIZE: usize> WritableBlockIO<BLOCK_SIZE> for XtsBlockIO<T, C, BLOCK_SIZE>
+where
+    T: WritableBlockIO<BLOCK_SIZE>,
+    C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>,
+{
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        let mut encrypted = *buf;
+        self.encrypt_block(block_id, &mut encrypted);
+        self.inner.write_block(block_id, &encrypted).await;
+    }
+
+    async fn flush(&self) {
+        self.inner.flush().await;
+    }
+}
@@ -0,0 +1,123 @@
+//! An interface for offloading memory copies from the CPU.
+//!
+//! Some platforms have DMA engines which can copy between regions of memory while the CPU does
+//! other work, signalling completion with an interrupt. [`CopyEngine`] abstracts over such engines,
+//! so that code which moves data between buffers, such as a block cache or the bounce buffers of a
+//! shared ring buffer client, can offload large copies where possible. [`SoftwareCopyEngine`] is a
+//! fallback which just copies with the CPU.
+//!
+//! A platform driver typically completes each copy from its interrupt handler, waking the task
+//! which is awaiting it (see `sel4-async-request-statuses`), uses [`DmaWindow`] to translate
+//! addresses of buffers which the engine can reach, and falls back to [`SoftwareCopyEngine`] for
+//! others.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+use core::ops::Range;
+
+pub trait CopyEngine {
+    /// Copies `len` bytes from `src` to `dst`, completing when the returned future resolves.
+    ///
+    /// Implementations which offload copies must not let a copy outlive the future which performs
+    /// it. If the future is dropped before the copy completes, the implementation must wait for,
+    /// or abort, the copy before the future's destructor returns.
+    ///
+    /// # Safety
+    ///
+    /// `src` must be valid for reads and `dst` must be valid for writes of `len` bytes for as long
+    /// as the returned future exists, and the two ranges must not overlap.
+    async unsafe fn copy_raw(&self, dst: *mut u8, src: *const u8, len: usize);
+
+    /// Copies `src` into `dst`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src` and `dst` have different lengths.
+    async fn copy(&self, dst: &mut [u8], src: &[u8]) {
+        assert_eq!(
+            dst.len(),
+            src.len(),
+            "destination and source slices have different lengths"
+        );
+        unsafe { self.copy_raw(dst.as_mut_ptr(), src.as_ptr(), src.len()) }.await
+    }
+}
+
+impl<T: CopyEngine + ?Sized> CopyEngine for &T {
+    async unsafe fn copy_raw(&self, dst: *mut u8, src: *const u8, len: usize) {
+        (**self).copy_raw(dst, src, len).await
+    }
+}
+
+/// A [`CopyEngine`] which copies with the CPU, completing immediately.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SoftwareCopyEngine;
+
+impl CopyEngine for SoftwareCopyEngine {
+    async unsafe fn copy_raw(&self, dst: *mut u8, src: *const u8, len: usize) {
+        dst.copy_from_nonoverlapping(src, len)
+    }
+}
+
+/// A [`CopyEngine`] which hands copies of at least `threshold` bytes to `inner`, and performs
+/// smaller ones with the CPU, for which the overhead of offloading would outweigh its benefit.
+#[derive(Copy, Clone, Debug)]
+pub struct OffloadLargeCopies<T> {
+    inner: T,
+    threshold: usize,
+}
+
+impl<T> OffloadLargeCopies<T> {
+    pub const fn new(inner: T, threshold: usize) -> Self {
+        Self { inner, threshold }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+}
+
+impl<T: CopyEngine> CopyEngine for OffloadLargeCopies<T> {
+    async unsafe fn copy_raw(&self, dst: *mut u8, src: *const u8, len: usize) {
+        if len >= self.threshold {
+            self.inner.copy_raw(dst, src, len).await
+        } else {
+            SoftwareCopyEngine.copy_raw(dst, src, len).await
+        }
+    }
+}
+
+/// A range of virtual memory which is mapped contiguously in the address space of a DMA engine,
+/// such as a memory region whose physical address is known.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmaWindow {
+    vaddr: usize,
+    io_addr: usize,
+    size: usize,
+}
+
+impl DmaWindow {
+    pub const fn new(vaddr: usize, io_addr: usize, size: usize) -> Self {
+        Self {
+            vaddr,
+            io_addr,
+            size,
+        }
+    }
+
+    pub fn vaddr_range(&self) -> Range<usize> {
+        self.vaddr..self.vaddr + self.size
+    }
+
+    /// Returns the address at which the DMA engine sees `ptr`, if all `len` bytes starting at
+    /// `ptr` lie within this window.
+    pub fn translate(&self, ptr: *const u8, len: usize) -> Option<usize> {
+        let offset = (ptr as usize).checked_sub(self.vaddr)?;
+        (offset.checked_add(len)? <= self.size).then_some(self.io_addr + offset)
+    }
+}
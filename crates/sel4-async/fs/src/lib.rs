@@ -0,0 +1,101 @@
+//! An asynchronous filesystem interface, so that components which serve or consume files can be
+//! written independently of where those files come from, whether an archive on a block device
+//! (`sel4-async-block-io-cpiofs`), a directory shared by the host (`sel4-async-9p`), a FAT32
+//! volume (`sel4-async-block-io-fat`), or the heap (`sel4-async-tmpfs`). [`FileSystem`] covers reading, and [`WritableFileSystem`]
+//! covers modification, for those filesystems which support it.
+//!
+//! Paths are `/`-separated and relative to the root of the filesystem. Leading and trailing
+//! separators are ignored.
+//!
+//! A [`MountTable`] combines several filesystems, of possibly different types, into one.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+mod mount_table;
+
+pub use mount_table::{BackendError, MountTable, MountTableError, MountedFile};
+
+pub trait FileSystem {
+    type Error: fmt::Debug + fmt::Display;
+
+    /// A handle to an open regular file.
+    type File;
+
+    async fn open(&self, path: &str) -> Result<Self::File, Self::Error>;
+
+    /// Reads up to `buf.len()` bytes from `file` at `offset`, returning the number of bytes read,
+    /// which is zero only at or beyond the end of the file.
+    async fn read(
+        &self,
+        file: &Self::File,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::Error>;
+
+    /// Releases any resources associated with `file`.
+    ///
+    /// The default implementation does nothing.
+    async fn close(&self, file: Self::File) -> Result<(), Self::Error> {
+        drop(file);
+        Ok(())
+    }
+
+    async fn stat(&self, path: &str) -> Result<Metadata, Self::Error>;
+
+    /// Returns the entries of the directory at `path`, excluding `.` and `..`.
+    async fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, Self::Error>;
+}
+
+pub trait WritableFileSystem: FileSystem {
+    /// Creates an empty regular file at `path`, or truncates the one already there, and opens it.
+    async fn create(&self, path: &str) -> Result<Self::File, Self::Error>;
+
+    /// Writes all of `buf` to `file` at `offset`, extending the file if necessary. Any gap between
+    /// the previous end of the file and `offset` reads as zeros.
+    async fn write(&self, file: &Self::File, offset: usize, buf: &[u8]) -> Result<(), Self::Error>;
+
+    /// Truncates or zero-extends `file` to `len` bytes.
+    async fn set_len(&self, file: &Self::File, len: usize) -> Result<(), Self::Error>;
+
+    async fn create_dir(&self, path: &str) -> Result<(), Self::Error>;
+
+    /// Removes the regular file or empty directory at `path`.
+    async fn remove(&self, path: &str) -> Result<(), Self::Error>;
+
+    /// Waits until all completed modifications have reached stable storage.
+    async fn flush(&self) -> Result<(), Self::Error>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EntryType {
+    RegularFile,
+    Directory,
+    SymbolicLink,
+    Other,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub ty: EntryType,
+    pub size: usize,
+    /// The time of last modification, in seconds since the Unix epoch, if known.
+    pub modified: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub ty: EntryType,
+}
+
+/// Splits `path` into its components, ignoring empty ones.
+pub fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|component| !component.is_empty())
+}
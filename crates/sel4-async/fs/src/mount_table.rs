@@ -0,0 +1,278 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+
+use crate::{components, DirEntry, EntryType, FileSystem, Metadata};
+
+/// Routes paths to filesystems by prefix, so that several filesystems can be presented as one.
+///
+/// A path is handled by the filesystem mounted at its longest prefix, with that prefix removed.
+/// Prefixes are matched component-wise, so a filesystem mounted at `www` handles `www/index.html`
+/// but not `wwwroot`. Mount points and their ancestors appear as directories, even where no
+/// filesystem is mounted at a prefix of them.
+#[derive(Default)]
+pub struct MountTable {
+    // Normalized prefixes, where `""` is the root.
+    mounts: Vec<(String, Rc<dyn DynFileSystem>)>,
+}
+
+/// A handle to a file opened through a [`MountTable`].
+pub struct MountedFile {
+    fs: Rc<dyn DynFileSystem>,
+    inner: Box<dyn Any>,
+}
+
+impl MountTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mounts `fs` at `prefix`, which must not already have a filesystem mounted at it.
+    pub fn mount<T>(&mut self, prefix: &str, fs: T) -> Result<(), MountTableError>
+    where
+        T: FileSystem + 'static,
+        T::File: 'static,
+        T::Error: 'static,
+    {
+        let prefix = normalize(prefix);
+        if self.mounts.iter().any(|(mounted, _)| *mounted == prefix) {
+            return Err(MountTableError::AlreadyMounted);
+        }
+        self.mounts.push((prefix, Rc::new(fs)));
+        Ok(())
+    }
+
+    /// Removes the filesystem mounted at `prefix`. Files which were opened through it remain
+    /// usable until closed.
+    pub fn unmount(&mut self, prefix: &str) -> Result<(), MountTableError> {
+        let prefix = normalize(prefix);
+        let i = self
+            .mounts
+            .iter()
+            .position(|(mounted, _)| *mounted == prefix)
+            .ok_or(MountTableError::NotMounted)?;
+        self.mounts.remove(i);
+        Ok(())
+    }
+
+    // Returns the filesystem with the longest prefix of `path`, which must be normalized, and the
+    // remainder of `path`.
+    fn route<'a>(&self, path: &'a str) -> Option<(&Rc<dyn DynFileSystem>, &'a str)> {
+        self.mounts
+            .iter()
+            .filter_map(|(prefix, fs)| Some((prefix, fs, strip_prefix(path, prefix)?)))
+            .max_by_key(|(prefix, _, _)| prefix.len())
+            .map(|(_, fs, rest)| (fs, rest))
+    }
+
+    // The names of the mount points, or of their ancestors, which are immediate children of
+    // `path`, which must be normalized.
+    fn mount_point_children(&self, path: &str) -> Vec<&str> {
+        let mut children = self
+            .mounts
+            .iter()
+            .filter_map(|(prefix, _)| components(strip_prefix(prefix, path)?).next())
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        children.dedup();
+        children
+    }
+}
+
+impl FileSystem for MountTable {
+    type Error = MountTableError;
+
+    type File = MountedFile;
+
+    async fn open(&self, path: &str) -> Result<Self::File, Self::Error> {
+        let path = normalize(path);
+        let (fs, rest) = self.route(&path).ok_or(MountTableError::NotMounted)?;
+        Ok(MountedFile {
+            fs: fs.clone(),
+            inner: fs.open(rest).await?,
+        })
+    }
+
+    async fn read(
+        &self,
+        file: &Self::File,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::Error> {
+        file.fs.read(&*file.inner, offset, buf).await
+    }
+
+    async fn close(&self, file: Self::File) -> Result<(), Self::Error> {
+        file.fs.close(file.inner).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<Metadata, Self::Error> {
+        let path = normalize(path);
+        if !self.mount_point_children(&path).is_empty() {
+            return Ok(Metadata {
+                ty: EntryType::Directory,
+                size: 0,
+                modified: None,
+            });
+        }
+        let (fs, rest) = self.route(&path).ok_or(MountTableError::NotMounted)?;
+        fs.stat(rest).await
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, Self::Error> {
+        let path = normalize(path);
+        let children = self.mount_point_children(&path);
+        // Where `path` leads to mount points, it is a directory even if the filesystem which
+        // handles it disagrees.
+        let mut entries = match self.route(&path) {
+            Some((fs, rest)) => match fs.read_dir(rest).await {
+                Ok(entries) => entries,
+                Err(_) if !children.is_empty() => Vec::new(),
+                Err(err) => return Err(err),
+            },
+            None if !children.is_empty() => Vec::new(),
+            None => return Err(MountTableError::NotMounted),
+        };
+        for name in children {
+            // Mount points shadow entries of the same name.
+            entries.retain(|entry| entry.name != name);
+            entries.push(DirEntry {
+                name: name.to_string(),
+                ty: EntryType::Directory,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Debug)]
+pub enum MountTableError {
+    /// No filesystem is mounted at a prefix of the path.
+    NotMounted,
+    AlreadyMounted,
+    /// An error from the filesystem which handled the path.
+    Backend(Box<dyn BackendError>),
+}
+
+impl fmt::Display for MountTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotMounted => write!(f, "not mounted"),
+            Self::AlreadyMounted => write!(f, "already mounted"),
+            Self::Backend(err) => err.fmt(f),
+        }
+    }
+}
+
+/// An error from a filesystem mounted in a [`MountTable`]. Use
+/// [`as_any`](BackendError::as_any) to downcast it to the filesystem's own error type.
+pub trait BackendError: fmt::Debug + fmt::Display {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: fmt::Debug + fmt::Display + 'static> BackendError for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+// An object-safe counterpart to `FileSystem`, with the associated types erased.
+trait DynFileSystem {
+    fn open<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn Any>, MountTableError>>;
+
+    fn read<'a>(
+        &'a self,
+        file: &'a dyn Any,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> LocalBoxFuture<'a, Result<usize, MountTableError>>;
+
+    fn close(&self, file: Box<dyn Any>) -> LocalBoxFuture<'_, Result<(), MountTableError>>;
+
+    fn stat<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Metadata, MountTableError>>;
+
+    fn read_dir<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<DirEntry>, MountTableError>>;
+}
+
+impl<T> DynFileSystem for T
+where
+    T: FileSystem,
+    T::File: 'static,
+    T::Error: 'static,
+{
+    fn open<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Box<dyn Any>, MountTableError>> {
+        Box::pin(async move {
+            let file = FileSystem::open(self, path).await.map_err(backend)?;
+            Ok(Box::new(file) as Box<dyn Any>)
+        })
+    }
+
+    fn read<'a>(
+        &'a self,
+        file: &'a dyn Any,
+        offset: usize,
+        buf: &'a mut [u8],
+    ) -> LocalBoxFuture<'a, Result<usize, MountTableError>> {
+        Box::pin(async move {
+            let file = file.downcast_ref().unwrap();
+            FileSystem::read(self, file, offset, buf)
+                .await
+                .map_err(backend)
+        })
+    }
+
+    fn close(&self, file: Box<dyn Any>) -> LocalBoxFuture<'_, Result<(), MountTableError>> {
+        Box::pin(async move {
+            let file = *file.downcast().unwrap();
+            FileSystem::close(self, file).await.map_err(backend)
+        })
+    }
+
+    fn stat<'a>(&'a self, path: &'a str) -> LocalBoxFuture<'a, Result<Metadata, MountTableError>> {
+        Box::pin(async move { FileSystem::stat(self, path).await.map_err(backend) })
+    }
+
+    fn read_dir<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> LocalBoxFuture<'a, Result<Vec<DirEntry>, MountTableError>> {
+        Box::pin(async move { FileSystem::read_dir(self, path).await.map_err(backend) })
+    }
+}
+
+fn backend(err: impl BackendError + 'static) -> MountTableError {
+    MountTableError::Backend(Box::new(err))
+}
+
+fn normalize(path: &str) -> String {
+    components(path).collect::<Vec<_>>().join("/")
+}
+
+// Strips `prefix` from `path` on a component boundary. Both must be normalized.
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return Some(path);
+    }
+    let rest = path.strip_prefix(prefix)?;
+    if rest.is_empty() {
+        Some(rest)
+    } else {
+        rest.strip_prefix('/')
+    }
+}
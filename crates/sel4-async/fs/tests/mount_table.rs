@@ -0,0 +1,104 @@
+#![feature(async_fn_in_trait)]
+
+use sel4_async_fs::{DirEntry, EntryType, FileSystem, MountTable, MountTableError};
+use sel4_async_test_utils::block_on;
+use sel4_async_tmpfs::TmpFs;
+
+fn tmpfs(files: &[(&str, &[u8])]) -> TmpFs {
+    block_on(async {
+        let mut fs = TmpFs::new();
+        for (path, data) in files {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                let _ = fs.create_dir(parent).await;
+            }
+            fs.create_file(path).await.unwrap();
+            fs.write(path, 0, data).await.unwrap();
+        }
+        fs
+    })
+}
+
+fn read_all(table: &MountTable, path: &str) -> Result<Vec<u8>, MountTableError> {
+    block_on(async {
+        let file = table.open(path).await?;
+        let mut buf = vec![0; table.stat(path).await?.size];
+        let n = table.read(&file, 0, &mut buf).await?;
+        assert_eq!(n, buf.len());
+        table.close(file).await?;
+        Ok(buf)
+    })
+}
+
+fn names(table: &MountTable, path: &str) -> Vec<(String, EntryType)> {
+    let mut entries = block_on(table.read_dir(path))
+        .unwrap()
+        .into_iter()
+        .map(|DirEntry { name, ty }| (name, ty))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+#[test]
+fn routes_by_longest_prefix() {
+    let mut table = MountTable::new();
+    table
+        .mount("", tmpfs(&[("index.html", b"root"), ("www/shadowed", b"")]))
+        .unwrap();
+    table
+        .mount("/www/", tmpfs(&[("index.html", b"www")]))
+        .unwrap();
+    table
+        .mount("data/logs", tmpfs(&[("today/log.txt", b"log")]))
+        .unwrap();
+    assert!(matches!(
+        table.mount("www", TmpFs::new()),
+        Err(MountTableError::AlreadyMounted)
+    ));
+
+    assert_eq!(read_all(&table, "index.html").unwrap(), b"root");
+    assert_eq!(read_all(&table, "www/index.html").unwrap(), b"www");
+    assert_eq!(
+        read_all(&table, "//data/logs/today/log.txt").unwrap(),
+        b"log"
+    );
+    assert!(matches!(
+        read_all(&table, "www/shadowed"),
+        Err(MountTableError::Backend(err))
+            if err.as_any().downcast_ref() == Some(&sel4_async_tmpfs::Error::NotFound)
+    ));
+
+    assert_eq!(
+        names(&table, ""),
+        [
+            ("data".to_owned(), EntryType::Directory),
+            ("index.html".to_owned(), EntryType::RegularFile),
+            ("www".to_owned(), EntryType::Directory),
+        ]
+    );
+    assert_eq!(
+        names(&table, "data"),
+        [("logs".to_owned(), EntryType::Directory)]
+    );
+    assert_eq!(
+        names(&table, "data/logs"),
+        [("today".to_owned(), EntryType::Directory)]
+    );
+    assert_eq!(
+        block_on(table.stat("data")).unwrap().ty,
+        EntryType::Directory
+    );
+
+    table.unmount("").unwrap();
+    assert!(matches!(
+        read_all(&table, "index.html"),
+        Err(MountTableError::NotMounted)
+    ));
+    assert_eq!(
+        names(&table, ""),
+        [
+            ("data".to_owned(), EntryType::Directory),
+            ("www".to_owned(), EntryType::Directory),
+        ]
+    );
+}
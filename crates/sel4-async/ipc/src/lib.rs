@@ -0,0 +1,150 @@
+//! Receiving IPC messages on an endpoint from within a single-threaded executor.
+//!
+//! As with `sel4-async-timers`, a [`SharedIpc`] is shared between tasks, which await incoming
+//! messages, and the event loop driving the executor, which blocks on the endpoint via
+//! [`SharedIpc::poll`] whenever the executor has stalled.
+//!
+//! A received message stays in the IPC buffer until it is replied to, so the driver does not
+//! receive another message while a [`Request`] is outstanding. Tasks must not perform other IPC
+//! while holding a [`Request`].
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::mem;
+use core::task::{Poll, Waker};
+
+use sel4::{Badge, ConveysReplyAuthority, Endpoint, MessageInfo};
+
+#[derive(Clone)]
+pub struct SharedIpc {
+    inner: Rc<RefCell<SharedIpcInner>>,
+}
+
+struct SharedIpcInner {
+    state: State,
+    wakers: Vec<Waker>,
+}
+
+enum State {
+    Idle { reply: Option<MessageInfo> },
+    Delivered { info: MessageInfo, badge: Badge },
+    Claimed,
+}
+
+impl SharedIpc {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(SharedIpcInner {
+                state: State::Idle { reply: None },
+                wakers: Vec::new(),
+            })),
+        }
+    }
+
+    fn inner(&self) -> &Rc<RefCell<SharedIpcInner>> {
+        &self.inner
+    }
+
+    /// Blocks until a message arrives on `endpoint` (using `reply_recv` if a reply to the previous
+    /// message is pending) and wakes the tasks waiting in [`SharedIpc::recv`].
+    ///
+    /// Returns `false` without blocking if the previous message has not yet been replied to or
+    /// dropped.
+    pub fn poll(&self, endpoint: Endpoint, reply_authority: impl ConveysReplyAuthority) -> bool {
+        let reply = match &mut self.inner().borrow_mut().state {
+            State::Idle { reply } => reply.take(),
+            _ => return false,
+        };
+        let (info, badge) = match reply {
+            Some(reply) => endpoint.reply_recv(reply, reply_authority),
+            None => endpoint.recv(reply_authority),
+        };
+        let mut inner = self.inner().borrow_mut();
+        inner.state = State::Delivered { info, badge };
+        for waker in mem::take(&mut inner.wakers) {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Waits for a message with any badge.
+    pub async fn recv(&self) -> Request {
+        self.recv_matching(|_| true).await
+    }
+
+    /// Waits for a message with the given badge.
+    ///
+    /// Messages are delivered one at a time, so a message which no task is waiting for prevents
+    /// further messages from being received.
+    pub async fn recv_with_badge(&self, badge: Badge) -> Request {
+        self.recv_matching(|candidate| candidate == badge).await
+    }
+
+    async fn recv_matching(&self, f: impl Fn(Badge) -> bool) -> Request {
+        poll_fn(|cx| {
+            let mut inner = self.inner().borrow_mut();
+            match &inner.state {
+                State::Delivered { info, badge } if f(*badge) => {
+                    let request = Request {
+                        shared: self.clone(),
+                        info: info.clone(),
+                        badge: *badge,
+                        replied: false,
+                    };
+                    inner.state = State::Claimed;
+                    Poll::Ready(request)
+                }
+                _ => {
+                    inner.wakers.push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl Default for SharedIpc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A received message, whose contents remain in the IPC buffer until it is replied to or dropped.
+pub struct Request {
+    shared: SharedIpc,
+    info: MessageInfo,
+    badge: Badge,
+    replied: bool,
+}
+
+impl Request {
+    pub fn info(&self) -> &MessageInfo {
+        &self.info
+    }
+
+    pub fn badge(&self) -> Badge {
+        self.badge
+    }
+
+    /// Replies with `info`, whose message registers must already have been written to the IPC
+    /// buffer. The reply is sent by the next call to [`SharedIpc::poll`].
+    pub fn reply(mut self, info: MessageInfo) {
+        self.replied = true;
+        self.shared.inner().borrow_mut().state = State::Idle { reply: Some(info) };
+    }
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        if !self.replied {
+            self.shared.inner().borrow_mut().state = State::Idle { reply: None };
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::mem;
+
+use sel4_async_network::io::AsyncReadExt;
+use sel4_async_network::TcpSocket;
+use sel4_async_network_tls::ClientTlsStream;
+
+use crate::{HttpError, Method, Response, Url};
+
+const READ_CHUNK_SIZE: usize = 4096;
+
+// Bounds the status line and headers of a response, and each chunk-size line and the trailer
+// section of a chunked body.
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+const MAX_HEADERS: usize = 64;
+
+pub(crate) enum Stream {
+    Plain(TcpSocket),
+    Tls(ClientTlsStream),
+}
+
+impl Stream {
+    async fn recv(&mut self, buf: &mut [u8]) -> Result<usize, HttpError> {
+        match self {
+            Self::Plain(socket) => socket.read(buf).await.map_err(HttpError::TcpSocket),
+            Self::Tls(stream) => stream.recv(buf).await.map_err(HttpError::Tls),
+        }
+    }
+
+    async fn send_all(&mut self, buf: &[u8]) -> Result<(), HttpError> {
+        match self {
+            Self::Plain(socket) => socket.send_all(buf).await.map_err(HttpError::TcpSocket),
+            Self::Tls(stream) => stream.send_all(buf).await.map_err(HttpError::Tls),
+        }
+    }
+
+    fn socket_mut(&mut self) -> &mut TcpSocket {
+        match self {
+            Self::Plain(socket) => socket,
+            Self::Tls(stream) => stream.socket_mut(),
+        }
+    }
+}
+
+/// A connection to an origin server, with any bytes received but not yet consumed.
+pub(crate) struct Connection {
+    stream: Stream,
+    buf: Vec<u8>,
+}
+
+struct Head {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, Vec<u8>)>,
+    keep_alive: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Framing {
+    Empty,
+    Length(usize),
+    Chunked,
+    UntilClose,
+}
+
+impl Connection {
+    pub(crate) fn new(stream: Stream) -> Self {
+        Self {
+            stream,
+            buf: Vec::new(),
+        }
+    }
+
+    pub(crate) async fn send_all(&mut self, buf: &[u8]) -> Result<(), HttpError> {
+        self.stream.send_all(buf).await
+    }
+
+    /// Closes the connection, sending a TLS `close_notify` alert if applicable.
+    pub(crate) async fn close(&mut self) {
+        // The connection is being discarded, so there is nothing to be done about a failure.
+        let _ = match &mut self.stream {
+            Stream::Plain(socket) => socket.close().await.map_err(HttpError::TcpSocket),
+            Stream::Tls(stream) => stream.close().await.map_err(HttpError::Tls),
+        };
+    }
+
+    pub(crate) fn abort(&mut self) {
+        self.stream.socket_mut().abort();
+    }
+
+    /// Reads a response to a request with `method`, skipping any interim (1xx) responses, and
+    /// returns it along with whether this connection may be reused for another request.
+    pub(crate) async fn read_response(
+        &mut self,
+        method: Method,
+        url: Url,
+        max_body_size: usize,
+    ) -> Result<(Response, bool), HttpError> {
+        let mut first = true;
+        let head = loop {
+            let head = self.read_head(first).await?;
+            if !(100..200).contains(&head.status) {
+                break head;
+            }
+            first = false;
+        };
+        let framing = framing(method, &head)?;
+        let body = match framing {
+            Framing::Empty => Vec::new(),
+            Framing::Length(len) => {
+                if len > max_body_size {
+                    return Err(HttpError::ResponseBodyTooLarge);
+                }
+                self.fill_to(len).await?;
+                self.buf.drain(..len).collect()
+            }
+            Framing::Chunked => self.read_chunked_body(max_body_size).await?,
+            Framing::UntilClose => {
+                while self.fill().await? {
+                    if self.buf.len() > max_body_size {
+                        return Err(HttpError::ResponseBodyTooLarge);
+                    }
+                }
+                mem::take(&mut self.buf)
+            }
+        };
+        let reusable = head.keep_alive && framing != Framing::UntilClose && self.buf.is_empty();
+        let response = Response {
+            url,
+            status: head.status,
+            reason: head.reason,
+            headers: head.headers,
+            body,
+        };
+        Ok((response, reusable))
+    }
+
+    async fn read_head(&mut self, first: bool) -> Result<Head, HttpError> {
+        loop {
+            let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+            let mut response = httparse::Response::new(&mut headers);
+            match response
+                .parse(&self.buf)
+                .map_err(HttpError::InvalidResponse)?
+            {
+                httparse::Status::Complete(n) => {
+                    let headers = response
+                        .headers
+                        .iter()
+                        .map(|header| (header.name.to_string(), header.value.to_vec()))
+                        .collect::<Vec<_>>();
+                    let keep_alive = match response.version.unwrap() {
+                        0 => has_token(&headers, "connection", "keep-alive"),
+                        _ => !has_token(&headers, "connection", "close"),
+                    };
+                    let head = Head {
+                        status: response.code.unwrap(),
+                        reason: response.reason.unwrap_or("").to_string(),
+                        headers,
+                        keep_alive,
+                    };
+                    self.buf.drain(..n);
+                    return Ok(head);
+                }
+                httparse::Status::Partial => {
+                    if self.buf.len() >= MAX_HEAD_SIZE {
+                        return Err(HttpError::ResponseHeadTooLarge);
+                    }
+                    let nothing_received = first && self.buf.is_empty();
+                    if !self.fill().await? {
+                        return Err(if nothing_received {
+                            HttpError::ConnectionClosed
+                        } else {
+                            HttpError::UnexpectedEof
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    async fn read_chunked_body(&mut self, max_body_size: usize) -> Result<Vec<u8>, HttpError> {
+        let mut body = Vec::new();
+        loop {
+            let (n, size) = loop {
+                match httparse::parse_chunk_size(&self.buf)
+                    .map_err(|_| HttpError::InvalidChunkedEncoding)?
+                {
+                    httparse::Status::Complete(r) => break r,
+                    httparse::Status::Partial => self.fill_within_head_limit().await?,
+                }
+            };
+            self.buf.drain(..n);
+            if size == 0 {
+                break;
+            }
+            let size = usize::try_from(size)
+                .ok()
+                .filter(|size| {
+                    body.len()
+                        .checked_add(*size)
+                        .map_or(false, |len| len <= max_body_size)
+                })
+                .ok_or(HttpError::ResponseBodyTooLarge)?;
+            self.fill_to(size + 2).await?;
+            if &self.buf[size..size + 2] != b"\r\n" {
+                return Err(HttpError::InvalidChunkedEncoding);
+            }
+            body.extend(self.buf.drain(..size));
+            self.buf.drain(..2);
+        }
+        // Discard the trailer section, which ends with an empty line.
+        loop {
+            match self.buf.windows(2).position(|window| window == b"\r\n") {
+                Some(i) => {
+                    self.buf.drain(..i + 2);
+                    if i == 0 {
+                        break;
+                    }
+                }
+                None => self.fill_within_head_limit().await?,
+            }
+        }
+        Ok(body)
+    }
+
+    // Receives more data into `self.buf`, returning `false` at the end of the stream.
+    async fn fill(&mut self) -> Result<bool, HttpError> {
+        let start = self.buf.len();
+        self.buf.resize(start + READ_CHUNK_SIZE, 0);
+        let r = self.stream.recv(&mut self.buf[start..]).await;
+        self.buf.truncate(start + *r.as_ref().unwrap_or(&0));
+        Ok(r? > 0)
+    }
+
+    async fn fill_to(&mut self, len: usize) -> Result<(), HttpError> {
+        while self.buf.len() < len {
+            if !self.fill().await? {
+                return Err(HttpError::UnexpectedEof);
+            }
+        }
+        Ok(())
+    }
+
+    async fn fill_within_head_limit(&mut self) -> Result<(), HttpError> {
+        if self.buf.len() >= MAX_HEAD_SIZE {
+            return Err(HttpError::ResponseHeadTooLarge);
+        }
+        if !self.fill().await? {
+            return Err(HttpError::UnexpectedEof);
+        }
+        Ok(())
+    }
+}
+
+fn framing(method: Method, head: &Head) -> Result<Framing, HttpError> {
+    if method == Method::Head || head.status == 204 || head.status == 304 {
+        return Ok(Framing::Empty);
+    }
+    if let Some(value) = header(&head.headers, "transfer-encoding") {
+        // As in RFC 9112, Section 6.3, a response whose final transfer coding is not chunked is
+        // delimited by the closing of the connection.
+        let last_coding = value.rsplit(|b| *b == b',').next().unwrap_or(&[]);
+        return Ok(
+            if trim_ascii(last_coding).eq_ignore_ascii_case(b"chunked") {
+                Framing::Chunked
+            } else {
+                Framing::UntilClose
+            },
+        );
+    }
+    if let Some(value) = header(&head.headers, "content-length") {
+        return core::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .map(Framing::Length)
+            .ok_or(HttpError::InvalidContentLength);
+    }
+    Ok(Framing::UntilClose)
+}
+
+fn header<'a>(headers: &'a [(String, Vec<u8>)], name: &str) -> Option<&'a [u8]> {
+    headers
+        .iter()
+        .find(|(this_name, _)| this_name.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_slice())
+}
+
+fn has_token(headers: &[(String, Vec<u8>)], name: &str, token: &str) -> bool {
+    headers
+        .iter()
+        .filter(|(this_name, _)| this_name.eq_ignore_ascii_case(name))
+        .flat_map(|(_, value)| value.split(|b| *b == b','))
+        .any(|this_token| trim_ascii(this_token).eq_ignore_ascii_case(token.as_bytes()))
+}
+
+fn trim_ascii(mut bytes: &[u8]) -> &[u8] {
+    while let [first, rest @ ..] = bytes {
+        if !first.is_ascii_whitespace() {
+            break;
+        }
+        bytes = rest;
+    }
+    while let [rest @ .., last] = bytes {
+        if !last.is_ascii_whitespace() {
+            break;
+        }
+        bytes = rest;
+    }
+    bytes
+}
@@ -0,0 +1,303 @@
+//! An HTTP/1.1 client over [`TcpSocket`], with TLS for `https` URLs provided by
+//! [`sel4_async_network_tls`].
+//!
+//! [`HttpClient`] resolves hostnames with a [`DnsResolver`], follows redirects, decodes chunked
+//! response bodies, and keeps connections alive for reuse, in a pool keyed by scheme, host, and
+//! port. A pooled connection which turns out to have been closed by the server is replaced with a
+//! fresh connection, provided that the request is idempotent.
+//!
+//! Request and response bodies are held in memory in full. Neither pipelining nor
+//! `Expect: 100-continue` are used. Wrap calls to [`HttpClient::send`] in
+//! [`SharedTimers::timeout`] to bound the total time taken by a request.
+//!
+//! [`TcpSocket`]: sel4_async_network::TcpSocket
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::cell::RefCell;
+use core::fmt;
+
+use log::debug;
+use smoltcp::time::Duration;
+
+use sel4_async_network::{DnsResolver, ResolveError, SharedNetwork, TcpSocketError};
+use sel4_async_network_tls::rustls::pki_types::ServerName;
+use sel4_async_network_tls::{ClientConfig, ClientTlsStream, TlsError};
+use sel4_async_timers::SharedTimers;
+
+mod connection;
+mod pool;
+mod request;
+mod response;
+mod url;
+
+use connection::{Connection, Stream};
+use pool::{ConnectionPool, Origin};
+
+pub use request::{Method, Request};
+pub use response::Response;
+pub use url::{Scheme, Url, UrlError};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    pub connect_timeout: Duration,
+    /// The maximum number of redirects followed for a single request.
+    pub max_redirects: usize,
+    /// The maximum number of idle connections kept for each origin. If zero, requests are sent
+    /// with `Connection: close`.
+    pub max_idle_connections_per_origin: usize,
+    /// How long an idle connection is kept before being discarded. This should be shorter than
+    /// the idle timeouts of the servers in question.
+    pub idle_timeout: Duration,
+    pub max_response_body_size: usize,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            max_redirects: 5,
+            max_idle_connections_per_origin: 2,
+            idle_timeout: Duration::from_secs(30),
+            max_response_body_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    Url(UrlError),
+    Resolve(ResolveError),
+    TcpSocket(TcpSocketError),
+    Tls(TlsError),
+    /// The URL is `https`, but the client has no TLS configuration.
+    TlsNotConfigured,
+    InvalidServerName,
+    /// The connection was closed before any part of a response was received.
+    ConnectionClosed,
+    /// The connection was closed part-way through a response.
+    UnexpectedEof,
+    InvalidResponse(httparse::Error),
+    InvalidContentLength,
+    InvalidChunkedEncoding,
+    ResponseHeadTooLarge,
+    ResponseBodyTooLarge,
+    TooManyRedirects,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Url(err) => write!(f, "invalid URL: {err}"),
+            Self::Resolve(err) => write!(f, "resolve error: {err}"),
+            Self::TcpSocket(err) => write!(f, "TCP socket error: {err:?}"),
+            Self::Tls(err) => write!(f, "{err}"),
+            Self::TlsNotConfigured => write!(f, "TLS not configured"),
+            Self::InvalidServerName => write!(f, "invalid server name"),
+            Self::ConnectionClosed => write!(f, "connection closed before response"),
+            Self::UnexpectedEof => write!(f, "connection closed during response"),
+            Self::InvalidResponse(err) => write!(f, "invalid response: {err}"),
+            Self::InvalidContentLength => write!(f, "invalid Content-Length"),
+            Self::InvalidChunkedEncoding => write!(f, "invalid chunked encoding"),
+            Self::ResponseHeadTooLarge => write!(f, "response head too large"),
+            Self::ResponseBodyTooLarge => write!(f, "response body too large"),
+            Self::TooManyRedirects => write!(f, "too many redirects"),
+        }
+    }
+}
+
+impl From<UrlError> for HttpError {
+    fn from(err: UrlError) -> Self {
+        Self::Url(err)
+    }
+}
+
+pub struct HttpClient {
+    network: SharedNetwork,
+    timers: SharedTimers,
+    resolver: DnsResolver,
+    tls_config: Option<Arc<ClientConfig>>,
+    config: HttpClientConfig,
+    pool: RefCell<ConnectionPool>,
+}
+
+impl HttpClient {
+    /// Without `tls_config`, requests to `https` URLs fail with [`HttpError::TlsNotConfigured`].
+    pub fn new(
+        network: SharedNetwork,
+        timers: SharedTimers,
+        resolver: DnsResolver,
+        tls_config: Option<Arc<ClientConfig>>,
+        config: HttpClientConfig,
+    ) -> Self {
+        Self {
+            network,
+            timers,
+            resolver,
+            tls_config,
+            config,
+            pool: RefCell::new(ConnectionPool::new(
+                config.max_idle_connections_per_origin,
+                config.idle_timeout,
+            )),
+        }
+    }
+
+    pub fn config(&self) -> &HttpClientConfig {
+        &self.config
+    }
+
+    pub fn num_idle_connections(&self) -> usize {
+        self.pool.borrow().num_idle()
+    }
+
+    pub fn clear_idle_connections(&self) {
+        self.pool.borrow_mut().clear();
+    }
+
+    pub async fn get(&self, url: &str) -> Result<Response, HttpError> {
+        self.send(Request::get(Url::parse(url)?)).await
+    }
+
+    /// Sends `request`, following redirects.
+    ///
+    /// For `303 See Other`, and for `301 Moved Permanently` and `302 Found` in response to a
+    /// `POST`, the redirect is followed with a `GET` without a body. Otherwise, the method and
+    /// body are preserved. `Authorization` and `Cookie` headers are not sent to a different
+    /// origin.
+    pub async fn send(&self, mut request: Request) -> Result<Response, HttpError> {
+        let mut num_redirects = 0;
+        loop {
+            let response = self.send_once(&request).await?;
+            let location = match response.status {
+                301 | 302 | 303 | 307 | 308 => response.header_str("location"),
+                _ => None,
+            };
+            let Some(location) = location else {
+                return Ok(response);
+            };
+            if num_redirects == self.config.max_redirects {
+                return Err(HttpError::TooManyRedirects);
+            }
+            num_redirects += 1;
+            let url = request.url.join(location)?;
+            debug!("following {} redirect to {}", response.status, url);
+            if response.status == 303
+                || (matches!(response.status, 301 | 302) && request.method == Method::Post)
+            {
+                if request.method != Method::Head {
+                    request.method = Method::Get;
+                }
+                request.body.clear();
+                request.remove_header("content-type");
+            }
+            if !url.same_origin(&request.url) {
+                request.remove_header("authorization");
+                request.remove_header("cookie");
+            }
+            request.url = url;
+        }
+    }
+
+    async fn send_once(&self, request: &Request) -> Result<Response, HttpError> {
+        let origin = Origin::of(&request.url);
+        let keep_alive = self.config.max_idle_connections_per_origin > 0;
+        let encoded = request.encode(keep_alive);
+
+        let pooled = self.pool.borrow_mut().take(&origin, self.timers.now());
+        if let Some(mut conn) = pooled {
+            match self.exchange(&mut conn, request, &encoded).await {
+                Err(HttpError::ConnectionClosed | HttpError::TcpSocket(_) | HttpError::Tls(_))
+                    if request.method.is_idempotent() =>
+                {
+                    debug!(
+                        "pooled connection to {} was closed, retrying on a fresh connection",
+                        request.url.host_header()
+                    );
+                    conn.abort();
+                }
+                r => return self.finish(origin, conn, r).await,
+            }
+        }
+
+        let mut conn = self.connect(&request.url).await?;
+        let r = self.exchange(&mut conn, request, &encoded).await;
+        self.finish(origin, conn, r).await
+    }
+
+    async fn exchange(
+        &self,
+        conn: &mut Connection,
+        request: &Request,
+        encoded: &[u8],
+    ) -> Result<(Response, bool), HttpError> {
+        conn.send_all(encoded).await?;
+        conn.read_response(
+            request.method,
+            request.url.clone(),
+            self.config.max_response_body_size,
+        )
+        .await
+    }
+
+    async fn finish(
+        &self,
+        origin: Origin,
+        mut conn: Connection,
+        r: Result<(Response, bool), HttpError>,
+    ) -> Result<Response, HttpError> {
+        match r {
+            Ok((response, true)) => {
+                self.pool.borrow_mut().put(origin, conn, self.timers.now());
+                Ok(response)
+            }
+            Ok((response, false)) => {
+                conn.close().await;
+                Ok(response)
+            }
+            Err(err) => {
+                conn.abort();
+                Err(err)
+            }
+        }
+    }
+
+    async fn connect(&self, url: &Url) -> Result<Connection, HttpError> {
+        let tls = match url.scheme() {
+            Scheme::Http => None,
+            Scheme::Https => {
+                let config = self.tls_config.clone().ok_or(HttpError::TlsNotConfigured)?;
+                let server_name = ServerName::try_from(url.host())
+                    .map_err(|_| HttpError::InvalidServerName)?
+                    .to_owned();
+                Some((config, server_name))
+            }
+        };
+        let address = self
+            .resolver
+            .resolve(url.host())
+            .await
+            .map_err(HttpError::Resolve)?;
+        let mut socket = self.network.new_tcp_socket();
+        socket
+            .connect_with_timeout(
+                (address, url.port()),
+                self.config.connect_timeout,
+                &self.timers,
+            )
+            .await
+            .map_err(HttpError::TcpSocket)?;
+        let stream = match tls {
+            None => Stream::Plain(socket),
+            Some((config, server_name)) => Stream::Tls(
+                ClientTlsStream::connect(socket, config, server_name)
+                    .await
+                    .map_err(HttpError::Tls)?,
+            ),
+        };
+        Ok(Connection::new(stream))
+    }
+}
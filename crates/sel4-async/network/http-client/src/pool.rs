@@ -0,0 +1,94 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use smoltcp::time::{Duration, Instant};
+
+use crate::connection::Connection;
+use crate::{Scheme, Url};
+
+/// Connections which may share a pool entry.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Origin {
+    scheme: Scheme,
+    host: String,
+    port: u16,
+}
+
+impl Origin {
+    pub(crate) fn of(url: &Url) -> Self {
+        Self {
+            scheme: url.scheme(),
+            host: url.host().into(),
+            port: url.port(),
+        }
+    }
+}
+
+struct IdleConnection {
+    conn: Connection,
+    idle_since: Instant,
+}
+
+/// Idle keep-alive connections, keyed by origin.
+pub(crate) struct ConnectionPool {
+    max_idle_per_origin: usize,
+    idle_timeout: Duration,
+    idle: BTreeMap<Origin, Vec<IdleConnection>>,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(max_idle_per_origin: usize, idle_timeout: Duration) -> Self {
+        Self {
+            max_idle_per_origin,
+            idle_timeout,
+            idle: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn num_idle(&self) -> usize {
+        self.idle.values().map(Vec::len).sum()
+    }
+
+    /// Takes the most recently used connection to `origin` which has not been idle for longer
+    /// than the idle timeout, discarding any which have.
+    pub(crate) fn take(&mut self, origin: &Origin, now: Instant) -> Option<Connection> {
+        let conns = self.idle.get_mut(origin)?;
+        let conn = loop {
+            match conns.pop() {
+                Some(mut idle) if idle.idle_since + self.idle_timeout < now => idle.conn.abort(),
+                Some(idle) => break Some(idle.conn),
+                None => break None,
+            }
+        };
+        if conns.is_empty() {
+            self.idle.remove(origin);
+        }
+        conn
+    }
+
+    /// Returns a connection to the pool, discarding the least recently used connection to
+    /// `origin` if there are already as many idle connections to it as are allowed.
+    pub(crate) fn put(&mut self, origin: Origin, mut conn: Connection, now: Instant) {
+        if self.max_idle_per_origin == 0 {
+            conn.abort();
+            return;
+        }
+        let conns = self.idle.entry(origin).or_default();
+        if conns.len() == self.max_idle_per_origin {
+            conns.remove(0).conn.abort();
+        }
+        conns.push(IdleConnection {
+            conn,
+            idle_since: now,
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for (_, conns) in core::mem::take(&mut self.idle) {
+            for mut idle in conns {
+                idle.conn.abort();
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::Url;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Options,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Patch => "PATCH",
+            Self::Delete => "DELETE",
+            Self::Options => "OPTIONS",
+        }
+    }
+
+    /// Whether repeating a request with this method has the same effect as making it once, in
+    /// which case it may be retried on a fresh connection if a pooled connection turns out to
+    /// have been closed by the server.
+    pub fn is_idempotent(self) -> bool {
+        !matches!(self, Self::Post | Self::Patch)
+    }
+}
+
+/// An HTTP request, built up with [`header`](Self::header) and [`body`](Self::body).
+///
+/// The `Host`, `Content-Length`, and `Connection` headers are supplied by [`HttpClient`], and any
+/// such headers added here are ignored.
+///
+/// [`HttpClient`]: crate::HttpClient
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: Method,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn get(url: Url) -> Self {
+        Self::new(Method::Get, url)
+    }
+
+    pub fn head(url: Url) -> Self {
+        Self::new(Method::Head, url)
+    }
+
+    pub fn post(url: Url) -> Self {
+        Self::new(Method::Post, url)
+    }
+
+    pub fn put(url: Url) -> Self {
+        Self::new(Method::Put, url)
+    }
+
+    pub fn delete(url: Url) -> Self {
+        Self::new(Method::Delete, url)
+    }
+
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub(crate) fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|(this_name, _)| !this_name.eq_ignore_ascii_case(name));
+    }
+
+    pub(crate) fn encode(&self, keep_alive: bool) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(256 + self.body.len());
+        buf.extend_from_slice(self.method.as_str().as_bytes());
+        buf.push(b' ');
+        buf.extend_from_slice(self.url.path_and_query().as_bytes());
+        buf.extend_from_slice(b" HTTP/1.1\r\n");
+        push_header(&mut buf, "Host", &self.url.host_header());
+        for (name, value) in &self.headers {
+            if !is_managed_header(name) {
+                push_header(&mut buf, name, value);
+            }
+        }
+        if !self.body.is_empty()
+            || matches!(self.method, Method::Post | Method::Put | Method::Patch)
+        {
+            push_header(&mut buf, "Content-Length", &self.body.len().to_string());
+        }
+        if !keep_alive {
+            push_header(&mut buf, "Connection", "close");
+        }
+        buf.extend_from_slice(b"\r\n");
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+fn push_header(buf: &mut Vec<u8>, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(b": ");
+    buf.extend_from_slice(value.as_bytes());
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn is_managed_header(name: &str) -> bool {
+    ["host", "content-length", "connection", "transfer-encoding"]
+        .iter()
+        .any(|managed| name.eq_ignore_ascii_case(managed))
+}
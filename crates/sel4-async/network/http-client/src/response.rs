@@ -0,0 +1,35 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Url;
+
+/// An HTTP response, with its body read in full.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    /// The URL of the request to which this is the response, after following any redirects.
+    pub url: Url,
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, Vec<u8>)>,
+    /// The body, with any chunked transfer coding removed.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .find(|(this_name, _)| this_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_slice())
+    }
+
+    pub fn header_str(&self, name: &str) -> Option<&str> {
+        self.header(name)
+            .and_then(|value| core::str::from_utf8(value).ok())
+    }
+}
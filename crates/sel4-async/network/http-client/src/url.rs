@@ -0,0 +1,233 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+impl Scheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+        }
+    }
+
+    pub fn default_port(self) -> u16 {
+        match self {
+            Self::Http => 80,
+            Self::Https => 443,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UrlError {
+    MissingScheme,
+    UnsupportedScheme,
+    /// The authority is empty, or contains userinfo or an IPv6 literal, neither of which are
+    /// supported.
+    InvalidHost,
+    InvalidPort,
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingScheme => write!(f, "missing scheme"),
+            Self::UnsupportedScheme => write!(f, "unsupported scheme"),
+            Self::InvalidHost => write!(f, "invalid host"),
+            Self::InvalidPort => write!(f, "invalid port"),
+        }
+    }
+}
+
+/// An absolute `http` or `https` URL.
+///
+/// The host is normalized to lowercase, the fragment is discarded, and an empty path is
+/// normalized to `/`. Percent-encoding is neither checked nor normalized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    scheme: Scheme,
+    host: String,
+    port: u16,
+    path_and_query: String,
+}
+
+impl Url {
+    pub fn parse(s: &str) -> Result<Self, UrlError> {
+        let (scheme, rest) = s.split_once("://").ok_or(UrlError::MissingScheme)?;
+        let scheme = if scheme.eq_ignore_ascii_case("http") {
+            Scheme::Http
+        } else if scheme.eq_ignore_ascii_case("https") {
+            Scheme::Https
+        } else {
+            return Err(UrlError::UnsupportedScheme);
+        };
+        let rest = strip_fragment(rest);
+        let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path_and_query) = rest.split_at(authority_end);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| UrlError::InvalidPort)?),
+            None => (authority, scheme.default_port()),
+        };
+        if host.is_empty() || host.contains(['@', '[', ']', ':']) {
+            return Err(UrlError::InvalidHost);
+        }
+        Ok(Self {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            path_and_query: normalize_path_and_query(path_and_query),
+        })
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The request target for this URL, as sent in the request line.
+    pub fn path_and_query(&self) -> &str {
+        &self.path_and_query
+    }
+
+    /// The value of the `Host` header for a request to this URL, which includes the port only if
+    /// it is not the default for the scheme.
+    pub fn host_header(&self) -> String {
+        if self.port == self.scheme.default_port() {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Whether requests to `self` and `other` may share a connection.
+    pub fn same_origin(&self, other: &Self) -> bool {
+        self.scheme == other.scheme && self.host == other.host && self.port == other.port
+    }
+
+    /// Resolves `reference`, such as the value of a `Location` header, against this URL.
+    pub fn join(&self, reference: &str) -> Result<Self, UrlError> {
+        let reference = strip_fragment(reference);
+        if has_scheme(reference) {
+            return Self::parse(reference);
+        }
+        if reference.starts_with("//") {
+            return Self::parse(&format!("{}:{}", self.scheme.as_str(), reference));
+        }
+        let path_and_query = if reference.is_empty() {
+            self.path_and_query.clone()
+        } else if reference.starts_with('/') {
+            remove_dot_segments(reference)
+        } else if reference.starts_with('?') {
+            format!("{}{}", self.path(), reference)
+        } else {
+            let base = self.path();
+            let dir = &base[..base.rfind('/').map(|i| i + 1).unwrap_or(0)];
+            remove_dot_segments(&format!("{}{}", dir, reference))
+        };
+        Ok(Self {
+            path_and_query: normalize_path_and_query(&path_and_query),
+            ..self.clone()
+        })
+    }
+
+    fn path(&self) -> &str {
+        let end = self
+            .path_and_query
+            .find('?')
+            .unwrap_or(self.path_and_query.len());
+        &self.path_and_query[..end]
+    }
+}
+
+impl FromStr for Url {
+    type Err = UrlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}{}",
+            self.scheme.as_str(),
+            self.host_header(),
+            self.path_and_query
+        )
+    }
+}
+
+fn strip_fragment(s: &str) -> &str {
+    s.split_once('#').map(|(s, _)| s).unwrap_or(s)
+}
+
+fn has_scheme(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+fn normalize_path_and_query(s: &str) -> String {
+    if s.starts_with('/') {
+        s.to_string()
+    } else {
+        format!("/{}", s)
+    }
+}
+
+// As in RFC 3986, Section 5.2.4, applied to the path only.
+fn remove_dot_segments(path_and_query: &str) -> String {
+    let (path, query) = match path_and_query.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path_and_query, None),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split('/').skip(1) {
+        trailing_slash = false;
+        match segment {
+            "." => trailing_slash = true,
+            ".." => {
+                segments.pop();
+                trailing_slash = true;
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let mut out = String::new();
+    for segment in &segments {
+        out.push('/');
+        out.push_str(segment);
+    }
+    if trailing_slash || out.is_empty() {
+        out.push('/');
+    }
+    if let Some(query) = query {
+        out.push('?');
+        out.push_str(query);
+    }
+    out
+}
@@ -0,0 +1,48 @@
+use sel4_async_network_http_client::{Scheme, Url, UrlError};
+
+#[test]
+fn parse() {
+    let url = Url::parse("HTTPS://Example.com:8443/a/b?c=d#e").unwrap();
+    assert_eq!(url.scheme(), Scheme::Https);
+    assert_eq!(url.host(), "example.com");
+    assert_eq!(url.port(), 8443);
+    assert_eq!(url.path_and_query(), "/a/b?c=d");
+    assert_eq!(url.host_header(), "example.com:8443");
+
+    let url = Url::parse("http://example.com?x").unwrap();
+    assert_eq!(url.port(), 80);
+    assert_eq!(url.path_and_query(), "/?x");
+    assert_eq!(url.to_string(), "http://example.com/?x");
+
+    assert_eq!(Url::parse("example.com"), Err(UrlError::MissingScheme));
+    assert_eq!(
+        Url::parse("ftp://example.com"),
+        Err(UrlError::UnsupportedScheme)
+    );
+    assert_eq!(Url::parse("http://:80/"), Err(UrlError::InvalidHost));
+    assert_eq!(
+        Url::parse("http://user@example.com/"),
+        Err(UrlError::InvalidHost)
+    );
+    assert_eq!(
+        Url::parse("http://example.com:x/"),
+        Err(UrlError::InvalidPort)
+    );
+}
+
+#[test]
+fn join() {
+    let base = Url::parse("https://example.com/a/b/c?q").unwrap();
+    let join = |reference| base.join(reference).unwrap().to_string();
+    assert_eq!(join("http://other.org/x"), "http://other.org/x");
+    assert_eq!(join("//other.org/x"), "https://other.org/x");
+    assert_eq!(join("/x?y"), "https://example.com/x?y");
+    assert_eq!(join("?y"), "https://example.com/a/b/c?y");
+    assert_eq!(join("d"), "https://example.com/a/b/d");
+    assert_eq!(join("../d"), "https://example.com/a/d");
+    assert_eq!(join("./"), "https://example.com/a/b/");
+    assert_eq!(join("../../.."), "https://example.com/");
+    assert_eq!(join(""), "https://example.com/a/b/c?q");
+    assert!(base.same_origin(&base.join("/x").unwrap()));
+    assert!(!base.same_origin(&base.join("http://example.com/").unwrap()));
+}
@@ -7,6 +7,7 @@ extern crate alloc;
 
 use alloc::borrow::Cow;
 use alloc::format;
+use core::borrow::BorrowMut;
 use core::cell::RefCell;
 use core::ffi::{c_int, c_size_t as size_t, c_uchar};
 use core::slice;
@@ -27,25 +28,25 @@ use sel4_async_network_mbedtls_mozilla_ca_list::CA_LIST;
 // re-export
 pub use mbedtls;
 
-pub struct TcpSocketWrapper {
-    inner: TcpSocket,
+pub struct TcpSocketWrapper<T = TcpSocket> {
+    inner: T,
 }
 
-impl TcpSocketWrapper {
-    pub fn new(inner: TcpSocket) -> Self {
+impl<T: BorrowMut<TcpSocket>> TcpSocketWrapper<T> {
+    pub fn new(inner: T) -> Self {
         Self { inner }
     }
 
     pub fn inner_mut(&mut self) -> &mut TcpSocket {
-        &mut self.inner
+        self.inner.borrow_mut()
     }
 
-    pub fn into_inner(self) -> TcpSocket {
+    pub fn into_inner(self) -> T {
         self.inner
     }
 }
 
-impl AsyncIo for TcpSocketWrapper {
+impl<T: BorrowMut<TcpSocket>> AsyncIo for TcpSocketWrapper<T> {
     type Error = TcpSocketError;
 
     fn poll_recv(
@@ -0,0 +1,149 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::cell::RefCell;
+use core::fmt;
+
+use smoltcp::{
+    time::{Duration, Instant},
+    wire::{DnsQueryType, IpAddress},
+};
+
+use sel4_async_timers::SharedTimers;
+
+use crate::{DnsError, SharedNetwork};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DnsResolverConfig {
+    /// How long to wait for a response before giving up on a query.
+    pub timeout: Duration,
+    /// How long a resolved address is reused for. smoltcp does not expose record TTLs, so this
+    /// applies uniformly.
+    pub cache_ttl: Duration,
+    /// The maximum number of cached names. When full, the entry closest to expiry is evicted.
+    pub cache_capacity: usize,
+}
+
+impl Default for DnsResolverConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            cache_ttl: Duration::from_secs(60),
+            cache_capacity: 32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    DnsError(DnsError),
+    TimedOut,
+    NoAddresses,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DnsError(err) => write!(f, "DNS error: {err:?}"),
+            Self::TimedOut => write!(f, "timed out"),
+            Self::NoAddresses => write!(f, "no addresses"),
+        }
+    }
+}
+
+/// Resolves hostnames to IPv4 addresses using the DNS servers configured for a
+/// [`SharedNetwork`], caching the results.
+pub struct DnsResolver {
+    network: SharedNetwork,
+    timers: SharedTimers,
+    config: DnsResolverConfig,
+    cache: RefCell<BTreeMap<String, CacheEntry>>,
+}
+
+#[derive(Copy, Clone)]
+struct CacheEntry {
+    address: IpAddress,
+    expiry: Instant,
+}
+
+impl DnsResolver {
+    pub fn new(network: SharedNetwork, timers: SharedTimers, config: DnsResolverConfig) -> Self {
+        Self {
+            network,
+            timers,
+            config,
+            cache: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &DnsResolverConfig {
+        &self.config
+    }
+
+    /// Literal addresses are returned as-is, without a query.
+    pub async fn resolve(&self, hostname: &str) -> Result<IpAddress, ResolveError> {
+        if let Ok(address) = hostname.parse() {
+            return Ok(address);
+        }
+
+        if let Some(address) = self.lookup_cached(hostname) {
+            return Ok(address);
+        }
+
+        let addresses = self
+            .timers
+            .timeout(
+                self.config.timeout,
+                self.network.dns_query(hostname, DnsQueryType::A),
+            )
+            .await
+            .map_err(|_| ResolveError::TimedOut)?
+            .map_err(ResolveError::DnsError)?;
+
+        let address = *addresses.first().ok_or(ResolveError::NoAddresses)?;
+        self.insert_cached(hostname, address);
+        Ok(address)
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    fn lookup_cached(&self, hostname: &str) -> Option<IpAddress> {
+        let now = self.timers.now();
+        let mut cache = self.cache.borrow_mut();
+        match cache.get(hostname) {
+            Some(entry) if entry.expiry > now => Some(entry.address),
+            Some(_) => {
+                cache.remove(hostname);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert_cached(&self, hostname: &str, address: IpAddress) {
+        if self.config.cache_capacity == 0 {
+            return;
+        }
+        let now = self.timers.now();
+        let mut cache = self.cache.borrow_mut();
+        if !cache.contains_key(hostname) && cache.len() >= self.config.cache_capacity {
+            cache.retain(|_, entry| entry.expiry > now);
+            if cache.len() >= self.config.cache_capacity {
+                let victim = cache
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expiry)
+                    .map(|(hostname, _)| hostname.clone())
+                    .unwrap();
+                cache.remove(&victim);
+            }
+        }
+        cache.insert(
+            hostname.to_string(),
+            CacheEntry {
+                address,
+                expiry: now + self.config.cache_ttl,
+            },
+        );
+    }
+}
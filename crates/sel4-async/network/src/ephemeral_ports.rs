@@ -0,0 +1,52 @@
+use alloc::collections::BTreeSet;
+use core::ops::RangeInclusive;
+
+/// The IANA-recommended dynamic port range.
+pub const DEFAULT_EPHEMERAL_PORT_RANGE: RangeInclusive<u16> = 49152..=65535;
+
+// Hands out local ports for outbound connections, cycling through the range so that recently
+// released ports are not immediately reused.
+pub(crate) struct EphemeralPortAllocator {
+    range: RangeInclusive<u16>,
+    next: u16,
+    in_use: BTreeSet<u16>,
+}
+
+impl EphemeralPortAllocator {
+    pub(crate) fn new(range: RangeInclusive<u16>) -> Self {
+        assert!(!range.is_empty());
+        Self {
+            next: *range.start(),
+            range,
+            in_use: BTreeSet::new(),
+        }
+    }
+
+    pub(crate) fn range(&self) -> &RangeInclusive<u16> {
+        &self.range
+    }
+
+    // Ports which are in use and lie outside of the new range remain reserved until released.
+    pub(crate) fn set_range(&mut self, range: RangeInclusive<u16>) {
+        assert!(!range.is_empty());
+        self.next = *range.start();
+        self.range = range;
+    }
+
+    pub(crate) fn allocate(&mut self) -> Option<u16> {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        let len = usize::from(end - start) + 1;
+        for _ in 0..len {
+            let port = self.next;
+            self.next = if port == end { start } else { port + 1 };
+            if self.in_use.insert(port) {
+                return Some(port);
+            }
+        }
+        None
+    }
+
+    pub(crate) fn release(&mut self, port: u16) {
+        assert!(self.in_use.remove(&port));
+    }
+}
@@ -0,0 +1,243 @@
+//! Byte stream traits, so that protocols can be implemented independently of [`TcpSocket`].
+//!
+//! These mirror `futures::io::{AsyncRead, AsyncWrite}`, which require `std`, except that errors
+//! are of an associated type rather than `std::io::Error`, and vectored writes take `&[&[u8]]`
+//! rather than `&[IoSlice]`.
+//!
+//! Writes complete as soon as data has been copied into the socket's transmit buffer, and wait
+//! while that buffer is full, so that a slow peer applies backpressure to the writer.
+
+use core::fmt;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures::future;
+
+use crate::{PooledTcpSocket, TcpSocket, TcpSocketError};
+
+pub trait AsyncRead {
+    type Error;
+
+    /// Returns `Ok(0)` once the peer has closed its side of the stream.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>>;
+}
+
+pub trait AsyncWrite {
+    type Error;
+
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Self::Error>>;
+
+    /// Writes from `bufs` in order, as though they were concatenated.
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[&[u8]],
+    ) -> Poll<Result<usize, Self::Error>> {
+        let buf = bufs
+            .iter()
+            .copied()
+            .find(|buf| !buf.is_empty())
+            .unwrap_or(&[]);
+        self.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+
+    /// Flushes, and then closes the writing side of the stream. For TCP, this sends a FIN.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReadExactError<E> {
+    UnexpectedEof,
+    Other(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ReadExactError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of stream"),
+            Self::Other(err) => err.fmt(f),
+        }
+    }
+}
+
+pub trait AsyncReadExt: AsyncRead + Unpin {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_read(cx, buf)).await
+    }
+
+    async fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), ReadExactError<Self::Error>> {
+        while !buf.is_empty() {
+            match self.read(buf).await.map_err(ReadExactError::Other)? {
+                0 => return Err(ReadExactError::UnexpectedEof),
+                n => buf = &mut core::mem::take(&mut buf)[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsyncRead + Unpin + ?Sized> AsyncReadExt for T {}
+
+pub trait AsyncWriteExt: AsyncWrite + Unpin {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_write(cx, buf)).await
+    }
+
+    async fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Self::Error> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_write_vectored(cx, bufs)).await
+    }
+
+    async fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Self::Error> {
+        while !buf.is_empty() {
+            let n = self.write(buf).await?;
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_flush(cx)).await
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        future::poll_fn(|cx| Pin::new(&mut *self).poll_close(cx)).await
+    }
+}
+
+impl<T: AsyncWrite + Unpin + ?Sized> AsyncWriteExt for T {}
+
+impl AsyncRead for TcpSocket {
+    type Error = TcpSocketError;
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        match self.get_mut().poll_recv(cx, buf) {
+            Poll::Ready(Err(TcpSocketError::InvalidState(_))) => Poll::Ready(Ok(0)),
+            r => r,
+        }
+    }
+}
+
+impl AsyncWrite for TcpSocket {
+    type Error = TcpSocketError;
+
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        self.get_mut().poll_send(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[&[u8]],
+    ) -> Poll<Result<usize, Self::Error>> {
+        self.get_mut().poll_send_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_close(cx)
+    }
+}
+
+impl AsyncRead for PooledTcpSocket {
+    type Error = TcpSocketError;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PooledTcpSocket {
+    type Error = TcpSocketError;
+
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[&[u8]],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
+
+impl<T: AsyncRead + Unpin + ?Sized> AsyncRead for &mut T {
+    type Error = T::Error;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin + ?Sized> AsyncWrite for &mut T {
+    type Error = T::Error;
+
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[&[u8]],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut **self).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
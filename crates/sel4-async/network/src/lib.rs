@@ -1,4 +1,17 @@
+//! Async networking on top of smoltcp.
+//!
+//! # Features
+//!
+//! - `ipv4-fragmentation`: Fragments outgoing IPv4 packets larger than the MTU and reassembles
+//!   incoming ones, using smoltcp's default buffers, which hold datagrams of up to 1500 bytes.
+//! - `large-fragmented-datagrams`: Like `ipv4-fragmentation`, but with the fragmentation and
+//!   reassembly buffers enlarged to 16 KiB, so that datagrams of up to that size can be exchanged
+//!   over links with a standard MTU. This does not raise the MTU of any link. Frames are never
+//!   larger than the MTU of the [`Device`], which for a virtio-net device is limited by both the
+//!   device and the size of the buffers shared with its driver.
+
 #![no_std]
+#![feature(async_fn_in_trait)]
 
 extern crate alloc;
 
@@ -7,20 +20,40 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::marker::PhantomData;
-use core::task::{self, Poll};
+use core::ops::RangeInclusive;
+use core::task::{self, Poll, Waker};
 
 use futures::prelude::*;
 use log::info;
+use sel4_async_timers::SharedTimers;
 use smoltcp::{
     iface::{Config, Context, Interface, SocketHandle, SocketSet},
     phy::Device,
-    socket::{dhcpv4, dns, tcp, AnySocket},
+    socket::{dhcpv4, dns, tcp, udp, AnySocket},
     time::{Duration, Instant},
     wire::{DnsQueryType, IpAddress, IpCidr, IpEndpoint, IpListenEndpoint, Ipv4Address, Ipv4Cidr},
 };
 
+mod dns_resolver;
+mod ephemeral_ports;
+pub mod io;
+mod sntp;
+mod socket_events;
+mod socket_pool;
+
+use ephemeral_ports::EphemeralPortAllocator;
+use socket_events::SocketEvents;
+
+pub use dns_resolver::{DnsResolver, DnsResolverConfig, ResolveError};
+pub use ephemeral_ports::DEFAULT_EPHEMERAL_PORT_RANGE;
+pub use sntp::{SntpClient, SntpClientConfig, SntpError, SNTP_PORT};
+pub use socket_events::{log_socket_event, SocketEvent, SocketEventHook};
+pub use socket_pool::{PooledTcpSocket, TcpSocketPool, TcpSocketPoolConfig};
+
 pub(crate) const DEFAULT_KEEP_ALIVE_INTERVAL: u64 = 75000;
 pub(crate) const DEFAULT_TCP_SOCKET_BUFFER_SIZE: usize = 65535;
+pub(crate) const DEFAULT_UDP_SOCKET_PACKET_CAPACITY: usize = 16;
+pub(crate) const DEFAULT_UDP_SOCKET_BUFFER_SIZE: usize = 8192;
 
 #[derive(Clone)]
 pub struct SharedNetwork {
@@ -33,6 +66,10 @@ struct SharedNetworkInner {
     dns_socket_handle: SocketHandle,
     dhcp_socket_handle: SocketHandle,
     dhcp_overrides: DhcpOverrides,
+    dhcp_config: Option<dhcpv4::Config<'static>>,
+    config_wakers: Vec<Waker>,
+    ephemeral_ports: EphemeralPortAllocator,
+    socket_events: SocketEvents,
 }
 
 #[derive(Default)]
@@ -42,20 +79,74 @@ pub struct DhcpOverrides {
     pub dns_servers: Option<Vec<Ipv4Address>>,
 }
 
+/// The interface's IPv4 configuration, as acquired using DHCP, with any [`DhcpOverrides`]
+/// applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub address: Ipv4Cidr,
+    pub router: Option<Ipv4Address>,
+    pub dns_servers: Vec<Ipv4Address>,
+}
+
 pub type TcpSocket = Socket<tcp::Socket<'static>>;
 
+pub type UdpSocket = Socket<udp::Socket<'static>>;
+
 pub struct Socket<T> {
     handle: SocketHandle,
     shared: SharedNetwork,
+    metrics: SocketMetrics,
+    ephemeral_port: Option<u16>,
     _phantom: PhantomData<T>,
 }
 
+/// Counters describing the traffic through and backpressure on a [`Socket`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SocketMetrics {
+    /// Number of bytes received.
+    pub rx_bytes: u64,
+    /// Number of bytes sent.
+    pub tx_bytes: u64,
+    /// Number of times a receive had to wait because the receive buffer was empty.
+    pub rx_stalls: u64,
+    /// Number of times a send had to wait because the transmit buffer was full.
+    pub tx_stalls: u64,
+}
+
+/// A snapshot of a TCP or UDP socket's state, as returned by [`SharedNetwork::sockets`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SocketSummary {
+    Tcp {
+        handle: SocketHandle,
+        state: tcp::State,
+        local_endpoint: Option<IpEndpoint>,
+        remote_endpoint: Option<IpEndpoint>,
+    },
+    Udp {
+        handle: SocketHandle,
+        local_endpoint: IpListenEndpoint,
+    },
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TcpSocketError {
     InvalidState(tcp::State), // TODO just use InvalidState variants of below errors?
     RecvError(tcp::RecvError),
     SendError(tcp::SendError),
     ConnectError(tcp::ConnectError),
+    /// All ports in the ephemeral port range are in use.
+    NoEphemeralPort,
+    TimedOut,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UdpSocketError {
+    BindError(udp::BindError),
+    SendError(udp::SendError),
+    /// The datagram does not fit in the socket's transmit buffer.
+    TooLarge,
+    /// All ports in the ephemeral port range are in use.
+    NoEphemeralPort,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -84,6 +175,10 @@ impl SharedNetwork {
             dns_socket_handle,
             dhcp_socket_handle,
             dhcp_overrides,
+            dhcp_config: None,
+            config_wakers: vec![],
+            ephemeral_ports: EphemeralPortAllocator::new(DEFAULT_EPHEMERAL_PORT_RANGE),
+            socket_events: SocketEvents::new(),
         };
 
         this.apply_dhcp_overrides();
@@ -114,15 +209,128 @@ impl SharedNetwork {
         self.new_socket(tcp::Socket::new(rx_buffer, tx_buffer))
     }
 
+    pub fn new_udp_socket(&self) -> UdpSocket {
+        self.new_udp_socket_with_buffer_sizes(
+            DEFAULT_UDP_SOCKET_PACKET_CAPACITY,
+            DEFAULT_UDP_SOCKET_BUFFER_SIZE,
+            DEFAULT_UDP_SOCKET_PACKET_CAPACITY,
+            DEFAULT_UDP_SOCKET_BUFFER_SIZE,
+        )
+    }
+
+    /// The receive queue holds at most `rx_packet_capacity` datagrams, totalling at most
+    /// `rx_buffer_size` bytes. Datagrams which arrive while it is full are dropped.
+    pub fn new_udp_socket_with_buffer_sizes(
+        &self,
+        rx_packet_capacity: usize,
+        rx_buffer_size: usize,
+        tx_packet_capacity: usize,
+        tx_buffer_size: usize,
+    ) -> UdpSocket {
+        let rx_buffer = udp::PacketBuffer::new(
+            vec![udp::PacketMetadata::EMPTY; rx_packet_capacity],
+            vec![0; rx_buffer_size],
+        );
+        let tx_buffer = udp::PacketBuffer::new(
+            vec![udp::PacketMetadata::EMPTY; tx_packet_capacity],
+            vec![0; tx_buffer_size],
+        );
+        self.new_socket(udp::Socket::new(rx_buffer, tx_buffer))
+    }
+
     pub fn new_socket<T: AnySocket<'static>>(&self, socket: T) -> Socket<T> {
         let handle = self.inner().borrow_mut().socket_set.add(socket);
         Socket {
             handle,
             shared: self.clone(),
+            metrics: SocketMetrics::default(),
+            ephemeral_port: None,
             _phantom: PhantomData,
         }
     }
 
+    pub fn ephemeral_port_range(&self) -> RangeInclusive<u16> {
+        self.inner().borrow().ephemeral_ports.range().clone()
+    }
+
+    /// Sets the range from which [`Socket::connect_with_timeout`] chooses local ports.
+    pub fn set_ephemeral_port_range(&self, range: RangeInclusive<u16>) {
+        self.inner().borrow_mut().ephemeral_ports.set_range(range)
+    }
+
+    /// The current configuration, or `None` if no address has been acquired or overridden.
+    pub fn config(&self) -> Option<NetworkConfig> {
+        self.inner().borrow().config()
+    }
+
+    /// Summarizes each TCP and UDP socket, for diagnostics. The sockets which this type uses
+    /// internally for DHCP and DNS are omitted.
+    pub fn sockets(&self) -> Vec<SocketSummary> {
+        self.inner()
+            .borrow()
+            .socket_set
+            .iter()
+            .filter_map(|(handle, socket)| {
+                if let Some(socket) = tcp::Socket::downcast(socket) {
+                    Some(SocketSummary::Tcp {
+                        handle,
+                        state: socket.state(),
+                        local_endpoint: socket.local_endpoint(),
+                        remote_endpoint: socket.remote_endpoint(),
+                    })
+                } else {
+                    udp::Socket::downcast(socket).map(|socket| SocketSummary::Udp {
+                        handle,
+                        local_endpoint: socket.endpoint(),
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Waits until the interface is configured, and returns its configuration.
+    pub async fn wait_for_config(&self) -> NetworkConfig {
+        future::poll_fn(|cx| {
+            let mut inner = self.inner().borrow_mut();
+            match inner.config() {
+                Some(config) => Poll::Ready(config),
+                None => {
+                    if !inner.config_wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                        inner.config_wakers.push(cx.waker().clone());
+                    }
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Restarts DHCP discovery, for example after the link has come back up, rather than
+    /// waiting for the current lease to expire.
+    pub fn reset_dhcp(&self) {
+        self.inner().borrow_mut().dhcp_socket_mut().reset()
+    }
+
+    /// Leases are renewed after half of their duration, which is capped at `max_lease_duration`
+    /// if provided. Renewal is driven by the timestamps passed to [`SharedNetwork::poll`] and
+    /// [`SharedNetwork::poll_delay`], which should come from the same clock as the
+    /// [`SharedTimers`] used with this network.
+    pub fn set_dhcp_max_lease_duration(&self, max_lease_duration: Option<Duration>) {
+        self.inner()
+            .borrow_mut()
+            .dhcp_socket_mut()
+            .set_max_lease_duration(max_lease_duration)
+    }
+
+    /// Sets how long data queued on a TCP socket must go unacknowledged before
+    /// [`SocketEvent::SendProgressStalled`] is reported to the socket's event hook.
+    pub fn set_send_stall_threshold(&self, threshold: Duration) {
+        self.inner()
+            .borrow_mut()
+            .socket_events
+            .set_send_stall_threshold(threshold)
+    }
+
     pub fn poll_delay(&self, timestamp: Instant) -> Option<Duration> {
         self.inner().borrow_mut().poll_delay(timestamp)
     }
@@ -131,6 +339,20 @@ impl SharedNetwork {
         self.inner().borrow_mut().poll(timestamp, device)
     }
 
+    #[cfg(feature = "ipv4-fragmentation")]
+    pub fn reassembly_timeout(&self) -> Duration {
+        self.inner().borrow().iface.reassembly_timeout()
+    }
+
+    #[cfg(feature = "ipv4-fragmentation")]
+    pub fn set_reassembly_timeout(&self, timeout: Duration) {
+        self.inner()
+            .borrow_mut()
+            .iface
+            .set_reassembly_timeout(timeout)
+    }
+
+    /// If the returned future is dropped before the query completes, the query is cancelled.
     pub async fn dns_query(
         &self,
         name: &str,
@@ -144,27 +366,74 @@ impl SharedNetwork {
                 .start_query(inner.iface.context(), name, query_type)
                 .map_err(DnsError::StartQueryError)?
         };
+        let mut in_flight = InFlightDnsQuery {
+            shared: self,
+            query_handle,
+            complete: false,
+        };
         future::poll_fn(|cx| {
             let inner = &mut *self.inner().borrow_mut();
-            let socket = inner
-                .socket_set
-                .get_mut::<dns::Socket>(inner.dns_socket_handle);
+            let socket = inner.dns_socket_mut();
             match socket.get_query_result(query_handle) {
                 Err(dns::GetQueryResultError::Pending) => {
                     socket.register_query_waker(query_handle, cx.waker());
                     Poll::Pending
                 }
-                r => Poll::Ready(
-                    r.map(|heapless_vec| heapless_vec.to_vec())
-                        .map_err(DnsError::GetQueryResultError),
-                ),
+                r => {
+                    in_flight.complete = true;
+                    Poll::Ready(
+                        r.map(|heapless_vec| heapless_vec.to_vec())
+                            .map_err(DnsError::GetQueryResultError),
+                    )
+                }
             }
         })
         .await
     }
 }
 
+// Frees the query's slot in the DNS socket if the query is abandoned.
+struct InFlightDnsQuery<'a> {
+    shared: &'a SharedNetwork,
+    query_handle: dns::QueryHandle,
+    complete: bool,
+}
+
+impl Drop for InFlightDnsQuery<'_> {
+    fn drop(&mut self) {
+        if !self.complete {
+            self.shared
+                .inner()
+                .borrow_mut()
+                .dns_socket_mut()
+                .cancel_query(self.query_handle);
+        }
+    }
+}
+
 impl<T: AnySocket<'static>> Socket<T> {
+    pub fn handle(&self) -> SocketHandle {
+        self.handle
+    }
+
+    /// Reports this socket's activity to `hook`, or stops reporting if `hook` is `None`. The hook
+    /// is called while the network is borrowed, and so must not use it.
+    pub fn set_event_hook(&mut self, hook: Option<SocketEventHook>) {
+        self.shared
+            .inner()
+            .borrow_mut()
+            .socket_events
+            .set_hook(self.handle, hook)
+    }
+
+    pub fn metrics(&self) -> &SocketMetrics {
+        &self.metrics
+    }
+
+    pub fn reset_metrics(&mut self) {
+        self.metrics = SocketMetrics::default();
+    }
+
     pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
         let network = self.shared.inner().borrow();
         let socket = network.socket_set.get(self.handle);
@@ -220,6 +489,39 @@ impl Socket<tcp::Socket<'static>> {
         .await
     }
 
+    /// Connects to `remote_endpoint` from a local port chosen from the network's ephemeral port
+    /// range, aborting the attempt if it has not succeeded within `timeout`.
+    pub async fn connect_with_timeout<T: Into<IpEndpoint>>(
+        &mut self,
+        remote_endpoint: T,
+        timeout: Duration,
+        timers: &SharedTimers,
+    ) -> Result<(), TcpSocketError> {
+        self.release_ephemeral_port();
+        let port = self
+            .shared
+            .inner()
+            .borrow_mut()
+            .ephemeral_ports
+            .allocate()
+            .ok_or(TcpSocketError::NoEphemeralPort)?;
+        self.ephemeral_port = Some(port);
+        let r = match timers
+            .timeout(timeout, self.connect(remote_endpoint, port))
+            .await
+        {
+            Ok(r) => r,
+            Err(_) => {
+                self.abort();
+                Err(TcpSocketError::TimedOut)
+            }
+        };
+        if r.is_err() {
+            self.release_ephemeral_port();
+        }
+        r
+    }
+
     pub async fn accept_with_keep_alive(
         &mut self,
         port: u16,
@@ -227,7 +529,9 @@ impl Socket<tcp::Socket<'static>> {
     ) -> Result<(), TcpSocketError> {
         future::poll_fn(|cx| {
             self.with_mut(|socket| match socket.state() {
-                tcp::State::Closed => {
+                // A connection in TIME-WAIT has been closed on both sides, so its socket can be
+                // reused.
+                tcp::State::Closed | tcp::State::TimeWait => {
                     socket.listen(port).unwrap();
                     Poll::Ready(())
                 }
@@ -287,7 +591,7 @@ impl Socket<tcp::Socket<'static>> {
         cx: &mut task::Context<'_>,
         buffer: &mut [u8],
     ) -> Poll<Result<usize, TcpSocketError>> {
-        self.with_mut(|socket| {
+        let r = self.with_mut(|socket| {
             if socket.can_recv() {
                 Poll::Ready(
                     socket
@@ -313,7 +617,13 @@ impl Socket<tcp::Socket<'static>> {
                     }
                 }
             }
-        })
+        });
+        match &r {
+            Poll::Ready(Ok(n)) => self.metrics.rx_bytes += u64::try_from(*n).unwrap(),
+            Poll::Pending => self.record_stall(SocketEvent::RecvStalled),
+            _ => {}
+        }
+        r
     }
 
     pub async fn send_all(&mut self, buffer: &[u8]) -> Result<(), TcpSocketError> {
@@ -337,7 +647,7 @@ impl Socket<tcp::Socket<'static>> {
         cx: &mut task::Context<'_>,
         buffer: &[u8],
     ) -> Poll<Result<usize, TcpSocketError>> {
-        self.with_mut(|socket| {
+        let r = self.with_mut(|socket| {
             if socket.can_send() {
                 Poll::Ready(socket.send_slice(buffer).map_err(TcpSocketError::SendError))
             } else {
@@ -355,6 +665,131 @@ impl Socket<tcp::Socket<'static>> {
                     }
                 }
             }
+        });
+        match &r {
+            Poll::Ready(Ok(n)) => self.metrics.tx_bytes += u64::try_from(*n).unwrap(),
+            Poll::Pending => self.record_stall(SocketEvent::SendStalled),
+            _ => {}
+        }
+        r
+    }
+
+    /// Like [`Socket::poll_send`], but sends as much of the concatenation of `buffers` as fits in
+    /// the transmit buffer.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_send_vectored(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        buffers: &[&[u8]],
+    ) -> Poll<Result<usize, TcpSocketError>> {
+        if buffers.iter().all(|buffer| buffer.is_empty()) {
+            return Poll::Ready(Ok(0));
+        }
+        let r = self.with_mut(|socket| {
+            if socket.can_send() {
+                Poll::Ready(
+                    socket
+                        .send(|free| {
+                            let mut n = 0;
+                            for buffer in buffers {
+                                let m = buffer.len().min(free.len() - n);
+                                free[n..n + m].copy_from_slice(&buffer[..m]);
+                                n += m;
+                            }
+                            (n, n)
+                        })
+                        .map_err(TcpSocketError::SendError),
+                )
+            } else {
+                let state = socket.state();
+                match state {
+                    tcp::State::FinWait1
+                    | tcp::State::FinWait2
+                    | tcp::State::Closed
+                    | tcp::State::Closing
+                    | tcp::State::CloseWait
+                    | tcp::State::TimeWait => Poll::Ready(Err(TcpSocketError::InvalidState(state))),
+                    _ => {
+                        socket.register_send_waker(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            }
+        });
+        match &r {
+            Poll::Ready(Ok(n)) => self.metrics.tx_bytes += u64::try_from(*n).unwrap(),
+            Poll::Pending => self.record_stall(SocketEvent::SendStalled),
+            _ => {}
+        }
+        r
+    }
+
+    /// Waits until data can be queued for sending, for use with [`Socket::try_send`].
+    pub async fn send_ready(&mut self) -> Result<(), TcpSocketError> {
+        future::poll_fn(|cx| self.poll_send_ready(cx)).await
+    }
+
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_send_ready(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), TcpSocketError>> {
+        let r = self.with_mut(|socket| {
+            if socket.can_send() {
+                Poll::Ready(Ok(()))
+            } else {
+                let state = socket.state();
+                match state {
+                    tcp::State::FinWait1
+                    | tcp::State::FinWait2
+                    | tcp::State::Closed
+                    | tcp::State::Closing
+                    | tcp::State::CloseWait
+                    | tcp::State::TimeWait => Poll::Ready(Err(TcpSocketError::InvalidState(state))),
+                    _ => {
+                        socket.register_send_waker(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            }
+        });
+        if r.is_pending() {
+            self.record_stall(SocketEvent::SendStalled);
+        }
+        r
+    }
+
+    /// Copies as much of `buffer` as fits straight into the transmit buffer, without waiting.
+    ///
+    /// Together with [`Socket::send_ready`], this allows data to be sent from buffers which cannot
+    /// be borrowed across an await point, such as those of a cache.
+    pub fn try_send(&mut self, buffer: &[u8]) -> Result<usize, TcpSocketError> {
+        let n = self
+            .with_mut(|socket| socket.send_slice(buffer))
+            .map_err(TcpSocketError::SendError)?;
+        self.metrics.tx_bytes += u64::try_from(n).unwrap();
+        Ok(n)
+    }
+
+    /// Waits for the transmit buffer to drain, and then sends a FIN. Unlike [`Socket::close`],
+    /// this succeeds if the connection is already closing or closed, so that it can be polled
+    /// repeatedly.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_close(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), TcpSocketError>> {
+        self.with_mut(|socket| match socket.state() {
+            tcp::State::Established | tcp::State::CloseWait | tcp::State::SynReceived => {
+                if socket.send_queue() > 0 {
+                    socket.register_send_waker(cx.waker());
+                    Poll::Pending
+                } else {
+                    socket.close();
+                    Poll::Ready(Ok(()))
+                }
+            }
+            tcp::State::Listen | tcp::State::SynSent => {
+                Poll::Ready(Err(TcpSocketError::InvalidState(socket.state())))
+            }
+            _ => Poll::Ready(Ok(())),
         })
     }
 
@@ -405,13 +840,135 @@ impl Socket<tcp::Socket<'static>> {
     }
 }
 
+impl Socket<udp::Socket<'static>> {
+    pub fn bind<T: Into<IpListenEndpoint>>(&mut self, endpoint: T) -> Result<(), UdpSocketError> {
+        self.with_mut(|socket| socket.bind(endpoint))
+            .map_err(UdpSocketError::BindError)
+    }
+
+    /// Binds to a port chosen from the network's ephemeral port range, and returns that port.
+    pub fn bind_ephemeral(&mut self) -> Result<u16, UdpSocketError> {
+        self.release_ephemeral_port();
+        let port = self
+            .shared
+            .inner()
+            .borrow_mut()
+            .ephemeral_ports
+            .allocate()
+            .ok_or(UdpSocketError::NoEphemeralPort)?;
+        self.ephemeral_port = Some(port);
+        if let Err(err) = self.bind(port) {
+            self.release_ephemeral_port();
+            return Err(err);
+        }
+        Ok(port)
+    }
+
+    pub fn local_endpoint(&self) -> IpListenEndpoint {
+        self.with(|socket| socket.endpoint())
+    }
+
+    /// Waits for a datagram, and copies its payload into `buffer`. Payloads longer than `buffer`
+    /// are truncated.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub async fn recv_from(&mut self, buffer: &mut [u8]) -> (usize, IpEndpoint) {
+        future::poll_fn(|cx| self.poll_recv_from(cx, buffer)).await
+    }
+
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_recv_from(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<(usize, IpEndpoint)> {
+        let r = self.with_mut(|socket| match socket.recv_slice(buffer) {
+            Ok((n, meta)) => Poll::Ready((n, meta.endpoint)),
+            Err(udp::RecvError::Exhausted) => {
+                socket.register_recv_waker(cx.waker());
+                Poll::Pending
+            }
+        });
+        match &r {
+            Poll::Ready((n, _)) => self.metrics.rx_bytes += u64::try_from(*n).unwrap(),
+            Poll::Pending => self.record_stall(SocketEvent::RecvStalled),
+        }
+        r
+    }
+
+    /// Waits for space in the transmit queue, and then enqueues `buffer` as a single datagram.
+    pub async fn send_to<T: Into<IpEndpoint>>(
+        &mut self,
+        buffer: &[u8],
+        remote_endpoint: T,
+    ) -> Result<(), UdpSocketError> {
+        let remote_endpoint = remote_endpoint.into();
+        future::poll_fn(|cx| self.poll_send_to(cx, buffer, remote_endpoint)).await
+    }
+
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_send_to(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        buffer: &[u8],
+        remote_endpoint: IpEndpoint,
+    ) -> Poll<Result<(), UdpSocketError>> {
+        let r = self.with_mut(|socket| {
+            if buffer.len() > socket.payload_send_capacity() {
+                return Poll::Ready(Err(UdpSocketError::TooLarge));
+            }
+            match socket.send_slice(buffer, remote_endpoint) {
+                Err(udp::SendError::BufferFull) => {
+                    socket.register_send_waker(cx.waker());
+                    Poll::Pending
+                }
+                r => Poll::Ready(r.map_err(UdpSocketError::SendError)),
+            }
+        });
+        match &r {
+            Poll::Ready(Ok(())) => self.metrics.tx_bytes += u64::try_from(buffer.len()).unwrap(),
+            Poll::Pending => self.record_stall(SocketEvent::SendStalled),
+            _ => {}
+        }
+        r
+    }
+
+    pub fn close(&mut self) {
+        self.with_mut(|socket| socket.close());
+        self.release_ephemeral_port();
+    }
+}
+
+impl<T> Socket<T> {
+    fn record_stall(&mut self, event: SocketEvent) {
+        match event {
+            SocketEvent::RecvStalled => self.metrics.rx_stalls += 1,
+            SocketEvent::SendStalled => self.metrics.tx_stalls += 1,
+            _ => unreachable!(),
+        }
+        self.shared
+            .inner()
+            .borrow()
+            .socket_events
+            .emit(self.handle, event);
+    }
+
+    fn release_ephemeral_port(&mut self) {
+        if let Some(port) = self.ephemeral_port.take() {
+            self.shared
+                .inner()
+                .borrow_mut()
+                .ephemeral_ports
+                .release(port);
+        }
+    }
+}
+
 impl<T> Drop for Socket<T> {
     fn drop(&mut self) {
-        self.shared
-            .inner
-            .borrow_mut()
-            .socket_set
-            .remove(self.handle);
+        self.release_ephemeral_port();
+        let mut inner = self.shared.inner.borrow_mut();
+        inner.socket_events.set_hook(self.handle, None);
+        inner.socket_set.remove(self.handle);
     }
 }
 
@@ -430,16 +987,48 @@ impl SharedNetworkInner {
 
     fn poll<D: Device + ?Sized>(&mut self, timestamp: Instant, device: &mut D) -> bool {
         let activity = self.iface.poll(timestamp, device, &mut self.socket_set);
+        self.socket_events.observe(&self.socket_set, timestamp);
         if activity {
             self.poll_dhcp();
         }
         activity
     }
 
+    fn config(&self) -> Option<NetworkConfig> {
+        let dhcp_config = self.dhcp_config.as_ref();
+        Some(NetworkConfig {
+            address: self
+                .dhcp_overrides
+                .address
+                .or(dhcp_config.map(|config| config.address))?,
+            router: self
+                .dhcp_overrides
+                .router
+                .unwrap_or(dhcp_config.and_then(|config| config.router)),
+            dns_servers: match &self.dhcp_overrides.dns_servers {
+                Some(dns_servers) => dns_servers.clone(),
+                None => dhcp_config
+                    .map(|config| config.dns_servers.to_vec())
+                    .unwrap_or_default(),
+            },
+        })
+    }
+
+    fn wake_config_waiters(&mut self) {
+        for waker in self.config_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
     // TODO should dhcp events instead just be monitored in a task?
     fn poll_dhcp(&mut self) {
         if let Some(event) = self.dhcp_socket_mut().poll() {
             let event = free_dhcp_event(event);
+            self.dhcp_config = match &event {
+                dhcpv4::Event::Configured(config) => Some(config.clone()),
+                dhcpv4::Event::Deconfigured => None,
+            };
+            self.wake_config_waiters();
             match event {
                 dhcpv4::Event::Configured(config) => {
                     info!("DHCP config acquired");
@@ -0,0 +1,168 @@
+use core::fmt;
+
+use smoltcp::{time::Duration, wire::IpEndpoint};
+
+use sel4_async_timers::{SharedTimers, SystemTime};
+
+use crate::{SharedNetwork, UdpSocketError};
+
+pub const SNTP_PORT: u16 = 123;
+
+const PACKET_SIZE: usize = 48;
+
+// Seconds from the NTP prime epoch (1900) to the Unix epoch (1970).
+const NTP_UNIX_EPOCH_OFFSET: u64 = 2_208_988_800;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SntpClientConfig {
+    /// How long to wait for a response before giving up on a query.
+    pub timeout: Duration,
+}
+
+impl Default for SntpClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SntpError {
+    UdpSocketError(UdpSocketError),
+    TimedOut,
+    /// The response was malformed, or did not answer our request.
+    InvalidResponse,
+    /// The server is not synchronized, or sent a kiss-o'-death packet with the given code.
+    Unsynchronized([u8; 4]),
+}
+
+impl fmt::Display for SntpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UdpSocketError(err) => write!(f, "UDP socket error: {err:?}"),
+            Self::TimedOut => write!(f, "timed out"),
+            Self::InvalidResponse => write!(f, "invalid response"),
+            Self::Unsynchronized(code) => write!(
+                f,
+                "server unsynchronized ({})",
+                core::str::from_utf8(code).unwrap_or("?")
+            ),
+        }
+    }
+}
+
+impl From<UdpSocketError> for SntpError {
+    fn from(err: UdpSocketError) -> Self {
+        Self::UdpSocketError(err)
+    }
+}
+
+/// Learns the wall-clock time from an NTP server using SNTP (RFC 4330), so that it can be
+/// tracked by a [`SharedTimers`] alongside its monotonic clock.
+pub struct SntpClient {
+    network: SharedNetwork,
+    timers: SharedTimers,
+    config: SntpClientConfig,
+}
+
+impl SntpClient {
+    pub fn new(network: SharedNetwork, timers: SharedTimers, config: SntpClientConfig) -> Self {
+        Self {
+            network,
+            timers,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &SntpClientConfig {
+        &self.config
+    }
+
+    /// Returns the wall-clock time at [`SharedTimers::now`], as of the arrival of the server's
+    /// response, compensating for half of the round-trip delay.
+    pub async fn query<T: Into<IpEndpoint>>(&self, server: T) -> Result<SystemTime, SntpError> {
+        let server = server.into();
+        let mut socket = self.network.new_udp_socket();
+        socket.bind_ephemeral()?;
+
+        let sent_at = self.timers.now();
+        // The transmit timestamp of a request is echoed as the originate timestamp of the
+        // response. Any value unique to this request will do.
+        let nonce = u64::try_from(sent_at.total_micros()).unwrap_or(0) | 1;
+        let mut request = [0; PACKET_SIZE];
+        request[0] = (4 << 3) | 3; // LI = 0, VN = 4, mode = client
+        request[40..48].copy_from_slice(&nonce.to_be_bytes());
+        socket.send_to(&request, server).await?;
+
+        let response = self
+            .timers
+            .timeout(self.config.timeout, async {
+                let mut buf = [0; PACKET_SIZE];
+                loop {
+                    let (n, from) = socket.recv_from(&mut buf).await;
+                    if from == server && n >= PACKET_SIZE && buf[24..32] == nonce.to_be_bytes() {
+                        return buf;
+                    }
+                }
+            })
+            .await
+            .map_err(|_| SntpError::TimedOut)?;
+        let received_at = self.timers.now();
+
+        socket.close();
+
+        let mode = response[0] & 0b111;
+        if mode != 4 && mode != 5 {
+            return Err(SntpError::InvalidResponse);
+        }
+        let leap_indicator = response[0] >> 6;
+        let stratum = response[1];
+        if leap_indicator == 3 || stratum == 0 {
+            return Err(SntpError::Unsynchronized(
+                response[12..16].try_into().unwrap(),
+            ));
+        }
+
+        let server_received = parse_timestamp(&response[32..40])?;
+        let server_transmitted = parse_timestamp(&response[40..48])?;
+        let round_trip = received_at - sent_at;
+        let server_delay = if server_transmitted >= server_received {
+            server_transmitted - server_received
+        } else {
+            Duration::ZERO
+        };
+        let network_delay = if round_trip >= server_delay {
+            round_trip - server_delay
+        } else {
+            Duration::ZERO
+        };
+        Ok(server_transmitted + network_delay / 2)
+    }
+
+    /// Queries `server`, and then passes the result to [`SharedTimers::set_system_time`].
+    pub async fn sync<T: Into<IpEndpoint>>(&self, server: T) -> Result<SystemTime, SntpError> {
+        let system_time = self.query(server).await?;
+        self.timers.set_system_time(system_time);
+        Ok(system_time)
+    }
+}
+
+fn parse_timestamp(bytes: &[u8]) -> Result<SystemTime, SntpError> {
+    let secs = u64::from(u32::from_be_bytes(bytes[..4].try_into().unwrap()));
+    let fraction = u64::from(u32::from_be_bytes(bytes[4..].try_into().unwrap()));
+    if secs == 0 && fraction == 0 {
+        return Err(SntpError::InvalidResponse);
+    }
+    // As suggested by RFC 4330, timestamps with the most significant bit clear are taken to be
+    // in NTP era 1, which begins in 2036.
+    let secs = if secs & (1 << 31) == 0 {
+        secs + (1 << 32)
+    } else {
+        secs
+    };
+    let micros = (fraction * 1_000_000) >> 32;
+    Ok(SystemTime::from_micros_since_unix_epoch(
+        (secs - NTP_UNIX_EPOCH_OFFSET) * 1_000_000 + micros,
+    ))
+}
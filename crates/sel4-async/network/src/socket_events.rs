@@ -0,0 +1,159 @@
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use core::fmt;
+
+use log::debug;
+use smoltcp::{
+    iface::{SocketHandle, SocketSet},
+    socket::{tcp, AnySocket},
+    time::{Duration, Instant},
+};
+
+pub(crate) const DEFAULT_SEND_STALL_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Activity on a [`Socket`](crate::Socket) observed by a [`SocketEventHook`].
+///
+/// smoltcp does not report retransmissions or changes in the peer's receive window. Instead,
+/// [`SocketEvent::SendProgressStalled`] indicates that queued data has not been acknowledged for
+/// some time, which is how both manifest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SocketEvent {
+    /// A TCP socket changed state.
+    StateChanged { from: tcp::State, to: tcp::State },
+    /// A receive had to wait because the receive buffer was empty.
+    RecvStalled,
+    /// A send had to wait because the transmit buffer was full.
+    SendStalled,
+    /// None of the data queued on a TCP socket has been acknowledged for the threshold set with
+    /// [`SharedNetwork::set_send_stall_threshold`](crate::SharedNetwork::set_send_stall_threshold).
+    SendProgressStalled { queued: usize },
+    /// Data was acknowledged after a [`SocketEvent::SendProgressStalled`].
+    SendProgressResumed { stalled_for: Duration },
+}
+
+impl fmt::Display for SocketEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::StateChanged { from, to } => write!(f, "state {from} -> {to}"),
+            Self::RecvStalled => write!(f, "recv stalled"),
+            Self::SendStalled => write!(f, "send stalled"),
+            Self::SendProgressStalled { queued } => {
+                write!(f, "send progress stalled with {queued} bytes queued")
+            }
+            Self::SendProgressResumed { stalled_for } => {
+                write!(f, "send progress resumed after {stalled_for}")
+            }
+        }
+    }
+}
+
+pub type SocketEventHook = Rc<dyn Fn(SocketHandle, &SocketEvent)>;
+
+/// A [`SocketEventHook`] which logs each event at the debug level.
+pub fn log_socket_event(handle: SocketHandle, event: &SocketEvent) {
+    debug!("socket {handle}: {event}");
+}
+
+pub(crate) struct SocketEvents {
+    traced: BTreeMap<SocketHandle, Traced>,
+    send_stall_threshold: Duration,
+}
+
+struct Traced {
+    hook: SocketEventHook,
+    tcp: Option<TcpTrace>,
+}
+
+struct TcpTrace {
+    state: tcp::State,
+    send_queue: usize,
+    // When the send queue last shrank or became non-empty.
+    progress_at: Instant,
+    stalled: bool,
+}
+
+impl SocketEvents {
+    pub(crate) fn new() -> Self {
+        Self {
+            traced: BTreeMap::new(),
+            send_stall_threshold: DEFAULT_SEND_STALL_THRESHOLD,
+        }
+    }
+
+    pub(crate) fn set_send_stall_threshold(&mut self, threshold: Duration) {
+        self.send_stall_threshold = threshold;
+    }
+
+    pub(crate) fn set_hook(&mut self, handle: SocketHandle, hook: Option<SocketEventHook>) {
+        match hook {
+            Some(hook) => {
+                let tcp = self.traced.remove(&handle).and_then(|traced| traced.tcp);
+                self.traced.insert(handle, Traced { hook, tcp });
+            }
+            None => {
+                self.traced.remove(&handle);
+            }
+        }
+    }
+
+    pub(crate) fn emit(&self, handle: SocketHandle, event: SocketEvent) {
+        if let Some(traced) = self.traced.get(&handle) {
+            (traced.hook)(handle, &event);
+        }
+    }
+
+    // Compares the state of each traced TCP socket with that observed at the previous call.
+    pub(crate) fn observe(&mut self, socket_set: &SocketSet, now: Instant) {
+        if self.traced.is_empty() {
+            return;
+        }
+        for (handle, socket) in socket_set.iter() {
+            let Some(traced) = self.traced.get_mut(&handle) else {
+                continue;
+            };
+            let Some(socket) = tcp::Socket::downcast(socket) else {
+                continue;
+            };
+            let state = socket.state();
+            let send_queue = socket.send_queue();
+            let trace = traced.tcp.get_or_insert(TcpTrace {
+                state,
+                send_queue: 0,
+                progress_at: now,
+                stalled: false,
+            });
+            if state != trace.state {
+                (traced.hook)(
+                    handle,
+                    &SocketEvent::StateChanged {
+                        from: trace.state,
+                        to: state,
+                    },
+                );
+                trace.state = state;
+            }
+            if send_queue < trace.send_queue || (send_queue > 0 && trace.send_queue == 0) {
+                if trace.stalled {
+                    (traced.hook)(
+                        handle,
+                        &SocketEvent::SendProgressResumed {
+                            stalled_for: now - trace.progress_at,
+                        },
+                    );
+                    trace.stalled = false;
+                }
+                trace.progress_at = now;
+            } else if send_queue > 0
+                && !trace.stalled
+                && now >= trace.progress_at + self.send_stall_threshold
+            {
+                (traced.hook)(
+                    handle,
+                    &SocketEvent::SendProgressStalled { queued: send_queue },
+                );
+                trace.stalled = true;
+            }
+            trace.send_queue = send_queue;
+        }
+    }
+}
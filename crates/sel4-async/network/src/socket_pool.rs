@@ -0,0 +1,314 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow;
+use core::cell::RefCell;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::task::{Poll, Waker};
+
+use futures::prelude::*;
+use smoltcp::socket::tcp;
+
+use crate::{SharedNetwork, SocketMetrics, TcpSocket, TcpSocketError};
+
+/// Sizing parameters for a [`TcpSocketPool`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TcpSocketPoolConfig {
+    pub num_sockets: usize,
+    pub rx_buffer_size: usize,
+    pub tx_buffer_size: usize,
+}
+
+/// A fixed set of TCP sockets whose buffers are carved out of a single arena, allocated once, up
+/// front.
+///
+/// Sockets are lent out as [`PooledTcpSocket`]s, which return themselves to the pool when dropped.
+/// Because each connection on a listening port requires its own socket, a pool of `N` sockets
+/// can be used to accept up to `N` concurrent connections.
+///
+/// A socket which is dropped after being closed is not lent out again until its connection has
+/// reached `CLOSED` or `TIME-WAIT`, so that the close can complete gracefully.
+#[derive(Clone)]
+pub struct TcpSocketPool {
+    inner: Rc<RefCell<TcpSocketPoolInner>>,
+}
+
+struct TcpSocketPoolInner {
+    config: TcpSocketPoolConfig,
+    free: Vec<TcpSocket>,
+    // Sockets which were returned while their connection was still closing.
+    closing: Vec<TcpSocket>,
+    waiters: BTreeMap<u64, Waker>,
+    next_waiter_id: u64,
+    retired_metrics: SocketMetrics,
+    num_acquire_stalls: u64,
+    // Must be declared after the sockets, whose buffers borrow from it, so that it is dropped
+    // after they have been removed from the network.
+    _arena: Arena,
+}
+
+impl TcpSocketPool {
+    /// # Panics
+    ///
+    /// Panics if the total size of the sockets' buffers overflows `usize`.
+    pub fn new(network: &SharedNetwork, config: TcpSocketPoolConfig) -> Self {
+        let arena_size = config
+            .rx_buffer_size
+            .checked_add(config.tx_buffer_size)
+            .and_then(|size| size.checked_mul(config.num_sockets))
+            .unwrap();
+        let arena = Arena::new(arena_size);
+        // SAFETY: the sockets are removed from the network before the arena is freed.
+        let mut rest = unsafe { arena.buffer() };
+        let free = (0..config.num_sockets)
+            .map(|_| {
+                let (rx_buffer, tail) = mem::take(&mut rest).split_at_mut(config.rx_buffer_size);
+                let (tx_buffer, tail) = tail.split_at_mut(config.tx_buffer_size);
+                rest = tail;
+                network.new_socket(tcp::Socket::new(
+                    tcp::SocketBuffer::new(rx_buffer),
+                    tcp::SocketBuffer::new(tx_buffer),
+                ))
+            })
+            .collect();
+        Self {
+            inner: Rc::new(RefCell::new(TcpSocketPoolInner {
+                config,
+                free,
+                closing: Vec::new(),
+                waiters: BTreeMap::new(),
+                next_waiter_id: 0,
+                retired_metrics: SocketMetrics::default(),
+                num_acquire_stalls: 0,
+                _arena: arena,
+            })),
+        }
+    }
+
+    pub fn config(&self) -> TcpSocketPoolConfig {
+        self.inner.borrow().config
+    }
+
+    pub fn num_free(&self) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.reap();
+        inner.free.len()
+    }
+
+    /// Number of sockets which have been returned to the pool, but whose connections have not yet
+    /// finished closing.
+    pub fn num_closing(&self) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.reap();
+        inner.closing.len()
+    }
+
+    pub fn num_in_use(&self) -> usize {
+        let inner = self.inner.borrow();
+        inner.config.num_sockets - inner.free.len() - inner.closing.len()
+    }
+
+    /// Number of calls to [`TcpSocketPool::acquire`] which had to wait for a socket to be returned
+    /// to the pool.
+    pub fn num_acquire_stalls(&self) -> u64 {
+        self.inner.borrow().num_acquire_stalls
+    }
+
+    /// Sum of the metrics of all sockets which have been returned to the pool.
+    pub fn retired_metrics(&self) -> SocketMetrics {
+        self.inner.borrow().retired_metrics
+    }
+
+    pub fn try_acquire(&self) -> Option<PooledTcpSocket> {
+        let mut inner = self.inner.borrow_mut();
+        inner.reap();
+        let socket = inner.free.pop()?;
+        if !inner.closing.is_empty() {
+            // The closing sockets may hold the waker of the task acquiring this socket, which
+            // will no longer be waiting. Let the remaining waiters register theirs.
+            inner.wake_waiters();
+        }
+        drop(inner);
+        Some(PooledTcpSocket::new(socket, self.clone()))
+    }
+
+    pub async fn acquire(&self) -> PooledTcpSocket {
+        let mut waiter = Waiter {
+            pool: self,
+            id: None,
+        };
+        future::poll_fn(|cx| {
+            if let Some(socket) = self.try_acquire() {
+                return Poll::Ready(socket);
+            }
+            let inner = &mut *self.inner.borrow_mut();
+            let id = *waiter.id.get_or_insert_with(|| {
+                inner.num_acquire_stalls += 1;
+                inner.next_waiter_id += 1;
+                inner.next_waiter_id
+            });
+            inner.waiters.insert(id, cx.waker().clone());
+            for socket in &mut inner.closing {
+                socket.with_mut(|socket| socket.register_recv_waker(cx.waker()));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Waits for a free socket, and then waits for it to accept a connection on `port`.
+    pub async fn accept(&self, port: u16) -> Result<PooledTcpSocket, TcpSocketError> {
+        let mut socket = self.acquire().await;
+        socket.accept(port).await?;
+        Ok(socket)
+    }
+
+    fn release(&self, mut socket: TcpSocket) {
+        let state = socket.with(|socket| socket.state());
+        let mut inner = self.inner.borrow_mut();
+        match state {
+            tcp::State::Closed | tcp::State::TimeWait => inner.free.push(socket),
+            tcp::State::FinWait1
+            | tcp::State::FinWait2
+            | tcp::State::Closing
+            | tcp::State::LastAck => inner.closing.push(socket),
+            tcp::State::Listen
+            | tcp::State::SynSent
+            | tcp::State::SynReceived
+            | tcp::State::Established
+            | tcp::State::CloseWait => {
+                socket.abort();
+                inner.free.push(socket);
+            }
+        }
+        inner.wake_waiters();
+    }
+}
+
+impl TcpSocketPoolInner {
+    fn reap(&mut self) {
+        while let Some(i) = self
+            .closing
+            .iter()
+            .position(|socket| !socket.with(|socket| socket.is_open()))
+        {
+            let socket = self.closing.swap_remove(i);
+            self.free.push(socket);
+        }
+    }
+
+    fn wake_waiters(&self) {
+        for waker in self.waiters.values() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+// Deregisters a caller of `TcpSocketPool::acquire` when it is done waiting, including when the
+// future is dropped.
+struct Waiter<'a> {
+    pool: &'a TcpSocketPool,
+    id: Option<u64>,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.pool.inner.borrow_mut().waiters.remove(&id);
+        }
+    }
+}
+
+struct Arena {
+    ptr: *mut [u8],
+}
+
+impl Arena {
+    fn new(size: usize) -> Self {
+        Self {
+            ptr: Box::into_raw(vec![0; size].into_boxed_slice()),
+        }
+    }
+
+    // SAFETY: must be called at most once, and the returned slice must not be used after the
+    // arena is dropped.
+    unsafe fn buffer(&self) -> &'static mut [u8] {
+        &mut *self.ptr
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.ptr) })
+    }
+}
+
+/// A [`TcpSocket`] on loan from a [`TcpSocketPool`].
+///
+/// On drop, the socket is returned to the pool. If its connection is still open, and has not been
+/// closed with [`close`](crate::Socket::close), it is aborted.
+pub struct PooledTcpSocket {
+    socket: Option<TcpSocket>,
+    pool: TcpSocketPool,
+}
+
+impl PooledTcpSocket {
+    fn new(socket: TcpSocket, pool: TcpSocketPool) -> Self {
+        Self {
+            socket: Some(socket),
+            pool,
+        }
+    }
+
+    pub fn pool(&self) -> &TcpSocketPool {
+        &self.pool
+    }
+}
+
+impl Deref for PooledTcpSocket {
+    type Target = TcpSocket;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledTcpSocket {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket.as_mut().unwrap()
+    }
+}
+
+impl borrow::Borrow<TcpSocket> for PooledTcpSocket {
+    fn borrow(&self) -> &TcpSocket {
+        self
+    }
+}
+
+impl borrow::BorrowMut<TcpSocket> for PooledTcpSocket {
+    fn borrow_mut(&mut self) -> &mut TcpSocket {
+        self
+    }
+}
+
+impl Drop for PooledTcpSocket {
+    fn drop(&mut self) {
+        let mut socket = self.socket.take().unwrap();
+        accumulate_metrics(
+            &mut self.pool.inner.borrow_mut().retired_metrics,
+            socket.metrics(),
+        );
+        socket.reset_metrics();
+        self.pool.release(socket);
+    }
+}
+
+fn accumulate_metrics(acc: &mut SocketMetrics, metrics: &SocketMetrics) {
+    acc.rx_bytes += metrics.rx_bytes;
+    acc.tx_bytes += metrics.tx_bytes;
+    acc.rx_stalls += metrics.rx_stalls;
+    acc.tx_stalls += metrics.tx_stalls;
+}
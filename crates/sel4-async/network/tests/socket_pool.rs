@@ -0,0 +1,155 @@
+use core::cell::RefCell;
+use core::pin::pin;
+use core::task::{Context, Poll};
+use std::rc::Rc;
+
+use futures::task::{noop_waker_ref, LocalSpawnExt};
+use futures::FutureExt;
+use sel4_async_network::{
+    DhcpOverrides, SharedNetwork, TcpSocketError, TcpSocketPool, TcpSocketPoolConfig,
+};
+use sel4_async_single_threaded_executor::LocalPool;
+use smoltcp::iface::Config;
+use smoltcp::phy::{Loopback, Medium};
+use smoltcp::socket::tcp;
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, HardwareAddress, Ipv4Address, Ipv4Cidr};
+
+const CONFIG: TcpSocketPoolConfig = TcpSocketPoolConfig {
+    num_sockets: 2,
+    rx_buffer_size: 1024,
+    tx_buffer_size: 512,
+};
+
+fn loopback_network() -> (SharedNetwork, Loopback) {
+    let mut device = Loopback::new(Medium::Ethernet);
+    let network = SharedNetwork::new(
+        Config::new(HardwareAddress::Ethernet(EthernetAddress([
+            0x02, 0, 0, 0, 0, 1,
+        ]))),
+        DhcpOverrides {
+            address: Some(Ipv4Cidr::new(Ipv4Address::new(127, 0, 0, 1), 8)),
+            ..Default::default()
+        },
+        &mut device,
+        Instant::ZERO,
+    );
+    (network, device)
+}
+
+#[test]
+fn sockets_use_configured_buffers() {
+    let (network, _device) = loopback_network();
+    let pool = TcpSocketPool::new(&network, CONFIG);
+    let a = pool.try_acquire().unwrap();
+    let b = pool.try_acquire().unwrap();
+    assert!(pool.try_acquire().is_none());
+    assert_eq!(pool.num_in_use(), 2);
+    for socket in [&a, &b] {
+        assert_eq!(
+            socket.with(|socket| (socket.recv_capacity(), socket.send_capacity())),
+            (CONFIG.rx_buffer_size, CONFIG.tx_buffer_size)
+        );
+    }
+    drop(a);
+    assert_eq!(pool.num_free(), 1);
+    assert_eq!(pool.num_in_use(), 1);
+}
+
+#[test]
+fn stall_is_counted_once_per_wait() {
+    let (network, _device) = loopback_network();
+    let pool = TcpSocketPool::new(
+        &network,
+        TcpSocketPoolConfig {
+            num_sockets: 1,
+            ..CONFIG
+        },
+    );
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    let socket = pool.try_acquire().unwrap();
+    let mut acquire = pin!(pool.acquire());
+    for _ in 0..3 {
+        assert!(acquire.poll_unpin(&mut cx).is_pending());
+    }
+    assert_eq!(pool.num_acquire_stalls(), 1);
+
+    drop(socket);
+    let socket = match acquire.poll_unpin(&mut cx) {
+        Poll::Ready(socket) => socket,
+        Poll::Pending => panic!(),
+    };
+    assert_eq!(pool.num_acquire_stalls(), 1);
+
+    // An abandoned wait still counts.
+    assert!(pin!(pool.acquire()).poll_unpin(&mut cx).is_pending());
+    assert_eq!(pool.num_acquire_stalls(), 2);
+    drop(socket);
+    assert!(pool.try_acquire().is_some());
+}
+
+#[test]
+fn closed_socket_is_not_aborted() {
+    let (network, mut device) = loopback_network();
+    let pool = TcpSocketPool::new(&network, CONFIG);
+    let mut executor = LocalPool::new();
+    let server_result = Rc::new(RefCell::new(None));
+
+    executor
+        .spawner()
+        .spawn_local({
+            let pool = pool.clone();
+            let server_result = server_result.clone();
+            async move {
+                let mut socket = pool.accept(80).await.unwrap();
+                let mut received = vec![];
+                let mut buf = [0; 64];
+                let err = loop {
+                    match socket.recv(&mut buf).await {
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                        Err(err) => break err,
+                    }
+                };
+                *server_result.borrow_mut() = Some((received, err));
+                socket.close().await.unwrap();
+            }
+        })
+        .unwrap();
+
+    executor
+        .spawner()
+        .spawn_local({
+            let pool = pool.clone();
+            async move {
+                let mut socket = pool.acquire().await;
+                socket
+                    .connect((Ipv4Address::new(127, 0, 0, 1), 80), 49152)
+                    .await
+                    .unwrap();
+                socket.send_all(b"hello").await.unwrap();
+                socket.close().await.unwrap();
+            }
+        })
+        .unwrap();
+
+    let mut now = Instant::ZERO;
+    for _ in 0..100 {
+        let _ = executor.run_all_until_stalled();
+        network.poll(now, &mut device);
+        now += smoltcp::time::Duration::from_millis(1);
+    }
+
+    assert_eq!(
+        server_result.take(),
+        Some((
+            b"hello".to_vec(),
+            TcpSocketError::InvalidState(tcp::State::CloseWait)
+        ))
+    );
+    assert_eq!(pool.num_in_use(), 0);
+    assert_eq!(pool.num_closing(), 0);
+    assert_eq!(pool.num_free(), 2);
+    assert_eq!(pool.retired_metrics().tx_bytes, 5);
+    assert_eq!(pool.retired_metrics().rx_bytes, 5);
+}
@@ -0,0 +1,99 @@
+//! A simple format for certificate chains and private keys provisioned at build time, for example
+//! by a capDL fill entry which copies the contents of a file into a frame mapped by the component.
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! magic: [u8; 4] = b"sTLS"
+//! num_certs: u32
+//! num_certs * { len: u32, DER-encoded X.509 certificate: [u8; len] }
+//! key_len: u32
+//! DER-encoded PKCS #8 private key: [u8; key_len]
+//! ```
+//!
+//! Trailing bytes, such as the zero padding at the end of a frame, are ignored.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+const MAGIC: &[u8; 4] = b"sTLS";
+
+#[derive(Debug)]
+pub struct Credentials {
+    pub cert_chain: Vec<CertificateDer<'static>>,
+    pub key: PrivatePkcs8KeyDer<'static>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CredentialsError {
+    BadMagic,
+    Truncated,
+}
+
+impl fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "bad magic"),
+            Self::Truncated => write!(f, "truncated"),
+        }
+    }
+}
+
+impl Credentials {
+    pub fn parse(mut bytes: &[u8]) -> Result<Self, CredentialsError> {
+        if take(&mut bytes, MAGIC.len())? != MAGIC {
+            return Err(CredentialsError::BadMagic);
+        }
+        let num_certs = take_u32(&mut bytes)?;
+        let cert_chain = (0..num_certs)
+            .map(|_| Ok(CertificateDer::from(take_chunk(&mut bytes)?.to_vec())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = PrivatePkcs8KeyDer::from(take_chunk(&mut bytes)?.to_vec());
+        Ok(Self { cert_chain, key })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        put_u32(&mut buf, self.cert_chain.len());
+        for cert in &self.cert_chain {
+            put_chunk(&mut buf, cert);
+        }
+        put_chunk(&mut buf, self.key.secret_pkcs8_der());
+        buf
+    }
+
+    /// For use with `ConfigBuilder::with_single_cert`.
+    pub fn into_parts(self) -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
+        (self.cert_chain, self.key.into())
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], CredentialsError> {
+    if bytes.len() < n {
+        return Err(CredentialsError::Truncated);
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<u32, CredentialsError> {
+    Ok(u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+}
+
+fn take_chunk<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], CredentialsError> {
+    let n = take_u32(bytes)?;
+    take(bytes, n.try_into().unwrap())
+}
+
+fn put_u32(buf: &mut Vec<u8>, n: usize) {
+    buf.extend_from_slice(&u32::try_from(n).unwrap().to_le_bytes());
+}
+
+fn put_chunk(buf: &mut Vec<u8>, chunk: &[u8]) {
+    put_u32(buf, chunk.len());
+    buf.extend_from_slice(chunk);
+}
@@ -0,0 +1,349 @@
+//! TLS over [`TcpSocket`], using [rustls](https://github.com/rustls/rustls)'s `no_std`
+//! unbuffered API.
+//!
+//! This crate does not select a cryptography backend. Callers construct a [`ServerConfig`] or
+//! [`ClientConfig`] with a [`rustls::crypto::CryptoProvider`] and a
+//! [`rustls::time_provider::TimeProvider`] suitable for their environment, using
+//! `ServerConfig::builder_with_details` or `ClientConfig::builder_with_details`. Certificates and
+//! keys can be provisioned at build time using the format described in [`credentials`].
+//!
+//! Early data is not supported.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::BorrowMut;
+use core::fmt;
+
+use rustls::client::{ClientConnectionData, UnbufferedClientConnection};
+use rustls::pki_types::ServerName;
+use rustls::server::{ServerConnectionData, UnbufferedServerConnection};
+use rustls::unbuffered::{
+    AppDataRecord, ConnectionState, EncodeError, EncryptError, UnbufferedStatus,
+};
+
+use sel4_async_network::{TcpSocket, TcpSocketError};
+
+pub mod credentials;
+
+// re-export
+pub use rustls;
+pub use rustls::{ClientConfig, ServerConfig};
+
+// The largest TLS record, including its header.
+const MAX_RECORD_SIZE: usize = 5 + 16384 + 2048;
+
+#[derive(Debug)]
+pub enum TlsError {
+    Tls(rustls::Error),
+    TcpSocket(TcpSocketError),
+    Encrypt(EncryptError),
+    /// The peer closed the TLS session before the operation could complete.
+    PeerClosed,
+    UnexpectedEarlyData,
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Tls(err) => write!(f, "TLS error: {err}"),
+            Self::TcpSocket(err) => write!(f, "TCP socket error: {err:?}"),
+            Self::Encrypt(err) => write!(f, "encryption error: {err}"),
+            Self::PeerClosed => write!(f, "peer closed TLS session"),
+            Self::UnexpectedEarlyData => write!(f, "unexpected early data"),
+        }
+    }
+}
+
+impl From<rustls::Error> for TlsError {
+    fn from(err: rustls::Error) -> Self {
+        Self::Tls(err)
+    }
+}
+
+impl From<TcpSocketError> for TlsError {
+    fn from(err: TcpSocketError) -> Self {
+        Self::TcpSocket(err)
+    }
+}
+
+/// An unbuffered rustls connection, either [`UnbufferedServerConnection`] or
+/// [`UnbufferedClientConnection`].
+pub trait Connection: sealing::Sealed {
+    type Data;
+
+    fn process_tls_records<'c, 'i>(
+        &'c mut self,
+        incoming_tls: &'i mut [u8],
+    ) -> UnbufferedStatus<'c, 'i, Self::Data>;
+}
+
+impl Connection for UnbufferedServerConnection {
+    type Data = ServerConnectionData;
+
+    fn process_tls_records<'c, 'i>(
+        &'c mut self,
+        incoming_tls: &'i mut [u8],
+    ) -> UnbufferedStatus<'c, 'i, Self::Data> {
+        (**self).process_tls_records(incoming_tls)
+    }
+}
+
+impl Connection for UnbufferedClientConnection {
+    type Data = ClientConnectionData;
+
+    fn process_tls_records<'c, 'i>(
+        &'c mut self,
+        incoming_tls: &'i mut [u8],
+    ) -> UnbufferedStatus<'c, 'i, Self::Data> {
+        (**self).process_tls_records(incoming_tls)
+    }
+}
+
+mod sealing {
+    use super::*;
+
+    pub trait Sealed {}
+
+    impl Sealed for UnbufferedServerConnection {}
+    impl Sealed for UnbufferedClientConnection {}
+}
+
+pub type ServerTlsStream<S = TcpSocket> = TlsStream<UnbufferedServerConnection, S>;
+
+pub type ClientTlsStream<S = TcpSocket> = TlsStream<UnbufferedClientConnection, S>;
+
+pub struct TlsStream<C, S = TcpSocket> {
+    conn: C,
+    socket: S,
+    incoming: Box<[u8]>,
+    incoming_len: usize,
+    outgoing: Vec<u8>,
+    plaintext: VecDeque<u8>,
+    peer_closed: bool,
+}
+
+#[derive(Copy, Clone)]
+enum Goal<'a> {
+    Handshake,
+    Recv,
+    Send(&'a [u8]),
+    Close,
+}
+
+impl<S: BorrowMut<TcpSocket>> TlsStream<UnbufferedServerConnection, S> {
+    /// Performs the server side of the handshake over an established connection.
+    pub async fn accept(socket: S, config: Arc<ServerConfig>) -> Result<Self, TlsError> {
+        Self::handshake(UnbufferedServerConnection::new(config)?, socket).await
+    }
+}
+
+impl<S: BorrowMut<TcpSocket>> TlsStream<UnbufferedClientConnection, S> {
+    /// Performs the client side of the handshake over an established connection.
+    pub async fn connect(
+        socket: S,
+        config: Arc<ClientConfig>,
+        server_name: ServerName<'static>,
+    ) -> Result<Self, TlsError> {
+        Self::handshake(
+            UnbufferedClientConnection::new(config, server_name)?,
+            socket,
+        )
+        .await
+    }
+}
+
+impl<C: Connection, S: BorrowMut<TcpSocket>> TlsStream<C, S> {
+    async fn handshake(conn: C, socket: S) -> Result<Self, TlsError> {
+        let mut this = Self {
+            conn,
+            socket,
+            incoming: vec![0; MAX_RECORD_SIZE].into_boxed_slice(),
+            incoming_len: 0,
+            outgoing: Vec::new(),
+            plaintext: VecDeque::new(),
+            peer_closed: false,
+        };
+        this.drive(Goal::Handshake).await?;
+        if this.peer_closed {
+            return Err(TlsError::PeerClosed);
+        }
+        Ok(this)
+    }
+
+    pub fn connection(&self) -> &C {
+        &self.conn
+    }
+
+    pub fn socket_mut(&mut self) -> &mut TcpSocket {
+        self.socket.borrow_mut()
+    }
+
+    /// Returns `Ok(0)` once the peer has closed the TLS session.
+    pub async fn recv(&mut self, buf: &mut [u8]) -> Result<usize, TlsError> {
+        if self.plaintext.is_empty() && !self.peer_closed {
+            self.drive(Goal::Recv).await?;
+        }
+        let n = buf.len().min(self.plaintext.len());
+        for (dst, src) in buf.iter_mut().zip(self.plaintext.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+
+    pub async fn send_all(&mut self, buf: &[u8]) -> Result<(), TlsError> {
+        if self.peer_closed {
+            return Err(TlsError::PeerClosed);
+        }
+        self.drive(Goal::Send(buf)).await?;
+        if self.peer_closed {
+            return Err(TlsError::PeerClosed);
+        }
+        Ok(())
+    }
+
+    /// Sends a `close_notify` alert and then closes the underlying socket.
+    pub async fn close(&mut self) -> Result<(), TlsError> {
+        if !self.peer_closed {
+            self.drive(Goal::Close).await?;
+        }
+        self.socket.borrow_mut().close().await?;
+        Ok(())
+    }
+
+    async fn drive(&mut self, goal: Goal<'_>) -> Result<(), TlsError> {
+        let Self {
+            conn,
+            socket,
+            incoming,
+            incoming_len,
+            outgoing,
+            plaintext,
+            peer_closed,
+        } = self;
+        let socket = socket.borrow_mut();
+        loop {
+            let UnbufferedStatus { mut discard, state } =
+                conn.process_tls_records(&mut incoming[..*incoming_len]);
+            let mut done = false;
+            let mut need_more = false;
+            match state? {
+                ConnectionState::ReadTraffic(mut state) => {
+                    while let Some(record) = state.next_record() {
+                        let AppDataRecord {
+                            discard: record_discard,
+                            payload,
+                        } = record?;
+                        discard += record_discard;
+                        plaintext.extend(payload);
+                    }
+                    done = matches!(goal, Goal::Recv);
+                }
+                ConnectionState::EncodeTlsData(mut state) => {
+                    append_encoded(
+                        outgoing,
+                        |buf| state.encode(buf),
+                        |err| match err {
+                            EncodeError::InsufficientSize(err) => Some(err.required_size),
+                            _ => None,
+                        },
+                    )
+                    .unwrap();
+                }
+                ConnectionState::TransmitTlsData(state) => {
+                    socket.send_all(outgoing).await?;
+                    outgoing.clear();
+                    state.done();
+                }
+                ConnectionState::BlockedHandshake => {
+                    need_more = true;
+                }
+                ConnectionState::WriteTraffic(mut state) => match goal {
+                    Goal::Handshake => {
+                        done = true;
+                    }
+                    Goal::Recv => {
+                        done = !plaintext.is_empty();
+                        need_more = !done;
+                    }
+                    Goal::Send(data) => {
+                        append_encoded(
+                            outgoing,
+                            |buf| state.encrypt(data, buf),
+                            |err| match err {
+                                EncryptError::InsufficientSize(err) => Some(err.required_size),
+                                _ => None,
+                            },
+                        )
+                        .map_err(TlsError::Encrypt)?;
+                        socket.send_all(outgoing).await?;
+                        outgoing.clear();
+                        done = true;
+                    }
+                    Goal::Close => {
+                        append_encoded(
+                            outgoing,
+                            |buf| state.queue_close_notify(buf),
+                            |err| match err {
+                                EncryptError::InsufficientSize(err) => Some(err.required_size),
+                                _ => None,
+                            },
+                        )
+                        .map_err(TlsError::Encrypt)?;
+                        socket.send_all(outgoing).await?;
+                        outgoing.clear();
+                        done = true;
+                    }
+                },
+                ConnectionState::Closed => {
+                    *peer_closed = true;
+                    done = true;
+                }
+                _ => {
+                    return Err(TlsError::UnexpectedEarlyData);
+                }
+            }
+            if discard > 0 {
+                incoming.copy_within(discard..*incoming_len, 0);
+                *incoming_len -= discard;
+            }
+            if done {
+                return Ok(());
+            }
+            if need_more {
+                assert!(*incoming_len < incoming.len());
+                *incoming_len += socket.recv(&mut incoming[*incoming_len..]).await?;
+            }
+        }
+    }
+}
+
+// Appends the output of `f` to `buf`, growing `buf` as required.
+fn append_encoded<E>(
+    buf: &mut Vec<u8>,
+    mut f: impl FnMut(&mut [u8]) -> Result<usize, E>,
+    required_size: impl Fn(&E) -> Option<usize>,
+) -> Result<(), E> {
+    let start = buf.len();
+    loop {
+        match f(&mut buf[start..]) {
+            Ok(n) => {
+                buf.truncate(start + n);
+                return Ok(());
+            }
+            Err(err) => match required_size(&err) {
+                Some(n) => buf.resize(start + n, 0),
+                None => {
+                    buf.truncate(start);
+                    return Err(err);
+                }
+            },
+        }
+    }
+}
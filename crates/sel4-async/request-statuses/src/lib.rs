@@ -17,6 +17,7 @@ struct RequestEntry<V, T> {
 enum RequestStatus<T> {
     Complete(T),
     Incomplete(Option<Waker>),
+    Cancelled,
 }
 
 pub struct Completion<K, V, T> {
@@ -30,6 +31,7 @@ pub enum Error {
     NotPresent,
     AlreadyPresent,
     AlreadyComplete,
+    Cancelled,
 }
 
 impl<K: Ord, V, T> RequestStatuses<K, V, T> {
@@ -55,12 +57,50 @@ impl<K: Ord, V, T> RequestStatuses<K, V, T> {
         self.0.get(key).map(|entry| &entry.value)
     }
 
-    pub fn mark_complete(&mut self, key: &K, complete: T) -> Result<(), Error> {
-        self.0
-            .get_mut(key)
-            .ok_or(Error::NotPresent)?
-            .status
-            .mark_complete(complete)
+    /// Marks the request for `key` as complete. If the request had been cancelled, it is removed
+    /// and returned, so that the caller can reclaim any resources it holds.
+    pub fn mark_complete(
+        &mut self,
+        key: &K,
+        complete: T,
+    ) -> Result<Option<Completion<K, V, T>>, Error> {
+        let entry = self.0.get_mut(key).ok_or(Error::NotPresent)?;
+        if let RequestStatus::Cancelled = entry.status {
+            let (key, entry) = self.0.remove_entry(key).unwrap();
+            return Ok(Some(Completion {
+                key,
+                value: entry.value,
+                complete,
+            }));
+        }
+        entry.status.mark_complete(complete)?;
+        Ok(None)
+    }
+
+    /// Cancels the request for `key`, for use when whoever was awaiting it loses interest. If the
+    /// request is already complete, it is removed and returned. Otherwise, it is kept until it is
+    /// passed to [`RequestStatuses::mark_complete`], which will then return it.
+    pub fn cancel(&mut self, key: &K) -> Result<Option<Completion<K, V, T>>, Error> {
+        let entry = self.0.get_mut(key).ok_or(Error::NotPresent)?;
+        match entry.status {
+            RequestStatus::Complete(_) => {
+                let (key, entry) = self.0.remove_entry(key).unwrap();
+                let complete = match entry.status {
+                    RequestStatus::Complete(complete) => complete,
+                    _ => unreachable!(),
+                };
+                Ok(Some(Completion {
+                    key,
+                    value: entry.value,
+                    complete,
+                }))
+            }
+            RequestStatus::Incomplete(_) => {
+                entry.status = RequestStatus::Cancelled;
+                Ok(None)
+            }
+            RequestStatus::Cancelled => Err(Error::Cancelled),
+        }
     }
 
     pub fn poll(&mut self, key: &K, waker: &Waker) -> Result<Poll<Completion<K, V, T>>, Error> {
@@ -72,6 +112,16 @@ impl<K: Ord, V, T> RequestStatuses<K, V, T> {
                 value,
                 complete,
             }),
+            RequestStatus::Cancelled => {
+                self.0.insert(
+                    key,
+                    RequestEntry {
+                        value,
+                        status: RequestStatus::Cancelled,
+                    },
+                );
+                return Err(Error::Cancelled);
+            }
             RequestStatus::Incomplete(_) => {
                 self.0.insert(
                     key,
@@ -90,6 +140,7 @@ impl<T> RequestStatus<T> {
     fn mark_complete(&mut self, complete: T) -> Result<(), Error> {
         match mem::replace(self, Self::Complete(complete)) {
             Self::Complete(_) => Err(Error::AlreadyComplete),
+            Self::Cancelled => unreachable!(),
             Self::Incomplete(maybe_waker) => {
                 if let Some(waker) = maybe_waker {
                     waker.wake();
@@ -0,0 +1,100 @@
+use core::task::Poll;
+use std::rc::Rc;
+
+use futures::task::noop_waker_ref;
+
+use sel4_async_request_statuses::{Completion, Error, RequestStatuses};
+
+// Stands in for the bounce buffer and queue permit held by an in-flight request. Reclaiming it
+// consumes it, so the strong count of the shared `Rc` shows whether it has been reclaimed.
+struct Resources(Rc<()>);
+
+#[derive(Default)]
+struct Pool {
+    resources: Rc<()>,
+    num_reclaimed: usize,
+}
+
+impl Pool {
+    fn take(&self) -> Resources {
+        Resources(self.resources.clone())
+    }
+
+    fn reclaim(&mut self, completion: Option<Completion<u32, Resources, &'static str>>) {
+        if let Some(completion) = completion {
+            assert_eq!(completion.key, 1);
+            assert_eq!(completion.complete, "ok");
+            drop(completion.value);
+            self.num_reclaimed += 1;
+        }
+    }
+
+    fn num_outstanding(&self) -> usize {
+        Rc::strong_count(&self.resources) - 1
+    }
+}
+
+fn poll(statuses: &mut RequestStatuses<u32, Resources, &'static str>) -> Result<Poll<()>, Error> {
+    statuses
+        .poll(&1, noop_waker_ref())
+        .map(|poll| poll.map(|_| ()))
+}
+
+#[test]
+fn cancel_before_complete() {
+    let mut pool = Pool::default();
+    let mut statuses = RequestStatuses::new();
+    statuses.add(1, pool.take()).unwrap();
+    assert_eq!(poll(&mut statuses), Ok(Poll::Pending));
+
+    // The device still owns the resources, so they must not be reclaimed yet.
+    let completion = statuses.cancel(&1).unwrap();
+    assert!(completion.is_none());
+    pool.reclaim(completion);
+    assert_eq!(pool.num_outstanding(), 1);
+    assert_eq!(poll(&mut statuses), Err(Error::Cancelled));
+    assert_eq!(statuses.cancel(&1).err(), Some(Error::Cancelled));
+
+    let completion = statuses.mark_complete(&1, "ok").unwrap();
+    assert!(completion.is_some());
+    pool.reclaim(completion);
+    assert_eq!(pool.num_reclaimed, 1);
+    assert_eq!(pool.num_outstanding(), 0);
+
+    assert_eq!(
+        statuses.mark_complete(&1, "ok").err(),
+        Some(Error::NotPresent)
+    );
+    assert_eq!(statuses.cancel(&1).err(), Some(Error::NotPresent));
+    assert_eq!(poll(&mut statuses), Err(Error::NotPresent));
+    assert_eq!(pool.num_reclaimed, 1);
+}
+
+#[test]
+fn cancel_after_complete() {
+    let mut pool = Pool::default();
+    let mut statuses = RequestStatuses::new();
+    statuses.add(1, pool.take()).unwrap();
+    assert_eq!(poll(&mut statuses), Ok(Poll::Pending));
+
+    let completion = statuses.mark_complete(&1, "ok").unwrap();
+    assert!(completion.is_none());
+    pool.reclaim(completion);
+    assert_eq!(pool.num_outstanding(), 1);
+
+    // Whoever was awaiting the request lost interest before polling it again.
+    let completion = statuses.cancel(&1).unwrap();
+    assert!(completion.is_some());
+    pool.reclaim(completion);
+    assert_eq!(pool.num_reclaimed, 1);
+    assert_eq!(pool.num_outstanding(), 0);
+
+    assert!(statuses.get(&1).is_none());
+    assert_eq!(
+        statuses.mark_complete(&1, "ok").err(),
+        Some(Error::NotPresent)
+    );
+    assert_eq!(statuses.cancel(&1).err(), Some(Error::NotPresent));
+    assert_eq!(poll(&mut statuses), Err(Error::NotPresent));
+    assert_eq!(pool.num_reclaimed, 1);
+}
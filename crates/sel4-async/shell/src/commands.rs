@@ -0,0 +1,128 @@
+//! Standard diagnostic commands for the runtime crates, each behind a feature of the same name as
+//! the crate it inspects.
+
+use crate::Shell;
+
+/// Registers `log-level`, which shows the maximum log level, or sets it if given an argument.
+///
+/// This is the `log` crate's global maximum, so it can lower, but not raise, the level at which a
+/// logger with its own filter was configured.
+#[cfg(feature = "log")]
+pub fn register_log_level(shell: &mut Shell) {
+    use log::LevelFilter;
+
+    shell.register(
+        "log-level",
+        "show or set the maximum log level",
+        |args, out| match args {
+            [] => writeln!(out, "{}", log::max_level()),
+            [level] => match level.parse::<LevelFilter>() {
+                Ok(level) => {
+                    log::set_max_level(level);
+                    Ok(())
+                }
+                Err(_) => writeln!(out, "invalid log level: {level}"),
+            },
+            _ => writeln!(out, "usage: log-level [off|error|warn|info|debug|trace]"),
+        },
+    );
+}
+
+/// Registers `timers`, which shows the number of pending timers and the next deadline.
+#[cfg(feature = "timers")]
+pub fn register_timers(shell: &mut Shell, timers: sel4_async_timers::SharedTimers) {
+    shell.register("timers", "show pending timers", move |_args, out| {
+        let now = timers.now();
+        writeln!(out, "now: {now}")?;
+        writeln!(out, "pending: {}", timers.num_pending())?;
+        match timers.next_deadline() {
+            Some(deadline) => writeln!(out, "next: {deadline} (in {})", deadline - now),
+            None => writeln!(out, "next: none"),
+        }
+    });
+}
+
+/// Registers `net`, which shows the interface's configuration, and `sockets`, which lists TCP and
+/// UDP sockets and their states.
+#[cfg(feature = "network")]
+pub fn register_network(shell: &mut Shell, network: sel4_async_network::SharedNetwork) {
+    use sel4_async_network::SocketSummary;
+
+    shell.register("net", "show network configuration", {
+        let network = network.clone();
+        move |_args, out| match network.config() {
+            Some(config) => {
+                writeln!(out, "address: {}", config.address)?;
+                match config.router {
+                    Some(router) => writeln!(out, "router: {router}")?,
+                    None => writeln!(out, "router: none")?,
+                }
+                for server in &config.dns_servers {
+                    writeln!(out, "dns: {server}")?;
+                }
+                Ok(())
+            }
+            None => writeln!(out, "unconfigured"),
+        }
+    });
+
+    shell.register(
+        "sockets",
+        "list sockets and their states",
+        move |_args, out| {
+            for socket in network.sockets() {
+                match socket {
+                    SocketSummary::Tcp {
+                        handle,
+                        state,
+                        local_endpoint,
+                        remote_endpoint,
+                    } => writeln!(
+                        out,
+                        "{handle} tcp {state} local={} remote={}",
+                        OrNone(local_endpoint),
+                        OrNone(remote_endpoint),
+                    )?,
+                    SocketSummary::Udp {
+                        handle,
+                        local_endpoint,
+                    } => writeln!(out, "{handle} udp local={local_endpoint}")?,
+                }
+            }
+            Ok(())
+        },
+    );
+}
+
+/// Registers a command named `name`, which shows the hit rate of a block cache, as reported by
+/// `stats`, such as a closure around [`CachedBlockIO::stats`].
+///
+/// [`CachedBlockIO::stats`]: sel4_async_block_io::CachedBlockIO::stats
+#[cfg(feature = "block-io")]
+pub fn register_block_cache(
+    shell: &mut Shell,
+    name: &str,
+    stats: impl Fn() -> sel4_async_block_io::CacheStats + 'static,
+) {
+    shell.register(name, "show block cache hit rate", move |_args, out| {
+        let stats = stats();
+        write!(out, "hits: {} misses: {}", stats.hits, stats.misses)?;
+        match stats.hit_rate() {
+            Some(rate) => writeln!(out, " hit rate: {:.1}%", rate * 100.0),
+            None => writeln!(out),
+        }
+    });
+}
+
+#[cfg(feature = "network")]
+struct OrNone<T>(Option<T>);
+
+#[cfg(feature = "network")]
+impl<T: core::fmt::Display> core::fmt::Display for OrNone<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => write!(f, "none"),
+        }
+    }
+}
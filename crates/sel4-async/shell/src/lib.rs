@@ -0,0 +1,121 @@
+//! A line editor and command shell for a terminal connected over a serial line, for use in
+//! on-device diagnostic shells.
+//!
+//! [`LineEditor`] turns a stream of input bytes into lines, echoing input and handling line
+//! editing and history. [`Shell`] dispatches each line to a registered command by its first word.
+//! [`run`] drives both from an asynchronous stream of input bytes.
+//!
+//! The [`commands`] module, which is enabled by the `block-io`, `log`, `network`, and `timers`
+//! features, provides standard commands for inspecting the corresponding runtime crates.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use futures::{Stream, StreamExt};
+
+mod line_editor;
+
+#[cfg(any(
+    feature = "block-io",
+    feature = "log",
+    feature = "network",
+    feature = "timers"
+))]
+pub mod commands;
+
+pub use line_editor::LineEditor;
+
+type CommandFn = Box<dyn FnMut(&[&str], &mut dyn fmt::Write) -> fmt::Result>;
+
+struct Command {
+    help: String,
+    f: CommandFn,
+}
+
+/// A set of commands, each named by the first word of the lines which invoke it.
+///
+/// The `help` command, which lists the registered commands, is built in.
+#[derive(Default)]
+pub struct Shell {
+    commands: BTreeMap<String, Command>,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a command, replacing any existing command of the same name. `f` is passed the
+    /// words of the line following the command's name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        help: &str,
+        f: impl FnMut(&[&str], &mut dyn fmt::Write) -> fmt::Result + 'static,
+    ) {
+        self.commands.insert(
+            name.to_string(),
+            Command {
+                help: help.to_string(),
+                f: Box::new(f),
+            },
+        );
+    }
+
+    /// Runs the command named by the first word of `line`, if any, writing its output to `out`.
+    pub fn run_line(&mut self, line: &str, out: &mut dyn fmt::Write) -> fmt::Result {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let Some((name, args)) = words.split_first() else {
+            return Ok(());
+        };
+        match self.commands.get_mut(*name) {
+            Some(command) => (command.f)(args, out),
+            None if *name == "help" => {
+                for (name, command) in &self.commands {
+                    writeln!(out, "{name:<16}{}", command.help)?;
+                }
+                writeln!(out, "{:<16}list commands", "help")
+            }
+            None => writeln!(out, "unknown command: {name}"),
+        }
+    }
+}
+
+/// Reads lines from `input` with `editor`, and runs them with `shell`, until `input` ends.
+///
+/// Output is written with `output`. Command output is translated so that `\n` becomes `\r\n`.
+pub async fn run<S: Stream<Item = u8> + Unpin>(
+    editor: &mut LineEditor,
+    shell: &mut Shell,
+    mut input: S,
+    mut output: impl FnMut(&[u8]),
+) {
+    editor.write_prompt(&mut output);
+    while let Some(b) = input.next().await {
+        if let Some(line) = editor.feed(b, &mut output) {
+            let _ = shell.run_line(&line, &mut TerminalWriter(&mut output));
+            editor.write_prompt(&mut output);
+        }
+    }
+}
+
+struct TerminalWriter<F>(F);
+
+impl<F: FnMut(&[u8])> fmt::Write for TerminalWriter<F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, part) in s.split('\n').enumerate() {
+            if i > 0 {
+                (self.0)(b"\r\n");
+            }
+            (self.0)(part.as_bytes());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+const CTRL_C: u8 = 0x03;
+const CTRL_U: u8 = 0x15;
+const ESC: u8 = 0x1b;
+
+/// A line discipline for a terminal connected over a serial line, which echoes input and supports
+/// backspace, erasing the line with `^U`, abandoning the line with `^C`, and recalling previous
+/// lines with the up and down arrow keys.
+///
+/// Only printable ASCII is accepted. Bytes are fed in one at a time with [`LineEditor::feed`],
+/// which writes any output, such as echoed characters, with the given function.
+pub struct LineEditor {
+    prompt: String,
+    max_len: usize,
+    buffer: Vec<u8>,
+    history: VecDeque<Vec<u8>>,
+    history_capacity: usize,
+    // Position while browsing history, where `history.len()` is the line being edited.
+    history_cursor: usize,
+    // The line being edited, saved while browsing history.
+    saved: Vec<u8>,
+    escape: EscapeState,
+    last_was_cr: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EscapeState {
+    None,
+    Esc,
+    Csi,
+}
+
+impl LineEditor {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            max_len: 256,
+            buffer: Vec::new(),
+            history: VecDeque::new(),
+            history_capacity: 32,
+            history_cursor: 0,
+            saved: Vec::new(),
+            escape: EscapeState::None,
+            last_was_cr: false,
+        }
+    }
+
+    /// Sets the maximum length of a line. Further input is ignored. Defaults to 256.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Sets the number of lines kept in the history. Defaults to 32.
+    pub fn history_capacity(mut self, history_capacity: usize) -> Self {
+        self.history_capacity = history_capacity;
+        self.history.truncate(history_capacity);
+        self.history_cursor = self.history.len();
+        self
+    }
+
+    /// The lines in the history, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history
+            .iter()
+            .map(|line| core::str::from_utf8(line).unwrap())
+    }
+
+    pub fn write_prompt(&self, out: &mut impl FnMut(&[u8])) {
+        out(self.prompt.as_bytes());
+    }
+
+    /// Processes one byte of input, and returns the line if the byte completes one. Completed
+    /// lines are followed by a newline, but not by a new prompt.
+    pub fn feed(&mut self, b: u8, out: &mut impl FnMut(&[u8])) -> Option<String> {
+        let last_was_cr = self.last_was_cr;
+        self.last_was_cr = b == b'\r';
+        match self.escape {
+            EscapeState::Esc => {
+                self.escape = if b == b'[' {
+                    EscapeState::Csi
+                } else {
+                    EscapeState::None
+                };
+                return None;
+            }
+            EscapeState::Csi => {
+                // Parameter and intermediate bytes precede the final byte.
+                if !(0x40..=0x7e).contains(&b) {
+                    return None;
+                }
+                self.escape = EscapeState::None;
+                match b {
+                    b'A' => self.recall(-1, out),
+                    b'B' => self.recall(1, out),
+                    _ => {}
+                }
+                return None;
+            }
+            EscapeState::None => {}
+        }
+        match b {
+            // Treat "\r\n" as a single line ending.
+            b'\n' if last_was_cr => None,
+            b'\r' | b'\n' => {
+                out(b"\r\n");
+                let line = core::mem::take(&mut self.buffer);
+                self.saved.clear();
+                if !line.is_empty() && self.history.back() != Some(&line) {
+                    if self.history.len() == self.history_capacity {
+                        self.history.pop_front();
+                    }
+                    if self.history_capacity > 0 {
+                        self.history.push_back(line.clone());
+                    }
+                }
+                self.history_cursor = self.history.len();
+                Some(String::from_utf8(line).unwrap())
+            }
+            BACKSPACE | DELETE => {
+                if self.buffer.pop().is_some() {
+                    out(b"\x08 \x08");
+                }
+                None
+            }
+            CTRL_U => {
+                self.buffer.clear();
+                self.redraw(out);
+                None
+            }
+            CTRL_C => {
+                out(b"^C\r\n");
+                self.buffer.clear();
+                self.saved.clear();
+                self.history_cursor = self.history.len();
+                self.write_prompt(out);
+                None
+            }
+            ESC => {
+                self.escape = EscapeState::Esc;
+                None
+            }
+            0x20..=0x7e => {
+                if self.buffer.len() < self.max_len {
+                    self.buffer.push(b);
+                    out(&[b]);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn recall(&mut self, direction: isize, out: &mut impl FnMut(&[u8])) {
+        let Some(cursor) = self.history_cursor.checked_add_signed(direction) else {
+            return;
+        };
+        if cursor > self.history.len() {
+            return;
+        }
+        if self.history_cursor == self.history.len() {
+            self.saved = core::mem::take(&mut self.buffer);
+        }
+        self.history_cursor = cursor;
+        self.buffer = match self.history.get(cursor) {
+            Some(line) => line.clone(),
+            None => core::mem::take(&mut self.saved),
+        };
+        self.redraw(out);
+    }
+
+    fn redraw(&self, out: &mut impl FnMut(&[u8])) {
+        // Return to the start of the line and erase it.
+        out(b"\r\x1b[K");
+        self.write_prompt(out);
+        out(&self.buffer);
+    }
+}
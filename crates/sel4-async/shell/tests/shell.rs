@@ -0,0 +1,63 @@
+use core::pin::pin;
+use core::task::Poll;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::stream;
+
+use sel4_async_shell::{run, LineEditor, Shell};
+use sel4_async_single_threaded_executor::run_until_stalled;
+
+fn feed_all(editor: &mut LineEditor, input: &[u8]) -> (Vec<String>, Vec<u8>) {
+    let mut out = vec![];
+    let lines = input
+        .iter()
+        .filter_map(|b| editor.feed(*b, &mut |bytes: &[u8]| out.extend_from_slice(bytes)))
+        .collect();
+    (lines, out)
+}
+
+#[test]
+fn editing_and_history() {
+    let mut editor = LineEditor::new("> ");
+    let (lines, out) = feed_all(&mut editor, b"lx\x7fs\r\ncaps\r\x15\n");
+    assert_eq!(lines, ["ls", "caps", ""]);
+    assert!(out.starts_with(b"lx\x08 \x08s\r\n"));
+    assert_eq!(editor.history().collect::<Vec<_>>(), ["ls", "caps"]);
+
+    // Up twice recalls "ls", down once recalls "caps".
+    let (lines, _) = feed_all(&mut editor, b"\x1b[A\x1b[A\x1b[B -l\r");
+    assert_eq!(lines, ["caps -l"]);
+
+    // Down past the newest entry restores the line being edited.
+    let (lines, _) = feed_all(&mut editor, b"me\x1b[A\x1b[Btrics\r");
+    assert_eq!(lines, ["metrics"]);
+}
+
+#[test]
+fn run_commands() {
+    let count = Rc::new(RefCell::new(0));
+    let mut shell = Shell::new();
+    shell.register("count", "increment a counter", {
+        let count = count.clone();
+        move |args, out| {
+            *count.borrow_mut() += args.len();
+            writeln!(out, "{}", count.borrow())
+        }
+    });
+    let mut editor = LineEditor::new("$ ");
+    let mut out = vec![];
+    let input = stream::iter(b"count a b\rnope\rhelp\r".iter().copied());
+    {
+        let fut = pin!(run(&mut editor, &mut shell, input, |bytes: &[u8]| {
+            out.extend_from_slice(bytes)
+        }));
+        assert_eq!(run_until_stalled(fut), Poll::Ready(()));
+    }
+    assert_eq!(*count.borrow(), 2);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("count a b\r\n2\r\n$ "));
+    assert!(out.contains("unknown command: nope\r\n"));
+    assert!(out.contains("count           increment a counter\r\n"));
+    assert!(out.ends_with("$ "));
+}
@@ -0,0 +1,111 @@
+use core::pin::Pin;
+use core::time::Duration;
+
+use futures::future::Future;
+use futures::task::Poll;
+
+use crate::{with_wake_source, StaticLocalPool, WakeSource};
+
+#[cfg(feature = "alloc")]
+use crate::LocalPool;
+
+/// Something which tasks wait on, such as a set of timers or a network stack, and which must be
+/// polled when the executor stalls in order to wake them.
+pub trait EventSource {
+    /// Processes pending events, waking the tasks waiting on them. Returns whether there was any
+    /// activity, in which case the executor is run again.
+    fn poll(&mut self) -> bool;
+
+    /// How long until this source must be polled again, even if no other event occurs.
+    fn poll_delay(&mut self) -> Option<Duration> {
+        None
+    }
+
+    /// The source to which wakes during [`EventSource::poll`] are attributed. See
+    /// [`with_wake_source`].
+    fn wake_source(&self) -> WakeSource {
+        WakeSource::Unknown
+    }
+}
+
+/// The result of [`LocalPool::drive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drive<T> {
+    Complete(T),
+    /// No further progress can be made until an event occurs or, if present, the given delay has
+    /// elapsed.
+    Idle {
+        poll_delay: Option<Duration>,
+    },
+}
+
+#[cfg(feature = "alloc")]
+impl LocalPool {
+    /// Runs `main` and the pool's tasks, alternating with polling `sources`, until either `main`
+    /// completes or no progress can be made.
+    ///
+    /// This is intended to be called from an event handler, such as
+    /// `sel4_microkit::Handler::notified`. When it returns [`Drive::Idle`] with a delay, the
+    /// caller must arrange to call it again once that delay has elapsed, for example by setting a
+    /// timeout.
+    pub fn drive<F: Future>(
+        &mut self,
+        main: Pin<&mut F>,
+        sources: &mut [&mut dyn EventSource],
+    ) -> Drive<F::Output> {
+        drive_with(main, sources, |main| self.run_until_stalled(main))
+    }
+}
+
+impl<const N: usize, const TASK_SIZE: usize> StaticLocalPool<N, TASK_SIZE> {
+    /// Like [`LocalPool::drive`](crate::LocalPool::drive), for a pool which does not require an
+    /// allocator.
+    pub fn drive<F: Future>(
+        &'static self,
+        main: Pin<&mut F>,
+        sources: &mut [&mut dyn EventSource],
+    ) -> Drive<F::Output> {
+        drive_with(main, sources, |main| self.run_until_stalled(main))
+    }
+}
+
+fn drive_with<F: Future>(
+    mut main: Pin<&mut F>,
+    sources: &mut [&mut dyn EventSource],
+    mut run_until_stalled: impl FnMut(Pin<&mut F>) -> Poll<F::Output>,
+) -> Drive<F::Output> {
+    loop {
+        if let Poll::Ready(output) = run_until_stalled(main.as_mut()) {
+            return Drive::Complete(output);
+        }
+        let mut activity = false;
+        for source in sources.iter_mut() {
+            activity |= with_wake_source(source.wake_source(), || source.poll());
+        }
+        if activity {
+            continue;
+        }
+        let poll_delay = sources
+            .iter_mut()
+            .filter_map(|source| source.poll_delay())
+            .min();
+        if poll_delay != Some(Duration::ZERO) {
+            return Drive::Idle { poll_delay };
+        }
+    }
+}
+
+/// Runs `future` to completion, calling `wait` whenever it stalls.
+///
+/// `wait` should block until an event which may allow `future` to make progress has occurred, for
+/// example by waiting on a notification.
+#[cfg(feature = "alloc")]
+pub fn block_on<F: Future>(future: F, mut wait: impl FnMut()) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = crate::run_until_stalled(future.as_mut()) {
+            return output;
+        }
+        wait();
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::task::{Poll, Waker};
+
+use futures::future::{AbortHandle, Abortable};
+use futures::task::{LocalSpawnExt, SpawnError};
+
+use crate::LocalSpawner;
+
+/// A collection of tasks spawned onto a [`LocalPool`](crate::LocalPool), whose outputs can be
+/// awaited collectively.
+///
+/// Dropping a `JoinSet` cancels all of its tasks which have not yet completed, so tasks spawned
+/// on behalf of some scope (e.g. a connection) do not outlive it. Cancelled tasks are dropped the
+/// next time the pool is polled.
+pub struct JoinSet<T> {
+    spawner: LocalSpawner,
+    shared: Rc<RefCell<Shared<T>>>,
+    next_task_id: u64,
+}
+
+struct Shared<T> {
+    completed: VecDeque<T>,
+    outstanding: usize,
+    waker: Option<Waker>,
+    // Handles for tasks which have not yet completed.
+    abort_handles: BTreeMap<u64, AbortHandle>,
+}
+
+impl<T: 'static> JoinSet<T> {
+    pub fn new(spawner: LocalSpawner) -> Self {
+        Self {
+            spawner,
+            shared: Rc::new(RefCell::new(Shared {
+                completed: VecDeque::new(),
+                outstanding: 0,
+                waker: None,
+                abort_handles: BTreeMap::new(),
+            })),
+            next_task_id: 0,
+        }
+    }
+
+    pub fn spawn(&mut self, future: impl Future<Output = T> + 'static) -> Result<(), SpawnError> {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let task_id = self.next_task_id;
+        let shared = self.shared.clone();
+        let task = Abortable::new(
+            async move {
+                let output = future.await;
+                let mut shared = shared.borrow_mut();
+                shared.abort_handles.remove(&task_id);
+                shared.completed.push_back(output);
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+            },
+            abort_registration,
+        );
+        self.spawner.spawn_local(async move {
+            let _ = task.await;
+        })?;
+        self.next_task_id += 1;
+        let mut shared = self.shared.borrow_mut();
+        shared.outstanding += 1;
+        shared.abort_handles.insert(task_id, abort_handle);
+        Ok(())
+    }
+
+    /// The number of tasks whose outputs have not yet been returned by [`JoinSet::join_next`].
+    pub fn len(&self) -> usize {
+        self.shared.borrow().outstanding
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits for any task in this set to complete, returning its output, or returns `None` if the
+    /// set is empty.
+    pub async fn join_next(&mut self) -> Option<T> {
+        poll_fn(|cx| {
+            let mut shared = self.shared.borrow_mut();
+            if let Some(output) = shared.completed.pop_front() {
+                shared.outstanding -= 1;
+                Poll::Ready(Some(output))
+            } else if shared.outstanding == 0 {
+                Poll::Ready(None)
+            } else {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Waits for all tasks in this set to complete, returning their outputs in order of
+    /// completion.
+    pub async fn join_all(&mut self) -> Vec<T> {
+        let mut outputs = Vec::new();
+        while let Some(output) = self.join_next().await {
+            outputs.push(output);
+        }
+        outputs
+    }
+
+    /// Cancels all tasks in this set which have not yet completed, and discards the outputs of
+    /// those which have.
+    pub fn abort_all(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        for (_, handle) in core::mem::take(&mut shared.abort_handles) {
+            handle.abort();
+        }
+        shared.completed.clear();
+        shared.outstanding = 0;
+    }
+}
+
+impl<T> Drop for JoinSet<T> {
+    fn drop(&mut self) {
+        for handle in self.shared.borrow().abort_handles.values() {
+            handle.abort();
+        }
+    }
+}
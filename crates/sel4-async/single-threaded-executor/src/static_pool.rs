@@ -0,0 +1,210 @@
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use futures::future::Future;
+
+use crate::enter;
+
+/// The alignment of each task slot of a [`StaticLocalPool`]. Futures with greater alignment cannot
+/// be spawned.
+pub const TASK_ALIGN: usize = 16;
+
+/// A pool of tasks which, unlike [`LocalPool`](crate::LocalPool), does not require an allocator.
+///
+/// Tasks are stored in place in `N` slots of `TASK_SIZE` bytes each. Spawning a future which does
+/// not fit in a slot, because it is larger than `TASK_SIZE` or more aligned than [`TASK_ALIGN`],
+/// is a compile-time error. Spawning a future when all slots are occupied fails at runtime.
+///
+/// Wakers refer to the pool directly, so it is used through a `&'static` reference, such as one to
+/// a `static mut`:
+///
+/// ```ignore
+/// static mut POOL: StaticLocalPool<4, 1024> = StaticLocalPool::new();
+///
+/// let pool = unsafe { &*core::ptr::addr_of!(POOL) };
+/// ```
+pub struct StaticLocalPool<const N: usize, const TASK_SIZE: usize> {
+    slots: [TaskSlot<TASK_SIZE>; N],
+    main_woken: AtomicBool,
+}
+
+struct TaskSlot<const TASK_SIZE: usize> {
+    woken: AtomicBool,
+    // `None` if the slot is free.
+    task: Cell<Option<TaskFns>>,
+    storage: UnsafeCell<MaybeUninit<TaskStorage<TASK_SIZE>>>,
+}
+
+#[derive(Copy, Clone)]
+struct TaskFns {
+    poll: unsafe fn(*mut u8, &mut Context) -> Poll<()>,
+    drop: unsafe fn(*mut u8),
+}
+
+#[repr(C, align(16))]
+struct TaskStorage<const TASK_SIZE: usize>([MaybeUninit<u8>; TASK_SIZE]);
+
+const _: () = assert!(mem::align_of::<TaskStorage<0>>() == TASK_ALIGN);
+
+impl<const TASK_SIZE: usize> TaskSlot<TASK_SIZE> {
+    // Only used as an array repeat operand, where each use yields a distinct value.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const FREE: Self = Self {
+        woken: AtomicBool::new(false),
+        task: Cell::new(None),
+        storage: UnsafeCell::new(MaybeUninit::uninit()),
+    };
+
+    fn storage_ptr(&self) -> *mut u8 {
+        self.storage.get().cast()
+    }
+
+    // Returns whether the slot was woken.
+    fn poll_if_woken(&'static self) -> bool {
+        let Some(task) = self.task.get() else {
+            return false;
+        };
+        if !self.woken.swap(false, Ordering::Acquire) {
+            return false;
+        }
+        let waker = flag_waker(&self.woken);
+        let mut cx = Context::from_waker(&waker);
+        // SAFETY: The slot holds a pinned future of the type for which `task` was instantiated,
+        // and this function is not reentrant, because the executor cannot be entered twice.
+        if unsafe { (task.poll)(self.storage_ptr(), &mut cx) }.is_ready() {
+            unsafe { (task.drop)(self.storage_ptr()) };
+            self.task.set(None);
+        }
+        true
+    }
+}
+
+impl<const N: usize, const TASK_SIZE: usize> StaticLocalPool<N, TASK_SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [TaskSlot::FREE; N],
+            main_woken: AtomicBool::new(false),
+        }
+    }
+
+    /// Spawns `future` into a free slot, or returns it if there is none.
+    ///
+    /// This may be called from within a task.
+    pub fn spawn<F: Future<Output = ()> + 'static>(&'static self, future: F) -> Result<(), F> {
+        #[allow(clippy::let_unit_value)]
+        let () = AssertFits::<F, TASK_SIZE>::OK;
+        let Some(slot) = self.slots.iter().find(|slot| slot.task.get().is_none()) else {
+            return Err(future);
+        };
+        // SAFETY: The slot is free, and `AssertFits` guarantees that `F` fits in it.
+        unsafe { slot.storage_ptr().cast::<F>().write(future) };
+        slot.task.set(Some(TaskFns {
+            poll: poll_task::<F>,
+            drop: drop_task::<F>,
+        }));
+        slot.woken.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// The number of tasks in the pool.
+    pub fn num_tasks(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.task.get().is_some())
+            .count()
+    }
+
+    pub fn run_all_until_stalled(&'static self) -> Poll<()> {
+        let stalled = self.run_executor_until_stalled(|_| Poll::<()>::Pending);
+        debug_assert!(stalled.is_pending());
+        if self.num_tasks() == 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    pub fn run_until_stalled<F: Future>(&'static self, mut future: Pin<&mut F>) -> Poll<F::Output> {
+        self.run_executor_until_stalled(|cx| future.as_mut().poll(cx))
+    }
+
+    fn run_executor_until_stalled<T>(
+        &'static self,
+        mut f: impl FnMut(&mut Context<'_>) -> Poll<T>,
+    ) -> Poll<T> {
+        let _enter = enter::enter()
+            .expect("cannot execute `StaticLocalPool` executor from within another executor");
+
+        let waker = flag_waker(&self.main_woken);
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(t) = f(&mut cx) {
+                return Poll::Ready(t);
+            }
+
+            let mut woken = false;
+            for slot in &self.slots {
+                woken |= slot.poll_if_woken();
+            }
+
+            if !woken && !self.main_woken.swap(false, Ordering::Acquire) {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl<const N: usize, const TASK_SIZE: usize> Default for StaticLocalPool<N, TASK_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const TASK_SIZE: usize> Drop for StaticLocalPool<N, TASK_SIZE> {
+    fn drop(&mut self) {
+        for slot in &self.slots {
+            if let Some(task) = slot.task.take() {
+                unsafe { (task.drop)(slot.storage_ptr()) };
+            }
+        }
+    }
+}
+
+struct AssertFits<F, const TASK_SIZE: usize>(PhantomData<F>);
+
+impl<F, const TASK_SIZE: usize> AssertFits<F, TASK_SIZE> {
+    const OK: () = assert!(
+        mem::size_of::<F>() <= TASK_SIZE && mem::align_of::<F>() <= TASK_ALIGN,
+        "future does not fit in a task slot of this StaticLocalPool",
+    );
+}
+
+unsafe fn poll_task<F: Future<Output = ()>>(ptr: *mut u8, cx: &mut Context) -> Poll<()> {
+    Pin::new_unchecked(&mut *ptr.cast::<F>()).poll(cx)
+}
+
+unsafe fn drop_task<F>(ptr: *mut u8) {
+    ptr.cast::<F>().drop_in_place()
+}
+
+// Wakers of a `StaticLocalPool` each set a flag in the pool, which outlives them because it is
+// `'static`.
+
+fn flag_waker(flag: &'static AtomicBool) -> Waker {
+    unsafe { Waker::from_raw(flag_raw_waker(flag)) }
+}
+
+fn flag_raw_waker(flag: *const AtomicBool) -> RawWaker {
+    RawWaker::new(flag.cast(), &FLAG_WAKER_VTABLE)
+}
+
+static FLAG_WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(
+    |flag| flag_raw_waker(flag.cast()),
+    |flag| unsafe { &*flag.cast::<AtomicBool>() }.store(true, Ordering::Release),
+    |flag| unsafe { &*flag.cast::<AtomicBool>() }.store(true, Ordering::Release),
+    |_| {},
+);
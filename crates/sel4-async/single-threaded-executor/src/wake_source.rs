@@ -0,0 +1,46 @@
+use core::cell::Cell;
+
+/// The kind of event which woke a task, as attributed with [`with_wake_source`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WakeSource {
+    Timer,
+    Channel,
+    Irq,
+    BlockIO,
+    Network,
+    /// A wake which occurred outside of [`with_wake_source`].
+    Unknown,
+}
+
+impl WakeSource {
+    pub const ALL: [Self; NUM_WAKE_SOURCES] = [
+        Self::Timer,
+        Self::Channel,
+        Self::Irq,
+        Self::BlockIO,
+        Self::Network,
+        Self::Unknown,
+    ];
+}
+
+pub(crate) const NUM_WAKE_SOURCES: usize = 6;
+
+#[thread_local]
+static CURRENT_WAKE_SOURCE: Cell<WakeSource> = Cell::new(WakeSource::Unknown);
+
+/// Attributes wakes of [instrumented](crate::instrument) tasks which occur during `f` to `source`.
+///
+/// This is intended to wrap the code which delivers an event to the things tasks wait on, such as
+/// polling timers in response to a timer interrupt. [`LocalPool::drive`](crate::LocalPool::drive)
+/// does so for each [`EventSource`](crate::EventSource).
+pub fn with_wake_source<R>(source: WakeSource, f: impl FnOnce() -> R) -> R {
+    let prev = CURRENT_WAKE_SOURCE.replace(source);
+    let r = f();
+    CURRENT_WAKE_SOURCE.set(prev);
+    r
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn current_wake_source() -> WakeSource {
+    CURRENT_WAKE_SOURCE.get()
+}
@@ -0,0 +1,103 @@
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::{LazyCell, RefCell};
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::future::Future;
+use futures::task::{waker, ArcWake, AtomicWaker, Context, Poll};
+
+use crate::wake_source::{current_wake_source, NUM_WAKE_SOURCES};
+use crate::WakeSource;
+
+#[thread_local]
+static REGISTRY: LazyCell<RefCell<Vec<Weak<TaskWaker>>>> = LazyCell::new(Default::default);
+
+/// Wraps `future` so that the wakes it receives are counted by source, and reported by
+/// [`task_wake_stats`] under `name`.
+pub fn instrument<F: Future>(name: &'static str, future: F) -> Instrumented<F> {
+    let task_waker = Arc::new(TaskWaker {
+        name,
+        inner: AtomicWaker::new(),
+        polls: AtomicUsize::new(0),
+        wakes: Default::default(),
+    });
+    REGISTRY.borrow_mut().push(Arc::downgrade(&task_waker));
+    Instrumented { future, task_waker }
+}
+
+/// A snapshot of an instrumented task's counters, as returned by [`task_wake_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskWakeStats {
+    pub name: &'static str,
+    /// The number of times the task has been polled.
+    pub polls: usize,
+    wakes: [usize; NUM_WAKE_SOURCES],
+}
+
+impl TaskWakeStats {
+    pub fn wakes_from(&self, source: WakeSource) -> usize {
+        self.wakes[source.index()]
+    }
+
+    pub fn total_wakes(&self) -> usize {
+        self.wakes.iter().sum()
+    }
+}
+
+/// Returns the counters of each live task wrapped with [`instrument`] on this thread, in the
+/// order in which they were instrumented.
+pub fn task_wake_stats() -> Vec<TaskWakeStats> {
+    let mut registry = REGISTRY.borrow_mut();
+    registry.retain(|task_waker| task_waker.strong_count() > 0);
+    registry
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|task_waker| TaskWakeStats {
+            name: task_waker.name,
+            polls: task_waker.polls.load(Ordering::Relaxed),
+            wakes: core::array::from_fn(|i| task_waker.wakes[i].load(Ordering::Relaxed)),
+        })
+        .collect()
+}
+
+/// Future returned by [`instrument`].
+pub struct Instrumented<F> {
+    future: F,
+    task_waker: Arc<TaskWaker>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // SAFETY: `future` is structurally pinned, and is never moved out of `self`.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.task_waker.polls.fetch_add(1, Ordering::Relaxed);
+        this.task_waker.inner.register(cx.waker());
+        let waker = waker(this.task_waker.clone());
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        future.poll(&mut Context::from_waker(&waker))
+    }
+}
+
+impl WakeSource {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+struct TaskWaker {
+    name: &'static str,
+    inner: AtomicWaker,
+    polls: AtomicUsize,
+    wakes: [AtomicUsize; NUM_WAKE_SOURCES],
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        let source = current_wake_source();
+        arc_self.wakes[source.index()].fetch_add(1, Ordering::Relaxed);
+        arc_self.inner.wake();
+    }
+}
@@ -0,0 +1,109 @@
+use core::cell::Cell;
+use core::pin::pin;
+use core::task::Poll;
+use std::rc::Rc;
+
+use futures::channel::oneshot;
+
+use sel4_async_single_threaded_executor::{JoinSet, LocalPool};
+
+// Records whether the task which owns it has been dropped.
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+fn join_next<T: 'static>(pool: &mut LocalPool, join_set: &mut JoinSet<T>) -> Poll<Option<T>> {
+    pool.run_until_stalled(pin!(join_set.join_next()))
+}
+
+#[test]
+fn join_next_returns_outputs_in_order_of_completion() {
+    let mut pool = LocalPool::new();
+    let mut join_set = JoinSet::new(pool.spawner());
+    let mut senders = vec![];
+    for i in 0..3 {
+        let (tx, rx) = oneshot::channel::<()>();
+        senders.push(tx);
+        join_set
+            .spawn(async move {
+                rx.await.unwrap();
+                i
+            })
+            .unwrap();
+    }
+    assert_eq!(join_set.len(), 3);
+    assert_eq!(join_next(&mut pool, &mut join_set), Poll::Pending);
+
+    let mut senders = senders.into_iter().map(Some).collect::<Vec<_>>();
+    for i in [2, 0, 1] {
+        senders[i].take().unwrap().send(()).unwrap();
+    }
+    for i in [2, 0, 1] {
+        assert_eq!(join_next(&mut pool, &mut join_set), Poll::Ready(Some(i)));
+    }
+    assert!(join_set.is_empty());
+    assert_eq!(join_next(&mut pool, &mut join_set), Poll::Ready(None));
+}
+
+#[test]
+fn abort_all_cancels_outstanding_and_discards_completed() {
+    let mut pool = LocalPool::new();
+    let mut join_set = JoinSet::new(pool.spawner());
+    let dropped = Rc::new(Cell::new(false));
+    let (tx, rx) = oneshot::channel::<()>();
+    join_set.spawn(async { 1 }).unwrap();
+    join_set
+        .spawn({
+            let flag = DropFlag(dropped.clone());
+            async move {
+                let _flag = flag;
+                rx.await.unwrap();
+                2
+            }
+        })
+        .unwrap();
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+
+    join_set.abort_all();
+    assert!(join_set.is_empty());
+    assert_eq!(join_next(&mut pool, &mut join_set), Poll::Ready(None));
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert!(dropped.get());
+    assert!(tx.send(()).is_err());
+
+    // The set remains usable.
+    join_set.spawn(async { 3 }).unwrap();
+    assert_eq!(join_next(&mut pool, &mut join_set), Poll::Ready(Some(3)));
+}
+
+#[test]
+fn drop_cancels_outstanding_tasks() {
+    let mut pool = LocalPool::new();
+    let mut join_set = JoinSet::new(pool.spawner());
+    let dropped = Rc::new(Cell::new(false));
+    let completed = Rc::new(Cell::new(false));
+    let (tx, rx) = oneshot::channel::<()>();
+    join_set
+        .spawn({
+            let flag = DropFlag(dropped.clone());
+            let completed = completed.clone();
+            async move {
+                let _flag = flag;
+                rx.await.unwrap();
+                completed.set(true);
+            }
+        })
+        .unwrap();
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+    assert!(!dropped.get());
+
+    drop(join_set);
+    let _ = tx.send(());
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert!(dropped.get());
+    assert!(!completed.get());
+}
@@ -0,0 +1,61 @@
+use core::cell::{Cell, RefCell};
+use core::pin::pin;
+use core::task::{Poll, Waker};
+
+use futures::future::poll_fn;
+
+use sel4_async_single_threaded_executor::StaticLocalPool;
+
+type Pool = StaticLocalPool<2, 64>;
+
+fn new_pool() -> &'static Pool {
+    Box::leak(Box::new(Pool::new()))
+}
+
+#[test]
+fn tasks_run_until_woken() {
+    let pool = new_pool();
+    let parked: &'static RefCell<Option<Waker>> = Box::leak(Default::default());
+    let polls: &'static Cell<usize> = Box::leak(Default::default());
+
+    pool.spawn(poll_fn(|cx| {
+        polls.set(polls.get() + 1);
+        if polls.get() == 2 {
+            return Poll::Ready(());
+        }
+        *parked.borrow_mut() = Some(cx.waker().clone());
+        Poll::Pending
+    }))
+    .unwrap_or_else(|_| panic!());
+
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+    assert_eq!(polls.get(), 1);
+
+    parked.borrow_mut().take().unwrap().wake();
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert_eq!(polls.get(), 2);
+    assert_eq!(pool.num_tasks(), 0);
+}
+
+#[test]
+fn slots_are_reused() {
+    let pool = new_pool();
+    let runs: &'static Cell<usize> = Box::leak(Default::default());
+
+    pool.spawn(async { runs.set(runs.get() + 1) })
+        .unwrap_or_else(|_| panic!());
+    pool.spawn(async { runs.set(runs.get() + 1) })
+        .unwrap_or_else(|_| panic!());
+    assert!(pool.spawn(async {}).is_err());
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert_eq!(runs.get(), 2);
+
+    let main = pin!(async {
+        pool.spawn(async { runs.set(runs.get() + 1) })
+            .unwrap_or_else(|_| panic!());
+    });
+    assert_eq!(pool.run_until_stalled(main), Poll::Ready(()));
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert_eq!(runs.get(), 3);
+}
@@ -0,0 +1,47 @@
+use core::cell::RefCell;
+use core::task::{Poll, Waker};
+use std::rc::Rc;
+
+use futures::future::poll_fn;
+use futures::task::LocalSpawnExt;
+
+use sel4_async_single_threaded_executor::{
+    instrument, task_wake_stats, with_wake_source, LocalPool, WakeSource,
+};
+
+#[test]
+fn wakes_are_attributed_to_sources() {
+    let parked: Rc<RefCell<Option<Waker>>> = Default::default();
+    let mut pool = LocalPool::new();
+    pool.spawner()
+        .spawn_local(instrument("waiter", {
+            let parked = parked.clone();
+            let mut remaining = 3;
+            poll_fn(move |cx| {
+                if remaining == 0 {
+                    return Poll::Ready(());
+                }
+                remaining -= 1;
+                *parked.borrow_mut() = Some(cx.waker().clone());
+                Poll::Pending
+            })
+        }))
+        .unwrap();
+
+    let wake = || parked.borrow_mut().take().unwrap().wake();
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+    with_wake_source(WakeSource::Timer, wake);
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+    with_wake_source(WakeSource::Timer, wake);
+    assert_eq!(pool.run_all_until_stalled(), Poll::Pending);
+
+    let stats = task_wake_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].name, "waiter");
+    assert_eq!(stats[0].polls, 3);
+    assert_eq!(stats[0].wakes_from(WakeSource::Timer), 2);
+
+    wake();
+    assert_eq!(pool.run_all_until_stalled(), Poll::Ready(()));
+    assert!(task_wake_stats().is_empty());
+}
@@ -0,0 +1,24 @@
+//! Synchronization primitives for tasks running on a single-threaded executor, such as
+//! `sel4-async-single-threaded-executor`.
+//!
+//! None of these types are `Send` or `Sync`. State is kept in [`RefCell`](core::cell::RefCell)s
+//! rather than atomics, and is shared between tasks either by reference or via
+//! [`Rc`](alloc::rc::Rc).
+//!
+//! Waiters are served in FIFO order, so a task waiting on a [`Semaphore`] or [`Mutex`] cannot be
+//! starved by tasks which arrive later.
+
+#![no_std]
+
+extern crate alloc;
+
+mod mutex;
+mod semaphore;
+mod wait_queue;
+
+pub mod mpsc;
+pub mod oneshot;
+pub mod tee;
+
+pub use mutex::{Mutex, MutexGuard};
+pub use semaphore::{Acquire, Permit, Semaphore, TryAcquireError};
@@ -0,0 +1,249 @@
+//! A bounded multi-producer, single-consumer channel.
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::wait_queue::WaitQueue;
+
+/// Creates a channel which holds at most `capacity` values. Senders wait, in FIFO order, for space
+/// when it is full.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert_ne!(capacity, 0);
+    let shared = Rc::new(RefCell::new(Shared {
+        queue: VecDeque::with_capacity(capacity),
+        capacity,
+        senders: 1,
+        receiver_alive: true,
+        receiver_waker: None,
+        send_waiters: WaitQueue::new(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    senders: usize,
+    receiver_alive: bool,
+    receiver_waker: Option<Waker>,
+    send_waiters: WaitQueue,
+}
+
+impl<T> Shared<T> {
+    fn enqueue(&mut self, value: T) {
+        self.queue.push_back(value);
+        if let Some(waker) = self.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Sender<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+pub struct Receiver<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+/// The [`Receiver`] has been dropped. Holds the value which could not be sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    /// All [`Sender`]s have been dropped and the channel is empty.
+    Closed,
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "receiver dropped")
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Full(_) => write!(f, "channel full"),
+            Self::Closed(_) => write!(f, "receiver dropped"),
+        }
+    }
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "channel empty"),
+            Self::Closed => write!(f, "all senders dropped"),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Waits for space in the channel, and then sends `value`.
+    pub fn send(&self, value: T) -> Send<'_, T> {
+        Send {
+            sender: self,
+            value: Some(value),
+            key: None,
+        }
+    }
+
+    /// Sends `value` if there is space in the channel and no other senders are waiting.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut shared = self.shared.borrow_mut();
+        if !shared.receiver_alive {
+            Err(TrySendError::Closed(value))
+        } else if shared.queue.len() < shared.capacity && shared.send_waiters.is_empty() {
+            shared.enqueue(value);
+            Ok(())
+        } else {
+            Err(TrySendError::Full(value))
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.borrow().receiver_alive
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.borrow_mut().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.senders -= 1;
+        if shared.senders == 0 {
+            if let Some(waker) = shared.receiver_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Future returned by [`Sender::send`].
+pub struct Send<'a, T> {
+    sender: &'a Sender<T>,
+    value: Option<T>,
+    key: Option<u64>,
+}
+
+// `value` is never pinned.
+impl<T> Unpin for Send<'_, T> {}
+
+impl<T> Future for Send<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut shared = this.sender.shared.borrow_mut();
+        if !shared.receiver_alive {
+            if let Some(key) = this.key.take() {
+                shared.send_waiters.remove(key);
+            }
+            return Poll::Ready(Err(SendError(this.value.take().unwrap())));
+        }
+        let turn = match this.key {
+            None => shared.send_waiters.is_empty(),
+            Some(key) => shared.send_waiters.is_first(key),
+        };
+        if turn && shared.queue.len() < shared.capacity {
+            if let Some(key) = this.key.take() {
+                shared.send_waiters.remove(key);
+            }
+            shared.enqueue(this.value.take().unwrap());
+            if shared.queue.len() < shared.capacity {
+                shared.send_waiters.wake_first();
+            }
+            Poll::Ready(Ok(()))
+        } else {
+            match this.key {
+                None => this.key = Some(shared.send_waiters.push(cx.waker())),
+                Some(key) => shared.send_waiters.update(key, cx.waker()),
+            }
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for Send<'_, T> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let mut shared = self.sender.shared.borrow_mut();
+            let was_first = shared.send_waiters.is_first(key);
+            shared.send_waiters.remove(key);
+            if was_first && shared.queue.len() < shared.capacity {
+                shared.send_waiters.wake_first();
+            }
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Waits for a value. Returns `None` once all [`Sender`]s have been dropped and the channel is
+    /// empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| match self.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(TryRecvError::Closed) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => {
+                self.shared.borrow_mut().receiver_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut shared = self.shared.borrow_mut();
+        match shared.queue.pop_front() {
+            Some(value) => {
+                shared.send_waiters.wake_first();
+                Ok(value)
+            }
+            None if shared.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.borrow().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.receiver_alive = false;
+        shared.send_waiters.wake_all();
+    }
+}
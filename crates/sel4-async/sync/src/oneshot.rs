@@ -0,0 +1,107 @@
+//! A channel for sending a single value.
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(RefCell::new(Shared {
+        value: None,
+        sender_alive: true,
+        receiver_alive: true,
+        receiver_waker: None,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    value: Option<T>,
+    sender_alive: bool,
+    receiver_alive: bool,
+    receiver_waker: Option<Waker>,
+}
+
+pub struct Sender<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+/// A future which resolves to the value sent, or to an error if the [`Sender`] is dropped without
+/// sending one.
+pub struct Receiver<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sender dropped without sending a value")
+    }
+}
+
+impl<T> Sender<T> {
+    /// Sends `value`, returning it if the [`Receiver`] has been dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut shared = self.shared.borrow_mut();
+        if !shared.receiver_alive {
+            return Err(value);
+        }
+        shared.value = Some(value);
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.borrow().receiver_alive
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.sender_alive = false;
+        if let Some(waker) = shared.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Takes the value if it has been sent, without waiting.
+    pub fn try_recv(&mut self) -> Option<Result<T, RecvError>> {
+        let mut shared = self.shared.borrow_mut();
+        match shared.value.take() {
+            Some(value) => Some(Ok(value)),
+            None if !shared.sender_alive => Some(Err(RecvError)),
+            None => None,
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.try_recv() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                self.shared.borrow_mut().receiver_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().receiver_alive = false;
+    }
+}
@@ -0,0 +1,141 @@
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::wait_queue::WaitQueue;
+
+/// A counting semaphore.
+pub struct Semaphore {
+    inner: RefCell<Inner>,
+}
+
+struct Inner {
+    permits: usize,
+    waiters: WaitQueue,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TryAcquireError {
+    /// No permits are available, or other tasks are already waiting for them.
+    NoPermits,
+}
+
+impl fmt::Display for TryAcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoPermits => write!(f, "no permits available"),
+        }
+    }
+}
+
+impl Semaphore {
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            inner: RefCell::new(Inner {
+                permits,
+                waiters: WaitQueue::new(),
+            }),
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.inner.borrow().permits
+    }
+
+    /// Adds `n` permits, waking the first waiter, if any.
+    pub fn add_permits(&self, n: usize) {
+        let mut inner = self.inner.borrow_mut();
+        inner.permits += n;
+        inner.waiters.wake_first();
+    }
+
+    /// Waits for a permit, which is returned to the semaphore when the [`Permit`] is dropped.
+    pub fn acquire(&self) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            key: None,
+        }
+    }
+
+    /// Takes a permit without waiting. Fails if none are available, or if other tasks are already
+    /// waiting for one.
+    pub fn try_acquire(&self) -> Result<Permit<'_>, TryAcquireError> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.permits > 0 && inner.waiters.is_empty() {
+            inner.permits -= 1;
+            Ok(Permit { semaphore: self })
+        } else {
+            Err(TryAcquireError::NoPermits)
+        }
+    }
+}
+
+/// Future returned by [`Semaphore::acquire`].
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    key: Option<u64>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = Permit<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let mut inner = semaphore.inner.borrow_mut();
+        let ready = match self.key {
+            None => inner.permits > 0 && inner.waiters.is_empty(),
+            Some(key) => inner.permits > 0 && inner.waiters.is_first(key),
+        };
+        if ready {
+            if let Some(key) = self.key.take() {
+                inner.waiters.remove(key);
+            }
+            inner.permits -= 1;
+            if inner.permits > 0 {
+                inner.waiters.wake_first();
+            }
+            Poll::Ready(Permit { semaphore })
+        } else {
+            match self.key {
+                None => self.key = Some(inner.waiters.push(cx.waker())),
+                Some(key) => inner.waiters.update(key, cx.waker()),
+            }
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let mut inner = self.semaphore.inner.borrow_mut();
+            let was_first = inner.waiters.is_first(key);
+            inner.waiters.remove(key);
+            if was_first && inner.permits > 0 {
+                inner.waiters.wake_first();
+            }
+        }
+    }
+}
+
+/// A permit acquired from a [`Semaphore`], which is returned to it on drop.
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Permit<'_> {
+    /// Consumes the permit without returning it to the semaphore. It can be restored later with
+    /// [`Semaphore::add_permits`].
+    pub fn forget(self) {
+        mem::forget(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.add_permits(1);
+    }
+}
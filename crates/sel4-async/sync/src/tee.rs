@@ -0,0 +1,271 @@
+//! Fan-out of a byte stream, such as serial or log output, to multiple consumers.
+//!
+//! A [`Writer`] retains at most `capacity` bytes which have not yet been consumed by every
+//! [`Reader`]. Each reader has its own position in the stream and its own [`LagPolicy`]: a
+//! [`LagPolicy::Block`] reader applies backpressure to the writer, while a [`LagPolicy::Skip`]
+//! reader which falls more than `capacity` bytes behind skips ahead, and is told how many bytes
+//! it missed. Skipping readers never delay the writer or other readers.
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// The writer waits for this reader.
+    Block,
+    /// This reader skips bytes which the writer has had to discard.
+    Skip,
+}
+
+/// The [`Reader`] fell behind, and this many bytes were skipped. Subsequent reads resume from
+/// the oldest retained byte.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Lagged(pub u64);
+
+impl fmt::Display for Lagged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "reader lagged by {} bytes", self.0)
+    }
+}
+
+struct Shared {
+    buf: VecDeque<u8>,
+    // Stream position of the front of `buf`.
+    base: u64,
+    capacity: usize,
+    readers: Vec<Option<ReaderState>>,
+    writer_alive: bool,
+    writer_waker: Option<Waker>,
+}
+
+struct ReaderState {
+    pos: u64,
+    policy: LagPolicy,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn head(&self) -> u64 {
+        self.base + u64::try_from(self.buf.len()).unwrap()
+    }
+
+    fn readers(&self) -> impl Iterator<Item = &ReaderState> {
+        self.readers.iter().flatten()
+    }
+
+    // Bytes which may be written without overtaking a blocking reader.
+    fn space(&self) -> usize {
+        let head = self.head();
+        let lag = self
+            .readers()
+            .filter(|reader| reader.policy == LagPolicy::Block)
+            .map(|reader| usize::try_from(head - reader.pos).unwrap())
+            .max()
+            .unwrap_or(0);
+        self.capacity - lag
+    }
+
+    fn write(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(self.space());
+        self.buf.extend(&data[..n]);
+        self.trim();
+        for reader in self.readers.iter_mut().flatten() {
+            if let Some(waker) = reader.waker.take() {
+                waker.wake();
+            }
+        }
+        n
+    }
+
+    // Discards bytes which every reader has consumed, and then, if necessary, the oldest bytes
+    // beyond `capacity`.
+    fn trim(&mut self) {
+        let head = self.head();
+        let min_pos = self
+            .readers()
+            .map(|reader| reader.pos)
+            .min()
+            .unwrap_or(head);
+        let keep_from = min_pos.max(head.saturating_sub(u64::try_from(self.capacity).unwrap()));
+        if keep_from > self.base {
+            let n = usize::try_from(keep_from - self.base).unwrap();
+            self.buf.drain(..n);
+            self.base = keep_from;
+        }
+    }
+
+    fn wake_writer(&mut self) {
+        if let Some(waker) = self.writer_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Writer {
+    shared: Rc<RefCell<Shared>>,
+}
+
+pub struct Reader {
+    shared: Rc<RefCell<Shared>>,
+    index: usize,
+}
+
+impl Writer {
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0);
+        Self {
+            shared: Rc::new(RefCell::new(Shared {
+                buf: VecDeque::with_capacity(capacity),
+                base: 0,
+                capacity,
+                readers: Vec::new(),
+                writer_alive: true,
+                writer_waker: None,
+            })),
+        }
+    }
+
+    /// Creates a reader which receives all bytes written from now on.
+    pub fn subscribe(&self, policy: LagPolicy) -> Reader {
+        let mut shared = self.shared.borrow_mut();
+        let state = ReaderState {
+            pos: shared.head(),
+            policy,
+            waker: None,
+        };
+        let index = match shared.readers.iter().position(Option::is_none) {
+            Some(index) => {
+                shared.readers[index] = Some(state);
+                index
+            }
+            None => {
+                shared.readers.push(Some(state));
+                shared.readers.len() - 1
+            }
+        };
+        Reader {
+            shared: self.shared.clone(),
+            index,
+        }
+    }
+
+    pub fn num_readers(&self) -> usize {
+        self.shared.borrow().readers().count()
+    }
+
+    /// Writes as much of `data` as blocking readers permit, without waiting.
+    pub fn try_write(&mut self, data: &[u8]) -> usize {
+        self.shared.borrow_mut().write(data)
+    }
+
+    /// Waits until some of `data` can be written, and then writes as much as possible.
+    pub async fn write(&mut self, data: &[u8]) -> usize {
+        poll_fn(|cx| {
+            let mut shared = self.shared.borrow_mut();
+            let n = shared.write(data);
+            if n > 0 || data.is_empty() {
+                Poll::Ready(n)
+            } else {
+                shared.writer_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    pub async fn write_all(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = self.write(data).await;
+            data = &data[n..];
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.writer_alive = false;
+        for reader in shared.readers.iter_mut().flatten() {
+            if let Some(waker) = reader.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Reader {
+    pub fn policy(&self) -> LagPolicy {
+        self.with_state(|_, state| state.policy)
+    }
+
+    /// The number of bytes available to this reader without waiting.
+    pub fn available(&self) -> usize {
+        self.with_state(|shared, state| usize::try_from(shared.head() - state.pos).unwrap())
+    }
+
+    /// Returns `Ok(0)` once the [`Writer`] has been dropped and all bytes have been read.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Lagged> {
+        poll_fn(|cx| match self.try_read(buf) {
+            Ok(0) if !buf.is_empty() && self.shared.borrow().writer_alive => {
+                self.with_state_mut(|_, state| state.waker = Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            r => Poll::Ready(r),
+        })
+        .await
+    }
+
+    /// Returns `Ok(0)` if no bytes are available.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> Result<usize, Lagged> {
+        self.with_state_mut(|shared, state| {
+            if state.pos < shared.base {
+                let skipped = shared.base - state.pos;
+                state.pos = shared.base;
+                return Err(Lagged(skipped));
+            }
+            let offset = usize::try_from(state.pos - shared.base).unwrap();
+            let n = buf.len().min(shared.buf.len() - offset);
+            for (dst, src) in buf.iter_mut().zip(shared.buf.range(offset..offset + n)) {
+                *dst = *src;
+            }
+            state.pos += u64::try_from(n).unwrap();
+            Ok(n)
+        })
+        .map(|n| {
+            if n > 0 {
+                let mut shared = self.shared.borrow_mut();
+                shared.trim();
+                shared.wake_writer();
+            }
+            n
+        })
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&Shared, &ReaderState) -> R) -> R {
+        let shared = self.shared.borrow();
+        f(&shared, shared.readers[self.index].as_ref().unwrap())
+    }
+
+    fn with_state_mut<R>(&self, f: impl FnOnce(&Shared, &mut ReaderState) -> R) -> R {
+        let mut shared = self.shared.borrow_mut();
+        let mut state = shared.readers[self.index].take().unwrap();
+        let r = f(&shared, &mut state);
+        shared.readers[self.index] = Some(state);
+        r
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.readers[self.index] = None;
+        shared.trim();
+        shared.wake_writer();
+    }
+}
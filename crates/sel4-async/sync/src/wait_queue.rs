@@ -0,0 +1,59 @@
+use alloc::collections::VecDeque;
+use core::task::Waker;
+
+// A FIFO queue of waiting tasks. Each waiter is identified by a key, which remains valid until
+// the waiter is removed.
+pub(crate) struct WaitQueue {
+    waiters: VecDeque<(u64, Waker)>,
+    next_key: u64,
+}
+
+impl WaitQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            waiters: VecDeque::new(),
+            next_key: 0,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, waker: &Waker) -> u64 {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.waiters.push_back((key, waker.clone()));
+        key
+    }
+
+    pub(crate) fn is_first(&self, key: u64) -> bool {
+        self.waiters.front().map(|(k, _)| *k) == Some(key)
+    }
+
+    pub(crate) fn update(&mut self, key: u64, waker: &Waker) {
+        if let Some((_, w)) = self.waiters.iter_mut().find(|(k, _)| *k == key) {
+            if !w.will_wake(waker) {
+                *w = waker.clone();
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: u64) {
+        if let Some(i) = self.waiters.iter().position(|(k, _)| *k == key) {
+            self.waiters.remove(i);
+        }
+    }
+
+    pub(crate) fn wake_first(&self) {
+        if let Some((_, waker)) = self.waiters.front() {
+            waker.wake_by_ref();
+        }
+    }
+
+    pub(crate) fn wake_all(&self) {
+        for (_, waker) in &self.waiters {
+            waker.wake_by_ref();
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use core::cell::RefCell;
+use std::rc::Rc;
+
+use futures::task::LocalSpawnExt;
+use futures::FutureExt;
+use sel4_async_single_threaded_executor::LocalPool;
+use sel4_async_sync::{mpsc, oneshot, tee, Mutex, Semaphore};
+
+#[test]
+fn semaphore_is_fifo() {
+    let mut pool = LocalPool::new();
+    let semaphore = Rc::new(Semaphore::new(0));
+    let log = Rc::new(RefCell::new(vec![]));
+
+    for i in 0..3 {
+        let semaphore = semaphore.clone();
+        let log = log.clone();
+        pool.spawner()
+            .spawn_local(async move {
+                semaphore.acquire().await.forget();
+                log.borrow_mut().push(i);
+            })
+            .unwrap();
+    }
+
+    let _ = pool.run_all_until_stalled();
+    assert!(semaphore.try_acquire().is_err());
+
+    semaphore.add_permits(2);
+    let _ = pool.run_all_until_stalled();
+    assert_eq!(*log.borrow(), [0, 1]);
+
+    // A newcomer may not overtake the remaining waiter.
+    semaphore.add_permits(1);
+    assert!(semaphore.try_acquire().is_err());
+    let _ = pool.run_all_until_stalled();
+    assert_eq!(*log.borrow(), [0, 1, 2]);
+    assert_eq!(semaphore.available_permits(), 0);
+}
+
+#[test]
+fn mutex_guard_held_across_await() {
+    let mut pool = LocalPool::new();
+    let mutex = Rc::new(Mutex::new(vec![]));
+    let (tx, rx) = oneshot::channel();
+
+    pool.spawner()
+        .spawn_local({
+            let mutex = mutex.clone();
+            async move {
+                let mut guard = mutex.lock().await;
+                guard.push(1);
+                rx.await.unwrap();
+                guard.push(2);
+            }
+        })
+        .unwrap();
+    pool.spawner()
+        .spawn_local({
+            let mutex = mutex.clone();
+            async move {
+                mutex.lock().await.push(3);
+            }
+        })
+        .unwrap();
+
+    let _ = pool.run_all_until_stalled();
+    assert!(mutex.try_lock().is_err());
+
+    tx.send(()).unwrap();
+    let _ = pool.run_all_until_stalled();
+    assert_eq!(*mutex.try_lock().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn mpsc_backpressure_and_close() {
+    let mut pool = LocalPool::new();
+    let (tx, mut rx) = mpsc::channel(2);
+    let received = Rc::new(RefCell::new(vec![]));
+
+    pool.spawner()
+        .spawn_local(async move {
+            for i in 0..5 {
+                tx.send(i).await.unwrap();
+            }
+        })
+        .unwrap();
+
+    let _ = pool.run_all_until_stalled();
+    assert_eq!(rx.len(), 2);
+
+    pool.spawner()
+        .spawn_local({
+            let received = received.clone();
+            async move {
+                while let Some(i) = rx.recv().await {
+                    received.borrow_mut().push(i);
+                }
+                received.borrow_mut().push(-1);
+            }
+        })
+        .unwrap();
+
+    let _ = pool.run_all_until_stalled();
+    assert_eq!(*received.borrow(), [0, 1, 2, 3, 4, -1]);
+}
+
+#[test]
+fn oneshot_sender_dropped() {
+    let (tx, mut rx) = oneshot::channel::<()>();
+    assert_eq!(rx.try_recv(), None);
+    drop(tx);
+    assert_eq!(rx.try_recv(), Some(Err(oneshot::RecvError)));
+}
+
+#[test]
+fn tee_blocking_and_skipping_readers() {
+    let mut pool = LocalPool::new();
+    let mut writer = tee::Writer::new(4);
+    let mut blocking = writer.subscribe(tee::LagPolicy::Block);
+    let mut skipping = writer.subscribe(tee::LagPolicy::Skip);
+    let mut buf = [0; 8];
+
+    assert_eq!(writer.try_write(b"abcdef"), 4);
+    assert_eq!(writer.try_write(b"ef"), 0);
+
+    assert_eq!(blocking.try_read(&mut buf[..2]), Ok(2));
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(writer.try_write(b"ef"), 2);
+
+    // The skipping reader has fallen behind, and only the newest bytes were retained for it.
+    assert_eq!(skipping.try_read(&mut buf), Err(tee::Lagged(2)));
+    assert_eq!(skipping.try_read(&mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"cdef");
+
+    let done = Rc::new(RefCell::new(false));
+    pool.spawner()
+        .spawn_local({
+            let done = done.clone();
+            async move {
+                writer.write_all(b"ghijkl").await;
+                *done.borrow_mut() = true;
+            }
+        })
+        .unwrap();
+
+    let _ = pool.run_all_until_stalled();
+    assert!(!*done.borrow());
+
+    let mut received = vec![];
+    while received.len() < 10 {
+        let n = blocking.try_read(&mut buf).unwrap();
+        received.extend_from_slice(&buf[..n]);
+        let _ = pool.run_all_until_stalled();
+    }
+    assert_eq!(received, b"cdefghijkl");
+    assert!(*done.borrow());
+    assert_eq!(blocking.read(&mut buf).now_or_never(), Some(Ok(0)));
+}
@@ -0,0 +1,115 @@
+//! Helpers shared by the tests of the `sel4-async-*` crates.
+//!
+//! This crate depends on `sel4-async-block-io`, so that crate's own tests keep their own helpers.
+
+#![feature(async_fn_in_trait)]
+
+use std::cell::{Cell, RefCell, RefMut};
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::Poll;
+
+use sel4_async_block_io::{BlockIO, BytesIO, WritableBlockIO, WritableBytesIO};
+use sel4_async_single_threaded_executor::run_until_stalled;
+
+/// Runs `future` to completion, which it must reach without waiting on anything external.
+///
+/// # Panics
+///
+/// Panics if `future` stalls.
+pub fn block_on<T>(future: impl Future<Output = T>) -> T {
+    match run_until_stalled(pin!(future)) {
+        Poll::Ready(v) => v,
+        Poll::Pending => panic!("stalled"),
+    }
+}
+
+/// An in-memory block device. Clones share the same contents.
+#[derive(Clone)]
+pub struct MemoryBlockIO<const BLOCK_SIZE: usize> {
+    blocks: Rc<RefCell<Vec<[u8; BLOCK_SIZE]>>>,
+    num_reads: Rc<Cell<usize>>,
+}
+
+impl<const BLOCK_SIZE: usize> MemoryBlockIO<BLOCK_SIZE> {
+    /// A device of `num_blocks` zeroed blocks.
+    pub fn new(num_blocks: usize) -> Self {
+        Self::from_blocks(vec![[0; BLOCK_SIZE]; num_blocks])
+    }
+
+    pub fn from_blocks(blocks: Vec<[u8; BLOCK_SIZE]>) -> Self {
+        Self {
+            blocks: Rc::new(RefCell::new(blocks)),
+            num_reads: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the length of `bytes` is not a multiple of `BLOCK_SIZE`.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len() % BLOCK_SIZE, 0);
+        Self::from_blocks(
+            bytes
+                .chunks_exact(BLOCK_SIZE)
+                .map(|block| block.try_into().unwrap())
+                .collect(),
+        )
+    }
+
+    /// The contents of the device, for inspection or tampering.
+    pub fn blocks(&self) -> RefMut<'_, Vec<[u8; BLOCK_SIZE]>> {
+        self.blocks.borrow_mut()
+    }
+
+    /// The number of calls to [`BlockIO::read_block`] so far.
+    pub fn num_reads(&self) -> usize {
+        self.num_reads.get()
+    }
+}
+
+impl<const BLOCK_SIZE: usize> BlockIO<BLOCK_SIZE> for MemoryBlockIO<BLOCK_SIZE> {
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.num_reads.set(self.num_reads.get() + 1);
+        *buf = self.blocks.borrow()[block_id];
+    }
+}
+
+impl<const BLOCK_SIZE: usize> WritableBlockIO<BLOCK_SIZE> for MemoryBlockIO<BLOCK_SIZE> {
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        self.blocks.borrow_mut()[block_id] = *buf;
+    }
+}
+
+/// An in-memory byte-addressable device. Clones share the same contents.
+#[derive(Clone)]
+pub struct MemoryBytesIO {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl MemoryBytesIO {
+    /// A device of `size` zeroed bytes.
+    pub fn new(size: usize) -> Self {
+        Self {
+            bytes: Rc::new(RefCell::new(vec![0; size])),
+        }
+    }
+
+    /// The contents of the device, for inspection or tampering.
+    pub fn bytes(&self) -> RefMut<'_, Vec<u8>> {
+        self.bytes.borrow_mut()
+    }
+}
+
+impl BytesIO for MemoryBytesIO {
+    async fn read(&self, offset: usize, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.bytes.borrow()[offset..][..buf.len()]);
+    }
+}
+
+impl WritableBytesIO for MemoryBytesIO {
+    async fn write(&self, offset: usize, buf: &[u8]) {
+        self.bytes.borrow_mut()[offset..][..buf.len()].copy_from_slice(buf);
+    }
+}
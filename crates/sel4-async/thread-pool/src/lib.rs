@@ -0,0 +1,303 @@
+//! An executor which multiplexes tasks over a fixed set of kernel threads, for services whose work
+//! no longer fits on a single thread running `sel4-async-single-threaded-executor`.
+//!
+//! Which task runs next is decided by a pluggable [`Policy`]. [`Fifo`], [`Priority`], and
+//! [`EarliestDeadlineFirst`] are provided.
+//!
+//! This crate does not itself make any system calls. Each worker is a kernel thread which calls
+//! [`ThreadPool::run_worker`], and idle workers block and are woken via a user-provided [`Park`]
+//! implementation. Typically, each worker waits on its own notification, but a [`Park`]
+//! implementation is also the place to yield a worker's scheduling context to another thread
+//! (with `YieldTo` or by donation on MCS configurations) rather than blocking outright.
+//!
+//! Scheduling between tasks is cooperative. A task keeps its worker until it returns
+//! `Poll::Pending`, so long-running computations should call [`yield_now`] periodically.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::{Context, Poll};
+
+use futures::task::{waker_ref, ArcWake};
+
+mod policy;
+
+pub use policy::{EarliestDeadlineFirst, Fifo, Policy, Priority};
+
+/// How an idle worker blocks until there is work for it.
+///
+/// A call to `unpark(worker)` which precedes the corresponding call to `park(worker)` must cause
+/// that call to return immediately, as is the case when each worker waits on its own notification.
+/// Spurious returns from `park` are harmless.
+pub trait Park: Send + Sync {
+    fn park(&self, worker: usize);
+
+    fn unpark(&self, worker: usize);
+}
+
+pub struct ThreadPool<T: Policy, K> {
+    shared: Arc<Shared<T, K>>,
+}
+
+impl<T: Policy, K> Clone for ThreadPool<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+struct Shared<T, K> {
+    state: spin::Mutex<State<T>>,
+    parker: K,
+}
+
+struct State<T> {
+    policy: T,
+    next_seq: u64,
+    idle: Vec<bool>,
+    shutting_down: bool,
+}
+
+impl<T: Policy + 'static, K: Park + 'static> ThreadPool<T, K> {
+    pub fn new(policy: T, parker: K, num_workers: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: spin::Mutex::new(State {
+                    policy,
+                    next_seq: 0,
+                    idle: vec![false; num_workers],
+                    shutting_down: false,
+                }),
+                parker,
+            }),
+        }
+    }
+
+    pub fn num_workers(&self) -> usize {
+        self.shared.state.lock().idle.len()
+    }
+
+    pub fn parker(&self) -> &K {
+        &self.shared.parker
+    }
+
+    /// Spawns a task, which the policy will schedule according to `params`.
+    ///
+    /// Tasks may be spawned from any thread, including from within other tasks.
+    pub fn spawn(&self, params: T::Params, future: impl Future<Output = ()> + Send + 'static) {
+        let scheduler: Weak<dyn Schedule<T::Params>> = Arc::<Shared<T, K>>::downgrade(&self.shared);
+        let task = Arc::new(Task {
+            future: UnsafeCell::new(Some(Box::pin(future))),
+            params,
+            state: AtomicU8::new(SCHEDULED),
+            scheduler,
+        });
+        self.shared.schedule(task);
+    }
+
+    /// Runs tasks on the calling thread, as worker `worker`, until [`shutdown`](Self::shutdown)
+    /// has been called and no tasks are runnable.
+    ///
+    /// Each worker index in `0..num_workers` must be used by at most one thread at a time.
+    pub fn run_worker(&self, worker: usize) {
+        loop {
+            let runnable = {
+                let mut state = self.shared.state.lock();
+                state.idle[worker] = false;
+                match state.policy.pop() {
+                    Some(runnable) => runnable,
+                    None => {
+                        if state.shutting_down {
+                            return;
+                        }
+                        state.idle[worker] = true;
+                        drop(state);
+                        self.shared.parker.park(worker);
+                        continue;
+                    }
+                }
+            };
+            let task = runnable.task;
+            if task.run() {
+                self.shared.schedule(task);
+            }
+        }
+    }
+
+    /// Causes each worker to return from [`run_worker`](Self::run_worker) once no tasks are
+    /// runnable. Tasks which are still pending at that point are not polled again.
+    pub fn shutdown(&self) {
+        let mut to_unpark = vec![];
+        {
+            let mut state = self.shared.state.lock();
+            state.shutting_down = true;
+            for (worker, idle) in state.idle.iter_mut().enumerate() {
+                if *idle {
+                    *idle = false;
+                    to_unpark.push(worker);
+                }
+            }
+        }
+        for worker in to_unpark {
+            self.shared.parker.unpark(worker);
+        }
+    }
+}
+
+trait Schedule<P>: Send + Sync {
+    fn schedule(&self, task: Arc<Task<P>>);
+}
+
+impl<T: Policy, K: Park> Schedule<T::Params> for Shared<T, K> {
+    fn schedule(&self, task: Arc<Task<T::Params>>) {
+        let worker = {
+            let mut state = self.state.lock();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.policy.push(Runnable { task, seq });
+            let worker = state.idle.iter().position(|idle| *idle);
+            if let Some(worker) = worker {
+                state.idle[worker] = false;
+            }
+            worker
+        };
+        if let Some(worker) = worker {
+            self.parker.unpark(worker);
+        }
+    }
+}
+
+/// A task which is ready to be polled, as seen by a [`Policy`].
+pub struct Runnable<P> {
+    task: Arc<Task<P>>,
+    seq: u64,
+}
+
+impl<P> Runnable<P> {
+    /// The parameters with which the task was spawned.
+    pub fn params(&self) -> &P {
+        &self.task.params
+    }
+
+    /// A value which increases each time any task in the pool becomes runnable.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+// Task states. The future is only accessed by the worker which moved the task into `RUNNING`.
+const IDLE: u8 = 0;
+const SCHEDULED: u8 = 1;
+const RUNNING: u8 = 2;
+const NOTIFIED: u8 = 3;
+const COMPLETE: u8 = 4;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct Task<P> {
+    future: UnsafeCell<Option<BoxFuture>>,
+    params: P,
+    state: AtomicU8,
+    // Weak, so that tasks which are never woken again do not keep the pool alive.
+    scheduler: Weak<dyn Schedule<P>>,
+}
+
+unsafe impl<P: Send> Send for Task<P> {}
+unsafe impl<P: Sync> Sync for Task<P> {}
+
+impl<P: Send + Sync + 'static> Task<P> {
+    // Polls the task once, returning whether it must be scheduled again.
+    fn run(self: &Arc<Self>) -> bool {
+        let prev = self.state.swap(RUNNING, Ordering::AcqRel);
+        assert_eq!(prev, SCHEDULED);
+        let waker = waker_ref(self);
+        let mut cx = Context::from_waker(&waker);
+        // SAFETY: only this worker accesses the future while the task is `RUNNING`.
+        let future = unsafe { &mut *self.future.get() };
+        match future.as_mut().unwrap().as_mut().poll(&mut cx) {
+            Poll::Ready(()) => {
+                *future = None;
+                self.state.store(COMPLETE, Ordering::Release);
+                false
+            }
+            Poll::Pending => {
+                match self.state.compare_exchange(
+                    RUNNING,
+                    IDLE,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => false,
+                    Err(NOTIFIED) => {
+                        self.state.store(SCHEDULED, Ordering::Release);
+                        true
+                    }
+                    Err(_) => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+impl<P: Send + Sync + 'static> ArcWake for Task<P> {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        let mut cur = arc_self.state.load(Ordering::Acquire);
+        loop {
+            let new = match cur {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return,
+            };
+            match arc_self.state.compare_exchange_weak(
+                cur,
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    if cur == IDLE {
+                        if let Some(scheduler) = arc_self.scheduler.upgrade() {
+                            scheduler.schedule(arc_self.clone());
+                        }
+                    }
+                    return;
+                }
+                Err(actual) => cur = actual,
+            }
+        }
+    }
+}
+
+/// Returns `Pending` once, after scheduling the calling task to be polled again, so that the
+/// policy may choose to run other tasks in the meantime.
+pub async fn yield_now() {
+    YieldNow { yielded: false }.await
+}
+
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::collections::{BinaryHeap, VecDeque};
+use core::cmp::{Ordering, Reverse};
+
+use crate::Runnable;
+
+/// Decides which runnable task a worker polls next.
+///
+/// A policy only ever sees tasks which are ready to be polled. Each task carries a
+/// [`Params`](Self::Params) value, given at spawn time, which the policy may use to order tasks.
+/// Tasks of equal standing should be ordered by [`Runnable::seq`], which increases each time a
+/// task becomes runnable, so that none are starved by others of the same standing.
+pub trait Policy: Send {
+    type Params: Send + Sync + 'static;
+
+    fn push(&mut self, task: Runnable<Self::Params>);
+
+    fn pop(&mut self) -> Option<Runnable<Self::Params>>;
+}
+
+/// Runs tasks in the order in which they become runnable.
+pub struct Fifo {
+    queue: VecDeque<Runnable<()>>,
+}
+
+impl Fifo {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Policy for Fifo {
+    type Params = ();
+
+    fn push(&mut self, task: Runnable<()>) {
+        self.queue.push_back(task)
+    }
+
+    fn pop(&mut self) -> Option<Runnable<()>> {
+        self.queue.pop_front()
+    }
+}
+
+/// Runs the runnable task with the highest priority, and tasks of equal priority in FIFO order.
+///
+/// Scheduling is cooperative, so a high priority task only preempts others at their next `.await`
+/// which returns `Pending`.
+pub struct Priority {
+    heap: BinaryHeap<Keyed<(u8, Reverse<u64>), u8>>,
+}
+
+impl Priority {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Policy for Priority {
+    type Params = u8;
+
+    fn push(&mut self, task: Runnable<u8>) {
+        let key = (*task.params(), Reverse(task.seq()));
+        self.heap.push(Keyed { key, task })
+    }
+
+    fn pop(&mut self) -> Option<Runnable<u8>> {
+        self.heap.pop().map(|keyed| keyed.task)
+    }
+}
+
+/// Runs the runnable task with the earliest deadline, and tasks with equal deadlines in FIFO
+/// order.
+///
+/// Deadlines are absolute, in whatever units the user chooses (for example, the ticks of a
+/// `sel4_async_timers::SharedTimers`). The pool does not itself keep time, so a task which misses
+/// its deadline is simply run as soon as possible.
+pub struct EarliestDeadlineFirst {
+    heap: BinaryHeap<Keyed<Reverse<(u64, u64)>, u64>>,
+}
+
+impl EarliestDeadlineFirst {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl Default for EarliestDeadlineFirst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Policy for EarliestDeadlineFirst {
+    type Params = u64;
+
+    fn push(&mut self, task: Runnable<u64>) {
+        let key = Reverse((*task.params(), task.seq()));
+        self.heap.push(Keyed { key, task })
+    }
+
+    fn pop(&mut self) -> Option<Runnable<u64>> {
+        self.heap.pop().map(|keyed| keyed.task)
+    }
+}
+
+// A heap entry, ordered by `key` alone.
+struct Keyed<K, P> {
+    key: K,
+    task: Runnable<P>,
+}
+
+impl<K: Ord, P> PartialEq for Keyed<K, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Ord, P> Eq for Keyed<K, P> {}
+
+impl<K: Ord, P> PartialOrd for Keyed<K, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, P> Ord for Keyed<K, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
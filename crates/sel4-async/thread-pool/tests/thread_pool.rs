@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use sel4_async_thread_pool::{
+    yield_now, EarliestDeadlineFirst, Fifo, Park, Policy, Priority, ThreadPool,
+};
+
+// Stands in for one notification per worker.
+struct Parker {
+    workers: Vec<(Mutex<bool>, Condvar)>,
+}
+
+impl Parker {
+    fn new(num_workers: usize) -> Self {
+        Self {
+            workers: (0..num_workers)
+                .map(|_| (Mutex::new(false), Condvar::new()))
+                .collect(),
+        }
+    }
+}
+
+impl Park for Parker {
+    fn park(&self, worker: usize) {
+        let (signaled, condvar) = &self.workers[worker];
+        let mut signaled = condvar
+            .wait_while(signaled.lock().unwrap(), |signaled| !*signaled)
+            .unwrap();
+        *signaled = false;
+    }
+
+    fn unpark(&self, worker: usize) {
+        let (signaled, condvar) = &self.workers[worker];
+        *signaled.lock().unwrap() = true;
+        condvar.notify_one();
+    }
+}
+
+#[test]
+fn many_tasks_many_workers() {
+    const NUM_WORKERS: usize = 4;
+    const NUM_TASKS: usize = 100;
+    const NUM_YIELDS: usize = 10;
+
+    let pool = ThreadPool::new(Fifo::new(), Parker::new(NUM_WORKERS), NUM_WORKERS);
+    let remaining = Arc::new(AtomicUsize::new(NUM_TASKS * 2));
+    let total = Arc::new(AtomicUsize::new(0));
+
+    for i in 0..NUM_TASKS {
+        let pool_ = pool.clone();
+        let remaining = remaining.clone();
+        let total = total.clone();
+        pool.spawn((), async move {
+            for _ in 0..NUM_YIELDS {
+                total.fetch_add(1, Ordering::SeqCst);
+                yield_now().await;
+            }
+            // Spawn from within a task
+            let pool = pool_.clone();
+            let remaining_ = remaining.clone();
+            pool_.spawn((), async move {
+                total.fetch_add(i, Ordering::SeqCst);
+                if remaining_.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    pool.shutdown();
+                }
+            });
+            if remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+                pool_.shutdown();
+            }
+        });
+    }
+
+    thread::scope(|s| {
+        for worker in 0..NUM_WORKERS {
+            let pool = &pool;
+            s.spawn(move || pool.run_worker(worker));
+        }
+    });
+
+    assert_eq!(
+        total.load(Ordering::SeqCst),
+        NUM_TASKS * NUM_YIELDS + (0..NUM_TASKS).sum::<usize>()
+    );
+}
+
+// With a single worker, tasks run in exactly the order given by the policy.
+fn order_of_completion<T: Policy + 'static>(policy: T, params: Vec<T::Params>) -> Vec<usize> {
+    let pool = ThreadPool::new(policy, Parker::new(1), 1);
+    let order = Arc::new(Mutex::new(vec![]));
+    for (i, params) in params.into_iter().enumerate() {
+        let order = order.clone();
+        pool.spawn(params, async move {
+            order.lock().unwrap().push(i);
+        });
+    }
+    pool.shutdown();
+    pool.run_worker(0);
+    Arc::try_unwrap(order).unwrap().into_inner().unwrap()
+}
+
+#[test]
+fn policies() {
+    assert_eq!(order_of_completion(Fifo::new(), vec![(); 4]), [0, 1, 2, 3]);
+    assert_eq!(
+        order_of_completion(Priority::new(), vec![1, 5, 1, 9, 5]),
+        [3, 1, 4, 0, 2]
+    );
+    assert_eq!(
+        order_of_completion(EarliestDeadlineFirst::new(), vec![30, 10, 20, 10]),
+        [1, 3, 2, 0]
+    );
+}
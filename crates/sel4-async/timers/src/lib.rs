@@ -5,29 +5,64 @@ extern crate alloc;
 
 use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
-use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::future::Future;
 use core::ops::Bound;
-use core::task::Poll;
-use core::task::Waker;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
 
 use futures::prelude::*;
 use smoltcp::time::{Duration, Instant};
 
+mod virtual_time;
+mod wall_clock;
+mod wheel;
+
+use wall_clock::WallClockAnchor;
+use wheel::TimerWheel;
+
+pub use virtual_time::{MockClock, VirtualTime};
+pub use wall_clock::{HttpDate, SystemTime};
+
 #[derive(Clone)]
 pub struct SharedTimers {
     inner: Rc<RefCell<SharedTimersInner>>,
 }
 
 struct SharedTimersInner {
-    pending: BTreeMap<Instant, Vec<Waker>>,
+    pending: Pending,
     now: Instant,
+    next_id: TimerId,
+    wall_clock: Option<WallClockAnchor>,
 }
 
+enum Pending {
+    Ordered(BTreeMap<(Instant, TimerId), Waker>),
+    Wheel(TimerWheel),
+}
+
+type TimerId = u64;
+
 impl SharedTimers {
     pub fn new(now: Instant) -> Self {
+        Self::with_pending(now, Pending::Ordered(BTreeMap::new()))
+    }
+
+    /// Like [`SharedTimers::new`], but keeps pending timers in a hierarchical timer wheel with
+    /// the given resolution, which suits workloads with many concurrent sleeps. Timers may
+    /// expire up to `resolution` late.
+    pub fn new_with_timer_wheel(now: Instant, resolution: Duration) -> Self {
+        Self::with_pending(now, Pending::Wheel(TimerWheel::new(now, resolution)))
+    }
+
+    fn with_pending(now: Instant, pending: Pending) -> Self {
         Self {
-            inner: Rc::new(RefCell::new(SharedTimersInner::new(now))),
+            inner: Rc::new(RefCell::new(SharedTimersInner {
+                pending,
+                now,
+                next_id: 0,
+                wall_clock: None,
+            })),
         }
     }
 
@@ -35,62 +70,286 @@ impl SharedTimers {
         &self.inner
     }
 
+    /// The timestamp passed to the most recent call to [`SharedTimers::poll`],
+    /// [`SharedTimers::poll_at`], or [`SharedTimers::poll_delay`].
+    pub fn now(&self) -> Instant {
+        *self.inner().borrow().now()
+    }
+
+    /// Records that the wall-clock time at [`SharedTimers::now`] is `system_time`, for example
+    /// as learned using SNTP. Subsequent wall-clock times are derived from the monotonic clock.
+    pub fn set_system_time(&self, system_time: SystemTime) {
+        let now = self.now();
+        self.set_system_time_at(now, system_time);
+    }
+
+    /// Like [`SharedTimers::set_system_time`], but for the wall-clock time at `instant`.
+    pub fn set_system_time_at(&self, instant: Instant, system_time: SystemTime) {
+        self.inner().borrow_mut().wall_clock = Some(WallClockAnchor::new(instant, system_time));
+    }
+
+    /// The wall-clock time at [`SharedTimers::now`], or `None` if it has not yet been set.
+    pub fn system_time(&self) -> Option<SystemTime> {
+        self.system_time_at(self.now())
+    }
+
+    pub fn system_time_at(&self, instant: Instant) -> Option<SystemTime> {
+        self.inner()
+            .borrow()
+            .wall_clock
+            .map(|anchor| anchor.system_time_at(instant))
+    }
+
+    /// The number of timers which are registered and have not yet fired, for diagnostics.
+    pub fn num_pending(&self) -> usize {
+        match &self.inner().borrow().pending {
+            Pending::Ordered(pending) => pending.len(),
+            Pending::Wheel(wheel) => wheel.num_pending(),
+        }
+    }
+
+    /// The earliest time at which a pending timer will have expired, without updating
+    /// [`SharedTimers::now`] as [`SharedTimers::poll_at`] does.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.inner().borrow().next_deadline()
+    }
+
     pub fn poll(&self, timestamp: Instant) -> bool {
         self.inner().borrow_mut().poll(timestamp)
     }
 
-    pub fn poll_at(&mut self, timestamp: Instant) -> Option<Instant> {
+    pub fn poll_at(&self, timestamp: Instant) -> Option<Instant> {
         self.inner().borrow_mut().poll_at(timestamp)
     }
 
-    pub fn poll_delay(&mut self, timestamp: Instant) -> Option<Duration> {
+    pub fn poll_delay(&self, timestamp: Instant) -> Option<Duration> {
         self.inner().borrow_mut().poll_delay(timestamp)
     }
 
+    /// Returns a [`TimerHandle`] which resolves once `expiry` has passed. Unlike
+    /// [`SharedTimers::sleep_until`], the timer can be cancelled explicitly with
+    /// [`TimerHandle::cancel`], as well as by dropping the handle.
+    pub fn set_timer(&self, expiry: Instant) -> TimerHandle {
+        TimerHandle {
+            timers: self.clone(),
+            expiry,
+            id: None,
+        }
+    }
+
     pub async fn sleep_until(&self, until: Instant) {
-        future::poll_fn(|cx| {
-            let mut inner = self.inner().borrow_mut();
-            if inner.now() < &until {
-                inner.set_timer(until, cx.waker());
-                Poll::Pending
-            } else {
-                Poll::Ready(())
-            }
-        })
-        .await;
+        self.set_timer(until).await;
     }
 
     pub async fn sleep(&self, d: Duration) {
-        let now = *self.inner().borrow().now();
+        let now = self.now();
         self.sleep_until(now + d).await;
     }
+
+    /// Wraps `future` so that it resolves to `Err(Elapsed)` if it has not completed by
+    /// `deadline`.
+    pub fn timeout_at<F: Future>(&self, deadline: Instant, future: F) -> Timeout<F> {
+        Timeout {
+            future,
+            timer: self.set_timer(deadline),
+        }
+    }
+
+    pub fn timeout<F: Future>(&self, d: Duration, future: F) -> Timeout<F> {
+        let now = self.now();
+        self.timeout_at(now + d, future)
+    }
+
+    /// Returns a stream which yields the scheduled time of each tick, the first of which is one
+    /// `period` from now.
+    ///
+    /// Ticks are scheduled relative to the first, rather than to when the previous tick was
+    /// observed, so that delays in polling do not accumulate.
+    pub fn interval(&self, period: Duration, missed_ticks: MissedTicks) -> Interval {
+        let now = self.now();
+        self.interval_at(now + period, period, missed_ticks)
+    }
+
+    /// Like [`SharedTimers::interval`], but with the first tick at `start`.
+    pub fn interval_at(
+        &self,
+        start: Instant,
+        period: Duration,
+        missed_ticks: MissedTicks,
+    ) -> Interval {
+        assert_ne!(period.total_micros(), 0);
+        Interval {
+            timers: self.clone(),
+            period,
+            missed_ticks,
+            next: start,
+            timer: None,
+        }
+    }
 }
 
-impl SharedTimersInner {
-    fn new(now: Instant) -> Self {
-        Self {
-            pending: BTreeMap::new(),
-            now,
+/// What an [`Interval`] does when it is polled after more than one tick has been missed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MissedTicks {
+    /// Yield each missed tick immediately, one after the other.
+    CatchUp,
+    /// Yield the latest missed tick only, and continue on the original schedule.
+    Skip,
+}
+
+/// Stream returned by [`SharedTimers::interval`] and [`SharedTimers::interval_at`].
+pub struct Interval {
+    timers: SharedTimers,
+    period: Duration,
+    missed_ticks: MissedTicks,
+    next: Instant,
+    timer: Option<TimerHandle>,
+}
+
+impl Interval {
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Waits for the next tick.
+    pub async fn tick(&mut self) -> Instant {
+        future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    fn poll_tick(&mut self, cx: &mut Context) -> Poll<Instant> {
+        let timer = self
+            .timer
+            .get_or_insert_with(|| self.timers.set_timer(self.next));
+        if Pin::new(timer).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.timer = None;
+        let mut tick = self.next;
+        if self.missed_ticks == MissedTicks::Skip {
+            let now = self.timers.now();
+            let missed = (now - tick).total_micros() / self.period.total_micros();
+            tick += Duration::from_micros(missed * self.period.total_micros());
+        }
+        self.next = tick + self.period;
+        Poll::Ready(tick)
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_tick(cx).map(Some)
+    }
+}
+
+/// A timer registered with [`SharedTimers::set_timer`], which resolves once its expiry has
+/// passed.
+///
+/// The timer's pending entry is registered when the handle is first polled, and removed when it
+/// fires, when the handle is dropped, or by [`TimerHandle::cancel`].
+pub struct TimerHandle {
+    timers: SharedTimers,
+    expiry: Instant,
+    id: Option<TimerId>,
+}
+
+impl TimerHandle {
+    pub fn expiry(&self) -> Instant {
+        self.expiry
+    }
+
+    pub fn cancel(self) {}
+
+    fn deregister(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.timers
+                .inner()
+                .borrow_mut()
+                .cancel_timer(self.expiry, id);
+        }
+    }
+}
+
+impl Future for TimerHandle {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let expiry = self.expiry;
+        if self.timers.now() >= expiry {
+            self.deregister();
+            return Poll::Ready(());
         }
+        let this = &mut *self;
+        let mut inner = this.timers.inner().borrow_mut();
+        let id = *this.id.get_or_insert_with(|| inner.fresh_id());
+        inner.set_timer(expiry, id, cx.waker());
+        Poll::Pending
     }
+}
 
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+/// Future returned by [`SharedTimers::timeout`] and [`SharedTimers::timeout_at`].
+pub struct Timeout<F> {
+    future: F,
+    timer: TimerHandle,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // SAFETY: `future` is structurally pinned, and is never moved out of `self`.
+        let future = unsafe { self.as_mut().map_unchecked_mut(|this| &mut this.future) };
+        if let Poll::Ready(output) = future.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        // SAFETY: `timer` is not structurally pinned, and `TimerHandle` is `Unpin`.
+        let timer = unsafe { &mut self.get_unchecked_mut().timer };
+        Pin::new(timer).poll(cx).map(|()| Err(Elapsed))
+    }
+}
+
+impl SharedTimersInner {
     fn now(&self) -> &Instant {
         &self.now
     }
 
     fn poll(&mut self, timestamp: Instant) -> bool {
         self.now = timestamp;
-        let mut cursor = self.pending.upper_bound_mut(Bound::Included(&timestamp));
-        let mut activity = false;
-        while cursor.remove_current_and_move_back().is_some() {
-            activity = true;
+        match &mut self.pending {
+            Pending::Ordered(pending) => {
+                let mut cursor =
+                    pending.upper_bound_mut(Bound::Included(&(timestamp, TimerId::MAX)));
+                let mut activity = false;
+                while let Some((_, waker)) = cursor.remove_current_and_move_back() {
+                    waker.wake();
+                    activity = true;
+                }
+                activity
+            }
+            Pending::Wheel(wheel) => wheel.poll(timestamp),
         }
-        activity
     }
 
     fn poll_at(&mut self, timestamp: Instant) -> Option<Instant> {
         self.now = timestamp;
-        self.pending.first_entry().map(|entry| *entry.key())
+        self.next_deadline()
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        match &self.pending {
+            Pending::Ordered(pending) => pending.first_key_value().map(|((expiry, _), _)| *expiry),
+            Pending::Wheel(wheel) => wheel.next_deadline(),
+        }
     }
 
     fn poll_delay(&mut self, timestamp: Instant) -> Option<Duration> {
@@ -98,7 +357,31 @@ impl SharedTimersInner {
             .map(|deadline| deadline.max(timestamp) - timestamp)
     }
 
-    fn set_timer(&mut self, expiry: Instant, waker: &Waker) {
-        self.pending.entry(expiry).or_default().push(waker.clone());
+    fn fresh_id(&mut self) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    // Registers the timer `id`, or replaces its waker if it is already registered.
+    fn set_timer(&mut self, expiry: Instant, id: TimerId, waker: &Waker) {
+        match &mut self.pending {
+            Pending::Ordered(pending) => {
+                pending.insert((expiry, id), waker.clone());
+            }
+            Pending::Wheel(wheel) => {
+                wheel.remove(expiry, id);
+                wheel.insert(expiry, id, waker);
+            }
+        }
+    }
+
+    fn cancel_timer(&mut self, expiry: Instant, id: TimerId) {
+        match &mut self.pending {
+            Pending::Ordered(pending) => {
+                pending.remove(&(expiry, id));
+            }
+            Pending::Wheel(wheel) => wheel.remove(expiry, id),
+        }
     }
 }
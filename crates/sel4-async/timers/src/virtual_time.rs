@@ -0,0 +1,109 @@
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+use smoltcp::time::{Duration, Instant};
+
+use crate::SharedTimers;
+
+/// A clock which advances only when told to, for driving [`SharedTimers`] in tests.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now: Rc<Cell<Instant>>,
+}
+
+impl MockClock {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            now: Rc::new(Cell::new(start)),
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    pub fn set(&self, now: Instant) {
+        assert!(now >= self.now());
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, d: Duration) {
+        self.set(self.now() + d);
+    }
+}
+
+/// Drives a [`SharedTimers`] in virtual time, so that timer-dependent code can be tested
+/// deterministically and without waiting.
+///
+/// Methods which advance time take a closure which runs the executor until it stalls. Timers are
+/// fired in order of expiry, and the executor is run after each, so that tasks observe the clock
+/// at exactly the expiry of the timer which woke them.
+pub struct VirtualTime {
+    clock: MockClock,
+    timers: SharedTimers,
+}
+
+impl VirtualTime {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            clock: MockClock::new(start),
+            timers: SharedTimers::new(start),
+        }
+    }
+
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    pub fn timers(&self) -> &SharedTimers {
+        &self.timers
+    }
+
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Advances the clock by `d`.
+    pub fn advance(&self, d: Duration, run_until_stalled: impl FnMut()) {
+        self.advance_to(self.now() + d, run_until_stalled)
+    }
+
+    /// Advances the clock to `target`, firing each timer which expires on the way.
+    pub fn advance_to(&self, target: Instant, mut run_until_stalled: impl FnMut()) {
+        run_until_stalled();
+        while let Some(deadline) = self.timers.poll_at(self.now()) {
+            if deadline > target {
+                break;
+            }
+            self.step_to(deadline.max(self.now()), &mut run_until_stalled);
+        }
+        self.step_to(target, &mut run_until_stalled);
+    }
+
+    /// Advances the clock to the earliest pending timer's expiry, if there is one, and fires it.
+    /// Returns the new time, or `None` if no timers are pending.
+    pub fn advance_to_next_timer(&self, mut run_until_stalled: impl FnMut()) -> Option<Instant> {
+        run_until_stalled();
+        let deadline = self.timers.poll_at(self.now())?.max(self.now());
+        self.step_to(deadline, &mut run_until_stalled);
+        Some(deadline)
+    }
+
+    /// Repeatedly advances to the next timer until none are pending or the clock would pass
+    /// `limit`, which guards against timers that re-arm themselves forever.
+    pub fn run_until_idle(&self, limit: Instant, mut run_until_stalled: impl FnMut()) {
+        run_until_stalled();
+        while let Some(deadline) = self.timers.poll_at(self.now()) {
+            if deadline > limit {
+                break;
+            }
+            self.step_to(deadline.max(self.now()), &mut run_until_stalled);
+        }
+    }
+
+    fn step_to(&self, now: Instant, run_until_stalled: &mut impl FnMut()) {
+        self.clock.set(now);
+        self.timers.poll(now);
+        run_until_stalled();
+    }
+}
@@ -0,0 +1,134 @@
+use core::fmt;
+use core::ops::{Add, Sub};
+
+use smoltcp::time::{Duration, Instant};
+
+/// A point in wall-clock time, with microsecond precision.
+///
+/// Unlike [`Instant`], which measures time since an arbitrary point such as boot, a `SystemTime`
+/// measures time since the Unix epoch, and so is only available once it has been learned from an
+/// external source. See [`SharedTimers::set_system_time`](crate::SharedTimers::set_system_time).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemTime {
+    micros_since_unix_epoch: u64,
+}
+
+impl SystemTime {
+    pub const UNIX_EPOCH: Self = Self::from_micros_since_unix_epoch(0);
+
+    pub const fn from_micros_since_unix_epoch(micros: u64) -> Self {
+        Self {
+            micros_since_unix_epoch: micros,
+        }
+    }
+
+    pub const fn from_secs_since_unix_epoch(secs: u64) -> Self {
+        Self::from_micros_since_unix_epoch(secs * 1_000_000)
+    }
+
+    pub const fn micros_since_unix_epoch(&self) -> u64 {
+        self.micros_since_unix_epoch
+    }
+
+    pub const fn secs_since_unix_epoch(&self) -> u64 {
+        self.micros_since_unix_epoch / 1_000_000
+    }
+
+    /// Formats this time as an IMF-fixdate (RFC 9110, Section 5.6.7), for use in HTTP headers
+    /// such as `Date:`.
+    pub fn http_date(&self) -> HttpDate {
+        HttpDate(*self)
+    }
+}
+
+impl Add<Duration> for SystemTime {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Self::from_micros_since_unix_epoch(self.micros_since_unix_epoch + rhs.total_micros())
+    }
+}
+
+impl Sub<Duration> for SystemTime {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        Self::from_micros_since_unix_epoch(self.micros_since_unix_epoch - rhs.total_micros())
+    }
+}
+
+impl Sub for SystemTime {
+    type Output = Duration;
+
+    fn sub(self, rhs: Self) -> Duration {
+        Duration::from_micros(self.micros_since_unix_epoch - rhs.micros_since_unix_epoch)
+    }
+}
+
+/// Returned by [`SystemTime::http_date`]. For example, `Sun, 06 Nov 1994 08:49:37 GMT`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HttpDate(SystemTime);
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+
+        let secs = self.0.secs_since_unix_epoch();
+        let days = secs / 86400;
+        let secs_of_day = secs % 86400;
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            WEEKDAYS[usize::try_from(days % 7).unwrap()],
+            day,
+            MONTHS[usize::try_from(month - 1).unwrap()],
+            year,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+        )
+    }
+}
+
+// Converts days since the Unix epoch to a (year, month, day) date in the proleptic Gregorian
+// calendar, after http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+// The wall-clock time corresponding to an instant, as learned from an external source.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct WallClockAnchor {
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+impl WallClockAnchor {
+    pub(crate) fn new(instant: Instant, system_time: SystemTime) -> Self {
+        Self {
+            instant,
+            system_time,
+        }
+    }
+
+    pub(crate) fn system_time_at(&self, instant: Instant) -> SystemTime {
+        if instant >= self.instant {
+            self.system_time + (instant - self.instant)
+        } else {
+            self.system_time - (self.instant - instant)
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use core::task::Waker;
+
+use smoltcp::time::{Duration, Instant};
+
+use crate::TimerId;
+
+const BITS_PER_LEVEL: u32 = 6;
+const SLOTS_PER_LEVEL: usize = 1 << BITS_PER_LEVEL;
+const NUM_LEVELS: usize = 4;
+
+/// A hierarchical timer wheel.
+///
+/// Time is divided into ticks of a fixed resolution, counted from an origin. A timer is placed in
+/// the lowest level whose slots are coarse enough for its expiry to fall within the level's next
+/// rotation, and is moved down a level (or fired) when the wheel advances past its slot. Timers
+/// beyond the range of the highest level are kept in an overflow list until they come into range.
+///
+/// Insertion is O(1), and timers never fire before their expiry, but may fire up to one tick
+/// after it.
+pub(crate) struct TimerWheel {
+    origin: Instant,
+    resolution: u64,
+    current: u64,
+    slots: Vec<Vec<Entry>>,
+    overflow: Vec<Entry>,
+}
+
+struct Entry {
+    tick: u64,
+    id: TimerId,
+    waker: Waker,
+}
+
+impl TimerWheel {
+    pub(crate) fn new(origin: Instant, resolution: Duration) -> Self {
+        let resolution = resolution.total_micros();
+        assert_ne!(resolution, 0);
+        Self {
+            origin,
+            resolution,
+            current: 0,
+            slots: (0..NUM_LEVELS * SLOTS_PER_LEVEL)
+                .map(|_| Vec::new())
+                .collect(),
+            overflow: Vec::new(),
+        }
+    }
+
+    fn micros_since_origin(&self, instant: Instant) -> u64 {
+        (instant.total_micros() - self.origin.total_micros())
+            .max(0)
+            .try_into()
+            .unwrap()
+    }
+
+    fn tick_for(&self, expiry: Instant) -> u64 {
+        let tick = (self.micros_since_origin(expiry) + self.resolution - 1) / self.resolution;
+        tick.max(self.current + 1)
+    }
+
+    pub(crate) fn insert(&mut self, expiry: Instant, id: TimerId, waker: &Waker) {
+        self.place(Entry {
+            tick: self.tick_for(expiry),
+            id,
+            waker: waker.clone(),
+        });
+    }
+
+    /// Removes the timer `id` with the given expiry, if it has not yet fired.
+    pub(crate) fn remove(&mut self, expiry: Instant, id: TimerId) {
+        // A timer which has not fired has a tick later than `current`, so `tick_for` recovers the
+        // tick with which it was inserted. Its entry is then in one of the slots covering that tick,
+        // or in the overflow list.
+        let tick = self.tick_for(expiry);
+        for level in 0..NUM_LEVELS {
+            let shift = BITS_PER_LEVEL * u32::try_from(level).unwrap();
+            let entries = &mut self.slots[slot_index(level, tick >> shift)];
+            if let Some(i) = entries.iter().position(|entry| entry.id == id) {
+                entries.swap_remove(i);
+                return;
+            }
+        }
+        if let Some(i) = self.overflow.iter().position(|entry| entry.id == id) {
+            self.overflow.swap_remove(i);
+        }
+    }
+
+    fn place(&mut self, entry: Entry) {
+        for level in 0..NUM_LEVELS {
+            let shift = BITS_PER_LEVEL * u32::try_from(level).unwrap();
+            if (entry.tick >> shift) - (self.current >> shift) < SLOTS_PER_LEVEL as u64 {
+                let i = slot_index(level, entry.tick >> shift);
+                self.slots[i].push(entry);
+                return;
+            }
+        }
+        self.overflow.push(entry);
+    }
+
+    /// Advances the wheel to `timestamp`, waking the timers which have expired. Returns whether
+    /// any were woken.
+    pub(crate) fn poll(&mut self, timestamp: Instant) -> bool {
+        let target = self.micros_since_origin(timestamp) / self.resolution;
+        if target <= self.current {
+            return false;
+        }
+        let mut taken = Vec::new();
+        for level in 0..NUM_LEVELS {
+            let shift = BITS_PER_LEVEL * u32::try_from(level).unwrap();
+            let first = self.current >> shift;
+            let passed = ((target >> shift) - first).min(SLOTS_PER_LEVEL as u64);
+            for slot in first + 1..=first + passed {
+                taken.append(&mut self.slots[slot_index(level, slot)]);
+            }
+        }
+        let top_shift = BITS_PER_LEVEL * u32::try_from(NUM_LEVELS).unwrap();
+        if target >> top_shift != self.current >> top_shift {
+            taken.append(&mut self.overflow);
+        }
+        self.current = target;
+        let mut activity = false;
+        for entry in taken {
+            if entry.tick <= target {
+                entry.waker.wake();
+                activity = true;
+            } else {
+                self.place(entry);
+            }
+        }
+        activity
+    }
+
+    pub(crate) fn num_pending(&self) -> usize {
+        self.slots
+            .iter()
+            .chain([&self.overflow])
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// The earliest time at which a timer will have expired.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let mut earliest = self.overflow.iter().map(|entry| entry.tick).min();
+        for level in 0..NUM_LEVELS {
+            let shift = BITS_PER_LEVEL * u32::try_from(level).unwrap();
+            let first = self.current >> shift;
+            // Slots cover disjoint, increasing ranges of ticks, so only the first occupied slot of
+            // each level need be considered.
+            let level_earliest = (first + 1..first + SLOTS_PER_LEVEL as u64)
+                .map(|slot| &self.slots[slot_index(level, slot)])
+                .find(|entries| !entries.is_empty())
+                .and_then(|entries| entries.iter().map(|entry| entry.tick).min());
+            earliest = match (earliest, level_earliest) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        earliest.map(|tick| {
+            Instant::from_micros(
+                self.origin.total_micros() + i64::try_from(tick * self.resolution).unwrap(),
+            )
+        })
+    }
+}
+
+fn slot_index(level: usize, slot: u64) -> usize {
+    level * SLOTS_PER_LEVEL + (slot as usize & (SLOTS_PER_LEVEL - 1))
+}
@@ -0,0 +1,87 @@
+use core::cell::RefCell;
+use core::pin::Pin;
+use core::task::Poll;
+use std::rc::Rc;
+
+use futures::prelude::*;
+use futures::task::LocalSpawnExt;
+use sel4_async_single_threaded_executor::LocalPool;
+use sel4_async_timers::{MissedTicks, SystemTime, VirtualTime};
+use smoltcp::time::{Duration, Instant};
+
+#[test]
+fn retransmit_with_backoff() {
+    let vt = VirtualTime::new(Instant::ZERO);
+    let mut pool = LocalPool::new();
+    let log = Rc::new(RefCell::new(vec![]));
+
+    pool.spawner()
+        .spawn_local({
+            let timers = vt.timers().clone();
+            let log = log.clone();
+            async move {
+                let mut backoff = Duration::from_millis(100);
+                for _ in 0..4 {
+                    let ack = future::pending::<()>();
+                    if timers.timeout(backoff, ack).await.is_err() {
+                        log.borrow_mut().push(timers.now());
+                        backoff *= 2;
+                    }
+                }
+            }
+        })
+        .unwrap();
+
+    let mut run = || {
+        let _ = pool.run_all_until_stalled();
+    };
+
+    vt.advance(Duration::from_millis(250), &mut run);
+    assert_eq!(*log.borrow(), [Instant::from_millis(100)],);
+
+    vt.run_until_idle(Instant::from_secs(10), &mut run);
+    assert_eq!(
+        *log.borrow(),
+        [100, 300, 700, 1500].map(Instant::from_millis),
+    );
+}
+
+#[test]
+fn interval_skips_missed_ticks() {
+    let vt = VirtualTime::new(Instant::ZERO);
+    let mut interval = vt
+        .timers()
+        .interval(Duration::from_millis(10), MissedTicks::Skip);
+    let mut next =
+        || sel4_async_single_threaded_executor::run_until_stalled(Pin::new(&mut interval.next()));
+
+    vt.advance(Duration::from_millis(10), || {});
+    assert_eq!(
+        next().map(Option::unwrap),
+        Poll::Ready(Instant::from_millis(10)),
+    );
+
+    vt.advance(Duration::from_millis(35), || {});
+    assert_eq!(
+        next().map(Option::unwrap),
+        Poll::Ready(Instant::from_millis(40)),
+    );
+    assert!(next().is_pending());
+}
+
+#[test]
+fn wall_clock_follows_monotonic_clock() {
+    let vt = VirtualTime::new(Instant::from_secs(10));
+    let timers = vt.timers();
+    assert_eq!(timers.system_time(), None);
+
+    timers.set_system_time(SystemTime::from_secs_since_unix_epoch(784111777));
+    vt.advance(Duration::from_secs(90061), || {});
+    let now = timers.system_time().unwrap();
+    assert_eq!(now.secs_since_unix_epoch(), 784111777 + 90061);
+    assert_eq!(now.http_date().to_string(), "Mon, 07 Nov 1994 09:50:38 GMT");
+    assert_eq!(
+        timers.system_time_at(Instant::ZERO).unwrap(),
+        SystemTime::from_secs_since_unix_epoch(784111767),
+    );
+}
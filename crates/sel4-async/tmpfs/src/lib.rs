@@ -0,0 +1,327 @@
+//! A heap-backed filesystem, for scratch space and for testing filesystem consumers without a
+//! block device.
+//!
+//! Paths are `/`-separated, with leading and trailing separators ignored, so that `""` and `"/"`
+//! both refer to the root directory. Components may not be empty, `.`, or `..`. As in `sel4-async-block-io-cpiofs`, operations are `async`
+//! even though they complete immediately, so that consumers can be written against either.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Bound;
+
+use sel4_async_fs::{self as fs, FileSystem};
+
+#[derive(Debug, Default)]
+pub struct TmpFs {
+    nodes: BTreeMap<String, Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    RegularFile(Vec<u8>),
+    Directory,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EntryType {
+    RegularFile,
+    Directory,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub ty: EntryType,
+    pub size: usize,
+}
+
+impl TmpFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn metadata(&self, path: &str) -> Result<Metadata, Error> {
+        let path = normalize(path);
+        if path.is_empty() {
+            return Ok(Metadata {
+                ty: EntryType::Directory,
+                size: 0,
+            });
+        }
+        match self.nodes.get(path).ok_or(Error::NotFound)? {
+            Node::RegularFile(data) => Ok(Metadata {
+                ty: EntryType::RegularFile,
+                size: data.len(),
+            }),
+            Node::Directory => Ok(Metadata {
+                ty: EntryType::Directory,
+                size: 0,
+            }),
+        }
+    }
+
+    pub async fn create_dir(&mut self, path: &str) -> Result<(), Error> {
+        let path = self.check_new(path)?;
+        self.nodes.insert(path.to_string(), Node::Directory);
+        Ok(())
+    }
+
+    /// Creates an empty regular file.
+    pub async fn create_file(&mut self, path: &str) -> Result<(), Error> {
+        let path = self.check_new(path)?;
+        self.nodes
+            .insert(path.to_string(), Node::RegularFile(Vec::new()));
+        Ok(())
+    }
+
+    /// Reads from the regular file at `path`, returning the number of bytes read, which is less
+    /// than `buf.len()` only at the end of the file.
+    pub async fn read(&self, path: &str, offset: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let data = self.file(path)?;
+        let start = offset.min(data.len());
+        let n = buf.len().min(data.len() - start);
+        buf[..n].copy_from_slice(&data[start..][..n]);
+        Ok(n)
+    }
+
+    /// Writes to the regular file at `path`, extending it with zeros if `offset` is past its end.
+    pub async fn write(&mut self, path: &str, offset: usize, buf: &[u8]) -> Result<(), Error> {
+        let data = self.file_mut(path)?;
+        let end = offset.checked_add(buf.len()).ok_or(Error::FileTooLarge)?;
+        if end > data.len() {
+            resize(data, end)?;
+        }
+        data[offset..end].copy_from_slice(buf);
+        Ok(())
+    }
+
+    /// Shrinks or extends (with zeros) the regular file at `path` to `len` bytes.
+    pub async fn truncate(&mut self, path: &str, len: usize) -> Result<(), Error> {
+        resize(self.file_mut(path)?, len)
+    }
+
+    /// Moves the file or directory at `from` to `to`, which must not exist.
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<(), Error> {
+        let from = normalize(from);
+        if from.is_empty() {
+            return Err(Error::InvalidPath);
+        }
+        if !self.nodes.contains_key(from) {
+            return Err(Error::NotFound);
+        }
+        let to = self.check_new(to)?.to_string();
+        if is_descendant(&to, from) {
+            return Err(Error::InvalidPath);
+        }
+        let mut moved = self.descendants(from).collect::<Vec<_>>();
+        moved.push(from.to_string());
+        for old in moved {
+            let node = self.nodes.remove(&old).unwrap();
+            let new = format!("{}{}", to, &old[from.len()..]);
+            self.nodes.insert(new, node);
+        }
+        Ok(())
+    }
+
+    /// Removes the regular file or empty directory at `path`.
+    pub async fn remove(&mut self, path: &str) -> Result<(), Error> {
+        let path = normalize(path);
+        if path.is_empty() {
+            return Err(Error::InvalidPath);
+        }
+        if !self.nodes.contains_key(path) {
+            return Err(Error::NotFound);
+        }
+        if self.descendants(path).next().is_some() {
+            return Err(Error::DirectoryNotEmpty);
+        }
+        self.nodes.remove(path);
+        Ok(())
+    }
+
+    /// Lists the names and types of the entries of the directory at `path`.
+    pub async fn read_dir(&self, path: &str) -> Result<Vec<(String, EntryType)>, Error> {
+        let path = normalize(path);
+        if self.metadata(path).await?.ty != EntryType::Directory {
+            return Err(Error::NotADirectory);
+        }
+        Ok(self
+            .descendants(path)
+            .filter_map(|child| {
+                let name = child[if path.is_empty() { 0 } else { path.len() + 1 }..].to_string();
+                if name.contains('/') {
+                    return None;
+                }
+                let ty = match self.nodes[&child] {
+                    Node::RegularFile(_) => EntryType::RegularFile,
+                    Node::Directory => EntryType::Directory,
+                };
+                Some((name, ty))
+            })
+            .collect())
+    }
+
+    fn file(&self, path: &str) -> Result<&Vec<u8>, Error> {
+        match self.nodes.get(normalize(path)) {
+            Some(Node::RegularFile(data)) => Ok(data),
+            Some(Node::Directory) => Err(Error::IsADirectory),
+            None if normalize(path).is_empty() => Err(Error::IsADirectory),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    fn file_mut(&mut self, path: &str) -> Result<&mut Vec<u8>, Error> {
+        let path = normalize(path);
+        match self.nodes.get_mut(path) {
+            Some(Node::RegularFile(data)) => Ok(data),
+            Some(Node::Directory) => Err(Error::IsADirectory),
+            None if path.is_empty() => Err(Error::IsADirectory),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    // Checks that `path` does not exist and that its parent is a directory.
+    fn check_new<'a>(&self, path: &'a str) -> Result<&'a str, Error> {
+        let path = normalize(path);
+        if path.is_empty() || !path.split('/').all(is_valid_component) {
+            return Err(Error::InvalidPath);
+        }
+        if self.nodes.contains_key(path) {
+            return Err(Error::AlreadyExists);
+        }
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            match self.nodes.get(parent) {
+                Some(Node::Directory) => {}
+                Some(Node::RegularFile(_)) => return Err(Error::NotADirectory),
+                None => return Err(Error::NotFound),
+            }
+        }
+        Ok(path)
+    }
+
+    // All paths below `path`, at any depth.
+    fn descendants<'a>(&'a self, path: &str) -> impl Iterator<Item = String> + 'a {
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path)
+        };
+        self.nodes
+            .range::<String, _>((Bound::Included(prefix.clone()), Bound::Unbounded))
+            .map(|(child, _)| child)
+            .take_while(move |child| child.starts_with(&prefix))
+            .cloned()
+    }
+}
+
+impl From<EntryType> for fs::EntryType {
+    fn from(ty: EntryType) -> Self {
+        match ty {
+            EntryType::RegularFile => Self::RegularFile,
+            EntryType::Directory => Self::Directory,
+        }
+    }
+}
+
+/// Files are identified by path, so writes through the inherent methods are visible to open files,
+/// and a file which is removed or renamed while open can no longer be read.
+impl FileSystem for TmpFs {
+    type Error = Error;
+
+    type File = String;
+
+    async fn open(&self, path: &str) -> Result<Self::File, Self::Error> {
+        self.file(path)?;
+        Ok(normalize(path).to_string())
+    }
+
+    async fn read(
+        &self,
+        file: &Self::File,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::Error> {
+        TmpFs::read(self, file, offset, buf).await
+    }
+
+    async fn stat(&self, path: &str) -> Result<fs::Metadata, Self::Error> {
+        let metadata = self.metadata(path).await?;
+        Ok(fs::Metadata {
+            ty: metadata.ty.into(),
+            size: metadata.size,
+            modified: None,
+        })
+    }
+
+    async fn read_dir(&self, path: &str) -> Result<Vec<fs::DirEntry>, Self::Error> {
+        Ok(TmpFs::read_dir(self, path)
+            .await?
+            .into_iter()
+            .map(|(name, ty)| fs::DirEntry {
+                name,
+                ty: ty.into(),
+            })
+            .collect())
+    }
+}
+
+// Fails, rather than aborting, if the heap cannot accommodate `len` bytes.
+fn resize(data: &mut Vec<u8>, len: usize) -> Result<(), Error> {
+    if let Some(additional) = len.checked_sub(data.len()) {
+        data.try_reserve_exact(additional)
+            .map_err(|_| Error::NoSpace)?;
+    }
+    data.resize(len, 0);
+    Ok(())
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+fn is_valid_component(component: &str) -> bool {
+    !matches!(component, "" | "." | "..")
+}
+
+fn is_descendant(path: &str, ancestor: &str) -> bool {
+    ancestor.is_empty()
+        || path
+            .strip_prefix(ancestor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    NotFound,
+    AlreadyExists,
+    NotADirectory,
+    IsADirectory,
+    DirectoryNotEmpty,
+    InvalidPath,
+    /// The size of the file would exceed `usize::MAX`.
+    FileTooLarge,
+    /// The heap cannot accommodate the file.
+    NoSpace,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::AlreadyExists => write!(f, "already exists"),
+            Self::NotADirectory => write!(f, "not a directory"),
+            Self::IsADirectory => write!(f, "is a directory"),
+            Self::DirectoryNotEmpty => write!(f, "directory not empty"),
+            Self::InvalidPath => write!(f, "invalid path"),
+            Self::FileTooLarge => write!(f, "file too large"),
+            Self::NoSpace => write!(f, "no space left"),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use sel4_async_test_utils::block_on;
+use sel4_async_tmpfs::{Error, TmpFs};
+
+fn read_all(fs: &TmpFs, path: &str) -> Vec<u8> {
+    block_on(async {
+        let mut buf = vec![0; fs.metadata(path).await.unwrap().size];
+        assert_eq!(fs.read(path, 0, &mut buf).await.unwrap(), buf.len());
+        buf
+    })
+}
+
+#[test]
+fn write_past_end_extends_with_zeros() {
+    let mut fs = TmpFs::new();
+    block_on(async {
+        fs.create_dir("/dir").await.unwrap();
+        fs.create_file("/dir/file").await.unwrap();
+        fs.write("/dir/file", 2, b"ab").await.unwrap();
+        fs.write("dir/file/", 1, b"x").await.unwrap();
+    });
+    assert_eq!(read_all(&fs, "dir/file"), b"\0xab");
+
+    block_on(fs.truncate("dir/file", 2)).unwrap();
+    assert_eq!(read_all(&fs, "dir/file"), b"\0x");
+}
+
+#[test]
+fn oversized_writes_fail_without_modifying_file() {
+    let mut fs = TmpFs::new();
+    block_on(async {
+        fs.create_file("file").await.unwrap();
+        fs.write("file", 0, b"abc").await.unwrap();
+    });
+
+    assert_eq!(
+        block_on(fs.write("file", usize::MAX, b"x")),
+        Err(Error::FileTooLarge)
+    );
+    // The allocation would exceed `isize::MAX` bytes, so this fails deterministically.
+    assert_eq!(
+        block_on(fs.write("file", usize::MAX - 1, b"x")),
+        Err(Error::NoSpace)
+    );
+    assert_eq!(
+        block_on(fs.truncate("file", usize::MAX)),
+        Err(Error::NoSpace)
+    );
+
+    assert_eq!(read_all(&fs, "file"), b"abc");
+}
+
+#[test]
+fn writes_to_directories_fail() {
+    let mut fs = TmpFs::new();
+    block_on(fs.create_dir("dir")).unwrap();
+    assert_eq!(block_on(fs.write("dir", 0, b"x")), Err(Error::IsADirectory));
+    assert_eq!(block_on(fs.write("/", 0, b"x")), Err(Error::IsADirectory));
+    assert_eq!(block_on(fs.write("missing", 0, b"x")), Err(Error::NotFound));
+}
+
+#[test]
+fn dot_components_are_invalid() {
+    let mut fs = TmpFs::new();
+    block_on(async {
+        fs.create_dir("dir").await.unwrap();
+        for path in [".", "..", "dir/.", "dir/..", "./file", "dir/../file"] {
+            assert_eq!(fs.create_file(path).await, Err(Error::InvalidPath));
+            assert_eq!(fs.create_dir(path).await, Err(Error::InvalidPath));
+        }
+        fs.create_file("dir/file").await.unwrap();
+        assert_eq!(
+            fs.rename("dir/file", "dir/..").await,
+            Err(Error::InvalidPath)
+        );
+    });
+    assert_eq!(block_on(fs.read_dir("")).unwrap().len(), 1);
+}
@@ -0,0 +1,101 @@
+// Copies a system's log output from a file or stdin to stdout, symbolizing each backtrace that it
+// contains using the ELF file of the component that emitted it.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use addr2line::Context;
+use clap::{App, Arg};
+use memmap::Mmap;
+
+use sel4_backtrace_types::Backtrace;
+
+fn main() {
+    let matches = App::new("")
+        .arg(
+            Arg::new("elf")
+                .short('e')
+                .long("elf")
+                .value_name("[IMAGE=]ELF")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .help("ELF file for an image, named by IMAGE or, by default, its file name"),
+        )
+        .arg(Arg::new("log").value_name("LOG"))
+        .get_matches();
+
+    let elfs = matches
+        .values_of("elf")
+        .into_iter()
+        .flatten()
+        .map(|arg| match arg.split_once('=') {
+            Some((image, path)) => (image.to_owned(), path.to_owned()),
+            None => (file_name(arg).to_owned(), arg.to_owned()),
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let log: Box<dyn BufRead> = match matches.value_of("log") {
+        Some(path) => Box::new(BufReader::new(File::open(path).unwrap())),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    for line in log.lines() {
+        let line = line.unwrap();
+        println!("{}", line);
+        if let Some(bt) = parse_backtrace(line.trim()) {
+            match find_elf(&elfs, bt.preamble.image.as_deref()) {
+                Some(elf_file_path) => {
+                    println!("backtrace: {}", elf_file_path);
+                    print!("{}", symbolize(&bt, elf_file_path));
+                }
+                None => {
+                    println!(
+                        "backtrace: no ELF file provided for image {:?}",
+                        bt.preamble.image
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn parse_backtrace(s: &str) -> Option<Backtrace<Option<String>>> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let bytes = hex::decode(s).ok()?;
+    Backtrace::recv_taking_all(&bytes).ok()
+}
+
+// Images are matched by name, and then by file name. A sole ELF file is used for any image.
+fn find_elf<'a>(elfs: &'a BTreeMap<String, String>, image: Option<&str>) -> Option<&'a str> {
+    image
+        .and_then(|image| elfs.get(image).or_else(|| elfs.get(file_name(image))))
+        .or_else(|| {
+            if elfs.len() == 1 {
+                elfs.values().next()
+            } else {
+                None
+            }
+        })
+        .map(String::as_str)
+}
+
+fn symbolize(bt: &Backtrace<Option<String>>, elf_file_path: &str) -> String {
+    let elf_file = File::open(elf_file_path).unwrap();
+    let map = unsafe { Mmap::map(&elf_file).unwrap() };
+    let elf_obj = &object::File::parse(&*map).unwrap();
+    let ctx = Context::new(elf_obj).unwrap();
+    let mut s = String::new();
+    bt.symbolize(&ctx, &mut s).unwrap();
+    s
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
@@ -14,9 +14,9 @@ pub use addr2line::object::File;
 
 use crate::Backtrace;
 
-// TODO handle inlining better (see TODOs scattered throughout this file)
-
 impl<T> Backtrace<T> {
+    /// Writes one line per frame, including frames for inlined calls, each followed by its
+    /// location.
     pub fn symbolize<R: Reader>(
         &self,
         ctx: &Context<R>,
@@ -28,10 +28,10 @@ impl<T> Backtrace<T> {
         for (i, entry) in self.entries.iter().enumerate() {
             let mut first = true;
             let frame = &entry.stack_frame;
-            // TODO
-            // let mut seen = false;
-            // let initial_location = ctx.find_location(frame.ip as u64)?;
-            ctx.find_frames(frame.ip as u64)
+            // Entries are return addresses, which point just past the call instruction, and so
+            // may belong to the next line or even to a different inlined frame.
+            let probe = (frame.ip as u64).saturating_sub(1);
+            ctx.find_frames(probe)
                 .skip_all_loads()?
                 .for_each(|inner_frame| {
                     if first {
@@ -39,52 +39,35 @@ impl<T> Backtrace<T> {
                     } else {
                         write!(w, " {:4}   {:18  }   ", "", "").unwrap();
                     }
-                    // TODO
-                    // if inner_frame.location == frame {
-                    //     seen = true;
-                    // }
                     match inner_frame.function {
                         Some(f) => {
-                            // TODO
-                            // let raw_name = f.raw_name()?;
-                            // let demangled = demangle(&raw_name);
                             let demangled = f.demangle()?;
                             write!(w, "{}", demangled).unwrap()
                         }
                         None => write!(w, "<unknown>").unwrap(),
                     }
                     write!(w, "\n").unwrap();
-                    // TODO
-                    // if let Some(loc) = inner_frame.location {
-                    //     writeln!(w, "      {:18}       at {}", "", fmt_location(loc)).unwrap();
-                    // }
+                    if let Some(loc) = inner_frame.location {
+                        writeln!(w, "      {:18}       at {}", "", fmt_location(loc)).unwrap();
+                    }
                     first = false;
                     Ok(())
                 })?;
-            // TODO this isn't accurate
-            if let Some(loc) = ctx.find_location(frame.ip as u64)? {
-                writeln!(w, "      {:18}       at {}", "", fmt_location(loc)).unwrap();
+            if first {
+                writeln!(w, " {:4}:  {:#18x} - <unknown>", i, frame.ip).unwrap();
             }
-            // TODO
-            // if !seen {
-            //     write!(w, "      ").unwrap();
-            //     write!(w, "warning: initial location missing: {}", initial_location).unwrap();
-            //     write!(w, "\n").unwrap();
-            // }
         }
         Ok(())
     }
 }
 
 fn fmt_location(loc: addr2line::Location) -> String {
-    format!(
-        "{} {},{}",
-        loc.file.unwrap_or("<unknown>"),
-        loc.line
-            .map(|x| x.to_string())
-            .unwrap_or(String::from("<unknown>")),
-        loc.column
-            .map(|x| x.to_string())
-            .unwrap_or(String::from("<unknown>")),
-    )
+    let mut s = loc.file.unwrap_or("<unknown>").to_string();
+    if let Some(line) = loc.line {
+        s.push_str(&format!(":{line}"));
+        if let Some(column) = loc.column {
+            s.push_str(&format!(":{column}"));
+        }
+    }
+    s
 }
@@ -11,6 +11,7 @@ pub struct Args {
     pub out_file_path: String,
     pub object_names_level: ObjectNamesLevel,
     pub embed_frames: bool,
+    pub placement_plan_path: Option<String>,
     pub verbose: bool,
 }
 
@@ -54,6 +55,12 @@ impl Args {
                     .value_name("EMBED_FRAMES")
                     .action(ArgAction::SetTrue),
             )
+            .arg(
+                Arg::new("placement_plan")
+                    .long("placement-plan")
+                    .short('p')
+                    .value_name("PLACEMENT_PLAN_FILE"),
+            )
             .arg(Arg::new("verbose").short('v').action(ArgAction::SetTrue))
             .get_matches();
 
@@ -77,6 +84,8 @@ impl Args {
 
         let embed_frames = *matches.get_one::<bool>("embed_frames").unwrap();
 
+        let placement_plan_path = matches.get_one::<String>("placement_plan").cloned();
+
         let verbose = *matches.get_one::<bool>("verbose").unwrap();
 
         Ok(Self {
@@ -86,6 +95,7 @@ impl Args {
             out_file_path,
             object_names_level,
             embed_frames,
+            placement_plan_path,
             verbose,
         })
     }
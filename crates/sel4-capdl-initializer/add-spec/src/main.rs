@@ -7,7 +7,7 @@ use std::fs;
 
 use anyhow::Result;
 
-use sel4_capdl_initializer_types::{Footprint, InputSpec};
+use sel4_capdl_initializer_types::{Footprint, InputSpec, Placement};
 use sel4_render_elf_with_data::{ConcreteFileHeader32, ConcreteFileHeader64, ElfBitWidth};
 
 mod args;
@@ -35,11 +35,18 @@ fn main() -> Result<()> {
 
     let input_spec = InputSpec::parse(&spec_json);
 
+    let mut placement_plan = match &args.placement_plan_path {
+        Some(path) => serde_json::from_str::<Vec<Placement>>(&fs::read_to_string(path)?)?,
+        None => vec![],
+    };
+    placement_plan.sort_by_key(|placement| placement.paddr);
+
     let (final_spec, serialized_spec) = reserialize_spec::reserialize_spec(
         &input_spec,
         fill_dir_path,
         object_names_level,
         embed_frames,
+        &placement_plan,
         GRANULE_SIZE_BITS,
         args.verbose,
     );
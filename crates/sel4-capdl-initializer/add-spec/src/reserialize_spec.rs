@@ -8,6 +8,7 @@ pub fn reserialize_spec<'a>(
     fill_dir_path: impl AsRef<Path>,
     object_names_level: &ObjectNamesLevel,
     embed_frames: bool,
+    placement_plan: &[Placement],
     granule_size_bits: usize,
     verbose: bool,
 ) -> (SpecWithIndirection<'a>, Vec<u8>) {
@@ -47,6 +48,7 @@ pub fn reserialize_spec<'a>(
     }
 
     let mut blob = postcard::to_allocvec(&final_spec).unwrap();
+    blob.extend(postcard::to_allocvec(placement_plan).unwrap());
     blob.extend(sources.build());
     (final_spec, blob)
 }
@@ -3,7 +3,8 @@ use sel4::{sel4_cfg, VMAttributes};
 #[sel4_cfg(any(ARCH_AARCH32, ARCH_AARCH64))]
 mod imp {
     pub(crate) type FrameType1 = sel4::cap_type::LargePage;
-    pub(crate) type FrameType2 = sel4::cap_type::HugePage;
+    pub(crate) const FRAME_SIZE_2_BITS: usize =
+        <sel4::cap_type::HugePage as sel4::FrameType>::FRAME_SIZE.bits();
 
     pub(crate) fn init_user_context(
         regs: &mut sel4::UserContext,
@@ -21,7 +22,8 @@ mod imp {
 #[sel4_cfg(any(ARCH_IA32, ARCH_X86_64))]
 mod imp {
     pub(crate) type FrameType1 = sel4::cap_type::LargePage;
-    pub(crate) type FrameType2 = sel4::cap_type::HugePage;
+    pub(crate) const FRAME_SIZE_2_BITS: usize =
+        <sel4::cap_type::HugePage as sel4::FrameType>::FRAME_SIZE.bits();
 
     pub(crate) fn init_user_context(
         regs: &mut sel4::UserContext,
@@ -38,7 +40,17 @@ mod imp {
 #[sel4_cfg(any(ARCH_RISCV32, ARCH_RISCV64))]
 mod imp {
     pub(crate) type FrameType1 = sel4::cap_type::MegaPage;
-    pub(crate) type FrameType2 = sel4::cap_type::GigaPage;
+
+    // On Sv32, megapages are mapped directly into the root page table, which spans the entire
+    // address space.
+    sel4::sel4_cfg_if! {
+        if #[cfg(PT_LEVELS = "2")] {
+            pub(crate) const FRAME_SIZE_2_BITS: usize = sel4::WORD_SIZE;
+        } else {
+            pub(crate) const FRAME_SIZE_2_BITS: usize =
+                <sel4::cap_type::GigaPage as sel4::FrameType>::FRAME_SIZE.bits();
+        }
+    }
 
     pub(crate) fn init_user_context(
         regs: &mut sel4::UserContext,
@@ -57,7 +69,7 @@ pub(crate) use imp::*;
 pub(crate) mod frame_types {
     use sel4::FrameType;
 
-    pub(crate) use super::{FrameType1, FrameType2};
+    pub(crate) use super::{FrameType1, FRAME_SIZE_2_BITS};
 
     pub(crate) type FrameType0 = sel4::cap_type::Granule;
 
@@ -69,7 +81,7 @@ sel4::sel4_cfg_if! {
     if #[cfg(ARCH_AARCH64)] {
         const CACHED: VMAttributes = VMAttributes::PAGE_CACHEABLE;
         const UNCACHED: VMAttributes = VMAttributes::DEFAULT;
-    } else if #[cfg(ARCH_RISCV64)] {
+    } else if #[cfg(any(ARCH_RISCV64, ARCH_RISCV32))] {
         const CACHED: VMAttributes = VMAttributes::DEFAULT;
         const UNCACHED: VMAttributes = VMAttributes::NONE;
     } else if #[cfg(ARCH_X86_64)] {
@@ -85,3 +97,27 @@ pub(crate) fn vm_attributes_from_whether_cached(cached: bool) -> VMAttributes {
         UNCACHED
     }
 }
+
+// Only counters which the kernel configuration makes accessible at user level are read, as
+// reading any other would fault.
+pub(crate) fn read_cycle_counter() -> Option<u64> {
+    sel4::sel4_cfg_if! {
+        if #[cfg(all(ARCH_AARCH64, EXPORT_VCNT_USER))] {
+            let value: u64;
+            unsafe {
+                core::arch::asm!("isb", "mrs {}, cntvct_el0", out(reg) value, options(nomem, nostack));
+            }
+            Some(value)
+        } else if #[cfg(all(ARCH_AARCH64, EXPORT_PCNT_USER))] {
+            let value: u64;
+            unsafe {
+                core::arch::asm!("isb", "mrs {}, cntpct_el0", out(reg) value, options(nomem, nostack));
+            }
+            Some(value)
+        } else if #[cfg(ARCH_X86_64)] {
+            Some(unsafe { core::arch::x86_64::_rdtsc() })
+        } else {
+            None
+        }
+    }
+}
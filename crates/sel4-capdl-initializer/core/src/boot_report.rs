@@ -0,0 +1,221 @@
+use core::fmt;
+
+use sel4_capdl_initializer_types::Object;
+
+/// Name of the frame object, if any, into which the [`BootReport`] is written just before the
+/// initializer starts threads. Components which map this frame can read the report from offset
+/// zero.
+pub const BOOT_REPORT_FRAME_NAME: &str = "sel4_capdl_initializer_boot_report";
+
+/// Log target with which the [`BootReport`] is logged as a single line of JSON at the info level.
+pub const BOOT_REPORT_LOG_TARGET: &str = "sel4_capdl_initializer_core::boot_report";
+
+pub const BOOT_REPORT_MAGIC: u32 = u32::from_le_bytes(*b"CDBR");
+
+pub const BOOT_REPORT_VERSION: u32 = 1;
+
+/// Value of an entry of [`BootReport::phase_cycles`] for a phase which was not run, or which could
+/// not be timed because no cycle counter is accessible to the initializer.
+pub const CYCLES_UNAVAILABLE: u64 = u64::MAX;
+
+/// Summary of the initializer's work, with a stable layout for consumption by components and
+/// tooling.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BootReport {
+    pub magic: u32,
+    pub version: u32,
+    /// Indexed by [`ObjectKind::code`].
+    pub objects_created: [u64; ObjectKind::NUM],
+    pub bytes_filled: u64,
+    /// Bytes of non-device untyped memory handed to the initializer by the kernel.
+    pub untyped_bytes_total: u64,
+    pub untyped_bytes_consumed: u64,
+    pub untyped_bytes_remaining: u64,
+    /// Indexed by [`Phase::code`].
+    pub phase_cycles: [u64; Phase::NUM],
+}
+
+impl BootReport {
+    pub const fn new() -> Self {
+        Self {
+            magic: BOOT_REPORT_MAGIC,
+            version: BOOT_REPORT_VERSION,
+            objects_created: [0; ObjectKind::NUM],
+            bytes_filled: 0,
+            untyped_bytes_total: 0,
+            untyped_bytes_consumed: 0,
+            untyped_bytes_remaining: 0,
+            phase_cycles: [CYCLES_UNAVAILABLE; Phase::NUM],
+        }
+    }
+
+    pub fn objects_created(&self, kind: ObjectKind) -> u64 {
+        self.objects_created[kind.code()]
+    }
+
+    pub fn phase_cycles(&self, phase: Phase) -> Option<u64> {
+        Some(self.phase_cycles[phase.code()]).filter(|cycles| *cycles != CYCLES_UNAVAILABLE)
+    }
+
+    pub(crate) fn record_object_created<D, M>(&mut self, obj: &Object<D, M>) {
+        self.objects_created[ObjectKind::of(obj).code()] += 1;
+    }
+
+    pub(crate) fn record_untyped(&mut self, size_bytes: usize) {
+        self.untyped_bytes_total += u64::try_from(size_bytes).unwrap();
+        self.untyped_bytes_remaining = self.untyped_bytes_total - self.untyped_bytes_consumed;
+    }
+
+    pub(crate) fn record_untyped_consumed(&mut self, size_bytes: usize) {
+        self.untyped_bytes_consumed += u64::try_from(size_bytes).unwrap();
+        self.untyped_bytes_remaining = self.untyped_bytes_total - self.untyped_bytes_consumed;
+    }
+
+    pub(crate) fn record_bytes_filled(&mut self, n: usize) {
+        self.bytes_filled += u64::try_from(n).unwrap();
+    }
+
+    pub(crate) fn record_phase(&mut self, phase: Phase, cycles: Option<u64>) {
+        self.phase_cycles[phase.code()] = cycles.unwrap_or(CYCLES_UNAVAILABLE);
+    }
+
+    pub fn json(&self) -> BootReportJson<'_> {
+        BootReportJson(self)
+    }
+}
+
+impl Default for BootReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returned by [`BootReport::json`].
+pub struct BootReportJson<'a>(&'a BootReport);
+
+impl fmt::Display for BootReportJson<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let report = self.0;
+        write!(f, "{{\"version\":{}", report.version)?;
+        write!(f, ",\"objects_created\":{{")?;
+        for (i, kind) in ObjectKind::ALL.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "\"{}\":{}", kind.name(), report.objects_created(*kind))?;
+        }
+        write!(f, "}},\"bytes_filled\":{}", report.bytes_filled)?;
+        write!(
+            f,
+            ",\"untyped_bytes\":{{\"total\":{},\"consumed\":{},\"remaining\":{}}}",
+            report.untyped_bytes_total,
+            report.untyped_bytes_consumed,
+            report.untyped_bytes_remaining,
+        )?;
+        write!(f, ",\"phase_cycles\":{{")?;
+        for (i, phase) in Phase::ALL.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match report.phase_cycles(*phase) {
+                Some(cycles) => write!(f, "\"{}\":{}", phase.name(), cycles)?,
+                None => write!(f, "\"{}\":null", phase.name())?,
+            }
+        }
+        write!(f, "}}}}")
+    }
+}
+
+macro_rules! codes {
+    (
+        $(#[$attr:meta])*
+        pub enum $ty:ident {
+            $($variant:ident = $code:literal => $name:literal,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(u32)]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum $ty {
+            $($variant = $code,)*
+        }
+
+        impl $ty {
+            pub const ALL: [Self; Self::NUM] = [$(Self::$variant,)*];
+
+            pub const NUM: usize = [$($code,)*].len();
+
+            pub const fn code(self) -> usize {
+                self as usize
+            }
+
+            pub const fn from_code(code: usize) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+codes! {
+    /// Kinds of objects counted in [`BootReport::objects_created`].
+    pub enum ObjectKind {
+        Untyped = 0 => "untyped",
+        Endpoint = 1 => "endpoint",
+        Notification = 2 => "notification",
+        CNode = 3 => "cnode",
+        TCB = 4 => "tcb",
+        IRQ = 5 => "irq",
+        VCPU = 6 => "vcpu",
+        Frame = 7 => "frame",
+        PageTable = 8 => "page_table",
+        ASIDPool = 9 => "asid_pool",
+        ArmIRQ = 10 => "arm_irq",
+        SchedContext = 11 => "sched_context",
+        Reply = 12 => "reply",
+    }
+}
+
+impl ObjectKind {
+    pub fn of<D, M>(obj: &Object<D, M>) -> Self {
+        match obj {
+            Object::Untyped(_) => Self::Untyped,
+            Object::Endpoint => Self::Endpoint,
+            Object::Notification => Self::Notification,
+            Object::CNode(_) => Self::CNode,
+            Object::TCB(_) => Self::TCB,
+            Object::IRQ(_) => Self::IRQ,
+            Object::VCPU => Self::VCPU,
+            Object::Frame(_) => Self::Frame,
+            Object::PageTable(_) => Self::PageTable,
+            Object::ASIDPool(_) => Self::ASIDPool,
+            Object::ArmIRQ(_) => Self::ArmIRQ,
+            Object::SchedContext(_) => Self::SchedContext,
+            Object::Reply => Self::Reply,
+        }
+    }
+}
+
+codes! {
+    /// Phases of the initializer, in the order in which they are run, timed in
+    /// [`BootReport::phase_cycles`].
+    pub enum Phase {
+        CreateObjects = 0 => "create_objects",
+        InitIRQs = 1 => "init_irqs",
+        InitASIDs = 2 => "init_asids",
+        InitFrames = 3 => "init_frames",
+        InitVSpaces = 4 => "init_vspaces",
+        InitSchedContexts = 5 => "init_sched_contexts",
+        InitTCBs = 6 => "init_tcbs",
+        InitCSpaces = 7 => "init_cspaces",
+    }
+}
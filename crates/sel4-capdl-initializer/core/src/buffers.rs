@@ -9,12 +9,16 @@ pub struct InitializerBuffers<T> {
 #[derive(Copy, Clone)]
 pub struct PerObjectBuffer {
     pub(crate) orig_slot: Option<InitCSpaceSlot>,
+    pub(crate) planned: bool,
 }
 
 #[allow(clippy::derivable_impls)] // until #![feature(derive_const)]
 impl PerObjectBuffer {
     pub const fn const_default() -> Self {
-        Self { orig_slot: None }
+        Self {
+            orig_slot: None,
+            planned: false,
+        }
     }
 }
 
@@ -11,6 +11,7 @@ pub enum CapDLInitializerError {
     TryFromObjectError(TryFromObjectError),
     TryFromCapError(TryFromCapError),
     TryFromIntError(TryFromIntError),
+    InvalidPlacement(Placement),
 }
 
 impl From<CSlotAllocatorError> for CapDLInitializerError {
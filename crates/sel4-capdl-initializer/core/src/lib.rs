@@ -27,18 +27,26 @@ use sel4::{
 use sel4_capdl_initializer_types::*;
 
 mod arch;
+mod boot_report;
 mod buffers;
 mod cslot_allocator;
 mod error;
 mod hold_slots;
 mod memory;
+mod placement;
 
 use arch::frame_types;
+pub use boot_report::{
+    BootReport, BootReportJson, ObjectKind, Phase, BOOT_REPORT_FRAME_NAME, BOOT_REPORT_LOG_TARGET,
+    BOOT_REPORT_MAGIC, BOOT_REPORT_VERSION, CYCLES_UNAVAILABLE,
+};
 pub use buffers::{InitializerBuffers, PerObjectBuffer};
 use cslot_allocator::{CSlotAllocator, CSlotAllocatorError};
 pub use error::CapDLInitializerError;
 use hold_slots::HoldSlots;
 use memory::{get_user_image_frame_slot, init_copy_addrs};
+pub use placement::PLACEMENT_LOG_TARGET;
+use placement::{Pinned, PlacementJson};
 
 type Result<T> = result::Result<T, CapDLInitializerError>;
 
@@ -48,17 +56,23 @@ pub struct Initializer<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B> {
     small_frame_copy_addr: usize,
     large_frame_copy_addr: usize,
     spec_with_sources: &'a SpecWithSources<'a, N, D, M>,
+    placement_plan: &'a [Placement],
     cslot_allocator: &'a mut CSlotAllocator,
     buffers: &'a mut InitializerBuffers<B>,
+    boot_report: BootReport,
 }
 
 impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObjectBuffer]>>
     Initializer<'a, N, D, M, B>
 {
+    /// Creates the objects, other than those which specify paddrs, at the physical addresses given
+    /// by `placement_plan`, if they are listed there. See [`Placement`] and
+    /// [`PLACEMENT_LOG_TARGET`].
     pub fn initialize(
         bootinfo: &BootInfo,
         user_image_bounds: Range<usize>,
         spec_with_sources: &SpecWithSources<N, D, M>,
+        placement_plan: &[Placement],
         buffers: &mut InitializerBuffers<B>,
     ) -> Result<!> {
         info!("Starting CapDL initializer");
@@ -74,8 +88,10 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
             small_frame_copy_addr,
             large_frame_copy_addr,
             spec_with_sources,
+            placement_plan,
             cslot_allocator: &mut cslot_allocator,
             buffers,
+            boot_report: BootReport::new(),
         }
         .run()
     }
@@ -91,18 +107,20 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
     // // //
 
     fn run(&mut self) -> Result<!> {
-        self.create_objects()?;
+        self.run_phase(Phase::CreateObjects, Self::create_objects)?;
 
-        self.init_irqs()?;
-        self.init_asids()?;
-        self.init_frames()?;
-        self.init_vspaces()?;
+        self.run_phase(Phase::InitIRQs, Self::init_irqs)?;
+        self.run_phase(Phase::InitASIDs, Self::init_asids)?;
+        self.run_phase(Phase::InitFrames, Self::init_frames)?;
+        self.run_phase(Phase::InitVSpaces, Self::init_vspaces)?;
 
         #[sel4::sel4_cfg(KERNEL_MCS)]
-        self.init_sched_contexts()?;
+        self.run_phase(Phase::InitSchedContexts, Self::init_sched_contexts)?;
+
+        self.run_phase(Phase::InitTCBs, Self::init_tcbs)?;
+        self.run_phase(Phase::InitCSpaces, Self::init_cspaces)?;
 
-        self.init_tcbs()?;
-        self.init_cspaces()?;
+        self.emit_boot_report()?;
 
         self.start_threads()?;
 
@@ -113,6 +131,17 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
         unreachable!()
     }
 
+    fn run_phase(&mut self, phase: Phase, f: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let start = arch::read_cycle_counter();
+        f(self)?;
+        let end = arch::read_cycle_counter();
+        self.boot_report.record_phase(
+            phase,
+            start.zip(end).map(|(start, end)| end.wrapping_sub(start)),
+        );
+        Ok(())
+    }
+
     fn create_objects(&mut self) -> Result<()> {
         // This algorithm differs from that found in the upstream C CapDL
         // loader. In particular, this one is implemented with objects
@@ -138,6 +167,8 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
             .partition_point(|named_obj| named_obj.object.paddr().is_some());
         let num_objs_with_paddr = first_obj_without_paddr;
 
+        self.check_placement_plan(first_obj_without_paddr)?;
+
         let mut by_size_start: [usize; sel4::WORD_SIZE] = array::from_fn(|_| 0);
         let mut by_size_end: [usize; sel4::WORD_SIZE] = array::from_fn(|_| 0);
         {
@@ -164,7 +195,7 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
 
         // Create root objects
 
-        let mut next_obj_with_paddr = 0;
+        let mut pinned = Pinned::new(num_objs_with_paddr, self.placement_plan);
         for i_ut in uts_by_paddr.iter() {
             let ut = &uts[*i_ut];
             let ut_size_bits = ut.size_bits();
@@ -172,6 +203,9 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
             let ut_paddr_start = ut.paddr();
             let ut_paddr_end = ut_paddr_start + ut_size_bytes;
             let mut cur_paddr = ut_paddr_start;
+            if !ut.is_device() {
+                self.boot_report.record_untyped(ut_size_bytes);
+            }
             trace!(
                 "Allocating from untyped: {:#x}..{:#x} (size_bits = {}, device = {:?})",
                 ut_paddr_start,
@@ -180,22 +214,32 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                 ut.is_device()
             );
             loop {
-                let target = if next_obj_with_paddr < num_objs_with_paddr {
-                    ut_paddr_end.min(self.spec().object(next_obj_with_paddr).paddr().unwrap())
-                } else {
-                    ut_paddr_end
+                let next_pinned = pinned.peek(|obj_id| self.spec().object(obj_id).paddr().unwrap());
+                let target = match &next_pinned {
+                    Some(placement) => ut_paddr_end.min(sel4::PAddr::new(placement.paddr)),
+                    None => ut_paddr_end,
                 };
                 let target_is_obj_with_paddr = target < ut_paddr_end;
+                if target_is_obj_with_paddr && target < cur_paddr {
+                    // Lies outside of any untyped, or overlaps an object already created
+                    return Err(CapDLInitializerError::InvalidPlacement(
+                        next_pinned.unwrap(),
+                    ));
+                }
                 while cur_paddr < target {
-                    let max_size_bits = usize::try_from(cur_paddr.trailing_zeros())
+                    let max_size_bits = usize::try_from(cur_paddr.addr().trailing_zeros())
                         .unwrap()
                         .min((target - cur_paddr).trailing_zeros().try_into().unwrap());
                     let mut created = false;
                     if !ut.is_device() {
                         for size_bits in (0..=max_size_bits).rev() {
                             let obj_id = &mut by_size_start[size_bits];
-                            // Skip embedded frames
+                            // Skip embedded frames and objects placed by the plan
                             while *obj_id < by_size_end[size_bits] {
+                                if self.buffers.per_obj()[*obj_id].planned {
+                                    *obj_id += 1;
+                                    continue;
+                                }
                                 if let Object::Frame(obj) = self.spec().object(*obj_id) {
                                     if let FrameInit::Embedded(embedded) = &obj.init {
                                         self.take_cap_for_embedded_frame(
@@ -227,6 +271,13 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                                     self.alloc_orig_cslot(*obj_id),
                                     1,
                                 )?;
+                                self.boot_report.record_object_created(&named_obj.object);
+                                self.boot_report.record_untyped_consumed(1 << size_bits);
+                                info!(
+                                    target: PLACEMENT_LOG_TARGET,
+                                    "{}",
+                                    PlacementJson(*obj_id, cur_paddr)
+                                );
                                 cur_paddr += 1 << size_bits;
                                 *obj_id += 1;
                                 created = true;
@@ -251,6 +302,9 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                                 1,
                             )?;
                             hold_slots.report_used();
+                            if !ut.is_device() {
+                                self.boot_report.record_untyped_consumed(1 << max_size_bits);
+                            }
                             cur_paddr += 1 << max_size_bits;
                         } else {
                             cur_paddr = target;
@@ -258,7 +312,8 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                     }
                 }
                 if target_is_obj_with_paddr {
-                    let obj_id = next_obj_with_paddr;
+                    let placement = next_pinned.unwrap();
+                    let obj_id = placement.object;
                     let named_obj = &self.spec().named_object(obj_id);
                     let blueprint = named_obj.object.blueprint().unwrap();
                     trace!(
@@ -273,14 +328,30 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                         self.alloc_orig_cslot(obj_id),
                         1,
                     )?;
+                    self.boot_report.record_object_created(&named_obj.object);
+                    if !ut.is_device() {
+                        self.boot_report
+                            .record_untyped_consumed(1 << blueprint.physical_size_bits());
+                    }
+                    if obj_id >= num_objs_with_paddr {
+                        info!(
+                            target: PLACEMENT_LOG_TARGET,
+                            "{}",
+                            PlacementJson(obj_id, cur_paddr)
+                        );
+                    }
                     cur_paddr += 1 << blueprint.physical_size_bits();
-                    next_obj_with_paddr += 1;
+                    pinned.advance(&placement);
                 } else {
                     break;
                 }
             }
         }
 
+        if let Some(placement) = pinned.remaining_placement() {
+            return Err(CapDLInitializerError::InvalidPlacement(*placement));
+        }
+
         // Ensure that we've created every root object
         for bits in 0..sel4::WORD_SIZE {
             assert_eq!(by_size_start[bits], by_size_end[bits], "!!! {}", bits);
@@ -305,6 +376,7 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                     self.alloc_orig_cslot(child_obj_id),
                     1,
                 )?;
+                self.boot_report.record_object_created(&child.object);
             }
         }
 
@@ -354,12 +426,38 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
                     }
                 }
                 self.set_orig_cslot(*handler, slot);
+                self.boot_report
+                    .record_object_created(self.spec().object(*handler));
             }
         }
 
         Ok(())
     }
 
+    // Checks that each entry of the plan is for a distinct root object which can be placed, and
+    // that entries are sorted by paddr, aligned, and do not overlap. Marks the objects as planned.
+    fn check_placement_plan(&mut self, first_obj_without_paddr: usize) -> Result<()> {
+        let mut end_of_prev = 0;
+        for placement in self.placement_plan.iter() {
+            let obj_id = placement.object;
+            let size_bits = (first_obj_without_paddr..self.spec().root_objects().len())
+                .contains(&obj_id)
+                .then(|| self.spec().object(obj_id))
+                .filter(|obj| !matches!(obj, Object::Frame(frame) if frame.init.is_embedded()))
+                .and_then(|obj| obj.blueprint())
+                .map(|blueprint| blueprint.physical_size_bits())
+                .filter(|size_bits| {
+                    placement.paddr >= end_of_prev
+                        && placement.paddr % (1 << size_bits) == 0
+                        && !self.buffers.per_obj()[obj_id].planned
+                })
+                .ok_or(CapDLInitializerError::InvalidPlacement(*placement))?;
+            end_of_prev = placement.paddr + (1 << size_bits);
+            self.buffers.per_obj_mut()[obj_id].planned = true;
+        }
+        Ok(())
+    }
+
     fn take_cap_for_embedded_frame(
         &mut self,
         obj_id: ObjectId,
@@ -441,7 +539,11 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
         Ok(())
     }
 
-    fn fill_frame<U: FrameType>(&self, frame: LocalCPtr<U>, fill: &[FillEntry<D>]) -> Result<()> {
+    fn fill_frame<U: FrameType>(
+        &mut self,
+        frame: LocalCPtr<U>,
+        fill: &[FillEntry<D>],
+    ) -> Result<()> {
         frame.frame_map(
             BootInfo::init_thread_vspace(),
             self.copy_addr::<U>(),
@@ -455,6 +557,7 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
             assert!(entry.range.end <= U::FRAME_SIZE.bytes());
             let dst_frame = ptr::from_exposed_addr_mut::<u8>(self.copy_addr::<U>());
             let dst = unsafe { slice::from_raw_parts_mut(dst_frame.add(offset), length) };
+            self.boot_report.record_bytes_filled(length);
             match &entry.content {
                 FillEntryContent::Data(content_data) => {
                     content_data.copy_out(self.spec_with_sources.content_source, dst);
@@ -484,6 +587,49 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
         Ok(())
     }
 
+    fn emit_boot_report(&mut self) -> Result<()> {
+        info!(target: BOOT_REPORT_LOG_TARGET, "{}", self.boot_report.json());
+        let frame = self
+            .spec()
+            .filter_objects::<&object::Frame<'a, D, M>>()
+            .find(|(obj_id, _)| {
+                self.object_name(self.spec().name(*obj_id)) == Some(BOOT_REPORT_FRAME_NAME)
+            });
+        if let Some((obj_id, obj)) = frame {
+            debug!("Writing boot report");
+            match obj.size_bits {
+                frame_types::FRAME_SIZE_0_BITS => {
+                    let frame = self.orig_local_cptr::<frame_types::FrameType0>(obj_id);
+                    self.write_boot_report(frame)?;
+                }
+                frame_types::FRAME_SIZE_1_BITS => {
+                    let frame = self.orig_local_cptr::<frame_types::FrameType1>(obj_id);
+                    self.write_boot_report(frame)?;
+                }
+                _ => {
+                    panic!()
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_boot_report<U: FrameType>(&self, frame: LocalCPtr<U>) -> Result<()> {
+        frame.frame_map(
+            BootInfo::init_thread_vspace(),
+            self.copy_addr::<U>(),
+            CapRights::read_write(),
+            arch::vm_attributes_from_whether_cached(false),
+        )?;
+        atomic::fence(Ordering::SeqCst); // lazy
+        unsafe {
+            ptr::from_exposed_addr_mut::<BootReport>(self.copy_addr::<U>()).write(self.boot_report);
+        }
+        atomic::fence(Ordering::SeqCst); // lazy
+        frame.frame_unmap()?;
+        Ok(())
+    }
+
     fn init_vspaces(&mut self) -> Result<()> {
         debug!("Initializing VSpaces");
         self.init_vspaces_arch()
@@ -614,6 +760,50 @@ impl<'a, N: ObjectName, D: Content, M: GetEmbeddedFrame, B: BorrowMut<[PerObject
         Ok(())
     }
 
+    #[sel4::sel4_cfg(ARCH_RISCV32)]
+    fn init_vspaces_arch(&mut self) -> Result<()> {
+        #[sel4::sel4_cfg(not(PT_LEVELS = "2"))]
+        compile_error!("unsupported configuration");
+
+        for (obj_id, obj) in self
+            .spec()
+            .filter_objects_with::<&object::PageTable>(|obj| obj.is_root)
+        {
+            let vspace = self.orig_local_cptr::<cap_type::PageTable>(obj_id);
+            for (i, cap) in obj.entries() {
+                let vaddr = i << 22;
+                match cap {
+                    PageTableEntry::Frame(cap) => {
+                        let frame = self.orig_local_cptr::<cap_type::MegaPage>(cap.object);
+                        let rights = (&cap.rights).into();
+                        self.copy(frame)?
+                            .frame_map(vspace, vaddr, rights, cap.vm_attributes())?;
+                    }
+                    PageTableEntry::PageTable(cap) => {
+                        let pt = self.orig_local_cptr::<cap_type::PageTable>(cap.object);
+                        pt.page_table_map(vspace, vaddr, cap.vm_attributes())?;
+                        for (i, cap) in self
+                            .spec()
+                            .lookup_object::<&object::PageTable>(cap.object)?
+                            .frames()
+                        {
+                            let frame = self.orig_local_cptr::<cap_type::_4KPage>(cap.object);
+                            let vaddr = vaddr + (i << FrameSize::_4K.bits());
+                            let rights = (&cap.rights).into();
+                            self.copy(frame)?.frame_map(
+                                vspace,
+                                vaddr,
+                                rights,
+                                cap.vm_attributes(),
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     #[sel4::sel4_cfg(ARCH_X86_64)]
     fn init_vspaces_arch(&mut self) -> Result<()> {
         for (obj_id, obj) in self
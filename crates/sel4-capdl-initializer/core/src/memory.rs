@@ -45,9 +45,11 @@ pub(crate) fn init_copy_addrs(
             &(user_image_bounds.start..bootinfo.footprint().end),
             frame_types::FrameType1::FRAME_SIZE.bytes(),
         );
+        // In u64, as this region may span the entire address space
+        let region_size = 1u64 << frame_types::FRAME_SIZE_2_BITS;
         match (
-            addr_space_footprint.start % frame_types::FrameType2::FRAME_SIZE.bytes(),
-            addr_space_footprint.end % frame_types::FrameType2::FRAME_SIZE.bytes(),
+            u64::try_from(addr_space_footprint.start).unwrap() % region_size,
+            u64::try_from(addr_space_footprint.end).unwrap() % region_size,
         ) {
             (0, 0) => panic!(), // absurd
             (_, 0) => {
@@ -0,0 +1,60 @@
+use core::fmt;
+
+use sel4_capdl_initializer_types::{ObjectId, Placement};
+
+/// Log target with which the placement of each root object which does not specify a paddr is
+/// logged at the info level, as a line of JSON which deserializes to a [`Placement`]. Collected
+/// into a JSON array, these lines form a placement plan for a later run with the same spec.
+pub const PLACEMENT_LOG_TARGET: &str = "sel4_capdl_initializer_core::placement";
+
+pub(crate) struct PlacementJson(pub(crate) ObjectId, pub(crate) sel4::PAddr);
+
+impl fmt::Display for PlacementJson {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{\"object\":{},\"paddr\":{}}}", self.0, self.1.addr())
+    }
+}
+
+// Merges the root objects which specify paddrs, which come first and are sorted by paddr, with
+// the entries of the placement plan.
+pub(crate) struct Pinned<'a> {
+    next_obj_with_paddr: usize,
+    num_objs_with_paddr: usize,
+    plan: &'a [Placement],
+    next_placement: usize,
+}
+
+impl<'a> Pinned<'a> {
+    pub(crate) fn new(num_objs_with_paddr: usize, plan: &'a [Placement]) -> Self {
+        Self {
+            next_obj_with_paddr: 0,
+            num_objs_with_paddr,
+            plan,
+            next_placement: 0,
+        }
+    }
+
+    pub(crate) fn peek(&self, paddr_of: impl Fn(ObjectId) -> usize) -> Option<Placement> {
+        let from_spec = (self.next_obj_with_paddr < self.num_objs_with_paddr).then(|| Placement {
+            object: self.next_obj_with_paddr,
+            paddr: paddr_of(self.next_obj_with_paddr),
+        });
+        let from_plan = self.plan.get(self.next_placement).copied();
+        match (from_spec, from_plan) {
+            (Some(a), Some(b)) => Some(if a.paddr <= b.paddr { a } else { b }),
+            (a, b) => a.or(b),
+        }
+    }
+
+    pub(crate) fn advance(&mut self, placement: &Placement) {
+        if placement.object < self.num_objs_with_paddr {
+            self.next_obj_with_paddr += 1;
+        } else {
+            self.next_placement += 1;
+        }
+    }
+
+    pub(crate) fn remaining_placement(&self) -> Option<&Placement> {
+        self.plan.get(self.next_placement)
+    }
+}
@@ -8,15 +8,18 @@
 extern crate alloc;
 
 use alloc::vec;
+use alloc::vec::Vec;
 use core::ops::Range;
 use core::ptr;
 use core::slice;
 
 use sel4::BootInfo;
-use sel4_capdl_initializer_core::{Initializer, InitializerBuffers, PerObjectBuffer};
+use sel4_capdl_initializer_core::{
+    Initializer, InitializerBuffers, PerObjectBuffer, BOOT_REPORT_LOG_TARGET, PLACEMENT_LOG_TARGET,
+};
 use sel4_capdl_initializer_types::{
-    IndirectDeflatedBytesContent, IndirectEmbeddedFrame, IndirectObjectName, SpecWithIndirection,
-    SpecWithSources,
+    IndirectDeflatedBytesContent, IndirectEmbeddedFrame, IndirectObjectName, Placement,
+    SpecWithIndirection, SpecWithSources,
 };
 use sel4_logging::{LevelFilter, Logger, LoggerBuilder};
 use sel4_root_task::root_task;
@@ -26,16 +29,27 @@ const LOG_LEVEL: LevelFilter =
     // LevelFilter::Debug
     LevelFilter::Info;
 
+// Whether to print the boot report as a line of JSON, given a log level of at least info.
+const PRINT_BOOT_REPORT: bool = true;
+
+// Whether to print the placement of each object as a line of JSON, given a log level of at least
+// info, for use as a placement plan for subsequent runs.
+const PRINT_PLACEMENTS: bool = false;
+
 static LOGGER: Logger = LoggerBuilder::const_default()
     .level_filter(LOG_LEVEL)
-    .filter(|meta| meta.target() == "sel4_capdl_initializer_core")
+    .filter(|meta| {
+        meta.target() == "sel4_capdl_initializer_core"
+            || (PRINT_BOOT_REPORT && meta.target() == BOOT_REPORT_LOG_TARGET)
+            || (PRINT_PLACEMENTS && meta.target() == PLACEMENT_LOG_TARGET)
+    })
     .write(|s| sel4::debug_print!("{}", s))
     .build();
 
 #[root_task(stack_size = 0x10000)]
 fn main(bootinfo: &BootInfo) -> ! {
     LOGGER.set().unwrap();
-    let spec_with_sources = get_spec_with_sources();
+    let (spec_with_sources, placement_plan) = get_spec_with_sources_and_placement_plan();
     let mut buffers = InitializerBuffers::new(vec![
         PerObjectBuffer::const_default();
         spec_with_sources.spec.objects.len()
@@ -44,6 +58,7 @@ fn main(bootinfo: &BootInfo) -> ! {
         bootinfo,
         user_image_bounds(),
         &spec_with_sources,
+        &placement_plan,
         &mut buffers,
     )
     .unwrap_or_else(|err| panic!("Error: {}", err))
@@ -73,25 +88,32 @@ static mut sel4_capdl_initializer_image_start: *mut u8 = ptr::null_mut();
 #[link_section = ".data"]
 static mut sel4_capdl_initializer_image_end: *mut u8 = ptr::null_mut();
 
-fn get_spec_with_sources<'a>() -> SpecWithSources<
-    'a,
-    Option<IndirectObjectName>,
-    IndirectDeflatedBytesContent,
-    IndirectEmbeddedFrame,
-> {
+fn get_spec_with_sources_and_placement_plan<'a>() -> (
+    SpecWithSources<
+        'a,
+        Option<IndirectObjectName>,
+        IndirectDeflatedBytesContent,
+        IndirectEmbeddedFrame,
+    >,
+    Vec<Placement>,
+) {
     let blob = unsafe {
         slice::from_raw_parts(
             sel4_capdl_initializer_serialized_spec_start,
             sel4_capdl_initializer_serialized_spec_size,
         )
     };
-    let (spec, source) = postcard::take_from_bytes::<SpecWithIndirection>(blob).unwrap();
-    SpecWithSources {
-        spec,
-        object_name_source: source,
-        content_source: source,
-        embedded_frame_source: source,
-    }
+    let (spec, rest) = postcard::take_from_bytes::<SpecWithIndirection>(blob).unwrap();
+    let (placement_plan, source) = postcard::take_from_bytes::<Vec<Placement>>(rest).unwrap();
+    (
+        SpecWithSources {
+            spec,
+            object_name_source: source,
+            content_source: source,
+            embedded_frame_source: source,
+        },
+        placement_plan,
+    )
 }
 
 fn user_image_bounds() -> Range<usize> {
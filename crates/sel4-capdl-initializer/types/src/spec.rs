@@ -42,6 +42,18 @@ pub struct IRQEntry {
 
 pub type ASIDSlotEntry = ObjectId;
 
+/// The physical address at which a root object is to be created, as an entry of a placement plan.
+///
+/// A placement plan is a list of these, sorted by `paddr`. It is typically recorded from the
+/// placements logged by a previous run of the initializer with the same spec, and is followed in
+/// order to reproduce that run's physical layout.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Placement {
+    pub object: ObjectId,
+    pub paddr: usize,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UntypedCover {
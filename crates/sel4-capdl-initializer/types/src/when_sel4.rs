@@ -31,6 +31,12 @@ impl<'a, D, M> Object<'a, D, M> {
                     sel4::FrameSize::GIGA_BITS => sel4::ObjectBlueprintArch::GigaPage.into(),
                     _ => panic!(),
                 },
+                #[sel4_cfg(ARCH_RISCV32)]
+                Object::Frame(obj) => match obj.size_bits {
+                    sel4::FrameSize::_4K_BITS => sel4::ObjectBlueprintArch::_4KPage.into(),
+                    sel4::FrameSize::MEGA_BITS => sel4::ObjectBlueprintArch::MegaPage.into(),
+                    _ => panic!(),
+                },
                 #[sel4_cfg(ARCH_X86_64)]
                 Object::Frame(obj) => match obj.size_bits {
                     sel4::FrameSize::_4K_BITS => sel4::ObjectBlueprintArch::_4K.into(),
@@ -49,7 +55,7 @@ impl<'a, D, M> Object<'a, D, M> {
                         _ => panic!(),
                     }
                 }
-                #[sel4_cfg(ARCH_RISCV64)]
+                #[sel4_cfg(any(ARCH_RISCV64, ARCH_RISCV32))]
                 Object::PageTable(obj) => {
                     assert!(obj.level.is_none()); // sanity check
                     sel4::ObjectBlueprintArch::PageTable.into()
@@ -135,7 +141,7 @@ sel4::sel4_cfg_if! {
     if #[cfg(ARCH_AARCH64)] {
         const CACHED: VMAttributes = VMAttributes::PAGE_CACHEABLE;
         const UNCACHED: VMAttributes = VMAttributes::DEFAULT;
-    } else if #[cfg(ARCH_RISCV64)] {
+    } else if #[cfg(any(ARCH_RISCV64, ARCH_RISCV32))] {
         const CACHED: VMAttributes = VMAttributes::DEFAULT;
         const UNCACHED: VMAttributes = VMAttributes::NONE;
     } else if #[cfg(ARCH_X86_64)] {
@@ -32,9 +32,13 @@ fn main(bootinfo: &BootInfo) -> ! {
         content_source: &trivial_source,
         embedded_frame_source: &trivial_source,
     };
-    Initializer::initialize(bootinfo, user_image_bounds(), &spec_with_sources, unsafe {
-        &mut BUFFERS
-    })
+    Initializer::initialize(
+        bootinfo,
+        user_image_bounds(),
+        &spec_with_sources,
+        &[],
+        unsafe { &mut BUFFERS },
+    )
     .unwrap_or_else(|err| panic!("Error: {}", err))
 }
 
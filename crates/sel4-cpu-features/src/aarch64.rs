@@ -0,0 +1,83 @@
+//! Features reported by `ID_AA64ISAR0_EL1`, and identification from `MIDR_EL1`.
+
+declare_features! {
+    Aes = 0 => "aes",
+    Pmull = 1 => "pmull",
+    Sha1 = 2 => "sha1",
+    Sha256 = 3 => "sha256",
+    Sha512 = 4 => "sha512",
+    Crc32 = 5 => "crc32",
+    /// Large System Extensions atomics (FEAT_LSE).
+    Lse = 6 => "lse",
+    Rdm = 7 => "rdm",
+    Sha3 = 8 => "sha3",
+    Sm3 = 9 => "sm3",
+    Sm4 = 10 => "sm4",
+    DotProd = 11 => "dotprod",
+    Rng = 12 => "rng",
+}
+
+impl Features {
+    pub fn from_id_aa64isar0_el1(value: u64) -> Self {
+        let field = |shift: u32| (value >> shift) & 0xf;
+        let mut this = Self::empty();
+        this.set(Feature::Aes, field(4) >= 1);
+        this.set(Feature::Pmull, field(4) >= 2);
+        this.set(Feature::Sha1, field(8) >= 1);
+        this.set(Feature::Sha256, field(12) >= 1);
+        this.set(Feature::Sha512, field(12) >= 2);
+        this.set(Feature::Crc32, field(16) >= 1);
+        this.set(Feature::Lse, field(20) >= 2);
+        this.set(Feature::Rdm, field(28) >= 1);
+        this.set(Feature::Sha3, field(32) >= 1);
+        this.set(Feature::Sm3, field(36) >= 1);
+        this.set(Feature::Sm4, field(40) >= 1);
+        this.set(Feature::DotProd, field(44) >= 1);
+        this.set(Feature::Rng, field(60) >= 1);
+        this
+    }
+}
+
+/// The fields of `MIDR_EL1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Midr {
+    pub implementer: u8,
+    pub variant: u8,
+    pub architecture: u8,
+    pub part_num: u16,
+    pub revision: u8,
+}
+
+impl Midr {
+    pub const IMPLEMENTER_ARM: u8 = 0x41;
+
+    pub fn from_bits(value: u64) -> Self {
+        Self {
+            implementer: (value >> 24) as u8,
+            variant: ((value >> 20) & 0xf) as u8,
+            architecture: ((value >> 16) & 0xf) as u8,
+            part_num: ((value >> 4) & 0xfff) as u16,
+            revision: (value & 0xf) as u8,
+        }
+    }
+}
+
+/// # Safety
+///
+/// The kernel must permit or emulate reads of `MIDR_EL1` at EL0. Otherwise, this faults.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn read_midr_el1() -> u64 {
+    let value: u64;
+    core::arch::asm!("mrs {}, midr_el1", out(reg) value, options(nomem, nostack));
+    value
+}
+
+/// # Safety
+///
+/// The kernel must permit or emulate reads of `ID_AA64ISAR0_EL1` at EL0. Otherwise, this faults.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn read_id_aa64isar0_el1() -> u64 {
+    let value: u64;
+    core::arch::asm!("mrs {}, id_aa64isar0_el1", out(reg) value, options(nomem, nostack));
+    value
+}
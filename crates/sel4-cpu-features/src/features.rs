@@ -0,0 +1,75 @@
+// Declares a `Feature` enum, each of whose variants is a bit position, and a `Features` set of
+// them.
+macro_rules! declare_features {
+    ($($(#[$attrs:meta])* $variant:ident = $bit:literal => $name:literal,)*) => {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum Feature {
+            $($(#[$attrs])* $variant = $bit,)*
+        }
+
+        impl Feature {
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            const fn mask(self) -> u64 {
+                1 << self as u64
+            }
+        }
+
+        impl core::fmt::Display for Feature {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        #[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct Features {
+            bits: u64,
+        }
+
+        impl Features {
+            pub const fn empty() -> Self {
+                Self { bits: 0 }
+            }
+
+            pub const fn has(&self, feature: Feature) -> bool {
+                self.bits & feature.mask() != 0
+            }
+
+            pub fn insert(&mut self, feature: Feature) {
+                self.bits |= feature.mask();
+            }
+
+            fn set(&mut self, feature: Feature, present: bool) {
+                if present {
+                    self.insert(feature);
+                }
+            }
+
+            pub fn iter(&self) -> impl Iterator<Item = Feature> + '_ {
+                Feature::ALL.iter().copied().filter(|feature| self.has(*feature))
+            }
+        }
+
+        impl core::fmt::Debug for Features {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.debug_set().entries(self.iter()).finish()
+            }
+        }
+
+        impl FromIterator<Feature> for Features {
+            fn from_iter<T: IntoIterator<Item = Feature>>(iter: T) -> Self {
+                let mut this = Self::empty();
+                for feature in iter {
+                    this.insert(feature);
+                }
+                this
+            }
+        }
+    };
+}
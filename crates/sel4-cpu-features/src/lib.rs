@@ -0,0 +1,25 @@
+//! Parsing of the registers and strings which describe a CPU's ISA extensions, with typed feature
+//! flags, so that code can select optimized implementations at runtime.
+//!
+//! Parsing is available for all architectures on all targets. Reading the underlying registers is
+//! only possible where the kernel permits it:
+//!
+//! - On x86, `cpuid` is always available to user space, so [`x86::Features::read`] is safe.
+//! - On AArch64, reads of `MIDR_EL1` and the ID registers from EL0 trap unless the kernel emulates
+//!   or permits them, so [`aarch64::read_midr_el1`] and [`aarch64::read_id_aa64isar0_el1`] are
+//!   `unsafe`. Otherwise, these values must be obtained from elsewhere, such as the device tree or
+//!   a more privileged component.
+//! - On RISC-V, `misa` is only accessible in machine mode, so [`riscv::Features`] are typically
+//!   parsed from the `riscv,isa` property of a CPU node of the device tree.
+//!
+//! Note that some features also require support from the kernel. For example, AVX requires that
+//! the kernel saves and restores its state.
+
+#![no_std]
+
+#[macro_use]
+mod features;
+
+pub mod aarch64;
+pub mod riscv;
+pub mod x86;
@@ -0,0 +1,141 @@
+//! Features reported by `misa` or by an ISA string, such as the `riscv,isa` property of a CPU node
+//! of the device tree.
+
+use core::fmt;
+
+declare_features! {
+    M = 0 => "m",
+    A = 1 => "a",
+    F = 2 => "f",
+    D = 3 => "d",
+    C = 4 => "c",
+    V = 5 => "v",
+    H = 6 => "h",
+    Zicsr = 7 => "zicsr",
+    Zifencei = 8 => "zifencei",
+    Zicbom = 9 => "zicbom",
+    Zicboz = 10 => "zicboz",
+    Zba = 11 => "zba",
+    Zbb = 12 => "zbb",
+    Zbc = 13 => "zbc",
+    Zbs = 14 => "zbs",
+    Zbkb = 15 => "zbkb",
+    Zbkc = 16 => "zbkc",
+    Zknd = 17 => "zknd",
+    Zkne = 18 => "zkne",
+    Zknh = 19 => "zknh",
+    Zkr = 20 => "zkr",
+    /// Supervisor-mode timer interrupts (`stimecmp`).
+    Sstc = 21 => "sstc",
+    Svpbmt = 22 => "svpbmt",
+    Svnapot = 23 => "svnapot",
+}
+
+const SINGLE_LETTER: &[Feature] = &[
+    Feature::M,
+    Feature::A,
+    Feature::F,
+    Feature::D,
+    Feature::C,
+    Feature::V,
+    Feature::H,
+];
+
+impl Features {
+    /// Parses the extension bits of `misa`. Only single-letter extensions are reported by `misa`.
+    pub fn from_misa(value: u64) -> Self {
+        let mut this = Self::empty();
+        for feature in SINGLE_LETTER {
+            let letter = feature.name().as_bytes()[0];
+            this.set(*feature, value & (1 << (letter - b'a')) != 0);
+        }
+        this
+    }
+
+    /// Parses an ISA string, such as `rv64imafdc_zicsr_zifencei_sstc`. Unknown extensions are
+    /// ignored.
+    pub fn from_isa_string(s: &str) -> Result<Self, IsaStringError> {
+        let s = s.trim_end_matches('\0');
+        let rest = ["rv32", "rv64", "rv128"]
+            .iter()
+            .find_map(|prefix| strip_prefix_ignore_case(s, prefix))
+            .ok_or(IsaStringError)?;
+        let mut this = Self::empty();
+        let mut segments = rest.split('_');
+        let single_letters = segments.next().unwrap();
+        let mut multi_letter_start = single_letters.len();
+        for (i, c) in single_letters.char_indices() {
+            let c = c.to_ascii_lowercase();
+            match c {
+                'z' | 's' | 'x' => {
+                    multi_letter_start = i;
+                    break;
+                }
+                'g' => {
+                    for feature in [
+                        Feature::M,
+                        Feature::A,
+                        Feature::F,
+                        Feature::D,
+                        Feature::Zicsr,
+                        Feature::Zifencei,
+                    ] {
+                        this.insert(feature);
+                    }
+                }
+                'a'..='z' => {
+                    if let Some(feature) = SINGLE_LETTER
+                        .iter()
+                        .find(|feature| feature.name().starts_with(c))
+                    {
+                        this.insert(*feature);
+                    }
+                }
+                // Version numbers, such as "2p0"
+                '0'..='9' => {}
+                _ => return Err(IsaStringError),
+            }
+        }
+        let first_multi_letter = &single_letters[multi_letter_start..];
+        for name in [first_multi_letter].into_iter().chain(segments) {
+            if name.is_empty() {
+                continue;
+            }
+            let name = strip_version(name);
+            if let Some(feature) = Feature::ALL
+                .iter()
+                .find(|feature| feature.name().eq_ignore_ascii_case(name))
+            {
+                this.insert(*feature);
+            }
+        }
+        Ok(this)
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+// Strips a trailing version number, such as "2" or "2p0".
+fn strip_version(name: &str) -> &str {
+    let without_minor = match name.trim_end_matches(|c: char| c.is_ascii_digit()) {
+        stripped if stripped.len() < name.len() => stripped
+            .strip_suffix(['p', 'P'])
+            .filter(|major| major.ends_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(stripped),
+        _ => return name,
+    };
+    without_minor.trim_end_matches(|c: char| c.is_ascii_digit())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IsaStringError;
+
+impl fmt::Display for IsaStringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ISA string")
+    }
+}
@@ -0,0 +1,91 @@
+//! Features reported by `cpuid`.
+
+declare_features! {
+    Sse2 = 0 => "sse2",
+    Sse3 = 1 => "sse3",
+    Ssse3 = 2 => "ssse3",
+    Sse41 = 3 => "sse4.1",
+    Sse42 = 4 => "sse4.2",
+    Pclmulqdq = 5 => "pclmulqdq",
+    Aes = 6 => "aes",
+    Avx = 7 => "avx",
+    Rdrand = 8 => "rdrand",
+    Fsgsbase = 9 => "fsgsbase",
+    Bmi1 = 10 => "bmi1",
+    Avx2 = 11 => "avx2",
+    Bmi2 = 12 => "bmi2",
+    Rdseed = 13 => "rdseed",
+    Adx = 14 => "adx",
+    Sha = 15 => "sha",
+}
+
+/// The registers returned by `cpuid` for a given leaf and subleaf.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CpuidResult {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+impl Features {
+    /// Parses the results of `cpuid` leaf 1 and leaf 7, subleaf 0. `leaf_7` should be `None` if
+    /// the maximum leaf, as reported by leaf 0, is less than 7.
+    pub fn from_cpuid(leaf_1: &CpuidResult, leaf_7: Option<&CpuidResult>) -> Self {
+        let mut this = Self::empty();
+        this.set(Feature::Sse2, leaf_1.edx & (1 << 26) != 0);
+        this.set(Feature::Sse3, leaf_1.ecx & (1 << 0) != 0);
+        this.set(Feature::Pclmulqdq, leaf_1.ecx & (1 << 1) != 0);
+        this.set(Feature::Ssse3, leaf_1.ecx & (1 << 9) != 0);
+        this.set(Feature::Sse41, leaf_1.ecx & (1 << 19) != 0);
+        this.set(Feature::Sse42, leaf_1.ecx & (1 << 20) != 0);
+        this.set(Feature::Aes, leaf_1.ecx & (1 << 25) != 0);
+        this.set(Feature::Avx, leaf_1.ecx & (1 << 28) != 0);
+        this.set(Feature::Rdrand, leaf_1.ecx & (1 << 30) != 0);
+        if let Some(leaf_7) = leaf_7 {
+            this.set(Feature::Fsgsbase, leaf_7.ebx & (1 << 0) != 0);
+            this.set(Feature::Bmi1, leaf_7.ebx & (1 << 3) != 0);
+            this.set(Feature::Avx2, leaf_7.ebx & (1 << 5) != 0);
+            this.set(Feature::Bmi2, leaf_7.ebx & (1 << 8) != 0);
+            this.set(Feature::Rdseed, leaf_7.ebx & (1 << 18) != 0);
+            this.set(Feature::Adx, leaf_7.ebx & (1 << 19) != 0);
+            this.set(Feature::Sha, leaf_7.ebx & (1 << 29) != 0);
+        }
+        this
+    }
+
+    /// Reads the features of the current CPU with `cpuid`.
+    #[cfg(target_arch = "x86_64")]
+    pub fn read() -> Self {
+        let leaf_0 = cpuid(0, 0);
+        let leaf_1 = cpuid(1, 0);
+        let leaf_7 = if leaf_0.eax >= 7 {
+            Some(cpuid(7, 0))
+        } else {
+            None
+        };
+        Self::from_cpuid(&leaf_1, leaf_7.as_ref())
+    }
+}
+
+/// Parses the vendor identification string, such as `GenuineIntel`, from the result of `cpuid`
+/// leaf 0.
+pub fn vendor(leaf_0: &CpuidResult) -> [u8; 12] {
+    let mut vendor = [0; 12];
+    vendor[0..4].copy_from_slice(&leaf_0.ebx.to_le_bytes());
+    vendor[4..8].copy_from_slice(&leaf_0.edx.to_le_bytes());
+    vendor[8..12].copy_from_slice(&leaf_0.ecx.to_le_bytes());
+    vendor
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid(leaf: u32, subleaf: u32) -> CpuidResult {
+    // SAFETY: cpuid is available on all x86_64 CPUs
+    let r = unsafe { core::arch::x86_64::__cpuid_count(leaf, subleaf) };
+    CpuidResult {
+        eax: r.eax,
+        ebx: r.ebx,
+        ecx: r.ecx,
+        edx: r.edx,
+    }
+}
@@ -0,0 +1,80 @@
+use sel4_cpu_features::{aarch64, riscv, x86};
+
+#[test]
+fn x86_cpuid() {
+    let leaf_1 = x86::CpuidResult {
+        ecx: (1 << 25) | (1 << 20),
+        edx: 1 << 26,
+        ..Default::default()
+    };
+    let leaf_7 = x86::CpuidResult {
+        ebx: (1 << 5) | (1 << 29),
+        ..Default::default()
+    };
+    let features = x86::Features::from_cpuid(&leaf_1, Some(&leaf_7));
+    assert_eq!(
+        features.iter().collect::<Vec<_>>(),
+        [
+            x86::Feature::Sse2,
+            x86::Feature::Sse42,
+            x86::Feature::Aes,
+            x86::Feature::Avx2,
+            x86::Feature::Sha,
+        ]
+    );
+    assert!(!x86::Features::from_cpuid(&leaf_1, None).has(x86::Feature::Avx2));
+}
+
+#[test]
+fn aarch64_id_registers() {
+    // Cortex-A72 r0p3
+    let midr = aarch64::Midr::from_bits(0x410f_d083);
+    assert_eq!(midr.implementer, aarch64::Midr::IMPLEMENTER_ARM);
+    assert_eq!(midr.part_num, 0xd08);
+    assert_eq!(midr.revision, 3);
+
+    // AES with PMULL, SHA1, SHA256, CRC32, and LSE atomics
+    let features = aarch64::Features::from_id_aa64isar0_el1(0x0021_1120);
+    for feature in [
+        aarch64::Feature::Aes,
+        aarch64::Feature::Pmull,
+        aarch64::Feature::Sha1,
+        aarch64::Feature::Sha256,
+        aarch64::Feature::Crc32,
+        aarch64::Feature::Lse,
+    ] {
+        assert!(features.has(feature), "{feature}");
+    }
+    assert!(!features.has(aarch64::Feature::Sha512));
+}
+
+#[test]
+fn riscv_isa_string() {
+    let features =
+        riscv::Features::from_isa_string("rv64imafdc_zicsr2p0_zifencei_Sstc_zvl128b\0").unwrap();
+    let expected = [
+        riscv::Feature::M,
+        riscv::Feature::A,
+        riscv::Feature::F,
+        riscv::Feature::D,
+        riscv::Feature::C,
+        riscv::Feature::Zicsr,
+        riscv::Feature::Zifencei,
+        riscv::Feature::Sstc,
+    ]
+    .into_iter()
+    .collect::<riscv::Features>();
+    assert_eq!(features, expected);
+    assert_eq!(
+        riscv::Features::from_isa_string("rv64gczba").unwrap(),
+        riscv::Features::from_isa_string("rv64imafdc_zicsr_zifencei_zba").unwrap(),
+    );
+    assert_eq!(
+        riscv::Features::from_misa(0x8000_0000_0014_112d),
+        expected
+            .iter()
+            .filter(|feature| feature.name().len() == 1)
+            .collect()
+    );
+    assert!(riscv::Features::from_isa_string("imafdc").is_err());
+}
@@ -0,0 +1,525 @@
+//! An index over a flattened device tree, for configuring drivers at runtime.
+//!
+//! [`DeviceTree::new`] walks the tree once, recording each node's path, `compatible` strings,
+//! `reg` entries, and interrupt specifiers, with `#address-cells`, `#size-cells`,
+//! `interrupt-parent`, and `status` resolved according to the nodes' ancestors. Subsequent queries
+//! do not re-parse the tree.
+//!
+//! On platforms where the kernel passes a device tree to the root task, the blob can be found with
+//! `sel4::BootInfo::extra`, as the content of the entry with id `sel4::BootInfoExtraId::Fdt`.
+//!
+//! Addresses in [`Reg`] entries are in the address space of the node's parent bus. `ranges`
+//! properties are not taken into account.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+
+use fdt::node::FdtNode;
+use fdt::{Fdt, FdtError};
+
+pub use fdt;
+
+const DEFAULT_ADDRESS_CELLS: usize = 2;
+const DEFAULT_SIZE_CELLS: usize = 1;
+
+const GIC_SPI: u32 = 0;
+const GIC_PPI: u32 = 1;
+const GIC_SPI_BASE: u32 = 32;
+const GIC_PPI_BASE: u32 = 16;
+
+pub type Phandle = u32;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DeviceId(usize);
+
+pub struct DeviceTree<'a> {
+    fdt: Fdt<'a>,
+    devices: Vec<Device<'a>>,
+    by_compatible: BTreeMap<&'a str, Vec<DeviceId>>,
+    by_phandle: BTreeMap<Phandle, DeviceId>,
+    by_path: BTreeMap<String, DeviceId>,
+}
+
+impl<'a> DeviceTree<'a> {
+    pub fn new(dtb: &'a [u8]) -> Result<Self, Error> {
+        let fdt = Fdt::new(dtb).map_err(Error::Fdt)?;
+        let mut devices = Vec::new();
+        visit(
+            &mut devices,
+            fdt.find_node("/").ok_or(Error::MissingRoot)?,
+            None,
+            Context::root(),
+        )?;
+
+        let mut this = Self {
+            fdt,
+            devices,
+            by_compatible: BTreeMap::new(),
+            by_phandle: BTreeMap::new(),
+            by_path: BTreeMap::new(),
+        };
+
+        for device in this.devices.iter() {
+            for compatible in device.compatible.iter() {
+                this.by_compatible
+                    .entry(*compatible)
+                    .or_default()
+                    .push(device.id);
+            }
+            if let Some(phandle) = device.phandle {
+                this.by_phandle.insert(phandle, device.id);
+            }
+            this.by_path.insert(device.path.clone(), device.id);
+        }
+
+        // Interrupt specifiers can only be parsed once phandles have been indexed, because their
+        // width is determined by the interrupt parent.
+        let interrupts = this
+            .devices
+            .iter()
+            .map(|device| this.parse_interrupts(device))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (device, interrupts) in this.devices.iter_mut().zip(interrupts) {
+            // Without an interrupt parent, the specifiers cannot be interpreted, but the rest of the
+            // device may still be of use.
+            match interrupts {
+                Some(interrupts) => device.interrupts = interrupts,
+                None => device.has_unresolved_interrupts = true,
+            }
+        }
+
+        Ok(this)
+    }
+
+    // Returns `None` if an interrupt parent, or its `#interrupt-cells`, cannot be found.
+    fn parse_interrupts(&self, device: &Device<'a>) -> Result<Option<Vec<Interrupt>>, Error> {
+        // As in Linux, `interrupts-extended` takes precedence over `interrupts`.
+        if let Some(value) = device.property("interrupts-extended") {
+            let malformed = || Error::malformed(device, "interrupts-extended");
+            let cells = parse_cells(value).ok_or_else(malformed)?;
+            let mut interrupts = Vec::new();
+            let mut rest = cells.as_slice();
+            while let Some((&parent, tail)) = rest.split_first() {
+                let Some(n) = self.interrupt_cells(parent) else {
+                    return Ok(None);
+                };
+                let specifier = tail.get(..n).ok_or_else(malformed)?;
+                interrupts.push(Interrupt {
+                    parent,
+                    cells: specifier.to_vec(),
+                });
+                rest = &tail[n..];
+            }
+            return Ok(Some(interrupts));
+        }
+
+        let Some(value) = device.property("interrupts") else {
+            return Ok(Some(Vec::new()));
+        };
+        let Some((parent, n)) = device
+            .interrupt_parent
+            .and_then(|parent| Some((parent, self.interrupt_cells(parent)?)))
+        else {
+            return Ok(None);
+        };
+        let cells = parse_cells(value)
+            .filter(|cells| n != 0 && cells.len() % n == 0)
+            .ok_or_else(|| Error::malformed(device, "interrupts"))?;
+        Ok(Some(
+            cells
+                .chunks(n)
+                .map(|chunk| Interrupt {
+                    parent,
+                    cells: chunk.to_vec(),
+                })
+                .collect(),
+        ))
+    }
+
+    fn interrupt_cells(&self, interrupt_parent: Phandle) -> Option<usize> {
+        self.find_phandle(interrupt_parent)?
+            .property_u32("#interrupt-cells")
+            .and_then(|n| n.try_into().ok())
+    }
+
+    pub fn fdt(&self) -> &Fdt<'a> {
+        &self.fdt
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &Device<'a>> {
+        self.devices.iter()
+    }
+
+    pub fn device(&self, id: DeviceId) -> &Device<'a> {
+        &self.devices[id.0]
+    }
+
+    pub fn root(&self) -> &Device<'a> {
+        self.device(DeviceId(0))
+    }
+
+    pub fn find_path(&self, path: &str) -> Option<&Device<'a>> {
+        self.by_path.get(path).map(|id| self.device(*id))
+    }
+
+    pub fn find_phandle(&self, phandle: Phandle) -> Option<&Device<'a>> {
+        self.by_phandle.get(&phandle).map(|id| self.device(*id))
+    }
+
+    /// Devices which are compatible with any of `compatible`, in order of preference.
+    pub fn find_compatible<'b, 'c>(
+        &'b self,
+        compatible: &'c [&'c str],
+    ) -> impl Iterator<Item = &'b Device<'a>> + 'c
+    where
+        'b: 'c,
+    {
+        compatible.iter().flat_map(|compatible| {
+            self.by_compatible
+                .get(*compatible)
+                .into_iter()
+                .flatten()
+                .map(|id| self.device(*id))
+        })
+    }
+
+    /// Like [`DeviceTree::find_compatible`], but skips devices which are not enabled (see
+    /// [`Device::is_enabled`]).
+    pub fn find_enabled_compatible<'b, 'c>(
+        &'b self,
+        compatible: &'c [&'c str],
+    ) -> impl Iterator<Item = &'b Device<'a>> + 'c
+    where
+        'b: 'c,
+    {
+        self.find_compatible(compatible)
+            .filter(|device| device.is_enabled())
+    }
+
+    pub fn parent(&self, device: &Device<'a>) -> Option<&Device<'a>> {
+        device.parent.map(|id| self.device(id))
+    }
+
+    pub fn interrupt_parent(&self, device: &Device<'a>) -> Option<&Device<'a>> {
+        device
+            .interrupt_parent
+            .and_then(|phandle| self.find_phandle(phandle))
+    }
+
+    /// The device whose `phandle` is the first cell of `device`'s property `name`, as in `clocks`.
+    pub fn phandle_property(&self, device: &Device<'a>, name: &str) -> Option<&Device<'a>> {
+        device
+            .property_u32(name)
+            .and_then(|phandle| self.find_phandle(phandle))
+    }
+
+    /// The value of `clock-frequency` for `device`, falling back to that of its first `clocks`
+    /// provider.
+    pub fn clock_frequency(&self, device: &Device<'a>) -> Option<u64> {
+        device.clock_frequency().or_else(|| {
+            self.phandle_property(device, "clocks")
+                .and_then(|clock| clock.clock_frequency())
+        })
+    }
+}
+
+impl<'a> fmt::Debug for DeviceTree<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeviceTree")
+            .field("devices", &self.devices)
+            .finish()
+    }
+}
+
+fn visit<'a>(
+    devices: &mut Vec<Device<'a>>,
+    node: FdtNode<'_, 'a>,
+    parent: Option<(DeviceId, &str)>,
+    context: Context,
+) -> Result<(), Error> {
+    let path = match parent {
+        None => String::from("/"),
+        Some((_, "/")) => format!("/{}", node.name),
+        Some((_, parent_path)) => format!("{}/{}", parent_path, node.name),
+    };
+
+    let mut device = Device {
+        id: DeviceId(devices.len()),
+        parent: parent.map(|(id, _)| id),
+        name: node.name,
+        path,
+        properties: node
+            .properties()
+            .map(|prop| (prop.name, prop.value))
+            .collect(),
+        compatible: Vec::new(),
+        phandle: None,
+        interrupt_parent: context.interrupt_parent,
+        reg: Vec::new(),
+        interrupts: Vec::new(),
+        has_unresolved_interrupts: false,
+        enabled: false,
+    };
+
+    if let Some(value) = device.property("compatible") {
+        device.compatible = value
+            .split(|b| *b == 0)
+            .filter(|s| !s.is_empty())
+            .map(str::from_utf8)
+            .collect::<Result<_, _>>()
+            .map_err(|_| Error::malformed(&device, "compatible"))?;
+    }
+
+    device.enabled =
+        context.enabled && matches!(device.property_str("status"), None | Some("okay" | "ok"));
+
+    device.phandle = device
+        .property_u32("phandle")
+        .or_else(|| device.property_u32("linux,phandle"));
+
+    if let Some(phandle) = device.property_u32("interrupt-parent") {
+        device.interrupt_parent = Some(phandle);
+    }
+
+    if let Some(value) = device.property("reg") {
+        let n = context
+            .address_cells
+            .checked_add(context.size_cells)
+            .filter(|n| *n != 0 && context.address_cells <= 2 && context.size_cells <= 2)
+            .ok_or_else(|| Error::malformed(&device, "reg"))?;
+        let cells = parse_cells(value)
+            .filter(|cells| cells.len() % n == 0)
+            .ok_or_else(|| Error::malformed(&device, "reg"))?;
+        device.reg = cells
+            .chunks(n)
+            .map(|chunk| {
+                let (addr, size) = chunk.split_at(context.address_cells);
+                Reg {
+                    addr: combine_cells(addr),
+                    size: combine_cells(size),
+                }
+            })
+            .collect();
+    }
+
+    let child_context = Context {
+        address_cells: device
+            .property_u32("#address-cells")
+            .map(|n| n.try_into().unwrap())
+            .unwrap_or(DEFAULT_ADDRESS_CELLS),
+        size_cells: device
+            .property_u32("#size-cells")
+            .map(|n| n.try_into().unwrap())
+            .unwrap_or(DEFAULT_SIZE_CELLS),
+        interrupt_parent: device.interrupt_parent,
+        enabled: device.enabled,
+    };
+
+    let id = device.id;
+    let path = device.path.clone();
+    devices.push(device);
+
+    for child in node.children() {
+        visit(devices, child, Some((id, &path)), child_context)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+struct Context {
+    address_cells: usize,
+    size_cells: usize,
+    interrupt_parent: Option<Phandle>,
+    enabled: bool,
+}
+
+impl Context {
+    fn root() -> Self {
+        Self {
+            address_cells: DEFAULT_ADDRESS_CELLS,
+            size_cells: DEFAULT_SIZE_CELLS,
+            interrupt_parent: None,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Device<'a> {
+    id: DeviceId,
+    parent: Option<DeviceId>,
+    name: &'a str,
+    path: String,
+    properties: Vec<(&'a str, &'a [u8])>,
+    compatible: Vec<&'a str>,
+    phandle: Option<Phandle>,
+    interrupt_parent: Option<Phandle>,
+    reg: Vec<Reg>,
+    interrupts: Vec<Interrupt>,
+    has_unresolved_interrupts: bool,
+    enabled: bool,
+}
+
+impl<'a> Device<'a> {
+    pub fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn compatible(&self) -> &[&'a str] {
+        &self.compatible
+    }
+
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible.iter().any(|c| *c == compatible)
+    }
+
+    pub fn phandle(&self) -> Option<Phandle> {
+        self.phandle
+    }
+
+    pub fn reg(&self) -> &[Reg] {
+        &self.reg
+    }
+
+    /// The entries of `interrupts-extended` if it is present, and otherwise of `interrupts`.
+    pub fn interrupts(&self) -> &[Interrupt] {
+        &self.interrupts
+    }
+
+    /// Whether this device has interrupts which could not be parsed because an interrupt parent, or
+    /// the parent's `#interrupt-cells`, could not be found. Such a device's [`Device::interrupts`]
+    /// is empty.
+    pub fn has_unresolved_interrupts(&self) -> bool {
+        self.has_unresolved_interrupts
+    }
+
+    /// Whether the `status` property of this device and of each of its ancestors is either absent
+    /// or `"okay"`.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn clock_frequency(&self) -> Option<u64> {
+        self.property_uint("clock-frequency")
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = (&'a str, &'a [u8])> + '_ {
+        self.properties.iter().copied()
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'a [u8]> {
+        self.properties
+            .iter()
+            .find(|(prop_name, _)| *prop_name == name)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        Some(u32::from_be_bytes(
+            self.property(name)?.get(..4)?.try_into().unwrap(),
+        ))
+    }
+
+    /// Reads a property which may be either one or two cells wide.
+    pub fn property_uint(&self, name: &str) -> Option<u64> {
+        let value = self.property(name)?;
+        match value.len() {
+            4 => Some(u32::from_be_bytes(value.try_into().unwrap()).into()),
+            8 => Some(u64::from_be_bytes(value.try_into().unwrap())),
+            _ => None,
+        }
+    }
+
+    pub fn property_str(&self, name: &str) -> Option<&'a str> {
+        let value = self.property(name)?;
+        str::from_utf8(value.strip_suffix(&[0]).unwrap_or(value)).ok()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Reg {
+    pub addr: u64,
+    pub size: u64,
+}
+
+/// An interrupt specifier, whose interpretation depends on the interrupt parent.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Interrupt {
+    pub parent: Phandle,
+    pub cells: Vec<u32>,
+}
+
+impl Interrupt {
+    /// Interprets this specifier according to the Arm GIC binding, returning the interrupt ID.
+    pub fn gic_irq(&self) -> Option<u32> {
+        match self.cells.as_slice() {
+            [GIC_SPI, n, ..] => n.checked_add(GIC_SPI_BASE),
+            [GIC_PPI, n, ..] => n.checked_add(GIC_PPI_BASE),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fdt(FdtError),
+    MissingRoot,
+    MalformedProperty { path: String, property: String },
+}
+
+impl Error {
+    fn malformed(device: &Device, property: &str) -> Self {
+        Self::MalformedProperty {
+            path: device.path.clone(),
+            property: property.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fdt(err) => write!(f, "invalid device tree: {:?}", err),
+            Self::MissingRoot => write!(f, "device tree has no root node"),
+            Self::MalformedProperty { path, property } => {
+                write!(f, "malformed property {:?} of {}", property, path)
+            }
+        }
+    }
+}
+
+fn parse_cells(value: &[u8]) -> Option<Vec<u32>> {
+    if value.len() % 4 != 0 {
+        return None;
+    }
+    Some(
+        value
+            .chunks_exact(4)
+            .map(|cell| u32::from_be_bytes(cell.try_into().unwrap()))
+            .collect(),
+    )
+}
+
+fn combine_cells(cells: &[u32]) -> u64 {
+    cells
+        .iter()
+        .fold(0, |acc, cell| (acc << 32) | u64::from(*cell))
+}
@@ -0,0 +1,79 @@
+use sel4_device_tree::{DeviceTree, Interrupt};
+
+// Generated from fixtures/test.dts with `dtc -O dtb`.
+const DTB: &[u8] = include_bytes!("fixtures/test.dtb");
+
+fn gic_irqs(dt: &DeviceTree, compatible: &str) -> Vec<Option<u32>> {
+    let device = dt.find_compatible(&[compatible]).next().unwrap();
+    device
+        .interrupts()
+        .iter()
+        .map(|irq| irq.gic_irq())
+        .collect()
+}
+
+#[test]
+fn index_fixture() {
+    let dt = DeviceTree::new(DTB).unwrap();
+
+    let serial = dt.find_path("/serial@9000000").unwrap();
+    assert_eq!(serial.compatible(), ["arm,pl011", "arm,primecell"]);
+    assert_eq!(
+        (serial.reg()[0].addr, serial.reg()[0].size),
+        (0x900_0000, 0x1000)
+    );
+    assert_eq!(dt.clock_frequency(serial), Some(24_000_000));
+    assert_eq!(
+        dt.interrupt_parent(serial).unwrap().path(),
+        "/interrupt-controller@8000000"
+    );
+    assert_eq!(gic_irqs(&dt, "arm,pl011"), [Some(33)]);
+    assert_eq!(gic_irqs(&dt, "arm,armv8-timer"), [Some(29), Some(30)]);
+    assert_eq!(gic_irqs(&dt, "test,bad-spi"), [None]);
+
+    let orphan = dt.find_compatible(&["test,orphan"]).next().unwrap();
+    assert!(orphan.has_unresolved_interrupts());
+    assert!(orphan.interrupts().is_empty());
+    assert!(!orphan.is_enabled());
+    assert!(!serial.has_unresolved_interrupts());
+
+    assert_eq!(
+        dt.find_enabled_compatible(&["test,orphan", "arm,primecell"])
+            .map(|device| device.name())
+            .collect::<Vec<_>>(),
+        ["serial@9000000"]
+    );
+}
+
+#[test]
+fn interrupts_extended() {
+    let dt = DeviceTree::new(DTB).unwrap();
+    let extended = dt.find_compatible(&["test,extended"]).next().unwrap();
+    assert_eq!(
+        extended.interrupts(),
+        [
+            Interrupt {
+                parent: 1,
+                cells: vec![0, 2, 4],
+            },
+            Interrupt {
+                parent: 4,
+                cells: vec![7],
+            },
+        ]
+    );
+    assert!(!extended.has_unresolved_interrupts());
+}
+
+#[test]
+fn disabled_parent_disables_children() {
+    let dt = DeviceTree::new(DTB).unwrap();
+    let child = dt.find_path("/bus@e000000/child@e000000").unwrap();
+    assert!(child.property("status").is_none());
+    assert!(!child.is_enabled());
+    assert_eq!(
+        dt.find_enabled_compatible(&["test,on-disabled-bus"])
+            .count(),
+        0
+    );
+}
@@ -12,6 +12,15 @@ use dlmalloc::{Allocator as DlmallocAllocator, Dlmalloc};
 
 use sel4_sync::{GenericMutex, MutexSyncOps};
 
+#[cfg(feature = "profiling")]
+mod profiling;
+
+#[cfg(feature = "profiling")]
+pub use profiling::{
+    with_call_site, AllocMetrics, CallSite, ProfilingGlobalAlloc, SiteMetrics, NUM_SITES,
+    NUM_SIZE_BUCKETS,
+};
+
 // TODO alignment should depend on configuration
 // TODO does this alignment provide any benefit?
 // TODO use SyncUnsafeCell
@@ -0,0 +1,292 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// The number of buckets in [`AllocMetrics::size_histogram`]. Bucket `i` counts allocations
+/// whose size is in `[2^i, 2^(i+1))`, with zero-sized allocations counted in bucket `0`.
+pub const NUM_SIZE_BUCKETS: usize = usize::BITS as usize;
+
+/// The number of distinct call sites tracked in [`AllocMetrics::sites`]. Allocations from call
+/// sites beyond this limit are attributed to [`CallSite::UNKNOWN`].
+pub const NUM_SITES: usize = 64;
+
+/// Allocation metrics, laid out so that they can be placed in a page shared with a monitoring
+/// component.
+///
+/// An all-zero page is a valid, empty `AllocMetrics`.
+#[repr(C)]
+pub struct AllocMetrics {
+    /// Bytes allocated and not yet freed. Blocks which were allocated before recording began are
+    /// not counted, and neither is their deallocation, so this saturates at zero rather than
+    /// underflowing.
+    pub live_bytes: AtomicUsize,
+    pub peak_live_bytes: AtomicUsize,
+    pub num_allocs: AtomicUsize,
+    pub num_deallocs: AtomicUsize,
+    pub num_failed_allocs: AtomicUsize,
+    pub size_histogram: [AtomicUsize; NUM_SIZE_BUCKETS],
+    pub sites: [SiteMetrics; NUM_SITES],
+}
+
+const _: () = assert!(mem::size_of::<AllocMetrics>() <= 4096);
+
+/// Per-call-site allocation metrics.
+///
+/// Because a [`GlobalAlloc`] cannot tell which call site an allocation was made from by the time
+/// it is freed, these counters are cumulative rather than live.
+#[repr(C)]
+pub struct SiteMetrics {
+    /// The [`CallSite`] hash, or `0` if this slot is unused.
+    pub site: AtomicUsize,
+    pub num_allocs: AtomicUsize,
+    pub bytes_allocated: AtomicUsize,
+    pub largest_alloc: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicUsize = AtomicUsize::new(0);
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SITE: SiteMetrics = SiteMetrics {
+    site: ZERO,
+    num_allocs: ZERO,
+    bytes_allocated: ZERO,
+    largest_alloc: ZERO,
+};
+
+impl AllocMetrics {
+    pub const fn new() -> Self {
+        Self {
+            live_bytes: ZERO,
+            peak_live_bytes: ZERO,
+            num_allocs: ZERO,
+            num_deallocs: ZERO,
+            num_failed_allocs: ZERO,
+            size_histogram: [ZERO; NUM_SIZE_BUCKETS],
+            sites: [EMPTY_SITE; NUM_SITES],
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must be suitably aligned, point to `size_of::<AllocMetrics>()` zeroed or previously
+    /// initialized bytes, and remain valid for the rest of the program.
+    pub unsafe fn from_ptr(ptr: *mut AllocMetrics) -> &'static Self {
+        &*ptr
+    }
+
+    fn record_alloc(&self, site: CallSite, size: usize) {
+        self.num_allocs.fetch_add(1, Ordering::Relaxed);
+        let live = self.live_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_live_bytes.fetch_max(live, Ordering::Relaxed);
+        self.size_histogram[size_bucket(size)].fetch_add(1, Ordering::Relaxed);
+        if let Some(site_metrics) = self.site(site) {
+            site_metrics.num_allocs.fetch_add(1, Ordering::Relaxed);
+            site_metrics
+                .bytes_allocated
+                .fetch_add(size, Ordering::Relaxed);
+            site_metrics
+                .largest_alloc
+                .fetch_max(size, Ordering::Relaxed);
+        }
+    }
+
+    fn record_dealloc(&self, size: usize) {
+        self.num_deallocs.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .live_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+                Some(live.saturating_sub(size))
+            });
+    }
+
+    fn record_failure(&self) {
+        self.num_failed_allocs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Open addressing with linear probing. Slots are claimed, never released.
+    fn site(&self, site: CallSite) -> Option<&SiteMetrics> {
+        let key = site.0;
+        let start = key % NUM_SITES;
+        for i in 0..NUM_SITES {
+            let slot = &self.sites[(start + i) % NUM_SITES];
+            match slot
+                .site
+                .compare_exchange(0, key, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return Some(slot),
+                Err(existing) if existing == key => return Some(slot),
+                Err(_) => {}
+            }
+        }
+        None
+    }
+}
+
+impl Default for AllocMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn size_bucket(size: usize) -> usize {
+    (usize::BITS - 1).saturating_sub(size.leading_zeros()) as usize
+}
+
+// // //
+
+/// A hash identifying the source of an allocation. See [`call_site!`](crate::call_site) and
+/// [`with_call_site`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CallSite(usize);
+
+impl CallSite {
+    pub const UNKNOWN: Self = Self(1);
+
+    /// Hashes `file` and `line` with FNV-1a.
+    pub const fn new(file: &str, line: u32) -> Self {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+        let bytes = file.as_bytes();
+        let mut hash = OFFSET_BASIS;
+        let mut i = 0;
+        while i < bytes.len() {
+            hash = (hash ^ bytes[i] as u64).wrapping_mul(PRIME);
+            i += 1;
+        }
+        let line = line.to_le_bytes();
+        let mut i = 0;
+        while i < line.len() {
+            hash = (hash ^ line[i] as u64).wrapping_mul(PRIME);
+            i += 1;
+        }
+        Self::from_hash(hash as usize)
+    }
+
+    /// `0` is reserved for empty slots, so it is mapped to [`CallSite::UNKNOWN`].
+    pub const fn from_hash(hash: usize) -> Self {
+        if hash == 0 {
+            Self::UNKNOWN
+        } else {
+            Self(hash)
+        }
+    }
+
+    pub const fn hash(&self) -> usize {
+        self.0
+    }
+}
+
+/// Evaluates to the [`CallSite`] of its invocation.
+#[macro_export]
+macro_rules! call_site {
+    () => {
+        $crate::CallSite::new(::core::file!(), ::core::line!())
+    };
+}
+
+static CURRENT_CALL_SITE: AtomicUsize = AtomicUsize::new(CallSite::UNKNOWN.0);
+
+/// Attributes allocations made by any [`ProfilingGlobalAlloc`] during `f` to `site`.
+///
+/// The current call site is global rather than per-thread, which suits single-threaded
+/// components.
+pub fn with_call_site<R>(site: CallSite, f: impl FnOnce() -> R) -> R {
+    let prev = CURRENT_CALL_SITE.swap(site.0, Ordering::Relaxed);
+    let r = f();
+    CURRENT_CALL_SITE.store(prev, Ordering::Relaxed);
+    r
+}
+
+fn current_call_site() -> CallSite {
+    CallSite(CURRENT_CALL_SITE.load(Ordering::Relaxed))
+}
+
+// // //
+
+/// A [`GlobalAlloc`] which records allocation metrics for an inner allocator into an
+/// [`AllocMetrics`].
+///
+/// Until [`ProfilingGlobalAlloc::set_metrics`] is called, allocations are passed through without
+/// being recorded.
+pub struct ProfilingGlobalAlloc<A> {
+    inner: A,
+    metrics: AtomicPtr<AllocMetrics>,
+}
+
+impl<A> ProfilingGlobalAlloc<A> {
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            metrics: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    pub const fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Starts recording into `metrics`, which may be backed by a shared page (see
+    /// [`AllocMetrics::from_ptr`]).
+    ///
+    /// Blocks allocated before this call are not tracked, so freeing them only saturates
+    /// [`AllocMetrics::live_bytes`] at zero.
+    pub fn set_metrics(&self, metrics: &'static AllocMetrics) {
+        self.metrics
+            .store(metrics as *const AllocMetrics as *mut _, Ordering::Release);
+    }
+
+    pub fn metrics(&self) -> Option<&'static AllocMetrics> {
+        let ptr = self.metrics.load(Ordering::Acquire);
+        unsafe { ptr.as_ref() }
+    }
+
+    fn record_alloc_result(&self, ptr: *mut u8, size: usize) {
+        if let Some(metrics) = self.metrics() {
+            if ptr.is_null() {
+                metrics.record_failure();
+            } else {
+                metrics.record_alloc(current_call_site(), size);
+            }
+        }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for ProfilingGlobalAlloc<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        self.record_alloc_result(ptr, layout.size());
+        ptr
+    }
+
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        self.record_alloc_result(ptr, layout.size());
+        ptr
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        if let Some(metrics) = self.metrics() {
+            metrics.record_dealloc(layout.size());
+        }
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if let Some(metrics) = self.metrics() {
+            if new_ptr.is_null() {
+                metrics.record_failure();
+            } else {
+                metrics.record_dealloc(layout.size());
+                metrics.record_alloc(current_call_site(), new_size);
+            }
+        }
+        new_ptr
+    }
+}
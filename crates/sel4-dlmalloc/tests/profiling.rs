@@ -0,0 +1,29 @@
+#![cfg(feature = "profiling")]
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::Ordering;
+use std::alloc::System;
+
+use sel4_dlmalloc::{AllocMetrics, ProfilingGlobalAlloc};
+
+static METRICS: AllocMetrics = AllocMetrics::new();
+
+#[test]
+fn freeing_untracked_blocks_does_not_underflow() {
+    let alloc = ProfilingGlobalAlloc::new(System);
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let untracked = alloc.alloc(layout);
+        alloc.set_metrics(&METRICS);
+        let tracked = alloc.alloc(Layout::from_size_align(16, 8).unwrap());
+        alloc.dealloc(untracked, layout);
+        assert_eq!(METRICS.live_bytes.load(Ordering::Relaxed), 0);
+        assert_eq!(METRICS.peak_live_bytes.load(Ordering::Relaxed), 16);
+        let tracked = alloc.realloc(tracked, Layout::from_size_align(16, 8).unwrap(), 32);
+        assert_eq!(METRICS.live_bytes.load(Ordering::Relaxed), 32);
+        alloc.dealloc(tracked, Layout::from_size_align(32, 8).unwrap());
+        assert_eq!(METRICS.live_bytes.load(Ordering::Relaxed), 0);
+    }
+    assert_eq!(METRICS.num_allocs.load(Ordering::Relaxed), 2);
+    assert_eq!(METRICS.num_deallocs.load(Ordering::Relaxed), 3);
+}
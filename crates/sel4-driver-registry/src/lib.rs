@@ -0,0 +1,218 @@
+//! A registry of drivers, keyed by device tree `compatible` strings.
+//!
+//! Driver crates describe themselves with a [`Driver`], which pairs the `compatible` strings they
+//! support with a probe function. A system collects the drivers it includes into a
+//! [`DriverRegistry`], and then calls [`DriverRegistry::probe_all`] with a [`DeviceTree`] and an
+//! implementation of [`Platform`], which knows how to map MMIO regions and obtain IRQ handles in
+//! that system's environment. Each enabled device is bound to the first driver that supports the
+//! most specific of its `compatible` strings.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use sel4_device_tree::{Device, DeviceId, DeviceTree, Interrupt, Reg};
+
+/// Provides the resources that drivers need, in a particular environment.
+pub trait Platform {
+    /// A mapping of an MMIO region.
+    type Mmio;
+
+    /// A handle for receiving and acknowledging an interrupt.
+    type Irq;
+
+    type Error;
+
+    fn map_mmio(&mut self, device: &Device, reg: &Reg) -> Result<Self::Mmio, Self::Error>;
+
+    fn irq(&mut self, device: &Device, interrupt: &Interrupt) -> Result<Self::Irq, Self::Error>;
+}
+
+/// The resources passed to a driver's probe function.
+pub struct Probe<'b, 'a, P: Platform> {
+    pub tree: &'b DeviceTree<'a>,
+    pub device: &'b Device<'a>,
+    /// One mapping per entry of the device's `reg` property, in order.
+    pub mmio: Vec<P::Mmio>,
+    /// One handle per entry of [`Device::interrupts`], in order.
+    pub irqs: Vec<P::Irq>,
+}
+
+pub type ProbeFn<P, T> = fn(Probe<'_, '_, P>) -> Result<T, <P as Platform>::Error>;
+
+/// A description of a driver. `T` is the type of an instantiated driver, which will typically be
+/// an enum or trait object defined by the system.
+pub struct Driver<P: Platform, T> {
+    pub name: &'static str,
+    pub compatible: &'static [&'static str],
+    pub probe: ProbeFn<P, T>,
+}
+
+impl<P: Platform, T> Clone for Driver<P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            compatible: self.compatible,
+            probe: self.probe,
+        }
+    }
+}
+
+impl<P: Platform, T> fmt::Debug for Driver<P, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Driver")
+            .field("name", &self.name)
+            .field("compatible", &self.compatible)
+            .finish()
+    }
+}
+
+pub struct DriverRegistry<P: Platform, T> {
+    drivers: Vec<Driver<P, T>>,
+}
+
+impl<P: Platform, T> Default for DriverRegistry<P, T> {
+    fn default() -> Self {
+        Self {
+            drivers: Vec::new(),
+        }
+    }
+}
+
+impl<P: Platform, T> DriverRegistry<P, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drivers registered earlier take precedence over those registered later when both support
+    /// the same `compatible` string.
+    pub fn register(&mut self, driver: Driver<P, T>) -> &mut Self {
+        self.drivers.push(driver);
+        self
+    }
+
+    pub fn drivers(&self) -> &[Driver<P, T>] {
+        &self.drivers
+    }
+
+    /// Finds the driver for the most specific of `device`'s `compatible` strings.
+    pub fn find_driver(&self, device: &Device) -> Option<&Driver<P, T>> {
+        device.compatible().iter().find_map(|compatible| {
+            self.drivers
+                .iter()
+                .find(|driver| driver.compatible.iter().any(|c| c == compatible))
+        })
+    }
+
+    /// Maps the resources of `device` and passes them to the probe function of `driver`.
+    ///
+    /// Fails without mapping anything if `device` has interrupts which could not be resolved,
+    /// because the driver would otherwise be given fewer interrupts than the device has.
+    pub fn probe<'a>(
+        &self,
+        driver: &Driver<P, T>,
+        tree: &DeviceTree<'a>,
+        device: &Device<'a>,
+        platform: &mut P,
+    ) -> Result<T, ProbeError<P::Error>> {
+        if device.has_unresolved_interrupts() {
+            return Err(ProbeError::UnresolvedInterrupts);
+        }
+        self.probe_resolved(driver, tree, device, platform)
+            .map_err(ProbeError::Probe)
+    }
+
+    fn probe_resolved<'a>(
+        &self,
+        driver: &Driver<P, T>,
+        tree: &DeviceTree<'a>,
+        device: &Device<'a>,
+        platform: &mut P,
+    ) -> Result<T, P::Error> {
+        let mmio = device
+            .reg()
+            .iter()
+            .map(|reg| platform.map_mmio(device, reg))
+            .collect::<Result<_, _>>()?;
+        let irqs = device
+            .interrupts()
+            .iter()
+            .map(|interrupt| platform.irq(device, interrupt))
+            .collect::<Result<_, _>>()?;
+        (driver.probe)(Probe {
+            tree,
+            device,
+            mmio,
+            irqs,
+        })
+    }
+
+    /// Probes every enabled device in `tree` for which a driver is registered, in tree order.
+    ///
+    /// A failure to probe one device, including one with unresolved interrupts, does not prevent
+    /// others from being probed.
+    pub fn probe_all(&self, tree: &DeviceTree<'_>, platform: &mut P) -> ProbeResults<P, T> {
+        let mut results = ProbeResults {
+            bound: Vec::new(),
+            failed: Vec::new(),
+        };
+        for device in tree.devices().filter(|device| device.is_enabled()) {
+            if let Some(driver) = self.find_driver(device) {
+                match self.probe(driver, tree, device, platform) {
+                    Ok(instance) => results.bound.push(BoundDevice {
+                        device: device.id(),
+                        driver: driver.name,
+                        instance,
+                    }),
+                    Err(err) => results.failed.push(FailedProbe {
+                        device: device.id(),
+                        driver: driver.name,
+                        err,
+                    }),
+                }
+            }
+        }
+        results
+    }
+}
+
+pub struct ProbeResults<P: Platform, T> {
+    pub bound: Vec<BoundDevice<T>>,
+    pub failed: Vec<FailedProbe<ProbeError<P::Error>>>,
+}
+
+#[derive(Debug)]
+pub struct BoundDevice<T> {
+    pub device: DeviceId,
+    pub driver: &'static str,
+    pub instance: T,
+}
+
+#[derive(Debug)]
+pub struct FailedProbe<E> {
+    pub device: DeviceId,
+    pub driver: &'static str,
+    pub err: E,
+}
+
+/// Error type returned by [`DriverRegistry::probe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError<E> {
+    /// The device has interrupts which could not be resolved. See
+    /// [`Device::has_unresolved_interrupts`].
+    UnresolvedInterrupts,
+    /// The platform failed to provide a resource, or the driver's probe function failed.
+    Probe(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ProbeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnresolvedInterrupts => write!(f, "device has unresolved interrupts"),
+            Self::Probe(err) => err.fmt(f),
+        }
+    }
+}
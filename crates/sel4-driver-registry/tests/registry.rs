@@ -0,0 +1,148 @@
+use sel4_device_tree::{Device, DeviceTree, Interrupt, Reg};
+use sel4_driver_registry::{Driver, DriverRegistry, Platform, Probe, ProbeError};
+
+// Shared with sel4-device-tree, whose tests describe its contents.
+const DTB: &[u8] = include_bytes!("../../sel4-device-tree/tests/fixtures/test.dtb");
+
+// Hands out physical regions and GIC interrupt IDs in place of mappings and handles.
+#[derive(Default)]
+struct TestPlatform {
+    num_mapped: usize,
+}
+
+impl Platform for TestPlatform {
+    type Mmio = Reg;
+    type Irq = u32;
+    type Error = &'static str;
+
+    fn map_mmio(&mut self, _device: &Device, reg: &Reg) -> Result<Self::Mmio, Self::Error> {
+        self.num_mapped += 1;
+        Ok(*reg)
+    }
+
+    fn irq(&mut self, _device: &Device, interrupt: &Interrupt) -> Result<Self::Irq, Self::Error> {
+        interrupt.gic_irq().ok_or("not a GIC interrupt")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Instance {
+    mmio: Vec<Reg>,
+    irqs: Vec<u32>,
+}
+
+fn probe(probe: Probe<'_, '_, TestPlatform>) -> Result<Instance, &'static str> {
+    Ok(Instance {
+        mmio: probe.mmio,
+        irqs: probe.irqs,
+    })
+}
+
+fn driver(
+    name: &'static str,
+    compatible: &'static [&'static str],
+) -> Driver<TestPlatform, Instance> {
+    Driver {
+        name,
+        compatible,
+        probe,
+    }
+}
+
+#[test]
+fn most_specific_compatible_wins() {
+    let dt = DeviceTree::new(DTB).unwrap();
+    let serial = dt.find_path("/serial@9000000").unwrap();
+
+    let mut registry = DriverRegistry::new();
+    registry
+        .register(driver("primecell", &["arm,primecell"]))
+        .register(driver("pl011", &["arm,pl011"]))
+        .register(driver("pl011-fallback", &["arm,pl011"]));
+    assert_eq!(registry.find_driver(serial).unwrap().name, "pl011");
+
+    let mut registry = DriverRegistry::new();
+    registry.register(driver("primecell", &["arm,primecell"]));
+    assert_eq!(registry.find_driver(serial).unwrap().name, "primecell");
+
+    let timer = dt.find_path("/timer").unwrap();
+    assert!(registry.find_driver(timer).is_none());
+}
+
+#[test]
+fn probe_all_binds_enabled_devices() {
+    let dt = DeviceTree::new(DTB).unwrap();
+    let mut registry = DriverRegistry::new();
+    registry
+        .register(driver("pl011", &["arm,pl011"]))
+        .register(driver("timer", &["arm,armv8-timer"]))
+        .register(driver("orphan", &["test,orphan"]))
+        .register(driver("lost", &["test,lost"]))
+        .register(driver("bad-spi", &["test,bad-spi"]));
+    let mut platform = TestPlatform::default();
+
+    let results = registry.probe_all(&dt, &mut platform);
+
+    let bound = results
+        .bound
+        .iter()
+        .map(|bound| {
+            (
+                dt.device(bound.device).path(),
+                bound.driver,
+                &bound.instance,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bound,
+        [
+            (
+                "/serial@9000000",
+                "pl011",
+                &Instance {
+                    mmio: vec![Reg {
+                        addr: 0x900_0000,
+                        size: 0x1000,
+                    }],
+                    irqs: vec![33],
+                }
+            ),
+            (
+                "/timer",
+                "timer",
+                &Instance {
+                    mmio: vec![],
+                    irqs: vec![29, 30],
+                }
+            ),
+        ]
+    );
+
+    // The orphan is disabled, so it is not probed at all. Nothing of the lost device is mapped,
+    // because its interrupts cannot be resolved.
+    let failed = results
+        .failed
+        .iter()
+        .map(|failed| {
+            (
+                dt.device(failed.device).path(),
+                failed.driver,
+                failed.err.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        failed,
+        [
+            ("/lost@b000000", "lost", ProbeError::UnresolvedInterrupts),
+            (
+                "/bad-spi",
+                "bad-spi",
+                ProbeError::Probe("not a GIC interrupt")
+            ),
+        ]
+    );
+
+    assert_eq!(platform.num_mapped, 1);
+}
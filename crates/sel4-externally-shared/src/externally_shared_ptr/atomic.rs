@@ -1,10 +1,13 @@
-use core::sync::atomic;
+use core::ops::RangeBounds;
+use core::sync::atomic::{self, Ordering};
 
 use crate::{
     access::{Readable, Writable},
     ExternallySharedPtr,
 };
 
+use super::bits::{BitField, BitPrimitive};
+
 pub trait AtomicPrimitive {
     type Atomic;
 
@@ -61,3 +64,106 @@ impl<'a, T: AtomicPrimitive, A: Readable + Writable> ExternallySharedPtr<'a, T,
         f(unsafe { T::wrap_atomic(self.as_raw_ptr().as_ptr()) })
     }
 }
+
+pub trait AtomicBitPrimitive: AtomicPrimitive + BitPrimitive {
+    fn fetch_or(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+
+    fn fetch_update<F: FnMut(Self) -> Option<Self>>(
+        atomic: &Self::Atomic,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<Self, Self>;
+}
+
+macro_rules! atomic_bit_primitive_impl {
+    ($prim:path) => {
+        impl AtomicBitPrimitive for $prim {
+            fn fetch_or(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self {
+                atomic.fetch_or(value, order)
+            }
+
+            fn fetch_update<F: FnMut(Self) -> Option<Self>>(
+                atomic: &Self::Atomic,
+                set_order: Ordering,
+                fetch_order: Ordering,
+                f: F,
+            ) -> Result<Self, Self> {
+                atomic.fetch_update(set_order, fetch_order, f)
+            }
+        }
+    };
+}
+
+#[cfg(target_has_atomic = "8")]
+atomic_bit_primitive_impl!(u8);
+#[cfg(target_has_atomic = "16")]
+atomic_bit_primitive_impl!(u16);
+#[cfg(target_has_atomic = "32")]
+atomic_bit_primitive_impl!(u32);
+#[cfg(target_has_atomic = "64")]
+atomic_bit_primitive_impl!(u64);
+#[cfg(target_has_atomic = "usize")]
+atomic_bit_primitive_impl!(usize);
+
+impl<'a, T: AtomicBitPrimitive, A: Readable + Writable> ExternallySharedPtr<'a, T, A> {
+    /// Like [`set_bits`](Self::set_bits), but replaces the bits in `range` atomically with respect
+    /// to other atomic accesses, retrying if the value changes concurrently.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use sel4_externally_shared::ExternallySharedPtr;
+    /// use core::ptr::NonNull;
+    /// use core::sync::atomic::Ordering;
+    ///
+    /// let mut value: u32 = 0xffff_0000;
+    /// let shared = unsafe { ExternallySharedPtr::new((&mut value).into()) };
+    /// shared.atomic_set_bits(12..20, 0xab, Ordering::Relaxed);
+    ///
+    /// assert_eq!(shared.read(), 0xfffa_b000);
+    /// ```
+    pub fn atomic_set_bits(self, range: impl RangeBounds<u32>, value: T, order: Ordering) {
+        let field = BitField::new(range);
+        field.check_fits(value);
+        self.with_atomic(|atomic| {
+            T::fetch_update(atomic, order, load_order(order), |word| {
+                Some(field.set(word, value))
+            })
+        })
+        .unwrap_or_else(|_| unreachable!());
+    }
+
+    /// Like [`test_and_set_bit`](Self::test_and_set_bit), but sets the bit atomically with
+    /// respect to other atomic accesses, so that of several agents racing to set the same bit,
+    /// exactly one observes it to have been clear.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use sel4_externally_shared::ExternallySharedPtr;
+    /// use core::ptr::NonNull;
+    /// use core::sync::atomic::Ordering;
+    ///
+    /// let mut value: u64 = 0;
+    /// let shared = unsafe { ExternallySharedPtr::new((&mut value).into()) };
+    ///
+    /// assert!(!shared.atomic_test_and_set_bit(63, Ordering::AcqRel));
+    /// assert!(shared.atomic_test_and_set_bit(63, Ordering::AcqRel));
+    /// ```
+    pub fn atomic_test_and_set_bit(self, bit: u32, order: Ordering) -> bool {
+        let field = BitField::bit(bit);
+        let old = self.with_atomic(|atomic| T::fetch_or(atomic, field.mask(), order));
+        field.get(old) != T::ZERO
+    }
+}
+
+// The strongest ordering permitted for the load of a `fetch_update` whose store has ordering
+// `order`.
+fn load_order(order: Ordering) -> Ordering {
+    match order {
+        Ordering::Release | Ordering::Relaxed => Ordering::Relaxed,
+        Ordering::AcqRel | Ordering::Acquire => Ordering::Acquire,
+        _ => Ordering::SeqCst,
+    }
+}
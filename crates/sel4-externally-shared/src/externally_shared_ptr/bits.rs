@@ -0,0 +1,198 @@
+use core::ops::{BitAnd, BitOr, Bound, Not, RangeBounds, Shl, Shr};
+
+use crate::{
+    access::{Readable, Writable},
+    ExternallySharedPtr,
+};
+
+/// Unsigned integer types whose bits can be accessed with [`ExternallySharedPtr::get_bits`] and
+/// related methods.
+pub trait BitPrimitive:
+    Copy
+    + Eq
+    + Not<Output = Self>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    /// The width of the type in bits.
+    const BITS: u32;
+
+    /// The value with no bits set.
+    const ZERO: Self;
+
+    /// The value with all bits set.
+    const MAX: Self;
+}
+
+macro_rules! bit_primitive_impl {
+    ($prim:ty) => {
+        impl BitPrimitive for $prim {
+            const BITS: u32 = <$prim>::BITS;
+            const ZERO: Self = 0;
+            const MAX: Self = <$prim>::MAX;
+        }
+    };
+}
+
+bit_primitive_impl!(u8);
+bit_primitive_impl!(u16);
+bit_primitive_impl!(u32);
+bit_primitive_impl!(u64);
+bit_primitive_impl!(u128);
+bit_primitive_impl!(usize);
+
+/// A contiguous range of bits of a `T`.
+#[derive(Copy, Clone)]
+pub(crate) struct BitField<T> {
+    start: u32,
+    // The mask of the field, shifted down to bit 0.
+    unshifted_mask: T,
+}
+
+impl<T: BitPrimitive> BitField<T> {
+    /// Panics if `range` does not lie within `T`.
+    pub(crate) fn new(range: impl RangeBounds<u32>) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.checked_add(1).unwrap(),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => end.checked_add(1).unwrap(),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => T::BITS,
+        };
+        assert!(
+            start <= end && end <= T::BITS,
+            "bit range {start}..{end} out of bounds for a {}-bit value",
+            T::BITS
+        );
+        let width = end - start;
+        let unshifted_mask = if width == 0 {
+            T::ZERO
+        } else {
+            T::MAX >> (T::BITS - width)
+        };
+        Self {
+            start,
+            unshifted_mask,
+        }
+    }
+
+    pub(crate) fn bit(bit: u32) -> Self {
+        Self::new(bit..=bit)
+    }
+
+    pub(crate) fn mask(self) -> T {
+        self.unshifted_mask << self.start
+    }
+
+    pub(crate) fn get(self, word: T) -> T {
+        (word >> self.start) & self.unshifted_mask
+    }
+
+    /// Panics if `value` does not fit in the field.
+    pub(crate) fn set(self, word: T, value: T) -> T {
+        self.check_fits(value);
+        (word & !self.mask()) | (value << self.start)
+    }
+
+    pub(crate) fn check_fits(self, value: T) {
+        assert!(
+            value & !self.unshifted_mask == T::ZERO,
+            "value does not fit in bit field"
+        );
+    }
+}
+
+impl<'a, T, A> ExternallySharedPtr<'a, T, A>
+where
+    T: BitPrimitive,
+{
+    /// Reads the contained value and returns the bits in `range`, shifted down to bit 0.
+    ///
+    /// Panics if `range` extends beyond the width of `T`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use sel4_externally_shared::ExternallySharedPtr;
+    /// use core::ptr::NonNull;
+    ///
+    /// let value: u32 = 0x1234_5678;
+    /// let shared = unsafe { ExternallySharedPtr::new_read_only(NonNull::from(&value)) };
+    ///
+    /// assert_eq!(shared.get_bits(8..16), 0x56);
+    /// assert_eq!(shared.get_bits(28..), 0x1);
+    /// ```
+    pub fn get_bits(self, range: impl RangeBounds<u32>) -> T
+    where
+        A: Readable,
+    {
+        BitField::new(range).get(self.read())
+    }
+
+    /// Replaces the bits in `range` with `value`, leaving the others unchanged, by reading and
+    /// then writing the contained value.
+    ///
+    /// The read and the write are separate accesses. Where another agent may modify the value in
+    /// between, use `atomic_set_bits` (with the `unstable` feature) instead.
+    ///
+    /// Panics if `range` extends beyond the width of `T`, or if `value` does not fit in `range`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use sel4_externally_shared::ExternallySharedPtr;
+    /// use core::ptr::NonNull;
+    ///
+    /// let mut value: u32 = 0xffff_0000;
+    /// let shared = unsafe { ExternallySharedPtr::new((&mut value).into()) };
+    /// shared.set_bits(12..20, 0xab);
+    ///
+    /// assert_eq!(shared.read(), 0xfffa_b000);
+    /// ```
+    pub fn set_bits(self, range: impl RangeBounds<u32>, value: T)
+    where
+        A: Readable + Writable,
+    {
+        let field = BitField::new(range);
+        self.update(|word| field.set(word, value));
+    }
+
+    /// Sets bit `bit` of the contained value, returning whether it was already set. The value is
+    /// only written if the bit was clear.
+    ///
+    /// As with [`set_bits`](Self::set_bits), the read and the write are separate accesses. See
+    /// `atomic_test_and_set_bit` (with the `unstable` feature) for an atomic alternative.
+    ///
+    /// Panics if `bit` is not less than the width of `T`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use sel4_externally_shared::ExternallySharedPtr;
+    /// use core::ptr::NonNull;
+    ///
+    /// let mut value: u8 = 0b0001;
+    /// let shared = unsafe { ExternallySharedPtr::new((&mut value).into()) };
+    ///
+    /// assert!(shared.test_and_set_bit(0));
+    /// assert!(!shared.test_and_set_bit(2));
+    /// assert_eq!(shared.read(), 0b0101);
+    /// ```
+    pub fn test_and_set_bit(self, bit: u32) -> bool
+    where
+        A: Readable + Writable,
+    {
+        let field = BitField::bit(bit);
+        let word = self.read();
+        let was_set = field.get(word) != T::ZERO;
+        if !was_set {
+            self.write(word | field.mask());
+        }
+        was_set
+    }
+}
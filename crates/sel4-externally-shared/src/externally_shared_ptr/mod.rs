@@ -2,6 +2,7 @@ use core::{fmt, marker::PhantomData, ptr::NonNull};
 
 use crate::access::ReadWrite;
 
+mod bits;
 mod macros;
 mod operations;
 
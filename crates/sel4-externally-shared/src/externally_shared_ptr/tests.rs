@@ -29,6 +29,49 @@ fn test_update() {
     assert_eq!(val, 43);
 }
 
+#[test]
+fn test_bits() {
+    let mut val: u16 = 0xf00f;
+    let shared = unsafe { ExternallySharedPtr::new(NonNull::from(&mut val)) };
+    assert_eq!(shared.get_bits(12..), 0xf);
+    assert_eq!(shared.get_bits(4..=11), 0);
+    assert_eq!(shared.get_bits(0..0), 0);
+    shared.set_bits(4..12, 0xa5);
+    shared.set_bits(.., shared.read() ^ 0x0001);
+    assert_eq!(shared.read(), 0xfa5e);
+    assert!(!shared.test_and_set_bit(0));
+    assert!(shared.test_and_set_bit(15));
+    assert_eq!(val, 0xfa5f);
+}
+
+#[test]
+#[should_panic]
+fn test_set_bits_too_wide() {
+    let mut val: u8 = 0;
+    let shared = unsafe { ExternallySharedPtr::new(NonNull::from(&mut val)) };
+    shared.set_bits(0..4, 0x10);
+}
+
+#[test]
+#[should_panic]
+fn test_get_bits_out_of_bounds() {
+    let val: u8 = 0;
+    unsafe { ExternallySharedPtr::new_read_only(NonNull::from(&val)) }.get_bits(4..9);
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn test_atomic_bits() {
+    use core::sync::atomic::Ordering;
+
+    let mut val: u32 = 0;
+    let shared = unsafe { ExternallySharedPtr::new(NonNull::from(&mut val)) };
+    shared.atomic_set_bits(8..16, 0x7f, Ordering::Relaxed);
+    assert!(!shared.atomic_test_and_set_bit(31, Ordering::SeqCst));
+    assert!(shared.atomic_test_and_set_bit(31, Ordering::SeqCst));
+    assert_eq!(val, 0x8000_7f00);
+}
+
 #[test]
 fn test_access() {
     let mut val: i64 = 42;
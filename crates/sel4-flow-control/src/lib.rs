@@ -0,0 +1,213 @@
+//! Credit-based flow control between a producer and a consumer which share memory, such as the two
+//! sides of a `sel4-shared-ring-buffer`.
+//!
+//! The consumer grants the producer a fixed number of credits, each of which entitles the producer
+//! to send one unit, such as a descriptor or a byte. The producer spends credits as it sends, and
+//! the consumer returns them as it finishes with what was sent, notifying the producer (for example,
+//! over a Microkit channel) so that a producer which has run out can continue. This way, a
+//! high-rate producer waits for a slow consumer instead of overrunning it.
+//!
+//! The counters live in a [`RawCredits`] in shared memory. The consumer writes only the count of
+//! credits granted, and the producer writes only the count of credits spent, so each side can
+//! observe the other's progress without further synchronization.
+
+#![no_std]
+
+use core::fmt;
+use core::num::Wrapping;
+use core::sync::atomic::Ordering;
+
+use sel4_externally_shared::{map_field, ExternallySharedRef};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RawCredits {
+    granted: u32,
+    spent: u32,
+}
+
+/// The producer's side of a [`RawCredits`].
+pub struct CreditProducer<'a> {
+    inner: ExternallySharedRef<'a, RawCredits>,
+    starvation_threshold: Option<u32>,
+    failed_attempts: u32,
+}
+
+impl<'a> CreditProducer<'a> {
+    pub fn new(inner: ExternallySharedRef<'a, RawCredits>) -> Self {
+        Self {
+            inner,
+            starvation_threshold: None,
+            failed_attempts: 0,
+        }
+    }
+
+    /// Sets the number of consecutive failed attempts to spend credits after which
+    /// [`try_spend`](Self::try_spend) reports [`CreditError::Starved`] rather than
+    /// [`CreditError::Insufficient`].
+    pub fn with_starvation_threshold(mut self, threshold: u32) -> Self {
+        self.starvation_threshold = Some(threshold);
+        self
+    }
+
+    fn granted(&self) -> Wrapping<u32> {
+        let ptr = self.inner.as_ptr();
+        Wrapping(map_field!(ptr.granted).read())
+    }
+
+    fn spent(&self) -> Wrapping<u32> {
+        let ptr = self.inner.as_ptr();
+        Wrapping(map_field!(ptr.spent).read())
+    }
+
+    /// The number of credits which may currently be spent.
+    pub fn available(&self) -> u32 {
+        (self.granted() - self.spent()).0
+    }
+
+    /// Spends `n` credits, or none if fewer than `n` are available.
+    pub fn try_spend(&mut self, n: u32) -> Result<(), CreditError> {
+        let available = self.available();
+        if n <= available {
+            let ptr = self.inner.as_mut_ptr();
+            map_field!(ptr.spent).with_atomic(|x| x.fetch_add(n, Ordering::Release));
+            self.failed_attempts = 0;
+            return Ok(());
+        }
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        Err(if self.is_starved() {
+            CreditError::Starved {
+                failed_attempts: self.failed_attempts,
+            }
+        } else {
+            CreditError::Insufficient { available }
+        })
+    }
+
+    /// The number of consecutive failed attempts to spend credits since credits were last spent.
+    pub fn failed_attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    /// Whether the number of consecutive failed attempts to spend credits has reached the
+    /// threshold set with [`with_starvation_threshold`](Self::with_starvation_threshold).
+    pub fn is_starved(&self) -> bool {
+        self.starvation_threshold
+            .map(|threshold| self.failed_attempts >= threshold)
+            .unwrap_or(false)
+    }
+}
+
+/// The consumer's side of a [`RawCredits`].
+pub struct CreditConsumer<'a, F> {
+    inner: ExternallySharedRef<'a, RawCredits>,
+    notify: F,
+    capacity: u32,
+    batch_size: u32,
+    unreturned: u32,
+}
+
+impl<'a, F: FnMut()> CreditConsumer<'a, F> {
+    /// `capacity` is the total number of credits, which bounds the number of units in flight. If
+    /// `initialize` is set, the producer is granted all of them, so the producer must not be
+    /// running yet. `notify` is called whenever credits are returned.
+    pub fn new(
+        inner: ExternallySharedRef<'a, RawCredits>,
+        notify: F,
+        capacity: u32,
+        initialize: bool,
+    ) -> Self {
+        let mut this = Self {
+            inner,
+            notify,
+            capacity,
+            batch_size: 1,
+            unreturned: 0,
+        };
+        if initialize {
+            let ptr = this.inner.as_mut_ptr();
+            map_field!(ptr.spent).write(0);
+            map_field!(ptr.granted).write(capacity);
+        }
+        this
+    }
+
+    /// Sets the number of credits to accumulate before returning them, to save notifications.
+    /// Credits are returned without waiting for a full batch when the producer has run out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero or greater than the capacity.
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        assert!(batch_size > 0 && batch_size <= self.capacity);
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// The number of credits which the producer may currently spend.
+    pub fn available_to_producer(&self) -> u32 {
+        let ptr = self.inner.as_ptr();
+        let granted = Wrapping(map_field!(ptr.granted).read());
+        let spent = Wrapping(map_field!(ptr.spent).read());
+        (granted - spent).0
+    }
+
+    /// Whether the producer has spent all of the credits it has been granted, and so is waiting
+    /// for some to be returned.
+    pub fn producer_is_blocked(&self) -> bool {
+        self.available_to_producer() == 0
+    }
+
+    /// Returns the credits for `n` units which the consumer has finished with, possibly holding
+    /// them back to complete a batch.
+    pub fn replenish(&mut self, n: u32) {
+        self.unreturned += n;
+        if self.unreturned >= self.batch_size || self.producer_is_blocked() {
+            self.flush();
+        }
+    }
+
+    /// Returns any credits which are being held back to complete a batch.
+    pub fn flush(&mut self) {
+        if self.unreturned == 0 {
+            return;
+        }
+        let n = self.unreturned;
+        self.unreturned = 0;
+        {
+            let ptr = self.inner.as_mut_ptr();
+            map_field!(ptr.granted).with_atomic(|x| x.fetch_add(n, Ordering::Release));
+        }
+        (self.notify)();
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CreditError {
+    Insufficient {
+        available: u32,
+    },
+    /// The producer has failed to spend credits at least as many consecutive times as its
+    /// starvation threshold, which suggests that the consumer is stuck.
+    Starved {
+        failed_attempts: u32,
+    },
+}
+
+impl fmt::Display for CreditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Insufficient { available } => {
+                write!(f, "insufficient credits ({available} available)")
+            }
+            Self::Starved { failed_attempts } => write!(
+                f,
+                "starved of credits ({failed_attempts} consecutive failed attempts)"
+            ),
+        }
+    }
+}
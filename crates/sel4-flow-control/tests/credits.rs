@@ -0,0 +1,53 @@
+use core::cell::Cell;
+use core::ptr::NonNull;
+
+use sel4_externally_shared::ExternallySharedRef;
+use sel4_flow_control::{CreditConsumer, CreditError, CreditProducer, RawCredits};
+
+#[test]
+fn batched_replenishment() {
+    let mut raw = RawCredits::default();
+    let ptr = NonNull::from(&mut raw);
+    let notifications = Cell::new(0);
+    let mut consumer = CreditConsumer::new(
+        unsafe { ExternallySharedRef::new(ptr) },
+        || notifications.set(notifications.get() + 1),
+        4,
+        true,
+    )
+    .with_batch_size(2);
+    let mut producer =
+        CreditProducer::new(unsafe { ExternallySharedRef::new(ptr) }).with_starvation_threshold(2);
+
+    producer.try_spend(3).unwrap();
+    assert_eq!(
+        producer.try_spend(2),
+        Err(CreditError::Insufficient { available: 1 })
+    );
+
+    // Held back to complete a batch.
+    consumer.replenish(1);
+    assert_eq!(notifications.get(), 0);
+    assert_eq!(producer.available(), 1);
+
+    consumer.replenish(1);
+    assert_eq!(notifications.get(), 1);
+    assert_eq!(producer.available(), 3);
+
+    producer.try_spend(3).unwrap();
+    assert!(consumer.producer_is_blocked());
+    assert_eq!(
+        producer.try_spend(1),
+        Err(CreditError::Insufficient { available: 0 })
+    );
+    assert_eq!(
+        producer.try_spend(1),
+        Err(CreditError::Starved { failed_attempts: 2 })
+    );
+
+    // Returned at once, because the producer is blocked.
+    consumer.replenish(1);
+    assert_eq!(notifications.get(), 2);
+    producer.try_spend(1).unwrap();
+    assert!(!producer.is_starved());
+}
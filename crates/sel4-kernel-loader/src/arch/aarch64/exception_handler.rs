@@ -11,16 +11,19 @@ static mut exception_register_state: Registers = [0; NUM_REGISTERS];
 #[no_mangle]
 unsafe extern "C" fn exception_handler(vector_table_index: usize) {
     let mut esr;
+    let mut elr;
     let mut far;
     let mut tpidr_el1;
     {
         asm!("mrs {}, esr_el2", out(reg) esr);
+        asm!("mrs {}, elr_el2", out(reg) elr);
         asm!("mrs {}, far_el2", out(reg) far);
         asm!("mrs {}, tpidr_el1", out(reg) tpidr_el1);
     }
     let exception = Exception {
         vector_table_index,
         esr,
+        elr,
         far,
         tpidr_el1,
         registers: unsafe { exception_register_state },
@@ -38,6 +41,7 @@ type Registers = [u64; NUM_REGISTERS];
 struct Exception {
     vector_table_index: usize,
     esr: usize,
+    elr: usize,
     far: usize,
     tpidr_el1: usize,
     registers: Registers,
@@ -51,7 +55,8 @@ impl fmt::Display for Exception {
             show_vector_table_index(self.vector_table_index).unwrap_or("<corrupted>")
         )?;
         writeln!(f, "ESR: 0x{:016x}", self.esr)?;
-        writeln!(f, "FSR: 0x{:016x}", self.far)?;
+        writeln!(f, "ELR: 0x{:016x}", self.elr)?;
+        writeln!(f, "FAR: 0x{:016x}", self.far)?;
         writeln!(f, "TPIDR_EL1: 0x{:016x}", self.tpidr_el1)?;
         for (i, value) in self.registers.iter().enumerate() {
             writeln!(f, "X{i}: 0x{value:016x}")?;
@@ -0,0 +1,96 @@
+use core::arch::asm;
+use core::fmt;
+
+use crate::arch::{Arch, ArchImpl};
+use crate::fmt::debug_println_without_synchronization;
+
+#[used]
+#[no_mangle]
+static mut exception_register_state: Registers = [0; NUM_REGISTERS];
+
+#[no_mangle]
+unsafe extern "C" fn exception_handler() {
+    let mut scause;
+    let mut sepc;
+    let mut stval;
+    {
+        asm!("csrr {}, scause", out(reg) scause);
+        asm!("csrr {}, sepc", out(reg) sepc);
+        asm!("csrr {}, stval", out(reg) stval);
+    }
+    let exception = Exception {
+        scause,
+        sepc,
+        stval,
+        registers: unsafe { exception_register_state },
+    };
+    debug_println_without_synchronization!("!!! Exception:\n{}", exception);
+    ArchImpl::idle()
+}
+
+//
+
+const NUM_REGISTERS: usize = 32;
+
+type Registers = [usize; NUM_REGISTERS];
+
+struct Exception {
+    scause: usize,
+    sepc: usize,
+    stval: usize,
+    registers: Registers,
+}
+
+const WIDTH: usize = 2 + 2 * core::mem::size_of::<usize>();
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let interrupt = self.scause & (1 << (usize::BITS - 1)) != 0;
+        let code = self.scause & !(1 << (usize::BITS - 1));
+        writeln!(
+            f,
+            "Cause: {}",
+            if interrupt {
+                show_interrupt_code(code)
+            } else {
+                show_exception_code(code)
+            }
+            .unwrap_or("<unknown>")
+        )?;
+        writeln!(f, "SCAUSE: {:#0WIDTH$x}", self.scause)?;
+        writeln!(f, "SEPC: {:#0WIDTH$x}", self.sepc)?;
+        writeln!(f, "STVAL: {:#0WIDTH$x}", self.stval)?;
+        for (i, value) in self.registers.iter().enumerate().skip(1) {
+            writeln!(f, "X{i}: {value:#0WIDTH$x}")?;
+        }
+        Ok(())
+    }
+}
+
+fn show_interrupt_code(code: usize) -> Option<&'static str> {
+    match code {
+        1 => Some("Supervisor software interrupt"),
+        5 => Some("Supervisor timer interrupt"),
+        9 => Some("Supervisor external interrupt"),
+        _ => None,
+    }
+}
+
+fn show_exception_code(code: usize) -> Option<&'static str> {
+    match code {
+        0 => Some("Instruction address misaligned"),
+        1 => Some("Instruction access fault"),
+        2 => Some("Illegal instruction"),
+        3 => Some("Breakpoint"),
+        4 => Some("Load address misaligned"),
+        5 => Some("Load access fault"),
+        6 => Some("Store/AMO address misaligned"),
+        7 => Some("Store/AMO access fault"),
+        8 => Some("Environment call from U-mode"),
+        9 => Some("Environment call from S-mode"),
+        12 => Some("Instruction page fault"),
+        13 => Some("Load page fault"),
+        15 => Some("Store/AMO page fault"),
+        _ => None,
+    }
+}
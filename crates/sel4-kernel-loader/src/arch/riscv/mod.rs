@@ -10,6 +10,8 @@ use crate::{
     arch::Arch, main, secondary_main, this_image::page_tables::kernel::kernel_boot_level_0_table,
 };
 
+pub(crate) mod exception_handler;
+
 pub(crate) struct PerCoreImpl {
     hart_id: usize,
 }
@@ -0,0 +1,191 @@
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::str;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{Log, Metadata, Record, SetLoggerError};
+
+use crate::{DisplayWrapper, Logger, WriteWrapper};
+
+/// A [`Log`] implementation which, rather than writing each record as it is logged, appends it to
+/// a ring buffer of `N` bytes, from which [`drain`](DeferredLogger::drain) later writes it using
+/// the wrapped [`Logger`].
+///
+/// Logging never blocks, and takes time proportional only to the length of the record. A record
+/// which does not fit in the space remaining in the ring buffer is dropped, and the number of
+/// dropped records is reported by the next call to `drain`.
+///
+/// The ring buffer has a single producer and a single consumer: records may be logged from only
+/// one thread, and `drain` may be called from only one thread, at a time. A typical arrangement
+/// is for `drain` to be called whenever the thread which logs has nothing better to do.
+pub struct DeferredLogger<const N: usize> {
+    logger: Logger,
+    buf: UnsafeCell<[u8; N]>,
+    // Both indices increase monotonically, wrapping at `usize::MAX`, and are reduced modulo `N`
+    // to index into `buf`. The producer owns `tail` and the consumer owns `head`.
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    num_dropped: AtomicUsize,
+    // Owned by the consumer.
+    num_dropped_reported: AtomicUsize,
+}
+
+unsafe impl<const N: usize> Sync for DeferredLogger<N> {}
+
+impl<const N: usize> DeferredLogger<N> {
+    /// Records are filtered and formatted according to `logger`, and written by `logger.write`.
+    pub const fn new(logger: Logger) -> Self {
+        Self {
+            logger,
+            buf: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            num_dropped: AtomicUsize::new(0),
+            num_dropped_reported: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn set(&'static self) -> Result<(), SetLoggerError> {
+        self.logger.set_max_level();
+        log::set_logger(self)?;
+        Ok(())
+    }
+
+    /// The total number of records which have been dropped for lack of space.
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Whether there are records waiting to be written.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Relaxed) == self.tail.load(Ordering::Acquire)
+    }
+
+    /// Writes all records which have been logged so far, preceded by a line noting the number of
+    /// records dropped since the last call, if any, and then calls `logger.flush`.
+    pub fn drain(&self) {
+        let mut writer = WriteWrapper(self.logger.write);
+
+        let num_dropped = self.num_dropped.load(Ordering::Relaxed);
+        let num_dropped_reported = self.num_dropped_reported.load(Ordering::Relaxed);
+        let num_newly_dropped = num_dropped.wrapping_sub(num_dropped_reported);
+        if num_newly_dropped > 0 {
+            writeln!(writer, "[{num_newly_dropped} log records dropped]").unwrap();
+            self.num_dropped_reported
+                .store(num_dropped, Ordering::Relaxed);
+        }
+
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if head != tail {
+            let buf = self.buf.get().cast::<u8>();
+            let start = head % N;
+            let len = tail.wrapping_sub(head);
+            let (first, second) = unsafe {
+                if start + len <= N {
+                    (slice(buf, start, len), slice(buf, 0, 0))
+                } else {
+                    (
+                        slice(buf, start, N - start),
+                        slice(buf, 0, len - (N - start)),
+                    )
+                }
+            };
+            write_split_utf8(&mut writer, first, second);
+            self.head.store(tail, Ordering::Release);
+        }
+
+        self.logger.flush();
+    }
+}
+
+impl<const N: usize> Log for DeferredLogger<N> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let tail = self.tail.load(Ordering::Relaxed);
+            let mut writer = RecordWriter {
+                logger: self,
+                free: N - tail.wrapping_sub(self.head.load(Ordering::Acquire)),
+                tail,
+            };
+            let wrapped = DisplayWrapper {
+                fmt: self.logger.fmt,
+                record,
+            };
+            match writeln!(writer, "{wrapped}") {
+                Ok(()) => self.tail.store(writer.tail, Ordering::Release),
+                Err(_) => {
+                    self.num_dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Does nothing, as records are only written by [`drain`](DeferredLogger::drain).
+    fn flush(&self) {}
+}
+
+// Writes the bytes of a record into the ring buffer past the published tail, so that they only
+// become visible to the consumer if the whole record fits.
+struct RecordWriter<'a, const N: usize> {
+    logger: &'a DeferredLogger<N>,
+    free: usize,
+    tail: usize,
+}
+
+impl<'a, const N: usize> fmt::Write for RecordWriter<'a, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > self.free {
+            return Err(fmt::Error);
+        }
+        let buf = self.logger.buf.get().cast::<u8>();
+        for (i, b) in s.bytes().enumerate() {
+            unsafe {
+                buf.add(self.tail.wrapping_add(i) % N).write(b);
+            }
+        }
+        self.tail = self.tail.wrapping_add(s.len());
+        self.free -= s.len();
+        Ok(())
+    }
+}
+
+unsafe fn slice<'a>(buf: *const u8, start: usize, len: usize) -> &'a [u8] {
+    core::slice::from_raw_parts(buf.add(start), len)
+}
+
+// `first` followed by `second` is valid UTF-8, but the boundary between them may fall within a
+// character.
+fn write_split_utf8(writer: &mut impl Write, first: &[u8], second: &[u8]) {
+    let split = match str::from_utf8(first) {
+        Ok(_) => first.len(),
+        Err(err) => err.valid_up_to(),
+    };
+    let (first, straddling_start) = first.split_at(split);
+    let straddling_len = match straddling_start.first() {
+        Some(b) => utf8_char_width(*b),
+        None => 0,
+    };
+    let (straddling_end, second) = second.split_at(straddling_len - straddling_start.len());
+    let mut straddling = [0; 4];
+    straddling[..straddling_start.len()].copy_from_slice(straddling_start);
+    straddling[straddling_start.len()..straddling_len].copy_from_slice(straddling_end);
+    for part in [first, &straddling[..straddling_len], second] {
+        writer.write_str(str::from_utf8(part).unwrap()).unwrap();
+    }
+}
+
+fn utf8_char_width(first_byte: u8) -> usize {
+    match first_byte.leading_ones() {
+        0 => 1,
+        n => n.try_into().unwrap(),
+    }
+}
@@ -7,6 +7,10 @@ use log::{Log, Metadata, Record, SetLoggerError};
 
 pub use log::{self, LevelFilter};
 
+mod deferred;
+
+pub use deferred::DeferredLogger;
+
 pub struct Logger {
     pub level_filter: LevelFilter,
     pub filter: fn(&Metadata) -> bool,
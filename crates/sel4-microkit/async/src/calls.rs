@@ -0,0 +1,174 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::task::{Poll, Waker};
+
+use sel4_microkit::{
+    with_msg_regs, with_msg_regs_mut, Channel, MessageInfo, MessageLabel, ReservedLabel,
+};
+
+use crate::wake_all;
+
+/// Label of the reply to a protected procedure call whose response is not yet ready.
+///
+/// The caller is notified on the same channel once the response is ready, and then collects it
+/// with a call labeled [`COLLECT_LABEL`]. [`Runtime::call`](crate::Runtime::call) does this
+/// automatically.
+pub const DEFERRED_LABEL: MessageLabel = ReservedLabel::Deferred.label();
+
+/// Label of a protected procedure call which collects a deferred response. See
+/// [`DEFERRED_LABEL`].
+pub const COLLECT_LABEL: MessageLabel = ReservedLabel::Collect.label();
+
+/// The contents of a request or response, copied out of the IPC buffer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    pub label: MessageLabel,
+    pub regs: Vec<sel4_microkit::MessageRegisterValue>,
+}
+
+impl Message {
+    pub fn new(label: MessageLabel, regs: Vec<sel4_microkit::MessageRegisterValue>) -> Self {
+        Self { label, regs }
+    }
+
+    pub(crate) fn read(msg_info: &MessageInfo) -> Self {
+        Self {
+            label: msg_info.label(),
+            regs: with_msg_regs(|regs| regs[..msg_info.count()].to_vec()),
+        }
+    }
+
+    pub(crate) fn write(&self) -> MessageInfo {
+        with_msg_regs_mut(|regs| regs[..self.regs.len()].copy_from_slice(&self.regs));
+        MessageInfo::new(self.label, self.regs.len())
+    }
+}
+
+/// A protected procedure call, received with [`Runtime::next_call`](crate::Runtime::next_call).
+///
+/// If the task handling the call replies before the executor stalls, the reply is delivered
+/// directly. Otherwise, the caller receives [`DEFERRED_LABEL`] and collects the response later.
+/// Dropping a `Call` without replying replies with an empty message.
+pub struct Call {
+    channel: Channel,
+    request: Message,
+    slot: Option<Rc<ReplySlot>>,
+}
+
+impl Call {
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    pub fn request(&self) -> &Message {
+        &self.request
+    }
+
+    pub fn reply(mut self, response: Message) {
+        self.slot.take().unwrap().fill(self.channel, response)
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.fill(self.channel, Message::default())
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ReplySlot {
+    state: RefCell<ReplySlotState>,
+}
+
+#[derive(Default)]
+struct ReplySlotState {
+    response: Option<Message>,
+    deferred: bool,
+}
+
+impl ReplySlot {
+    fn fill(&self, channel: Channel, response: Message) {
+        let mut state = self.state.borrow_mut();
+        state.response = Some(response);
+        if state.deferred {
+            channel.notify();
+        }
+    }
+
+    fn take(&self) -> Option<Message> {
+        self.state.borrow_mut().response.take()
+    }
+
+    fn defer(&self) {
+        self.state.borrow_mut().deferred = true;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Calls {
+    incoming: VecDeque<Call>,
+    wakers: Vec<Waker>,
+    // At most one per channel, as a caller must collect a deferred response before its next call.
+    deferred: BTreeMap<Channel, Rc<ReplySlot>>,
+}
+
+impl Calls {
+    pub(crate) fn poll_next(&mut self, waker: &Waker) -> Poll<Call> {
+        match self.incoming.pop_front() {
+            Some(call) => Poll::Ready(call),
+            None => {
+                if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+                    self.wakers.push(waker.clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Handles the special labels, or else queues the call for dispatch and returns its reply
+    /// slot.
+    pub(crate) fn receive(
+        &mut self,
+        channel: Channel,
+        msg_info: &MessageInfo,
+    ) -> Result<Rc<ReplySlot>, MessageInfo> {
+        if msg_info.label() == COLLECT_LABEL {
+            return Err(match self.deferred.get(&channel).map(|slot| slot.take()) {
+                Some(Some(response)) => {
+                    self.deferred.remove(&channel);
+                    response.write()
+                }
+                Some(None) => MessageInfo::new(DEFERRED_LABEL, 0),
+                None => MessageInfo::default(),
+            });
+        }
+        // Not dispatched until the outstanding response has been collected.
+        if self.deferred.contains_key(&channel) {
+            return Err(MessageInfo::new(DEFERRED_LABEL, 0));
+        }
+        let slot = Rc::new(ReplySlot::default());
+        self.incoming.push_back(Call {
+            channel,
+            request: Message::read(msg_info),
+            slot: Some(slot.clone()),
+        });
+        wake_all(&mut self.wakers);
+        Ok(slot)
+    }
+
+    /// Called once the executor has stalled after [`receive`](Self::receive) returned `slot`.
+    pub(crate) fn complete(&mut self, channel: Channel, slot: Rc<ReplySlot>) -> MessageInfo {
+        match slot.take() {
+            Some(response) => response.write(),
+            None => {
+                slot.defer();
+                self.deferred.insert(channel, slot);
+                MessageInfo::new(DEFERRED_LABEL, 0)
+            }
+        }
+    }
+}
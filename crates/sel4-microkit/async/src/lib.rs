@@ -0,0 +1,277 @@
+//! Runs async tasks in a [seL4 Microkit](https://github.com/seL4/microkit) protection domain.
+//!
+//! [`AsyncHandler`] implements [`sel4_microkit::Handler`] by driving a
+//! [`LocalPool`](sel4_async_single_threaded_executor::LocalPool) after each event:
+//!
+//! - Notifications wake tasks waiting in [`Runtime::notified`].
+//! - Protected procedure calls are received by tasks via [`Runtime::next_call`]. A call which is
+//!   not answered by the time the executor stalls is answered later, using the protocol described
+//!   at [`DEFERRED_LABEL`].
+//! - If a [`TimerDriver`] is provided, the [`SharedTimers`] returned by [`Runtime::timers`] are
+//!   polled with its clock, and it is asked for a timeout whenever the executor stalls with timers
+//!   pending. Notifications on its channel are consumed by the runtime.
+
+#![no_std]
+#![feature(never_type)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::{Poll, Waker};
+use core::time::Duration;
+
+use futures::future::LocalBoxFuture;
+use smoltcp::time::Instant;
+
+use sel4_async_single_threaded_executor::{
+    Drive, EventSource, LocalPool, LocalSpawner, WakeSource,
+};
+use sel4_async_timers::SharedTimers;
+use sel4_microkit::{Channel, Handler, MessageInfo};
+
+mod calls;
+
+pub use calls::{Call, Message, COLLECT_LABEL, DEFERRED_LABEL};
+
+use calls::Calls;
+
+/// A monotonic clock and one-shot timeout, typically provided by a timer driver protection
+/// domain.
+pub trait TimerDriver {
+    /// The current time, in microseconds.
+    fn now(&mut self) -> u64;
+
+    /// Arranges for a notification on the timer driver channel after `delay`, replacing any
+    /// earlier timeout.
+    fn set_timeout(&mut self, delay: Duration);
+}
+
+/// A handle to the runtime, for use by tasks.
+#[derive(Clone)]
+pub struct Runtime {
+    shared: Rc<Shared>,
+    spawner: LocalSpawner,
+    timers: Option<SharedTimers>,
+}
+
+#[derive(Default)]
+struct Shared {
+    notifications: RefCell<BTreeMap<Channel, ChannelState>>,
+    calls: RefCell<Calls>,
+}
+
+#[derive(Default)]
+struct ChannelState {
+    pending: bool,
+    wakers: Vec<Waker>,
+}
+
+impl Runtime {
+    pub fn spawner(&self) -> &LocalSpawner {
+        &self.spawner
+    }
+
+    /// `None` if the runtime was built without a [`TimerDriver`].
+    pub fn timers(&self) -> Option<&SharedTimers> {
+        self.timers.as_ref()
+    }
+
+    /// Waits for a notification on `channel`.
+    ///
+    /// Notifications which arrive while no task is waiting are coalesced, as in a notification
+    /// object, and satisfy the next wait.
+    pub async fn notified(&self, channel: Channel) {
+        poll_fn(|cx| {
+            let mut notifications = self.shared.notifications.borrow_mut();
+            let state = notifications.entry(channel).or_default();
+            if state.pending {
+                state.pending = false;
+                Poll::Ready(())
+            } else {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Waits for the next protected procedure call, from any channel.
+    pub async fn next_call(&self) -> Call {
+        poll_fn(|cx| self.shared.calls.borrow_mut().poll_next(cx.waker())).await
+    }
+
+    /// Makes a protected procedure call, waiting for the response if the callee defers it.
+    pub async fn call(&self, channel: Channel, request: &Message) -> Message {
+        let mut msg_info = channel.pp_call(request.write());
+        while msg_info.label() == DEFERRED_LABEL {
+            self.notified(channel).await;
+            msg_info = channel.pp_call(MessageInfo::new(COLLECT_LABEL, 0));
+        }
+        Message::read(&msg_info)
+    }
+}
+
+/// A [`Handler`] which drives async tasks. See the [crate documentation](crate).
+pub struct AsyncHandler {
+    shared: Rc<Shared>,
+    local_pool: LocalPool,
+    fut: LocalBoxFuture<'static, !>,
+    timer: Option<Timer>,
+    event_sources: Vec<Box<dyn EventSource>>,
+}
+
+struct Timer {
+    channel: Channel,
+    driver: Box<dyn TimerDriver>,
+    shared_timers: SharedTimers,
+    // As of the most recent call to `poll`.
+    now: Instant,
+}
+
+fn read_clock(driver: &mut dyn TimerDriver) -> Instant {
+    Instant::from_micros(i64::try_from(driver.now()).unwrap())
+}
+
+impl EventSource for Timer {
+    fn poll(&mut self) -> bool {
+        self.now = read_clock(&mut *self.driver);
+        self.shared_timers.poll(self.now)
+    }
+
+    fn poll_delay(&mut self) -> Option<Duration> {
+        self.shared_timers
+            .poll_delay(self.now)
+            .map(|delay| Duration::from_micros(delay.total_micros()))
+    }
+
+    fn wake_source(&self) -> WakeSource {
+        WakeSource::Timer
+    }
+}
+
+impl AsyncHandler {
+    pub fn builder() -> AsyncHandlerBuilder {
+        AsyncHandlerBuilder {
+            timer: None,
+            event_sources: Vec::new(),
+        }
+    }
+
+    fn react(&mut self) {
+        let mut sources: Vec<&mut dyn EventSource> = Vec::new();
+        if let Some(timer) = &mut self.timer {
+            sources.push(timer);
+        }
+        for source in &mut self.event_sources {
+            sources.push(&mut **source);
+        }
+        match self.local_pool.drive(Pin::new(&mut self.fut), &mut sources) {
+            Drive::Complete(never) => never,
+            Drive::Idle { poll_delay } => {
+                if let (Some(timer), Some(delay)) = (&mut self.timer, poll_delay) {
+                    timer.driver.set_timeout(delay);
+                }
+            }
+        }
+    }
+}
+
+pub struct AsyncHandlerBuilder {
+    timer: Option<(Channel, Box<dyn TimerDriver>)>,
+    event_sources: Vec<Box<dyn EventSource>>,
+}
+
+impl AsyncHandlerBuilder {
+    pub fn timer(mut self, channel: Channel, driver: impl TimerDriver + 'static) -> Self {
+        self.timer = Some((channel, Box::new(driver)));
+        self
+    }
+
+    /// Adds an event source, such as a driver client, to be polled whenever the executor stalls.
+    pub fn event_source(mut self, source: impl EventSource + 'static) -> Self {
+        self.event_sources.push(Box::new(source));
+        self
+    }
+
+    /// Builds the handler, and runs the future returned by `f` until it first stalls.
+    pub fn build<T: Future<Output = !> + 'static>(
+        self,
+        f: impl FnOnce(Runtime) -> T,
+    ) -> AsyncHandler {
+        let shared = Rc::new(Shared::default());
+
+        let timer = self.timer.map(|(channel, mut driver)| {
+            let now = read_clock(&mut *driver);
+            Timer {
+                channel,
+                driver,
+                shared_timers: SharedTimers::new(now),
+                now,
+            }
+        });
+
+        let local_pool = LocalPool::new();
+
+        let fut = Box::pin(f(Runtime {
+            shared: shared.clone(),
+            spawner: local_pool.spawner(),
+            timers: timer.as_ref().map(|timer| timer.shared_timers.clone()),
+        }));
+
+        let mut this = AsyncHandler {
+            shared,
+            local_pool,
+            fut,
+            timer,
+            event_sources: self.event_sources,
+        };
+
+        this.react();
+
+        this
+    }
+}
+
+impl Handler for AsyncHandler {
+    type Error = !;
+
+    fn notified(&mut self, channel: Channel) -> Result<(), Self::Error> {
+        if self.timer.as_ref().map(|timer| timer.channel) != Some(channel) {
+            let mut notifications = self.shared.notifications.borrow_mut();
+            let state = notifications.entry(channel).or_default();
+            state.pending = true;
+            wake_all(&mut state.wakers);
+        }
+        self.react();
+        Ok(())
+    }
+
+    fn protected(
+        &mut self,
+        channel: Channel,
+        msg_info: MessageInfo,
+    ) -> Result<MessageInfo, Self::Error> {
+        let received = self.shared.calls.borrow_mut().receive(channel, &msg_info);
+        Ok(match received {
+            Ok(slot) => {
+                self.react();
+                self.shared.calls.borrow_mut().complete(channel, slot)
+            }
+            Err(reply) => reply,
+        })
+    }
+}
+
+fn wake_all(wakers: &mut Vec<Waker>) {
+    for waker in wakers.drain(..) {
+        waker.wake();
+    }
+}
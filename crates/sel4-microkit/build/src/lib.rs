@@ -0,0 +1,233 @@
+//! Generates a protection domain's linker script from its seL4 Microkit system description, for use
+//! in build scripts.
+//!
+//! The generated script lays out the protection domain's image at [`DEFAULT_IMAGE_BASE`], places
+//! the [configuration page](https://docs.rs/sel4-microkit/latest/sel4_microkit/config) on a page
+//! of its own in a read-only segment, places the main thread's stack in a segment of its own
+//! with unmapped guard pages below it, and defines absolute symbols
+//! `__sel4_microkit_mr_<name>_vaddr` and `__sel4_microkit_mr_<name>_size` for each memory region
+//! mapped into the protection domain, where `<name>` is the name of the memory region with
+//! characters other than ASCII alphanumerics replaced by `_`. Linking fails if the image, including
+//! the IPC buffer which follows it, overlaps any of those memory regions.
+//!
+//! # Examples
+//!
+//! In `build.rs`:
+//!
+//! ```ignore
+//! fn main() {
+//!     sel4_microkit_build::emit_linker_script("../system.xml", "server").unwrap();
+//! }
+//! ```
+
+use std::env;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use xmltree::{Element, XMLNode};
+
+/// The address at which images are linked by default, which matches `libmicrokit`.
+pub const DEFAULT_IMAGE_BASE: u64 = 0x200000;
+
+pub const PAGE_SIZE: u64 = 4096;
+
+/// Generates the linker script for protection domain `pd` of the system description at
+/// `system_path`, and tells Cargo to link with it and to rerun the build script if the system
+/// description changes.
+pub fn emit_linker_script(system_path: impl AsRef<Path>, pd: &str) -> Result<PathBuf> {
+    let system_path = system_path.as_ref();
+    println!("cargo:rerun-if-changed={}", system_path.display());
+    let xml = fs::read(system_path)
+        .with_context(|| format!("failed to read '{}'", system_path.display()))?;
+    LinkerScript::from_system_description(&xml, pd)?.emit()
+}
+
+/// A memory region mapped into a protection domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedRegion {
+    pub name: String,
+    pub vaddr: u64,
+    pub size: u64,
+}
+
+impl MappedRegion {
+    fn symbol_prefix(&self) -> String {
+        let name = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        format!("__sel4_microkit_mr_{name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkerScript {
+    pd: String,
+    image_base: u64,
+    stack_guard_pages: u64,
+    regions: Vec<MappedRegion>,
+}
+
+impl LinkerScript {
+    pub fn from_system_description(xml: &[u8], pd: &str) -> Result<Self> {
+        let root = Element::parse(xml)?;
+        let pd_elem =
+            find_pd(&root, pd).with_context(|| format!("no protection domain named '{pd}'"))?;
+        let regions = elements(pd_elem)
+            .filter(|child| child.name == "map")
+            .map(|map| {
+                let name = attr(map, "mr")?;
+                let mr = elements(&root)
+                    .find(|child| {
+                        child.name == "memory_region"
+                            && child.attributes.get("name").map(String::as_str) == Some(name)
+                    })
+                    .with_context(|| format!("no memory region named '{name}'"))?;
+                Ok(MappedRegion {
+                    name: name.to_owned(),
+                    vaddr: parse_int(attr(map, "vaddr")?)?,
+                    size: parse_int(attr(mr, "size")?)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            pd: pd.to_owned(),
+            image_base: DEFAULT_IMAGE_BASE,
+            stack_guard_pages: 1,
+            regions,
+        })
+    }
+
+    pub fn image_base(mut self, image_base: u64) -> Self {
+        assert_eq!(image_base % PAGE_SIZE, 0);
+        self.image_base = image_base;
+        self
+    }
+
+    /// The number of unmapped pages below the stack. Defaults to 1.
+    pub fn stack_guard_pages(mut self, stack_guard_pages: u64) -> Self {
+        self.stack_guard_pages = stack_guard_pages;
+        self
+    }
+
+    pub fn regions(&self) -> &[MappedRegion] {
+        &self.regions
+    }
+
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        self.write(&mut s).unwrap();
+        s
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> fmt::Result {
+        let base = self.image_base;
+        let guard = self.stack_guard_pages * PAGE_SIZE;
+
+        writeln!(
+            w,
+            "/* Generated by sel4-microkit-build for protection domain \"{}\". */",
+            self.pd
+        )?;
+        write!(
+            w,
+            r#"
+ENTRY(_start)
+
+PHDRS
+{{
+    text PT_LOAD FILEHDR PHDRS FLAGS(5);
+    rodata PT_LOAD FLAGS(4);
+    stack PT_LOAD FLAGS(6);
+    data PT_LOAD FLAGS(6);
+    tls PT_TLS;
+    eh_frame_hdr PT_GNU_EH_FRAME;
+}}
+
+SECTIONS
+{{
+    . = {base:#x} + SIZEOF_HEADERS;
+
+    .text : {{ *(.text .text.*) }} :text
+
+    .microkit_config : ALIGN({PAGE_SIZE:#x}) {{ *(.data.microkit_config) }} :rodata
+    .rodata : ALIGN({PAGE_SIZE:#x}) {{ *(.rodata .rodata.*) }} :rodata
+    .eh_frame_hdr : {{ *(.eh_frame_hdr) }} :rodata :eh_frame_hdr
+    .eh_frame : {{ KEEP(*(.eh_frame)) }} :rodata
+    .gcc_except_table : {{ *(.gcc_except_table .gcc_except_table.*) }} :rodata
+
+    . = ALIGN({PAGE_SIZE:#x}) + {guard:#x};
+
+    .stack (NOLOAD) : {{ *(.bss.__sel4_runtime_stack) . = ALIGN({PAGE_SIZE:#x}); }} :stack
+
+    .tdata : ALIGN({PAGE_SIZE:#x}) {{ *(.tdata .tdata.*) }} :data :tls
+    .tbss : {{ *(.tbss .tbss.*) }} :data :tls
+    .data : {{ *(.data .data.*) }} :data
+    .bss : {{ *(.bss .bss.*) *(COMMON) }} :data
+
+    _end = .;
+}}
+
+/* The IPC buffer occupies the page following the image. */
+__sel4_microkit_image_end = ALIGN(_end, {PAGE_SIZE:#x}) + {PAGE_SIZE:#x};
+"#
+        )?;
+        for region in &self.regions {
+            let prefix = region.symbol_prefix();
+            let end = region.vaddr + region.size;
+            writeln!(w)?;
+            writeln!(w, "{prefix}_vaddr = {:#x};", region.vaddr)?;
+            writeln!(w, "{prefix}_size = {:#x};", region.size)?;
+            writeln!(
+                w,
+                "ASSERT(__sel4_microkit_image_end <= {:#x} || {base:#x} >= {end:#x}, \"image overlaps memory region '{}'\");",
+                region.vaddr, region.name,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes this linker script to `$OUT_DIR`, and tells Cargo to link with it.
+    pub fn emit(&self) -> Result<PathBuf> {
+        let out_dir = env::var("OUT_DIR").context("OUT_DIR is not set")?;
+        let path = PathBuf::from(out_dir).join(format!("{}.ld", self.pd));
+        fs::write(&path, self.render())?;
+        println!("cargo:rustc-link-arg=-T{}", path.display());
+        Ok(path)
+    }
+}
+
+fn find_pd<'a>(elem: &'a Element, name: &str) -> Option<&'a Element> {
+    elements(elem)
+        .filter(|child| child.name == "protection_domain")
+        .find_map(|child| {
+            if child.attributes.get("name").map(String::as_str) == Some(name) {
+                Some(child)
+            } else {
+                find_pd(child, name)
+            }
+        })
+}
+
+fn elements(elem: &Element) -> impl Iterator<Item = &Element> {
+    elem.children.iter().filter_map(XMLNode::as_element)
+}
+
+fn attr<'a>(elem: &'a Element, name: &str) -> Result<&'a str> {
+    elem.attributes
+        .get(name)
+        .map(String::as_str)
+        .with_context(|| format!("<{}> is missing attribute '{name}'", elem.name))
+}
+
+fn parse_int(s: &str) -> Result<u64> {
+    let s = s.replace('_', "");
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .with_context(|| format!("invalid integer '{s}'"))
+}
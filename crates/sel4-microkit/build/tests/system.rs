@@ -0,0 +1,43 @@
+use sel4_microkit_build::{LinkerScript, MappedRegion};
+
+const SYSTEM: &str = r#"
+<system>
+    <memory_region name="shared-buf" size="0x2_000" />
+    <memory_region name="uart" size="0x1000" phys_addr="0x9000000" />
+    <protection_domain name="parent">
+        <protection_domain name="child" id="1">
+            <program_image path="child.elf" />
+            <map mr="shared-buf" vaddr="0x4000000" perms="rw" />
+            <map mr="uart" vaddr="0x5000000" perms="rw" cached="false" />
+        </protection_domain>
+    </protection_domain>
+</system>
+"#;
+
+#[test]
+fn nested_pd_regions() {
+    let script = LinkerScript::from_system_description(SYSTEM.as_bytes(), "child").unwrap();
+    assert_eq!(
+        script.regions(),
+        &[
+            MappedRegion {
+                name: "shared-buf".to_owned(),
+                vaddr: 0x4000000,
+                size: 0x2000,
+            },
+            MappedRegion {
+                name: "uart".to_owned(),
+                vaddr: 0x5000000,
+                size: 0x1000,
+            },
+        ]
+    );
+    let rendered = script.render();
+    assert!(rendered.contains("__sel4_microkit_mr_shared_buf_vaddr = 0x4000000;"));
+    assert!(rendered.contains("__sel4_microkit_mr_uart_size = 0x1000;"));
+}
+
+#[test]
+fn unknown_pd() {
+    assert!(LinkerScript::from_system_description(SYSTEM.as_bytes(), "orphan").is_err());
+}
@@ -0,0 +1,106 @@
+use std::fs;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::{App, Arg};
+use object::{Object, ObjectSection, ObjectSymbol};
+
+const SYMBOL: &str = "microkit_config";
+
+#[derive(Debug)]
+struct Args {
+    in_file_path: String,
+    config_path: String,
+    out_file_path: String,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let matches = App::new("")
+            .arg(
+                Arg::new("in_file")
+                    .short('i')
+                    .value_name("IN_FILE")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("config")
+                    .short('c')
+                    .value_name("CONFIG")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("out_file")
+                    .short('o')
+                    .value_name("OUT_FILE")
+                    .required(true),
+            )
+            .get_matches();
+
+        let in_file_path = matches.value_of("in_file").unwrap().to_owned();
+        let config_path = matches.value_of("config").unwrap().to_owned();
+        let out_file_path = matches.value_of("out_file").unwrap().to_owned();
+
+        Self {
+            in_file_path,
+            config_path,
+            out_file_path,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut elf = fs::read(&args.in_file_path)?;
+    let config = fs::read_to_string(&args.config_path)?;
+    validate(&config)?;
+    let (offset, size) = locate_symbol(&elf)?;
+    ensure!(
+        config.len() < size,
+        "configuration is {} bytes, but must be fewer than {} bytes",
+        config.len(),
+        size
+    );
+    let page = &mut elf[offset..][..size];
+    page.fill(0);
+    page[..config.len()].copy_from_slice(config.as_bytes());
+    fs::write(&args.out_file_path, elf)?;
+    Ok(())
+}
+
+// Mirrors the rules applied by sel4_microkit::config at runtime, so that mistakes surface at build
+// time.
+fn validate(config: &str) -> Result<()> {
+    ensure!(!config.contains('\0'), "configuration contains NUL byte");
+    for (i, line) in config.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.split_once('=').is_none() {
+            bail!("line {}: expected 'key = value', found {:?}", i + 1, line);
+        }
+    }
+    Ok(())
+}
+
+// Returns the file offset and size of the configuration page.
+fn locate_symbol(elf: &[u8]) -> Result<(usize, usize)> {
+    let file = object::File::parse(elf)?;
+    let symbol = file
+        .symbol_by_name(SYMBOL)
+        .with_context(|| format!("symbol '{SYMBOL}' not found"))?;
+    let section = file.section_by_index(
+        symbol
+            .section_index()
+            .with_context(|| format!("symbol '{SYMBOL}' is not defined in a section"))?,
+    )?;
+    let (section_offset, section_size) = section
+        .file_range()
+        .with_context(|| format!("section containing '{SYMBOL}' has no file data"))?;
+    let offset_in_section = symbol.address() - section.address();
+    ensure!(offset_in_section + symbol.size() <= section_size);
+    Ok((
+        (section_offset + offset_in_section).try_into()?,
+        symbol.size().try_into()?,
+    ))
+}
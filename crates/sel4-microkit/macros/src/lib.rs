@@ -3,7 +3,8 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::parse_macro_input;
 
-/// Declares the initialization function, stack size, and, optionally, heap and heap size.
+/// Declares the initialization function, stack size, and, optionally, heap and heap size and
+/// logger.
 ///
 /// The syntax is:
 ///
@@ -14,10 +15,12 @@ use syn::parse_macro_input;
 /// }
 /// ```
 ///
-/// Where the possible keys are:
+/// Where the possible keys, which must appear in this order, are:
 ///   - `stack_size`: Sets the stack size. Defaults to `0x4000`.
 ///   - `heap_size`: Declares a `#[global_allocator]` implemented using Dlmalloc and a
 ///     statically-allocated heap. Optional.
+///   - `log_level`: Installs a logger, before the initialization function runs, which writes
+///     records permitted by the given `log::LevelFilter` to the debug console. Optional.
 ///
 /// The function to which the attribute is applied will be used to initialize the protection domain.
 /// It must satisfy `FnOnce() -> T where T: Handler`.
@@ -26,7 +29,7 @@ use syn::parse_macro_input;
 /// are equivalent:
 ///
 /// ```rust
-/// #[protection_domain(stack_size = 0x12000, heap_size = 0x34000)]
+/// #[protection_domain(stack_size = 0x12000, heap_size = 0x34000, log_level = LevelFilter::Info)]
 /// fn init() -> impl Handler {
 ///     // ...
 /// }
@@ -37,6 +40,7 @@ use syn::parse_macro_input;
 ///     init = my_init,
 ///     stack_size = 0x12000,
 ///     heap_size = 0x34000,
+///     log_level = LevelFilter::Info,
 /// }
 ///
 /// fn init() -> impl Handler {
@@ -1,44 +1,71 @@
 #![no_std]
 #![feature(int_roundings)]
 #![feature(never_type)]
-#![feature(unwrap_infallible)]
+
+#[cfg(feature = "overflow")]
+extern crate alloc;
 
 use core::fmt;
 use core::mem;
 
-#[cfg(feature = "postcard")]
+#[cfg(feature = "cbor")]
+use minicbor::{Decode, Encode};
+
+#[cfg(any(feature = "json", feature = "postcard"))]
 use serde::{Deserialize, Serialize};
 
-use sel4_microkit::{with_msg_bytes, with_msg_bytes_mut, MessageInfo, MessageRegisterValue};
+use sel4_microkit::{
+    is_reserved_label, with_msg_bytes, with_msg_bytes_mut, MessageInfo, MessageRegisterValue,
+    ReservedLabel,
+};
 
 use sel4_microkit_message_types::{
     EmptyMessage, MessageLabel, MessageRecv, MessageSend, MessageValueRecv, MessageValueSend,
     TriviallyLabeled, TryFromDefaultMessageLabelError,
 };
 
+#[cfg(feature = "cbor")]
+use sel4_microkit_message_types::MessageValueUsingCbor;
+
+#[cfg(feature = "json")]
+use sel4_microkit_message_types::MessageValueUsingJson;
+
 #[cfg(feature = "postcard")]
 use sel4_microkit_message_types::MessageValueUsingPostcard;
 
 pub use sel4_microkit_message_types as types;
 
-pub const UNSPECIFIED_ERROR_LABEL: MessageLabel = (1 << MessageInfo::label_width()) - 1;
+#[cfg(feature = "postcard")]
+mod typed;
+
+#[cfg(feature = "postcard")]
+pub use typed::{CallError, ChannelExt, PostcardRecvError, PostcardSendError};
+
+#[cfg(feature = "overflow")]
+pub use typed::{OverflowRegion, OVERFLOW_LABEL};
+
+pub const UNSPECIFIED_ERROR_LABEL: MessageLabel = ReservedLabel::UnspecifiedError.label();
 
 pub trait MessageInfoExt: Sized {
-    fn send<T: MessageSend>(val: T) -> Result<Self, T::Error>;
+    /// Writes `val` to the message registers. Fails with [`MessageSendError::ReservedLabel`] if
+    /// the label of `val` is reserved (see [`sel4_microkit::RESERVED_LABELS_START`]).
+    fn send<T: MessageSend>(val: T) -> Result<Self, MessageSendErrorFor<T>>;
 
     fn recv<T: MessageRecv>(self) -> Result<T, MessageRecvErrorFor<T>>;
 
     fn send_unspecified_error() -> Self;
 
     fn send_empty() -> Self {
-        Self::send(EmptyMessage).into_ok()
+        Self::send(EmptyMessage).unwrap()
     }
 
     fn recv_empty(self) -> Result<(), MessageRecvErrorFor<EmptyMessage>> {
         self.recv().map(|EmptyMessage| ())
     }
 
-    fn send_with_trivial_label<T: MessageValueSend>(val: T) -> Result<Self, T::Error> {
+    fn send_with_trivial_label<T: MessageValueSend>(
+        val: T,
+    ) -> Result<Self, MessageSendError<T::Error>> {
         Self::send(TriviallyLabeled(val))
     }
 
@@ -51,7 +78,8 @@ pub trait MessageInfoExt: Sized {
     #[cfg(feature = "postcard")]
     fn send_using_postcard<T: Serialize>(
         val: T,
-    ) -> Result<Self, <MessageValueUsingPostcard<T> as MessageValueSend>::Error> {
+    ) -> Result<Self, MessageSendError<<MessageValueUsingPostcard<T> as MessageValueSend>::Error>>
+    {
         Self::send_with_trivial_label(MessageValueUsingPostcard(val))
     }
 
@@ -68,13 +96,58 @@ pub trait MessageInfoExt: Sized {
         self.recv_with_trivial_label()
             .map(|MessageValueUsingPostcard(val)| val)
     }
+
+    #[cfg(feature = "json")]
+    fn send_using_json<T: Serialize>(
+        val: T,
+    ) -> Result<Self, MessageSendError<<MessageValueUsingJson<T> as MessageValueSend>::Error>> {
+        Self::send_with_trivial_label(MessageValueUsingJson(val))
+    }
+
+    #[cfg(feature = "json")]
+    fn recv_using_json<T: for<'a> Deserialize<'a>>(
+        self,
+    ) -> Result<
+        T,
+        MessageRecvError<
+            TryFromDefaultMessageLabelError,
+            <MessageValueUsingJson<T> as MessageValueRecv>::Error,
+        >,
+    > {
+        self.recv_with_trivial_label()
+            .map(|MessageValueUsingJson(val)| val)
+    }
+
+    #[cfg(feature = "cbor")]
+    fn send_using_cbor<T: Encode<()>>(
+        val: T,
+    ) -> Result<Self, MessageSendError<<MessageValueUsingCbor<T> as MessageValueSend>::Error>> {
+        Self::send_with_trivial_label(MessageValueUsingCbor(val))
+    }
+
+    #[cfg(feature = "cbor")]
+    fn recv_using_cbor<T: for<'a> Decode<'a, ()>>(
+        self,
+    ) -> Result<
+        T,
+        MessageRecvError<
+            TryFromDefaultMessageLabelError,
+            <MessageValueUsingCbor<T> as MessageValueRecv>::Error,
+        >,
+    > {
+        self.recv_with_trivial_label()
+            .map(|MessageValueUsingCbor(val)| val)
+    }
 }
 
 impl MessageInfoExt for MessageInfo {
-    fn send<T: MessageSend>(val: T) -> Result<Self, T::Error> {
-        let (label, num_bytes) = with_msg_bytes_mut(|buf| val.write_message(buf))?;
+    fn send<T: MessageSend>(val: T) -> Result<Self, MessageSendErrorFor<T>> {
+        let (label, num_bytes) = with_msg_bytes_mut(|buf| val.write_message(buf))
+            .map_err(MessageSendError::ValueError)?;
         let label = label.into();
-        assert!(label < UNSPECIFIED_ERROR_LABEL); // TODO return error instead?
+        if is_reserved_label(label) {
+            return Err(MessageSendError::ReservedLabel(label));
+        }
         Ok(Self::new(label, bytes_to_mrs(num_bytes)))
     }
 
@@ -95,6 +168,23 @@ impl MessageInfoExt for MessageInfo {
     }
 }
 
+pub type MessageSendErrorFor<T> = MessageSendError<<T as MessageSend>::Error>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessageSendError<E> {
+    ReservedLabel(MessageLabel),
+    ValueError(E),
+}
+
+impl<E: fmt::Display> fmt::Display for MessageSendError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ReservedLabel(label) => write!(f, "label {:#x} is reserved", label),
+            Self::ValueError(err) => write!(f, "value error: {}", err),
+        }
+    }
+}
+
 pub type MessageRecvErrorFor<T> = MessageRecvError<
     <<T as MessageRecv>::Label as TryFrom<MessageLabel>>::Error,
     <T as MessageRecv>::Error,
@@ -0,0 +1,153 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use sel4_microkit::{Channel, MessageInfo};
+
+use sel4_microkit_message_types::TryFromDefaultMessageLabelError;
+
+#[cfg(feature = "overflow")]
+use sel4_externally_shared::ExternallySharedRef;
+
+#[cfg(feature = "overflow")]
+use sel4_microkit::{with_msg_regs, with_msg_regs_mut, ReservedLabel};
+
+#[cfg(feature = "overflow")]
+use sel4_microkit_message_types::MessageLabel;
+
+use crate::{MessageInfoExt, MessageRecvError, MessageSendError};
+
+/// The error returned when sending a value encoded with `postcard`.
+pub type PostcardSendError = MessageSendError<postcard::Error>;
+
+/// The error returned when receiving a value encoded with `postcard`.
+pub type PostcardRecvError = MessageRecvError<TryFromDefaultMessageLabelError, postcard::Error>;
+
+/// Typed protected procedure calls, with requests and responses encoded using `postcard`.
+pub trait ChannelExt {
+    /// Makes a protected procedure call whose request and response each fit in the message
+    /// registers.
+    ///
+    /// The callee receives the request with [`MessageInfoExt::recv_using_postcard`] and responds
+    /// with [`MessageInfoExt::send_using_postcard`].
+    fn pp_call_typed<Req: Serialize, Resp: for<'a> Deserialize<'a>>(
+        &self,
+        req: &Req,
+    ) -> Result<Resp, CallError>;
+
+    /// Like [`pp_call_typed`](Self::pp_call_typed), but with requests and responses which do not
+    /// fit in the message registers passed through `overflow`.
+    ///
+    /// The callee receives the request with [`OverflowRegion::recv`] and responds with
+    /// [`OverflowRegion::send`], using the same region.
+    #[cfg(feature = "overflow")]
+    fn pp_call_typed_with_overflow<Req: Serialize, Resp: for<'a> Deserialize<'a>>(
+        &self,
+        req: &Req,
+        overflow: &mut OverflowRegion,
+    ) -> Result<Resp, CallError>;
+}
+
+impl ChannelExt for Channel {
+    fn pp_call_typed<Req: Serialize, Resp: for<'a> Deserialize<'a>>(
+        &self,
+        req: &Req,
+    ) -> Result<Resp, CallError> {
+        let msg_info = MessageInfo::send_using_postcard(req).map_err(CallError::SendError)?;
+        self.pp_call(msg_info)
+            .recv_using_postcard()
+            .map_err(CallError::RecvError)
+    }
+
+    #[cfg(feature = "overflow")]
+    fn pp_call_typed_with_overflow<Req: Serialize, Resp: for<'a> Deserialize<'a>>(
+        &self,
+        req: &Req,
+        overflow: &mut OverflowRegion,
+    ) -> Result<Resp, CallError> {
+        let msg_info = overflow.send(req).map_err(CallError::SendError)?;
+        overflow
+            .recv(self.pp_call(msg_info))
+            .map_err(CallError::RecvError)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    SendError(PostcardSendError),
+    RecvError(PostcardRecvError),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SendError(err) => write!(f, "send error: {}", err),
+            Self::RecvError(err) => write!(f, "recv error: {}", err),
+        }
+    }
+}
+
+/// Label of a message whose `postcard` encoding is in an [`OverflowRegion`] rather than in the
+/// message registers. The first message register holds the length of the encoding.
+#[cfg(feature = "overflow")]
+pub const OVERFLOW_LABEL: MessageLabel = ReservedLabel::Overflow.label();
+
+/// A memory region shared by the two ends of a channel, through which values encoded with
+/// `postcard` are passed when they do not fit in the message registers.
+///
+/// Because a protected procedure call is synchronous, one region serves both the request and the
+/// response. Values which do fit in the message registers are sent as by
+/// [`MessageInfoExt::send_using_postcard`], so either end may use the region only when it is
+/// needed.
+#[cfg(feature = "overflow")]
+pub struct OverflowRegion {
+    inner: ExternallySharedRef<'static, [u8]>,
+}
+
+#[cfg(feature = "overflow")]
+impl OverflowRegion {
+    pub fn new(inner: ExternallySharedRef<'static, [u8]>) -> Self {
+        Self { inner }
+    }
+
+    /// Writes `val` to the message registers, or, if it does not fit, to this region.
+    pub fn send<T: Serialize>(&mut self, val: &T) -> Result<MessageInfo, PostcardSendError> {
+        match MessageInfo::send_using_postcard(val) {
+            Err(MessageSendError::ValueError(postcard::Error::SerializeBufferFull)) => {}
+            r => return r,
+        }
+        let buf = postcard::to_allocvec(val).map_err(MessageSendError::ValueError)?;
+        if buf.len() > self.inner.as_ptr().len() {
+            return Err(MessageSendError::ValueError(
+                postcard::Error::SerializeBufferFull,
+            ));
+        }
+        self.inner
+            .as_mut_ptr()
+            .index(..buf.len())
+            .copy_from_slice(&buf);
+        with_msg_regs_mut(|regs| regs[0] = buf.len().try_into().unwrap());
+        Ok(MessageInfo::new(OVERFLOW_LABEL, 1))
+    }
+
+    /// Reads a value sent with [`send`](Self::send), from the message registers or from this
+    /// region.
+    pub fn recv<T: for<'a> Deserialize<'a>>(
+        &self,
+        msg_info: MessageInfo,
+    ) -> Result<T, PostcardRecvError> {
+        if msg_info.label() != OVERFLOW_LABEL {
+            return msg_info.recv_using_postcard();
+        }
+        let len = match msg_info.count() {
+            0 => None,
+            _ => with_msg_regs(|regs| usize::try_from(regs[0]).ok()),
+        }
+        .filter(|len| *len <= self.inner.as_ptr().len())
+        .ok_or(MessageRecvError::ValueError(
+            postcard::Error::DeserializeUnexpectedEnd,
+        ))?;
+        let buf = self.inner.as_ptr().index(..len).copy_to_vec();
+        postcard::from_bytes(&buf).map_err(MessageRecvError::ValueError)
+    }
+}
@@ -7,9 +7,21 @@ use core::mem;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use zerocopy::{AsBytes, FromBytes, Unalign};
 
+#[cfg(feature = "cbor")]
+mod when_cbor;
+
+#[cfg(feature = "json")]
+mod when_json;
+
 #[cfg(feature = "postcard")]
 mod when_postcard;
 
+#[cfg(feature = "cbor")]
+pub use when_cbor::MessageValueUsingCbor;
+
+#[cfg(feature = "json")]
+pub use when_json::MessageValueUsingJson;
+
 #[cfg(feature = "postcard")]
 pub use when_postcard::MessageValueUsingPostcard;
 
@@ -0,0 +1,31 @@
+use minicbor::encode::write::EndOfSlice;
+use minicbor::{Decode, Encode};
+
+use crate::{MessageValueRecv, MessageValueSend};
+
+/// Encodes a value as CBOR, using `minicbor`.
+///
+/// Types whose fields are encoded by index into a map (`#[cbor(map)]`) remain compatible when
+/// `Option` fields are added to or removed from either party's definition, so that independently
+/// updated protection domains can continue to communicate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MessageValueUsingCbor<T>(pub T);
+
+impl<T: Encode<()>> MessageValueSend for MessageValueUsingCbor<T> {
+    type Error = minicbor::encode::Error<EndOfSlice>;
+
+    fn write_message_value(self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let capacity = buf.len();
+        let mut remaining = buf;
+        minicbor::encode(&self.0, &mut remaining)?;
+        Ok(capacity - remaining.len())
+    }
+}
+
+impl<T: for<'a> Decode<'a, ()>> MessageValueRecv for MessageValueUsingCbor<T> {
+    type Error = minicbor::decode::Error;
+
+    fn read_message_value(buf: &[u8]) -> Result<Self, Self::Error> {
+        minicbor::decode(buf).map(MessageValueUsingCbor)
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{MessageValueRecv, MessageValueSend};
+
+/// Encodes a value as JSON, which is larger than postcard but tolerates fields being added to or
+/// reordered in either party's definition of `T`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MessageValueUsingJson<T>(pub T);
+
+impl<T: Serialize> MessageValueSend for MessageValueUsingJson<T> {
+    type Error = serde_json_core::ser::Error;
+
+    fn write_message_value(self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        serde_json_core::to_slice(&self.0, buf)
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> MessageValueRecv for MessageValueUsingJson<T> {
+    type Error = serde_json_core::de::Error;
+
+    fn read_message_value(buf: &[u8]) -> Result<Self, Self::Error> {
+        // Message registers are transferred in whole words, so the value may be followed by
+        // padding, which must not be checked for trailing characters.
+        let mut deserializer = serde_json_core::de::Deserializer::new(buf, None);
+        T::deserialize(&mut deserializer).map(MessageValueUsingJson)
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::{App, Arg};
+use xmltree::{Element, XMLNode};
+
+// Matches the default applied by the microkit tool.
+const DEFAULT_PRIORITY: u64 = 0;
+
+const SHUTDOWN_ORDER_KEY: &str = "shutdown_order";
+
+#[derive(Debug)]
+struct Args {
+    system_path: String,
+    supervisor: Option<String>,
+    out_file_path: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let matches = App::new("")
+            .arg(
+                Arg::new("system")
+                    .short('s')
+                    .value_name("SYSTEM")
+                    .required(true),
+            )
+            .arg(Arg::new("supervisor").long("supervisor").value_name("PD"))
+            .arg(Arg::new("out_file").short('o').value_name("OUT_FILE"))
+            .get_matches();
+
+        let system_path = matches.value_of("system").unwrap().to_owned();
+        let supervisor = matches.value_of("supervisor").map(ToOwned::to_owned);
+        let out_file_path = matches.value_of("out_file").map(ToOwned::to_owned);
+
+        Self {
+            system_path,
+            supervisor,
+            out_file_path,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let system = System::parse(&fs::read(&args.system_path)?)?;
+    if let Some(supervisor) = &args.supervisor {
+        ensure!(
+            system.pds.contains_key(supervisor),
+            "no protection domain named '{supervisor}'"
+        );
+    }
+    let excluded = args.supervisor.iter().cloned().collect();
+    let order = system.shutdown_order(&excluded)?;
+    let out = match &args.supervisor {
+        None => order.iter().map(|pd| [pd, "\n"].concat()).collect(),
+        Some(supervisor) => {
+            let channels = order
+                .iter()
+                .map(|pd| system.supervisor_channel(supervisor, pd))
+                .collect::<Result<Vec<_>>>()?;
+            format!(
+                "{SHUTDOWN_ORDER_KEY} = {}\n",
+                channels
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    };
+    match &args.out_file_path {
+        Some(path) => fs::write(path, out)?,
+        None => print!("{out}"),
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct System {
+    pds: BTreeMap<String, Pd>,
+    channels: Vec<[ChannelEnd; 2]>,
+    device_memory_regions: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct Pd {
+    priority: u64,
+    pp: bool,
+    has_irqs: bool,
+    maps: BTreeSet<String>,
+    parent: Option<String>,
+}
+
+#[derive(Debug)]
+struct ChannelEnd {
+    pd: String,
+    id: u64,
+}
+
+impl System {
+    fn parse(xml: &[u8]) -> Result<Self> {
+        let root = Element::parse(xml)?;
+        let mut this = Self {
+            pds: BTreeMap::new(),
+            channels: vec![],
+            device_memory_regions: BTreeSet::new(),
+        };
+        for child in elements(&root) {
+            match child.name.as_str() {
+                "memory_region" => {
+                    if child.attributes.contains_key("phys_addr") {
+                        this.device_memory_regions
+                            .insert(attr(child, "name")?.to_owned());
+                    }
+                }
+                "protection_domain" => this.add_pd(child, None)?,
+                "channel" => {
+                    let ends = elements(child)
+                        .filter(|end| end.name == "end")
+                        .map(|end| {
+                            Ok(ChannelEnd {
+                                pd: attr(end, "pd")?.to_owned(),
+                                id: parse_int(attr(end, "id")?)?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    this.channels.push(
+                        ends.try_into()
+                            .ok()
+                            .context("channel must have exactly two ends")?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        for [a, b] in &this.channels {
+            for end in [a, b] {
+                ensure!(
+                    this.pds.contains_key(&end.pd),
+                    "channel refers to unknown protection domain '{}'",
+                    end.pd
+                );
+            }
+        }
+        Ok(this)
+    }
+
+    fn add_pd(&mut self, elem: &Element, parent: Option<&str>) -> Result<()> {
+        let name = attr(elem, "name")?;
+        let pd = Pd {
+            priority: elem
+                .attributes
+                .get("priority")
+                .map(|v| parse_int(v))
+                .transpose()?
+                .unwrap_or(DEFAULT_PRIORITY),
+            pp: elem.attributes.get("pp").map(String::as_str) == Some("true"),
+            has_irqs: elements(elem).any(|child| child.name == "irq"),
+            maps: elements(elem)
+                .filter(|child| child.name == "map")
+                .map(|child| Ok(attr(child, "mr")?.to_owned()))
+                .collect::<Result<_>>()?,
+            parent: parent.map(ToOwned::to_owned),
+        };
+        ensure!(
+            self.pds.insert(name.to_owned(), pd).is_none(),
+            "duplicate protection domain '{name}'"
+        );
+        for child in elements(elem).filter(|child| child.name == "protection_domain") {
+            self.add_pd(child, Some(name))?;
+        }
+        Ok(())
+    }
+
+    // Drivers handle interrupts or access device memory directly.
+    fn is_driver(&self, pd: &str) -> bool {
+        let pd = &self.pds[pd];
+        pd.has_irqs || !pd.maps.is_disjoint(&self.device_memory_regions)
+    }
+
+    // Of two protection domains which communicate, returns the one which serves the other, if that
+    // can be determined. Protected procedure calls flow towards the server, which the microkit
+    // tool requires to have a higher priority than its clients.
+    fn server<'a>(&self, a: &'a str, b: &'a str) -> Option<&'a str> {
+        let (pa, pb) = (&self.pds[a], &self.pds[b]);
+        if pa.pp != pb.pp {
+            return Some(if pa.pp { a } else { b });
+        }
+        if pa.priority != pb.priority {
+            return Some(if pa.priority > pb.priority { a } else { b });
+        }
+        match (self.is_driver(a), self.is_driver(b)) {
+            (true, false) => Some(a),
+            (false, true) => Some(b),
+            _ => None,
+        }
+    }
+
+    // Maps each protection domain to the set of protection domains which depend on it.
+    fn dependents(&self, excluded: &BTreeSet<String>) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut dependents: BTreeMap<&str, BTreeSet<&str>> = self
+            .pds
+            .keys()
+            .filter(|pd| !excluded.contains(*pd))
+            .map(|pd| (pd.as_str(), BTreeSet::new()))
+            .collect();
+        let mut add = |client: &str, server: &str| {
+            if client != server && !excluded.contains(client) && !excluded.contains(server) {
+                let client = self.pds.get_key_value(client).unwrap().0.as_str();
+                dependents.get_mut(server).unwrap().insert(client);
+            }
+        };
+        for [a, b] in &self.channels {
+            if let Some(server) = self.server(&a.pd, &b.pd) {
+                let client = if server == a.pd { &b.pd } else { &a.pd };
+                add(client, server);
+            }
+        }
+        // Shared memory other than device memory is a channel in all but name.
+        let names = self.pds.keys().collect::<Vec<_>>();
+        for (i, a) in names.iter().enumerate() {
+            for b in &names[i + 1..] {
+                let shared = self.pds[*a]
+                    .maps
+                    .intersection(&self.pds[*b].maps)
+                    .any(|mr| !self.device_memory_regions.contains(mr));
+                if shared {
+                    if let Some(server) = self.server(a, b) {
+                        let client = if server == a.as_str() { b } else { a };
+                        add(client, server);
+                    }
+                }
+            }
+        }
+        // Children are managed by their parents.
+        for (name, pd) in &self.pds {
+            if let Some(parent) = &pd.parent {
+                add(name, parent);
+            }
+        }
+        dependents
+    }
+
+    // Returns protection domains in an order in which each comes after all of those which depend
+    // on it. Among those which are free to go next, clients go before drivers, and lower priorities
+    // go before higher ones.
+    fn shutdown_order(&self, excluded: &BTreeSet<String>) -> Result<Vec<&str>> {
+        let mut remaining = self.dependents(excluded);
+        let mut order = vec![];
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .filter(|(_, dependents)| dependents.is_empty())
+                .map(|(pd, _)| *pd)
+                .min_by_key(|pd| (self.is_driver(pd), self.pds[*pd].priority, *pd));
+            let Some(next) = next else {
+                bail!(
+                    "dependency cycle among protection domains: {}",
+                    remaining.keys().copied().collect::<Vec<_>>().join(", ")
+                );
+            };
+            remaining.remove(next);
+            for dependents in remaining.values_mut() {
+                dependents.remove(next);
+            }
+            order.push(next);
+        }
+        Ok(order)
+    }
+
+    // Returns the supervisor's id for its channel with `pd`, after checking that the supervisor can
+    // make protected procedure calls to `pd`.
+    fn supervisor_channel(&self, supervisor: &str, pd: &str) -> Result<u64> {
+        ensure!(
+            self.pds[pd].pp,
+            "protection domain '{pd}' must have pp=\"true\" to accept protected procedure calls \
+             from supervisor '{supervisor}'"
+        );
+        let (supervisor_priority, pd_priority) =
+            (self.pds[supervisor].priority, self.pds[pd].priority);
+        ensure!(
+            supervisor_priority < pd_priority,
+            "supervisor '{supervisor}' (priority {supervisor_priority}) must have a lower priority \
+             than protection domain '{pd}' (priority {pd_priority})"
+        );
+        self.channels
+            .iter()
+            .find_map(|[a, b]| {
+                if a.pd == supervisor && b.pd == pd {
+                    Some(a.id)
+                } else if b.pd == supervisor && a.pd == pd {
+                    Some(b.id)
+                } else {
+                    None
+                }
+            })
+            .with_context(|| format!("no channel between '{supervisor}' and '{pd}'"))
+    }
+}
+
+fn elements(elem: &Element) -> impl Iterator<Item = &Element> {
+    elem.children.iter().filter_map(XMLNode::as_element)
+}
+
+fn attr<'a>(elem: &'a Element, name: &str) -> Result<&'a str> {
+    elem.attributes
+        .get(name)
+        .map(String::as_str)
+        .with_context(|| format!("<{}> is missing attribute '{name}'", elem.name))
+}
+
+fn parse_int(s: &str) -> Result<u64> {
+    let s = s.replace('_', "");
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .with_context(|| format!("invalid integer '{s}'"))
+}
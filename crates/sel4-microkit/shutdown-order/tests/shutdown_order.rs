@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Runs the CLI on `system`, returning its output on success and its error message on failure.
+fn run(name: &str, system: &str, supervisor: Option<&str>) -> Result<String, String> {
+    let system_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.system"));
+    fs::write(&system_path, system).unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_sel4-microkit-shutdown-order"));
+    cmd.arg("-s").arg(&system_path);
+    if let Some(supervisor) = supervisor {
+        cmd.arg("--supervisor").arg(supervisor);
+    }
+    let output = cmd.output().unwrap();
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout).unwrap())
+    } else {
+        Err(String::from_utf8(output.stderr).unwrap())
+    }
+}
+
+fn system(supervisor_priority: u64, server_attrs: &str) -> String {
+    format!(
+        r#"
+<system>
+    <memory_region name="uart" size="0x1000" phys_addr="0x9000000" />
+    <memory_region name="buf" size="0x1000" />
+    <protection_domain name="supervisor" priority="{supervisor_priority}" />
+    <protection_domain name="driver" priority="200" pp="true">
+        <map mr="uart" vaddr="0x5000000" perms="rw" cached="false" />
+        <irq irq="33" id="0" />
+    </protection_domain>
+    <protection_domain name="server" {server_attrs}>
+        <map mr="buf" vaddr="0x4000000" perms="rw" />
+    </protection_domain>
+    <protection_domain name="client" priority="50" pp="true">
+        <map mr="buf" vaddr="0x4000000" perms="rw" />
+    </protection_domain>
+    <channel>
+        <end pd="server" id="1" />
+        <end pd="driver" id="2" />
+    </channel>
+    <channel>
+        <end pd="supervisor" id="3" />
+        <end pd="client" id="0" />
+    </channel>
+    <channel>
+        <end pd="supervisor" id="4" />
+        <end pd="server" id="0" />
+    </channel>
+    <channel>
+        <end pd="supervisor" id="5" />
+        <end pd="driver" id="0" />
+    </channel>
+</system>
+"#
+    )
+}
+
+#[test]
+fn clients_before_servers_before_drivers() {
+    let system = system(10, r#"priority="100" pp="true""#);
+    assert_eq!(
+        run("order", &system, None).unwrap(),
+        "supervisor\nclient\nserver\ndriver\n"
+    );
+    assert_eq!(
+        run("order-supervised", &system, Some("supervisor")).unwrap(),
+        "shutdown_order = 3, 4, 5\n"
+    );
+}
+
+#[test]
+fn rejects_supervised_pd_without_pp() {
+    let system = system(10, r#"priority="100""#);
+    let err = run("without-pp", &system, Some("supervisor")).unwrap_err();
+    assert!(err.contains("'server' must have pp=\"true\""), "{err}");
+}
+
+#[test]
+fn rejects_supervisor_without_lower_priority() {
+    let system = system(50, r#"priority="100" pp="true""#);
+    let err = run("equal-priority", &system, Some("supervisor")).unwrap_err();
+    assert!(
+        err.contains("supervisor 'supervisor' (priority 50) must have a lower priority than protection domain 'client' (priority 50)"),
+        "{err}"
+    );
+}
@@ -0,0 +1,143 @@
+//! Benchmarking utilities, for kernels configured with `KernelBenchmarks`.
+//!
+//! Per-thread utilisation is available for [`Child`] protection domains, whose TCBs this protection
+//! domain holds. The kernel log is read through a [`LogBuffer`]. Tracepoint events are decoded by
+//! `LogBuffer::finalize_tracepoints`, whereas other kinds of log entries can be read from
+//! [`LogBuffer::region`].
+
+use core::fmt;
+
+use sel4_externally_shared::access::ReadOnly;
+
+use crate::MemoryRegion;
+
+/// Corresponds to `seL4_BenchmarkResetLog`.
+pub fn reset_log() -> Result<(), BenchError> {
+    sel4::benchmark_reset_log().map_err(BenchError::from_sel4_error)
+}
+
+/// Corresponds to `seL4_BenchmarkFinalizeLog`. Returns the number of entries in the log.
+pub fn finalize_log() -> usize {
+    sel4::benchmark_finalize_log().try_into().unwrap()
+}
+
+/// The kernel log buffer, mapped into this protection domain as a memory region.
+pub struct LogBuffer {
+    region: MemoryRegion<[u8], ReadOnly>,
+}
+
+impl LogBuffer {
+    /// Tells the kernel to log into `frame`, which must be the frame backing `region`.
+    ///
+    /// The `microkit` tool does not grant protection domains capabilities for the frames of their
+    /// memory regions, so `frame` must be provided by other means.
+    pub fn new(
+        frame: sel4::LargePage,
+        region: MemoryRegion<[u8], ReadOnly>,
+    ) -> Result<Self, BenchError> {
+        assert!(
+            region.as_raw_ptr().len()
+                >= <sel4::cap_type::LargePage as sel4::FrameType>::FRAME_SIZE.bytes()
+        );
+        sel4::benchmark_set_log_buffer(frame).map_err(BenchError::from_sel4_error)?;
+        Ok(Self { region })
+    }
+
+    pub fn region(&self) -> &MemoryRegion<[u8], ReadOnly> {
+        &self.region
+    }
+
+    /// Finalizes the log, and returns an iterator over its tracepoint events.
+    #[sel4::sel4_cfg(BENCHMARK_TRACEPOINTS)]
+    pub fn finalize_tracepoints(&self) -> impl Iterator<Item = TracepointEvent> + '_ {
+        let n = finalize_log().min(self.region.as_raw_ptr().len() / TracepointEvent::ENTRY_SIZE);
+        (0..n).map(|i| {
+            let word_at = |j: usize| {
+                let mut bytes = [0; core::mem::size_of::<sel4::Word>()];
+                let offset = i * TracepointEvent::ENTRY_SIZE + j * bytes.len();
+                self.region
+                    .as_ptr()
+                    .index(offset..offset + bytes.len())
+                    .copy_into_slice(&mut bytes);
+                sel4::Word::from_ne_bytes(bytes)
+            };
+            TracepointEvent {
+                id: word_at(0),
+                duration: word_at(1),
+            }
+        })
+    }
+}
+
+/// Corresponds to `benchmark_tracepoint_log_entry_t`.
+#[sel4::sel4_cfg(BENCHMARK_TRACEPOINTS)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TracepointEvent {
+    pub id: sel4::Word,
+    pub duration: sel4::Word,
+}
+
+#[sel4::sel4_cfg(BENCHMARK_TRACEPOINTS)]
+impl TracepointEvent {
+    const ENTRY_SIZE: usize = 2 * core::mem::size_of::<sel4::Word>();
+}
+
+/// Utilisation of a protection domain's thread, in cycles, as reported by
+/// `seL4_BenchmarkGetThreadUtilisation`, since utilisation was last reset.
+#[sel4::sel4_cfg(BENCHMARK_TRACK_UTILISATION)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ThreadUtilisation {
+    pub thread: u64,
+    pub idle: u64,
+    pub idle_since_thread_start: u64,
+    pub total: u64,
+    pub num_schedules: u64,
+    pub kernel: u64,
+    pub num_kernel_entries: u64,
+}
+
+#[sel4::sel4_cfg(BENCHMARK_TRACK_UTILISATION)]
+impl crate::Child {
+    pub fn utilisation(&self) -> ThreadUtilisation {
+        sel4::benchmark_get_thread_utilisation(self.tcb());
+        // The kernel writes 64-bit values, indexed by `benchmark_track_util_ipc_index`, to the
+        // start of the message registers.
+        sel4::with_borrow_ipc_buffer(|ipc_buffer| {
+            let at = |i: usize| {
+                u64::from_ne_bytes(ipc_buffer.msg_bytes()[i * 8..][..8].try_into().unwrap())
+            };
+            ThreadUtilisation {
+                thread: at(0),
+                idle: at(1),
+                idle_since_thread_start: at(2),
+                total: at(3),
+                num_schedules: at(4),
+                kernel: at(5),
+                num_kernel_entries: at(6),
+            }
+        })
+    }
+
+    pub fn reset_utilisation(&self) {
+        sel4::benchmark_reset_thread_utilisation(self.tcb())
+    }
+}
+
+/// Error type returned by the functions of this module which make fallible benchmarking system
+/// calls.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BenchError {
+    sel4_error: sel4::Error,
+}
+
+impl BenchError {
+    fn from_sel4_error(sel4_error: sel4::Error) -> Self {
+        Self { sel4_error }
+    }
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "benchmark error: {:?}", self.sel4_error)
+    }
+}
@@ -0,0 +1,106 @@
+//! Child protection domains, whose faults are delivered to their parent's [`Handler::fault`].
+
+use core::fmt;
+
+use crate::cspace::{slot_to_local_cptr, BASE_TCB_CAP};
+use crate::message::MessageInfo;
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+const MAX_CHILDREN: usize = 64;
+
+/// A child of this protection domain, identified by the `id` given to it in the system
+/// description.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Child {
+    index: usize,
+}
+
+impl Child {
+    pub const fn new(index: usize) -> Self {
+        assert!(index < MAX_CHILDREN);
+        Self { index }
+    }
+
+    /// Like [`Child::new`], for indices which are valid by construction, such as those decoded
+    /// from badges. The check is omitted with the `release-fast` feature.
+    pub(crate) const fn from_trusted_index(index: usize) -> Self {
+        #[cfg(not(feature = "release-fast"))]
+        assert!(index < MAX_CHILDREN);
+        Self { index }
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn tcb(&self) -> sel4::TCB {
+        slot_to_local_cptr(BASE_TCB_CAP + self.index)
+    }
+
+    /// Restarts this child at `entry_point`, like `microkit_pd_restart`.
+    ///
+    /// A child which is blocked on a fault is restarted without a reply, so [`Handler::fault`]
+    /// should return [`FaultAction::NoReply`] after calling this.
+    pub fn restart(&self, entry_point: usize) -> Result<(), ChildError> {
+        let mut ctx = sel4::UserContext::default();
+        *ctx.pc_mut() = entry_point.try_into().unwrap();
+        self.tcb()
+            .tcb_write_registers(true, 1, &mut ctx)
+            .map_err(ChildError::from_sel4_error)
+    }
+
+    /// Stops this child, like `microkit_pd_stop`.
+    pub fn stop(&self) -> Result<(), ChildError> {
+        self.tcb()
+            .tcb_suspend()
+            .map_err(ChildError::from_sel4_error)
+    }
+
+    /// Reads this child's registers without suspending it, for example to inspect the state of a
+    /// crashed child before restarting it.
+    pub fn read_registers(&self) -> Result<sel4::UserContext, ChildError> {
+        self.tcb()
+            .tcb_read_all_registers(false)
+            .map_err(ChildError::from_sel4_error)
+    }
+}
+
+/// What the main loop does about a fault once [`Handler::fault`] returns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Replies to the fault, resuming the child. For most kinds of fault, the child re-executes the
+    /// faulting instruction, so the handler must first have dealt with the fault's cause.
+    Resume,
+    /// Leaves the fault unanswered, for example because the child has been restarted or stopped.
+    NoReply,
+}
+
+impl FaultAction {
+    pub(crate) fn reply_tag(self) -> Option<MessageInfo> {
+        match self {
+            Self::Resume => Some(MessageInfo::default()),
+            Self::NoReply => None,
+        }
+    }
+}
+
+/// Error type returned by the methods of [`Child`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChildError {
+    sel4_error: sel4::Error,
+}
+
+impl ChildError {
+    fn from_sel4_error(sel4_error: sel4::Error) -> Self {
+        Self { sel4_error }
+    }
+}
+
+impl fmt::Display for ChildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "child error: {:?}", self.sel4_error)
+    }
+}
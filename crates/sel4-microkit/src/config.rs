@@ -0,0 +1,93 @@
+//! Per-protection domain configuration.
+//!
+//! Each protection domain contains a read-only configuration page, exposed through the symbol
+//! `microkit_config`, which the build tooling populates with settings drawn from the system
+//! description (see the `sel4-microkit-inject-config` CLI). This allows parameters such as buffer
+//! sizes or IP addresses to be changed without recompiling the protection domain.
+//!
+//! The page contains UTF-8 text, terminated by the first NUL byte or by the end of the page. Each
+//! line is either empty, a comment beginning with `#`, or a `key = value` entry. Whitespace
+//! around keys and values is ignored. If a key appears more than once, the last entry wins.
+
+use core::fmt;
+use core::str::{self, FromStr};
+
+use sel4_immutable_cell::ImmutableCell;
+
+use crate::abort;
+
+/// The size of the configuration page.
+pub const CONFIG_PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+#[used(linker)]
+#[link_section = ".data.microkit_config"]
+static microkit_config: ImmutableCell<[u8; CONFIG_PAGE_SIZE]> =
+    ImmutableCell::new([0; CONFIG_PAGE_SIZE]);
+
+fn contents() -> &'static str {
+    let all_bytes = microkit_config.get();
+    let bytes = match core::ffi::CStr::from_bytes_until_nul(all_bytes) {
+        Ok(cstr) => cstr.to_bytes(),
+        Err(_) => all_bytes,
+    };
+    str::from_utf8(bytes).unwrap_or_else(|_| {
+        // abort to avoid recursive panic
+        abort!("invalid embedded protection domain configuration");
+    })
+}
+
+/// Returns an iterator over the entries of this protection domain's configuration page, in order.
+pub fn entries() -> impl Iterator<Item = (&'static str, &'static str)> {
+    contents().lines().filter_map(|line| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (key, value) = line.split_once('=').unwrap_or_else(|| {
+            abort!(
+                "malformed protection domain configuration entry: {:?}",
+                line
+            );
+        });
+        Some((key.trim(), value.trim()))
+    })
+}
+
+/// Returns the raw value associated with `key`, if present.
+pub fn get_str(key: &str) -> Option<&'static str> {
+    entries().filter(|(k, _)| *k == key).map(|(_, v)| v).last()
+}
+
+/// Returns the value associated with `key`, parsed as a `T`.
+pub fn get<T: FromStr>(key: &str) -> Result<T, ConfigError> {
+    get_str(key)
+        .ok_or(ConfigError::Missing)?
+        .parse()
+        .map_err(|_| ConfigError::Invalid)
+}
+
+/// Like [`get`], but returns `default` if `key` is absent.
+pub fn get_or<T: FromStr>(key: &str, default: T) -> Result<T, ConfigError> {
+    match get(key) {
+        Err(ConfigError::Missing) => Ok(default),
+        r => r,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The key is not present in the configuration page.
+    Missing,
+    /// The value could not be parsed as the requested type.
+    Invalid,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing configuration entry"),
+            Self::Invalid => write!(f, "invalid configuration entry"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::message::MessageInfo;
 
@@ -15,10 +16,31 @@ pub(crate) const MONITOR_EP_CAP: sel4::Endpoint = slot_to_local_cptr(5);
 const BASE_OUTPUT_NOTIFICATION_CAP: Slot = 10;
 const BASE_ENDPOINT_CAP: Slot = BASE_OUTPUT_NOTIFICATION_CAP + 64;
 const BASE_IRQ_CAP: Slot = BASE_ENDPOINT_CAP + 64;
+pub(crate) const BASE_TCB_CAP: Slot = BASE_IRQ_CAP + 64;
+pub(crate) const BASE_SPARE_REPLY_CAP: Slot = BASE_TCB_CAP + 64;
+
+pub(crate) const MAX_CHANNELS: Slot = 63;
+
+// Notifications queued by `Channel::notify_deferred`, one bit per channel.
+static QUEUED_NOTIFICATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sends the notifications queued by [`Channel::notify_deferred`], in order of channel index. If
+/// `coalesce` is set, the last of them is instead returned, to be fused with the main loop's next
+/// receive.
+pub(crate) fn send_queued_notifications(coalesce: bool) -> Option<PreparedDeferredAction> {
+    let mut queued = QUEUED_NOTIFICATIONS.swap(0, Ordering::Relaxed);
+    while queued != 0 {
+        let channel = Channel::from_trusted_index(queued.trailing_zeros().try_into().unwrap());
+        queued &= queued - 1;
+        if queued == 0 && coalesce {
+            return Some(channel.defer_notify().prepare());
+        }
+        channel.notify();
+    }
+    None
+}
 
-const MAX_CHANNELS: Slot = 63;
-
-const fn slot_to_local_cptr<T: sel4::CapType>(slot: Slot) -> sel4::LocalCPtr<T> {
+pub(crate) const fn slot_to_local_cptr<T: sel4::CapType>(slot: Slot) -> sel4::LocalCPtr<T> {
     sel4::LocalCPtr::from_bits(slot as sel4::CPtrBits)
 }
 
@@ -34,6 +56,18 @@ impl Channel {
         Self { index }
     }
 
+    /// Like [`Channel::new`], for indices which are valid by construction, such as those decoded
+    /// from badges. The check is omitted with the `release-fast` feature.
+    pub(crate) const fn from_trusted_index(index: usize) -> Self {
+        #[cfg(not(feature = "release-fast"))]
+        assert!(index < MAX_CHANNELS);
+        Self { index }
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
     fn local_cptr<T: sel4::CapType>(&self, offset: Slot) -> sel4::LocalCPtr<T> {
         slot_to_local_cptr(offset + self.index)
     }
@@ -43,10 +77,17 @@ impl Channel {
     }
 
     fn irq_handler(&self) -> sel4::IRQHandler {
-        self.local_cptr::<sel4::cap_type::IRQHandler>(BASE_IRQ_CAP)
+        let cptr = self.local_cptr::<sel4::cap_type::Unspecified>(BASE_IRQ_CAP);
+        // Without this check, acknowledging a channel which is not an IRQ channel surfaces only
+        // as an opaque invalid capability error.
+        #[cfg(debug_assertions)]
+        if let Err(err) = cptr.try_downcast::<sel4::cap_type::IRQHandler>() {
+            panic!("channel {} is not an IRQ channel: {}", self.index, err);
+        }
+        cptr.downcast()
     }
 
-    fn endpoint(&self) -> sel4::Endpoint {
+    pub(crate) fn endpoint(&self) -> sel4::Endpoint {
         self.local_cptr::<sel4::cap_type::Endpoint>(BASE_ENDPOINT_CAP)
     }
 
@@ -54,6 +95,24 @@ impl Channel {
         self.notification().signal()
     }
 
+    /// Queues a notification on this channel, to be sent by the main loop once the handler
+    /// returns.
+    ///
+    /// Notifications queued on the same channel before the main loop sends them are coalesced into
+    /// one, which the peer could not have distinguished anyway. When the main loop's next syscall is
+    /// a receive on which it is not replying, the last queued notification is fused with it using
+    /// `seL4_NBSendRecv`, as with [`Handler::take_deferred_action`], which takes precedence.
+    pub fn notify_deferred(&self) {
+        QUEUED_NOTIFICATIONS.fetch_or(1 << self.index, Ordering::Relaxed);
+    }
+
+    /// Acknowledges the interrupt delivered on this channel, typically from within
+    /// [`Handler::notified`], so that it may be delivered again.
+    ///
+    /// # Panics
+    ///
+    /// With `debug_assertions` enabled and on debug kernels, panics if this channel is not an IRQ
+    /// channel.
     pub fn irq_ack(&self) -> Result<(), IrqAckError> {
         self.irq_handler()
             .irq_handler_ack()
@@ -0,0 +1,101 @@
+//! The event which the main loop is dispatching to the [`Handler`], for use in diagnostics such as
+//! the default panic hook.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::child::Child;
+use crate::cspace::Channel;
+use crate::message::{MessageInfo, MessageLabel};
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+const KIND_NONE: usize = 0;
+const KIND_NOTIFIED: usize = 1;
+const KIND_PROTECTED: usize = 2;
+const KIND_FAULT: usize = 3;
+
+// Protection domains are single-threaded, so these are only ever accessed by one thread, and are
+// atomic only so that they can be statics.
+static KIND: AtomicUsize = AtomicUsize::new(KIND_NONE);
+static INDEX: AtomicUsize = AtomicUsize::new(0);
+static LABEL: AtomicUsize = AtomicUsize::new(0);
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// An event being dispatched to a [`Handler`] method.
+#[derive(Debug, Clone)]
+pub enum Dispatch {
+    Notified {
+        channel: Channel,
+    },
+    Protected {
+        channel: Channel,
+        msg_info: MessageInfo,
+    },
+    Fault {
+        child: Child,
+    },
+}
+
+impl fmt::Display for Dispatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Notified { channel } => {
+                write!(f, "notification from channel {}", channel.index())
+            }
+            Self::Protected { channel, msg_info } => write!(
+                f,
+                "protected procedure call from channel {} (label={:#x}, count={})",
+                channel.index(),
+                msg_info.label(),
+                msg_info.count()
+            ),
+            Self::Fault { child } => write!(f, "fault from child {}", child.index()),
+        }
+    }
+}
+
+/// The event which the main loop is currently dispatching, if any.
+pub fn current_dispatch() -> Option<Dispatch> {
+    let index = INDEX.load(Ordering::Relaxed);
+    Some(match KIND.load(Ordering::Relaxed) {
+        KIND_NOTIFIED => Dispatch::Notified {
+            channel: Channel::from_trusted_index(index),
+        },
+        KIND_PROTECTED => Dispatch::Protected {
+            channel: Channel::from_trusted_index(index),
+            msg_info: MessageInfo::new(
+                LABEL.load(Ordering::Relaxed) as MessageLabel,
+                COUNT.load(Ordering::Relaxed),
+            ),
+        },
+        KIND_FAULT => Dispatch::Fault {
+            child: Child::from_trusted_index(index),
+        },
+        _ => return None,
+    })
+}
+
+/// Records `dispatch` as the current event for the duration of `f`. If `f` panics or returns an
+/// error, it remains recorded.
+pub(crate) fn with_dispatch<T, E>(
+    dispatch: &Dispatch,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let (kind, index) = match dispatch {
+        Dispatch::Notified { channel } => (KIND_NOTIFIED, channel.index()),
+        Dispatch::Protected { channel, msg_info } => {
+            LABEL.store(msg_info.label() as usize, Ordering::Relaxed);
+            COUNT.store(msg_info.count(), Ordering::Relaxed);
+            (KIND_PROTECTED, channel.index())
+        }
+        Dispatch::Fault { child } => (KIND_FAULT, child.index()),
+    };
+    INDEX.store(index, Ordering::Relaxed);
+    KIND.store(kind, Ordering::Relaxed);
+    let r = f()?;
+    KIND.store(KIND_NONE, Ordering::Relaxed);
+    Ok(r)
+}
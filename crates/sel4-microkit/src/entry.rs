@@ -2,6 +2,7 @@ pub use sel4_panicking::catch_unwind;
 pub use sel4_panicking_env::{abort, debug_print, debug_println};
 
 use crate::env::get_ipc_buffer;
+use crate::error::report_and_abort;
 use crate::handler::{run_handler, Handler};
 use crate::panicking::init_panicking;
 
@@ -58,7 +59,7 @@ macro_rules! declare_init {
 #[allow(clippy::missing_safety_doc)]
 pub unsafe fn run_main<T: Handler>(init: impl FnOnce() -> T) {
     match catch_unwind(|| run_handler(init()).into_err()) {
-        Ok(err) => abort!("main thread terminated with error: {err}"),
+        Ok(err) => report_and_abort(&err, T::error_kind(&err)),
         Err(_) => abort!("main thread panicked"),
     }
 }
@@ -17,7 +17,12 @@ pub(crate) unsafe fn get_ipc_buffer() -> sel4::IPCBuffer {
 #[link_section = ".data"]
 static passive: ImmutableCell<bool> = ImmutableCell::new(false); // just a placeholder
 
-/// Returns whether this projection domain is a passive server.
+/// Returns whether this protection domain is a passive server, as declared with `passive="true"`
+/// in the system description.
+///
+/// The main loop run by [`protection_domain`](crate::protection_domain) handles the hand-over of
+/// a passive protection domain's scheduling context to the monitor, so handlers need not consult
+/// this.
 pub fn pd_is_passive() -> bool {
     *passive.get()
 }
@@ -27,7 +32,7 @@ pub fn pd_is_passive() -> bool {
 #[link_section = ".data"]
 static microkit_name: ImmutableCell<[u8; 16]> = ImmutableCell::new([0; 16]);
 
-/// Returns the name of this projection domain.
+/// Returns the name of this protection domain.
 pub fn pd_name() -> &'static str {
     let all_bytes = microkit_name.get();
     let bytes = match core::ffi::CStr::from_bytes_until_nul(all_bytes) {
@@ -0,0 +1,101 @@
+//! Classified errors for [`Handler`] entrypoints.
+//!
+//! When an entrypoint returns an error, the main loop stops, and the error is reported along with
+//! the event which the main loop was dispatching (see [`current_dispatch`]) and, if
+//! [`Handler::error_kind`] classifies it, its [`HandlerErrorKind`]. The report is printed, and the
+//! fault report channel set with [`crate::panicking::set_fault_report_channel`] is notified, as it
+//! is for a panic. The protection domain then aborts, which the monitor observes as a fault.
+
+use core::fmt;
+
+use sel4_panicking_env::{abort, debug_println};
+
+use crate::dispatch::current_dispatch;
+use crate::panicking::notify_fault_report_channel;
+use crate::pd_name;
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+/// The category of a protection domain's failure.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HandlerErrorKind {
+    /// A peer sent a message which does not conform to the protocol of its channel.
+    ProtocolViolation,
+    /// A queue, pool, or other resource was exhausted.
+    ResourceExhaustion,
+    /// A device driven by this protection domain failed or misbehaved.
+    DeviceFailure,
+    /// An invariant of this protection domain did not hold.
+    InternalBug,
+}
+
+impl fmt::Display for HandlerErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::ProtocolViolation => "protocol violation",
+            Self::ResourceExhaustion => "resource exhaustion",
+            Self::DeviceFailure => "device failure",
+            Self::InternalBug => "internal bug",
+        })
+    }
+}
+
+/// A general-purpose [`Handler::Error`], which carries its classification.
+///
+/// Handlers which use this type can classify their errors by returning [`HandlerError::kind`] from
+/// [`Handler::error_kind`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HandlerError {
+    kind: HandlerErrorKind,
+    detail: &'static str,
+}
+
+impl HandlerError {
+    pub const fn new(kind: HandlerErrorKind, detail: &'static str) -> Self {
+        Self { kind, detail }
+    }
+
+    pub const fn protocol_violation(detail: &'static str) -> Self {
+        Self::new(HandlerErrorKind::ProtocolViolation, detail)
+    }
+
+    pub const fn resource_exhaustion(detail: &'static str) -> Self {
+        Self::new(HandlerErrorKind::ResourceExhaustion, detail)
+    }
+
+    pub const fn device_failure(detail: &'static str) -> Self {
+        Self::new(HandlerErrorKind::DeviceFailure, detail)
+    }
+
+    pub const fn internal_bug(detail: &'static str) -> Self {
+        Self::new(HandlerErrorKind::InternalBug, detail)
+    }
+
+    pub const fn kind(&self) -> HandlerErrorKind {
+        self.kind
+    }
+
+    pub const fn detail(&self) -> &'static str {
+        self.detail
+    }
+}
+
+impl fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.detail)
+    }
+}
+
+pub(crate) fn report_and_abort<E: fmt::Display>(err: &E, kind: Option<HandlerErrorKind>) -> ! {
+    match kind {
+        Some(kind) => debug_println!("{}: handler failed ({}): {}", pd_name(), kind, err),
+        None => debug_println!("{}: handler failed: {}", pd_name(), err),
+    }
+    if let Some(dispatch) = current_dispatch() {
+        debug_println!("{}: while handling {}", pd_name(), dispatch);
+    }
+    notify_fault_report_channel();
+    abort!("main thread terminated with error")
+}
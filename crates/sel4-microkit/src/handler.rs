@@ -1,16 +1,30 @@
 use core::fmt;
 
+use crate::child::{Child, FaultAction};
 use crate::cspace::{
-    Channel, DeferredAction, PreparedDeferredAction, INPUT_CAP, MONITOR_EP_CAP, REPLY_CAP,
+    send_queued_notifications, Channel, DeferredAction, PreparedDeferredAction, INPUT_CAP,
+    MONITOR_EP_CAP,
 };
+use crate::dispatch::{with_dispatch, Dispatch};
+use crate::error::HandlerErrorKind;
+use crate::liveness::{take_deferred_notifications, PING_LABEL};
 use crate::message::MessageInfo;
+use crate::notifications::{handle_notifications, NotificationOrder};
 use crate::pd_is_passive;
+use crate::reply::{recv_reply_object, with_current_caller};
+use crate::shutdown::{Quiesce, QUIESCE_LABEL, STOP_LABEL};
 
-const EVENT_TYPE_MASK: sel4::Word = 1 << (sel4::WORD_SIZE - 1);
+pub(crate) const EVENT_TYPE_MASK: sel4::Word = 1 << (sel4::WORD_SIZE - 1);
+
+const FAULT_MASK: sel4::Word = 1 << (sel4::WORD_SIZE - 2);
+
+const CHILD_MASK: sel4::Word = 0xff;
 
 /// Trait for the application-specific part of a protection domain's main loop.
 pub trait Handler {
     /// Error type returned by this protection domain's entrypoints.
+    ///
+    /// An error stops the main loop, and is reported as described in [`crate::error`].
     type Error: fmt::Display = !;
 
     /// This method has the same meaning and type as its analog in `libmicrokit`.
@@ -22,6 +36,8 @@ pub trait Handler {
 
     /// This method has the same meaning and type as its analog in `libmicrokit`.
     ///
+    /// To answer the call later instead, call [`crate::reply::defer_reply`] before returning.
+    ///
     /// The default implementation just panics.
     fn protected(
         &mut self,
@@ -31,19 +47,70 @@ pub trait Handler {
         panic!("unexpected protected procedure call from channel {channel:?} with msg_info={msg_info:?}")
     }
 
+    /// Called when a child of this protection domain faults, with the fault decoded from the fault
+    /// message.
+    ///
+    /// The default implementation just panics.
+    fn fault(&mut self, child: Child, fault_info: sel4::Fault) -> Result<FaultAction, Self::Error> {
+        panic!("unexpected fault from child {child:?}: {fault_info:?}")
+    }
+
+    /// Classifies an error returned by one of this handler's entrypoints, for the report made when
+    /// the main loop stops.
+    ///
+    /// The default implementation returns `None`, which leaves errors unclassified.
+    fn error_kind(_err: &Self::Error) -> Option<HandlerErrorKind> {
+        None
+    }
+
     /// An advanced feature for use by protection domains which seek to coalesce syscalls when
     /// possible.
     ///
     /// This method is used by the main loop to fuse a queued `seL4_Send` call with the next
     /// `seL4_Recv` using `seL4_NBSendRecv`. Its default implementation just returns `None`.
+    ///
+    /// Handlers which only send notifications can use [`Channel::notify_deferred`] instead.
     fn take_deferred_action(&mut self) -> Option<DeferredAction> {
         None
     }
+
+    /// The order in which the main loop delivers notifications from several channels which arrive
+    /// in the same badge.
+    ///
+    /// The default implementation returns [`NotificationOrder::LowestFirst`], which matches
+    /// `libmicrokit`.
+    fn notification_order(&self) -> NotificationOrder {
+        NotificationOrder::LowestFirst
+    }
+
+    /// Called when a supervisor asks this protection domain to quiesce ahead of being stopped (see
+    /// [`crate::shutdown`]).
+    ///
+    /// Handlers which need time to tear down return [`Quiesce::InProgress`], and are asked again
+    /// later. The default implementation just returns [`Quiesce::Done`].
+    fn quiesce(&mut self) -> Result<Quiesce, Self::Error> {
+        Ok(Quiesce::Done)
+    }
+
+    /// Called just before the main loop stops delivering events to this handler (see
+    /// [`crate::shutdown`]).
+    ///
+    /// The default implementation does nothing.
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 pub(crate) fn run_handler<T: Handler>(mut handler: T) -> Result<!, T::Error> {
     let mut reply_tag: Option<MessageInfo> = None;
 
+    let mut notification_cursor = 0;
+
+    let mut stopped = false;
+
+    // A passive protection domain tells the monitor that it is ready to give up its scheduling
+    // context by coalescing a message to the monitor with its first receive, as in `libmicrokit`.
+    // From then on, it runs only on scheduling contexts donated by callers and notifications.
     let mut prepared_deferred_action: Option<PreparedDeferredAction> = if pd_is_passive() {
         sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| ipc_buffer.msg_regs_mut()[0] = 0);
         Some(PreparedDeferredAction::new(
@@ -56,40 +123,100 @@ pub(crate) fn run_handler<T: Handler>(mut handler: T) -> Result<!, T::Error> {
 
     loop {
         let (tag, badge) = match (reply_tag.take(), prepared_deferred_action.take()) {
-            (Some(tag), None) => INPUT_CAP.reply_recv(tag.into_sel4(), REPLY_CAP),
+            (Some(tag), None) => INPUT_CAP.reply_recv(tag.into_sel4(), recv_reply_object()),
             (None, Some(action)) => action.cptr().nb_send_recv(
                 action.msg_info(),
                 INPUT_CAP.cast::<sel4::cap_type::Unspecified>(),
-                REPLY_CAP,
+                recv_reply_object(),
             ),
-            (None, None) => INPUT_CAP.recv(REPLY_CAP),
+            (None, None) => INPUT_CAP.recv(recv_reply_object()),
             _ => unreachable!(),
         };
 
         let tag = MessageInfo::from_sel4(tag);
 
         let is_endpoint = badge & EVENT_TYPE_MASK != 0;
+        let is_fault = !is_endpoint && badge & FAULT_MASK != 0;
 
         if is_endpoint {
             let channel_index = badge & (sel4::Word::try_from(sel4::WORD_SIZE).unwrap() - 1);
-            reply_tag =
-                Some(handler.protected(Channel::new(channel_index.try_into().unwrap()), tag)?);
-        } else {
-            let mut badge_bits = badge;
-            while badge_bits != 0 {
-                let i = badge_bits.trailing_zeros();
-                handler.notified(Channel::new(i.try_into().unwrap()))?;
-                badge_bits &= !(1 << i);
+            let channel = Channel::from_trusted_index(channel_index as usize);
+            reply_tag = match tag.label() {
+                PING_LABEL => {
+                    channel.notify();
+                    Some(MessageInfo::default())
+                }
+                QUIESCE_LABEL | STOP_LABEL if stopped => Some(MessageInfo::default()),
+                _ if stopped => Some(MessageInfo::new(STOP_LABEL, 0)),
+                QUIESCE_LABEL => {
+                    let dispatch = Dispatch::Protected {
+                        channel,
+                        msg_info: tag.clone(),
+                    };
+                    Some(with_dispatch(&dispatch, || handler.quiesce())?.into_msg_info())
+                }
+                STOP_LABEL => {
+                    let dispatch = Dispatch::Protected {
+                        channel,
+                        msg_info: tag.clone(),
+                    };
+                    with_dispatch(&dispatch, || handler.stop())?;
+                    stopped = true;
+                    Some(MessageInfo::default())
+                }
+                _ => {
+                    let dispatch = Dispatch::Protected {
+                        channel,
+                        msg_info: tag.clone(),
+                    };
+                    with_dispatch(&dispatch, || {
+                        with_current_caller(channel, || handler.protected(channel, tag))
+                    })?
+                }
+            };
+        } else if is_fault {
+            // Once stopped, faulting children are left blocked.
+            if !stopped {
+                let child = Child::from_trusted_index((badge & CHILD_MASK) as usize);
+                let fault_info = sel4::with_borrow_ipc_buffer(|ipc_buffer| {
+                    sel4::Fault::new(ipc_buffer, &tag.clone().into_sel4())
+                });
+                reply_tag = with_dispatch(&Dispatch::Fault { child }, || {
+                    handler.fault(child, fault_info)
+                })?
+                .reply_tag();
             }
+        } else if !stopped {
+            handle_notifications(&mut handler, badge, &mut notification_cursor)?;
         };
 
+        if stopped {
+            take_deferred_notifications();
+            send_queued_notifications(false);
+            continue;
+        }
+
+        // Deliver notifications which arrived while the handler was waiting on a ping.
+        loop {
+            let deferred = take_deferred_notifications();
+            if deferred == 0 {
+                break;
+            }
+            handle_notifications(&mut handler, deferred, &mut notification_cursor)?;
+        }
+
         prepared_deferred_action = handler
             .take_deferred_action()
             .as_ref()
             .map(DeferredAction::prepare);
 
-        if prepared_deferred_action.is_some() && is_endpoint {
-            panic!("handler yielded deferred action after call to 'protected()'");
+        if prepared_deferred_action.is_some() && (is_endpoint || reply_tag.is_some()) {
+            panic!("handler yielded deferred action after call to 'protected()' or 'fault()'");
+        }
+
+        let can_coalesce = prepared_deferred_action.is_none() && reply_tag.is_none();
+        if let Some(action) = send_queued_notifications(can_coalesce) {
+            prepared_deferred_action = Some(action);
         }
     }
 }
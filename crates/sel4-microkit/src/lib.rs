@@ -34,10 +34,21 @@
 //! `rustc` target specs distributed as part of the [rust-sel4
 //! project](https://github.com/seL4/rust-sel4) provide `__sel4_ipc_buffer_obj`, and the
 //! [`memory_region_symbol`] macro provides a conveneint way to declare memory region address
-//! symbols.
+//! symbols. The [`memory_region`] macro builds on it, declaring a [`MemoryRegion`] whose alignment
+//! and size are checked at startup.
+//!
+//! The `sel4-microkit-build` crate generates a protection domain's linker script from the system
+//! description, for use in its build script. The generated script places the stack between guard
+//! pages and the configuration page on a page of its own, and checks at link time that the image
+//! does not overlap the protection domain's memory regions.
 //!
 //! Use the [`protection_domain`] macro to declare the initialization function, stack size, and,
-//! optionally, heap and heap size.
+//! optionally, heap and heap size and a logger.
+//!
+//! The `release-fast` feature omits checks from the main loop and message accessors whose inputs
+//! are valid by construction, such as the decoding of channels from badges and the bounds checks
+//! in [`get_mr_at`] and [`set_mr_at`]. It also disables the collection of [`notification_stats`],
+//! which then remain zero.
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
@@ -46,27 +57,54 @@ use sel4_panicking_env::abort;
 
 pub use sel4_microkit_macros::protection_domain;
 
+mod child;
 mod cspace;
+mod dispatch;
 mod entry;
 mod env;
 mod handler;
+mod liveness;
+mod logging;
 mod memory_region;
 mod message;
+mod notifications;
+mod ppc;
+mod readiness;
 
+#[sel4::sel4_cfg(ENABLE_BENCHMARKS)]
+pub mod bench;
+pub mod config;
+pub mod error;
 pub mod panicking;
+pub mod reply;
+pub mod shutdown;
 
+pub use child::{Child, ChildError, FaultAction};
 pub use cspace::{
     Channel, DeferredAction, DeferredActionInterface, DeferredActionSlot, IrqAckError,
 };
+pub use dispatch::{current_dispatch, Dispatch};
 pub use env::{pd_is_passive, pd_name};
+pub use error::{HandlerError, HandlerErrorKind};
 pub use handler::{Handler, NullHandler};
-pub use memory_region::{cast_memory_region_checked, cast_memory_region_to_slice_checked};
+pub use liveness::{PingTimeout, PING_LABEL};
+pub use memory_region::{
+    cast_memory_region_checked, cast_memory_region_to_slice_checked, MemoryRegion,
+};
 pub use message::{
-    get_mr, set_mr, with_msg_bytes, with_msg_bytes_mut, with_msg_regs, with_msg_regs_mut,
-    MessageInfo, MessageLabel, MessageRegisterValue,
+    get_mr, get_mr_at, is_reserved_label, set_mr, set_mr_at, with_msg_bytes, with_msg_bytes_mut,
+    with_msg_regs, with_msg_regs_mut, MessageInfo, MessageLabel, MessageRegisterIndex,
+    MessageRegisterValue, ReservedLabel, RESERVED_LABELS_START,
+};
+pub use notifications::{
+    notification_stats, reset_notification_stats, NotificationOrder, NotificationStats,
+    NUM_CHANNELS,
 };
+pub use ppc::{ppc_reply_err, ppc_reply_ok, PpcError, PpcReply, TooManyResults, PPC_OK_LABEL};
+pub use readiness::{await_ready, ReadinessFlag, ReadinessTimeout};
 
-/// Declares the initialization function, stack size, and, optionally, heap and heap size.
+/// Declares the initialization function, stack size, and, optionally, heap and heap size and
+/// logger.
 ///
 /// See the [`protection_domain`] attribute macro for more detail.
 #[macro_export]
@@ -100,6 +138,21 @@ macro_rules! declare_protection_domain {
             $(stack_size = $stack_size,)?
         }
     };
+    {
+        init = $init:expr,
+        $(stack_size = $stack_size:expr,)?
+        $(heap_size = $heap_size:expr,)?
+        log_level = $log_level:expr $(,)?
+    } => {
+        $crate::_private::declare_protection_domain! {
+            init = || {
+                $crate::_private::init_logger($log_level);
+                ($init)()
+            },
+            $(stack_size = $stack_size,)?
+            $(heap_size = $heap_size,)?
+        }
+    };
 }
 
 // For macros
@@ -107,9 +160,13 @@ macro_rules! declare_protection_domain {
 pub mod _private {
     pub use sel4_immutable_cell::ImmutableCell;
 
+    pub use sel4_externally_shared::access;
+
     pub use sel4_runtime_common::{declare_stack, declare_static_heap};
 
-    pub use crate::{declare_init, declare_protection_domain, entry::run_main};
+    pub use crate::{
+        declare_init, declare_protection_domain, entry::run_main, logging::init_logger,
+    };
 
     pub const DEFAULT_STACK_SIZE: usize = 0x10000;
 }
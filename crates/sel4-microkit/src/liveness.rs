@@ -0,0 +1,106 @@
+//! A lightweight handshake for checking whether the peer on a channel is up and running.
+//!
+//! A ping is a message with the reserved label [`PING_LABEL`], sent without blocking to the peer's
+//! endpoint. The main loop of the peer (see [`Handler`]) answers pings itself, without involving
+//! its handler, by notifying the pinging protection domain over the same channel.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cspace::{Channel, INPUT_CAP};
+use crate::handler::EVENT_TYPE_MASK;
+use crate::message::{MessageInfo, MessageLabel, ReservedLabel};
+use crate::reply::recv_reply_object;
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+/// The label reserved for pings. Protected procedure calls with this label never reach
+/// [`Handler::protected`].
+pub const PING_LABEL: MessageLabel = ReservedLabel::Ping.label();
+
+// Notifications received while waiting for a response to a ping, to be delivered by the main loop.
+static DEFERRED_NOTIFICATIONS: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn take_deferred_notifications() -> sel4::Word {
+    DEFERRED_NOTIFICATIONS
+        .swap(0, Ordering::Relaxed)
+        .try_into()
+        .unwrap()
+}
+
+impl Channel {
+    /// Checks whether the peer on this channel is running its main loop, giving up after
+    /// `max_attempts` attempts.
+    ///
+    /// See [`Channel::ping_until`], including its note on passive peers, which cannot be pinged.
+    pub fn ping(&self, max_attempts: usize) -> Result<(), PingTimeout> {
+        let mut attempts = 0;
+        self.ping_until(|| {
+            attempts += 1;
+            attempts >= max_attempts
+        })
+    }
+
+    /// Checks whether the peer on this channel is running its main loop, retrying until
+    /// `deadline_has_passed` returns `true`.
+    ///
+    /// The peer must accept protected procedure calls on this channel. Unlike
+    /// [`Channel::pp_call`], this method does not block if the peer has crashed or has not yet
+    /// started. Notifications on other channels which arrive in the meantime are delivered to this
+    /// protection domain's [`Handler`] once control returns to its main loop. The peer's response
+    /// takes the form of a notification on this channel, so a late response may surface as a
+    /// spurious call to [`Handler::notified`] for this channel.
+    ///
+    /// This method must not be used by protection domains which themselves accept protected
+    /// procedure calls, because such a call arriving during the handshake could not be answered.
+    ///
+    /// Passive peers cannot be pinged. A passive protection domain has no scheduling context of
+    /// its own, and runs only on one donated by a caller or bound to its notification. A ping is
+    /// sent rather than called, so it donates nothing, and the peer never runs to answer it.
+    /// Pinging a passive peer therefore always fails with [`PingTimeout`], even if the peer is
+    /// healthy. Its liveness can only be checked with a protected procedure call, which blocks if
+    /// it has crashed.
+    pub fn ping_until(
+        &self,
+        mut deadline_has_passed: impl FnMut() -> bool,
+    ) -> Result<(), PingTimeout> {
+        let this_bit = 1 << self.index();
+        loop {
+            self.endpoint()
+                .nb_send(MessageInfo::new(PING_LABEL, 0).into_sel4());
+            sel4::r#yield();
+            let (_, badge) = INPUT_CAP.nb_recv(recv_reply_object());
+            if badge & EVENT_TYPE_MASK != 0 {
+                panic!("received protected procedure call while pinging channel {self:?}");
+            }
+            let others: usize = (badge & !this_bit).try_into().unwrap();
+            DEFERRED_NOTIFICATIONS.fetch_or(others, Ordering::Relaxed);
+            if badge & this_bit != 0 {
+                return Ok(());
+            }
+            if deadline_has_passed() {
+                return Err(PingTimeout { channel: *self });
+            }
+        }
+    }
+}
+
+/// Error type returned by [`Channel::ping`] and [`Channel::ping_until`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PingTimeout {
+    channel: Channel,
+}
+
+impl PingTimeout {
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+}
+
+impl fmt::Display for PingTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no response to ping on channel {:?}", self.channel)
+    }
+}
@@ -0,0 +1,23 @@
+use sel4_immediate_sync_once_cell::ImmediateSyncOnceCell;
+use sel4_logging::{LevelFilter, Logger, LoggerBuilder};
+
+static LOGGER: ImmediateSyncOnceCell<Logger> = ImmediateSyncOnceCell::new();
+
+/// Installs a logger which writes to the debug console, as declared with the `log_level` key of
+/// [`declare_protection_domain`](crate::declare_protection_domain).
+pub fn init_logger(level_filter: LevelFilter) {
+    LOGGER
+        .set(
+            LoggerBuilder::const_default()
+                .level_filter(level_filter)
+                .write(write)
+                .build(),
+        )
+        .unwrap_or_else(|_| panic!());
+    LOGGER.get().unwrap().set().unwrap();
+}
+
+#[allow(unused_variables)]
+fn write(s: &str) {
+    crate::debug_print!("{}", s)
+}
@@ -1,8 +1,14 @@
 //! Utilities for declaring and using share memory regions.
 
+use core::marker::PhantomData;
 use core::mem;
 use core::ptr::NonNull;
 
+use sel4_externally_shared::{
+    access::{Access, ReadWrite},
+    ExternallySharedPtr,
+};
+
 /// Declares a symbol via which the `microkit` tool can inject a memory region's address, and
 /// returns the memory region's address at runtime.
 ///
@@ -81,3 +87,84 @@ pub fn cast_memory_region_to_slice_checked<T: Sized>(bytes_ptr: NonNull<[u8]>) -
     let n = bytes_ptr.len() / mem::size_of::<T>();
     NonNull::slice_from_raw_parts(ptr, n)
 }
+
+/// A memory region whose address is injected by the `microkit` tool, as declared with
+/// [`memory_region`](crate::memory_region!), with the access given by `A`.
+pub struct MemoryRegion<T: ?Sized, A = ReadWrite> {
+    ptr: NonNull<T>,
+    access: PhantomData<A>,
+}
+
+impl<T: ?Sized, A: Access> MemoryRegion<T, A> {
+    /// # Safety
+    ///
+    /// `ptr` must point to memory which is mapped into this protection domain, with at least the
+    /// access given by `A`, for the rest of its lifetime.
+    pub const unsafe fn new(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            access: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> ExternallySharedPtr<'static, T, A> {
+        unsafe { ExternallySharedPtr::new_restricted(A::default(), self.ptr) }
+    }
+
+    pub fn as_raw_ptr(&self) -> NonNull<T> {
+        self.ptr
+    }
+}
+
+/// Declares a memory region whose address is injected by the `microkit` tool, and returns a
+/// [`MemoryRegion`] for it at runtime.
+///
+/// A region declared with `*const` is read-only, and one declared with `*mut` is read-write.
+/// `size` is the size of the region in bytes, as given in the system description. At runtime, the
+/// address is checked to be suitably aligned, and `size` is checked to accommodate a `T`, or, for
+/// `[T]`, to be a multiple of the size of `T`, in which case the slice covers the whole region.
+///
+/// Attributes, such as `#[used(linker)]`, are applied to the symbol as by
+/// [`memory_region_symbol`](crate::memory_region_symbol!).
+///
+/// # Examples
+///
+/// ```rust
+/// let region_in: MemoryRegion<[u8], ReadOnly> =
+///     memory_region!(region_in_start: *const [u8], size = REGION_SIZE);
+///
+/// let region_out: MemoryRegion<Foo> =
+///     memory_region!(region_out_start: *mut Foo, size = *var!(region_out_size: usize = 0));
+/// ```
+#[macro_export]
+macro_rules! memory_region {
+    ($(#[$attrs:meta])* $symbol:ident: *const [$ty:ty], size = $size:expr) => {
+        $crate::memory_region!(
+            @new $(#[$attrs])* $symbol: [$ty], $size, ReadOnly, cast_memory_region_to_slice_checked
+        )
+    };
+    ($(#[$attrs:meta])* $symbol:ident: *mut [$ty:ty], size = $size:expr) => {
+        $crate::memory_region!(
+            @new $(#[$attrs])* $symbol: [$ty], $size, ReadWrite, cast_memory_region_to_slice_checked
+        )
+    };
+    ($(#[$attrs:meta])* $symbol:ident: *const $ty:ty, size = $size:expr) => {
+        $crate::memory_region!(
+            @new $(#[$attrs])* $symbol: $ty, $size, ReadOnly, cast_memory_region_checked
+        )
+    };
+    ($(#[$attrs:meta])* $symbol:ident: *mut $ty:ty, size = $size:expr) => {
+        $crate::memory_region!(
+            @new $(#[$attrs])* $symbol: $ty, $size, ReadWrite, cast_memory_region_checked
+        )
+    };
+    (@new $(#[$attrs:meta])* $symbol:ident: $ty:ty, $size:expr, $access:ident, $cast:ident) => {
+        // SAFETY: the microkit tool injects the address of a region of the declared size, which is
+        // mapped with at least the declared access.
+        unsafe {
+            $crate::MemoryRegion::<$ty, $crate::_private::access::$access>::new($crate::$cast(
+                $crate::memory_region_symbol!($(#[$attrs])* $symbol: *mut [u8], n = $size),
+            ))
+        }
+    };
+}
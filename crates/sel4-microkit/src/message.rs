@@ -40,6 +40,45 @@ impl Default for MessageInfo {
     }
 }
 
+const NUM_RESERVED_LABELS: MessageLabel = 16;
+
+/// Labels at or above this value are reserved for the protocols implemented by this crate and its
+/// companion crates, and are listed in [`ReservedLabel`]. Messages defined by users must have
+/// labels below it.
+pub const RESERVED_LABELS_START: MessageLabel =
+    (1 << MessageInfo::label_width()) - NUM_RESERVED_LABELS;
+
+/// The reserved labels, each at a fixed offset from [`RESERVED_LABELS_START`]. They are all
+/// allocated here, so that no two protocols can claim the same label.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ReservedLabel {
+    /// See `sel4_microkit_message::OVERFLOW_LABEL`.
+    Overflow = 9,
+    /// See `sel4_microkit_async::COLLECT_LABEL`.
+    Collect = 10,
+    /// See `sel4_microkit_async::DEFERRED_LABEL`.
+    Deferred = 11,
+    /// See [`STOP_LABEL`](crate::shutdown::STOP_LABEL).
+    Stop = 12,
+    /// See [`QUIESCE_LABEL`](crate::shutdown::QUIESCE_LABEL).
+    Quiesce = 13,
+    /// See [`PING_LABEL`](crate::PING_LABEL).
+    Ping = 14,
+    /// See `sel4_microkit_message::UNSPECIFIED_ERROR_LABEL`.
+    UnspecifiedError = 15,
+}
+
+impl ReservedLabel {
+    pub const fn label(self) -> MessageLabel {
+        RESERVED_LABELS_START + self as MessageLabel
+    }
+}
+
+pub const fn is_reserved_label(label: MessageLabel) -> bool {
+    label >= RESERVED_LABELS_START
+}
+
 pub fn with_msg_regs<T>(f: impl FnOnce(&[MessageRegisterValue]) -> T) -> T {
     sel4::with_borrow_ipc_buffer(|ipc_buffer| f(ipc_buffer.msg_regs()))
 }
@@ -56,6 +95,51 @@ pub fn with_msg_bytes_mut<T>(f: impl FnOnce(&mut [u8]) -> T) -> T {
     sel4::with_borrow_ipc_buffer_mut(|ipc_buffer| f(ipc_buffer.msg_bytes_mut()))
 }
 
+/// The index of a message register, checked when constructed, typically in a `const` context.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MessageRegisterIndex(usize);
+
+impl MessageRegisterIndex {
+    pub const fn new(index: usize) -> Self {
+        assert!(index < sel4::NUM_MESSAGE_REGISTERS);
+        Self(index)
+    }
+
+    pub const fn get(self) -> usize {
+        self.0
+    }
+}
+
+/// Like [`set_mr`], but with an index which was checked at construction. With the `release-fast`
+/// feature, no further bounds check is performed.
+pub fn set_mr_at(index: MessageRegisterIndex, value: MessageRegisterValue) {
+    with_msg_regs_mut(|regs| {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "release-fast")] {
+                // SAFETY: `index` was checked against the length of `regs` when constructed.
+                *unsafe { regs.get_unchecked_mut(index.get()) } = value
+            } else {
+                regs[index.get()] = value
+            }
+        }
+    })
+}
+
+/// Like [`get_mr`], but with an index which was checked at construction. With the `release-fast`
+/// feature, no further bounds check is performed.
+pub fn get_mr_at(index: MessageRegisterIndex) -> MessageRegisterValue {
+    with_msg_regs(|regs| {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "release-fast")] {
+                // SAFETY: `index` was checked against the length of `regs` when constructed.
+                *unsafe { regs.get_unchecked(index.get()) }
+            } else {
+                regs[index.get()]
+            }
+        }
+    })
+}
+
 pub fn set_mr(i: usize, value: MessageRegisterValue) {
     with_msg_regs_mut(|regs| regs[i] = value)
 }
@@ -0,0 +1,146 @@
+//! Controls over, and statistics about, the delivery of notifications to [`Handler::notified`].
+//!
+//! Notifications which arrive on several channels while a protection domain is busy are coalesced
+//! into a single badge, whose bits the main loop delivers one at a time.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cspace::Channel;
+use crate::dispatch::{with_dispatch, Dispatch};
+use crate::Handler;
+
+/// The number of channels whose notifications are counted in [`NotificationStats`].
+pub const NUM_CHANNELS: usize = sel4::WORD_SIZE;
+
+/// The order in which the main loop delivers notifications which arrive together. See
+/// [`Handler::notification_order`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NotificationOrder {
+    /// Lowest-numbered channel first.
+    #[default]
+    LowestFirst,
+    /// Starting just after the channel which was delivered first last time, wrapping around, so
+    /// that a busy low-numbered channel cannot systematically delay higher-numbered ones.
+    RoundRobin,
+    /// The listed channels first, in the order given, followed by any others, lowest-numbered
+    /// first.
+    Priority(&'static [Channel]),
+}
+
+/// A snapshot of notification delivery statistics, as returned by [`notification_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationStats {
+    /// The number of badges received, each of which includes notifications from one or more
+    /// channels.
+    pub badges: usize,
+    /// The number of badges which included notifications from more than one channel.
+    pub coalesced_badges: usize,
+    /// The number of notifications delivered from each channel.
+    pub delivered: [usize; NUM_CHANNELS],
+    /// The number of notifications from each channel which were delivered in the same badge as
+    /// notifications from other channels.
+    pub delivered_coalesced: [usize; NUM_CHANNELS],
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicUsize = AtomicUsize::new(0);
+
+static BADGES: AtomicUsize = ZERO;
+static COALESCED_BADGES: AtomicUsize = ZERO;
+static DELIVERED: [AtomicUsize; NUM_CHANNELS] = [ZERO; NUM_CHANNELS];
+static DELIVERED_COALESCED: [AtomicUsize; NUM_CHANNELS] = [ZERO; NUM_CHANNELS];
+
+pub fn notification_stats() -> NotificationStats {
+    let load = |counter: &AtomicUsize| counter.load(Ordering::Relaxed);
+    NotificationStats {
+        badges: load(&BADGES),
+        coalesced_badges: load(&COALESCED_BADGES),
+        delivered: core::array::from_fn(|i| load(&DELIVERED[i])),
+        delivered_coalesced: core::array::from_fn(|i| load(&DELIVERED_COALESCED[i])),
+    }
+}
+
+pub fn reset_notification_stats() {
+    for counter in [&BADGES, &COALESCED_BADGES]
+        .into_iter()
+        .chain(&DELIVERED)
+        .chain(&DELIVERED_COALESCED)
+    {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+fn record_badge(badge: sel4::Word) {
+    if cfg!(feature = "release-fast") {
+        return;
+    }
+    let coalesced = badge.count_ones() > 1;
+    BADGES.fetch_add(1, Ordering::Relaxed);
+    if coalesced {
+        COALESCED_BADGES.fetch_add(1, Ordering::Relaxed);
+    }
+    for_each_bit(badge, 0, |i| {
+        DELIVERED[i].fetch_add(1, Ordering::Relaxed);
+        if coalesced {
+            DELIVERED_COALESCED[i].fetch_add(1, Ordering::Relaxed);
+        }
+        Ok::<_, !>(())
+    })
+    .into_ok();
+}
+
+/// Delivers the notifications in `badge` to `handler`, in the order given by
+/// [`Handler::notification_order`]. `cursor` is the main loop's round-robin state.
+pub(crate) fn handle_notifications<T: Handler>(
+    handler: &mut T,
+    badge: sel4::Word,
+    cursor: &mut u32,
+) -> Result<(), T::Error> {
+    record_badge(badge);
+    let mut remaining = badge;
+    let start = match handler.notification_order() {
+        NotificationOrder::LowestFirst => 0,
+        NotificationOrder::RoundRobin => *cursor,
+        NotificationOrder::Priority(channels) => {
+            for channel in channels {
+                let bit = 1 << channel.index();
+                if remaining & bit != 0 {
+                    remaining &= !bit;
+                    deliver(handler, *channel)?;
+                }
+            }
+            0
+        }
+    };
+    let mut first = None;
+    for_each_bit(remaining, start, |i| {
+        first.get_or_insert(i);
+        deliver(handler, Channel::from_trusted_index(i))
+    })?;
+    if let Some(first) = first {
+        *cursor = (u32::try_from(first).unwrap() + 1) % sel4::Word::BITS;
+    }
+    Ok(())
+}
+
+fn deliver<T: Handler>(handler: &mut T, channel: Channel) -> Result<(), T::Error> {
+    with_dispatch(&Dispatch::Notified { channel }, || {
+        handler.notified(channel)
+    })
+}
+
+// Calls `f` with the index of each set bit of `bits`, in ascending order starting from `start` and
+// wrapping around.
+fn for_each_bit<E>(
+    bits: sel4::Word,
+    start: u32,
+    mut f: impl FnMut(usize) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut rotated = bits.rotate_right(start);
+    while rotated != 0 {
+        let j = rotated.trailing_zeros();
+        f(((j + start) % sel4::Word::BITS).try_into().unwrap())?;
+        rotated &= !(1 << j);
+    }
+    Ok(())
+}
@@ -7,25 +7,47 @@ pub use sel4_panicking::{
     SmallPayloadValue, UpcastIntoPayload,
 };
 
-use crate::pd_name;
+use crate::dispatch::current_dispatch;
+use crate::{pd_name, Channel};
 
 static PANIC_HOOK: ImmediateSyncOnceCell<PanicHook> = ImmediateSyncOnceCell::new();
 
+static FAULT_REPORT_CHANNEL: ImmediateSyncOnceCell<Channel> = ImmediateSyncOnceCell::new();
+
 pub fn set_hook(hook: PanicHook) {
     PANIC_HOOK.set(hook).unwrap_or_else(|_| panic!())
 }
 
+/// Sets a channel to be notified whenever this protection domain panics, after the panic hook has
+/// run, or its handler fails (see [`crate::error`]), so that a supervisor can observe the crash.
+pub fn set_fault_report_channel(channel: Channel) {
+    FAULT_REPORT_CHANNEL
+        .set(channel)
+        .unwrap_or_else(|_| panic!())
+}
+
 fn get_hook() -> &'static PanicHook {
     const DEFAULT_HOOK: PanicHook = &default_hook;
     PANIC_HOOK.get().unwrap_or(&DEFAULT_HOOK)
 }
 
+/// Prints the panic, along with the event which the main loop was dispatching, if any.
 fn default_hook(info: &ExternalPanicInfo) {
     debug_println!("{}: {}", pd_name(), info);
+    if let Some(dispatch) = current_dispatch() {
+        debug_println!("{}: while handling {}", pd_name(), dispatch);
+    }
 }
 
 fn outer_hook(info: &ExternalPanicInfo) {
-    (get_hook())(info)
+    (get_hook())(info);
+    notify_fault_report_channel();
+}
+
+pub(crate) fn notify_fault_report_channel() {
+    if let Some(channel) = FAULT_REPORT_CHANNEL.get() {
+        channel.notify();
+    }
 }
 
 pub(crate) fn init_panicking() {
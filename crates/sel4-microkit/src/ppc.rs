@@ -0,0 +1,137 @@
+//! Protected procedure calls whose arguments and results are passed directly in the message
+//! registers, following a simple convention: a reply with label `0` is a success, and a reply with
+//! any other label is an error whose code is that label.
+
+use core::fmt;
+
+use crate::cspace::Channel;
+use crate::liveness::PingTimeout;
+use crate::message::{
+    with_msg_regs, with_msg_regs_mut, MessageInfo, MessageLabel, MessageRegisterValue,
+};
+
+/// The label of a successful reply, by the convention of [`Channel::pp_call_with_mrs`].
+pub const PPC_OK_LABEL: MessageLabel = 0;
+
+impl Channel {
+    /// Makes a protected procedure call with `label` and with `args` in the message registers.
+    ///
+    /// A reply with label [`PPC_OK_LABEL`] yields a [`PpcReply`], whose results are in the message
+    /// registers. A reply with any other label yields [`PpcError::Callee`], with that label as the
+    /// error code. The callee builds its replies with [`ppc_reply_ok`] and [`ppc_reply_err`].
+    pub fn pp_call_with_mrs(
+        &self,
+        label: MessageLabel,
+        args: &[MessageRegisterValue],
+    ) -> Result<PpcReply, PpcError> {
+        if args.len() > sel4::NUM_MESSAGE_REGISTERS {
+            return Err(PpcError::TooManyArguments);
+        }
+        with_msg_regs_mut(|regs| regs[..args.len()].copy_from_slice(args));
+        let reply = self.pp_call(MessageInfo::new(label, args.len()));
+        match reply.label() {
+            PPC_OK_LABEL => Ok(PpcReply {
+                count: reply.count(),
+            }),
+            label => Err(PpcError::Callee { label }),
+        }
+    }
+
+    /// Like [`Channel::pp_call_with_mrs`], but first checks that the peer is running its main loop
+    /// with [`Channel::ping`], so that a crashed or not-yet-started peer yields
+    /// [`PpcError::Timeout`] rather than blocking this protection domain indefinitely.
+    ///
+    /// The kernel offers callers no way to bound the duration of the call itself, even on MCS
+    /// configurations, so a peer which accepts the call but never replies still blocks the caller.
+    /// The restrictions of [`Channel::ping_until`] apply.
+    pub fn pp_call_with_mrs_after_ping(
+        &self,
+        max_ping_attempts: usize,
+        label: MessageLabel,
+        args: &[MessageRegisterValue],
+    ) -> Result<PpcReply, PpcError> {
+        self.ping(max_ping_attempts).map_err(PpcError::Timeout)?;
+        self.pp_call_with_mrs(label, args)
+    }
+}
+
+/// Builds a successful reply to a call made with [`Channel::pp_call_with_mrs`], for return from
+/// [`Handler::protected`](crate::Handler::protected), with `results` in the message registers.
+pub fn ppc_reply_ok(results: &[MessageRegisterValue]) -> Result<MessageInfo, TooManyResults> {
+    if results.len() > sel4::NUM_MESSAGE_REGISTERS {
+        return Err(TooManyResults(()));
+    }
+    with_msg_regs_mut(|regs| regs[..results.len()].copy_from_slice(results));
+    Ok(MessageInfo::new(PPC_OK_LABEL, results.len()))
+}
+
+/// Builds an error reply to a call made with [`Channel::pp_call_with_mrs`], for return from
+/// [`Handler::protected`](crate::Handler::protected).
+///
+/// # Panics
+///
+/// Panics if `label` is [`PPC_OK_LABEL`].
+pub fn ppc_reply_err(label: MessageLabel) -> MessageInfo {
+    assert_ne!(label, PPC_OK_LABEL);
+    MessageInfo::new(label, 0)
+}
+
+/// A successful reply to [`Channel::pp_call_with_mrs`].
+///
+/// The results are read from the message registers, so they must be read before this protection
+/// domain sends or receives another message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PpcReply {
+    count: usize,
+}
+
+impl PpcReply {
+    /// The number of message registers in the reply.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the `i`th result, or `None` if the reply has fewer than `i + 1` message registers.
+    pub fn get(&self, i: usize) -> Option<MessageRegisterValue> {
+        (i < self.count).then(|| with_msg_regs(|regs| regs[i]))
+    }
+
+    /// Copies the results into an array, or returns `None` if the reply does not have exactly `N`
+    /// message registers.
+    pub fn to_array<const N: usize>(&self) -> Option<[MessageRegisterValue; N]> {
+        (self.count == N).then(|| with_msg_regs(|regs| regs[..N].try_into().unwrap()))
+    }
+}
+
+/// Error type returned by [`Channel::pp_call_with_mrs`] and
+/// [`Channel::pp_call_with_mrs_after_ping`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PpcError {
+    /// More arguments were given than there are message registers.
+    TooManyArguments,
+    /// The callee replied with an error code.
+    Callee { label: MessageLabel },
+    /// The peer did not respond to a ping.
+    Timeout(PingTimeout),
+}
+
+impl fmt::Display for PpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooManyArguments => write!(f, "too many arguments for the message registers"),
+            Self::Callee { label } => write!(f, "callee replied with error {label:#x}"),
+            Self::Timeout(err) => err.fmt(f),
+        }
+    }
+}
+
+/// Error returned by [`ppc_reply_ok`] when more results are given than there are message
+/// registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TooManyResults(());
+
+impl fmt::Display for TooManyResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "too many results for the message registers")
+    }
+}
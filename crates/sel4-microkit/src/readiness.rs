@@ -0,0 +1,117 @@
+//! A protocol for announcing and awaiting readiness during system bring-up.
+//!
+//! Each protection domain which others depend on owns a [`ReadinessFlag`] in a memory region that
+//! its dependents map read-only. Once it has initialized, and just before returning its
+//! [`Handler`] from its initialization function, it calls [`ReadinessFlag::set_ready`].
+//! Dependents call [`await_ready`] from their own initialization functions to wait until all of
+//! their dependencies are ready, or give up after a deadline, rather than racing them.
+//!
+//! Memory regions are zero-initialized by the `microkit` tool, so flags start out not ready.
+
+use core::fmt;
+use core::ptr::NonNull;
+use core::sync::atomic::{fence, Ordering};
+
+use sel4_externally_shared::ExternallySharedRef;
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+const NOT_READY: u32 = 0;
+const READY: u32 = 0x5245_4459; // "REDY"
+
+/// A word in shared memory through which a protection domain announces that it is ready to handle
+/// events.
+pub struct ReadinessFlag {
+    inner: ExternallySharedRef<'static, u32>,
+}
+
+impl ReadinessFlag {
+    /// # Safety
+    ///
+    /// `ptr` must be valid for the lifetime of the protection domain, and must only be written
+    /// through a [`ReadinessFlag`] by its owner.
+    pub unsafe fn new(ptr: NonNull<u32>) -> Self {
+        Self {
+            inner: ExternallySharedRef::new(ptr),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        let ready = self.inner.as_ptr().read() == READY;
+        if ready {
+            fence(Ordering::Acquire);
+        }
+        ready
+    }
+
+    /// Announces that the owner of this flag is ready. Writes to memory made before this call are
+    /// visible to any protection domain which subsequently observes the flag as ready.
+    pub fn set_ready(&mut self) {
+        fence(Ordering::Release);
+        self.inner.as_mut_ptr().write(READY);
+    }
+
+    pub fn clear(&mut self) {
+        self.inner.as_mut_ptr().write(NOT_READY);
+    }
+}
+
+impl fmt::Debug for ReadinessFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReadinessFlag")
+            .field("ready", &self.is_ready())
+            .finish()
+    }
+}
+
+/// Yields until each of `dependencies` is ready, or until `deadline_has_passed` returns `true`.
+///
+/// This waits by calling `seL4_Yield`, which only gives way to threads of the same priority. A
+/// dependency with a higher priority than the caller preempts it whenever it is runnable, and one
+/// with the same priority runs when the caller yields, but one with a lower priority never runs
+/// while the caller waits, so the caller would spin until its deadline. So each dependency must
+/// have a priority at least as high as the caller's.
+///
+/// A protection domain cannot observe priorities at run time, so this is not checked here.
+/// Systems described with the `sel4-system-composition` crate can declare their readiness
+/// dependencies with its `SystemBuilder::awaits_ready`, in which case the requirement is checked
+/// when the system is built.
+///
+/// On timeout, returns the index of the first dependency which was not ready.
+pub fn await_ready(
+    dependencies: &[&ReadinessFlag],
+    mut deadline_has_passed: impl FnMut() -> bool,
+) -> Result<(), ReadinessTimeout> {
+    loop {
+        match dependencies.iter().position(|flag| !flag.is_ready()) {
+            None => return Ok(()),
+            Some(index) => {
+                if deadline_has_passed() {
+                    return Err(ReadinessTimeout { index });
+                }
+                sel4::r#yield();
+            }
+        }
+    }
+}
+
+/// Error type returned by [`await_ready`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReadinessTimeout {
+    index: usize,
+}
+
+impl ReadinessTimeout {
+    /// The index of the first dependency which was not ready when the deadline passed.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for ReadinessTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "dependency {} did not become ready in time", self.index)
+    }
+}
@@ -0,0 +1,175 @@
+//! Answering a protected procedure call after [`Handler::protected`] has returned, for example
+//! once a request to another protection domain on which the answer depends has completed.
+//!
+//! A handler calls [`defer_reply`] from within [`Handler::protected`], in which case the
+//! [`MessageInfo`] returned by `protected` is discarded, and later answers the call with
+//! [`DeferredReply::reply`].
+//!
+//! A deferred call stays bound to the reply object with which it was received, so the main loop
+//! must receive subsequent calls with another one. A protection domain has only one reply object
+//! of its own, so deferral requires spare reply objects, provided with [`add_reply_objects`]. Each
+//! outstanding deferred reply holds one of them, and [`defer_reply`] fails if none is left, in
+//! which case the handler must answer the call from [`Handler::protected`] as usual.
+//!
+//! # Provisioning spare reply objects
+//!
+//! A protection domain has no untyped memory from which to create reply objects, and the seL4
+//! Microkit tool cannot provision them, so a system built with that tool alone cannot defer
+//! replies. Instead, describe the system with the `sel4-system-composition` crate, giving the
+//! protection domain spare reply objects with its `ProtectionDomain::spare_reply_objects`, and
+//! build it from the CapDL spec emitted by `System::to_capdl_spec`. The spec places capabilities
+//! for them in a range of CSpace slots reserved for that purpose, and the protection domain then
+//! calls [`add_provisioned_reply_objects`] with the same number during initialization.
+
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cspace::{slot_to_local_cptr, Channel, BASE_SPARE_REPLY_CAP, REPLY_CAP};
+use crate::message::MessageInfo;
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+const NONE: usize = usize::MAX;
+
+/// The maximum number of spare reply objects that can be provided with [`add_reply_objects`].
+pub const MAX_SPARE_REPLY_OBJECTS: usize = 16;
+
+// The channel whose call is being handled by `Handler::protected`, if it has not been deferred.
+static CURRENT_CALLER: AtomicUsize = AtomicUsize::new(NONE);
+
+// The reply object with which the main loop receives.
+static RECV_REPLY_OBJECT: AtomicUsize = AtomicUsize::new(REPLY_CAP.bits() as usize);
+
+// Reply objects which are bound neither to the main loop nor to a deferred call, as a stack.
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_REPLY_OBJECT: AtomicUsize = AtomicUsize::new(NONE);
+static SPARE_REPLY_OBJECTS: [AtomicUsize; MAX_SPARE_REPLY_OBJECTS] =
+    [NO_REPLY_OBJECT; MAX_SPARE_REPLY_OBJECTS];
+static NUM_SPARE_REPLY_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+
+/// The right to answer a protected procedure call whose reply has been deferred. See
+/// [`crate::reply`].
+#[derive(Debug)]
+#[must_use = "the caller remains blocked until the deferred reply is answered"]
+pub struct DeferredReply {
+    channel: Channel,
+    reply_object: sel4::Reply,
+}
+
+impl DeferredReply {
+    /// The channel on which the deferred call was made.
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// Answers the deferred call with the message in the IPC buffer described by `msg_info`.
+    pub fn reply(self, msg_info: MessageInfo) {
+        // An `seL4_Send` on a reply object answers the call bound to it.
+        self.reply_object
+            .cast::<sel4::cap_type::Endpoint>()
+            .send(msg_info.into_sel4());
+        push_spare(self.reply_object);
+    }
+}
+
+/// Error returned by [`defer_reply`] when no spare reply object is available.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NoSpareReplyObject(());
+
+impl fmt::Display for NoSpareReplyObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no spare reply object with which to defer a reply")
+    }
+}
+
+/// Provides reply objects, in addition to this protection domain's own, which allow that many
+/// replies to be deferred at once.
+///
+/// # Panics
+///
+/// Panics if more than [`MAX_SPARE_REPLY_OBJECTS`] are provided in total.
+pub fn add_reply_objects(reply_objects: impl IntoIterator<Item = sel4::Reply>) {
+    for reply_object in reply_objects {
+        push_spare(reply_object);
+    }
+}
+
+/// Provides the `n` spare reply objects placed in this protection domain's CSpace by the system's
+/// CapDL spec. See [the module documentation](self#provisioning-spare-reply-objects).
+///
+/// # Panics
+///
+/// Panics if more than [`MAX_SPARE_REPLY_OBJECTS`] are provided in total.
+pub fn add_provisioned_reply_objects(n: usize) {
+    add_reply_objects((0..n).map(|i| slot_to_local_cptr(BASE_SPARE_REPLY_CAP + i)));
+}
+
+/// Defers the reply to the protected procedure call currently being handled.
+///
+/// Fails if all spare reply objects are held by other deferred replies, in which case the call
+/// must be answered by [`Handler::protected`].
+///
+/// # Panics
+///
+/// Panics if not called from within [`Handler::protected`], or if called more than once for the
+/// same call.
+pub fn defer_reply() -> Result<DeferredReply, NoSpareReplyObject> {
+    let index = CURRENT_CALLER.load(Ordering::Relaxed);
+    assert!(
+        index != NONE,
+        "defer_reply() called outside of Handler::protected()"
+    );
+    let spare = pop_spare().ok_or(NoSpareReplyObject(()))?;
+    CURRENT_CALLER.store(NONE, Ordering::Relaxed);
+    let reply_object = sel4::Reply::from_bits(
+        RECV_REPLY_OBJECT
+            .swap(spare.bits().try_into().unwrap(), Ordering::Relaxed)
+            .try_into()
+            .unwrap(),
+    );
+    Ok(DeferredReply {
+        channel: Channel::from_trusted_index(index),
+        reply_object,
+    })
+}
+
+/// Calls `f`, which is to run `Handler::protected` for a call on `channel`, returning `None` if
+/// the reply was deferred.
+pub(crate) fn with_current_caller<E>(
+    channel: Channel,
+    f: impl FnOnce() -> Result<MessageInfo, E>,
+) -> Result<Option<MessageInfo>, E> {
+    CURRENT_CALLER.store(channel.index(), Ordering::Relaxed);
+    let result = f();
+    let deferred = CURRENT_CALLER.swap(NONE, Ordering::Relaxed) == NONE;
+    let msg_info = result?;
+    Ok((!deferred).then_some(msg_info))
+}
+
+/// The reply object with which the main loop is to receive.
+pub(crate) fn recv_reply_object() -> sel4::Reply {
+    sel4::Reply::from_bits(
+        RECV_REPLY_OBJECT
+            .load(Ordering::Relaxed)
+            .try_into()
+            .unwrap(),
+    )
+}
+
+fn push_spare(reply_object: sel4::Reply) {
+    let n = NUM_SPARE_REPLY_OBJECTS.load(Ordering::Relaxed);
+    assert!(n < MAX_SPARE_REPLY_OBJECTS, "too many spare reply objects");
+    SPARE_REPLY_OBJECTS[n].store(reply_object.bits().try_into().unwrap(), Ordering::Relaxed);
+    NUM_SPARE_REPLY_OBJECTS.store(n + 1, Ordering::Relaxed);
+}
+
+fn pop_spare() -> Option<sel4::Reply> {
+    let n = NUM_SPARE_REPLY_OBJECTS
+        .load(Ordering::Relaxed)
+        .checked_sub(1)?;
+    NUM_SPARE_REPLY_OBJECTS.store(n, Ordering::Relaxed);
+    let bits = SPARE_REPLY_OBJECTS[n].swap(NONE, Ordering::Relaxed);
+    Some(sel4::Reply::from_bits(bits.try_into().unwrap()))
+}
@@ -0,0 +1,151 @@
+//! Cooperative, ordered shutdown of a system's protection domains.
+//!
+//! A supervising protection domain shuts down its peers one at a time, in an order in which each
+//! protection domain is stopped only after every protection domain which depends on it, so that
+//! clients stop before the drivers they use, and drivers stop before the buses behind them. The
+//! `sel4-microkit-shutdown-order` CLI derives such an order from the system description, and
+//! emits it as a configuration entry for the supervisor under [`SHUTDOWN_ORDER_KEY`].
+//!
+//! Each step consists of two protected procedure calls, with the reserved labels
+//! [`QUIESCE_LABEL`] and [`STOP_LABEL`], which the main loop of the peer (see [`Handler`]) answers
+//! on behalf of its handler:
+//!
+//! - In response to a quiesce request, the main loop calls [`Handler::quiesce`]. A handler which
+//!   needs more time to tear down, for example to drain in-flight requests, returns
+//!   [`Quiesce::InProgress`], and continues to handle events as usual until the supervisor asks
+//!   again.
+//! - In response to a stop request, the main loop calls [`Handler::stop`], after which it no
+//!   longer delivers events to the handler. Notifications are dropped, further quiesce and stop
+//!   requests succeed immediately, and other protected procedure calls are answered with an empty
+//!   message with the label [`STOP_LABEL`].
+//!
+//! Supervised protection domains must accept protected procedure calls on their channel with the
+//! supervisor, which therefore must have a lower priority than each of them.
+
+use core::fmt;
+
+use crate::config::{self, ConfigError};
+use crate::cspace::{Channel, MAX_CHANNELS};
+use crate::message::{MessageInfo, MessageLabel, ReservedLabel};
+
+// For rustdoc.
+#[allow(unused_imports)]
+use crate::Handler;
+
+/// The label reserved for quiesce requests. Protected procedure calls with this label never reach
+/// [`Handler::protected`].
+pub const QUIESCE_LABEL: MessageLabel = ReservedLabel::Quiesce.label();
+
+/// The label reserved for stop requests. Protected procedure calls with this label never reach
+/// [`Handler::protected`].
+pub const STOP_LABEL: MessageLabel = ReservedLabel::Stop.label();
+
+/// The configuration key under which a supervisor's shutdown order is stored, as a
+/// comma-separated list of channel indices. See [`shutdown_order`].
+pub const SHUTDOWN_ORDER_KEY: &str = "shutdown_order";
+
+const QUIESCE_DONE: MessageLabel = 0;
+const QUIESCE_IN_PROGRESS: MessageLabel = 1;
+
+/// Returned by [`Handler::quiesce`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Quiesce {
+    /// The handler is ready to be stopped.
+    Done,
+    /// The handler needs more time, and will be asked again.
+    InProgress,
+}
+
+impl Quiesce {
+    pub(crate) fn into_msg_info(self) -> MessageInfo {
+        MessageInfo::new(
+            match self {
+                Self::Done => QUIESCE_DONE,
+                Self::InProgress => QUIESCE_IN_PROGRESS,
+            },
+            0,
+        )
+    }
+}
+
+impl Channel {
+    /// Asks the peer on this channel to quiesce, repeating the request until the peer is done or
+    /// `deadline_has_passed` returns `true`.
+    pub fn quiesce_until(
+        &self,
+        mut deadline_has_passed: impl FnMut() -> bool,
+    ) -> Result<(), ShutdownTimeout> {
+        loop {
+            let resp = self.pp_call(MessageInfo::new(QUIESCE_LABEL, 0));
+            if resp.label() == QUIESCE_DONE {
+                return Ok(());
+            }
+            if deadline_has_passed() {
+                return Err(ShutdownTimeout { channel: *self });
+            }
+            sel4::r#yield();
+        }
+    }
+
+    /// Asks the peer on this channel to stop handling events. The peer should already have been
+    /// quiesced with [`Channel::quiesce_until`].
+    pub fn stop(&self) {
+        self.pp_call(MessageInfo::new(STOP_LABEL, 0));
+    }
+}
+
+/// Quiesces and then stops the peer on each channel in `order`, one at a time.
+///
+/// `deadline_has_passed` is consulted while waiting for the peer on the given channel to quiesce.
+/// On timeout, the peer is left running, as are all those which come after it in `order`.
+pub fn shutdown(
+    order: impl IntoIterator<Item = Channel>,
+    mut deadline_has_passed: impl FnMut(Channel) -> bool,
+) -> Result<(), ShutdownTimeout> {
+    for channel in order {
+        channel.quiesce_until(|| deadline_has_passed(channel))?;
+        channel.stop();
+    }
+    Ok(())
+}
+
+/// Returns the shutdown order stored in this protection domain's configuration page under
+/// [`SHUTDOWN_ORDER_KEY`].
+pub fn shutdown_order() -> Result<impl Iterator<Item = Channel>, ConfigError> {
+    let value = config::get_str(SHUTDOWN_ORDER_KEY).ok_or(ConfigError::Missing)?;
+    let parse = |index: &str| {
+        index
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < MAX_CHANNELS)
+    };
+    if value.split(',').any(|index| parse(index).is_none()) {
+        return Err(ConfigError::Invalid);
+    }
+    Ok(value
+        .split(',')
+        .map(move |index| Channel::new(parse(index).unwrap())))
+}
+
+/// Error type returned by [`Channel::quiesce_until`] and [`shutdown`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShutdownTimeout {
+    channel: Channel,
+}
+
+impl ShutdownTimeout {
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+}
+
+impl fmt::Display for ShutdownTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "peer on channel {:?} did not quiesce in time",
+            self.channel
+        )
+    }
+}
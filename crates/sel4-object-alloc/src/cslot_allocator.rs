@@ -0,0 +1,35 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use sel4::InitCSpaceSlot;
+
+/// Allocates slots in the root task's CSpace, reusing slots which have been freed before taking
+/// fresh ones.
+#[derive(Debug)]
+pub struct CSlotAllocator {
+    fresh: Range<InitCSpaceSlot>,
+    freed: Vec<InitCSpaceSlot>,
+}
+
+impl CSlotAllocator {
+    /// Allocates from `empty`, which is typically [`BootInfo::empty`](sel4::BootInfo::empty).
+    pub fn new(empty: Range<InitCSpaceSlot>) -> Self {
+        Self {
+            fresh: empty,
+            freed: Vec::new(),
+        }
+    }
+
+    pub fn alloc(&mut self) -> Option<InitCSpaceSlot> {
+        self.freed.pop().or_else(|| self.fresh.next())
+    }
+
+    /// Returns `slot`, which must be empty, to the allocator.
+    pub fn free(&mut self, slot: InitCSpaceSlot) {
+        self.freed.push(slot)
+    }
+
+    pub fn num_free(&self) -> usize {
+        self.fresh.len() + self.freed.len()
+    }
+}
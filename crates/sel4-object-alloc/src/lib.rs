@@ -0,0 +1,270 @@
+//! An allocator of kernel objects for root tasks, over the untyped capabilities described by the
+//! [`BootInfo`].
+//!
+//! Objects are retyped from each untyped at a watermark which mirrors the kernel's own free index.
+//! The kernel only reuses the memory of an untyped once it has no children, so memory becomes
+//! available again either when every object allocated from an untyped has been freed with
+//! [`ObjectAllocator::free`], or when the untyped is revoked with [`ObjectAllocator::revoke`].
+//! [`ObjectAllocator::split`] carves a child untyped out of the pool, so that a group of objects can
+//! be revoked together without disturbing the rest.
+//!
+//! Capability slots for objects are taken from a [`CSlotAllocator`], which is also available for
+//! other uses with [`ObjectAllocator::cslots`]. The allocator does not keep track of copies of
+//! the capabilities it hands out. An object should only be freed once any copies have been
+//! deleted.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use sel4::{
+    cap_type, vspace::PagingStructureAllocator, BootInfo, CNode, CapType, InitCSpaceSlot,
+    LocalCPtr, ObjectBlueprint, Unspecified, Untyped,
+};
+
+mod cslot_allocator;
+
+pub use cslot_allocator::CSlotAllocator;
+
+/// How [`ObjectAllocator`] chooses which untyped to allocate an object from.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Use the first untyped with room for the object at its watermark.
+    #[default]
+    FirstFit,
+    /// Use the untyped which would have the least room left after allocating the object, leaving
+    /// larger untypeds for larger objects.
+    BestFit,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    OutOfSlots,
+    OutOfMemory,
+    Kernel(sel4::Error),
+}
+
+impl From<sel4::Error> for Error {
+    fn from(err: sel4::Error) -> Self {
+        Self::Kernel(err)
+    }
+}
+
+/// Identifies an untyped tracked by an [`ObjectAllocator`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UntypedId(usize);
+
+pub struct ObjectAllocator {
+    cnode: CNode,
+    cslots: CSlotAllocator,
+    strategy: Strategy,
+    // Indexed by `UntypedId`. `None` once deleted by the revocation of an ancestor.
+    untypeds: Vec<Option<UntypedState>>,
+    // The untyped from which each live object was allocated.
+    objects: BTreeMap<InitCSpaceSlot, UntypedId>,
+}
+
+struct UntypedState {
+    slot: InitCSpaceSlot,
+    size_bits: usize,
+    parent: Option<UntypedId>,
+    watermark: usize,
+    num_children: usize,
+}
+
+impl UntypedState {
+    fn new(slot: InitCSpaceSlot, size_bits: usize, parent: Option<UntypedId>) -> Self {
+        Self {
+            slot,
+            size_bits,
+            parent,
+            watermark: 0,
+            num_children: 0,
+        }
+    }
+
+    // Returns the offset at which an object of the given size would be placed.
+    fn offset_for(&self, size_bits: usize) -> Option<usize> {
+        if size_bits > self.size_bits {
+            return None;
+        }
+        let mask = (1 << size_bits) - 1;
+        let offset = (self.watermark + mask) & !mask;
+        (offset + (1 << size_bits) <= 1 << self.size_bits).then_some(offset)
+    }
+
+    fn room_after(&self, offset: usize, size_bits: usize) -> usize {
+        (1 << self.size_bits) - (offset + (1 << size_bits))
+    }
+
+    fn reset(&mut self) {
+        self.watermark = 0;
+        self.num_children = 0;
+    }
+}
+
+impl ObjectAllocator {
+    /// Allocates from the non-device untypeds in `bootinfo`, and into its empty slots.
+    pub fn new(bootinfo: &BootInfo, strategy: Strategy) -> Self {
+        let untypeds = bootinfo
+            .untyped()
+            .zip(bootinfo.untyped_list())
+            .filter(|(_, desc)| !desc.is_device())
+            .map(|(slot, desc)| Some(UntypedState::new(slot, desc.size_bits(), None)))
+            .collect();
+        Self {
+            cnode: BootInfo::init_thread_cnode(),
+            cslots: CSlotAllocator::new(bootinfo.empty()),
+            strategy,
+            untypeds,
+            objects: BTreeMap::new(),
+        }
+    }
+
+    pub fn cslots(&mut self) -> &mut CSlotAllocator {
+        &mut self.cslots
+    }
+
+    /// Retypes an object of the given blueprint into a fresh slot.
+    pub fn allocate<T: CapType>(
+        &mut self,
+        blueprint: ObjectBlueprint,
+    ) -> Result<LocalCPtr<T>, Error> {
+        let (id, slot) = self.allocate_slot(blueprint)?;
+        self.objects.insert(slot, id);
+        Ok(BootInfo::init_cspace_local_cptr(slot))
+    }
+
+    /// Deletes an object allocated with [`ObjectAllocator::allocate`], and frees its slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` was not allocated by this allocator, or has already been freed.
+    pub fn free<T: CapType>(&mut self, cap: LocalCPtr<T>) -> Result<(), Error> {
+        let slot: InitCSpaceSlot = cap.bits().try_into().unwrap();
+        let id = *self.objects.get(&slot).unwrap();
+        self.cnode.relative(cap).delete()?;
+        self.objects.remove(&slot);
+        self.cslots.free(slot);
+        let state = self.state_mut(id);
+        state.num_children -= 1;
+        if state.num_children == 0 {
+            state.reset();
+        }
+        Ok(())
+    }
+
+    /// Retypes a child untyped of `size_bits` out of the pool, and adds it to the pool.
+    ///
+    /// Objects are allocated from the child like from any other untyped, and can all be deleted at
+    /// once by revoking it.
+    pub fn split(&mut self, size_bits: usize) -> Result<UntypedId, Error> {
+        let (parent, slot) = self.allocate_slot(ObjectBlueprint::Untyped { size_bits })?;
+        self.untypeds
+            .push(Some(UntypedState::new(slot, size_bits, Some(parent))));
+        Ok(UntypedId(self.untypeds.len() - 1))
+    }
+
+    /// The untyped from which `cap` was allocated, if it was allocated by this allocator.
+    pub fn untyped_of<T: CapType>(&self, cap: LocalCPtr<T>) -> Option<UntypedId> {
+        let slot: InitCSpaceSlot = cap.bits().try_into().unwrap();
+        self.objects.get(&slot).copied()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `id` was deleted by the revocation of an ancestor.
+    pub fn untyped(&self, id: UntypedId) -> Untyped {
+        BootInfo::init_cspace_local_cptr(self.state(id).slot)
+    }
+
+    /// Revokes an untyped, which deletes every object allocated from it and every untyped split
+    /// from it. Their slots are freed, and the untyped's memory becomes available again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was deleted by the revocation of an ancestor.
+    pub fn revoke(&mut self, id: UntypedId) -> Result<(), Error> {
+        self.cnode.relative(self.untyped(id)).revoke()?;
+        let is_strict_descendant = |untypeds: &[Option<UntypedState>], mut other: UntypedId| {
+            while let Some(parent) = untypeds[other.0].as_ref().and_then(|state| state.parent) {
+                if parent == id {
+                    return true;
+                }
+                other = parent;
+            }
+            false
+        };
+        let deleted = (0..self.untypeds.len())
+            .map(UntypedId)
+            .filter(|other| is_strict_descendant(&self.untypeds, *other))
+            .collect::<Vec<_>>();
+        self.objects.retain(|slot, parent| {
+            let retain = *parent != id && !deleted.contains(parent);
+            if !retain {
+                self.cslots.free(*slot);
+            }
+            retain
+        });
+        for other in deleted {
+            let state = self.untypeds[other.0].take().unwrap();
+            self.cslots.free(state.slot);
+        }
+        self.state_mut(id).reset();
+        Ok(())
+    }
+
+    fn allocate_slot(
+        &mut self,
+        blueprint: ObjectBlueprint,
+    ) -> Result<(UntypedId, InitCSpaceSlot), Error> {
+        let size_bits = blueprint.physical_size_bits();
+        let (id, offset) = self.choose(size_bits).ok_or(Error::OutOfMemory)?;
+        let slot = self.cslots.alloc().ok_or(Error::OutOfSlots)?;
+        if let Err(err) =
+            self.untyped(id)
+                .untyped_retype(&blueprint, &self.cnode.relative_self(), slot, 1)
+        {
+            self.cslots.free(slot);
+            return Err(err.into());
+        }
+        let state = self.state_mut(id);
+        state.watermark = offset + (1 << size_bits);
+        state.num_children += 1;
+        Ok((id, slot))
+    }
+
+    fn choose(&self, size_bits: usize) -> Option<(UntypedId, usize)> {
+        let mut candidates = self.untypeds.iter().enumerate().filter_map(|(i, state)| {
+            let state = state.as_ref()?;
+            let offset = state.offset_for(size_bits)?;
+            Some((UntypedId(i), offset, state.room_after(offset, size_bits)))
+        });
+        let (id, offset, _) = match self.strategy {
+            Strategy::FirstFit => candidates.next(),
+            Strategy::BestFit => candidates.min_by_key(|(_, _, room)| *room),
+        }?;
+        Some((id, offset))
+    }
+
+    fn state(&self, id: UntypedId) -> &UntypedState {
+        self.untypeds[id.0].as_ref().unwrap()
+    }
+
+    fn state_mut(&mut self, id: UntypedId) -> &mut UntypedState {
+        self.untypeds[id.0].as_mut().unwrap()
+    }
+}
+
+impl PagingStructureAllocator for ObjectAllocator {
+    fn allocate(&mut self, blueprint: ObjectBlueprint) -> sel4::Result<Unspecified> {
+        self.allocate::<cap_type::Unspecified>(blueprint)
+            .map_err(|err| match err {
+                Error::OutOfSlots | Error::OutOfMemory => sel4::Error::NotEnoughMemory,
+                Error::Kernel(err) => err,
+            })
+    }
+}
@@ -27,6 +27,8 @@ macro_rules! declare_stack {
     ($size:expr) => {
         #[no_mangle]
         static __sel4_runtime_stack_top: $crate::_private::start::StackTop = {
+            // Named so that linker scripts can place the stack, for example between guard pages.
+            #[link_section = ".bss.__sel4_runtime_stack"]
             static mut STACK: $crate::_private::start::Stack<{ $size }> =
                 $crate::_private::start::Stack::new();
             unsafe { STACK.top() }
@@ -0,0 +1,74 @@
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+/// The local view of the ownership of a range of a [`SharedArena`](crate::SharedArena).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum State {
+    /// Referenced by exactly one [`SharedBytesMut`](crate::SharedBytesMut).
+    Exclusive,
+    /// Referenced by one or more [`SharedBytes`](crate::SharedBytes).
+    Frozen,
+    /// Owned by a peer, which may hand it back using a [`Transfer`](crate::Transfer).
+    Relinquished,
+}
+
+/// Tracks the state of each range handed out by a [`SharedArena`](crate::SharedArena), and panics
+/// on transitions which would allow a range to be aliased.
+#[derive(Debug, Default)]
+pub(crate) struct Ledger {
+    entries: BTreeMap<usize, (usize, State)>,
+}
+
+impl Ledger {
+    pub(crate) fn claim(&mut self, range: &Range<usize>) {
+        if let Some((start, (end, state))) = self.overlapping(range) {
+            panic!(
+                "claimed range {range:?} overlaps range {start:#x}..{end:#x} in state {state:?}"
+            );
+        }
+        self.entries
+            .insert(range.start, (range.end, State::Exclusive));
+    }
+
+    pub(crate) fn accept(&mut self, range: &Range<usize>) {
+        match self.entries.get(&range.start) {
+            Some((end, State::Relinquished)) if *end == range.end => {
+                self.entries
+                    .insert(range.start, (range.end, State::Exclusive));
+            }
+            _ => self.claim(range),
+        }
+    }
+
+    pub(crate) fn transition(&mut self, range: &Range<usize>, from: State, to: State) {
+        let state = self.get_mut(range);
+        assert_eq!(*state, from, "invalid transition for range {range:?}");
+        *state = to;
+    }
+
+    pub(crate) fn release(&mut self, range: &Range<usize>, from: State) {
+        assert_eq!(
+            *self.get_mut(range),
+            from,
+            "invalid release of range {range:?}"
+        );
+        self.entries.remove(&range.start);
+    }
+
+    fn get_mut(&mut self, range: &Range<usize>) -> &mut State {
+        match self.entries.get_mut(&range.start) {
+            Some((end, state)) if *end == range.end => state,
+            _ => panic!("range {range:?} is not tracked"),
+        }
+    }
+
+    fn overlapping(&self, range: &Range<usize>) -> Option<(usize, (usize, State))> {
+        // Entries never overlap one another, so only the last one starting before the end of
+        // `range` can overlap it.
+        self.entries
+            .range(..range.end)
+            .next_back()
+            .filter(|(_, (end, _))| *end > range.start)
+            .map(|(start, entry)| (*start, *entry))
+    }
+}
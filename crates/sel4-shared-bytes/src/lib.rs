@@ -0,0 +1,384 @@
+//! Handles to ranges of memory shared between protection domains, with explicit ownership
+//! transfer.
+//!
+//! Each protection domain sharing a region of memory constructs a [`SharedArena`] over its own
+//! mapping of that region. At any point in time, each range of the arena is owned by at most one
+//! protection domain, which accesses it through a [`SharedBytesMut`] (exclusive and mutable) or
+//! one or more [`SharedBytes`] (frozen and immutable). Ownership moves between protection
+//! domains using the following handshake:
+//!
+//! 1. The producer relinquishes its handle, obtaining a [`Transfer`]
+//!    ([`SharedBytesMut::relinquish`] or [`SharedBytes::try_relinquish`]).
+//! 2. Only then does it signal the consumer, for example by sending the [`Transfer`] in a
+//!    protected procedure call.
+//! 3. The consumer takes ownership of the range with [`SharedArena::accept`].
+//!
+//! The consumer may later hand the range back in the same way, at which point the producer
+//! [`accept`](SharedArena::accept)s it again, or the producer may [`forget`](SharedArena::forget)
+//! the range if the consumer is to keep it.
+//!
+//! Each arena tracks the local state of every range it has handed out, and panics on any step which
+//! would leave a range aliased, such as claiming or accepting a range that overlaps one which is
+//! still held locally or has been relinquished.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut, Range};
+use core::ptr::{self, NonNull};
+use core::slice;
+use core::str::{self, Utf8Error};
+
+use zerocopy::{AsBytes, FromBytes};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+mod ledger;
+
+use ledger::{Ledger, State};
+
+/// One protection domain's view of a region of memory shared with its peers.
+#[derive(Clone)]
+pub struct SharedArena {
+    inner: Rc<SharedArenaInner>,
+}
+
+struct SharedArenaInner {
+    region: NonNull<[u8]>,
+    ledger: RefCell<Ledger>,
+}
+
+impl SharedArena {
+    /// # Safety
+    ///
+    /// `region` must be valid for reads and writes for as long as this arena or any handle derived
+    /// from it exists. Every protection domain sharing the underlying memory must follow the
+    /// handshake described in the [crate-level documentation](crate), only accessing ranges which
+    /// it currently owns.
+    pub unsafe fn new(region: NonNull<[u8]>) -> Self {
+        Self {
+            inner: Rc::new(SharedArenaInner {
+                region,
+                ledger: RefCell::new(Ledger::default()),
+            }),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.inner.region.len()
+    }
+
+    /// Takes ownership of a range which this protection domain owns by convention, for example
+    /// because it was allocated by a bounce buffer allocator which manages this arena.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range overlaps one which is held locally or has been relinquished.
+    pub fn claim(&self, range: Range<usize>) -> SharedBytesMut {
+        self.check_range(&range);
+        self.ledger().claim(&range);
+        SharedBytesMut::new(self.clone(), range)
+    }
+
+    /// Takes ownership of a range which a peer has relinquished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range does not fit in the address space, overlaps one which is held locally,
+    /// or overlaps one which has been relinquished other than exactly this range.
+    pub fn accept(&self, transfer: Transfer) -> SharedBytesMut {
+        let range = transfer.range().unwrap();
+        self.check_range(&range);
+        self.ledger().accept(&range);
+        SharedBytesMut::new(self.clone(), range)
+    }
+
+    /// Gives up any claim to a range previously relinquished by this protection domain, so that
+    /// the range can be [`claim`](Self::claim)ed afresh once a peer hands it back out of band.
+    pub fn forget(&self, transfer: Transfer) {
+        self.ledger()
+            .release(&transfer.range().unwrap(), State::Relinquished);
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(!range.is_empty());
+        assert!(range.end <= self.size());
+    }
+
+    fn ledger(&self) -> core::cell::RefMut<'_, Ledger> {
+        self.inner.ledger.borrow_mut()
+    }
+
+    fn ptr(&self, range: &Range<usize>) -> *mut u8 {
+        unsafe { self.inner.region.cast::<u8>().as_ptr().add(range.start) }
+    }
+}
+
+impl fmt::Debug for SharedArena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedArena")
+            .field("region", &self.inner.region)
+            .finish()
+    }
+}
+
+/// The message which moves ownership of a range of a [`SharedArena`] from one protection domain
+/// to another.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, AsBytes, FromBytes)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transfer {
+    offset: usize,
+    len: usize,
+}
+
+impl Transfer {
+    pub fn new(offset: usize, len: usize) -> Self {
+        Self { offset, len }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The range of the arena that this transfer refers to, or `None` if its end does not fit in
+    /// a `usize`. A transfer received from a peer may have any offset and length.
+    pub fn range(&self) -> Option<Range<usize>> {
+        Some(self.offset..self.offset.checked_add(self.len)?)
+    }
+
+    fn from_range(range: Range<usize>) -> Self {
+        Self::new(range.start, range.len())
+    }
+}
+
+/// Exclusive, mutable access to a range of a [`SharedArena`].
+pub struct SharedBytesMut {
+    arena: SharedArena,
+    range: Range<usize>,
+}
+
+impl SharedBytesMut {
+    fn new(arena: SharedArena, range: Range<usize>) -> Self {
+        Self { arena, range }
+    }
+
+    pub fn arena(&self) -> &SharedArena {
+        &self.arena
+    }
+
+    /// The range of the arena that this handle refers to.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Gives up access to this range so that a peer can [`accept`](SharedArena::accept) it.
+    pub fn relinquish(self) -> Transfer {
+        let (arena, range) = self.into_parts();
+        arena
+            .ledger()
+            .transition(&range, State::Exclusive, State::Relinquished);
+        Transfer::from_range(range)
+    }
+
+    /// Gives up mutable access to this range in exchange for a handle which can be cloned.
+    pub fn freeze(self) -> SharedBytes {
+        let (arena, range) = self.into_parts();
+        arena
+            .ledger()
+            .transition(&range, State::Exclusive, State::Frozen);
+        SharedBytes {
+            inner: Rc::new(SharedBytesInner { arena, range }),
+        }
+    }
+
+    fn into_parts(self) -> (SharedArena, Range<usize>) {
+        let this = ManuallyDrop::new(self);
+        let arena = unsafe { ptr::read(&this.arena) };
+        (arena, this.range.clone())
+    }
+}
+
+impl Deref for SharedBytesMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.arena.ptr(&self.range), self.range.len()) }
+    }
+}
+
+impl DerefMut for SharedBytesMut {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.arena.ptr(&self.range), self.range.len()) }
+    }
+}
+
+impl Drop for SharedBytesMut {
+    fn drop(&mut self) {
+        self.arena.ledger().release(&self.range, State::Exclusive);
+    }
+}
+
+impl fmt::Debug for SharedBytesMut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedBytesMut")
+            .field("range", &self.range)
+            .finish()
+    }
+}
+
+/// Shared, immutable access to a range of a [`SharedArena`].
+#[derive(Clone)]
+pub struct SharedBytes {
+    inner: Rc<SharedBytesInner>,
+}
+
+struct SharedBytesInner {
+    arena: SharedArena,
+    range: Range<usize>,
+}
+
+impl SharedBytes {
+    pub fn arena(&self) -> &SharedArena {
+        &self.inner.arena
+    }
+
+    /// The range of the arena that this handle refers to.
+    pub fn range(&self) -> Range<usize> {
+        self.inner.range.clone()
+    }
+
+    /// Gives up access to this range so that a peer can [`accept`](SharedArena::accept) it. Fails
+    /// if other clones of this handle exist.
+    pub fn try_relinquish(self) -> Result<Transfer, Self> {
+        let inner = Rc::try_unwrap(self.inner).map_err(|inner| Self { inner })?;
+        let inner = ManuallyDrop::new(inner);
+        let arena = unsafe { ptr::read(&inner.arena) };
+        let range = inner.range.clone();
+        arena
+            .ledger()
+            .transition(&range, State::Frozen, State::Relinquished);
+        Ok(Transfer::from_range(range))
+    }
+}
+
+impl Deref for SharedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        let inner = &self.inner;
+        unsafe { slice::from_raw_parts(inner.arena.ptr(&inner.range), inner.range.len()) }
+    }
+}
+
+impl Drop for SharedBytesInner {
+    fn drop(&mut self) {
+        self.arena.ledger().release(&self.range, State::Frozen);
+    }
+}
+
+impl fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedBytes")
+            .field("range", &self.inner.range)
+            .finish()
+    }
+}
+
+/// A [`SharedBytes`] known to contain valid UTF-8.
+#[derive(Clone)]
+pub struct SharedStr {
+    bytes: SharedBytes,
+}
+
+impl SharedStr {
+    pub fn from_utf8(bytes: SharedBytes) -> Result<Self, (SharedBytes, Utf8Error)> {
+        match str::from_utf8(&bytes) {
+            Ok(_) => Ok(Self { bytes }),
+            Err(err) => Err((bytes, err)),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &SharedBytes {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> SharedBytes {
+        self.bytes
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        // The underlying range is frozen, so it cannot have changed since it was validated.
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::*;
+
+    fn with_arena(f: impl FnOnce(SharedArena)) {
+        let mut backing = vec![0u8; 0x100];
+        f(unsafe { SharedArena::new(NonNull::from(backing.as_mut_slice())) });
+    }
+
+    #[test]
+    fn round_trip() {
+        with_arena(|arena| {
+            let mut buf = arena.claim(0x10..0x15);
+            buf.copy_from_slice(b"hello");
+            let transfer = buf.freeze().try_relinquish().unwrap();
+            let s = SharedStr::from_utf8(arena.accept(transfer).freeze()).unwrap();
+            assert_eq!(&*s, "hello");
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_aliasing() {
+        with_arena(|arena| {
+            let _buf = arena.claim(0x10..0x20);
+            arena.accept(Transfer::new(0x18, 0x10));
+        });
+    }
+
+    #[test]
+    fn rejects_overflowing_transfer() {
+        assert_eq!(Transfer::new(usize::MAX, 1).range(), None);
+        assert_eq!(
+            Transfer::new(usize::MAX, 0).range(),
+            Some(usize::MAX..usize::MAX)
+        );
+    }
+}
@@ -8,13 +8,15 @@ extern crate alloc;
 use alloc::rc::Rc;
 use core::alloc::Layout;
 use core::cell::RefCell;
+use core::ops::Range;
 use core::task::{ready, Poll};
 
-use async_unsync::semaphore::Semaphore;
 use futures::prelude::*;
 
-use sel4_async_block_io::BlockIO as BlockIOTrait;
+use sel4_async_block_io::{BlockIO as BlockIOTrait, WritableBlockIO as WritableBlockIOTrait};
+use sel4_async_copy_engine::{CopyEngine, SoftwareCopyEngine};
 use sel4_async_request_statuses::RequestStatuses;
+use sel4_async_sync::{Permit, Semaphore};
 use sel4_bounce_buffer_allocator::{Basic, BounceBufferAllocator};
 use sel4_externally_shared::ExternallySharedRef;
 use sel4_shared_ring_buffer::{
@@ -26,9 +28,18 @@ use sel4_shared_ring_buffer_block_io_types::{
 
 pub const BLOCK_SIZE: usize = 512;
 
-#[derive(Clone)]
-pub struct BlockIO {
+pub struct BlockIO<C = SoftwareCopyEngine> {
     shared_inner: Rc<RefCell<Inner>>,
+    copy_engine: Rc<C>,
+}
+
+impl<C> Clone for BlockIO<C> {
+    fn clone(&self) -> Self {
+        Self {
+            shared_inner: self.shared_inner.clone(),
+            copy_engine: self.copy_engine.clone(),
+        }
+    }
 }
 
 type EncodedAddr = usize;
@@ -47,6 +58,24 @@ impl BlockIO {
         dma_region: ExternallySharedRef<'static, [u8]>,
         dma_region_paddr: usize,
         ring_buffers: RingBuffers<'static, fn() -> Result<(), !>, BlockIORequest>,
+    ) -> Self {
+        Self::with_copy_engine(
+            dma_region,
+            dma_region_paddr,
+            ring_buffers,
+            SoftwareCopyEngine,
+        )
+    }
+}
+
+impl<C> BlockIO<C> {
+    /// Like [`BlockIO::new`], but copies data into and out of bounce buffers in the DMA region
+    /// using `copy_engine`.
+    pub fn with_copy_engine(
+        dma_region: ExternallySharedRef<'static, [u8]>,
+        dma_region_paddr: usize,
+        ring_buffers: RingBuffers<'static, fn() -> Result<(), !>, BlockIORequest>,
+        copy_engine: C,
     ) -> Self {
         let max_alignment = 1
             << dma_region
@@ -68,6 +97,7 @@ impl BlockIO {
                 request_statuses: RequestStatuses::new(),
                 queue_guard: Rc::new(Semaphore::new(RING_BUFFER_SIZE)),
             })),
+            copy_engine: Rc::new(copy_engine),
         }
     }
 
@@ -88,7 +118,11 @@ impl BlockIO {
             let expected_req = completed_req;
             let actual_req = inner.request_statuses.get(&key).unwrap();
             assert_eq!(&expected_req, actual_req);
-            inner.request_statuses.mark_complete(&key, status).unwrap();
+            if let Some(cancelled) = inner.request_statuses.mark_complete(&key, status).unwrap() {
+                let range = inner.buf_range(&cancelled.value);
+                inner.bounce_buffer_allocator.deallocate(range);
+                inner.queue_guard.add_permits(1);
+            }
             notify = true;
         }
 
@@ -100,21 +134,130 @@ impl BlockIO {
     }
 }
 
-impl BlockIOTrait<BLOCK_SIZE> for BlockIO {
-    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+impl Inner {
+    fn buf_range(&self, req: &BlockIORequest) -> Range<usize> {
+        let start = req.buf().encoded_addr() - self.dma_region_paddr;
+        let end = start + usize::try_from(req.buf().len()).unwrap();
+        start..end
+    }
+}
+
+// A bounce buffer which is returned to the allocator when dropped, unless ownership has been
+// passed on with `into_range`. This covers futures which are dropped while a copy is in flight.
+struct BounceBuffer<'a> {
+    shared_inner: &'a RefCell<Inner>,
+    range: Option<Range<usize>>,
+}
+
+impl<'a> BounceBuffer<'a> {
+    fn new(shared_inner: &'a RefCell<Inner>, range: Range<usize>) -> Self {
+        Self {
+            shared_inner,
+            range: Some(range),
+        }
+    }
+
+    fn range(&self) -> Range<usize> {
+        self.range.clone().unwrap()
+    }
+
+    // The DMA region is 'static, so this pointer outlives any copy.
+    fn as_mut_ptr(&self) -> *mut u8 {
+        self.shared_inner
+            .borrow_mut()
+            .dma_region
+            .as_mut_ptr()
+            .index(self.range())
+            .as_raw_ptr()
+            .cast::<u8>()
+            .as_ptr()
+    }
+
+    fn into_range(mut self) -> Range<usize> {
+        self.range.take().unwrap()
+    }
+}
+
+impl Drop for BounceBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(range) = self.range.take() {
+            self.shared_inner
+                .borrow_mut()
+                .bounce_buffer_allocator
+                .deallocate(range);
+        }
+    }
+}
+
+// Ensures that, if a request's future is dropped before the request completes, its bounce buffer
+// and queue slot are not reclaimed until the device is done with them.
+struct InFlightRequest<'a> {
+    shared_inner: &'a RefCell<Inner>,
+    key: EncodedAddr,
+    permit: Option<Permit<'a>>,
+    complete: bool,
+}
+
+impl Drop for InFlightRequest<'_> {
+    fn drop(&mut self) {
+        if self.complete {
+            return;
+        }
+        let mut inner = self.shared_inner.borrow_mut();
+        match inner.request_statuses.cancel(&self.key).unwrap() {
+            Some(completion) => {
+                let range = inner.buf_range(&completion.value);
+                inner.bounce_buffer_allocator.deallocate(range);
+            }
+            None => {
+                // Reclaimed by BlockIO::poll once the device completes the request
+                self.permit.take().unwrap().forget();
+            }
+        }
+    }
+}
+
+enum Transfer<'a> {
+    Read(&'a mut [u8; BLOCK_SIZE]),
+    Write(&'a [u8; BLOCK_SIZE]),
+    None,
+}
+
+impl<C: CopyEngine> BlockIO<C> {
+    async fn request(&self, ty: BlockIORequestType, block_id: usize, transfer: Transfer<'_>) {
         let sem = self.shared_inner.borrow().queue_guard.clone();
         let permit = sem.acquire().await;
 
+        // Requests without data still get a (minimal) bounce buffer, whose address serves as the
+        // request's key.
+        let len = match transfer {
+            Transfer::Read(_) | Transfer::Write(_) => BLOCK_SIZE,
+            Transfer::None => 1,
+        };
+        let bounce_buffer = BounceBuffer::new(
+            &self.shared_inner,
+            self.shared_inner
+                .borrow_mut()
+                .bounce_buffer_allocator
+                .allocate(Layout::from_size_align(len, 1).unwrap())
+                .unwrap(),
+        );
+
+        if let Transfer::Write(buf) = &transfer {
+            unsafe {
+                self.copy_engine
+                    .copy_raw(bounce_buffer.as_mut_ptr(), buf.as_ptr(), BLOCK_SIZE)
+            }
+            .await;
+        }
+
         let key = {
+            let range = bounce_buffer.into_range();
             let mut inner = self.shared_inner.borrow_mut();
-            let range = inner
-                .bounce_buffer_allocator
-                .allocate(Layout::from_size_align(buf.len(), 1).unwrap())
-                .unwrap();
             let key = range.start;
             let req = BlockIORequest::new(
                 BlockIORequestStatus::Pending,
-                BlockIORequestType::Read,
+                ty,
                 block_id,
                 Descriptor::new(
                     inner.dma_region_paddr + range.start,
@@ -128,24 +271,51 @@ impl BlockIOTrait<BLOCK_SIZE> for BlockIO {
             key
         };
 
-        future::poll_fn(|cx| {
+        let mut in_flight = InFlightRequest {
+            shared_inner: &self.shared_inner,
+            key,
+            permit: Some(permit),
+            complete: false,
+        };
+
+        let range = future::poll_fn(|cx| {
             let mut inner = self.shared_inner.borrow_mut();
             let completion = ready!(inner.request_statuses.poll(&key, cx.waker()).unwrap());
+            in_flight.complete = true;
             assert_eq!(completion.complete, BlockIORequestStatus::Ok);
-            let req = completion.value;
-            let range_start = req.buf().encoded_addr() - inner.dma_region_paddr;
-            let range_end = range_start + usize::try_from(req.buf().len()).unwrap();
-            let range = range_start..range_end;
-            inner
-                .dma_region
-                .as_mut_ptr()
-                .index(range.clone())
-                .copy_into_slice(buf);
-            inner.bounce_buffer_allocator.deallocate(range);
-            Poll::Ready(())
+            Poll::Ready(inner.buf_range(&completion.value))
         })
         .await;
 
-        drop(permit); // explicit extent of scope
+        drop(in_flight); // explicit extent of scope
+
+        let bounce_buffer = BounceBuffer::new(&self.shared_inner, range);
+
+        if let Transfer::Read(buf) = transfer {
+            unsafe {
+                self.copy_engine
+                    .copy_raw(buf.as_mut_ptr(), bounce_buffer.as_mut_ptr(), BLOCK_SIZE)
+            }
+            .await;
+        }
+    }
+}
+
+impl<C: CopyEngine> BlockIOTrait<BLOCK_SIZE> for BlockIO<C> {
+    async fn read_block(&self, block_id: usize, buf: &mut [u8; BLOCK_SIZE]) {
+        self.request(BlockIORequestType::Read, block_id, Transfer::Read(buf))
+            .await
+    }
+}
+
+impl<C: CopyEngine> WritableBlockIOTrait<BLOCK_SIZE> for BlockIO<C> {
+    async fn write_block(&self, block_id: usize, buf: &[u8; BLOCK_SIZE]) {
+        self.request(BlockIORequestType::Write, block_id, Transfer::Write(buf))
+            .await
+    }
+
+    async fn flush(&self) {
+        self.request(BlockIORequestType::Flush, 0, Transfer::None)
+            .await
     }
 }
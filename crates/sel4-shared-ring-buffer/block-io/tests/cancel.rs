@@ -0,0 +1,99 @@
+#![feature(never_type)]
+
+use core::future::Future;
+use core::mem;
+use core::pin::pin;
+use core::ptr::NonNull;
+use core::task::Context;
+
+use futures::task::noop_waker_ref;
+
+use sel4_async_block_io::BlockIO as _;
+use sel4_externally_shared::ExternallySharedRef;
+use sel4_shared_ring_buffer::{RawRingBuffer, RingBuffer, RingBuffers};
+use sel4_shared_ring_buffer_block_io::{BlockIO, BLOCK_SIZE};
+use sel4_shared_ring_buffer_block_io_types::{BlockIORequest, BlockIORequestStatus};
+
+type DeviceRingBuffers = RingBuffers<'static, fn() -> Result<(), !>, BlockIORequest>;
+
+// The bounce buffer allocator rounds each request up to 2048 bytes. This leaves room for two
+// requests, so that a leaked bounce buffer makes the second allocation fail.
+const DMA_REGION_SIZE: usize = 2 * 2048;
+
+const DMA_REGION_PADDR: usize = 0x1000_0000;
+
+// Returns the client, and the device's view of the same ring buffers.
+fn setup() -> (BlockIO, DeviceRingBuffers) {
+    let dma_region = NonNull::from(Box::leak(vec![0u8; DMA_REGION_SIZE].into_boxed_slice()));
+    let free = NonNull::from(Box::leak(Box::new(unsafe {
+        mem::zeroed::<RawRingBuffer<BlockIORequest>>()
+    })));
+    let used = NonNull::from(Box::leak(Box::new(unsafe {
+        mem::zeroed::<RawRingBuffer<BlockIORequest>>()
+    })));
+    let notify: fn() -> Result<(), !> = || Ok(());
+    let (client, device) = unsafe {
+        (
+            RingBuffers::new(
+                RingBuffer::from_ptr(free),
+                RingBuffer::from_ptr(used),
+                notify,
+                true,
+            ),
+            RingBuffers::new(
+                RingBuffer::from_ptr(free),
+                RingBuffer::from_ptr(used),
+                notify,
+                false,
+            ),
+        )
+    };
+    let block_io = BlockIO::new(
+        unsafe { ExternallySharedRef::new(dma_region) },
+        DMA_REGION_PADDR,
+        client,
+    );
+    (block_io, device)
+}
+
+fn complete(device: &mut DeviceRingBuffers, mut req: BlockIORequest) {
+    req.set_status(BlockIORequestStatus::Ok);
+    device.used_mut().enqueue(req).unwrap();
+}
+
+#[test]
+fn cancelled_read_keeps_bounce_buffer_until_completion() {
+    let (block_io, mut device) = setup();
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    let mut buf = [0; BLOCK_SIZE];
+    {
+        let mut read = pin!(block_io.read_block(0, &mut buf));
+        assert!(read.as_mut().poll(&mut cx).is_pending());
+    }
+    let cancelled = device.free_mut().dequeue().unwrap();
+
+    // The device may still write to the cancelled request's bounce buffer, so another request
+    // must not be given it.
+    {
+        let mut read = pin!(block_io.read_block(1, &mut buf));
+        assert!(read.as_mut().poll(&mut cx).is_pending());
+        let req = device.free_mut().dequeue().unwrap();
+        assert_ne!(req.buf().encoded_addr(), cancelled.buf().encoded_addr());
+        complete(&mut device, req);
+        assert!(block_io.poll());
+        assert!(read.as_mut().poll(&mut cx).is_ready());
+    }
+
+    // Once the device is done with it, the cancelled request's bounce buffer is reclaimed, so that
+    // two requests fit in the DMA region again.
+    complete(&mut device, cancelled);
+    assert!(block_io.poll());
+    let mut other_buf = [0; BLOCK_SIZE];
+    let mut read = pin!(block_io.read_block(2, &mut buf));
+    let mut other_read = pin!(block_io.read_block(3, &mut other_buf));
+    assert!(read.as_mut().poll(&mut cx).is_pending());
+    assert!(other_read.as_mut().poll(&mut cx).is_pending());
+    assert!(device.free_mut().dequeue().is_ok());
+    assert!(device.free_mut().dequeue().is_ok());
+}
@@ -19,6 +19,7 @@ pub struct BlockIORequest {
 pub enum BlockIORequestType {
     Read = 0,
     Write = 1,
+    Flush = 2,
 }
 
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
@@ -0,0 +1,118 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use smoltcp::phy::{self, DeviceCapabilities};
+use smoltcp::time::Instant;
+
+use sel4_shared_ring_buffer::Descriptor;
+
+use crate::NetClient;
+
+/// A `smoltcp` device backed by a [`NetClient`].
+pub struct Device {
+    client: Rc<RefCell<NetClient>>,
+    mtu: usize,
+}
+
+impl Device {
+    /// # Panics
+    ///
+    /// Panics if `mtu` exceeds the client's buffer size.
+    pub fn new(client: NetClient, mtu: usize) -> Self {
+        assert!(mtu <= client.buffer_size());
+        Self {
+            client: Rc::new(RefCell::new(client)),
+            mtu,
+        }
+    }
+
+    pub fn client(&self) -> &RefCell<NetClient> {
+        &self.client
+    }
+}
+
+impl phy::Device for Device {
+    type RxToken<'a> = RxToken;
+    type TxToken<'a> = TxToken;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut cap = DeviceCapabilities::default();
+        cap.max_transmission_unit = self.mtu;
+        cap
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let mut client = self.client.borrow_mut();
+        let tx_buffer = client.take_tx_buffer()?;
+        let Some(rx_buffer) = client.receive() else {
+            client.return_tx_buffer(tx_buffer);
+            return None;
+        };
+        Some((
+            RxToken {
+                buffer: rx_buffer,
+                client: self.client.clone(),
+            },
+            TxToken {
+                buffer: Some(tx_buffer),
+                client: self.client.clone(),
+            },
+        ))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        let tx_buffer = self.client.borrow_mut().take_tx_buffer()?;
+        Some(TxToken {
+            buffer: Some(tx_buffer),
+            client: self.client.clone(),
+        })
+    }
+}
+
+pub struct RxToken {
+    buffer: Descriptor,
+    client: Rc<RefCell<NetClient>>,
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let ptr = self.client.borrow_mut().buffer(&self.buffer);
+        f(unsafe { ptr.as_mut().unwrap() })
+    }
+}
+
+impl Drop for RxToken {
+    fn drop(&mut self) {
+        self.client.borrow_mut().return_rx_buffer(self.buffer)
+    }
+}
+
+pub struct TxToken {
+    // Taken once transmitted.
+    buffer: Option<Descriptor>,
+    client: Rc<RefCell<NetClient>>,
+}
+
+impl phy::TxToken for TxToken {
+    fn consume<R, F>(mut self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let buffer = self.buffer.take().unwrap();
+        let ptr = self.client.borrow_mut().whole_buffer(&buffer);
+        let r = f(unsafe { &mut ptr.as_mut().unwrap()[..len] });
+        self.client.borrow_mut().transmit(buffer, len);
+        r
+    }
+}
+
+impl Drop for TxToken {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.client.borrow_mut().return_tx_buffer(buffer)
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! The network client and driver protocol of the [seL4 Device Driver
+//! Framework](https://github.com/lucypa/sDDF), for interoperating with its drivers and
+//! multiplexers.
+//!
+//! Packets are exchanged in fixed-size buffers in a data region shared between a client and a
+//! driver (or a multiplexer, which plays the part of the driver to each of its clients). Each
+//! direction uses a pair of ring buffers, whose descriptors refer to buffers by an encoded address
+//! and carry an opaque cookie, which the other side returns unchanged:
+//!
+//! - RX: the client enqueues empty buffers on the free ring, and the driver returns them, filled,
+//!   on the used ring.
+//! - TX: the driver returns sent buffers on the free ring, and the client enqueues filled buffers
+//!   on the used ring.
+//!
+//! The client owns all of the buffers, and initially places each on the free ring of its
+//! direction.
+//!
+//! Note that this differs from the TX convention of
+//! [`sel4-shared-ring-buffer-smoltcp`](https://docs.rs/sel4-shared-ring-buffer-smoltcp), in which
+//! the client allocates bounce buffers and enqueues them on the free ring.
+//!
+//! [`NetClient`] implements the client side, and [`Device`] adapts it for `smoltcp`.
+//! [`NetDriverQueues`] implements the driver side.
+
+#![no_std]
+#![feature(never_type)]
+#![feature(strict_provenance)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use sel4_externally_shared::ExternallySharedRef;
+use sel4_shared_ring_buffer::{
+    Descriptor, Error as SharedRingBuffersError, RingBuffer, RingBuffers,
+};
+
+mod device;
+
+pub use device::{Device, RxToken, TxToken};
+
+pub type NetRingBuffers = RingBuffers<'static, fn() -> Result<(), !>>;
+
+/// The arrangement of buffers in the data region: `num_rx_buffers` RX buffers followed by
+/// `num_tx_buffers` TX buffers, each of `buffer_size` bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BufferLayout {
+    pub num_rx_buffers: usize,
+    pub num_tx_buffers: usize,
+    pub buffer_size: usize,
+}
+
+impl BufferLayout {
+    pub fn region_size(&self) -> usize {
+        (self.num_rx_buffers + self.num_tx_buffers) * self.buffer_size
+    }
+}
+
+/// The client side of the protocol.
+pub struct NetClient {
+    data_region: ExternallySharedRef<'static, [u8]>,
+    encoded_addr_base: usize,
+    buffer_size: usize,
+    rx_ring_buffers: NetRingBuffers,
+    tx_ring_buffers: NetRingBuffers,
+    // TX buffers which have been taken from the free ring but not sent.
+    spare_tx_buffers: Vec<Descriptor>,
+}
+
+impl NetClient {
+    /// Buffers are referred to in descriptors by `encoded_addr_base` plus their offset in
+    /// `data_region`. Depending on the peer, `encoded_addr_base` is, for example, the physical
+    /// address or the peer's virtual address of `data_region`.
+    ///
+    /// If `initialize` is true, the free rings are filled with the buffers described by `layout`.
+    /// This must happen before the peer starts.
+    pub fn new(
+        data_region: ExternallySharedRef<'static, [u8]>,
+        encoded_addr_base: usize,
+        mut rx_ring_buffers: NetRingBuffers,
+        mut tx_ring_buffers: NetRingBuffers,
+        layout: BufferLayout,
+        initialize: bool,
+    ) -> Self {
+        assert!(layout.region_size() <= data_region.as_ptr().len());
+        assert!(u32::try_from(layout.buffer_size).is_ok());
+        if initialize {
+            let mut buffers = (0..layout.num_rx_buffers + layout.num_tx_buffers).map(|i| {
+                let offset = i * layout.buffer_size;
+                descriptor_of(encoded_addr_base, offset..offset + layout.buffer_size)
+            });
+            for desc in buffers.by_ref().take(layout.num_rx_buffers) {
+                rx_ring_buffers.free_mut().enqueue(desc).unwrap();
+            }
+            for desc in buffers {
+                tx_ring_buffers.free_mut().enqueue(desc).unwrap();
+            }
+        }
+        Self {
+            data_region,
+            encoded_addr_base,
+            buffer_size: layout.buffer_size,
+            rx_ring_buffers,
+            tx_ring_buffers,
+            spare_tx_buffers: Vec::new(),
+        }
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Takes the next received packet, if any.
+    pub fn receive(&mut self) -> Option<Descriptor> {
+        dequeue(self.rx_ring_buffers.used_mut())
+    }
+
+    /// Returns a buffer taken with [`NetClient::receive`] to the driver, to be filled again.
+    pub fn return_rx_buffer(&mut self, desc: Descriptor) {
+        self.buffer_range(&desc);
+        let desc = with_len(&desc, self.buffer_size);
+        self.rx_ring_buffers.free_mut().enqueue(desc).unwrap();
+        self.rx_ring_buffers.notify().unwrap();
+    }
+
+    /// Takes a buffer to transmit a packet in, if one is available.
+    pub fn take_tx_buffer(&mut self) -> Option<Descriptor> {
+        self.spare_tx_buffers
+            .pop()
+            .or_else(|| dequeue(self.tx_ring_buffers.free_mut()))
+    }
+
+    /// Transmits the first `len` bytes of a buffer taken with [`NetClient::take_tx_buffer`].
+    pub fn transmit(&mut self, desc: Descriptor, len: usize) {
+        assert!(len <= self.buffer_size);
+        self.buffer_range(&desc);
+        let desc = with_len(&desc, len);
+        self.tx_ring_buffers.used_mut().enqueue(desc).unwrap();
+        self.tx_ring_buffers.notify().unwrap();
+    }
+
+    /// Returns a buffer taken with [`NetClient::take_tx_buffer`] without transmitting it.
+    pub fn return_tx_buffer(&mut self, desc: Descriptor) {
+        self.spare_tx_buffers.push(desc);
+    }
+
+    /// Returns a pointer to the contents of the buffer described by `desc`.
+    pub fn buffer(&mut self, desc: &Descriptor) -> *mut [u8] {
+        let range = self.buffer_range(desc);
+        self.data_region
+            .as_mut_ptr()
+            .index(range)
+            .as_raw_ptr()
+            .as_ptr()
+    }
+
+    /// Like [`NetClient::buffer`], but for the whole of the buffer, regardless of the length in
+    /// `desc`.
+    pub fn whole_buffer(&mut self, desc: &Descriptor) -> *mut [u8] {
+        let start = self.buffer_range(desc).start;
+        self.data_region
+            .as_mut_ptr()
+            .index(start..start + self.buffer_size)
+            .as_raw_ptr()
+            .as_ptr()
+    }
+
+    fn buffer_range(&self, desc: &Descriptor) -> Range<usize> {
+        let start = desc
+            .encoded_addr()
+            .checked_sub(self.encoded_addr_base)
+            .unwrap();
+        let len = usize::try_from(desc.len()).unwrap();
+        assert_eq!(start % self.buffer_size, 0);
+        assert!(len <= self.buffer_size);
+        assert!(start + self.buffer_size <= self.data_region.as_ptr().len());
+        start..start + len
+    }
+}
+
+/// The driver side of the protocol, over the rings shared with one client.
+///
+/// The driver does not interpret encoded addresses or cookies, and returns them unchanged.
+pub struct NetDriverQueues {
+    rx_ring_buffers: NetRingBuffers,
+    tx_ring_buffers: NetRingBuffers,
+}
+
+impl NetDriverQueues {
+    pub fn new(rx_ring_buffers: NetRingBuffers, tx_ring_buffers: NetRingBuffers) -> Self {
+        Self {
+            rx_ring_buffers,
+            tx_ring_buffers,
+        }
+    }
+
+    pub fn rx_ring_buffers(&self) -> &NetRingBuffers {
+        &self.rx_ring_buffers
+    }
+
+    pub fn tx_ring_buffers(&self) -> &NetRingBuffers {
+        &self.tx_ring_buffers
+    }
+
+    /// Takes an empty buffer to receive a packet into, if the client has provided one.
+    pub fn take_rx_buffer(&mut self) -> Option<Descriptor> {
+        dequeue(self.rx_ring_buffers.free_mut())
+    }
+
+    /// Passes a buffer taken with [`NetDriverQueues::take_rx_buffer`], now holding a packet of
+    /// `len` bytes, to the client.
+    ///
+    /// The client is not notified. Use [`NetDriverQueues::rx_ring_buffers`] to do so, for example
+    /// once per batch of packets.
+    pub fn deliver_rx(&mut self, desc: Descriptor, len: u32) {
+        assert!(len <= desc.len());
+        let desc = with_len(&desc, len.try_into().unwrap());
+        self.rx_ring_buffers.used_mut().enqueue(desc).unwrap();
+    }
+
+    /// Takes the next packet which the client has asked to transmit, if any.
+    pub fn take_tx_packet(&mut self) -> Option<Descriptor> {
+        dequeue(self.tx_ring_buffers.used_mut())
+    }
+
+    /// Returns a buffer taken with [`NetDriverQueues::take_tx_packet`] to the client once it has
+    /// been sent.
+    ///
+    /// The client is not notified. Use [`NetDriverQueues::tx_ring_buffers`] to do so.
+    pub fn complete_tx(&mut self, desc: Descriptor) {
+        self.tx_ring_buffers.free_mut().enqueue(desc).unwrap();
+    }
+}
+
+fn dequeue(ring_buffer: &mut RingBuffer<'static>) -> Option<Descriptor> {
+    ring_buffer
+        .dequeue()
+        .map_err(|err| assert_eq!(err, SharedRingBuffersError::RingIsEmpty))
+        .ok()
+}
+
+fn descriptor_of(encoded_addr_base: usize, range: Range<usize>) -> Descriptor {
+    Descriptor::new(
+        encoded_addr_base + range.start,
+        range.len().try_into().unwrap(),
+        0,
+    )
+}
+
+// Preserves the encoded address and cookie, which the peer may use to identify the buffer.
+fn with_len(desc: &Descriptor, len: usize) -> Descriptor {
+    Descriptor::new(desc.encoded_addr(), len.try_into().unwrap(), desc.cookie())
+}
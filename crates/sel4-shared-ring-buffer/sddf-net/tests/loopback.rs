@@ -0,0 +1,106 @@
+#![feature(never_type)]
+
+use core::ptr::NonNull;
+
+use smoltcp::phy::{Device as _, RxToken as _, TxToken as _};
+use smoltcp::time::Instant;
+
+use sel4_externally_shared::ExternallySharedRef;
+use sel4_shared_ring_buffer::{RawRingBuffer, RingBuffer, RingBuffers};
+use sel4_shared_ring_buffer_sddf_net::{
+    BufferLayout, Device, NetClient, NetDriverQueues, NetRingBuffers,
+};
+
+const ENCODED_ADDR_BASE: usize = 0x8000_0000;
+
+const LAYOUT: BufferLayout = BufferLayout {
+    num_rx_buffers: 2,
+    num_tx_buffers: 2,
+    buffer_size: 128,
+};
+
+fn notify() -> Result<(), !> {
+    Ok(())
+}
+
+fn new_raw_ring_buffer() -> NonNull<RawRingBuffer> {
+    NonNull::from(Box::leak(Box::new(unsafe { core::mem::zeroed() })))
+}
+
+// Returns the client's and the driver's views of a pair of rings.
+fn new_ring_buffers_pair() -> (NetRingBuffers, NetRingBuffers) {
+    let free = new_raw_ring_buffer();
+    let used = new_raw_ring_buffer();
+    let new = |initialize| unsafe {
+        RingBuffers::new(
+            RingBuffer::from_ptr(free),
+            RingBuffer::from_ptr(used),
+            notify as fn() -> Result<(), !>,
+            initialize,
+        )
+    };
+    (new(true), new(false))
+}
+
+fn new_pair() -> (NetClient, NetDriverQueues, NonNull<[u8]>) {
+    let region = NonNull::from(Box::leak(
+        vec![0u8; LAYOUT.region_size()].into_boxed_slice(),
+    ));
+    let (client_rx, driver_rx) = new_ring_buffers_pair();
+    let (client_tx, driver_tx) = new_ring_buffers_pair();
+    let client = NetClient::new(
+        unsafe { ExternallySharedRef::new(region) },
+        ENCODED_ADDR_BASE,
+        client_rx,
+        client_tx,
+        LAYOUT,
+        true,
+    );
+    (client, NetDriverQueues::new(driver_rx, driver_tx), region)
+}
+
+fn slice_at(region: NonNull<[u8]>, encoded_addr: usize, len: u32) -> &'static mut [u8] {
+    let start = encoded_addr - ENCODED_ADDR_BASE;
+    unsafe { &mut (*region.as_ptr())[start..start + usize::try_from(len).unwrap()] }
+}
+
+#[test]
+fn packets_flow_in_both_directions() {
+    let (client, mut driver, region) = new_pair();
+    let mut device = Device::new(client, 100);
+
+    // The client starts with all of its TX buffers available.
+    let token = device.transmit(Instant::ZERO).unwrap();
+    token.consume(5, |buf| buf.copy_from_slice(b"hello"));
+    let desc = driver.take_tx_packet().unwrap();
+    assert_eq!(slice_at(region, desc.encoded_addr(), desc.len()), b"hello");
+    assert!(driver.take_tx_packet().is_none());
+    driver.complete_tx(desc);
+
+    let desc = driver.take_rx_buffer().unwrap();
+    assert_eq!(desc.len(), 128);
+    slice_at(region, desc.encoded_addr(), 5).copy_from_slice(b"world");
+    driver.deliver_rx(desc, 5);
+
+    let (rx_token, _tx_token) = device.receive(Instant::ZERO).unwrap();
+    rx_token.consume(|buf| assert_eq!(buf, b"world"));
+    assert!(device.receive(Instant::ZERO).is_none());
+
+    // Both RX buffers are available to the driver again.
+    assert!(driver.take_rx_buffer().is_some());
+    assert!(driver.take_rx_buffer().is_some());
+    assert!(driver.take_rx_buffer().is_none());
+}
+
+#[test]
+fn unused_tx_buffers_are_kept() {
+    let (client, _driver, _region) = new_pair();
+    let mut device = Device::new(client, 100);
+
+    for _ in 0..3 {
+        let a = device.transmit(Instant::ZERO).unwrap();
+        let b = device.transmit(Instant::ZERO).unwrap();
+        assert!(device.transmit(Instant::ZERO).is_none());
+        drop((a, b));
+    }
+}
@@ -3,12 +3,16 @@ use core::alloc::Layout;
 use core::iter;
 use core::ops::Range;
 
+use smoltcp::time::Instant;
+
 use sel4_bounce_buffer_allocator::{Basic, BounceBufferAllocator};
 use sel4_externally_shared::ExternallySharedRef;
 use sel4_shared_ring_buffer::{
     Descriptor, Error as SharedRingBuffersError, RingBuffers, RING_BUFFER_SIZE,
 };
 
+use crate::CaptureFn;
+
 pub(crate) struct Inner {
     dma_region: ExternallySharedRef<'static, [u8]>,
     dma_region_paddr: usize,
@@ -18,6 +22,7 @@ pub(crate) struct Inner {
     rx_buffers: Vec<RxBufferEntry>,
     tx_buffers: Vec<TxBufferEntry>,
     mtu: usize,
+    capture: Option<CaptureFn>,
 }
 
 pub(crate) type RxBufferIndex = usize;
@@ -59,6 +64,8 @@ impl Inner {
         rx_buffer_size: usize,
         mtu: usize,
     ) -> Self {
+        assert!(mtu <= rx_buffer_size);
+
         let max_alignment = 1
             << dma_region
                 .as_ptr()
@@ -100,6 +107,7 @@ impl Inner {
             rx_buffers,
             tx_buffers,
             mtu,
+            capture: None,
         }
     }
 
@@ -107,6 +115,10 @@ impl Inner {
         self.mtu
     }
 
+    pub(crate) fn set_capture(&mut self, capture: Option<CaptureFn>) {
+        self.capture = capture;
+    }
+
     pub(crate) fn poll(&mut self) -> bool {
         let mut notify_rx = false;
 
@@ -238,20 +250,26 @@ impl Inner {
         entry.state = TxBufferState::SlotClaimed;
     }
 
-    pub(crate) fn consume_rx_start(&mut self, index: RxBufferIndex) -> *mut [u8] {
+    pub(crate) fn consume_rx_start(
+        &mut self,
+        index: RxBufferIndex,
+        timestamp: Instant,
+    ) -> *mut [u8] {
         let entry = self.rx_buffer_entry_mut(index);
         let range = entry.range.clone();
         let len = match entry.state {
             RxBufferState::Claimed { len } => len,
             _ => panic!(),
         };
-        unsafe {
-            self.dma_region
-                .as_mut_ptr()
-                .index(range.start..range.start + len)
-                .as_raw_ptr()
-                .as_mut()
+        let ptr = self
+            .dma_region
+            .as_mut_ptr()
+            .index(range.start..range.start + len)
+            .as_raw_ptr();
+        if let Some(capture) = &mut self.capture {
+            capture(timestamp, unsafe { ptr.as_ref() });
         }
+        ptr.as_ptr()
     }
 
     pub(crate) fn drop_rx(&mut self, index: RxBufferIndex) {
@@ -272,7 +290,13 @@ impl Inner {
         }
     }
 
-    pub(crate) fn consume_tx<F, R>(&mut self, index: TxBufferIndex, len: usize, f: F) -> R
+    pub(crate) fn consume_tx<F, R>(
+        &mut self,
+        index: TxBufferIndex,
+        timestamp: Instant,
+        len: usize,
+        f: F,
+    ) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
     {
@@ -285,13 +309,15 @@ impl Inner {
         entry.state = TxBufferState::Sent {
             range: range.clone(),
         };
-        let r = f(unsafe {
-            self.dma_region
-                .as_mut_ptr()
-                .index(range.clone())
-                .as_raw_ptr()
-                .as_mut()
-        });
+        let mut ptr = self
+            .dma_region
+            .as_mut_ptr()
+            .index(range.clone())
+            .as_raw_ptr();
+        let r = f(unsafe { ptr.as_mut() });
+        if let Some(capture) = &mut self.capture {
+            capture(timestamp, unsafe { ptr.as_ref() });
+        }
         let desc = descriptor_of(self.dma_region_paddr, range);
         self.tx_ring_buffers.free_mut().enqueue(desc).unwrap();
         self.tx_ring_buffers.notify().unwrap();
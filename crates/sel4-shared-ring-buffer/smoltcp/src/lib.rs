@@ -4,6 +4,7 @@
 
 extern crate alloc;
 
+use alloc::boxed::Box;
 use alloc::rc::Rc;
 use core::cell::RefCell;
 
@@ -23,6 +24,9 @@ pub struct DeviceImpl {
 
 type SharedInner = Rc<RefCell<Inner>>;
 
+/// See [`DeviceImpl::set_capture`].
+pub type CaptureFn = Box<dyn FnMut(Instant, &[u8])>;
+
 impl DeviceImpl {
     pub fn new(
         dma_region: ExternallySharedRef<'static, [u8]>,
@@ -54,16 +58,26 @@ impl DeviceImpl {
         self.shared_inner().borrow_mut().poll()
     }
 
-    fn new_rx_token(&self, rx_buffer: RxBufferIndex) -> RxToken {
+    /// Sets a function to be called on each frame received or transmitted, with the timestamp
+    /// passed to [`Device::receive`] or [`Device::transmit`], for example to capture traffic for
+    /// debugging. Received frames are passed before smoltcp processes them, and transmitted frames
+    /// after smoltcp fills them.
+    pub fn set_capture(&self, capture: Option<CaptureFn>) {
+        self.shared_inner().borrow_mut().set_capture(capture)
+    }
+
+    fn new_rx_token(&self, rx_buffer: RxBufferIndex, timestamp: Instant) -> RxToken {
         RxToken {
             buffer: rx_buffer,
+            timestamp,
             shared_inner: self.shared_inner().clone(),
         }
     }
 
-    fn new_tx_token(&self, tx_buffer: TxBufferIndex) -> TxToken {
+    fn new_tx_token(&self, tx_buffer: TxBufferIndex, timestamp: Instant) -> TxToken {
         TxToken {
             buffer: tx_buffer,
+            timestamp,
             shared_inner: self.shared_inner().clone(),
         }
     }
@@ -79,23 +93,28 @@ impl Device for DeviceImpl {
         cap
     }
 
-    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
         let r = self.shared_inner().borrow_mut().receive();
-        r.ok()
-            .map(|(rx_ix, tx_ix)| (self.new_rx_token(rx_ix), self.new_tx_token(tx_ix)))
+        r.ok().map(|(rx_ix, tx_ix)| {
+            (
+                self.new_rx_token(rx_ix, timestamp),
+                self.new_tx_token(tx_ix, timestamp),
+            )
+        })
     }
 
-    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
         self.shared_inner()
             .borrow_mut()
             .transmit()
             .ok()
-            .map(|ix| self.new_tx_token(ix))
+            .map(|ix| self.new_tx_token(ix, timestamp))
     }
 }
 
 pub struct RxToken {
     buffer: RxBufferIndex,
+    timestamp: Instant,
     shared_inner: SharedInner,
 }
 
@@ -105,7 +124,10 @@ impl phy::RxToken for RxToken {
         F: FnOnce(&mut [u8]) -> R,
     {
         // let r = self.shared_inner.borrow_mut().consume_rx(self.buffer, f);
-        let ptr = self.shared_inner.borrow_mut().consume_rx_start(self.buffer);
+        let ptr = self
+            .shared_inner
+            .borrow_mut()
+            .consume_rx_start(self.buffer, self.timestamp);
         let r = f(unsafe { ptr.as_mut().unwrap() });
         drop(self);
         r
@@ -120,6 +142,7 @@ impl Drop for RxToken {
 
 pub struct TxToken {
     buffer: TxBufferIndex,
+    timestamp: Instant,
     shared_inner: SharedInner,
 }
 
@@ -131,7 +154,7 @@ impl phy::TxToken for TxToken {
         let r = self
             .shared_inner
             .borrow_mut()
-            .consume_tx(self.buffer, len, f);
+            .consume_tx(self.buffer, self.timestamp, len, f);
         drop(self);
         r
     }
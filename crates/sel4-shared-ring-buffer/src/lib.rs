@@ -2,7 +2,7 @@
 
 use core::num::Wrapping;
 use core::ptr::NonNull;
-use core::sync::atomic::Ordering;
+use core::sync::atomic::{self, Ordering};
 
 use zerocopy::{AsBytes, FromBytes};
 
@@ -60,6 +60,9 @@ impl<'a, T, F: FnMut() -> R, R> RingBuffers<'a, F, T> {
     }
 }
 
+/// The layout of the indices and descriptors is that of `ring_buffer_t` in the seL4 Device Driver
+/// Framework. The doorbell suppression flag (see [`RingBuffer::arm_notification`]) follows them,
+/// so peers which do not use it need not know about it.
 // TODO: zerocopy AsBytes and FromBytes
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
@@ -67,6 +70,7 @@ pub struct RawRingBuffer<T = Descriptor> {
     write_index: u32,
     read_index: u32,
     descriptors: [T; RING_BUFFER_SIZE],
+    notification_requested: u32,
 }
 
 #[repr(C)]
@@ -142,6 +146,8 @@ impl<'a, T: Copy> RingBuffer<'a, T> {
     fn initialize(&mut self) {
         self.set_write_index(Wrapping(0));
         self.set_read_index(Wrapping(0));
+        let ptr = self.inner.as_mut_ptr();
+        map_field!(ptr.notification_requested).write(1);
     }
 
     fn descriptor(&mut self, index: Wrapping<u32>) -> ExternallySharedPtr<'_, T> {
@@ -183,6 +189,35 @@ impl<'a, T: Copy> RingBuffer<'a, T> {
     }
 }
 
+/// Doorbell suppression, by which a consumer asks to be notified only when it is about to wait.
+///
+/// A consumer which opts in calls [`RingBuffer::arm_notification`] once it has drained the ring,
+/// and waits only if it returns `true`. A producer which opts in notifies the consumer after
+/// enqueueing only if [`RingBuffer::take_notification_request`] returns `true`. A newly
+/// initialized ring starts with a request outstanding.
+impl<'a, T: Copy> RingBuffer<'a, T> {
+    /// Requests a notification for the next enqueue. Returns `false` if the ring was not empty, in
+    /// which case the consumer should dequeue rather than wait.
+    pub fn arm_notification(&mut self) -> bool {
+        {
+            let ptr = self.inner.as_mut_ptr();
+            map_field!(ptr.notification_requested).with_atomic(|x| x.store(1, Ordering::SeqCst));
+        }
+        // Order the request before the check, pairing with the fence in
+        // take_notification_request, so that an enqueue cannot be missed by both sides.
+        atomic::fence(Ordering::SeqCst);
+        self.is_empty()
+    }
+
+    /// Returns whether the consumer has requested a notification since this last returned `true`,
+    /// clearing the request.
+    pub fn take_notification_request(&mut self) -> bool {
+        atomic::fence(Ordering::SeqCst);
+        let ptr = self.inner.as_mut_ptr();
+        map_field!(ptr.notification_requested).with_atomic(|x| x.swap(0, Ordering::SeqCst)) != 0
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq)]
 pub enum Error {
     RingIsFull,
@@ -0,0 +1,26 @@
+use core::ptr::NonNull;
+
+use sel4_shared_ring_buffer::{Descriptor, RawRingBuffer, RingBuffer, RingBuffers};
+
+#[test]
+fn notification_requests() {
+    let mut free: Box<RawRingBuffer> = Box::new(unsafe { core::mem::zeroed() });
+    let mut used: Box<RawRingBuffer> = Box::new(unsafe { core::mem::zeroed() });
+    let free = unsafe { RingBuffer::<Descriptor>::from_ptr(NonNull::from(&mut *free)) };
+    let used = unsafe { RingBuffer::<Descriptor>::from_ptr(NonNull::from(&mut *used)) };
+    let mut ring_buffers = RingBuffers::new(free, used, || (), true);
+    let ring = ring_buffers.used_mut();
+
+    // Initially requested, then cleared once taken.
+    assert!(ring.take_notification_request());
+    assert!(!ring.take_notification_request());
+
+    ring.enqueue(Descriptor::new(0, 0, 0)).unwrap();
+    assert!(!ring.take_notification_request());
+
+    // Arming a non-empty ring tells the consumer to dequeue instead of waiting.
+    assert!(!ring.arm_notification());
+    ring.dequeue().unwrap();
+    assert!(ring.arm_notification());
+    assert!(ring.take_notification_request());
+}
@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use object::elf::SHF_ALLOC;
+use object::{Object, ObjectSection, ObjectSegment, SectionFlags};
+
+use crate::{System, DEFAULT_PAGE_SIZE};
+
+/// The memory occupied by a loaded program image.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImageFootprint {
+    /// The total size of the image's loadable segments, each rounded out to page boundaries.
+    pub total: u64,
+    /// The sizes of the image's allocated sections, largest first.
+    pub sections: Vec<(String, u64)>,
+}
+
+impl ImageFootprint {
+    pub fn of_elf(path: impl AsRef<Path>) -> Result<Self, ImageBudgetError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| ImageBudgetError::Io {
+            path: path.to_owned(),
+            err,
+        })?;
+        Self::of_elf_bytes(&bytes).map_err(|err| ImageBudgetError::Parse {
+            path: path.to_owned(),
+            err,
+        })
+    }
+
+    pub fn of_elf_bytes(bytes: &[u8]) -> Result<Self, object::Error> {
+        let file = object::File::parse(bytes)?;
+        let page_size = u64::try_from(DEFAULT_PAGE_SIZE).unwrap();
+        let total = file
+            .segments()
+            .map(|segment| {
+                let start = segment.address() / page_size;
+                let end = (segment.address() + segment.size() + page_size - 1) / page_size;
+                (end - start) * page_size
+            })
+            .sum();
+        let mut sections = file
+            .sections()
+            .filter(|section| match section.flags() {
+                SectionFlags::Elf { sh_flags } => sh_flags & u64::from(SHF_ALLOC) != 0,
+                _ => false,
+            })
+            .filter(|section| section.size() != 0)
+            .map(|section| Ok((section.name()?.to_owned(), section.size())))
+            .collect::<Result<Vec<_>, object::Error>>()?;
+        sections.sort_by(|(_, a), (_, b)| b.cmp(a));
+        Ok(Self { total, sections })
+    }
+}
+
+impl System {
+    /// Checks the program image of each protection domain which declares a budget (see
+    /// [`ProtectionDomain::image_budget`](crate::ProtectionDomain::image_budget)) against that
+    /// budget. Intended to be run as a post-link build step.
+    pub fn check_image_budgets(&self) -> Result<(), ImageBudgetError> {
+        let mut exceeded = vec![];
+        for (_, pd) in self.protection_domains() {
+            if let Some(budget) = pd.image_budget {
+                let footprint = ImageFootprint::of_elf(&pd.program_image)?;
+                if footprint.total > u64::try_from(budget).unwrap() {
+                    exceeded.push(ImageBudgetExceeded {
+                        pd: pd.name.clone(),
+                        budget,
+                        footprint,
+                    });
+                }
+            }
+        }
+        if exceeded.is_empty() {
+            Ok(())
+        } else {
+            Err(ImageBudgetError::Exceeded(exceeded))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImageBudgetExceeded {
+    pub pd: String,
+    pub budget: usize,
+    pub footprint: ImageFootprint,
+}
+
+/// Error type returned by [`System::check_image_budgets`].
+#[derive(Debug)]
+pub enum ImageBudgetError {
+    Io { path: PathBuf, err: io::Error },
+    Parse { path: PathBuf, err: object::Error },
+    Exceeded(Vec<ImageBudgetExceeded>),
+}
+
+impl fmt::Display for ImageBudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, err } => write!(f, "failed to read {}: {err}", path.display()),
+            Self::Parse { path, err } => write!(f, "failed to parse {}: {err}", path.display()),
+            Self::Exceeded(exceeded) => {
+                for (i, entry) in exceeded.iter().enumerate() {
+                    if i != 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "program image of {:?} occupies {:#x} bytes, exceeding its budget of {:#x} bytes",
+                        entry.pd, entry.footprint.total, entry.budget
+                    )?;
+                    for (name, size) in &entry.footprint.sections {
+                        write!(f, "\n    {name:<24} {size:#x}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ImageBudgetError {}
@@ -0,0 +1,201 @@
+use sel4_capdl_initializer_types::{
+    cap, object, Cap, CapTableEntry, FileContent, Fill, FrameInit, IRQEntry, Indirect, NamedObject,
+    Object, ObjectId, Rights, Spec,
+};
+
+use crate::{ChannelEnd, ProtectionDomainId, System};
+
+/// The spec type emitted by [`System::to_capdl_spec`]. Its JSON serialization is of the same form
+/// as that consumed by `sel4-capdl-initializer-add-spec`.
+pub type OutputSpec = Spec<'static, String, FileContent, ()>;
+
+// Mirrors the CSpace layout expected by the `sel4-microkit` crate.
+const INPUT_CAP: usize = 1;
+const REPLY_CAP: usize = 4;
+const BASE_OUTPUT_NOTIFICATION_CAP: usize = 10;
+const BASE_ENDPOINT_CAP: usize = BASE_OUTPUT_NOTIFICATION_CAP + 64;
+const BASE_IRQ_CAP: usize = BASE_ENDPOINT_CAP + 64;
+const BASE_TCB_CAP: usize = BASE_IRQ_CAP + 64;
+const BASE_SPARE_REPLY_CAP: usize = BASE_TCB_CAP + 64;
+
+const CNODE_SIZE_BITS: usize = 9;
+
+const PP_BADGE_BIT: u64 = 1 << 63;
+
+const ALL_RIGHTS: Rights = Rights {
+    read: true,
+    write: true,
+    grant: true,
+    grant_reply: true,
+};
+
+struct PdObjects {
+    notification: ObjectId,
+    endpoint: Option<ObjectId>,
+    reply: ObjectId,
+    spare_replies: Vec<ObjectId>,
+}
+
+impl System {
+    /// Emits the kernel objects implied by this system's topology: the frames backing each memory
+    /// region, each protection domain's notification, endpoint, reply objects, and CNode (laid out
+    /// as the `sel4-microkit` crate expects), and IRQ handlers.
+    ///
+    /// Objects whose contents depend on program images, such as TCBs and address spaces, are not
+    /// emitted, and are expected to be added by image-aware tooling.
+    pub fn to_capdl_spec(&self) -> OutputSpec {
+        let mut objects: Vec<NamedObject<'static, String, FileContent, ()>> = vec![];
+
+        let mut push = |name: String, object| {
+            objects.push(NamedObject { name, object });
+            objects.len() - 1
+        };
+
+        for (_, mr) in self.memory_regions() {
+            for i in 0..mr.num_pages() {
+                push(
+                    format!("mr_{}_{}", mr.name, i),
+                    Object::Frame(object::Frame {
+                        size_bits: mr.page_size.trailing_zeros().try_into().unwrap(),
+                        paddr: mr.phys_addr.map(|paddr| paddr + i * mr.page_size),
+                        init: FrameInit::Fill(Fill {
+                            entries: Indirect::from_owned(vec![].into_boxed_slice()),
+                        }),
+                    }),
+                );
+            }
+        }
+
+        let pd_objects = self
+            .protection_domains()
+            .map(|(_, pd)| PdObjects {
+                notification: push(format!("notification_{}", pd.name), Object::Notification),
+                endpoint: pd
+                    .pp
+                    .then(|| push(format!("endpoint_{}", pd.name), Object::Endpoint)),
+                reply: push(format!("reply_{}", pd.name), Object::Reply),
+                spare_replies: (0..pd.spare_reply_objects)
+                    .map(|i| push(format!("reply_{}_spare_{}", pd.name, i), Object::Reply))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut irqs = vec![];
+
+        for ((pd_id, pd), this) in self.protection_domains().zip(pd_objects.iter()) {
+            let mut slots: Vec<CapTableEntry> = vec![];
+
+            slots.push((
+                INPUT_CAP,
+                match this.endpoint {
+                    Some(object) => Cap::Endpoint(cap::Endpoint {
+                        object,
+                        badge: 0,
+                        rights: ALL_RIGHTS,
+                    }),
+                    None => Cap::Notification(cap::Notification {
+                        object: this.notification,
+                        badge: 0,
+                        rights: ALL_RIGHTS,
+                    }),
+                },
+            ));
+
+            slots.push((REPLY_CAP, Cap::Reply(cap::Reply { object: this.reply })));
+
+            for (i, &object) in this.spare_replies.iter().enumerate() {
+                slots.push((BASE_SPARE_REPLY_CAP + i, Cap::Reply(cap::Reply { object })));
+            }
+
+            for (local, remote) in self.channel_ends_of(pd_id) {
+                let peer = &pd_objects[remote.pd.index];
+                slots.push((
+                    BASE_OUTPUT_NOTIFICATION_CAP + local.id,
+                    Cap::Notification(cap::Notification {
+                        object: peer.notification,
+                        badge: 1 << remote.id,
+                        rights: ALL_RIGHTS,
+                    }),
+                ));
+                if let Some(object) = peer.endpoint {
+                    slots.push((
+                        BASE_ENDPOINT_CAP + local.id,
+                        Cap::Endpoint(cap::Endpoint {
+                            object,
+                            badge: PP_BADGE_BIT | u64::try_from(remote.id).unwrap(),
+                            rights: ALL_RIGHTS,
+                        }),
+                    ));
+                }
+            }
+
+            for irq in pd.irqs.iter() {
+                let handler = push(
+                    format!("irq_{}_{}", pd.name, irq.irq),
+                    Object::IRQ(object::IRQ {
+                        slots: Indirect::from_owned(
+                            vec![(
+                                object::IRQ::SLOT_NOTIFICATION,
+                                Cap::Notification(cap::Notification {
+                                    object: this.notification,
+                                    badge: 1 << irq.id,
+                                    rights: ALL_RIGHTS,
+                                }),
+                            )]
+                            .into_boxed_slice(),
+                        ),
+                    }),
+                );
+                irqs.push(IRQEntry {
+                    irq: irq.irq,
+                    handler,
+                });
+                slots.push((
+                    BASE_IRQ_CAP + irq.id,
+                    Cap::IRQHandler(cap::IRQHandler { object: handler }),
+                ));
+            }
+
+            slots.sort_by_key(|(slot, _)| *slot);
+
+            push(
+                format!("cnode_{}", pd.name),
+                Object::CNode(object::CNode {
+                    size_bits: CNODE_SIZE_BITS,
+                    slots: Indirect::from_owned(slots.into_boxed_slice()),
+                }),
+            );
+        }
+
+        let num_objects = objects.len();
+
+        Spec {
+            objects: Indirect::from_owned(objects.into_boxed_slice()),
+            irqs: Indirect::from_owned(irqs.into_boxed_slice()),
+            asid_slots: Indirect::from_owned(vec![].into_boxed_slice()),
+            root_objects: 0..num_objects,
+            untyped_covers: Indirect::from_owned(vec![].into_boxed_slice()),
+        }
+    }
+
+    /// Serializes the output of [`System::to_capdl_spec`] as JSON.
+    pub fn to_capdl_spec_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_capdl_spec()).unwrap()
+    }
+
+    fn channel_ends_of(
+        &self,
+        pd: ProtectionDomainId,
+    ) -> impl Iterator<Item = (ChannelEnd, ChannelEnd)> + '_ {
+        self.channels().iter().filter_map(move |channel| {
+            let [a, b] = channel.ends;
+            if a.pd == pd {
+                Some((a, b))
+            } else if b.pd == pd {
+                Some((b, a))
+            } else {
+                None
+            }
+        })
+    }
+}
@@ -0,0 +1,684 @@
+//! A typed builder for describing the static topology of a system of components.
+//!
+//! Users describe protection domains, memory regions, devices, and channels using
+//! [`SystemBuilder`], and then [`SystemBuilder::build`] validates the description. The resulting
+//! [`System`] can be rendered as a seL4 Microkit system description (see [`System::to_microkit_xml`])
+//! or as a CapDL spec of the same shape as those consumed by `sel4-capdl-initializer` (see
+//! [`System::to_capdl_spec`]).
+//!
+//! Protection domains can also declare budgets for the memory occupied by their program images,
+//! which [`System::check_image_budgets`] enforces after linking.
+//!
+//! A single description can thus serve as the source of truth for system topology, and custom
+//! generators can be written against the typed model in this crate rather than against XML or JSON.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod budget;
+mod capdl;
+mod microkit;
+mod validate;
+
+pub use budget::{ImageBudgetError, ImageBudgetExceeded, ImageFootprint};
+pub use capdl::OutputSpec;
+pub use validate::ValidationError;
+
+/// Channel identifiers are local to a protection domain, and are shared between notifications,
+/// protected procedure calls, and IRQs, just like in the seL4 Microkit.
+pub type ChannelId = usize;
+
+/// The largest channel identifier supported by the seL4 Microkit, plus one.
+pub const MAX_CHANNELS: ChannelId = 63;
+
+/// The largest priority supported by the seL4 Microkit.
+pub const MAX_PRIORITY: u8 = 254;
+
+/// The largest number of spare reply objects supported by the `sel4-microkit` crate.
+pub const MAX_SPARE_REPLY_OBJECTS: usize = 16;
+
+pub const DEFAULT_PAGE_SIZE: usize = 0x1000;
+
+/// Identifies the [`SystemBuilder`] which issued an ID, so that IDs issued by one builder are
+/// rejected by another.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+struct BuilderId(usize);
+
+impl BuilderId {
+    fn fresh() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ProtectionDomainId {
+    builder: BuilderId,
+    index: usize,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MemoryRegionId {
+    builder: BuilderId,
+    index: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProtectionDomain {
+    pub name: String,
+    pub program_image: PathBuf,
+    pub priority: u8,
+    pub pp: bool,
+    pub passive: bool,
+    pub maps: Vec<Map>,
+    pub irqs: Vec<Irq>,
+    pub setvars: Vec<SetVar>,
+    pub image_budget: Option<usize>,
+    pub spare_reply_objects: usize,
+}
+
+impl ProtectionDomain {
+    pub fn new(name: impl Into<String>, program_image: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            program_image: program_image.into(),
+            priority: 100,
+            pp: false,
+            passive: false,
+            maps: vec![],
+            irqs: vec![],
+            setvars: vec![],
+            image_budget: None,
+            spare_reply_objects: 0,
+        }
+    }
+
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Whether this protection domain accepts protected procedure calls.
+    pub fn pp(mut self, pp: bool) -> Self {
+        self.pp = pp;
+        self
+    }
+
+    pub fn passive(mut self, passive: bool) -> Self {
+        self.passive = passive;
+        self
+    }
+
+    /// The maximum number of bytes that this protection domain's program image may occupy once
+    /// loaded. See [`System::check_image_budgets`].
+    pub fn image_budget(mut self, bytes: usize) -> Self {
+        self.image_budget = Some(bytes);
+        self
+    }
+
+    /// The number of reply objects, in addition to its own, with which this protection domain can
+    /// defer replies to protected procedure calls (see `sel4_microkit::reply`). They are placed in
+    /// the CSpace slots from which `sel4_microkit::reply::add_provisioned_reply_objects` takes
+    /// them.
+    ///
+    /// The seL4 Microkit tool cannot provision reply objects, so they are only emitted by
+    /// [`System::to_capdl_spec`], and are omitted from [`System::to_microkit_xml`].
+    pub fn spare_reply_objects(mut self, n: usize) -> Self {
+        self.spare_reply_objects = n;
+        self
+    }
+
+    pub fn setvar(mut self, symbol: impl Into<String>, value: SetVarValue) -> Self {
+        self.setvars.push(SetVar {
+            symbol: symbol.into(),
+            value,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub size: usize,
+    pub page_size: usize,
+    pub phys_addr: Option<usize>,
+}
+
+impl MemoryRegion {
+    pub fn new(name: impl Into<String>, size: usize) -> Self {
+        Self {
+            name: name.into(),
+            size,
+            page_size: DEFAULT_PAGE_SIZE,
+            phys_addr: None,
+        }
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn phys_addr(mut self, phys_addr: usize) -> Self {
+        self.phys_addr = Some(phys_addr);
+        self
+    }
+
+    pub fn num_pages(&self) -> usize {
+        self.size / self.page_size
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Map {
+    pub mr: MemoryRegionId,
+    pub vaddr: usize,
+    pub perms: Perms,
+    pub cached: bool,
+    pub setvar_vaddr: Option<String>,
+}
+
+impl Map {
+    pub fn new(mr: MemoryRegionId, vaddr: usize) -> Self {
+        Self {
+            mr,
+            vaddr,
+            perms: Perms::RW,
+            cached: true,
+            setvar_vaddr: None,
+        }
+    }
+
+    pub fn perms(mut self, perms: Perms) -> Self {
+        self.perms = perms;
+        self
+    }
+
+    pub fn cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+
+    pub fn setvar_vaddr(mut self, symbol: impl Into<String>) -> Self {
+        self.setvar_vaddr = Some(symbol.into());
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Perms {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Perms {
+    pub const R: Self = Self {
+        read: true,
+        write: false,
+        execute: false,
+    };
+
+    pub const RW: Self = Self {
+        read: true,
+        write: true,
+        execute: false,
+    };
+
+    pub const RX: Self = Self {
+        read: true,
+        write: false,
+        execute: true,
+    };
+}
+
+impl fmt::Display for Perms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.read {
+            write!(f, "r")?;
+        }
+        if self.write {
+            write!(f, "w")?;
+        }
+        if self.execute {
+            write!(f, "x")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Irq {
+    pub irq: u64,
+    pub id: ChannelId,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SetVar {
+    pub symbol: String,
+    pub value: SetVarValue,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SetVarValue {
+    /// A literal value, rendered using the `vaddr` attribute.
+    Literal(usize),
+    /// The physical address of a memory region.
+    RegionPaddr(MemoryRegionId),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Channel {
+    pub ends: [ChannelEnd; 2],
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ChannelEnd {
+    pub pd: ProtectionDomainId,
+    pub id: ChannelId,
+}
+
+/// A protection domain which waits, with `sel4_microkit::await_ready`, for another to become
+/// ready. See [`SystemBuilder::awaits_ready`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReadinessDependency {
+    pub dependent: ProtectionDomainId,
+    pub dependency: ProtectionDomainId,
+}
+
+/// Accumulates a description of a system. See the [crate-level documentation](crate).
+///
+/// IDs issued by one builder may only be used with that builder, or with clones of it made after
+/// they were issued. Otherwise, [`SystemBuilder::build`] fails.
+#[derive(Debug, Clone)]
+pub struct SystemBuilder {
+    id: BuilderId,
+    memory_regions: Vec<MemoryRegion>,
+    protection_domains: Vec<ProtectionDomain>,
+    channels: Vec<Channel>,
+    readiness_dependencies: Vec<ReadinessDependency>,
+    // Attached to their protection domains by `build`, once their IDs have been checked.
+    maps: Vec<(ProtectionDomainId, Map)>,
+    irqs: Vec<(ProtectionDomainId, Irq)>,
+}
+
+impl Default for SystemBuilder {
+    fn default() -> Self {
+        Self {
+            id: BuilderId::fresh(),
+            memory_regions: vec![],
+            protection_domains: vec![],
+            channels: vec![],
+            readiness_dependencies: vec![],
+            maps: vec![],
+            irqs: vec![],
+        }
+    }
+}
+
+impl SystemBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn memory_region(&mut self, mr: MemoryRegion) -> MemoryRegionId {
+        self.memory_regions.push(mr);
+        MemoryRegionId {
+            builder: self.id,
+            index: self.memory_regions.len() - 1,
+        }
+    }
+
+    /// Declares a device's MMIO region, which is just a memory region at a fixed physical address.
+    pub fn device(
+        &mut self,
+        name: impl Into<String>,
+        phys_addr: usize,
+        size: usize,
+    ) -> MemoryRegionId {
+        self.memory_region(MemoryRegion::new(name, size).phys_addr(phys_addr))
+    }
+
+    pub fn protection_domain(&mut self, pd: ProtectionDomain) -> ProtectionDomainId {
+        self.protection_domains.push(pd);
+        ProtectionDomainId {
+            builder: self.id,
+            index: self.protection_domains.len() - 1,
+        }
+    }
+
+    pub fn map(&mut self, pd: ProtectionDomainId, map: Map) -> &mut Self {
+        self.maps.push((pd, map));
+        self
+    }
+
+    pub fn irq(&mut self, pd: ProtectionDomainId, irq: u64, id: ChannelId) -> &mut Self {
+        self.irqs.push((pd, Irq { irq, id }));
+        self
+    }
+
+    pub fn channel(
+        &mut self,
+        a: (ProtectionDomainId, ChannelId),
+        b: (ProtectionDomainId, ChannelId),
+    ) -> &mut Self {
+        self.channels.push(Channel {
+            ends: [a, b].map(|(pd, id)| ChannelEnd { pd, id }),
+        });
+        self
+    }
+
+    /// Declares that `dependent` waits for `dependency` to become ready during bring-up.
+    ///
+    /// `sel4_microkit::await_ready` yields while it waits, and a yield only gives way to threads
+    /// of the same priority, so [`SystemBuilder::build`] checks that `dependency` does not have a
+    /// lower priority than `dependent`.
+    pub fn awaits_ready(
+        &mut self,
+        dependent: ProtectionDomainId,
+        dependency: ProtectionDomainId,
+    ) -> &mut Self {
+        self.readiness_dependencies.push(ReadinessDependency {
+            dependent,
+            dependency,
+        });
+        self
+    }
+
+    pub fn build(mut self) -> Result<System, ValidationError> {
+        for (pd, map) in self.maps {
+            self.protection_domains
+                .get_mut(pd.index)
+                .filter(|_| pd.builder == self.id)
+                .ok_or(ValidationError::ForeignProtectionDomainId)?
+                .maps
+                .push(map);
+        }
+        for (pd, irq) in self.irqs {
+            self.protection_domains
+                .get_mut(pd.index)
+                .filter(|_| pd.builder == self.id)
+                .ok_or(ValidationError::ForeignProtectionDomainId)?
+                .irqs
+                .push(irq);
+        }
+        let system = System {
+            builder: self.id,
+            memory_regions: self.memory_regions,
+            protection_domains: self.protection_domains,
+            channels: self.channels,
+            readiness_dependencies: self.readiness_dependencies,
+        };
+        system.validate()?;
+        Ok(system)
+    }
+}
+
+/// A validated system description.
+#[derive(Debug, Clone)]
+pub struct System {
+    builder: BuilderId,
+    memory_regions: Vec<MemoryRegion>,
+    protection_domains: Vec<ProtectionDomain>,
+    channels: Vec<Channel>,
+    readiness_dependencies: Vec<ReadinessDependency>,
+}
+
+impl System {
+    pub fn memory_regions(&self) -> impl Iterator<Item = (MemoryRegionId, &MemoryRegion)> {
+        self.memory_regions.iter().enumerate().map(|(index, mr)| {
+            (
+                MemoryRegionId {
+                    builder: self.builder,
+                    index,
+                },
+                mr,
+            )
+        })
+    }
+
+    pub fn protection_domains(
+        &self,
+    ) -> impl Iterator<Item = (ProtectionDomainId, &ProtectionDomain)> {
+        self.protection_domains
+            .iter()
+            .enumerate()
+            .map(|(index, pd)| {
+                (
+                    ProtectionDomainId {
+                        builder: self.builder,
+                        index,
+                    },
+                    pd,
+                )
+            })
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    pub fn readiness_dependencies(&self) -> &[ReadinessDependency] {
+        &self.readiness_dependencies
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `id` was issued by a builder other than the one from which this system was built.
+    pub fn memory_region(&self, id: MemoryRegionId) -> &MemoryRegion {
+        assert_eq!(id.builder, self.builder, "foreign memory region ID");
+        &self.memory_regions[id.index]
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `id` was issued by a builder other than the one from which this system was built.
+    pub fn protection_domain(&self, id: ProtectionDomainId) -> &ProtectionDomain {
+        assert_eq!(id.builder, self.builder, "foreign protection domain ID");
+        &self.protection_domains[id.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sel4_capdl_initializer_types::{Cap, Object};
+
+    use super::*;
+
+    fn example() -> SystemBuilder {
+        let mut builder = SystemBuilder::new();
+        let uart = builder.device("uart", 0x900_0000, 0x1000);
+        let driver = builder.protection_domain(
+            ProtectionDomain::new("driver", "driver.elf")
+                .priority(254)
+                .pp(true),
+        );
+        let client = builder.protection_domain(ProtectionDomain::new("client", "client.elf"));
+        builder
+            .map(
+                driver,
+                Map::new(uart, 0x5_000_000_000)
+                    .cached(false)
+                    .setvar_vaddr("uart_vaddr"),
+            )
+            .irq(driver, 33, 0)
+            .channel((client, 0), (driver, 1));
+        builder
+    }
+
+    #[test]
+    fn renders_microkit_xml() {
+        let xml = example().build().unwrap().to_microkit_xml();
+        assert!(xml.contains(r#"<memory_region name="uart" size="0x1000" page_size="0x1000" phys_addr="0x9000000" />"#));
+        assert!(xml.contains(r#"<protection_domain name="driver" priority="254" pp="true">"#));
+        assert!(xml.contains(r#"<irq irq="33" id="0" />"#));
+        assert!(xml.contains(r#"<end pd="client" id="0" />"#));
+    }
+
+    #[test]
+    fn rejects_reused_channel_id() {
+        let mut builder = example();
+        let [driver, client] = [0, 1].map(|index| ProtectionDomainId {
+            builder: builder.id,
+            index,
+        });
+        builder.channel((client, 1), (driver, 0));
+        assert!(matches!(
+            builder.build(),
+            Err(ValidationError::ChannelIdInUse { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_map_past_end_of_address_space() {
+        let mut builder = SystemBuilder::new();
+        let mr = builder.memory_region(MemoryRegion::new("mr", 0x2000));
+        let pd = builder.protection_domain(ProtectionDomain::new("pd", "pd.elf"));
+        builder.map(pd, Map::new(mr, usize::MAX - 0xfff));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::MapOutOfRange {
+                pd: "pd".to_owned(),
+                mr: "mr".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_ids_from_other_builders() {
+        let mut other = SystemBuilder::new();
+        let other_mr = other.memory_region(MemoryRegion::new("other", 0x1000));
+        let other_pd = other.protection_domain(ProtectionDomain::new("other", "other.elf"));
+
+        let mut builder = example();
+        builder.map(other_pd, Map::new(other_mr, 0x1000));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::ForeignProtectionDomainId
+        );
+
+        let mut builder = example();
+        builder.protection_domain(
+            ProtectionDomain::new("pd", "pd.elf")
+                .setvar("other_paddr", SetVarValue::RegionPaddr(other_mr)),
+        );
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::ForeignMemoryRegionId {
+                pd: "pd".to_owned()
+            }
+        );
+
+        let mut builder = example();
+        let pd = builder.protection_domain(ProtectionDomain::new("pd", "pd.elf"));
+        builder.channel((pd, 0), (other_pd, 0));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::ForeignProtectionDomainId
+        );
+    }
+
+    #[test]
+    fn rejects_readiness_dependency_on_lower_priority() {
+        let mut builder = example();
+        let [driver, client] = [0, 1].map(|index| ProtectionDomainId {
+            builder: builder.id,
+            index,
+        });
+        builder.awaits_ready(client, driver);
+        assert!(builder.clone().build().is_ok());
+
+        builder.awaits_ready(driver, client);
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::ReadinessDependencyPriority {
+                pd: "driver".to_owned(),
+                dependency: "client".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn emits_capdl_irqs() {
+        let spec = example().build().unwrap().to_capdl_spec();
+        assert_eq!(spec.irqs.len(), 1);
+        assert_eq!(spec.irqs[0].irq, 33);
+    }
+
+    #[test]
+    fn emits_capdl_spare_reply_objects() {
+        let mut builder = example();
+        builder.protection_domain(
+            ProtectionDomain::new("server", "server.elf")
+                .pp(true)
+                .spare_reply_objects(2),
+        );
+        let spec = builder.build().unwrap().to_capdl_spec();
+        let reply_ids = ["reply_server_spare_0", "reply_server_spare_1"].map(|name| {
+            let id = spec
+                .objects
+                .iter()
+                .position(|object| object.name == name)
+                .unwrap();
+            assert!(matches!(spec.objects[id].object, Object::Reply));
+            id
+        });
+        let cnode = spec
+            .objects
+            .iter()
+            .find_map(|object| match &object.object {
+                Object::CNode(cnode) if object.name == "cnode_server" => Some(cnode),
+                _ => None,
+            })
+            .unwrap();
+        for (i, reply_id) in reply_ids.into_iter().enumerate() {
+            // `BASE_SPARE_REPLY_CAP` in `sel4-microkit`.
+            let slot = 266 + i;
+            assert!(slot < 1 << cnode.size_bits);
+            assert!(cnode.slots.iter().any(|(s, cap)| {
+                *s == slot && matches!(cap, Cap::Reply(reply) if reply.object == reply_id)
+            }));
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_spare_reply_objects() {
+        let mut builder = SystemBuilder::new();
+        builder.protection_domain(
+            ProtectionDomain::new("pd", "pd.elf")
+                .pp(true)
+                .spare_reply_objects(MAX_SPARE_REPLY_OBJECTS + 1),
+        );
+        assert_eq!(
+            builder.build().unwrap_err(),
+            ValidationError::TooManySpareReplyObjects {
+                pd: "pd".to_owned(),
+                num: MAX_SPARE_REPLY_OBJECTS + 1,
+            }
+        );
+    }
+
+    #[test]
+    fn checks_image_budgets() {
+        let this_exe = std::env::current_exe().unwrap();
+        let check = |budget| {
+            let mut builder = SystemBuilder::new();
+            builder.protection_domain(ProtectionDomain::new("pd", &this_exe).image_budget(budget));
+            builder.build().unwrap().check_image_budgets()
+        };
+        assert!(check(usize::MAX).is_ok());
+        match check(DEFAULT_PAGE_SIZE) {
+            Err(ImageBudgetError::Exceeded(exceeded)) => {
+                assert_eq!(exceeded[0].pd, "pd");
+                assert!(exceeded[0]
+                    .footprint
+                    .sections
+                    .iter()
+                    .any(|(name, _)| name == ".text"));
+            }
+            r => panic!("{r:?}"),
+        }
+    }
+}
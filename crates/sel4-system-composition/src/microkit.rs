@@ -0,0 +1,123 @@
+use std::fmt::{self, Write};
+
+use crate::{SetVarValue, System};
+
+impl System {
+    /// Renders this system as a seL4 Microkit system description.
+    ///
+    /// The seL4 Microkit tool cannot provision spare reply objects, so
+    /// [`ProtectionDomain::spare_reply_objects`](crate::ProtectionDomain::spare_reply_objects) is
+    /// not reflected in the output.
+    pub fn to_microkit_xml(&self) -> String {
+        let mut s = String::new();
+        self.write_microkit_xml(&mut s).unwrap();
+        s
+    }
+
+    pub fn write_microkit_xml(&self, w: &mut impl Write) -> fmt::Result {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, "<system>")?;
+
+        for (_, mr) in self.memory_regions() {
+            write!(
+                w,
+                r#"    <memory_region name="{}" size="{:#x}" page_size="{:#x}""#,
+                escape(&mr.name),
+                mr.size,
+                mr.page_size,
+            )?;
+            if let Some(phys_addr) = mr.phys_addr {
+                write!(w, r#" phys_addr="{:#x}""#, phys_addr)?;
+            }
+            writeln!(w, " />")?;
+        }
+
+        for (_, pd) in self.protection_domains() {
+            writeln!(w)?;
+            write!(
+                w,
+                r#"    <protection_domain name="{}" priority="{}""#,
+                escape(&pd.name),
+                pd.priority,
+            )?;
+            if pd.pp {
+                write!(w, r#" pp="true""#)?;
+            }
+            if pd.passive {
+                write!(w, r#" passive="true""#)?;
+            }
+            writeln!(w, ">")?;
+            writeln!(
+                w,
+                r#"        <program_image path="{}" />"#,
+                escape(&pd.program_image.display().to_string()),
+            )?;
+            for map in pd.maps.iter() {
+                write!(
+                    w,
+                    r#"        <map mr="{}" vaddr="{:#x}" perms="{}" cached="{}""#,
+                    escape(&self.memory_region(map.mr).name),
+                    map.vaddr,
+                    map.perms,
+                    map.cached,
+                )?;
+                if let Some(symbol) = &map.setvar_vaddr {
+                    write!(w, r#" setvar_vaddr="{}""#, escape(symbol))?;
+                }
+                writeln!(w, " />")?;
+            }
+            for irq in pd.irqs.iter() {
+                writeln!(w, r#"        <irq irq="{}" id="{}" />"#, irq.irq, irq.id)?;
+            }
+            for setvar in pd.setvars.iter() {
+                match &setvar.value {
+                    SetVarValue::Literal(value) => writeln!(
+                        w,
+                        r#"        <setvar symbol="{}" vaddr="{:#x}" />"#,
+                        escape(&setvar.symbol),
+                        value,
+                    )?,
+                    SetVarValue::RegionPaddr(mr) => writeln!(
+                        w,
+                        r#"        <setvar symbol="{}" region_paddr="{}" />"#,
+                        escape(&setvar.symbol),
+                        escape(&self.memory_region(*mr).name),
+                    )?,
+                }
+            }
+            writeln!(w, "    </protection_domain>")?;
+        }
+
+        for channel in self.channels() {
+            writeln!(w)?;
+            writeln!(w, "    <channel>")?;
+            for end in channel.ends.iter() {
+                writeln!(
+                    w,
+                    r#"        <end pd="{}" id="{}" />"#,
+                    escape(&self.protection_domain(end.pd).name),
+                    end.id,
+                )?;
+            }
+            writeln!(w, "    </channel>")?;
+        }
+
+        writeln!(w, "</system>")?;
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
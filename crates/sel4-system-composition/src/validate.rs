@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::{
+    ChannelId, MemoryRegionId, ProtectionDomainId, SetVarValue, System, MAX_CHANNELS, MAX_PRIORITY,
+    MAX_SPARE_REPLY_OBJECTS,
+};
+
+/// Error type returned by [`SystemBuilder::build`](crate::SystemBuilder::build).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ValidationError {
+    DuplicateProtectionDomainName(String),
+    DuplicateMemoryRegionName(String),
+    MemoryRegionSizeNotPageAligned {
+        mr: String,
+    },
+    MemoryRegionPhysAddrNotPageAligned {
+        mr: String,
+    },
+    InvalidPageSize {
+        mr: String,
+        page_size: usize,
+    },
+    MapVaddrNotPageAligned {
+        pd: String,
+        mr: String,
+    },
+    MapOutOfRange {
+        pd: String,
+        mr: String,
+    },
+    OverlappingMaps {
+        pd: String,
+        mrs: [String; 2],
+    },
+    PriorityOutOfRange {
+        pd: String,
+        priority: u8,
+    },
+    PassiveWithoutPp {
+        pd: String,
+    },
+    TooManySpareReplyObjects {
+        pd: String,
+        num: usize,
+    },
+    ChannelIdOutOfRange {
+        pd: String,
+        id: ChannelId,
+    },
+    ChannelIdInUse {
+        pd: String,
+        id: ChannelId,
+    },
+    ChannelToSelf {
+        pd: String,
+    },
+    /// `pd` awaits the readiness of a protection domain with a lower priority, which would not run
+    /// while `pd` waits.
+    ReadinessDependencyPriority {
+        pd: String,
+        dependency: String,
+    },
+    /// A protection domain ID issued by a different [`SystemBuilder`](crate::SystemBuilder).
+    ForeignProtectionDomainId,
+    /// A memory region ID issued by a different [`SystemBuilder`](crate::SystemBuilder).
+    ForeignMemoryRegionId {
+        pd: String,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateProtectionDomainName(name) => {
+                write!(f, "duplicate protection domain name: {name:?}")
+            }
+            Self::DuplicateMemoryRegionName(name) => {
+                write!(f, "duplicate memory region name: {name:?}")
+            }
+            Self::MemoryRegionSizeNotPageAligned { mr } => {
+                write!(
+                    f,
+                    "size of memory region {mr:?} is not a multiple of its page size"
+                )
+            }
+            Self::MemoryRegionPhysAddrNotPageAligned { mr } => {
+                write!(
+                    f,
+                    "physical address of memory region {mr:?} is not page-aligned"
+                )
+            }
+            Self::InvalidPageSize { mr, page_size } => {
+                write!(
+                    f,
+                    "invalid page size {page_size:#x} for memory region {mr:?}"
+                )
+            }
+            Self::MapVaddrNotPageAligned { pd, mr } => {
+                write!(f, "mapping of {mr:?} into {pd:?} is not page-aligned")
+            }
+            Self::MapOutOfRange { pd, mr } => {
+                write!(
+                    f,
+                    "mapping of {mr:?} into {pd:?} extends past the end of the address space"
+                )
+            }
+            Self::OverlappingMaps { pd, mrs: [a, b] } => {
+                write!(f, "mappings of {a:?} and {b:?} into {pd:?} overlap")
+            }
+            Self::PriorityOutOfRange { pd, priority } => {
+                write!(f, "priority {priority} of {pd:?} exceeds {MAX_PRIORITY}")
+            }
+            Self::PassiveWithoutPp { pd } => {
+                write!(
+                    f,
+                    "{pd:?} is passive but does not accept protected procedure calls"
+                )
+            }
+            Self::TooManySpareReplyObjects { pd, num } => {
+                write!(
+                    f,
+                    "{pd:?} has {num} spare reply objects, more than {MAX_SPARE_REPLY_OBJECTS}"
+                )
+            }
+            Self::ChannelIdOutOfRange { pd, id } => {
+                write!(f, "channel id {id} of {pd:?} exceeds {}", MAX_CHANNELS - 1)
+            }
+            Self::ChannelIdInUse { pd, id } => {
+                write!(f, "channel id {id} of {pd:?} is used more than once")
+            }
+            Self::ChannelToSelf { pd } => {
+                write!(f, "channel from {pd:?} to itself")
+            }
+            Self::ReadinessDependencyPriority { pd, dependency } => {
+                write!(
+                    f,
+                    "{pd:?} awaits the readiness of {dependency:?}, which has a lower priority"
+                )
+            }
+            Self::ForeignProtectionDomainId => {
+                write!(f, "protection domain ID issued by a different builder")
+            }
+            Self::ForeignMemoryRegionId { pd } => {
+                write!(
+                    f,
+                    "memory region ID used by {pd:?} was issued by a different builder"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+impl System {
+    pub(crate) fn validate(&self) -> Result<(), ValidationError> {
+        let mut mr_names = BTreeSet::new();
+        for mr in self.memory_regions.iter() {
+            if !mr_names.insert(&mr.name) {
+                return Err(ValidationError::DuplicateMemoryRegionName(mr.name.clone()));
+            }
+            if !mr.page_size.is_power_of_two() {
+                return Err(ValidationError::InvalidPageSize {
+                    mr: mr.name.clone(),
+                    page_size: mr.page_size,
+                });
+            }
+            if mr.size == 0 || mr.size % mr.page_size != 0 {
+                return Err(ValidationError::MemoryRegionSizeNotPageAligned {
+                    mr: mr.name.clone(),
+                });
+            }
+            if let Some(phys_addr) = mr.phys_addr {
+                if phys_addr % mr.page_size != 0 {
+                    return Err(ValidationError::MemoryRegionPhysAddrNotPageAligned {
+                        mr: mr.name.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut pd_names = BTreeSet::new();
+        let mut channel_ids = BTreeMap::<_, BTreeSet<ChannelId>>::new();
+
+        for (pd_id, pd) in self.protection_domains() {
+            if !pd_names.insert(&pd.name) {
+                return Err(ValidationError::DuplicateProtectionDomainName(
+                    pd.name.clone(),
+                ));
+            }
+            if pd.priority > MAX_PRIORITY {
+                return Err(ValidationError::PriorityOutOfRange {
+                    pd: pd.name.clone(),
+                    priority: pd.priority,
+                });
+            }
+            if pd.passive && !pd.pp {
+                return Err(ValidationError::PassiveWithoutPp {
+                    pd: pd.name.clone(),
+                });
+            }
+            if pd.spare_reply_objects > MAX_SPARE_REPLY_OBJECTS {
+                return Err(ValidationError::TooManySpareReplyObjects {
+                    pd: pd.name.clone(),
+                    num: pd.spare_reply_objects,
+                });
+            }
+
+            let foreign_mr = || ValidationError::ForeignMemoryRegionId {
+                pd: pd.name.clone(),
+            };
+            for setvar in pd.setvars.iter() {
+                if let SetVarValue::RegionPaddr(mr) = setvar.value {
+                    if !self.issued_memory_region_id(mr) {
+                        return Err(foreign_mr());
+                    }
+                }
+            }
+
+            let mut ranges = vec![];
+            for map in pd.maps.iter() {
+                if !self.issued_memory_region_id(map.mr) {
+                    return Err(foreign_mr());
+                }
+                let mr = self.memory_region(map.mr);
+                if map.vaddr % mr.page_size != 0 {
+                    return Err(ValidationError::MapVaddrNotPageAligned {
+                        pd: pd.name.clone(),
+                        mr: mr.name.clone(),
+                    });
+                }
+                let end = map.vaddr.checked_add(mr.size).ok_or_else(|| {
+                    ValidationError::MapOutOfRange {
+                        pd: pd.name.clone(),
+                        mr: mr.name.clone(),
+                    }
+                })?;
+                ranges.push((map.vaddr..end, &mr.name));
+            }
+            ranges.sort_by_key(|(range, _)| range.start);
+            for pair in ranges.windows(2) {
+                let [(a, a_name), (b, b_name)] = pair else {
+                    unreachable!()
+                };
+                if a.end > b.start {
+                    return Err(ValidationError::OverlappingMaps {
+                        pd: pd.name.clone(),
+                        mrs: [a_name.to_string(), b_name.to_string()],
+                    });
+                }
+            }
+
+            for irq in pd.irqs.iter() {
+                claim_channel_id(&mut channel_ids, pd_id, &pd.name, irq.id)?;
+            }
+        }
+
+        for channel in self.channels.iter() {
+            if !channel
+                .ends
+                .iter()
+                .all(|end| self.issued_protection_domain_id(end.pd))
+            {
+                return Err(ValidationError::ForeignProtectionDomainId);
+            }
+            let [a, b] = &channel.ends;
+            if a.pd == b.pd {
+                return Err(ValidationError::ChannelToSelf {
+                    pd: self.protection_domain(a.pd).name.clone(),
+                });
+            }
+            for end in &channel.ends {
+                let name = &self.protection_domain(end.pd).name;
+                claim_channel_id(&mut channel_ids, end.pd, name, end.id)?;
+            }
+        }
+
+        for dep in self.readiness_dependencies.iter() {
+            if !self.issued_protection_domain_id(dep.dependent)
+                || !self.issued_protection_domain_id(dep.dependency)
+            {
+                return Err(ValidationError::ForeignProtectionDomainId);
+            }
+            let pd = self.protection_domain(dep.dependent);
+            let dependency = self.protection_domain(dep.dependency);
+            if dependency.priority < pd.priority {
+                return Err(ValidationError::ReadinessDependencyPriority {
+                    pd: pd.name.clone(),
+                    dependency: dependency.name.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn issued_memory_region_id(&self, id: MemoryRegionId) -> bool {
+        id.builder == self.builder && id.index < self.memory_regions.len()
+    }
+
+    fn issued_protection_domain_id(&self, id: ProtectionDomainId) -> bool {
+        id.builder == self.builder && id.index < self.protection_domains.len()
+    }
+}
+
+fn claim_channel_id<K: Ord>(
+    channel_ids: &mut BTreeMap<K, BTreeSet<ChannelId>>,
+    key: K,
+    pd_name: &str,
+    id: ChannelId,
+) -> Result<(), ValidationError> {
+    if id >= MAX_CHANNELS {
+        return Err(ValidationError::ChannelIdOutOfRange {
+            pd: pd_name.to_owned(),
+            id,
+        });
+    }
+    if !channel_ids.entry(key).or_default().insert(id) {
+        return Err(ValidationError::ChannelIdInUse {
+            pd: pd_name.to_owned(),
+            id,
+        });
+    }
+    Ok(())
+}
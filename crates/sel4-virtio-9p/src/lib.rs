@@ -0,0 +1,411 @@
+//! A [`sel4_async_9p::Transport`] which carries 9P messages over a virtio-9p device, such as one
+//! backed by a directory on the host with QEMU's `-virtfs` option.
+//!
+//! virtio-drivers has no driver for this device type, so [`Virtio9pTransport`] drives its single
+//! request queue itself, using only the [`Hal`] and [`Transport`] with which the device is
+//! accessed. Each transaction occupies a pair of descriptors: one for the request, which the
+//! device reads, and one for the response, which the device writes. Completions are collected
+//! by [`Virtio9pTransport::handle_interrupt`], which the protection domain hosting the client
+//! must call when the device's interrupt fires.
+
+#![no_std]
+#![feature(async_fn_in_trait)]
+#![feature(int_roundings)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{fence, Ordering};
+use core::task::{Poll, Waker};
+
+use virtio_drivers::{
+    transport::{DeviceType, Transport},
+    BufferDirection, Hal, PhysAddr, PAGE_SIZE,
+};
+
+const QUEUE: u16 = 0;
+
+const VIRTIO_9P_MOUNT_TAG: u64 = 1 << 0;
+const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+const VIRTQ_DESC_F_NEXT: u16 = 1;
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+/// `QUEUE_SIZE` is the number of descriptors in the device's request queue, which must be a power
+/// of two. Up to half as many transactions may be in flight at once.
+pub struct Virtio9pTransport<H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    inner: RefCell<Inner<H, T, QUEUE_SIZE>>,
+    max_message_size: usize,
+    mount_tag: String,
+}
+
+struct Inner<H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    transport: T,
+    queue: Queue<H, QUEUE_SIZE>,
+    buffers: Dma<H>,
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+    slot_waiters: Vec<Waker>,
+}
+
+enum Slot {
+    Free,
+    InFlight(Option<Waker>),
+    Completed(usize),
+    // The transaction's future was dropped before the device completed it.
+    Abandoned,
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Virtio9pTransport<H, T, QUEUE_SIZE> {
+    /// `max_message_size` bounds the size of requests and responses, and so should be at least
+    /// the `max_message_size` with which the client is configured.
+    pub fn new(mut transport: T, max_message_size: usize) -> Result<Self, Error> {
+        assert_eq!(transport.device_type(), DeviceType::_9P);
+        assert!(QUEUE_SIZE.is_power_of_two());
+
+        transport.begin_init(|features| features & (VIRTIO_9P_MOUNT_TAG | VIRTIO_F_VERSION_1));
+
+        if transport.queue_used(QUEUE) {
+            return Err(Error::Virtio(virtio_drivers::Error::AlreadyUsed));
+        }
+        if transport.max_queue_size() < u32::try_from(QUEUE_SIZE).unwrap() {
+            return Err(Error::Virtio(virtio_drivers::Error::InvalidParam));
+        }
+        let queue = Queue::new();
+        transport.queue_set(
+            QUEUE,
+            QUEUE_SIZE.try_into().unwrap(),
+            queue.paddr(Queue::<H, QUEUE_SIZE>::DESCRIPTORS_OFFSET),
+            queue.paddr(Queue::<H, QUEUE_SIZE>::AVAIL_OFFSET),
+            queue.paddr(Queue::<H, QUEUE_SIZE>::used_offset()),
+        );
+
+        let mount_tag = read_mount_tag(&transport)?;
+        log::debug!("mount tag: {:?}", mount_tag);
+
+        transport.finish_init();
+
+        let num_slots = QUEUE_SIZE / 2;
+        Ok(Self {
+            inner: RefCell::new(Inner {
+                transport,
+                queue,
+                buffers: Dma::new(pages(num_slots * 2 * max_message_size)),
+                slots: (0..num_slots).map(|_| Slot::Free).collect(),
+                free_slots: (0..num_slots).rev().collect(),
+                slot_waiters: Vec::new(),
+            }),
+            max_message_size,
+            mount_tag,
+        })
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// The name with which the host labels the shared directory.
+    pub fn mount_tag(&self) -> &str {
+        &self.mount_tag
+    }
+
+    /// Acknowledges an interrupt at the device, and then calls `ack_irq` to acknowledge it at the
+    /// interrupt controller, before waking any transactions which the device has completed.
+    pub fn handle_interrupt(&self, ack_irq: impl FnOnce()) {
+        let mut inner = self.inner.borrow_mut();
+        inner.transport.ack_interrupt();
+        ack_irq();
+        inner.collect_completions();
+    }
+
+    fn request_offset(&self, slot: usize) -> usize {
+        2 * slot * self.max_message_size
+    }
+
+    fn response_offset(&self, slot: usize) -> usize {
+        self.request_offset(slot) + self.max_message_size
+    }
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Inner<H, T, QUEUE_SIZE> {
+    fn collect_completions(&mut self) {
+        while let Some((head, len)) = self.queue.pop_used() {
+            let slot = usize::from(head) / 2;
+            match mem::replace(&mut self.slots[slot], Slot::Completed(len)) {
+                Slot::InFlight(waker) => {
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+                Slot::Abandoned => self.free_slot(slot),
+                _ => panic!("device completed slot {slot} which was not in flight"),
+            }
+        }
+    }
+
+    fn free_slot(&mut self, slot: usize) {
+        self.slots[slot] = Slot::Free;
+        self.free_slots.push(slot);
+        for waker in self.slot_waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> sel4_async_9p::Transport
+    for Virtio9pTransport<H, T, QUEUE_SIZE>
+{
+    type Error = Error;
+
+    async fn transact(&self, request: &[u8], response: &mut [u8]) -> Result<usize, Self::Error> {
+        if request.len() > self.max_message_size {
+            return Err(Error::MessageTooLarge);
+        }
+
+        let slot = poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            match inner.free_slots.pop() {
+                Some(slot) => Poll::Ready(slot),
+                None => {
+                    inner.slot_waiters.push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await;
+
+        let guard = SlotGuard {
+            transport: self,
+            slot,
+        };
+
+        {
+            let mut inner = self.inner.borrow_mut();
+            let inner = &mut *inner;
+            let request_offset = self.request_offset(slot);
+            let response_offset = self.response_offset(slot);
+            inner.buffers.as_mut_slice()[request_offset..][..request.len()]
+                .copy_from_slice(request);
+            let head = u16::try_from(2 * slot).unwrap();
+            inner.queue.write_descriptor(
+                head,
+                inner.buffers.paddr + request_offset,
+                request.len(),
+                VIRTQ_DESC_F_NEXT,
+                head + 1,
+            );
+            inner.queue.write_descriptor(
+                head + 1,
+                inner.buffers.paddr + response_offset,
+                self.max_message_size,
+                VIRTQ_DESC_F_WRITE,
+                0,
+            );
+            inner.slots[slot] = Slot::InFlight(None);
+            inner.queue.push_avail(head);
+            inner.transport.notify(QUEUE);
+        }
+
+        let len = poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            match &mut inner.slots[slot] {
+                Slot::Completed(len) => Poll::Ready(*len),
+                Slot::InFlight(waker) => {
+                    *waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                _ => unreachable!(),
+            }
+        })
+        .await;
+
+        let result = if len > response.len() || len > self.max_message_size {
+            Err(Error::ResponseTooLarge)
+        } else {
+            let inner = self.inner.borrow();
+            response[..len]
+                .copy_from_slice(&inner.buffers.as_slice()[self.response_offset(slot)..][..len]);
+            Ok(len)
+        };
+        drop(guard);
+        result
+    }
+}
+
+struct SlotGuard<'a, H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    transport: &'a Virtio9pTransport<H, T, QUEUE_SIZE>,
+    slot: usize,
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Drop for SlotGuard<'_, H, T, QUEUE_SIZE> {
+    fn drop(&mut self) {
+        let mut inner = self.transport.inner.borrow_mut();
+        match inner.slots[self.slot] {
+            // The device still owns the slot's buffers.
+            Slot::InFlight(_) => inner.slots[self.slot] = Slot::Abandoned,
+            _ => inner.free_slot(self.slot),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Virtio(virtio_drivers::Error),
+    MessageTooLarge,
+    ResponseTooLarge,
+}
+
+fn read_mount_tag<T: Transport>(transport: &T) -> Result<String, Error> {
+    let config = transport
+        .config_space::<u8>()
+        .map_err(Error::Virtio)?
+        .as_ptr();
+    // struct virtio_9p_config { le16 tag_len; u8 tag[tag_len]; }
+    let read = |offset: usize| unsafe { ptr::read_volatile(config.add(offset)) };
+    let tag_len = u16::from_le_bytes([read(0), read(1)]).into();
+    let tag = (0..tag_len).map(|i| read(2 + i)).collect::<Vec<_>>();
+    Ok(String::from_utf8_lossy(&tag).into_owned())
+}
+
+fn pages(size: usize) -> usize {
+    size.div_ceil(PAGE_SIZE)
+}
+
+struct Dma<H: Hal> {
+    paddr: PhysAddr,
+    vaddr: NonNull<u8>,
+    pages: usize,
+    _phantom: PhantomData<H>,
+}
+
+impl<H: Hal> Dma<H> {
+    fn new(pages: usize) -> Self {
+        let (paddr, vaddr) = H::dma_alloc(pages, BufferDirection::Both);
+        Self {
+            paddr,
+            vaddr,
+            pages,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.vaddr.as_ptr(), self.pages * PAGE_SIZE) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.vaddr.as_ptr(), self.pages * PAGE_SIZE) }
+    }
+}
+
+impl<H: Hal> Drop for Dma<H> {
+    fn drop(&mut self) {
+        unsafe {
+            H::dma_dealloc(self.paddr, self.vaddr, self.pages);
+        }
+    }
+}
+
+// A split virtqueue, laid out as required by legacy devices, which modern devices also accept:
+// the descriptor table and available ring are contiguous, and the used ring begins on the next
+// page.
+struct Queue<H: Hal, const SIZE: usize> {
+    dma: Dma<H>,
+    next_avail: u16,
+    last_used: u16,
+}
+
+#[repr(C)]
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+impl<H: Hal, const SIZE: usize> Queue<H, SIZE> {
+    const DESCRIPTORS_OFFSET: usize = 0;
+    const AVAIL_OFFSET: usize = SIZE * mem::size_of::<Descriptor>();
+
+    const fn used_offset() -> usize {
+        // flags[2] idx[2] ring[2 * SIZE] used_event[2]
+        let avail_size = 2 + 2 + 2 * SIZE + 2;
+        (Self::AVAIL_OFFSET + avail_size).next_multiple_of(PAGE_SIZE)
+    }
+
+    const fn size() -> usize {
+        // flags[2] idx[2] ring[8 * SIZE] avail_event[2]
+        let used_size = 2 + 2 + 8 * SIZE + 2;
+        Self::used_offset() + used_size
+    }
+
+    fn new() -> Self {
+        let dma = Dma::new(pages(Self::size()));
+        unsafe {
+            ptr::write_bytes(dma.vaddr.as_ptr(), 0, dma.pages * PAGE_SIZE);
+        }
+        Self {
+            dma,
+            next_avail: 0,
+            last_used: 0,
+        }
+    }
+
+    fn paddr(&self, offset: usize) -> PhysAddr {
+        self.dma.paddr + offset
+    }
+
+    fn ptr<U>(&self, offset: usize) -> *mut U {
+        unsafe { self.dma.vaddr.as_ptr().add(offset).cast() }
+    }
+
+    fn write_descriptor(&mut self, i: u16, addr: PhysAddr, len: usize, flags: u16, next: u16) {
+        let desc = Descriptor {
+            addr: addr.try_into().unwrap(),
+            len: len.try_into().unwrap(),
+            flags,
+            next,
+        };
+        unsafe {
+            ptr::write_volatile(
+                self.ptr(Self::DESCRIPTORS_OFFSET + usize::from(i) * mem::size_of::<Descriptor>()),
+                desc,
+            );
+        }
+    }
+
+    fn push_avail(&mut self, head: u16) {
+        let slot = usize::from(self.next_avail) % SIZE;
+        unsafe {
+            ptr::write_volatile(self.ptr(Self::AVAIL_OFFSET + 4 + 2 * slot), head);
+        }
+        self.next_avail = self.next_avail.wrapping_add(1);
+        // The descriptors and ring entry must be visible before the index which publishes them.
+        fence(Ordering::SeqCst);
+        unsafe {
+            ptr::write_volatile(self.ptr(Self::AVAIL_OFFSET + 2), self.next_avail);
+        }
+        fence(Ordering::SeqCst);
+    }
+
+    // Returns the head of the next descriptor chain completed by the device, and the number of
+    // bytes it wrote.
+    fn pop_used(&mut self) -> Option<(u16, usize)> {
+        let used_idx: u16 = unsafe { ptr::read_volatile(self.ptr(Self::used_offset() + 2)) };
+        if used_idx == self.last_used {
+            return None;
+        }
+        fence(Ordering::SeqCst);
+        let elem = Self::used_offset() + 4 + 8 * (usize::from(self.last_used) % SIZE);
+        let id: u32 = unsafe { ptr::read_volatile(self.ptr(elem)) };
+        let len: u32 = unsafe { ptr::read_volatile(self.ptr(elem + 4)) };
+        self.last_used = self.last_used.wrapping_add(1);
+        Some((id.try_into().unwrap(), len.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,279 @@
+//! Batching of notifications to virtio devices.
+//!
+//! The drivers in virtio-drivers notify the device each time they add a buffer to a queue, and
+//! each notification is a trap to the hypervisor when the device is emulated. [`BatchingTransport`]
+//! wraps a [`Transport`] so that notifications for chosen queues are instead recorded, and then
+//! sent once per queue when the driver calls [`NotificationBatch::flush`], for example after
+//! submitting all of the requests which a client has queued.
+//!
+//! Notifications must not be deferred while the driver waits for the device to use a buffer it has
+//! just added, or the driver would wait forever. For example, `VirtIONet::send` waits for the
+//! packet to be sent, so the transmit queue of a `VirtIONet` must not be deferred. Occasional
+//! synchronous operations on a deferred queue, such as `VirtIOBlk::flush`, can be performed within
+//! [`NotificationBatch::unbatched`].
+//!
+//! In the other direction, [`NotificationBatch::set_interrupts_suppressed`] asks the device not to
+//! interrupt the driver when it uses buffers, for drivers which poll their queues. virtio-drivers
+//! does not expose the rings of its queues, so [`BatchingTransport`] records where each queue's
+//! driver area is when the queue is set up.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::ptr::NonNull;
+use core::sync::atomic::{fence, Ordering};
+
+use virtio_drivers::{
+    transport::{DeviceStatus, DeviceType, Transport},
+    PhysAddr,
+};
+
+/// A [`Transport`] which defers notifications for some queues until
+/// [`NotificationBatch::flush`] is called.
+pub struct BatchingTransport<T> {
+    shared: Rc<Shared<T>>,
+}
+
+/// A handle with which to send the notifications deferred by a [`BatchingTransport`].
+pub struct NotificationBatch<T> {
+    shared: Rc<Shared<T>>,
+}
+
+struct Shared<T> {
+    transport: RefCell<T>,
+    // Bitmasks of queue indices.
+    deferred_queues: u64,
+    pending: Cell<u64>,
+    extra_features: Cell<u64>,
+    unbatched: Cell<bool>,
+    stats: Cell<NotificationStats>,
+    // The physical address of the driver area of each queue which has been set up.
+    driver_areas: RefCell<Vec<(u16, PhysAddr)>>,
+}
+
+// In the flags field at the start of the driver area (the available ring).
+const VIRTQ_AVAIL_F_NO_INTERRUPT: u16 = 1;
+
+/// Counts of notifications, by which the effect of batching can be measured.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NotificationStats {
+    /// The number of notifications requested by the driver.
+    pub requested: u64,
+    /// The number of notifications sent to the device.
+    pub sent: u64,
+}
+
+impl<T: Transport> BatchingTransport<T> {
+    /// Wraps `transport`, deferring notifications for each queue in `deferred_queues`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a queue index in `deferred_queues` is 64 or greater.
+    pub fn new(transport: T, deferred_queues: &[u16]) -> (Self, NotificationBatch<T>) {
+        let deferred_queues = deferred_queues.iter().fold(0, |mask, &queue| {
+            assert!(queue < 64);
+            mask | (1 << queue)
+        });
+        let shared = Rc::new(Shared {
+            transport: RefCell::new(transport),
+            deferred_queues,
+            pending: Cell::new(0),
+            extra_features: Cell::new(0),
+            unbatched: Cell::new(false),
+            stats: Cell::new(NotificationStats::default()),
+            driver_areas: RefCell::new(Vec::new()),
+        });
+        (
+            Self {
+                shared: shared.clone(),
+            },
+            NotificationBatch { shared },
+        )
+    }
+}
+
+impl<T> BatchingTransport<T> {
+    /// Negotiates those of `features` which the device offers, in addition to the features which
+    /// the driver negotiates. This is for features which need no support from the driver, such as
+    /// those which only make fields of the device's configuration space valid. Must be called
+    /// before the driver is initialized with this transport.
+    pub fn negotiate_extra_features(&mut self, features: u64) {
+        self.shared.extra_features.set(features)
+    }
+}
+
+impl<T: Transport> NotificationBatch<T> {
+    /// Sends a single notification for each queue which has been notified since the last flush.
+    /// Returns the number of notifications sent.
+    pub fn flush(&self) -> usize {
+        let pending = self.shared.pending.replace(0);
+        let mut transport = self.shared.transport.borrow_mut();
+        for queue in 0..64u16 {
+            if pending & (1 << queue) != 0 {
+                transport.notify(queue);
+            }
+        }
+        let num_sent = pending.count_ones();
+        self.shared
+            .update_stats(|stats| stats.sent += u64::from(num_sent));
+        num_sent.try_into().unwrap()
+    }
+
+    /// Flushes, and then calls `f` with notifications passed straight to the device.
+    pub fn unbatched<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.flush();
+        let prev = self.shared.unbatched.replace(true);
+        let r = f();
+        self.shared.unbatched.set(prev);
+        r
+    }
+
+    pub fn stats(&self) -> NotificationStats {
+        self.shared.stats.get()
+    }
+}
+
+impl<T> NotificationBatch<T> {
+    /// Sets or clears `VIRTQ_AVAIL_F_NO_INTERRUPT` for each of `queues` which has been set up,
+    /// asking the device not to interrupt the driver when it uses buffers from that queue.
+    /// `phys_to_virt` translates the physical address of a driver area, which is within memory
+    /// allocated by `Hal::dma_alloc`, to a pointer through which the driver can access it.
+    ///
+    /// The flag is only a hint, which the device may ignore. Once interrupts are no longer
+    /// suppressed, the driver must check its queues again before waiting for an interrupt, because
+    /// the device may have used buffers without interrupting it.
+    pub fn set_interrupts_suppressed(
+        &self,
+        queues: &[u16],
+        suppressed: bool,
+        phys_to_virt: impl Fn(PhysAddr) -> NonNull<u8>,
+    ) {
+        let flags = if suppressed {
+            VIRTQ_AVAIL_F_NO_INTERRUPT
+        } else {
+            0
+        };
+        for &(queue, driver_area) in self.shared.driver_areas.borrow().iter() {
+            if queues.contains(&queue) {
+                // SAFETY: the driver area is valid for as long as the queue is set up, and the
+                // device only reads its flags.
+                unsafe {
+                    phys_to_virt(driver_area)
+                        .cast::<u16>()
+                        .as_ptr()
+                        .write_volatile(flags.to_le());
+                }
+            }
+        }
+        // Order the update before subsequent reads of the used rings.
+        fence(Ordering::SeqCst);
+    }
+}
+
+impl<T> Shared<T> {
+    fn update_stats(&self, f: impl FnOnce(&mut NotificationStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+impl<T: Transport> Transport for BatchingTransport<T> {
+    fn device_type(&self) -> DeviceType {
+        self.shared.transport.borrow().device_type()
+    }
+
+    fn read_device_features(&mut self) -> u64 {
+        self.shared.transport.borrow_mut().read_device_features()
+    }
+
+    fn write_driver_features(&mut self, driver_features: u64) {
+        let mut transport = self.shared.transport.borrow_mut();
+        let extra_features = transport.read_device_features() & self.shared.extra_features.get();
+        transport.write_driver_features(driver_features | extra_features)
+    }
+
+    fn max_queue_size(&self) -> u32 {
+        self.shared.transport.borrow().max_queue_size()
+    }
+
+    fn notify(&mut self, queue: u16) {
+        self.shared.update_stats(|stats| stats.requested += 1);
+        if !self.shared.unbatched.get()
+            && queue < 64
+            && self.shared.deferred_queues & (1 << queue) != 0
+        {
+            self.shared
+                .pending
+                .set(self.shared.pending.get() | (1 << queue));
+        } else {
+            self.shared.update_stats(|stats| stats.sent += 1);
+            self.shared.transport.borrow_mut().notify(queue)
+        }
+    }
+
+    fn get_status(&self) -> DeviceStatus {
+        self.shared.transport.borrow().get_status()
+    }
+
+    fn set_status(&mut self, status: DeviceStatus) {
+        self.shared.transport.borrow_mut().set_status(status)
+    }
+
+    fn set_guest_page_size(&mut self, guest_page_size: u32) {
+        self.shared
+            .transport
+            .borrow_mut()
+            .set_guest_page_size(guest_page_size)
+    }
+
+    fn requires_legacy_layout(&self) -> bool {
+        self.shared.transport.borrow().requires_legacy_layout()
+    }
+
+    fn queue_set(
+        &mut self,
+        queue: u16,
+        size: u32,
+        descriptors: PhysAddr,
+        driver_area: PhysAddr,
+        device_area: PhysAddr,
+    ) {
+        {
+            let mut driver_areas = self.shared.driver_areas.borrow_mut();
+            driver_areas.retain(|&(q, _)| q != queue);
+            driver_areas.push((queue, driver_area));
+        }
+        self.shared.transport.borrow_mut().queue_set(
+            queue,
+            size,
+            descriptors,
+            driver_area,
+            device_area,
+        )
+    }
+
+    fn queue_unset(&mut self, queue: u16) {
+        self.shared
+            .driver_areas
+            .borrow_mut()
+            .retain(|&(q, _)| q != queue);
+        self.shared.transport.borrow_mut().queue_unset(queue)
+    }
+
+    fn queue_used(&mut self, queue: u16) -> bool {
+        self.shared.transport.borrow_mut().queue_used(queue)
+    }
+
+    fn ack_interrupt(&mut self) -> bool {
+        self.shared.transport.borrow_mut().ack_interrupt()
+    }
+
+    fn config_space<U: 'static>(&self) -> virtio_drivers::Result<NonNull<U>> {
+        self.shared.transport.borrow().config_space()
+    }
+}
@@ -0,0 +1,143 @@
+//! Discovery of virtio MMIO devices.
+//!
+//! Platforms such as QEMU's `virt` machines provide a bank of virtio MMIO slots, of which only
+//! those backed by a device (for example, by one passed with `-device`) respond with a non-zero
+//! device ID. Which slot a device lands in depends on the order of the command line, so rather than
+//! hard-coding offsets, a driver can map the whole bank, or the part of it it has been given, and
+//! use [`probe_region`] to find a device of the type it expects. Where a device tree is available,
+//! `probe_device_tree` (with the `device-tree` feature) does the same for each `virtio,mmio` node,
+//! and also reports the device's interrupt.
+
+#![no_std]
+
+#[cfg(feature = "device-tree")]
+extern crate alloc;
+
+use core::ptr::{self, NonNull};
+
+use virtio_drivers::transport::{
+    mmio::{MmioError, MmioTransport, VirtIOHeader},
+    DeviceType, Transport,
+};
+
+/// The value of the `MagicValue` register of every virtio MMIO device ("virt").
+pub const MAGIC: u32 = 0x7472_6976;
+
+/// The interval at which QEMU's `virt` machines lay out virtio MMIO slots.
+pub const QEMU_VIRT_SLOT_SIZE: usize = 0x200;
+
+/// The `compatible` string of virtio MMIO nodes in a device tree.
+pub const COMPATIBLE: &str = "virtio,mmio";
+
+const MAGIC_OFFSET: usize = 0x000;
+const VERSION_OFFSET: usize = 0x004;
+const DEVICE_ID_OFFSET: usize = 0x008;
+
+/// A mapped region of memory which may contain virtio MMIO devices, one every `slot_size` bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MmioRegion {
+    pub vaddr: usize,
+    pub paddr: usize,
+    pub size: usize,
+    pub slot_size: usize,
+}
+
+/// A virtio MMIO device which has been found to be present.
+#[derive(Debug)]
+pub struct ProbedDevice {
+    header: NonNull<VirtIOHeader>,
+    paddr: usize,
+    version: u32,
+    device_type: DeviceType,
+    irq: Option<u32>,
+}
+
+impl ProbedDevice {
+    pub fn header(&self) -> NonNull<VirtIOHeader> {
+        self.header
+    }
+
+    pub fn paddr(&self) -> usize {
+        self.paddr
+    }
+
+    /// The value of the device's `Version` register: 1 for legacy devices, and 2 otherwise.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    /// The device's interrupt, if it was found using a device tree.
+    pub fn irq(&self) -> Option<u32> {
+        self.irq
+    }
+
+    /// # Safety
+    ///
+    /// The device must not be accessed other than through the returned transport.
+    pub unsafe fn transport(&self) -> Result<MmioTransport, MmioError> {
+        MmioTransport::new(self.header)
+    }
+}
+
+/// Returns the devices present in `region`, in order of address.
+///
+/// # Safety
+///
+/// `region` must be mapped as device memory, and each of its slots must either be a virtio MMIO
+/// device or be safe to read from.
+pub unsafe fn probe_region(region: &MmioRegion) -> impl Iterator<Item = ProbedDevice> + '_ {
+    (0..region.size / region.slot_size).filter_map(|i| {
+        let offset = i * region.slot_size;
+        probe(region.vaddr + offset, region.paddr + offset, None)
+    })
+}
+
+/// Returns the devices present at the `virtio,mmio` nodes of `tree`, in the order in which they
+/// appear. `map` is called with the `reg` entry of each enabled node, and returns the virtual
+/// address at which it is mapped, or `None` if the node should be skipped.
+///
+/// # Safety
+///
+/// The addresses returned by `map` must be mapped as device memory.
+#[cfg(feature = "device-tree")]
+pub unsafe fn probe_device_tree(
+    tree: &sel4_device_tree::DeviceTree,
+    mut map: impl FnMut(&sel4_device_tree::Reg) -> Option<usize>,
+) -> alloc::vec::Vec<ProbedDevice> {
+    tree.find_enabled_compatible(&[COMPATIBLE])
+        .filter_map(|device| {
+            let reg = device.reg().first()?;
+            let vaddr = map(reg)?;
+            let irq = device
+                .interrupts()
+                .first()
+                .and_then(|interrupt| interrupt.gic_irq());
+            probe(vaddr, reg.addr.try_into().unwrap(), irq)
+        })
+        .collect()
+}
+
+unsafe fn probe(vaddr: usize, paddr: usize, irq: Option<u32>) -> Option<ProbedDevice> {
+    let read = |offset: usize| ptr::read_volatile((vaddr + offset) as *const u32);
+    if read(MAGIC_OFFSET) != MAGIC {
+        return None;
+    }
+    let version = read(VERSION_OFFSET);
+    // Slots without a device have a device ID of zero.
+    if read(DEVICE_ID_OFFSET) == 0 {
+        return None;
+    }
+    let header = NonNull::new(vaddr as *mut VirtIOHeader).unwrap();
+    let device_type = MmioTransport::new(header).ok()?.device_type();
+    Some(ProbedDevice {
+        header,
+        paddr,
+        version,
+        device_type,
+        irq,
+    })
+}
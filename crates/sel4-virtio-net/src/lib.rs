@@ -0,0 +1,296 @@
+//! A driver which connects a virtio-net device to a client in another protection domain.
+//!
+//! The client shares a DMA region with the driver, and passes buffers within that region back and
+//! forth through a pair of [`RingBuffers`] for each direction, as expected by
+//! `sel4-shared-ring-buffer-smoltcp`. [`VirtioNetDriver`] is generic over the [`Hal`] and
+//! [`Transport`] with which the device is accessed, so that it can be used in any environment with
+//! an implementation of each.
+//!
+//! Notifications to the device for returned receive buffers are batched with
+//! `sel4-virtio-batching`, so that [`VirtioNetDriver::service`] notifies the receive queue at most
+//! once. virtio-drivers waits for each transmitted packet to be sent, so transmit notifications
+//! cannot be batched.
+
+#![no_std]
+
+use core::fmt;
+use core::ptr;
+
+use virtio_drivers::{device::net::VirtIONet, transport::Transport, Hal};
+
+use sel4_externally_shared::ExternallySharedRef;
+use sel4_shared_ring_buffer::RingBuffers;
+use sel4_virtio_batching::{BatchingTransport, NotificationBatch};
+
+pub use sel4_virtio_batching::NotificationStats;
+
+/// The size of the header which prefixes each buffer, given that `VIRTIO_F_VERSION_1` has been
+/// negotiated.
+pub const VIRTIO_NET_HDR_LEN: usize = 12;
+
+/// The size of an Ethernet header. Unlike virtio-net, smoltcp includes it in the MTU. See
+/// [`VirtioNetDriver::max_frame_len`].
+pub const ETHERNET_HEADER_LEN: usize = 14;
+
+// As assigned by virtio-drivers.
+const QUEUE_RECEIVE: u16 = 0;
+const QUEUE_TRANSMIT: u16 = 1;
+
+const VIRTIO_NET_F_CSUM: u64 = 1 << 0;
+const VIRTIO_NET_F_GUEST_CSUM: u64 = 1 << 1;
+const VIRTIO_NET_F_MTU: u64 = 1 << 3;
+
+// The start of the device's configuration space. `mtu` is only valid if `VIRTIO_NET_F_MTU` has
+// been offered.
+#[allow(dead_code)]
+#[repr(C)]
+struct DeviceConfig {
+    mac: [u8; 6],
+    status: u16,
+    max_virtqueue_pairs: u16,
+    mtu: u16,
+}
+
+// Checksum offload requires access to the header of each buffer, which virtio-drivers does not
+// provide, so no offloads can be negotiated yet.
+const SUPPORTED_CHECKSUM_OFFLOAD: ChecksumOffload = ChecksumOffload::NONE;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VirtioNetConfig {
+    /// The size of each buffer in the device's queues, including the header. The MTU is this less
+    /// [`VIRTIO_NET_HDR_LEN`] and [`ETHERNET_HEADER_LEN`], or the MTU reported by the device, if
+    /// that is smaller.
+    pub buffer_len: usize,
+    /// The offloads to use, if the device offers them.
+    pub checksum_offload: ChecksumOffload,
+}
+
+impl Default for VirtioNetConfig {
+    fn default() -> Self {
+        Self {
+            buffer_len: 2048,
+            checksum_offload: ChecksumOffload::NONE,
+        }
+    }
+}
+
+/// Which checksums are computed or verified by the device rather than by the client.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChecksumOffload {
+    /// The device computes checksums for transmitted packets (`VIRTIO_NET_F_CSUM`).
+    pub tx: bool,
+    /// The device verifies checksums of received packets (`VIRTIO_NET_F_GUEST_CSUM`).
+    pub rx: bool,
+}
+
+impl ChecksumOffload {
+    pub const NONE: Self = Self {
+        tx: false,
+        rx: false,
+    };
+
+    fn from_features(features: u64) -> Self {
+        Self {
+            tx: features & VIRTIO_NET_F_CSUM != 0,
+            rx: features & VIRTIO_NET_F_GUEST_CSUM != 0,
+        }
+    }
+
+    fn intersection(self, other: Self) -> Self {
+        Self {
+            tx: self.tx && other.tx,
+            rx: self.rx && other.rx,
+        }
+    }
+}
+
+impl fmt::Display for ChecksumOffload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tx={}, rx={}", self.tx, self.rx)
+    }
+}
+
+/// `QUEUE_SIZE` is the number of buffers in each of the device's receive and transmit queues.
+pub struct VirtioNetDriver<'a, H: Hal, T: Transport, F, const QUEUE_SIZE: usize> {
+    dev: VirtIONet<H, BatchingTransport<T>, QUEUE_SIZE>,
+    notification_batch: NotificationBatch<T>,
+    mtu: usize,
+    checksum_offload: ChecksumOffload,
+    client_region: ExternallySharedRef<'a, [u8]>,
+    client_region_paddr: usize,
+    rx_ring_buffers: RingBuffers<'a, F>,
+    tx_ring_buffers: RingBuffers<'a, F>,
+}
+
+impl<'a, H: Hal, T: Transport, F, const QUEUE_SIZE: usize>
+    VirtioNetDriver<'a, H, T, F, QUEUE_SIZE>
+{
+    pub fn new(
+        mut transport: T,
+        config: &VirtioNetConfig,
+        client_region: ExternallySharedRef<'a, [u8]>,
+        client_region_paddr: usize,
+        rx_ring_buffers: RingBuffers<'a, F>,
+        tx_ring_buffers: RingBuffers<'a, F>,
+    ) -> virtio_drivers::Result<Self> {
+        let device_features = transport.read_device_features();
+        let offered = ChecksumOffload::from_features(device_features);
+        let checksum_offload = config
+            .checksum_offload
+            .intersection(offered)
+            .intersection(SUPPORTED_CHECKSUM_OFFLOAD);
+        log::debug!(
+            "checksum offload: requested {}, offered {}, negotiated {}",
+            config.checksum_offload,
+            offered,
+            checksum_offload
+        );
+        let device_mtu = if device_features & VIRTIO_NET_F_MTU != 0 {
+            let device_config = transport.config_space::<DeviceConfig>()?;
+            // SAFETY: `config_space` returns a pointer to the device's configuration space.
+            let mtu = unsafe { ptr::addr_of!((*device_config.as_ptr()).mtu).read_volatile() };
+            Some(usize::from(u16::from_le(mtu)))
+        } else {
+            None
+        };
+        let buffer_mtu = config.buffer_len - VIRTIO_NET_HDR_LEN - ETHERNET_HEADER_LEN;
+        let mtu = device_mtu.map_or(buffer_mtu, |device_mtu| device_mtu.min(buffer_mtu));
+        log::debug!(
+            "mtu: device {:?}, buffers {}, using {}",
+            device_mtu,
+            buffer_mtu,
+            mtu
+        );
+        let (mut transport, notification_batch) =
+            BatchingTransport::new(transport, &[QUEUE_RECEIVE]);
+        // With VIRTIO_NET_F_MTU negotiated, the device does not send packets larger than its MTU.
+        transport.negotiate_extra_features(VIRTIO_NET_F_MTU);
+        let dev = VirtIONet::new(transport, config.buffer_len)?;
+        // Publish the initial receive buffers.
+        notification_batch.flush();
+        Ok(Self {
+            dev,
+            notification_batch,
+            mtu,
+            checksum_offload,
+            client_region,
+            client_region_paddr,
+            rx_ring_buffers,
+            tx_ring_buffers,
+        })
+    }
+
+    pub fn mac_address(&self) -> [u8; 6] {
+        self.dev.mac_address()
+    }
+
+    /// The largest IP packet which can be sent or received, which is the smaller of the MTU
+    /// reported by the device, if any, and that allowed by the buffer size.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// The largest Ethernet frame which can be sent or received, which is what smoltcp calls the
+    /// MTU.
+    pub fn max_frame_len(&self) -> usize {
+        self.mtu + ETHERNET_HEADER_LEN
+    }
+
+    /// The offloads which were both requested and offered by the device. Checksums not covered by
+    /// these must be computed and verified by the client.
+    pub fn checksum_offload(&self) -> ChecksumOffload {
+        self.checksum_offload
+    }
+
+    /// Counts of the notifications requested by virtio-drivers and those actually sent to the
+    /// device.
+    pub fn notification_stats(&self) -> NotificationStats {
+        self.notification_batch.stats()
+    }
+
+    /// Asks the device not to interrupt the driver when it uses buffers, while the driver polls
+    /// instead. See [`NotificationBatch::set_interrupts_suppressed`], whose requirements apply.
+    pub fn set_interrupts_suppressed(
+        &self,
+        suppressed: bool,
+        phys_to_virt: impl Fn(usize) -> ptr::NonNull<u8>,
+    ) {
+        self.notification_batch.set_interrupts_suppressed(
+            &[QUEUE_RECEIVE, QUEUE_TRANSMIT],
+            suppressed,
+            phys_to_virt,
+        )
+    }
+
+    /// Acknowledges an interrupt at the device, and then calls `ack_irq` to acknowledge it at the
+    /// interrupt controller. In the other order, a level-triggered interrupt would fire again
+    /// immediately.
+    pub fn ack_interrupt(&mut self, ack_irq: impl FnOnce()) {
+        self.dev.ack_interrupt();
+        ack_irq();
+    }
+}
+
+impl<
+        'a,
+        H: Hal,
+        T: Transport,
+        F: Fn() -> Result<(), E>,
+        E: fmt::Debug,
+        const QUEUE_SIZE: usize,
+    > VirtioNetDriver<'a, H, T, F, QUEUE_SIZE>
+{
+    /// Moves received packets into buffers provided by the client, and transmits packets in
+    /// buffers passed by the client, for as long as both sides are ready. Notifies the client of
+    /// any buffers returned to it, and returns the number of packets handled.
+    pub fn service(&mut self) -> Result<usize, E> {
+        let mut num_rx = 0;
+
+        while self.dev.can_recv() && !self.rx_ring_buffers.free().is_empty() {
+            let rx_buf = self.dev.receive().unwrap();
+            let desc = self.rx_ring_buffers.free_mut().dequeue().unwrap();
+            let desc_len = usize::try_from(desc.len()).unwrap();
+            assert!(desc_len >= rx_buf.packet_len());
+            let buf_range = {
+                let start = desc.encoded_addr() - self.client_region_paddr;
+                start..start + rx_buf.packet_len()
+            };
+            self.client_region
+                .as_mut_ptr()
+                .index(buf_range)
+                .copy_from_slice(rx_buf.packet());
+            self.dev.recycle_rx_buffer(rx_buf).unwrap();
+            self.rx_ring_buffers.used_mut().enqueue(desc).unwrap();
+            num_rx += 1;
+        }
+
+        if num_rx > 0 {
+            self.notification_batch.flush();
+            self.rx_ring_buffers.notify()?;
+        }
+
+        let mut num_tx = 0;
+
+        while !self.tx_ring_buffers.free().is_empty() && self.dev.can_send() {
+            let desc = self.tx_ring_buffers.free_mut().dequeue().unwrap();
+            let buf_range = {
+                let start = desc.encoded_addr() - self.client_region_paddr;
+                start..start + usize::try_from(desc.len()).unwrap()
+            };
+            let mut tx_buf = self.dev.new_tx_buffer(buf_range.len());
+            self.client_region
+                .as_ptr()
+                .index(buf_range)
+                .copy_into_slice(tx_buf.packet_mut());
+            self.dev.send(tx_buf).unwrap();
+            self.tx_ring_buffers.used_mut().enqueue(desc).unwrap();
+            num_tx += 1;
+        }
+
+        if num_tx > 0 {
+            self.tx_ring_buffers.notify()?;
+        }
+
+        Ok(num_rx + num_tx)
+    }
+}
@@ -0,0 +1,874 @@
+//! A driver for virtio-vsock devices, with a stream socket API, so that a guest can talk to agents
+//! on its host, such as those listening on an `AF_VSOCK` socket on the other side of QEMU's
+//! `vhost-vsock-device`, without any network configuration.
+//!
+//! [`VirtioVsock`] drives the device's receive, transmit, and event queues itself, using only the
+//! [`Hal`] and [`Transport`] with which the device is accessed. Received packets are processed by
+//! [`VirtioVsock::handle_interrupt`], which the protection domain hosting the driver must call when
+//! the device's interrupt fires.
+//!
+//! Only stream sockets are supported. Flow control follows the credit scheme of the virtio
+//! specification: each connection advertises a receive buffer of
+//! [`VirtioVsockConfig::connection_buffer_size`] bytes, and sends no more than the peer has
+//! advertised. A transport reset, such as after the guest is migrated, resets all connections.
+
+#![no_std]
+#![feature(int_roundings)]
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::future::poll_fn;
+use core::ops::Range;
+use core::ptr;
+use core::task::{Context, Poll, Waker};
+
+use virtio_drivers::{
+    transport::{DeviceType, Transport},
+    Hal,
+};
+
+mod packet;
+mod queue;
+
+use packet::{
+    Header, EVENT_TRANSPORT_RESET, HEADER_SIZE, OP_CREDIT_REQUEST, OP_CREDIT_UPDATE, OP_REQUEST,
+    OP_RESPONSE, OP_RST, OP_RW, OP_SHUTDOWN, SHUTDOWN_RCV, SHUTDOWN_SEND, TYPE_STREAM,
+};
+use queue::{pages, Dma, Queue};
+
+const RX_QUEUE: u16 = 0;
+const TX_QUEUE: u16 = 1;
+const EVENT_QUEUE: u16 = 2;
+
+const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+// Each receive and transmit buffer holds a header followed by up to `MAX_PAYLOAD` bytes.
+const BUFFER_SIZE: usize = 4096;
+const MAX_PAYLOAD: usize = BUFFER_SIZE - HEADER_SIZE;
+
+// struct virtio_vsock_event { le32 id; }
+const EVENT_SIZE: usize = 4;
+
+const FIRST_EPHEMERAL_PORT: u32 = 1024;
+// `u32::MAX` is `VMADDR_PORT_ANY`.
+const LAST_EPHEMERAL_PORT: u32 = u32::MAX - 1;
+
+/// The CID of the host.
+pub const VMADDR_CID_HOST: u64 = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VsockAddr {
+    pub cid: u64,
+    pub port: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VirtioVsockConfig {
+    /// The size of each connection's receive buffer, which bounds how much data the peer may send
+    /// ahead of it being read.
+    pub connection_buffer_size: u32,
+    /// The maximum number of connections which may wait to be accepted on each listening port.
+    /// Further connection requests are refused.
+    pub accept_backlog: usize,
+}
+
+impl Default for VirtioVsockConfig {
+    fn default() -> Self {
+        Self {
+            connection_buffer_size: 64 * 1024,
+            accept_backlog: 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Virtio(virtio_drivers::Error),
+    ConnectionRefused,
+    ConnectionReset,
+    /// The connection has been shut down for sending, by this side or by the peer.
+    Shutdown,
+    AddressInUse,
+    NoEphemeralPort,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Virtio(err) => write!(f, "virtio error: {err:?}"),
+            Self::ConnectionRefused => write!(f, "connection refused"),
+            Self::ConnectionReset => write!(f, "connection reset"),
+            Self::Shutdown => write!(f, "connection shut down"),
+            Self::AddressInUse => write!(f, "address in use"),
+            Self::NoEphemeralPort => write!(f, "no ephemeral port available"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ConnectionKey {
+    local_port: u32,
+    peer: VsockAddr,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Connecting,
+    Connected,
+    // Both directions have been shut down by this side, and the peer's reset, which completes the
+    // disconnect, has not yet arrived.
+    Closing,
+    Closed,
+    Refused,
+    Reset,
+}
+
+struct Connection {
+    state: State,
+    local_shutdown_send: bool,
+    peer_shutdown_send: bool,
+    peer_shutdown_recv: bool,
+    rx: VecDeque<u8>,
+    fwd_cnt: u32,
+    advertised_fwd_cnt: u32,
+    tx_cnt: u32,
+    peer_buf_alloc: u32,
+    peer_fwd_cnt: u32,
+    credit_requested: bool,
+    recv_waker: Option<Waker>,
+    send_waker: Option<Waker>,
+}
+
+impl Connection {
+    fn new(state: State) -> Self {
+        Self {
+            state,
+            local_shutdown_send: false,
+            peer_shutdown_send: false,
+            peer_shutdown_recv: false,
+            rx: VecDeque::new(),
+            fwd_cnt: 0,
+            advertised_fwd_cnt: 0,
+            tx_cnt: 0,
+            peer_buf_alloc: 0,
+            peer_fwd_cnt: 0,
+            credit_requested: false,
+            recv_waker: None,
+            send_waker: None,
+        }
+    }
+
+    fn peer_credit(&self) -> u32 {
+        self.peer_buf_alloc
+            .saturating_sub(self.tx_cnt.wrapping_sub(self.peer_fwd_cnt))
+    }
+
+    fn wake(&mut self) {
+        for waker in [self.recv_waker.take(), self.send_waker.take()]
+            .into_iter()
+            .flatten()
+        {
+            waker.wake();
+        }
+    }
+}
+
+struct Listener {
+    backlog: VecDeque<ConnectionKey>,
+    waker: Option<Waker>,
+}
+
+/// `QUEUE_SIZE` is the number of descriptors in each of the device's queues, which must be a power
+/// of two. It bounds the number of packets in flight in each direction.
+pub struct VirtioVsock<H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    inner: RefCell<Inner<H, T, QUEUE_SIZE>>,
+}
+
+struct Inner<H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    transport: T,
+    config: VirtioVsockConfig,
+    guest_cid: u64,
+    rx: Queue<H, QUEUE_SIZE>,
+    rx_buffers: Dma<H>,
+    tx: Queue<H, QUEUE_SIZE>,
+    tx_buffers: Dma<H>,
+    free_tx_buffers: Vec<u16>,
+    tx_waiters: Vec<Waker>,
+    // Control packets waiting for a transmit buffer.
+    tx_backlog: VecDeque<Header>,
+    event: Queue<H, QUEUE_SIZE>,
+    event_buffers: Dma<H>,
+    connections: BTreeMap<ConnectionKey, Connection>,
+    listeners: BTreeMap<u32, Listener>,
+    next_ephemeral_port: u32,
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> VirtioVsock<H, T, QUEUE_SIZE> {
+    pub fn new(mut transport: T, config: VirtioVsockConfig) -> Result<Self, Error> {
+        assert_eq!(transport.device_type(), DeviceType::Socket);
+        assert!(QUEUE_SIZE.is_power_of_two());
+
+        transport.begin_init(|features| features & VIRTIO_F_VERSION_1);
+
+        let rx = set_up_queue(&mut transport, RX_QUEUE)?;
+        let tx = set_up_queue(&mut transport, TX_QUEUE)?;
+        let event = set_up_queue(&mut transport, EVENT_QUEUE)?;
+
+        let guest_cid = read_guest_cid(&transport)?;
+        log::debug!("guest cid: {}", guest_cid);
+
+        transport.finish_init();
+
+        let mut inner = Inner {
+            transport,
+            config,
+            guest_cid,
+            rx,
+            rx_buffers: Dma::new(pages(QUEUE_SIZE * BUFFER_SIZE)),
+            tx,
+            tx_buffers: Dma::new(pages(QUEUE_SIZE * BUFFER_SIZE)),
+            free_tx_buffers: (0..QUEUE_SIZE)
+                .rev()
+                .map(|i| i.try_into().unwrap())
+                .collect(),
+            tx_waiters: Vec::new(),
+            tx_backlog: VecDeque::new(),
+            event,
+            event_buffers: Dma::new(pages(QUEUE_SIZE * EVENT_SIZE)),
+            connections: BTreeMap::new(),
+            listeners: BTreeMap::new(),
+            next_ephemeral_port: FIRST_EPHEMERAL_PORT,
+        };
+
+        for i in 0..QUEUE_SIZE {
+            let i = i.try_into().unwrap();
+            inner.post_rx_buffer(i);
+            inner.post_event_buffer(i);
+        }
+        inner.transport.notify(RX_QUEUE);
+        inner.transport.notify(EVENT_QUEUE);
+
+        Ok(Self {
+            inner: RefCell::new(inner),
+        })
+    }
+
+    /// The context identifier of this guest, which the host uses to address it.
+    pub fn guest_cid(&self) -> u64 {
+        self.inner.borrow().guest_cid
+    }
+
+    /// Acknowledges an interrupt at the device, and then calls `ack_irq` to acknowledge it at the
+    /// interrupt controller, before processing received packets and completed transmissions.
+    pub fn handle_interrupt(&self, ack_irq: impl FnOnce()) {
+        let mut inner = self.inner.borrow_mut();
+        inner.transport.ack_interrupt();
+        ack_irq();
+        inner.process_events();
+        inner.process_rx();
+        inner.process_tx_completions();
+    }
+
+    /// Connects to `peer` from an ephemeral port.
+    pub async fn connect(
+        &self,
+        peer: VsockAddr,
+    ) -> Result<VsockStream<'_, H, T, QUEUE_SIZE>, Error> {
+        let key = {
+            let mut inner = self.inner.borrow_mut();
+            let local_port = inner
+                .allocate_ephemeral_port()
+                .ok_or(Error::NoEphemeralPort)?;
+            let key = ConnectionKey { local_port, peer };
+            inner
+                .connections
+                .insert(key, Connection::new(State::Connecting));
+            inner.send_control(key, OP_REQUEST, 0);
+            key
+        };
+
+        // Resets the connection if dropped before it is established.
+        let stream = VsockStream { vsock: self, key };
+
+        poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            let conn = inner.connections.get_mut(&key).unwrap();
+            match conn.state {
+                State::Connecting => {
+                    conn.send_waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                State::Refused => Poll::Ready(Err(Error::ConnectionRefused)),
+                State::Reset => Poll::Ready(Err(Error::ConnectionReset)),
+                _ => Poll::Ready(Ok(())),
+            }
+        })
+        .await?;
+
+        Ok(stream)
+    }
+
+    pub fn listen(&self, port: u32) -> Result<VsockListener<'_, H, T, QUEUE_SIZE>, Error> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.port_in_use(port) {
+            return Err(Error::AddressInUse);
+        }
+        inner.listeners.insert(
+            port,
+            Listener {
+                backlog: VecDeque::new(),
+                waker: None,
+            },
+        );
+        Ok(VsockListener { vsock: self, port })
+    }
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Inner<H, T, QUEUE_SIZE> {
+    fn post_rx_buffer(&mut self, i: u16) {
+        self.rx.write_descriptor(
+            i,
+            self.rx_buffers.paddr + usize::from(i) * BUFFER_SIZE,
+            BUFFER_SIZE,
+            VIRTQ_DESC_F_WRITE,
+            0,
+        );
+        self.rx.push_avail(i);
+    }
+
+    fn post_event_buffer(&mut self, i: u16) {
+        self.event.write_descriptor(
+            i,
+            self.event_buffers.paddr + usize::from(i) * EVENT_SIZE,
+            EVENT_SIZE,
+            VIRTQ_DESC_F_WRITE,
+            0,
+        );
+        self.event.push_avail(i);
+    }
+
+    fn process_events(&mut self) {
+        let mut reposted = false;
+        while let Some((i, len)) = self.event.pop_used() {
+            let buf = &self.event_buffers.as_slice()[usize::from(i) * EVENT_SIZE..][..EVENT_SIZE];
+            let id = u32::from_le_bytes(buf.try_into().unwrap());
+            if len >= EVENT_SIZE && id == EVENT_TRANSPORT_RESET {
+                self.reset();
+            }
+            self.post_event_buffer(i);
+            reposted = true;
+        }
+        if reposted {
+            self.transport.notify(EVENT_QUEUE);
+        }
+    }
+
+    fn reset(&mut self) {
+        match read_guest_cid(&self.transport) {
+            Ok(guest_cid) => self.guest_cid = guest_cid,
+            Err(err) => log::warn!("failed to read guest cid after transport reset: {err:?}"),
+        }
+        log::debug!("transport reset, guest cid: {}", self.guest_cid);
+        self.tx_backlog.clear();
+        for listener in self.listeners.values_mut() {
+            for key in listener.backlog.drain(..) {
+                self.connections.remove(&key);
+            }
+        }
+        for conn in self.connections.values_mut() {
+            conn.state = State::Reset;
+            conn.wake();
+        }
+    }
+
+    fn process_rx(&mut self) {
+        let mut reposted = false;
+        while let Some((i, len)) = self.rx.pop_used() {
+            let offset = usize::from(i) * BUFFER_SIZE;
+            let len = len.min(BUFFER_SIZE);
+            match Header::decode(&self.rx_buffers.as_slice()[offset..][..len]) {
+                Some(header) => {
+                    let payload_start = offset + HEADER_SIZE;
+                    let payload_len = usize::try_from(header.len).unwrap().min(len - HEADER_SIZE);
+                    self.handle_packet(&header, payload_start..payload_start + payload_len);
+                }
+                None => log::warn!("dropping truncated packet"),
+            }
+            self.post_rx_buffer(i);
+            reposted = true;
+        }
+        if reposted {
+            self.transport.notify(RX_QUEUE);
+        }
+    }
+
+    fn handle_packet(&mut self, header: &Header, payload: Range<usize>) {
+        if header.dst_cid != self.guest_cid {
+            return;
+        }
+        if header.ty != TYPE_STREAM {
+            if header.op != OP_RST {
+                self.send_reset_in_reply_to(header);
+            }
+            return;
+        }
+        let key = ConnectionKey {
+            local_port: header.dst_port,
+            peer: VsockAddr {
+                cid: header.src_cid,
+                port: header.src_port,
+            },
+        };
+        if header.op == OP_REQUEST {
+            self.handle_request(key, header);
+            return;
+        }
+        let Some(conn) = self.connections.get_mut(&key) else {
+            if header.op != OP_RST {
+                self.send_reset_in_reply_to(header);
+            }
+            return;
+        };
+        conn.peer_buf_alloc = header.buf_alloc;
+        conn.peer_fwd_cnt = header.fwd_cnt;
+        conn.credit_requested = false;
+        let reply = match header.op {
+            OP_RESPONSE => {
+                if conn.state == State::Connecting {
+                    conn.state = State::Connected;
+                }
+                None
+            }
+            OP_RST => {
+                conn.state = match conn.state {
+                    State::Connecting => State::Refused,
+                    State::Closing | State::Closed => State::Closed,
+                    _ if conn.peer_shutdown_send && conn.peer_shutdown_recv => State::Closed,
+                    _ => State::Reset,
+                };
+                None
+            }
+            OP_SHUTDOWN => {
+                conn.peer_shutdown_recv |= header.flags & SHUTDOWN_RCV != 0;
+                conn.peer_shutdown_send |= header.flags & SHUTDOWN_SEND != 0;
+                // A clean disconnect ends with a reset from the side which receives the final
+                // shutdown.
+                if conn.peer_shutdown_recv
+                    && conn.peer_shutdown_send
+                    && matches!(conn.state, State::Connected | State::Closing)
+                {
+                    conn.state = State::Closed;
+                    Some(OP_RST)
+                } else {
+                    None
+                }
+            }
+            OP_RW => {
+                let payload = &self.rx_buffers.as_slice()[payload];
+                if conn.state != State::Connected || conn.peer_shutdown_send {
+                    None
+                } else if conn.rx.len() + payload.len()
+                    > usize::try_from(self.config.connection_buffer_size).unwrap()
+                {
+                    log::warn!("peer {:?} exceeded its credit", key.peer);
+                    conn.state = State::Reset;
+                    Some(OP_RST)
+                } else {
+                    conn.rx.extend(payload);
+                    None
+                }
+            }
+            OP_CREDIT_REQUEST => Some(OP_CREDIT_UPDATE),
+            _ => None,
+        };
+        conn.wake();
+        if let Some(op) = reply {
+            self.send_control(key, op, 0);
+        }
+    }
+
+    fn handle_request(&mut self, key: ConnectionKey, header: &Header) {
+        if self.connections.contains_key(&key) {
+            return;
+        }
+        match self.listeners.get_mut(&key.local_port) {
+            Some(listener) if listener.backlog.len() < self.config.accept_backlog => {
+                listener.backlog.push_back(key);
+                if let Some(waker) = listener.waker.take() {
+                    waker.wake();
+                }
+                let mut conn = Connection::new(State::Connected);
+                conn.peer_buf_alloc = header.buf_alloc;
+                conn.peer_fwd_cnt = header.fwd_cnt;
+                self.connections.insert(key, conn);
+                self.send_control(key, OP_RESPONSE, 0);
+            }
+            _ => self.send_reset_in_reply_to(header),
+        }
+    }
+
+    fn process_tx_completions(&mut self) {
+        let mut freed = false;
+        while let Some((i, _)) = self.tx.pop_used() {
+            self.free_tx_buffers.push(i);
+            freed = true;
+        }
+        while !self.tx_backlog.is_empty() && !self.free_tx_buffers.is_empty() {
+            let header = self.tx_backlog.pop_front().unwrap();
+            let i = self.free_tx_buffers.pop().unwrap();
+            self.transmit(i, &header, &[]);
+        }
+        if freed {
+            for waker in self.tx_waiters.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    fn header(&mut self, key: ConnectionKey, op: u16, flags: u32, len: usize) -> Header {
+        let fwd_cnt = match self.connections.get_mut(&key) {
+            Some(conn) => {
+                conn.advertised_fwd_cnt = conn.fwd_cnt;
+                conn.fwd_cnt
+            }
+            None => 0,
+        };
+        Header {
+            src_cid: self.guest_cid,
+            dst_cid: key.peer.cid,
+            src_port: key.local_port,
+            dst_port: key.peer.port,
+            len: len.try_into().unwrap(),
+            ty: TYPE_STREAM,
+            op,
+            flags,
+            buf_alloc: self.config.connection_buffer_size,
+            fwd_cnt,
+        }
+    }
+
+    fn send_control(&mut self, key: ConnectionKey, op: u16, flags: u32) {
+        let header = self.header(key, op, flags, 0);
+        self.send_control_header(header);
+    }
+
+    fn send_reset_in_reply_to(&mut self, header: &Header) {
+        self.send_control_header(Header {
+            src_cid: self.guest_cid,
+            dst_cid: header.src_cid,
+            src_port: header.dst_port,
+            dst_port: header.src_port,
+            ty: header.ty,
+            op: OP_RST,
+            ..Default::default()
+        });
+    }
+
+    fn send_control_header(&mut self, header: Header) {
+        match self.free_tx_buffers.pop() {
+            Some(i) if self.tx_backlog.is_empty() => self.transmit(i, &header, &[]),
+            i => {
+                self.free_tx_buffers.extend(i);
+                self.tx_backlog.push_back(header);
+            }
+        }
+    }
+
+    fn transmit(&mut self, i: u16, header: &Header, payload: &[u8]) {
+        let offset = usize::from(i) * BUFFER_SIZE;
+        let buf = &mut self.tx_buffers.as_mut_slice()[offset..][..BUFFER_SIZE];
+        header.encode(buf);
+        buf[HEADER_SIZE..][..payload.len()].copy_from_slice(payload);
+        self.tx.write_descriptor(
+            i,
+            self.tx_buffers.paddr + offset,
+            HEADER_SIZE + payload.len(),
+            0,
+            0,
+        );
+        self.tx.push_avail(i);
+        self.transport.notify(TX_QUEUE);
+    }
+
+    fn port_in_use(&self, port: u32) -> bool {
+        self.listeners.contains_key(&port)
+            || self.connections.keys().any(|key| key.local_port == port)
+    }
+
+    fn allocate_ephemeral_port(&mut self) -> Option<u32> {
+        // Each listener and connection occupies at most one port, so one more attempt than there
+        // are of them is enough to find a free port.
+        for _ in 0..=(self.listeners.len() + self.connections.len()) {
+            let port = self.next_ephemeral_port;
+            self.next_ephemeral_port = if port == LAST_EPHEMERAL_PORT {
+                FIRST_EPHEMERAL_PORT
+            } else {
+                port + 1
+            };
+            if !self.port_in_use(port) {
+                return Some(port);
+            }
+        }
+        None
+    }
+}
+
+/// A connected stream socket.
+///
+/// Dropping a stream which has not been [closed](Self::close) resets the connection.
+pub struct VsockStream<'a, H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    vsock: &'a VirtioVsock<H, T, QUEUE_SIZE>,
+    key: ConnectionKey,
+}
+
+impl<'a, H: Hal, T: Transport, const QUEUE_SIZE: usize> VsockStream<'a, H, T, QUEUE_SIZE> {
+    pub fn local_addr(&self) -> VsockAddr {
+        VsockAddr {
+            cid: self.vsock.guest_cid(),
+            port: self.key.local_port,
+        }
+    }
+
+    pub fn peer_addr(&self) -> VsockAddr {
+        self.key.peer
+    }
+
+    /// Returns `Ok(0)` once the peer has shut down its side of the stream and all data it sent
+    /// has been read.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let buffer_size = inner.config.connection_buffer_size;
+        let conn = inner.connections.get_mut(&self.key).unwrap();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        if !conn.rx.is_empty() {
+            let n = buf.len().min(conn.rx.len());
+            for (dst, src) in buf.iter_mut().zip(conn.rx.drain(..n)) {
+                *dst = src;
+            }
+            conn.fwd_cnt = conn.fwd_cnt.wrapping_add(n.try_into().unwrap());
+            // Tell a peer which may be waiting for credit about the space freed, but not after
+            // every read.
+            if conn.state == State::Connected
+                && !conn.peer_shutdown_send
+                && conn.fwd_cnt.wrapping_sub(conn.advertised_fwd_cnt) >= buffer_size / 2
+            {
+                inner.send_control(self.key, OP_CREDIT_UPDATE, 0);
+            }
+            return Poll::Ready(Ok(n));
+        }
+        match conn.state {
+            State::Reset => Poll::Ready(Err(Error::ConnectionReset)),
+            State::Closed => Poll::Ready(Ok(0)),
+            _ if conn.peer_shutdown_send => Poll::Ready(Ok(0)),
+            _ => {
+                conn.recv_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub async fn recv(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        poll_fn(|cx| self.poll_recv(cx, buf)).await
+    }
+
+    /// Sends as much of `buf` as the peer's credit and a single packet allow, waiting until at
+    /// least one byte can be sent.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_send(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, Error>> {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let inner = &mut *inner;
+        inner.process_tx_completions();
+        let conn = inner.connections.get_mut(&self.key).unwrap();
+        match conn.state {
+            State::Connected => {}
+            State::Reset => return Poll::Ready(Err(Error::ConnectionReset)),
+            _ => return Poll::Ready(Err(Error::Shutdown)),
+        }
+        if conn.local_shutdown_send || conn.peer_shutdown_recv {
+            return Poll::Ready(Err(Error::Shutdown));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let credit = conn.peer_credit();
+        if credit == 0 {
+            conn.send_waker = Some(cx.waker().clone());
+            if !conn.credit_requested {
+                conn.credit_requested = true;
+                inner.send_control(self.key, OP_CREDIT_REQUEST, 0);
+            }
+            return Poll::Pending;
+        }
+        if !inner.tx_backlog.is_empty() || inner.free_tx_buffers.is_empty() {
+            inner.tx_waiters.push(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let n = buf.len().min(credit.try_into().unwrap()).min(MAX_PAYLOAD);
+        let i = inner.free_tx_buffers.pop().unwrap();
+        let header = inner.header(self.key, OP_RW, 0, n);
+        inner.transmit(i, &header, &buf[..n]);
+        let conn = inner.connections.get_mut(&self.key).unwrap();
+        conn.tx_cnt = conn.tx_cnt.wrapping_add(n.try_into().unwrap());
+        Poll::Ready(Ok(n))
+    }
+
+    pub async fn send(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        poll_fn(|cx| self.poll_send(cx, buf)).await
+    }
+
+    pub async fn send_all(&mut self, mut buf: &[u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            let n = self.send(buf).await?;
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    /// Tells the peer that no more data will be sent. Data may still be received.
+    pub fn shutdown_send(&mut self) -> Result<(), Error> {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let conn = inner.connections.get_mut(&self.key).unwrap();
+        match conn.state {
+            State::Connected => {}
+            State::Reset => return Err(Error::ConnectionReset),
+            _ => return Ok(()),
+        }
+        if !conn.local_shutdown_send {
+            conn.local_shutdown_send = true;
+            inner.send_control(self.key, OP_SHUTDOWN, SHUTDOWN_SEND);
+        }
+        Ok(())
+    }
+
+    /// Shuts down both directions of the stream, and waits for the peer to complete the
+    /// disconnect. Data which has not yet been read is discarded.
+    pub async fn close(self) -> Result<(), Error> {
+        {
+            let mut inner = self.vsock.inner.borrow_mut();
+            let conn = inner.connections.get_mut(&self.key).unwrap();
+            if conn.state == State::Connected {
+                conn.state = State::Closing;
+                conn.local_shutdown_send = true;
+                conn.rx.clear();
+                inner.send_control(self.key, OP_SHUTDOWN, SHUTDOWN_RCV | SHUTDOWN_SEND);
+            }
+        }
+        poll_fn(|cx| {
+            let mut inner = self.vsock.inner.borrow_mut();
+            let conn = inner.connections.get_mut(&self.key).unwrap();
+            match conn.state {
+                State::Closing => {
+                    conn.recv_waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                State::Reset => Poll::Ready(Err(Error::ConnectionReset)),
+                _ => Poll::Ready(Ok(())),
+            }
+        })
+        .await
+    }
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Drop for VsockStream<'_, H, T, QUEUE_SIZE> {
+    fn drop(&mut self) {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let state = inner.connections.get(&self.key).unwrap().state;
+        if matches!(state, State::Connecting | State::Connected | State::Closing) {
+            inner.send_control(self.key, OP_RST, 0);
+        }
+        inner.connections.remove(&self.key);
+    }
+}
+
+/// A port on which connection requests are accepted.
+///
+/// Dropping a listener stops listening, and resets any connections which have not been accepted.
+pub struct VsockListener<'a, H: Hal, T: Transport, const QUEUE_SIZE: usize> {
+    vsock: &'a VirtioVsock<H, T, QUEUE_SIZE>,
+    port: u32,
+}
+
+impl<'a, H: Hal, T: Transport, const QUEUE_SIZE: usize> VsockListener<'a, H, T, QUEUE_SIZE> {
+    pub fn local_addr(&self) -> VsockAddr {
+        VsockAddr {
+            cid: self.vsock.guest_cid(),
+            port: self.port,
+        }
+    }
+
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<VsockStream<'a, H, T, QUEUE_SIZE>> {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let listener = inner.listeners.get_mut(&self.port).unwrap();
+        match listener.backlog.pop_front() {
+            Some(key) => Poll::Ready(VsockStream {
+                vsock: self.vsock,
+                key,
+            }),
+            None => {
+                listener.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub async fn accept(&mut self) -> VsockStream<'a, H, T, QUEUE_SIZE> {
+        poll_fn(|cx| self.poll_accept(cx)).await
+    }
+}
+
+impl<H: Hal, T: Transport, const QUEUE_SIZE: usize> Drop for VsockListener<'_, H, T, QUEUE_SIZE> {
+    fn drop(&mut self) {
+        let mut inner = self.vsock.inner.borrow_mut();
+        let listener = inner.listeners.remove(&self.port).unwrap();
+        for key in listener.backlog {
+            inner.send_control(key, OP_RST, 0);
+            inner.connections.remove(&key);
+        }
+    }
+}
+
+fn set_up_queue<H: Hal, T: Transport, const SIZE: usize>(
+    transport: &mut T,
+    i: u16,
+) -> Result<Queue<H, SIZE>, Error> {
+    if transport.queue_used(i) {
+        return Err(Error::Virtio(virtio_drivers::Error::AlreadyUsed));
+    }
+    if transport.max_queue_size() < u32::try_from(SIZE).unwrap() {
+        return Err(Error::Virtio(virtio_drivers::Error::InvalidParam));
+    }
+    let queue = Queue::new();
+    transport.queue_set(
+        i,
+        SIZE.try_into().unwrap(),
+        queue.paddr(Queue::<H, SIZE>::DESCRIPTORS_OFFSET),
+        queue.paddr(Queue::<H, SIZE>::AVAIL_OFFSET),
+        queue.paddr(Queue::<H, SIZE>::used_offset()),
+    );
+    Ok(queue)
+}
+
+fn read_guest_cid<T: Transport>(transport: &T) -> Result<u64, Error> {
+    let config = transport
+        .config_space::<u8>()
+        .map_err(Error::Virtio)?
+        .as_ptr();
+    // struct virtio_vsock_config { le64 guest_cid; }
+    let read = |offset: usize| unsafe { ptr::read_volatile(config.add(offset)) };
+    Ok(u64::from_le_bytes(core::array::from_fn(read)))
+}
@@ -0,0 +1,67 @@
+// struct virtio_vsock_hdr, from Section 5.10.6 of the virtio specification.
+
+pub(crate) const HEADER_SIZE: usize = 44;
+
+pub(crate) const TYPE_STREAM: u16 = 1;
+
+pub(crate) const OP_REQUEST: u16 = 1;
+pub(crate) const OP_RESPONSE: u16 = 2;
+pub(crate) const OP_RST: u16 = 3;
+pub(crate) const OP_SHUTDOWN: u16 = 4;
+pub(crate) const OP_RW: u16 = 5;
+pub(crate) const OP_CREDIT_UPDATE: u16 = 6;
+pub(crate) const OP_CREDIT_REQUEST: u16 = 7;
+
+pub(crate) const SHUTDOWN_RCV: u32 = 1 << 0;
+pub(crate) const SHUTDOWN_SEND: u32 = 1 << 1;
+
+pub(crate) const EVENT_TRANSPORT_RESET: u32 = 0;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub(crate) src_cid: u64,
+    pub(crate) dst_cid: u64,
+    pub(crate) src_port: u32,
+    pub(crate) dst_port: u32,
+    pub(crate) len: u32,
+    pub(crate) ty: u16,
+    pub(crate) op: u16,
+    pub(crate) flags: u32,
+    pub(crate) buf_alloc: u32,
+    pub(crate) fwd_cnt: u32,
+}
+
+impl Header {
+    pub(crate) fn encode(&self, buf: &mut [u8]) {
+        let buf = &mut buf[..HEADER_SIZE];
+        buf[0..8].copy_from_slice(&self.src_cid.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.dst_cid.to_le_bytes());
+        buf[16..20].copy_from_slice(&self.src_port.to_le_bytes());
+        buf[20..24].copy_from_slice(&self.dst_port.to_le_bytes());
+        buf[24..28].copy_from_slice(&self.len.to_le_bytes());
+        buf[28..30].copy_from_slice(&self.ty.to_le_bytes());
+        buf[30..32].copy_from_slice(&self.op.to_le_bytes());
+        buf[32..36].copy_from_slice(&self.flags.to_le_bytes());
+        buf[36..40].copy_from_slice(&self.buf_alloc.to_le_bytes());
+        buf[40..44].copy_from_slice(&self.fwd_cnt.to_le_bytes());
+    }
+
+    pub(crate) fn decode(buf: &[u8]) -> Option<Self> {
+        let buf = buf.get(..HEADER_SIZE)?;
+        let u64_at = |i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let u16_at = |i: usize| u16::from_le_bytes(buf[i..i + 2].try_into().unwrap());
+        Some(Self {
+            src_cid: u64_at(0),
+            dst_cid: u64_at(8),
+            src_port: u32_at(16),
+            dst_port: u32_at(20),
+            len: u32_at(24),
+            ty: u16_at(28),
+            op: u16_at(30),
+            flags: u32_at(32),
+            buf_alloc: u32_at(36),
+            fwd_cnt: u32_at(40),
+        })
+    }
+}
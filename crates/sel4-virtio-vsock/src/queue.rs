@@ -0,0 +1,150 @@
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{fence, Ordering};
+
+use virtio_drivers::{BufferDirection, Hal, PhysAddr, PAGE_SIZE};
+
+pub(crate) fn pages(size: usize) -> usize {
+    size.div_ceil(PAGE_SIZE)
+}
+
+pub(crate) struct Dma<H: Hal> {
+    pub(crate) paddr: PhysAddr,
+    vaddr: NonNull<u8>,
+    pages: usize,
+    _phantom: PhantomData<H>,
+}
+
+impl<H: Hal> Dma<H> {
+    pub(crate) fn new(pages: usize) -> Self {
+        let (paddr, vaddr) = H::dma_alloc(pages, BufferDirection::Both);
+        Self {
+            paddr,
+            vaddr,
+            pages,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.vaddr.as_ptr(), self.pages * PAGE_SIZE) }
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.vaddr.as_ptr(), self.pages * PAGE_SIZE) }
+    }
+}
+
+impl<H: Hal> Drop for Dma<H> {
+    fn drop(&mut self) {
+        unsafe {
+            H::dma_dealloc(self.paddr, self.vaddr, self.pages);
+        }
+    }
+}
+
+// A split virtqueue, laid out as required by legacy devices, which modern devices also accept:
+// the descriptor table and available ring are contiguous, and the used ring begins on the next
+// page.
+pub(crate) struct Queue<H: Hal, const SIZE: usize> {
+    dma: Dma<H>,
+    next_avail: u16,
+    last_used: u16,
+}
+
+#[repr(C)]
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+impl<H: Hal, const SIZE: usize> Queue<H, SIZE> {
+    pub(crate) const DESCRIPTORS_OFFSET: usize = 0;
+    pub(crate) const AVAIL_OFFSET: usize = SIZE * mem::size_of::<Descriptor>();
+
+    pub(crate) const fn used_offset() -> usize {
+        // flags[2] idx[2] ring[2 * SIZE] used_event[2]
+        let avail_size = 2 + 2 + 2 * SIZE + 2;
+        (Self::AVAIL_OFFSET + avail_size).next_multiple_of(PAGE_SIZE)
+    }
+
+    const fn size() -> usize {
+        // flags[2] idx[2] ring[8 * SIZE] avail_event[2]
+        let used_size = 2 + 2 + 8 * SIZE + 2;
+        Self::used_offset() + used_size
+    }
+
+    pub(crate) fn new() -> Self {
+        let dma = Dma::new(pages(Self::size()));
+        unsafe {
+            ptr::write_bytes(dma.vaddr.as_ptr(), 0, dma.pages * PAGE_SIZE);
+        }
+        Self {
+            dma,
+            next_avail: 0,
+            last_used: 0,
+        }
+    }
+
+    pub(crate) fn paddr(&self, offset: usize) -> PhysAddr {
+        self.dma.paddr + offset
+    }
+
+    fn ptr<U>(&self, offset: usize) -> *mut U {
+        unsafe { self.dma.vaddr.as_ptr().add(offset).cast() }
+    }
+
+    pub(crate) fn write_descriptor(
+        &mut self,
+        i: u16,
+        addr: PhysAddr,
+        len: usize,
+        flags: u16,
+        next: u16,
+    ) {
+        let desc = Descriptor {
+            addr: addr.try_into().unwrap(),
+            len: len.try_into().unwrap(),
+            flags,
+            next,
+        };
+        unsafe {
+            ptr::write_volatile(
+                self.ptr(Self::DESCRIPTORS_OFFSET + usize::from(i) * mem::size_of::<Descriptor>()),
+                desc,
+            );
+        }
+    }
+
+    pub(crate) fn push_avail(&mut self, head: u16) {
+        let slot = usize::from(self.next_avail) % SIZE;
+        unsafe {
+            ptr::write_volatile(self.ptr(Self::AVAIL_OFFSET + 4 + 2 * slot), head);
+        }
+        self.next_avail = self.next_avail.wrapping_add(1);
+        // The descriptors and ring entry must be visible before the index which publishes them.
+        fence(Ordering::SeqCst);
+        unsafe {
+            ptr::write_volatile(self.ptr(Self::AVAIL_OFFSET + 2), self.next_avail);
+        }
+        fence(Ordering::SeqCst);
+    }
+
+    // Returns the head of the next descriptor chain completed by the device, and the number of
+    // bytes it wrote.
+    pub(crate) fn pop_used(&mut self) -> Option<(u16, usize)> {
+        let used_idx: u16 = unsafe { ptr::read_volatile(self.ptr(Self::used_offset() + 2)) };
+        if used_idx == self.last_used {
+            return None;
+        }
+        fence(Ordering::SeqCst);
+        let elem = Self::used_offset() + 4 + 8 * (usize::from(self.last_used) % SIZE);
+        let id: u32 = unsafe { ptr::read_volatile(self.ptr(elem)) };
+        let len: u32 = unsafe { ptr::read_volatile(self.ptr(elem + 4)) };
+        self.last_used = self.last_used.wrapping_add(1);
+        Some((id.try_into().unwrap(), len.try_into().unwrap()))
+    }
+}
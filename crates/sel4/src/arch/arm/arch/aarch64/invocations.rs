@@ -1,8 +1,8 @@
 use sel4_config::sel4_cfg;
 
 use crate::{
-    local_cptr::*, AbsoluteCPtr, CapRights, Error, FrameType, InvocationContext, LocalCPtr, Result,
-    VMAttributes, Word,
+    local_cptr::*, AbsoluteCPtr, CanMap, CapRights, Error, FrameType, InvocationContext, LocalCPtr,
+    PAddr, Result, VMAttributes, Word,
 };
 
 #[sel4_cfg(ARM_HYPERVISOR_SUPPORT)]
@@ -63,7 +63,7 @@ impl<C: InvocationContext> VCPU<C> {
     }
 }
 
-impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
+impl<T: CanMap, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_ARM_Page_Map`.
     pub fn frame_map(
         self,
@@ -82,7 +82,9 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
             )
         }))
     }
+}
 
+impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_ARM_Page_Unmap`.
     pub fn frame_unmap(self) -> Result<()> {
         Error::wrap(
@@ -91,12 +93,12 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     }
 
     /// Corresponds to `seL4_ARM_Page_GetAddress`.
-    pub fn frame_get_address(self) -> Result<usize> {
+    pub fn frame_get_address(self) -> Result<PAddr> {
         let ret = self.invoke(|cptr, ipc_buffer| {
             ipc_buffer.inner_mut().seL4_ARM_Page_GetAddress(cptr.bits())
         });
         match Error::from_sys(ret.error) {
-            None => Ok(ret.paddr.try_into().unwrap()),
+            None => Ok(PAddr::from_word(ret.paddr)),
             Some(err) => Err(err),
         }
     }
@@ -7,6 +7,8 @@ mod vspace;
 #[sel4_config::sel4_cfg(ARM_HYPERVISOR_SUPPORT)]
 mod vcpu_reg;
 
+pub(crate) use vspace::PagingStructure;
+
 pub(crate) mod top_level {
     pub use super::{
         object::{
@@ -1,4 +1,4 @@
-use crate::{newtype_methods, sys, Word};
+use crate::{named_registers_method, newtype_methods, sys, Word};
 
 /// Corresponds to `seL4_UserContext`.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -7,6 +7,45 @@ pub struct UserContext(sys::seL4_UserContext);
 impl UserContext {
     newtype_methods!(sys::seL4_UserContext);
 
+    named_registers_method!(
+        pc,
+        sp,
+        spsr,
+        x0,
+        x1,
+        x2,
+        x3,
+        x4,
+        x5,
+        x6,
+        x7,
+        x8,
+        x9,
+        x10,
+        x11,
+        x12,
+        x13,
+        x14,
+        x15,
+        x16,
+        x17,
+        x18,
+        x19,
+        x20,
+        x21,
+        x22,
+        x23,
+        x24,
+        x25,
+        x26,
+        x27,
+        x28,
+        x29,
+        x30,
+        tpidr_el0,
+        tpidrro_el0
+    );
+
     pub fn pc(&self) -> &Word {
         &self.0.pc
     }
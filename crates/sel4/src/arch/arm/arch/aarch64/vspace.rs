@@ -1,5 +1,6 @@
 use crate::{
-    cap_type, sys, FrameType, ObjectBlueprint, ObjectBlueprintAArch64, ObjectBlueprintArm,
+    cap_type, local_cptr::*, sys, FrameType, InvocationContext, ObjectBlueprint,
+    ObjectBlueprintAArch64, ObjectBlueprintArm, Result, VMAttributes,
 };
 
 /// Frame sizes for AArch64.
@@ -39,6 +40,8 @@ impl FrameType for cap_type::HugePage {
     const FRAME_SIZE: FrameSize = FrameSize::Huge;
 }
 
+crate::cap_traits::seal_frame_types!(SmallPage, LargePage, HugePage);
+
 //
 
 impl cap_type::PUD {
@@ -52,3 +55,50 @@ impl cap_type::PD {
 impl cap_type::PT {
     pub const SPAN_BITS: usize = FrameSize::Small.bits() + (sys::seL4_PageTableIndexBits as usize);
 }
+
+//
+
+/// The paging structures beneath a [`cap_type::PGD`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PagingStructure {
+    PUD,
+    PD,
+    PT,
+}
+
+impl PagingStructure {
+    pub(crate) const fn from_span_bits(span_bits: usize) -> Option<Self> {
+        match span_bits {
+            cap_type::PUD::SPAN_BITS => Some(Self::PUD),
+            cap_type::PD::SPAN_BITS => Some(Self::PD),
+            cap_type::PT::SPAN_BITS => Some(Self::PT),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn blueprint(self) -> ObjectBlueprint {
+        match self {
+            Self::PUD => {
+                ObjectBlueprint::Arch(ObjectBlueprintArm::SeL4Arch(ObjectBlueprintAArch64::PUD))
+            }
+            Self::PD => ObjectBlueprint::Arch(ObjectBlueprintArm::PD),
+            Self::PT => ObjectBlueprint::Arch(ObjectBlueprintArm::PT),
+        }
+    }
+
+    pub(crate) fn map<C: InvocationContext>(
+        self,
+        cap: Unspecified<C>,
+        vspace: VSpace,
+        vaddr: usize,
+        attrs: VMAttributes,
+    ) -> Result<()> {
+        match self {
+            Self::PUD => cap
+                .downcast::<cap_type::PUD>()
+                .pud_map(vspace, vaddr, attrs),
+            Self::PD => cap.downcast::<cap_type::PD>().pd_map(vspace, vaddr, attrs),
+            Self::PT => cap.downcast::<cap_type::PT>().pt_map(vspace, vaddr, attrs),
+        }
+    }
+}
@@ -6,6 +6,8 @@ mod vm_attributes;
 
 pub(crate) mod fault;
 
+pub(crate) use arch::PagingStructure;
+
 pub(crate) mod top_level {
     pub use super::{
         arch::top_level::*,
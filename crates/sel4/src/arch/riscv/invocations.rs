@@ -1,9 +1,9 @@
 use crate::{
-    local_cptr::*, AbsoluteCPtr, CapRights, Error, FrameType, InvocationContext, LocalCPtr, Result,
-    VMAttributes,
+    local_cptr::*, AbsoluteCPtr, CanMap, CapRights, Error, FrameType, InvocationContext, LocalCPtr,
+    PAddr, Result, VMAttributes,
 };
 
-impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
+impl<T: CanMap, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_RISCV_Page_Map`.
     pub fn frame_map(
         self,
@@ -22,7 +22,9 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
             )
         }))
     }
+}
 
+impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_RISCV_Page_Unmap`.
     pub fn frame_unmap(self) -> Result<()> {
         Error::wrap(
@@ -33,14 +35,14 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     }
 
     /// Corresponds to `seL4_RISCV_Page_GetAddress`.
-    pub fn frame_get_address(self) -> Result<usize> {
+    pub fn frame_get_address(self) -> Result<PAddr> {
         let ret = self.invoke(|cptr, ipc_buffer| {
             ipc_buffer
                 .inner_mut()
                 .seL4_RISCV_Page_GetAddress(cptr.bits())
         });
         match Error::from_sys(ret.error) {
-            None => Ok(ret.paddr.try_into().unwrap()),
+            None => Ok(PAddr::from_word(ret.paddr)),
             Some(err) => Err(err),
         }
     }
@@ -6,6 +6,8 @@ mod vspace;
 
 pub(crate) mod fault;
 
+pub(crate) use vspace::PagingStructure;
+
 pub(crate) mod top_level {
     pub use super::{
         object::{ObjectBlueprintArch, ObjectBlueprintRISCV, ObjectTypeArch, ObjectTypeRISCV},
@@ -1,4 +1,4 @@
-use crate::{newtype_methods, sys, Word};
+use crate::{named_registers_method, newtype_methods, sys, Word};
 
 /// Corresponds to `seL4_UserContext`.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -7,6 +7,11 @@ pub struct UserContext(sys::seL4_UserContext);
 impl UserContext {
     newtype_methods!(sys::seL4_UserContext);
 
+    named_registers_method!(
+        pc, ra, sp, gp, s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11, a0, a1, a2, a3, a4, a5,
+        a6, a7, t0, t1, t2, t3, t4, t5, t6, tp
+    );
+
     pub fn pc(&self) -> &Word {
         &self.0.pc
     }
@@ -1,5 +1,8 @@
 #[allow(unused_imports)]
-use crate::{cap_type, sys, FrameType, ObjectBlueprint, ObjectBlueprintRISCV};
+use crate::{
+    cap_type, local_cptr::*, sys, FrameType, InvocationContext, ObjectBlueprint,
+    ObjectBlueprintRISCV, Result, VMAttributes,
+};
 
 #[sel4_config::sel4_cfg_enum]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -43,6 +46,51 @@ impl FrameType for cap_type::GigaPage {
     const FRAME_SIZE: FrameSize = FrameSize::Giga;
 }
 
+crate::cap_traits::seal_frame_types!(
+    _4KPage,
+    MegaPage,
+    #[sel4_config::sel4_cfg(any(PT_LEVELS = "3", PT_LEVELS = "4"))]
+    GigaPage,
+);
+
 impl cap_type::PageTable {
     pub const INDEX_BITS: usize = sys::seL4_PageTableIndexBits as usize;
 }
+
+/// The paging structures beneath the root [`cap_type::PageTable`], which are all page tables.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PagingStructure {
+    PageTable,
+}
+
+impl PagingStructure {
+    pub(crate) const fn from_span_bits(span_bits: usize) -> Option<Self> {
+        if span_bits > FrameSize::_4K_BITS
+            && (span_bits - FrameSize::_4K_BITS) % cap_type::PageTable::INDEX_BITS == 0
+        {
+            Some(Self::PageTable)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) const fn blueprint(self) -> ObjectBlueprint {
+        match self {
+            Self::PageTable => ObjectBlueprint::Arch(ObjectBlueprintRISCV::PageTable),
+        }
+    }
+
+    pub(crate) fn map<C: InvocationContext>(
+        self,
+        cap: Unspecified<C>,
+        vspace: VSpace,
+        vaddr: usize,
+        attrs: VMAttributes,
+    ) -> Result<()> {
+        match self {
+            Self::PageTable => cap
+                .downcast::<cap_type::PageTable>()
+                .page_table_map(vspace, vaddr, attrs),
+        }
+    }
+}
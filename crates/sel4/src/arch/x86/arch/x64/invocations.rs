@@ -1,9 +1,9 @@
 use crate::{
-    local_cptr::*, AbsoluteCPtr, CapRights, Error, FrameType, InvocationContext, LocalCPtr, Result,
-    VMAttributes,
+    local_cptr::*, sel4_cfg, AbsoluteCPtr, CanMap, CapRights, Error, FrameType, InvocationContext,
+    LocalCPtr, PAddr, Result, VMAttributes,
 };
 
-impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
+impl<T: CanMap, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_X86_Page_Map`.
     pub fn frame_map(
         self,
@@ -22,7 +22,9 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
             )
         }))
     }
+}
 
+impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_X86_Page_Unmap`.
     pub fn frame_unmap(self) -> Result<()> {
         Error::wrap(
@@ -31,17 +33,32 @@ impl<T: FrameType, C: InvocationContext> LocalCPtr<T, C> {
     }
 
     /// Corresponds to `seL4_X86_Page_GetAddress`.
-    pub fn frame_get_address(self) -> Result<usize> {
+    pub fn frame_get_address(self) -> Result<PAddr> {
         let ret = self.invoke(|cptr, ipc_buffer| {
             ipc_buffer.inner_mut().seL4_X86_Page_GetAddress(cptr.bits())
         });
         match Error::from_sys(ret.error) {
-            None => Ok(ret.paddr.try_into().unwrap()),
+            None => Ok(PAddr::from_word(ret.paddr)),
             Some(err) => Err(err),
         }
     }
 }
 
+#[sel4_cfg(IOMMU)]
+impl<T: CanMap, C: InvocationContext> LocalCPtr<T, C> {
+    /// Corresponds to `seL4_X86_Page_MapIO`.
+    pub fn frame_map_io(self, iospace: IOSpace, rights: CapRights, ioaddr: usize) -> Result<()> {
+        Error::wrap(self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer.inner_mut().seL4_X86_Page_MapIO(
+                cptr.bits(),
+                iospace.bits(),
+                rights.into_inner(),
+                ioaddr.try_into().unwrap(),
+            )
+        }))
+    }
+}
+
 impl<C: InvocationContext> PDPT<C> {
     pub fn pdpt_map(self, vspace: VSpace, vaddr: usize, attr: VMAttributes) -> Result<()> {
         Error::wrap(self.invoke(|cptr, ipc_buffer| {
@@ -86,6 +103,29 @@ impl<C: InvocationContext> PageTable<C> {
     }
 }
 
+#[sel4_cfg(IOMMU)]
+impl<C: InvocationContext> IOPageTable<C> {
+    /// Corresponds to `seL4_X86_IOPageTable_Map`.
+    pub fn io_page_table_map(self, iospace: IOSpace, ioaddr: usize) -> Result<()> {
+        Error::wrap(self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer.inner_mut().seL4_X86_IOPageTable_Map(
+                cptr.bits(),
+                iospace.bits(),
+                ioaddr.try_into().unwrap(),
+            )
+        }))
+    }
+
+    /// Corresponds to `seL4_X86_IOPageTable_Unmap`.
+    pub fn io_page_table_unmap(self) -> Result<()> {
+        Error::wrap(self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer
+                .inner_mut()
+                .seL4_X86_IOPageTable_Unmap(cptr.bits())
+        }))
+    }
+}
+
 // TODO
 impl<C: InvocationContext> IRQControl<C> {}
 
@@ -3,6 +3,8 @@ mod object;
 mod user_context;
 mod vspace;
 
+pub(crate) use vspace::PagingStructure;
+
 pub(crate) mod top_level {
     pub use super::{
         object::{ObjectBlueprintSeL4Arch, ObjectBlueprintX64, ObjectTypeSeL4Arch, ObjectTypeX64},
@@ -1,4 +1,4 @@
-use crate::{newtype_methods, sys, Word};
+use crate::{named_registers_method, newtype_methods, sys, Word};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct UserContext(sys::seL4_UserContext);
@@ -6,6 +6,11 @@ pub struct UserContext(sys::seL4_UserContext);
 impl UserContext {
     newtype_methods!(sys::seL4_UserContext);
 
+    named_registers_method!(
+        rip, rsp, rflags, rax, rbx, rcx, rdx, rsi, rdi, rbp, r8, r9, r10, r11, r12, r13, r14, r15,
+        fs_base, gs_base
+    );
+
     pub fn pc(&self) -> &Word {
         &self.0.rip
     }
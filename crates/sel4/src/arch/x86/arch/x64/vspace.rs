@@ -1,4 +1,7 @@
-use crate::{cap_type, sys, FrameType, ObjectBlueprint, ObjectBlueprintX64, ObjectBlueprintX86};
+use crate::{
+    cap_type, local_cptr::*, sys, FrameType, InvocationContext, ObjectBlueprint,
+    ObjectBlueprintX64, ObjectBlueprintX86, Result, VMAttributes,
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FrameSize {
@@ -36,6 +39,8 @@ impl FrameType for cap_type::HugePage {
     const FRAME_SIZE: FrameSize = FrameSize::Huge;
 }
 
+crate::cap_traits::seal_frame_types!(_4K, LargePage, HugePage);
+
 //
 
 impl cap_type::PDPT {
@@ -51,3 +56,54 @@ impl cap_type::PageDirectory {
 impl cap_type::PageTable {
     pub const SPAN_BITS: usize = FrameSize::_4K.bits() + (sys::seL4_PageTableIndexBits as usize);
 }
+
+//
+
+/// The paging structures beneath a [`cap_type::PML4`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PagingStructure {
+    PDPT,
+    PageDirectory,
+    PageTable,
+}
+
+impl PagingStructure {
+    pub(crate) const fn from_span_bits(span_bits: usize) -> Option<Self> {
+        match span_bits {
+            cap_type::PDPT::SPAN_BITS => Some(Self::PDPT),
+            cap_type::PageDirectory::SPAN_BITS => Some(Self::PageDirectory),
+            cap_type::PageTable::SPAN_BITS => Some(Self::PageTable),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn blueprint(self) -> ObjectBlueprint {
+        match self {
+            Self::PDPT => {
+                ObjectBlueprint::Arch(ObjectBlueprintX86::SeL4Arch(ObjectBlueprintX64::PDPT))
+            }
+            Self::PageDirectory => ObjectBlueprint::Arch(ObjectBlueprintX86::PageDirectory),
+            Self::PageTable => ObjectBlueprint::Arch(ObjectBlueprintX86::PageTable),
+        }
+    }
+
+    pub(crate) fn map<C: InvocationContext>(
+        self,
+        cap: Unspecified<C>,
+        vspace: VSpace,
+        vaddr: usize,
+        attrs: VMAttributes,
+    ) -> Result<()> {
+        match self {
+            Self::PDPT => cap
+                .downcast::<cap_type::PDPT>()
+                .pdpt_map(vspace, vaddr, attrs),
+            Self::PageDirectory => cap
+                .downcast::<cap_type::PageDirectory>()
+                .page_directory_map(vspace, vaddr, attrs),
+            Self::PageTable => cap
+                .downcast::<cap_type::PageTable>()
+                .page_table_map(vspace, vaddr, attrs),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::Word;
+
+/// The capability data of an `seL4_X86_IOSpace` capability, which selects a PCI device and the
+/// IOMMU domain in which its DMA is translated.
+///
+/// The root task's IOSpace capability must be minted with this data before it can be used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IOSpaceCapData {
+    pub domain_id: u16,
+    pub pci_bus: u8,
+    pub pci_dev: u8,
+    pub pci_fn: u8,
+}
+
+impl IOSpaceCapData {
+    pub const fn into_word(self) -> Word {
+        ((self.domain_id as Word) << 16)
+            | ((self.pci_bus as Word) << 8)
+            | (((self.pci_dev & 0x1f) as Word) << 3)
+            | ((self.pci_fn & 0x7) as Word)
+    }
+}
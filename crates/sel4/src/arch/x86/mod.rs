@@ -1,11 +1,16 @@
-use crate::sys;
+use crate::{sel4_cfg, sys};
 
 mod arch;
 mod object;
 mod vm_attributes;
 
+#[sel4_cfg(IOMMU)]
+mod io_space;
+
 pub(crate) mod fault;
 
+pub(crate) use arch::PagingStructure;
+
 pub(crate) mod top_level {
     pub use super::{
         arch::top_level::*,
@@ -14,12 +19,15 @@ pub(crate) mod top_level {
         vm_attributes::VMAttributes,
         NUM_FAST_MESSAGE_REGISTERS,
     };
+
+    #[sel4_cfg(IOMMU)]
+    pub use super::io_space::IOSpaceCapData;
 }
 
 pub const NUM_FAST_MESSAGE_REGISTERS: usize = sys::seL4_FastMessageRegisters as usize; // no other const way to convert
 
 pub(crate) mod cap_type_arch {
-    use crate::declare_cap_type;
+    use crate::{declare_cap_type, sel4_cfg};
 
     declare_cap_type!(_4K);
     declare_cap_type!(LargePage);
@@ -30,12 +38,24 @@ pub(crate) mod cap_type_arch {
     declare_cap_type!(PageDirectory);
     declare_cap_type!(PageTable);
 
+    #[sel4_cfg(IOMMU)]
+    declare_cap_type! {
+        /// Corresponds to `seL4_X86_IOSpace`.
+        IOSpace
+    }
+
+    #[sel4_cfg(IOMMU)]
+    declare_cap_type! {
+        /// Corresponds to `seL4_X86_IOPageTable`.
+        IOPageTable
+    }
+
     pub type VSpace = PML4;
     pub type Granule = _4K;
 }
 
 pub(crate) mod local_cptr_arch {
-    use crate::declare_local_cptr_alias;
+    use crate::{declare_local_cptr_alias, sel4_cfg};
 
     declare_local_cptr_alias!(_4K);
     declare_local_cptr_alias!(LargePage);
@@ -45,4 +65,10 @@ pub(crate) mod local_cptr_arch {
     declare_local_cptr_alias!(PDPT);
     declare_local_cptr_alias!(PageDirectory);
     declare_local_cptr_alias!(PageTable);
+
+    #[sel4_cfg(IOMMU)]
+    declare_local_cptr_alias!(IOSpace);
+
+    #[sel4_cfg(IOMMU)]
+    declare_local_cptr_alias!(IOPageTable);
 }
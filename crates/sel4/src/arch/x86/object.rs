@@ -1,8 +1,8 @@
 use core::ffi::c_uint;
 
 use crate::{
-    const_helpers::u32_into_usize, sys, ObjectBlueprint, ObjectBlueprintSeL4Arch, ObjectType,
-    ObjectTypeSeL4Arch,
+    const_helpers::u32_into_usize, sel4_cfg, sel4_cfg_enum, sel4_cfg_match, sys, ObjectBlueprint,
+    ObjectBlueprintSeL4Arch, ObjectType, ObjectTypeSeL4Arch,
 };
 
 pub type ObjectTypeArch = ObjectTypeX86;
@@ -10,21 +10,27 @@ pub type ObjectTypeArch = ObjectTypeX86;
 pub type ObjectBlueprintArch = ObjectBlueprintX86;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[sel4_cfg_enum]
 pub enum ObjectTypeX86 {
     _4K,
     LargePage,
     PageTable,
     PageDirectory,
+    #[sel4_cfg(IOMMU)]
+    IOPageTable,
     SeL4Arch(ObjectTypeSeL4Arch),
 }
 
 impl ObjectTypeX86 {
     pub(crate) const fn into_sys(self) -> c_uint {
+        #[sel4_cfg_match]
         match self {
             Self::_4K => sys::_object::seL4_X86_4K,
             Self::LargePage => sys::_object::seL4_X86_LargePageObject,
             Self::PageTable => sys::_object::seL4_X86_PageTableObject,
             Self::PageDirectory => sys::_object::seL4_X86_PageDirectoryObject,
+            #[sel4_cfg(IOMMU)]
+            Self::IOPageTable => sys::_object::seL4_X86_IOPageTableObject,
             Self::SeL4Arch(sel4_arch) => sel4_arch.into_sys(),
         }
     }
@@ -43,31 +49,40 @@ impl From<ObjectTypeSeL4Arch> for ObjectType {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[sel4_cfg_enum]
 pub enum ObjectBlueprintX86 {
     _4K,
     LargePage,
     PageTable,
     PageDirectory,
+    #[sel4_cfg(IOMMU)]
+    IOPageTable,
     SeL4Arch(ObjectBlueprintSeL4Arch),
 }
 
 impl ObjectBlueprintX86 {
     pub(crate) const fn ty(self) -> ObjectTypeX86 {
+        #[sel4_cfg_match]
         match self {
             Self::_4K => ObjectTypeX86::_4K,
             Self::LargePage => ObjectTypeX86::LargePage,
             Self::PageTable => ObjectTypeX86::PageTable,
             Self::PageDirectory => ObjectTypeX86::PageDirectory,
+            #[sel4_cfg(IOMMU)]
+            Self::IOPageTable => ObjectTypeX86::IOPageTable,
             Self::SeL4Arch(sel4_arch) => ObjectTypeX86::SeL4Arch(sel4_arch.ty()),
         }
     }
 
     pub(crate) const fn physical_size_bits(self) -> usize {
+        #[sel4_cfg_match]
         match self {
             Self::_4K => u32_into_usize(sys::seL4_PageBits),
             Self::LargePage => u32_into_usize(sys::seL4_LargePageBits),
             Self::PageTable => u32_into_usize(sys::seL4_PageTableBits),
             Self::PageDirectory => u32_into_usize(sys::seL4_PageDirBits),
+            #[sel4_cfg(IOMMU)]
+            Self::IOPageTable => u32_into_usize(sys::seL4_IOPageTableBits),
             Self::SeL4Arch(sel4_arch) => sel4_arch.physical_size_bits(),
         }
     }
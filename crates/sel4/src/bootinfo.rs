@@ -4,12 +4,15 @@ use core::slice;
 
 use crate::{
     newtype_methods, sel4_cfg, sys, ASIDControl, ASIDPool, CNode, CPtr, CapType, IPCBuffer,
-    IRQControl, LocalCPtr, Null, VSpace, GRANULE_SIZE, TCB,
+    IRQControl, LocalCPtr, Null, PAddr, VSpace, GRANULE_SIZE, TCB,
 };
 
 #[sel4_cfg(KERNEL_MCS)]
 use crate::SchedControl;
 
+#[sel4_cfg(all(any(ARCH_IA32, ARCH_X86_64), IOMMU))]
+use crate::IOSpace;
+
 /// Corresponds to `seL4_BootInfo`.
 #[derive(Debug)]
 pub struct BootInfo {
@@ -158,6 +161,11 @@ impl BootInfo {
         )
     }
 
+    #[sel4_cfg(all(any(ARCH_IA32, ARCH_X86_64), IOMMU))]
+    pub fn io_space() -> IOSpace {
+        IOSpace::from_bits(sys::seL4_RootCapSlot::seL4_CapIOSpace.try_into().unwrap())
+    }
+
     pub fn init_cspace_cptr(slot: InitCSpaceSlot) -> CPtr {
         CPtr::from_bits(slot.try_into().unwrap())
     }
@@ -182,8 +190,8 @@ pub struct UntypedDesc(sys::seL4_UntypedDesc);
 impl UntypedDesc {
     newtype_methods!(sys::seL4_UntypedDesc);
 
-    pub fn paddr(&self) -> usize {
-        self.inner().paddr.try_into().unwrap()
+    pub fn paddr(&self) -> PAddr {
+        PAddr::from_word(self.inner().paddr)
     }
 
     pub fn size_bits(&self) -> usize {
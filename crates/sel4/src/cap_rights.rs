@@ -35,6 +35,11 @@ impl CapRights {
     pub fn write_only() -> Self {
         CapRightsBuilder::none().write(true).build()
     }
+
+    pub fn without_grant(mut self) -> Self {
+        self.inner_mut().set_capAllowGrant(0);
+        self
+    }
 }
 
 impl From<CapRightsBuilder> for CapRights {
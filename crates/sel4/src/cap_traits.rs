@@ -0,0 +1,45 @@
+//! Marker traits for the invocations which capabilities of a given [`CapType`] support.
+//!
+//! These traits classify capabilities by the type of object they refer to. They say nothing about
+//! the rights of any particular capability, which the kernel checks when the capability is
+//! invoked. They are sealed, so that they are only implemented for the types listed here and in
+//! the architecture-specific modules.
+
+use crate::{cap_type, CapType, FrameType};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+pub(crate) use sealed::Sealed;
+
+/// Capability types which can be retyped into other objects.
+pub trait CanRetype: CapType + Sealed {}
+
+/// Capability types whose objects can be mapped into an address space.
+pub trait CanMap: CapType + Sealed {}
+
+/// Capability types which can carry the grant right.
+pub trait CanGrant: CapType + Sealed {}
+
+impl Sealed for cap_type::Untyped {}
+impl CanRetype for cap_type::Untyped {}
+
+impl Sealed for cap_type::Endpoint {}
+impl CanGrant for cap_type::Endpoint {}
+
+// [`FrameType`] is sealed too, so that only the frame types of the current architecture can be
+// mapped.
+impl<T: FrameType> CanMap for T {}
+
+// Seals each of an architecture's frame types.
+macro_rules! seal_frame_types {
+    ($($(#[$attr:meta])* $t:ident),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl $crate::cap_traits::Sealed for $crate::cap_type::$t {}
+        )*
+    };
+}
+
+pub(crate) use seal_frame_types;
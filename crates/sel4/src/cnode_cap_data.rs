@@ -1,6 +1,14 @@
+use core::fmt;
+use core::result;
+
 use crate::{newtype_methods, sys, Word, WORD_SIZE};
 
 /// Corresponds to `seL4_CNode_CapData`.
+///
+/// A CNode capability with a guard of `guard_size` bits resolves `guard_size` bits of a capability
+/// address against its guard, and then a further `radix_bits` bits, as determined by the size of
+/// the CNode, to index into the CNode. The helpers which take a depth compute guards so that the
+/// number of bits resolved is as desired, for example [`WORD_SIZE`] for a single-level CSpace.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CNodeCapData(sys::seL4_CNode_CapData);
 
@@ -14,12 +22,62 @@ impl CNodeCapData {
         ))
     }
 
+    /// Like [`CNodeCapData::new`], but checks that `guard` fits in `guard_size` bits, and that
+    /// `guard_size` fits in the guard size field.
+    pub fn try_new(guard: Word, guard_size: usize) -> result::Result<Self, CNodeCapDataError> {
+        if guard_size > Self::max_guard_size() {
+            return Err(CNodeCapDataError::GuardSizeTooLarge { guard_size });
+        }
+        if guard_size < WORD_SIZE && guard >> guard_size != 0 {
+            return Err(CNodeCapDataError::GuardTooLarge { guard, guard_size });
+        }
+        Ok(Self::new(guard, guard_size))
+    }
+
+    /// The guard data for a CNode of `radix_bits` which, together with its guard, resolves
+    /// `depth` bits of a capability address. The guard is zero.
+    pub fn for_depth(radix_bits: usize, depth: usize) -> result::Result<Self, CNodeCapDataError> {
+        Self::with_guard_for_depth(0, radix_bits, depth)
+    }
+
+    /// Like [`CNodeCapData::for_depth`], with a guard of `guard`.
+    pub fn with_guard_for_depth(
+        guard: Word,
+        radix_bits: usize,
+        depth: usize,
+    ) -> result::Result<Self, CNodeCapDataError> {
+        if depth > WORD_SIZE || radix_bits > depth {
+            return Err(CNodeCapDataError::InvalidDepth { radix_bits, depth });
+        }
+        Self::try_new(guard, depth - radix_bits)
+    }
+
     pub fn skip(num_bits: usize) -> Self {
         Self::new(0, num_bits)
     }
 
     pub fn skip_high_bits(cnode_size_bits: usize) -> Self {
-        Self::skip(WORD_SIZE - cnode_size_bits)
+        Self::for_depth(cnode_size_bits, WORD_SIZE).unwrap()
+    }
+
+    pub fn guard(&self) -> Word {
+        self.inner().get_guard()
+    }
+
+    pub fn guard_size(&self) -> usize {
+        self.inner().get_guardSize().try_into().unwrap()
+    }
+
+    /// The number of bits of a capability address resolved by a CNode of `radix_bits` with this
+    /// guard data.
+    pub fn depth(&self, radix_bits: usize) -> usize {
+        self.guard_size() + radix_bits
+    }
+
+    /// The largest guard size which can be encoded, which, depending on the configuration, may be
+    /// less than the largest guard size accepted by the kernel.
+    pub fn max_guard_size() -> usize {
+        WORD_SIZE.min((1 << sys::seL4_CNode_CapData::width_of_guardSize()) - 1)
     }
 
     pub fn into_word(self) -> Word {
@@ -28,3 +86,52 @@ impl CNodeCapData {
         arr[0]
     }
 }
+
+impl fmt::Display for CNodeCapData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.guard_size() {
+            0 => write!(f, "no guard"),
+            guard_size => write!(
+                f,
+                "guard 0b{:0width$b} ({} bits)",
+                self.guard(),
+                guard_size,
+                width = guard_size,
+            ),
+        }
+    }
+}
+
+/// Error returned by the checked constructors of [`CNodeCapData`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CNodeCapDataError {
+    GuardTooLarge {
+        guard: Word,
+        guard_size: usize,
+    },
+    GuardSizeTooLarge {
+        guard_size: usize,
+    },
+    /// The depth exceeds [`WORD_SIZE`], or is less than the radix of the CNode.
+    InvalidDepth {
+        radix_bits: usize,
+        depth: usize,
+    },
+}
+
+impl fmt::Display for CNodeCapDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GuardTooLarge { guard, guard_size } => {
+                write!(f, "guard {guard:#x} does not fit in {guard_size} bits")
+            }
+            Self::GuardSizeTooLarge { guard_size } => {
+                write!(f, "guard size {guard_size} is too large")
+            }
+            Self::InvalidDepth { radix_bits, depth } => write!(
+                f,
+                "a CNode of {radix_bits} bits cannot resolve a depth of {depth} bits"
+            ),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Checks, at compile time, that the kernel configuration agrees with the target, and with the
+// requirements declared by dependent crates through this crate's "requires-*" features. Otherwise,
+// such mismatches surface as missing symbols or failed invocations, far from their causes.
+
+use sel4_config::{sel4_cfg_if, sel4_cfg_usize};
+
+const _: () = assert!(
+    sel4_cfg_usize!(WORD_SIZE) == usize::BITS as usize,
+    "the kernel configuration's WORD_SIZE does not match the target's pointer width",
+);
+
+#[cfg(all(feature = "requires-mcs", feature = "requires-non-mcs"))]
+compile_error!(
+    r#"features "requires-mcs" and "requires-non-mcs" of the sel4 crate are both enabled"#
+);
+
+#[cfg(feature = "requires-mcs")]
+sel4_cfg_if! {
+    if #[cfg(not(KERNEL_MCS))] {
+        compile_error!(r#"feature "requires-mcs" of the sel4 crate is enabled, but the kernel configuration does not enable KERNEL_MCS"#);
+    }
+}
+
+#[cfg(feature = "requires-non-mcs")]
+sel4_cfg_if! {
+    if #[cfg(KERNEL_MCS)] {
+        compile_error!(r#"feature "requires-non-mcs" of the sel4 crate is enabled, but the kernel configuration enables KERNEL_MCS"#);
+    }
+}
+
+#[cfg(feature = "requires-hypervisor")]
+sel4_cfg_if! {
+    if #[cfg(any(ARCH_AARCH32, ARCH_AARCH64))] {
+        sel4_cfg_if! {
+            if #[cfg(not(ARM_HYPERVISOR_SUPPORT))] {
+                compile_error!(r#"feature "requires-hypervisor" of the sel4 crate is enabled, but the kernel configuration does not enable ARM_HYPERVISOR_SUPPORT"#);
+            }
+        }
+    } else {
+        compile_error!(r#"feature "requires-hypervisor" of the sel4 crate is only supported on ARM"#);
+    }
+}
@@ -1,5 +1,6 @@
 use core::fmt;
 use core::marker::PhantomData;
+use core::result;
 
 use sel4_config::sel4_cfg;
 
@@ -112,6 +113,10 @@ impl<T: CapType, C> LocalCPtr<T, C> {
     pub fn without_context(self) -> LocalCPtr<T> {
         self.with(NoExplicitInvocationContext::new())
     }
+
+    pub(crate) fn invocation_context(&self) -> &C {
+        &self.invocation_context
+    }
 }
 
 impl<T: CapType> LocalCPtr<T> {
@@ -145,6 +150,10 @@ impl<T: CapType, C> fmt::Debug for LocalCPtr<T, C> {
 // NOTE require 'Copy' for convenience to make up for limitations of automatic trait derivation
 pub trait CapType: Copy {
     const NAME: &'static str;
+
+    /// The value returned by `seL4_DebugCapIdentify` for capabilities of this type, if it is known
+    /// independently of the kernel's architecture and version.
+    const IDENTIFY_TAG: Option<u32> = None;
 }
 
 pub mod cap_type {
@@ -160,37 +169,37 @@ pub mod cap_type {
 
     declare_cap_type! {
         /// Corresponds to `seL4_Untyped`.
-        Untyped
+        Untyped = 2
     }
 
     declare_cap_type! {
         /// Corresponds to the endpoint capability type.
-        Endpoint
+        Endpoint = 4
     }
 
     declare_cap_type! {
         /// Corresponds to the notification capability type.
-        Notification
+        Notification = 6
     }
 
     declare_cap_type! {
         /// Corresponds to `seL4_TCB`.
-        TCB
+        TCB = 12
     }
 
     declare_cap_type! {
         /// Corresponds to `seL4_CNode`.
-        CNode
+        CNode = 10
     }
 
     declare_cap_type! {
         /// Corresponds to `seL4_IRQControl`.
-        IRQControl
+        IRQControl = 14
     }
 
     declare_cap_type! {
         /// Corresponds to `seL4_IRQHandler`.
-        IRQHandler
+        IRQHandler = 16
     }
 
     declare_cap_type! {
@@ -205,7 +214,7 @@ pub mod cap_type {
 
     declare_cap_type! {
         /// Corresponds to the null capability.
-        Null
+        Null = 0
     }
 
     declare_cap_type! {
@@ -213,21 +222,36 @@ pub mod cap_type {
         Unspecified
     }
 
+    /// A capability of type `T` which has been diminished to lack the grant right.
+    ///
+    /// The type only records how the capability was minted. It is the kernel which enforces the
+    /// missing right: for example, it does not transfer capabilities in messages sent through a
+    /// `NoGrant<Endpoint>`.
+    ///
+    /// See [`AbsoluteCPtr::mint_without_grant`](crate::AbsoluteCPtr::mint_without_grant).
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    pub struct NoGrant<T>(core::marker::PhantomData<T>);
+
+    impl<T: crate::CapType> crate::CapType for NoGrant<T> {
+        const NAME: &'static str = "NoGrant";
+        const IDENTIFY_TAG: Option<u32> = T::IDENTIFY_TAG;
+    }
+
     sel4_cfg_if! {
         if #[cfg(KERNEL_MCS)] {
             declare_cap_type! {
                 /// Corresponds to the reply capability type (MCS only).
-                Reply
+                Reply = 8
             }
 
             declare_cap_type! {
                 /// Corresponds to the scheduling context capability type (MCS only).
-                SchedContext
+                SchedContext = 22
             }
 
             declare_cap_type! {
                 /// Corresponds to `seL4_SchedControl`.
-                SchedControl
+                SchedControl = 24
             }
         }
     }
@@ -275,6 +299,48 @@ impl<C> Unspecified<C> {
     pub fn downcast<T: CapType>(self) -> LocalCPtr<T, C> {
         self.cast()
     }
+
+    /// Like [`downcast`](Self::downcast), but, on debug kernels, first uses
+    /// `seL4_DebugCapIdentify` to check that the slot holds a capability of type `T`.
+    ///
+    /// For types whose [`CapType::IDENTIFY_TAG`] is `None`, only the presence of some capability in
+    /// the slot is checked. On non-debug kernels, no check is performed.
+    pub fn try_downcast<T: CapType>(self) -> result::Result<LocalCPtr<T, C>, CapTypeMismatch> {
+        #[sel4_cfg(DEBUG_BUILD)]
+        {
+            let actual_tag = sys::seL4_DebugCapIdentify(self.bits());
+            let ok = match T::IDENTIFY_TAG {
+                Some(tag) => actual_tag == tag,
+                None => Some(actual_tag) != cap_type::Null::IDENTIFY_TAG,
+            };
+            if !ok {
+                return Err(CapTypeMismatch {
+                    expected: T::NAME,
+                    actual_tag,
+                });
+            }
+        }
+        Ok(self.downcast())
+    }
+}
+
+/// Error returned by [`Unspecified::try_downcast`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CapTypeMismatch {
+    /// The [`CapType::NAME`] of the requested type.
+    pub expected: &'static str,
+    /// The value returned by `seL4_DebugCapIdentify`.
+    pub actual_tag: u32,
+}
+
+impl fmt::Display for CapTypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected {} capability, found capability with tag {}",
+            self.expected, self.actual_tag
+        )
+    }
 }
 
 /// A [`CPtrWithDepth`] in a particular [`CNode`].
@@ -21,7 +21,7 @@ macro_rules! newtype_methods {
 macro_rules! declare_cap_type {
     (
         $(#[$outer:meta])*
-        $t:ident
+        $t:ident $(= $identify_tag:literal)?
     ) => {
         $(#[$outer])*
         #[derive(Copy, Clone, Eq, PartialEq)]
@@ -29,6 +29,7 @@ macro_rules! declare_cap_type {
 
         impl $crate::CapType for $t {
             const NAME: &'static str = stringify!($t);
+            $(const IDENTIFY_TAG: Option<u32> = Some($identify_tag);)?
         }
     };
 }
@@ -61,7 +62,22 @@ macro_rules! declare_fault_newtype {
     };
 }
 
+macro_rules! named_registers_method {
+    ($pc:ident, $($reg:ident),* $(,)?) => {
+        /// The name and value of each register, in a fixed order, beginning with the program
+        /// counter.
+        pub fn named_registers(&self) -> impl Iterator<Item = (&'static str, Word)> {
+            [
+                (stringify!($pc), self.inner().$pc),
+                $((stringify!($reg), self.inner().$reg)),*
+            ]
+            .into_iter()
+        }
+    };
+}
+
 pub(crate) use declare_cap_type;
 pub(crate) use declare_fault_newtype;
 pub(crate) use declare_local_cptr_alias;
+pub(crate) use named_registers_method;
 pub(crate) use newtype_methods;
@@ -1,22 +1,57 @@
-use core::mem;
+use core::{fmt, mem, result};
 
-use sel4_config::{sel4_cfg, sel4_cfg_if};
+use sel4_config::{sel4_cfg, sel4_cfg_bool, sel4_cfg_if};
 
 use crate::{
-    local_cptr::*, sys, AbsoluteCPtr, CNodeCapData, CapRights, Error, InvocationContext,
-    ObjectBlueprint, Result, UserContext, Word,
+    cap_type, local_cptr::*, sys, AbsoluteCPtr, CNodeCapData, CPtr, CanGrant, CanRetype, CapRights,
+    Error, InvocationContext, LocalCPtr, ObjectBlueprint, Result, UserContext, Word, WORD_SIZE,
 };
 
 #[sel4_cfg(KERNEL_MCS)]
 use crate::Badge;
 
-#[sel4_cfg(not(KERNEL_MCS))]
-use crate::CPtr;
-
 #[sel4_cfg(KERNEL_MCS)]
 pub type Time = u64;
 
-impl<C: InvocationContext> Untyped<C> {
+#[sel4_cfg(KERNEL_MCS)]
+type FaultEndpoint = Endpoint;
+
+#[sel4_cfg(not(KERNEL_MCS))]
+type FaultEndpoint = CPtr;
+
+/// Error returned by [`TCB::tcb_set_space`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TCBSetSpaceError {
+    /// The fault endpoint cannot be used as a fault handler. Only reported on MCS kernels, where
+    /// the fault endpoint is checked when it is set.
+    InvalidFaultEndpoint,
+    /// The CSpace root, with its guard data, or the VSpace root is not a valid root, or one of the
+    /// thread's current roots is being deleted.
+    InvalidRoot,
+    Other(Error),
+}
+
+impl TCBSetSpaceError {
+    fn from_error(err: Error) -> Self {
+        match err {
+            Error::InvalidCapability if sel4_cfg_bool!(KERNEL_MCS) => Self::InvalidFaultEndpoint,
+            Error::IllegalOperation => Self::InvalidRoot,
+            err => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for TCBSetSpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidFaultEndpoint => write!(f, "invalid fault endpoint"),
+            Self::InvalidRoot => write!(f, "invalid CSpace or VSpace root"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl<T: CanRetype, C: InvocationContext> LocalCPtr<T, C> {
     /// Corresponds to `seL4_Untyped_Retype`.
     pub fn untyped_retype(
         self,
@@ -150,6 +185,34 @@ impl<C: InvocationContext> TCB<C> {
         }
     }
 
+    /// Corresponds to `seL4_TCB_SetSpace`.
+    ///
+    /// On MCS kernels, `fault_ep` is an endpoint capability in the caller's CSpace. Otherwise, it
+    /// is looked up in the thread's new CSpace when a fault occurs.
+    ///
+    /// The kernel only sets a thread's fault endpoint together with its CSpace and VSpace, so to
+    /// move a thread to a new fault endpoint, pass its current roots again. The thread may be
+    /// running.
+    pub fn tcb_set_space(
+        self,
+        fault_ep: FaultEndpoint,
+        cspace_root: CNode,
+        cspace_root_data: CNodeCapData,
+        vspace_root: VSpace,
+    ) -> result::Result<(), TCBSetSpaceError> {
+        Error::wrap(self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer.inner_mut().seL4_TCB_SetSpace(
+                cptr.bits(),
+                fault_ep.bits(),
+                cspace_root.bits(),
+                cspace_root_data.into_word(),
+                vspace_root.bits(),
+                0, /* HACK */
+            )
+        }))
+        .map_err(TCBSetSpaceError::from_error)
+    }
+
     sel4_cfg_if! {
         if #[cfg(KERNEL_MCS)] {
             /// Corresponds to `seL4_TCB_SetSchedParams`.
@@ -251,6 +314,37 @@ impl<C: InvocationContext> SchedControl<C> {
     }
 }
 
+#[sel4_cfg(KERNEL_MCS)]
+impl<C: InvocationContext> SchedContext<C> {
+    /// Corresponds to `seL4_SchedContext_Consumed`.
+    ///
+    /// Returns the time, in microseconds, consumed from this scheduling context since it was last
+    /// queried, either by this invocation or by
+    /// [`sched_context_yield_to`](Self::sched_context_yield_to).
+    pub fn sched_context_consumed(self) -> Result<Time> {
+        let ret = self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer
+                .inner_mut()
+                .seL4_SchedContext_Consumed(cptr.bits())
+        });
+        Error::or(ret.error, ret.consumed)
+    }
+
+    /// Corresponds to `seL4_SchedContext_YieldTo`.
+    ///
+    /// Places the thread bound to this scheduling context at the head of its scheduling queue,
+    /// running it immediately if its priority is at least that of the caller. Returns the time
+    /// consumed, as for [`sched_context_consumed`](Self::sched_context_consumed).
+    pub fn sched_context_yield_to(self) -> Result<Time> {
+        let ret = self.invoke(|cptr, ipc_buffer| {
+            ipc_buffer
+                .inner_mut()
+                .seL4_SchedContext_YieldTo(cptr.bits())
+        });
+        Error::or(ret.error, ret.consumed)
+    }
+}
+
 impl<C: InvocationContext> IRQControl<C> {
     /// Corresponds to `seL4_IRQControl_Get`.
     pub fn irq_control_get(self, irq: Word, dst: &AbsoluteCPtr) -> Result<()> {
@@ -283,6 +377,28 @@ impl<C: InvocationContext> IRQHandler<C> {
         }))
     }
 
+    /// Binds this IRQ handler to `notification` in place of any notification it is already bound
+    /// to, and then acknowledges the IRQ.
+    ///
+    /// This hands an IRQ over to a restarted or replacement driver without re-issuing
+    /// `seL4_IRQControl_Get`. An IRQ which arrived after the previous driver stopped remains
+    /// masked until it is acknowledged, so the acknowledgement ensures that the new driver is
+    /// notified of it. Acknowledging an IRQ which is not pending has no effect.
+    ///
+    /// The core to which the IRQ is delivered is fixed when the IRQ handler capability is created.
+    /// However, threads on any core may wait on `notification`, so moving a driver to another core
+    /// requires no rebinding.
+    pub fn irq_handler_rebind(self, notification: Notification) -> Result<()> {
+        self.invoke(|cptr, ipc_buffer| {
+            Error::wrap(
+                ipc_buffer
+                    .inner_mut()
+                    .seL4_IRQHandler_SetNotification(cptr.bits(), notification.bits()),
+            )?;
+            Error::wrap(ipc_buffer.inner_mut().seL4_IRQHandler_Ack(cptr.bits()))
+        })
+    }
+
     /// Corresponds to `seL4_IRQHandler_Clear`.
     pub fn irq_handler_clear(self) -> Result<()> {
         Error::wrap(
@@ -347,6 +463,27 @@ impl<C: InvocationContext> AbsoluteCPtr<C> {
         }))
     }
 
+    /// Like [`AbsoluteCPtr::mint`], but for a source capability `src` of type `T`, resolved
+    /// relative to the same CNode as `self`, and with the grant right removed from `rights`. The
+    /// returned [`LocalCPtr`], which is marked with the narrowed type, is only meaningful if the
+    /// root of `self` is the current thread's CSpace root.
+    pub fn mint_without_grant<T: CanGrant>(
+        self,
+        src: LocalCPtr<T>,
+        rights: CapRights,
+        badge: Word,
+    ) -> Result<LocalCPtr<cap_type::NoGrant<T>>> {
+        let src = CNode::from_bits(self.root().bits()).relative(src);
+        let path = *self.path();
+        self.mint(&src, rights.without_grant(), badge)?;
+        // The kernel resolves a CPtr from its most significant bit down, and stops at the first
+        // capability which is not a CNode. So, the slot at the end of a path of depth `d` is also
+        // found by the CPtr whose top `d` bits are those of the path. `d` is at least 1, since the
+        // kernel rejected the mint otherwise.
+        let bits = path.bits() << (WORD_SIZE - path.depth());
+        Ok(CPtr::from_bits(bits).cast())
+    }
+
     /// Corresponds to `seL4_CNode_Mutate`.
     pub fn mutate(self, src: &AbsoluteCPtr, badge: Word) -> Result<()> {
         Error::wrap(self.invoke(|cptr, path, ipc_buffer| {
@@ -362,6 +499,20 @@ impl<C: InvocationContext> AbsoluteCPtr<C> {
         }))
     }
 
+    /// Corresponds to `seL4_CNode_Move`.
+    pub fn r#move(self, src: &AbsoluteCPtr) -> Result<()> {
+        Error::wrap(self.invoke(|cptr, path, ipc_buffer| {
+            ipc_buffer.inner_mut().seL4_CNode_Move(
+                cptr.bits(),
+                path.bits(),
+                path.depth_for_kernel(),
+                src.root().bits(),
+                src.path().bits(),
+                src.path().depth_for_kernel(),
+            )
+        }))
+    }
+
     /// Corresponds to `seL4_CNode_SaveCaller`.
     #[sel4_cfg(not(KERNEL_MCS))]
     pub fn save_caller(self) -> Result<()> {
@@ -69,13 +69,13 @@ impl IPCBuffer {
     pub fn recv_slot(&self) -> AbsoluteCPtr {
         let inner = self.inner();
         CNode::from_bits(inner.receiveCNode)
-            .relative_bits_with_depth(inner.receiveIndex, inner.receiveCNode.try_into().unwrap())
+            .relative_bits_with_depth(inner.receiveIndex, inner.receiveDepth.try_into().unwrap())
     }
 
     pub fn set_recv_slot(&mut self, slot: &AbsoluteCPtr) {
         let inner = self.inner_mut();
         inner.receiveCNode = slot.root().bits();
         inner.receiveIndex = slot.path().bits();
-        inner.receiveCNode = slot.path().depth().try_into().unwrap();
+        inner.receiveDepth = slot.path().depth().try_into().unwrap();
     }
 }
@@ -26,6 +26,11 @@
 //! for runtimes where ELF TLS is not supported, but is only safe to use when this crate will only
 //! be running in a single thread.
 //!
+//! Crates which depend on a particular kernel configuration can declare so by enabling the
+//! `"requires-mcs"`, `"requires-non-mcs"`, or `"requires-hypervisor"` features, which cause a
+//! compile-time error with a clear message when the kernel configuration does not match.
+//! Independently, this crate checks that the configured `WORD_SIZE` matches the target.
+//!
 //! ### Building
 //!
 //! This crate and its dependencies depend, at build time, on the libsel4 headers. The location of
@@ -54,7 +59,9 @@ pub use sel4_sys as sys;
 mod arch;
 mod bootinfo;
 mod cap_rights;
+mod cap_traits;
 mod cnode_cap_data;
+mod config_checks;
 mod const_helpers;
 mod cptr;
 mod error;
@@ -64,30 +71,37 @@ mod invocations;
 mod ipc_buffer;
 mod message_info;
 mod object;
+mod paddr;
 mod reply_authority;
 mod syscalls;
-mod vspace;
+mod user_context_diff;
 
 pub mod fault;
+pub mod vspace;
 
 pub use bootinfo::{BootInfo, BootInfoExtra, BootInfoExtraId, InitCSpaceSlot, UntypedDesc};
 pub use cap_rights::{CapRights, CapRightsBuilder};
-pub use cnode_cap_data::CNodeCapData;
+pub use cap_traits::{CanGrant, CanMap, CanRetype};
+pub use cnode_cap_data::{CNodeCapData, CNodeCapDataError};
 pub use cptr::{
-    cap_type, local_cptr, AbsoluteCPtr, CPtr, CPtrBits, CPtrWithDepth, CapType, HasCPtrWithDepth,
-    LocalCPtr,
+    cap_type, local_cptr, AbsoluteCPtr, CPtr, CPtrBits, CPtrWithDepth, CapType, CapTypeMismatch,
+    HasCPtrWithDepth, LocalCPtr,
 };
 pub use error::{Error, Result};
 pub use invocation_context::{
     ExplicitInvocationContext, InvocationContext, NoExplicitInvocationContext, NoInvocationContext,
 };
+pub use invocations::TCBSetSpaceError;
 pub use ipc_buffer::IPCBuffer;
 pub use message_info::{MessageInfo, MessageInfoBuilder};
 pub use object::{ObjectBlueprint, ObjectType};
+pub use paddr::PAddr;
 pub use reply_authority::{ConveysReplyAuthority, ReplyAuthority};
 pub use syscalls::{
-    r#yield, Badge, CallWithMRs, FastMessages, IPCCapType, RecvWithMRs, NUM_MESSAGE_REGISTERS,
+    r#yield, Badge, CallWithMRs, EndpointCapType, FastMessages, IPCCapType, RecvWithMRs,
+    NUM_MESSAGE_REGISTERS,
 };
+pub use user_context_diff::{NoSymbols, SymbolTable, Symbolize, UserContextDiff};
 pub use vspace::{FrameType, GRANULE_SIZE};
 
 sel4_cfg_if! {
@@ -108,7 +122,8 @@ pub use local_cptr::*;
 pub use fault::*;
 
 pub(crate) use helper_macros::{
-    declare_cap_type, declare_fault_newtype, declare_local_cptr_alias, newtype_methods,
+    declare_cap_type, declare_fault_newtype, declare_local_cptr_alias, named_registers_method,
+    newtype_methods,
 };
 
 sel4_cfg_if! {
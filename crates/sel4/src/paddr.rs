@@ -0,0 +1,125 @@
+use core::fmt;
+use core::ops::{Add, AddAssign, Sub};
+
+use crate::{FrameSize, Word};
+
+/// A physical address.
+///
+/// This type exists to keep physical addresses from being confused with virtual addresses, which
+/// are represented as plain `usize`s throughout this crate.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PAddr(usize);
+
+impl PAddr {
+    pub const fn new(addr: usize) -> Self {
+        Self(addr)
+    }
+
+    pub const fn addr(self) -> usize {
+        self.0
+    }
+
+    pub fn from_word(word: Word) -> Self {
+        Self::new(word.try_into().unwrap())
+    }
+
+    pub fn to_word(self) -> Word {
+        self.addr().try_into().unwrap()
+    }
+
+    pub const fn is_aligned_to(self, align_bits: usize) -> bool {
+        self.0 & mask(align_bits) == 0
+    }
+
+    pub const fn is_aligned_to_frame(self, frame_size: FrameSize) -> bool {
+        self.is_aligned_to(frame_size.bits())
+    }
+
+    pub const fn align_down(self, align_bits: usize) -> Self {
+        Self(self.0 & !mask(align_bits))
+    }
+
+    pub const fn align_up(self, align_bits: usize) -> Option<Self> {
+        match self.0.checked_add(mask(align_bits)) {
+            Some(addr) => Some(Self(addr).align_down(align_bits)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_add(self, offset: usize) -> Option<Self> {
+        match self.0.checked_add(offset) {
+            Some(addr) => Some(Self(addr)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, offset: usize) -> Option<Self> {
+        match self.0.checked_sub(offset) {
+            Some(addr) => Some(Self(addr)),
+            None => None,
+        }
+    }
+
+    /// The offset of `self` from `base`, if `self` is not below `base`.
+    pub const fn offset_from(self, base: Self) -> Option<usize> {
+        self.0.checked_sub(base.0)
+    }
+}
+
+const fn mask(bits: usize) -> usize {
+    // `1 << usize::BITS` would overflow.
+    if bits >= usize::BITS as usize {
+        usize::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+impl Add<usize> for PAddr {
+    type Output = Self;
+
+    fn add(self, rhs: usize) -> Self::Output {
+        self.checked_add(rhs).unwrap()
+    }
+}
+
+impl AddAssign<usize> for PAddr {
+    fn add_assign(&mut self, rhs: usize) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<usize> for PAddr {
+    type Output = Self;
+
+    fn sub(self, rhs: usize) -> Self::Output {
+        self.checked_sub(rhs).unwrap()
+    }
+}
+
+impl Sub for PAddr {
+    type Output = usize;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.offset_from(rhs).unwrap()
+    }
+}
+
+impl fmt::Debug for PAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PAddr({:#x})", self.0)
+    }
+}
+
+impl fmt::LowerHex for PAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for PAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
@@ -3,7 +3,7 @@ use core::array;
 use sel4_config::{sel4_cfg, sel4_cfg_if};
 
 use crate::{
-    cap_type, const_helpers::u32_into_usize, sys, CapType, ConveysReplyAuthority, Endpoint,
+    cap_type, const_helpers::u32_into_usize, sys, CapType, ConveysReplyAuthority,
     InvocationContext, LocalCPtr, MessageInfo, Notification, Word, NUM_FAST_MESSAGE_REGISTERS,
 };
 
@@ -22,9 +22,18 @@ impl IPCCapType for cap_type::Notification {}
 
 impl IPCCapType for cap_type::Endpoint {}
 
+impl IPCCapType for cap_type::NoGrant<cap_type::Endpoint> {}
+
 // HACK
 impl IPCCapType for cap_type::Unspecified {}
 
+/// Marks the types of endpoint capabilities, with or without the grant right.
+pub trait EndpointCapType: IPCCapType {}
+
+impl EndpointCapType for cap_type::Endpoint {}
+
+impl EndpointCapType for cap_type::NoGrant<cap_type::Endpoint> {}
+
 sel4_cfg_if! {
     if #[cfg(KERNEL_MCS)] {
         pub type WaitMessageInfo = MessageInfo;
@@ -41,7 +50,7 @@ sel4_cfg_if! {
     }
 }
 
-impl<C: InvocationContext> Endpoint<C> {
+impl<E: EndpointCapType, C: InvocationContext> LocalCPtr<E, C> {
     /// Corresponds to `seL4_Send`.
     pub fn send(self, info: MessageInfo) {
         self.invoke(|cptr, ipc_buffer| {
@@ -221,14 +230,14 @@ pub fn r#yield() {
 
 const UNUSED_FOR_IN: Word = 0;
 
-/// The result of [`Endpoint::recv_with_mrs`].
+/// The result of [`recv_with_mrs`](LocalCPtr::recv_with_mrs).
 pub struct RecvWithMRs {
     pub info: MessageInfo,
     pub badge: Badge,
     pub msg: [Word; NUM_FAST_MESSAGE_REGISTERS],
 }
 
-/// The result of [`Endpoint::call_with_mrs`].
+/// The result of [`call_with_mrs`](LocalCPtr::call_with_mrs).
 pub struct CallWithMRs {
     pub info: MessageInfo,
     pub msg: [Word; NUM_FAST_MESSAGE_REGISTERS],
@@ -0,0 +1,119 @@
+use core::fmt;
+
+use crate::{UserContext, Word};
+
+/// Resolves addresses to symbols, for annotating program counters in [`UserContextDiff`].
+pub trait Symbolize {
+    /// Returns the name of the symbol containing `addr`, and the offset of `addr` within it.
+    fn symbolize(&self, addr: Word) -> Option<(&str, Word)>;
+}
+
+/// Symbolizes nothing.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoSymbols;
+
+impl Symbolize for NoSymbols {
+    fn symbolize(&self, _addr: Word) -> Option<(&str, Word)> {
+        None
+    }
+}
+
+/// A symbol table consisting of `(start address, name)` pairs, sorted by start address. Each
+/// symbol is taken to extend to the start of the next.
+#[derive(Copy, Clone, Debug)]
+pub struct SymbolTable<'a>(pub &'a [(Word, &'a str)]);
+
+impl Symbolize for SymbolTable<'_> {
+    fn symbolize(&self, addr: Word) -> Option<(&str, Word)> {
+        let i = self.0.partition_point(|(start, _)| *start <= addr);
+        let (start, name) = self.0.get(i.checked_sub(1)?)?;
+        Some((*name, addr - start))
+    }
+}
+
+/// Renders the registers of two snapshots of a thread's [`UserContext`], marking those which
+/// differ. Created by [`UserContext::diff`].
+pub struct UserContextDiff<'a, S = NoSymbols> {
+    before: &'a UserContext,
+    after: &'a UserContext,
+    symbols: S,
+    changed_only: bool,
+}
+
+impl UserContext {
+    pub fn diff<'a>(&'a self, after: &'a UserContext) -> UserContextDiff<'a> {
+        UserContextDiff {
+            before: self,
+            after,
+            symbols: NoSymbols,
+            changed_only: false,
+        }
+    }
+}
+
+impl<'a, S> UserContextDiff<'a, S> {
+    /// Annotates the program counter with symbols from `symbols`.
+    pub fn with_symbols<T: Symbolize>(self, symbols: T) -> UserContextDiff<'a, T> {
+        UserContextDiff {
+            before: self.before,
+            after: self.after,
+            symbols,
+            changed_only: self.changed_only,
+        }
+    }
+
+    /// Omits registers which did not change.
+    pub fn changed_only(mut self, changed_only: bool) -> Self {
+        self.changed_only = changed_only;
+        self
+    }
+
+    /// Returns the names of the registers which changed.
+    pub fn changed(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.before
+            .named_registers()
+            .zip(self.after.named_registers())
+            .filter(|((_, before), (_, after))| before != after)
+            .map(|((name, _), _)| name)
+    }
+}
+
+const NAME_WIDTH: usize = 11;
+const VALUE_WIDTH: usize = 2 + 2 * core::mem::size_of::<Word>();
+
+impl<S: Symbolize> UserContextDiff<'_, S> {
+    fn fmt_value(&self, f: &mut fmt::Formatter, is_pc: bool, value: Word) -> fmt::Result {
+        write!(f, "{value:#0VALUE_WIDTH$x}")?;
+        if is_pc {
+            if let Some((name, offset)) = self.symbols.symbolize(value) {
+                write!(f, " <{name}+{offset:#x}>")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Symbolize> fmt::Display for UserContextDiff<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, ((name, before), (_, after))) in self
+            .before
+            .named_registers()
+            .zip(self.after.named_registers())
+            .enumerate()
+        {
+            let changed = before != after;
+            if self.changed_only && !changed {
+                continue;
+            }
+            let is_pc = i == 0;
+            write!(f, "{} {name:NAME_WIDTH$} ", if changed { '*' } else { ' ' })?;
+            self.fmt_value(f, is_pc, before)?;
+            if changed {
+                write!(f, " -> ")?;
+                self.fmt_value(f, is_pc, after)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{cap_type, CapType, FrameSize};
+//! Frame sizes, and a high-level interface for mapping frames into a VSpace.
+
+use crate::{
+    arch::PagingStructure, cap_traits::Sealed, cap_type, local_cptr::*, sys, CapRights, CapType,
+    Error, FrameSize, InvocationContext, LocalCPtr, NoExplicitInvocationContext, ObjectBlueprint,
+    Result, VMAttributes,
+};
 
 /// The smallest [`FrameSize`].
 pub const GRANULE_SIZE: FrameSize = cap_type::Granule::FRAME_SIZE;
@@ -13,6 +19,113 @@ impl FrameSize {
     }
 }
 
-pub trait FrameType: CapType {
+/// Capability types of the current architecture's frames. This trait is sealed.
+pub trait FrameType: CapType + Sealed {
     const FRAME_SIZE: FrameSize;
 }
+
+/// A source of objects for the paging structures which [`VSpace::map_frame`] creates.
+pub trait PagingStructureAllocator {
+    /// Retypes an object of the given blueprint into a fresh slot in the current CSpace.
+    fn allocate(&mut self, blueprint: ObjectBlueprint) -> Result<Unspecified>;
+}
+
+impl<A: PagingStructureAllocator + ?Sized> PagingStructureAllocator for &mut A {
+    fn allocate(&mut self, blueprint: ObjectBlueprint) -> Result<Unspecified> {
+        A::allocate(self, blueprint)
+    }
+}
+
+/// A handle to a [`cap_type::VSpace`] which creates the paging structures beneath its root as
+/// they are needed.
+///
+/// Intermediate paging structures are allocated with `A`, and are never freed. If mapping a
+/// freshly allocated paging structure fails, then that object is leaked.
+pub struct VSpace<A, C = NoExplicitInvocationContext> {
+    root: crate::VSpace<C>,
+    allocator: A,
+}
+
+impl<A, C> VSpace<A, C> {
+    pub fn new(root: crate::VSpace<C>, allocator: A) -> Self {
+        Self { root, allocator }
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+
+    pub fn allocator_mut(&mut self) -> &mut A {
+        &mut self.allocator
+    }
+
+    pub fn into_parts(self) -> (crate::VSpace<C>, A) {
+        (self.root, self.allocator)
+    }
+}
+
+impl<A, C: Clone> VSpace<A, C> {
+    pub fn root(&self) -> crate::VSpace<C> {
+        self.root.clone()
+    }
+}
+
+impl<A: PagingStructureAllocator, C: InvocationContext + Clone> VSpace<A, C> {
+    /// Maps `frame` at `vaddr`, first creating any missing paging structures beneath the root.
+    ///
+    /// `attrs` are also used when mapping paging structures.
+    pub fn map_frame<T: FrameType>(
+        &mut self,
+        frame: LocalCPtr<T>,
+        vaddr: usize,
+        rights: CapRights,
+        attrs: VMAttributes,
+    ) -> Result<()> {
+        loop {
+            let err = match frame.with(self.context()).frame_map(
+                self.root.clone().without_context(),
+                vaddr,
+                rights.clone(),
+                attrs,
+            ) {
+                Err(err @ Error::FailedLookup) => err,
+                res => return res,
+            };
+            let Some(structure) = PagingStructure::from_span_bits(self.failed_lookup_span_bits())
+            else {
+                return Err(err);
+            };
+            self.map_paging_structure(structure, vaddr, attrs)?;
+        }
+    }
+
+    fn map_paging_structure(
+        &mut self,
+        structure: PagingStructure,
+        vaddr: usize,
+        attrs: VMAttributes,
+    ) -> Result<()> {
+        let cap = self.allocator.allocate(structure.blueprint())?;
+        structure.map(
+            cap.with(self.context()),
+            self.root.clone().without_context(),
+            vaddr,
+            attrs,
+        )
+    }
+
+    // After an `Error::FailedLookup` from a mapping invocation, the kernel reports the number of
+    // bits of the virtual address which remained to be translated, which is the span of the
+    // missing paging structure.
+    fn failed_lookup_span_bits(&self) -> usize {
+        self.root.clone().invoke(|_cptr, ipc_buffer| {
+            ipc_buffer.msg_regs()[usize::try_from(sys::SEL4_MAPPING_LOOKUP_LEVEL).unwrap()]
+                .try_into()
+                .unwrap()
+        })
+    }
+
+    fn context(&self) -> C {
+        self.root.invocation_context().clone()
+    }
+}
@@ -49,11 +49,15 @@ pub fn generate_rust(
     }
 
     {
-        // HACK for risc64imac (already handled in upstream bindgen for riscv64gc)
+        // HACK for risc64imac and riscv32imac (already handled in upstream bindgen for riscv64gc
+        // and riscv32i)
         let target = env::var("TARGET").unwrap();
         if let Some(rest) = target.strip_prefix("riscv64imac-") {
             builder = builder.clang_arg(format!("--target=riscv64-{}", rest));
         }
+        if let Some(rest) = target.strip_prefix("riscv32imac-") {
+            builder = builder.clang_arg(format!("--target=riscv32-{}", rest));
+        }
     }
 
     builder
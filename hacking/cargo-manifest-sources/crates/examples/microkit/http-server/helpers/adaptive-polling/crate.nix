@@ -0,0 +1,8 @@
+{ mk, serdeWith }:
+
+mk {
+  package.name = "microkit-http-server-example-adaptive-polling";
+  dependencies = {
+    serde = serdeWith [ "derive" ];
+  };
+}
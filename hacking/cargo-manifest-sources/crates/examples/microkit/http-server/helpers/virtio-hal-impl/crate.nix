@@ -8,6 +8,7 @@ mk {
     virtio-drivers = virtioDriversWith [];
   };
   nix.local.dependencies = with localCrates; [
+    sel4
     sel4-sync
     sel4-immediate-sync-once-cell
     sel4-externally-shared
@@ -0,0 +1,5 @@
+{ mk }:
+
+mk {
+  package.name = "microkit-http-server-example-load-generator-core";
+}
@@ -0,0 +1,16 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "microkit-http-server-example-load-generator";
+  dependencies = {
+    sel4-microkit = { default-features = false; features = [ "alloc" ]; };
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-microkit
+    sel4-microkit-message
+    microkit-http-server-example-load-generator-core
+    microkit-http-server-example-sp804-driver-interface-types
+  ];
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "sel4" ];
+}
@@ -1,4 +1,4 @@
-{ mk, localCrates, versions, smoltcpWith, mbedtlsWith }:
+{ mk, localCrates, versions, serdeWith, smoltcpWith, mbedtlsWith }:
 
 mk {
   package.name = "microkit-http-server-example-server-core";
@@ -16,6 +16,13 @@ mk {
 
     httparse = { version = "1.8.0"; default-features = false; };
 
+    serde = serdeWith [ "derive" ];
+
+    serde-json-core = {
+      version = versions.serde-json-core;
+      default-features = false;
+    };
+
     mbedtls = mbedtlsWith [];
   };
   nix.local.dependencies = with localCrates; [
@@ -23,6 +30,7 @@ mk {
     sel4-async-network
     sel4-async-network-mbedtls
     sel4-async-timers
+    sel4-async-tmpfs
     sel4-panicking-env
     sel4-async-block-io
     sel4-async-block-io-cpiofs
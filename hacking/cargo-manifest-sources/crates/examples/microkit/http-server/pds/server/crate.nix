@@ -16,8 +16,6 @@ mk {
 
     smoltcp = smoltcpWith [];
 
-    async-unsync = { version = "0.2.2"; default-features = false; };
-
     sel4-newlib = {
       features = [
         "nosys"
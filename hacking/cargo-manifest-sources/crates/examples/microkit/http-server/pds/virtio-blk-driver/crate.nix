@@ -12,6 +12,7 @@ mk {
   };
   nix.local.dependencies = with localCrates; [
     sel4-microkit
+    sel4-microkit-message
     sel4
     sel4-sync
     sel4-logging
@@ -20,7 +21,11 @@ mk {
     sel4-shared-ring-buffer
     sel4-shared-ring-buffer-block-io-types
     sel4-bounce-buffer-allocator
+    sel4-virtio-mmio
+    sel4-virtio-batching
 
     microkit-http-server-example-virtio-hal-impl
+    microkit-http-server-example-virtio-blk-driver-interface-types
+    microkit-http-server-example-adaptive-polling
   ];
 }
@@ -0,0 +1,11 @@
+{ mk, localCrates, serdeWith }:
+
+mk {
+  package.name = "microkit-http-server-example-virtio-blk-driver-interface-types";
+  dependencies = {
+    serde = serdeWith [];
+  };
+  nix.local.dependencies = with localCrates; [
+    microkit-http-server-example-adaptive-polling
+  ];
+}
@@ -20,8 +20,11 @@ mk {
     sel4-externally-shared
     sel4-shared-ring-buffer
     sel4-bounce-buffer-allocator
+    sel4-virtio-mmio
+    sel4-virtio-net
 
     microkit-http-server-example-virtio-hal-impl
+    microkit-http-server-example-adaptive-polling
     microkit-http-server-example-virtio-net-driver-interface-types
   ];
 }
@@ -7,5 +7,6 @@ mk {
   };
   nix.local.dependencies = with localCrates; [
     # sel4-microkit-message-types
+    microkit-http-server-example-adaptive-polling
   ];
 }
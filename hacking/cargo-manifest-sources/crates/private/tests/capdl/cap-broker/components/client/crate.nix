@@ -0,0 +1,18 @@
+{ mk, localCrates, serdeWith }:
+
+mk {
+  package.name = "tests-capdl-cap-broker-components-client";
+  dependencies = {
+    serde = serdeWith [ "alloc" "derive" ];
+    sel4-simple-task-rpc.features = [ "postcard" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4
+    sel4-simple-task-runtime
+    sel4-simple-task-config-types
+    sel4-simple-task-rpc
+    tests-capdl-cap-broker-interface-types
+  ];
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "sel4" ];
+}
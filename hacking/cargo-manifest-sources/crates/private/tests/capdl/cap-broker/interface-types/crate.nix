@@ -0,0 +1,8 @@
+{ mk, serdeWith }:
+
+mk {
+  package.name = "tests-capdl-cap-broker-interface-types";
+  dependencies = {
+    serde = serdeWith [ "alloc" "derive" ];
+  };
+}
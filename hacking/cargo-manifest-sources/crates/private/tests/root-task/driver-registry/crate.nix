@@ -0,0 +1,13 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "tests-root-task-driver-registry";
+  nix.local.dependencies = with localCrates; [
+    sel4
+    sel4-device-tree
+    sel4-driver-registry
+    sel4-root-task
+  ];
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "sel4" ];
+}
@@ -0,0 +1,14 @@
+{ mk, localCrates, versions }:
+
+mk {
+  package.name = "sel4-async-9p";
+  dependencies = {
+    inherit (versions) log;
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-fs
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+  ];
+}
@@ -4,6 +4,7 @@ mk {
   package.name = "sel4-async-block-io-cpiofs";
   dependencies = rec {
     inherit (versions) log zerocopy;
+    hashbrown = "0.13.2";
     hex = { version = "0.4.3"; default-features = false; };
     lru = "0.10.0";
     futures = {
@@ -16,5 +17,6 @@ mk {
   };
   nix.local.dependencies = with localCrates; [
     sel4-async-block-io
+    sel4-async-fs
   ];
 }
@@ -1,4 +1,4 @@
-{ mk, versions }:
+{ mk, localCrates, versions }:
 
 mk {
   package.name = "sel4-async-block-io";
@@ -18,4 +18,10 @@ mk {
     alloc = [ "lru" "futures" ];
     default = [ "alloc" ];
   };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-copy-engine
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-single-threaded-executor
+  ];
 }
@@ -0,0 +1,13 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-block-io-fat";
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io
+    sel4-async-fs
+    sel4-async-sync
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+  ];
+}
@@ -0,0 +1,14 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-block-io-verity-cli";
+  dependencies = {
+    clap = "3.2.23";
+    hex = "0.4.3";
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io-verity
+  ];
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "unix" ];
+}
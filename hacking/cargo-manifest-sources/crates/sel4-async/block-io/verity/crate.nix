@@ -0,0 +1,19 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-block-io-verity";
+  dependencies = {
+    sel4-async-block-io.default-features = false;
+    sha2 = { version = "0.10.6"; default-features = false; };
+  };
+  features = {
+    alloc = [];
+    default = [ "alloc" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+  ];
+}
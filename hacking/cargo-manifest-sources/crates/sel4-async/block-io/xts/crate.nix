@@ -0,0 +1,18 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-block-io-xts";
+  dependencies = {
+    aes = "0.8.3";
+    sel4-async-block-io.default-features = false;
+  };
+  dev-dependencies = {
+    hex = "0.4.3";
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+  ];
+}
@@ -0,0 +1,5 @@
+{ mk }:
+
+mk {
+  package.name = "sel4-async-copy-engine";
+}
@@ -0,0 +1,9 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-fs";
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+    sel4-async-tmpfs
+  ];
+}
@@ -0,0 +1,9 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-ipc";
+  nix.local.dependencies = with localCrates; [
+    sel4
+  ];
+  nix.meta.requirements = [ "sel4" ];
+}
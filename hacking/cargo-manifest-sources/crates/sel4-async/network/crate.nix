@@ -1,4 +1,4 @@
-{ mk, versions, smoltcpWith }:
+{ mk, localCrates, versions, smoltcpWith }:
 
 mk {
   package.name = "sel4-async-network";
@@ -14,7 +14,30 @@ mk {
     smoltcp = smoltcpWith [
       "async"
       "alloc"
+      "socket-udp"
       # "verbose"
     ];
   };
+  dev-dependencies = {
+    smoltcp = smoltcpWith [
+      "medium-ethernet"
+    ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-timers
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-single-threaded-executor
+  ];
+  features = {
+    ipv4-fragmentation = [
+      "smoltcp/proto-ipv4-fragmentation"
+    ];
+    # Enlarges the buffers for fragmentation and reassembly. Does not raise the MTU of any link.
+    large-fragmented-datagrams = [
+      "ipv4-fragmentation"
+      "smoltcp/fragmentation-buffer-size-16384"
+      "smoltcp/reassembly-buffer-size-16384"
+    ];
+  };
 }
@@ -0,0 +1,15 @@
+{ mk, localCrates, versions, smoltcpWith }:
+
+mk {
+  package.name = "sel4-async-network-http-client";
+  dependencies = {
+    inherit (versions) log;
+    httparse = { version = "1.8.0"; default-features = false; };
+    smoltcp = smoltcpWith [];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-network
+    sel4-async-network-tls
+    sel4-async-timers
+  ];
+}
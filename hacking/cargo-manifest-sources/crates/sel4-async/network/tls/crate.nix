@@ -0,0 +1,17 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-network-tls";
+  dependencies = {
+    rustls = {
+      version = "0.23.12";
+      default-features = false;
+      features = [
+        "tls12"
+      ];
+    };
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-network
+  ];
+}
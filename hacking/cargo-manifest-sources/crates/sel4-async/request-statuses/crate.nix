@@ -1,5 +1,8 @@
-{ mk }:
+{ mk, versions }:
 
 mk {
   package.name = "sel4-async-request-statuses";
+  dev-dependencies = {
+    inherit (versions) futures;
+  };
 }
@@ -0,0 +1,31 @@
+{ mk, localCrates, versions }:
+
+mk {
+  package.name = "sel4-async-shell";
+  dependencies = {
+    futures = {
+      version = versions.futures;
+      default-features = false;
+      features = [
+        "alloc"
+      ];
+    };
+    log = { version = versions.log; optional = true; };
+    sel4-async-block-io.optional = true;
+    sel4-async-network.optional = true;
+    sel4-async-timers.optional = true;
+  };
+  features = {
+    block-io = [ "sel4-async-block-io" ];
+    network = [ "sel4-async-network" ];
+    timers = [ "sel4-async-timers" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io
+    sel4-async-network
+    sel4-async-timers
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-single-threaded-executor
+  ];
+}
@@ -7,9 +7,10 @@ mk {
     futures = {
       version = versions.futures;
       default-features = false;
-      features = [
-        "alloc"
-      ];
     };
   };
+  features = {
+    alloc = [ "futures/alloc" ];
+    default = [ "alloc" ];
+  };
 }
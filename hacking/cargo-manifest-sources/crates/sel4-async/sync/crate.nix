@@ -0,0 +1,17 @@
+{ mk, localCrates, versions }:
+
+mk {
+  package.name = "sel4-async-sync";
+  dev-dependencies = {
+    futures = {
+      version = versions.futures;
+      default-features = false;
+      features = [
+        "alloc"
+      ];
+    };
+  };
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-single-threaded-executor
+  ];
+}
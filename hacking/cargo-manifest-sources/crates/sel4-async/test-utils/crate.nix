@@ -0,0 +1,12 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-test-utils";
+  dependencies = {
+    sel4-async-block-io.default-features = false;
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-block-io
+    sel4-async-single-threaded-executor
+  ];
+}
@@ -0,0 +1,15 @@
+{ mk, versions }:
+
+mk {
+  package.name = "sel4-async-thread-pool";
+  dependencies = {
+    futures = {
+      version = versions.futures;
+      default-features = false;
+      features = [
+        "alloc"
+      ];
+    };
+    spin = "0.9.4";
+  };
+}
@@ -1,4 +1,4 @@
-{ mk, versions, smoltcpWith }:
+{ mk, localCrates, versions, smoltcpWith }:
 
 mk {
   package.name = "sel4-async-timers";
@@ -13,4 +13,7 @@ mk {
     };
     smoltcp = smoltcpWith [];
   };
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-single-threaded-executor
+  ];
 }
@@ -0,0 +1,11 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-async-tmpfs";
+  nix.local.dependencies = with localCrates; [
+    sel4-async-fs
+  ];
+  nix.local.dev-dependencies = with localCrates; [
+    sel4-async-test-utils
+  ];
+}
@@ -0,0 +1,8 @@
+{ mk }:
+
+mk {
+  package.name = "sel4-device-tree";
+  dependencies = {
+    fdt = "0.1.4";
+  };
+}
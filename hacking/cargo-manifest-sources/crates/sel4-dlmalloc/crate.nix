@@ -5,6 +5,9 @@ mk {
   dependencies = {
     dlmalloc = "0.2.3";
   };
+  features = {
+    profiling = [];
+  };
   nix.local.dependencies = with localCrates; [
     sel4-sync
   ];
@@ -0,0 +1,8 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-driver-registry";
+  nix.local.dependencies = with localCrates; [
+    sel4-device-tree
+  ];
+}
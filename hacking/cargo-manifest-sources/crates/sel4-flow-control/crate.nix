@@ -0,0 +1,11 @@
+{ mk, localCrates }:
+
+mk {
+  package.name = "sel4-flow-control";
+  dependencies = {
+    sel4-externally-shared.features = [ "unstable" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-externally-shared
+  ];
+}
@@ -0,0 +1,20 @@
+{ mk, localCrates, versions, smoltcpWith }:
+
+mk {
+  package.name = "sel4-microkit-async";
+  dependencies = {
+    futures = {
+      version = versions.futures;
+      default-features = false;
+      features = [
+        "alloc"
+      ];
+    };
+    smoltcp = smoltcpWith [];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-microkit
+    sel4-async-single-threaded-executor
+    sel4-async-timers
+  ];
+}
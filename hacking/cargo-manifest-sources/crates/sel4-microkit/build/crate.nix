@@ -0,0 +1,12 @@
+{ mk, versions }:
+
+mk {
+  package.name = "sel4-microkit-build";
+  dependencies = {
+    xmltree = "0.10.3";
+    inherit (versions)
+      anyhow
+    ;
+  };
+  nix.meta.requirements = [ "linux" ];
+}
@@ -6,6 +6,7 @@ mk {
     inherit (versions) cfg-if;
     sel4-runtime-common.features = [ "tls" "unwinding" "start" "static-heap" ];
     sel4.features = [ "single-threaded" ];
+    sel4-externally-shared.features = [ "unstable" ];
   };
   features = {
     default = [
@@ -15,6 +16,7 @@ mk {
       "default"
       "alloc"
     ];
+    release-fast = [];
     unwinding = [
       "sel4-panicking/unwinding"
     ];
@@ -29,6 +31,7 @@ mk {
     sel4-runtime-common
     sel4-immediate-sync-once-cell
     sel4-immutable-cell
+    sel4-logging
     sel4-microkit-macros
     sel4-externally-shared
   ];
@@ -0,0 +1,14 @@
+{ mk, versions }:
+
+mk {
+  package.name = "sel4-microkit-inject-config";
+  dependencies = {
+    object = { version = versions.object; features = [ "all" ]; };
+    clap = "3.2.23";
+    inherit (versions)
+      anyhow
+    ;
+  };
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "unix" ];
+}
@@ -1,18 +1,34 @@
-{ mk, localCrates, serdeWith }:
+{ mk, localCrates, versions, serdeWith, postcardWith }:
 
 mk {
   package.name = "sel4-microkit-message";
   nix.local.dependencies = with localCrates; [
     sel4-microkit
     sel4-microkit-message-types
+    sel4-externally-shared
   ];
   dependencies = {
+    minicbor = {
+      version = versions.minicbor;
+      default-features = false;
+      optional = true;
+    };
     serde = serdeWith [] // {
       optional = true;
     };
+    postcard = postcardWith [] // {
+      optional = true;
+    };
+    sel4-externally-shared = {
+      features = [ "unstable" "alloc" ];
+      optional = true;
+    };
   };
   features = {
+    cbor = [ "dep:minicbor" "sel4-microkit-message-types/cbor" ];
     default = [ "postcard" ];
-    postcard = [ "dep:serde" "sel4-microkit-message-types/postcard" ];
+    json = [ "dep:serde" "sel4-microkit-message-types/json" ];
+    overflow = [ "postcard" "postcard/alloc" "dep:sel4-externally-shared" ];
+    postcard = [ "dep:postcard" "dep:serde" "sel4-microkit-message-types/postcard" ];
   };
 }
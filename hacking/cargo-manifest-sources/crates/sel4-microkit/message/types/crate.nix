@@ -4,16 +4,29 @@ mk {
   package.name = "sel4-microkit-message-types";
   dependencies = {
     inherit (versions) zerocopy;
+    minicbor = {
+      version = versions.minicbor;
+      default-features = false;
+      features = [ "derive" ];
+      optional = true;
+    };
     num_enum = { version = versions.num_enum; default-features = false; };
     serde = serdeWith [] // {
       optional = true;
     };
+    serde-json-core = {
+      version = versions.serde-json-core;
+      default-features = false;
+      optional = true;
+    };
     postcard = postcardWith [] // {
       optional = true;
     };
   };
   features = {
+    cbor = [ "dep:minicbor" ];
     default = [ "postcard" ];
+    json = [ "dep:serde-json-core" "dep:serde" ];
     postcard = [ "dep:postcard" "dep:serde" ];
   };
 }
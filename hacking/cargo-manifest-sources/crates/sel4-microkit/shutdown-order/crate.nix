@@ -0,0 +1,14 @@
+{ mk, versions }:
+
+mk {
+  package.name = "sel4-microkit-shutdown-order";
+  dependencies = {
+    clap = "3.2.23";
+    xmltree = "0.10.3";
+    inherit (versions)
+      anyhow
+    ;
+  };
+  nix.meta.labels = [ "leaf" ];
+  nix.meta.requirements = [ "unix" ];
+}
@@ -0,0 +1,9 @@
+{ mk, versions, serdeWith }:
+
+mk {
+  package.name = "sel4-shared-bytes";
+  dependencies = {
+    inherit (versions) zerocopy;
+    serde = serdeWith [ "derive" ] // { optional = true; };
+  };
+}
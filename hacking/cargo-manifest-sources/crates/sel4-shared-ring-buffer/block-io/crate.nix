@@ -14,8 +14,6 @@ mk {
       ];
     };
 
-    async-unsync = { version = "0.2.2"; default-features = false; };
-
     sel4-externally-shared.features = [ "unstable" ];
   };
   nix.local.dependencies = with localCrates; [
@@ -24,6 +22,8 @@ mk {
     sel4-shared-ring-buffer-block-io-types
     sel4-bounce-buffer-allocator
     sel4-async-request-statuses
+    sel4-async-sync
     sel4-async-block-io
+    sel4-async-copy-engine
   ];
 }
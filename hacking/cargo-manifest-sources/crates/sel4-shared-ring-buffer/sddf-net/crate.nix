@@ -0,0 +1,13 @@
+{ mk, localCrates, smoltcpWith }:
+
+mk {
+  package.name = "sel4-shared-ring-buffer-sddf-net";
+  dependencies = {
+    smoltcp = smoltcpWith [];
+    sel4-externally-shared.features = [ "unstable" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-externally-shared
+    sel4-shared-ring-buffer
+  ];
+}
@@ -0,0 +1,12 @@
+{ mk, localCrates, versions }:
+
+mk {
+  package.name = "sel4-system-composition";
+  dependencies = {
+    sel4-capdl-initializer-types.features = [ "std" "serde" ];
+    inherit (versions) object serde_json;
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-capdl-initializer-types
+  ];
+}
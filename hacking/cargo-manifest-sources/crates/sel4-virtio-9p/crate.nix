@@ -0,0 +1,12 @@
+{ mk, localCrates, versions, virtioDriversWith }:
+
+mk {
+  package.name = "sel4-virtio-9p";
+  dependencies = {
+    inherit (versions) log;
+    virtio-drivers = virtioDriversWith [ "alloc" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-async-9p
+  ];
+}
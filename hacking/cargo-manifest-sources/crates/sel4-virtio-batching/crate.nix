@@ -0,0 +1,8 @@
+{ mk, virtioDriversWith }:
+
+mk {
+  package.name = "sel4-virtio-batching";
+  dependencies = {
+    virtio-drivers = virtioDriversWith [];
+  };
+}
@@ -0,0 +1,15 @@
+{ mk, localCrates, virtioDriversWith }:
+
+mk {
+  package.name = "sel4-virtio-mmio";
+  dependencies = {
+    virtio-drivers = virtioDriversWith [];
+    sel4-device-tree.optional = true;
+  };
+  features = {
+    device-tree = [ "dep:sel4-device-tree" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-device-tree
+  ];
+}
@@ -0,0 +1,15 @@
+{ mk, localCrates, versions, virtioDriversWith }:
+
+mk {
+  package.name = "sel4-virtio-net";
+  dependencies = {
+    inherit (versions) log;
+    virtio-drivers = virtioDriversWith [ "alloc" ];
+    sel4-externally-shared.features = [ "unstable" ];
+  };
+  nix.local.dependencies = with localCrates; [
+    sel4-externally-shared
+    sel4-shared-ring-buffer
+    sel4-virtio-batching
+  ];
+}
@@ -0,0 +1,9 @@
+{ mk, versions, virtioDriversWith }:
+
+mk {
+  package.name = "sel4-virtio-vsock";
+  dependencies = {
+    inherit (versions) log;
+    virtio-drivers = virtioDriversWith [];
+  };
+}
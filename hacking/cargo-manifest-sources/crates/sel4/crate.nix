@@ -10,6 +10,9 @@ mk {
     default = [ "state" ];
     state = [];
     single-threaded = [];
+    requires-mcs = [];
+    requires-non-mcs = [];
+    requires-hypervisor = [];
   };
   nix.local.dependencies = with localCrates; [
     sel4-config
@@ -87,6 +87,7 @@ let
         gimli = "0.28.0";
         heapless = "0.7.16";
         log = "0.4.17";
+        minicbor = "0.19.1";
         num = "0.4.1";
         num-traits = "0.2.16";
         num_enum = "0.5.9";
@@ -95,6 +96,7 @@ let
         proc-macro2 = "1.0.50";
         quote = "1.0.23";
         serde = "1.0.147";
+        serde-json-core = "0.6.0";
         serde_json = "1.0.87";
         serde_yaml = "0.9.14";
         smoltcp = "0.10.0";
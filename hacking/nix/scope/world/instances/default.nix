@@ -40,6 +40,7 @@ in rec {
 
   all = lib.filter (v: v != null) [
     tests.root-task.loader
+    tests.root-task.driver-registry
     tests.root-task.core-libs
     tests.root-task.config
     tests.root-task.tls
@@ -51,6 +52,7 @@ in rec {
     tests.root-task.c
     tests.capdl.threads
     tests.capdl.utcover
+    tests.capdl.cap-broker
     microkit.examples.hello
     microkit.examples.banscii
     microkit.examples.http-server
@@ -76,6 +78,16 @@ in rec {
         };
       });
 
+      driver-registry = maybe (haveKernelLoader && haveFullRuntime) (mkInstance {
+        rootTask = mkTask {
+          rootCrate = crates.tests-root-task-driver-registry;
+          release = false;
+        };
+        extraPlatformArgs = lib.optionalAttrs canSimulate  {
+          canAutomateSimply = true;
+        };
+      });
+
       core-libs = maybe haveFullRuntime (mkInstance {
         rootTask = mkTask {
           rootCrate = crates.tests-root-task-core-libs;
@@ -226,6 +238,31 @@ in rec {
           canAutomateSimply = true;
         };
       });
+
+      cap-broker = maybe (haveFullRuntime && haveCapDLInitializer) (mkInstance {
+        rootTask = mkCapDLRootTask rec {
+          small = true;
+          script = sources.srcRoot + "/crates/private/tests/capdl/cap-broker/cdl.py";
+          config = {
+            components = {
+              broker.image = passthru.broker.elf;
+              producer.image = passthru.client.elf;
+              consumer.image = passthru.client.elf;
+            };
+          };
+          passthru = {
+            broker = mkTask {
+              rootCrate = crates.tests-capdl-cap-broker-components-broker;
+            };
+            client = mkTask {
+              rootCrate = crates.tests-capdl-cap-broker-components-client;
+            };
+          };
+        };
+        extraPlatformArgs = lib.optionalAttrs canSimulate  {
+          canAutomateSimply = true;
+        };
+      });
     };
   };
 